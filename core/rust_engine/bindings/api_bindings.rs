@@ -1,6 +1,7 @@
 use pyo3::prelude::*;
 
-use crate::bindings::{error_handlers::map_error, type_converters::py_list_to_vec_i64};
+use crate::bindings::error_handlers::map_error;
+use crate::bindings::type_converters::{RichType, RichValue, py_list_to_vec_i64};
 use crate::error::{ForziumError, catch_unwind_py};
 
 /// Sum a list of integers passed from Python.
@@ -28,10 +29,30 @@ pub fn current_span_id(py: Python<'_>) -> PyResult<Option<String>> {
     mon.getattr("get_current_span_id")?.call0()?.extract()
 }
 
+/// Parse the text form of a `kind` value ("datetime", "date", "uuid",
+/// "decimal" or "path"), as found in headers and JSON fields, into its
+/// Python type.
+#[pyfunction]
+pub fn parse_value(kind: &str, text: &str) -> PyResult<RichValue> {
+    let ty = RichType::from_name(kind)
+        .ok_or_else(|| map_error(ForziumError::Validation(format!("unknown kind {kind:?}"))))?;
+    ty.parse(text)
+        .map_err(|msg| map_error(ForziumError::Validation(msg.into())))
+}
+
+/// Text form of a datetime, date, UUID, Decimal or path, for
+/// `json.dumps(obj, default=json_default)`; raises `TypeError` otherwise.
+#[pyfunction]
+pub fn json_default(value: RichValue) -> String {
+    value.to_text()
+}
+
 /// Register API bindings on the supplied module.
 pub fn register(m: &Bound<PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(sum_list, m)?)?;
     m.add_function(wrap_pyfunction!(echo_list, m)?)?;
     m.add_function(wrap_pyfunction!(current_span_id, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_value, m)?)?;
+    m.add_function(wrap_pyfunction!(json_default, m)?)?;
     Ok(())
 }
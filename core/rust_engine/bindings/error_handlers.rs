@@ -1,8 +1,10 @@
 use pyo3::prelude::*;
+use tracing::debug;
 
 use crate::error::ForziumError;
 
 /// Map a [`ForziumError`] into a Python exception.
 pub fn map_error(err: ForziumError) -> PyErr {
+    debug!(code = err.code(), error = %err, "raising error in Python");
     err.into()
 }
@@ -1,3 +1,5 @@
 pub mod api_bindings;
 pub mod error_handlers;
+pub mod models;
+pub mod msgpack;
 pub mod type_converters;
@@ -0,0 +1,261 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use pyo3::conversion::FromPyObjectOwned;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString, PyType};
+
+use crate::interpreter::PerInterpreter;
+
+/// A Rust struct mirroring a Python request or response model: a
+/// dataclass, a `TypedDict` or a plain dict with the same keys.
+pub trait PyModel: Sized + 'static {
+    /// Names of the model's fields, indexing those passed to
+    /// [`PyModel::from_fields`] and returned by [`PyModel::into_fields`].
+    const FIELDS: &'static [&'static str];
+
+    fn from_fields(fields: &Fields<'_>) -> PyResult<Self>;
+
+    /// Values of the fields, `None` for those left out.
+    fn into_fields<'py>(self, py: Python<'py>) -> PyResult<Vec<Option<Bound<'py, PyAny>>>>;
+}
+
+/// Field values read from a Python model, in [`PyModel::FIELDS`] order.
+pub struct Fields<'py> {
+    names: &'static [&'static str],
+    values: Vec<Option<Bound<'py, PyAny>>>,
+}
+
+impl<'py> Fields<'py> {
+    /// Value of field `index`, which must be present.
+    pub fn required<T: FromPyObjectOwned<'py>>(&self, index: usize) -> PyResult<T> {
+        match &self.values[index] {
+            Some(value) => self.convert(index, value),
+            None => Err(PyValueError::new_err(format!(
+                "{} field required",
+                self.names[index]
+            ))),
+        }
+    }
+
+    /// Value of field `index`, `None` if it is missing or `None`.
+    pub fn optional<T: FromPyObjectOwned<'py>>(&self, index: usize) -> PyResult<Option<T>> {
+        match &self.values[index] {
+            Some(value) if !value.is_none() => self.convert(index, value).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn convert<T: FromPyObjectOwned<'py>>(
+        &self,
+        index: usize,
+        value: &Bound<'py, PyAny>,
+    ) -> PyResult<T> {
+        value.extract::<T>().map_err(|err| {
+            let err: PyErr = err.into();
+            PyTypeError::new_err(format!("{}: {err}", self.names[index]))
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Fields are attributes and the class is called with them as
+    /// keyword arguments.
+    Dataclass,
+    /// Fields are keys of a dict.
+    Mapping,
+}
+
+/// How a model's fields are laid out in one Python class, worked out once
+/// per class so extraction costs one lookup per field.
+struct Layout {
+    /// Keeps the class, whose address keys the layout, alive.
+    _class: Py<PyType>,
+    kind: Kind,
+    /// The model's field names, interned.
+    names: Vec<Py<PyString>>,
+    /// Whether the class declares each field.
+    declared: Vec<bool>,
+}
+
+type Layouts = Mutex<HashMap<(usize, TypeId), Arc<Layout>>>;
+
+static LAYOUTS: Lazy<PerInterpreter<Layouts>> = Lazy::new(PerInterpreter::new);
+
+/// Layout of model `M` in `class`, from the cache of the current
+/// interpreter.
+fn layout<M: PyModel>(class: &Bound<'_, PyType>) -> PyResult<Arc<Layout>> {
+    let py = class.py();
+    let layouts = LAYOUTS.get_or_try_init(py, || Ok(Mutex::new(HashMap::new())))?;
+    let key = (class.as_ptr() as usize, TypeId::of::<M>());
+    if let Some(layout) = layouts.lock().get(&key) {
+        return Ok(layout.clone());
+    }
+    let names: Vec<Py<PyString>> = M::FIELDS
+        .iter()
+        .map(|name| PyString::intern(py, name).unbind())
+        .collect();
+    // TypedDict classes refuse `issubclass`
+    let (kind, declared) =
+        if class.hasattr("__required_keys__")? || class.is_subclass_of::<PyDict>()? {
+            (Kind::Mapping, vec![true; names.len()])
+        } else if let Ok(fields) = class.getattr("__dataclass_fields__") {
+            let fields = fields.cast_into::<PyDict>()?;
+            let declared = M::FIELDS
+                .iter()
+                .map(|name| fields.contains(name))
+                .collect::<PyResult<_>>()?;
+            (Kind::Dataclass, declared)
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "expected a dataclass, TypedDict or dict, got {}",
+                class.name()?
+            )));
+        };
+    let layout = Arc::new(Layout {
+        _class: class.clone().unbind(),
+        kind,
+        names,
+        declared,
+    });
+    Ok(layouts.lock().entry(key).or_insert(layout).clone())
+}
+
+/// Extract model `M` from a dataclass instance or a dict.
+pub fn extract_model<'py, M: PyModel>(obj: &Bound<'py, PyAny>) -> PyResult<M> {
+    let layout = layout::<M>(&obj.get_type())?;
+    let py = obj.py();
+    let mut values = Vec::with_capacity(layout.names.len());
+    for (name, declared) in layout.names.iter().zip(&layout.declared) {
+        let name = name.bind(py);
+        let value = match layout.kind {
+            _ if !declared => None,
+            Kind::Dataclass => Some(obj.getattr(name)?),
+            Kind::Mapping => obj.get_item(name).ok(),
+        };
+        values.push(value);
+    }
+    M::from_fields(&Fields {
+        names: M::FIELDS,
+        values,
+    })
+}
+
+/// Build an instance of `class`, a dataclass, `TypedDict` or `dict`, from
+/// model `M`.
+pub fn model_into_py<'py, M: PyModel>(
+    model: M,
+    class: &Bound<'py, PyType>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = class.py();
+    let layout = layout::<M>(class)?;
+    let fields = PyDict::new(py);
+    for (name, value) in layout.names.iter().zip(model.into_fields(py)?) {
+        if let Some(value) = value {
+            fields.set_item(name.bind(py), value)?;
+        }
+    }
+    match layout.kind {
+        Kind::Dataclass => class.call((), Some(&fields)),
+        Kind::Mapping if class.is(py.get_type::<PyDict>()) => Ok(fields.into_any()),
+        Kind::Mapping => class.call1((fields,)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: f64,
+        label: Option<String>,
+    }
+
+    impl PyModel for Point {
+        const FIELDS: &'static [&'static str] = &["x", "label"];
+
+        fn from_fields(fields: &Fields<'_>) -> PyResult<Self> {
+            Ok(Point {
+                x: fields.required(0)?,
+                label: fields.optional(1)?,
+            })
+        }
+
+        fn into_fields<'py>(self, py: Python<'py>) -> PyResult<Vec<Option<Bound<'py, PyAny>>>> {
+            Ok(vec![
+                Some(self.x.into_pyobject(py)?.into_any()),
+                self.label.map(|label| PyString::new(py, &label).into_any()),
+            ])
+        }
+    }
+
+    const CLASSES: &str = "
+import dataclasses, typing
+
+@dataclasses.dataclass
+class Point:
+    x: float
+    label: typing.Optional[str] = None
+
+class PointDict(typing.TypedDict, total=False):
+    x: float
+    label: str
+";
+
+    #[test]
+    fn models_round_trip_through_dataclasses_and_dicts() {
+        Python::attach(|py| {
+            let code = CString::new(CLASSES).unwrap();
+            let module = PyModule::from_code(py, &code, c"models.py", c"models").unwrap();
+            let dataclass = module
+                .getattr("Point")
+                .unwrap()
+                .cast_into::<PyType>()
+                .unwrap();
+            let typed_dict = module
+                .getattr("PointDict")
+                .unwrap()
+                .cast_into::<PyType>()
+                .unwrap();
+
+            let obj = dataclass.call1((1.5, "a")).unwrap();
+            let point: Point = extract_model(&obj).unwrap();
+            assert_eq!(
+                point,
+                Point {
+                    x: 1.5,
+                    label: Some("a".into())
+                }
+            );
+            // the layout is cached, the second extraction reads it
+            assert_eq!(extract_model::<Point>(&obj).unwrap(), point);
+
+            let dict = model_into_py(point, &typed_dict).unwrap();
+            assert!(dict.is_instance_of::<PyDict>());
+            let point: Point = extract_model(&dict).unwrap();
+            let back = model_into_py(
+                Point {
+                    label: None,
+                    ..point
+                },
+                &dataclass,
+            )
+            .unwrap();
+            assert!(back.getattr("label").unwrap().is_none());
+
+            let missing = PyDict::new(py);
+            let err = extract_model::<Point>(&missing).unwrap_err();
+            assert!(err.to_string().contains("x field required"), "{err}");
+            let wrong = PyDict::new(py);
+            wrong.set_item("x", "one").unwrap();
+            assert!(extract_model::<Point>(&wrong).is_err());
+        });
+    }
+}
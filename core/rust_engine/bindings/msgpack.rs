@@ -0,0 +1,308 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple,
+};
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::bindings::error_handlers::map_error;
+use crate::bindings::type_converters::RichValue;
+use crate::error::{ForziumError, catch_unwind_py};
+
+/// Largest message encoded or decoded, the default upload limit.
+pub const MAX_MESSAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Deepest nesting of arrays and maps encoded or decoded.
+const MAX_DEPTH: usize = 256;
+
+/// A Python value serialised as MessagePack: `None`, `bool`, `int`,
+/// `float`, `str`, `bytes`, `bytearray`, lists, tuples and dicts of them,
+/// and the types of [`RichValue`] as their text form.
+struct Encode<'a, 'py> {
+    obj: &'a Bound<'py, PyAny>,
+    depth: usize,
+}
+
+impl<'a, 'py> Encode<'a, 'py> {
+    fn child<'b>(&self, obj: &'b Bound<'py, PyAny>) -> Encode<'b, 'py> {
+        Encode {
+            obj,
+            depth: self.depth + 1,
+        }
+    }
+}
+
+impl Serialize for Encode<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let obj = self.obj;
+        if self.depth > MAX_DEPTH {
+            return Err(ser::Error::custom("nesting too deep"));
+        }
+        if obj.is_none() {
+            serializer.serialize_unit()
+        } else if let Ok(value) = obj.cast::<PyBool>() {
+            serializer.serialize_bool(value.is_true())
+        } else if let Ok(value) = obj.cast::<PyInt>() {
+            if let Ok(value) = value.extract::<i64>() {
+                serializer.serialize_i64(value)
+            } else if let Ok(value) = value.extract::<u64>() {
+                serializer.serialize_u64(value)
+            } else {
+                Err(ser::Error::custom("integer out of range"))
+            }
+        } else if let Ok(value) = obj.cast::<PyFloat>() {
+            serializer.serialize_f64(value.value())
+        } else if let Ok(value) = obj.cast::<PyString>() {
+            serializer.serialize_str(&value.to_cow().map_err(ser::Error::custom)?)
+        } else if let Ok(value) = obj.cast::<PyBytes>() {
+            serializer.serialize_bytes(value.as_bytes())
+        } else if let Ok(value) = obj.cast::<PyByteArray>() {
+            serializer.serialize_bytes(&value.to_vec())
+        } else if let Ok(dict) = obj.cast::<PyDict>() {
+            let mut map = serializer.serialize_map(Some(dict.len()))?;
+            for (key, value) in dict.iter() {
+                map.serialize_entry(&self.child(&key), &self.child(&value))?;
+            }
+            map.end()
+        } else if let Ok(list) = obj.cast::<PyList>() {
+            let mut seq = serializer.serialize_seq(Some(list.len()))?;
+            for item in list.iter() {
+                seq.serialize_element(&self.child(&item))?;
+            }
+            seq.end()
+        } else if let Ok(tuple) = obj.cast::<PyTuple>() {
+            let mut seq = serializer.serialize_seq(Some(tuple.len()))?;
+            for item in tuple.iter() {
+                seq.serialize_element(&self.child(&item))?;
+            }
+            seq.end()
+        } else if let Ok(value) = obj.extract::<RichValue>() {
+            serializer.serialize_str(&value.to_text())
+        } else {
+            let type_name = obj.get_type().name().map_err(ser::Error::custom)?;
+            Err(ser::Error::custom(format!("cannot encode {type_name}")))
+        }
+    }
+}
+
+/// Builds the Python value of a MessagePack value: arrays become lists,
+/// maps dicts and binary data `bytes`.
+#[derive(Clone, Copy)]
+struct Decode<'py>(Python<'py>);
+
+impl<'de, 'py> DeserializeSeed<'de> for Decode<'py> {
+    type Value = Bound<'py, PyAny>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'py> Visitor<'de> for Decode<'py> {
+    type Value = Bound<'py, PyAny>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a MessagePack value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(self.0.None().into_bound(self.0))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.visit_unit()
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(PyBool::new(self.0, value).to_owned().into_any())
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(PyInt::new(self.0, value).into_any())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(PyInt::new(self.0, value).into_any())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(PyFloat::new(self.0, value).into_any())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(PyString::new(self.0, value).into_any())
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok(PyBytes::new(self.0, value).into_any())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let list = PyList::empty(self.0);
+        while let Some(item) = seq.next_element_seed(self)? {
+            list.append(item).map_err(de::Error::custom)?;
+        }
+        Ok(list.into_any())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let dict = PyDict::new(self.0);
+        while let Some((key, value)) = map.next_entry_seed(self, self)? {
+            dict.set_item(key, value).map_err(de::Error::custom)?;
+        }
+        Ok(dict.into_any())
+    }
+}
+
+/// Writer failing once more than [`MAX_MESSAGE_BYTES`] are written.
+#[derive(Default)]
+struct Limited {
+    buf: Vec<u8>,
+    exceeded: bool,
+}
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + buf.len() > MAX_MESSAGE_BYTES {
+            self.exceeded = true;
+            return Err(io::Error::other("message too large"));
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn too_large() -> PyErr {
+    map_error(ForziumError::ResourceLimit(format!(
+        "MessagePack message exceeds {MAX_MESSAGE_BYTES} bytes"
+    )))
+}
+
+/// Encode `obj` as MessagePack.
+///
+/// `datetime`, `date`, `UUID`, `Decimal` and path values are encoded as
+/// their text form. Messages are limited to 10MB.
+#[pyfunction]
+pub fn msgpack_dumps<'py>(
+    py: Python<'py>,
+    obj: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    catch_unwind_py(|| {
+        let mut out = Limited::default();
+        let encoded = rmp_serde::encode::write(&mut out, &Encode { obj, depth: 0 });
+        match encoded {
+            Ok(()) => Ok(PyBytes::new(py, &out.buf)),
+            Err(_) if out.exceeded => Err(too_large()),
+            Err(err) => Err(map_error(ForziumError::Validation(err.to_string()))),
+        }
+    })
+}
+
+/// Decode a MessagePack message of at most 10MB holding one value.
+///
+/// With a `validator`, such as `ComputeRequestSchema().validate`, the
+/// decoded value is passed to it and its result returned instead.
+#[pyfunction]
+#[pyo3(signature = (data, validator=None))]
+pub fn msgpack_loads<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    validator: Option<&Bound<'py, PyAny>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    catch_unwind_py(|| {
+        if data.len() > MAX_MESSAGE_BYTES {
+            return Err(too_large());
+        }
+        let invalid = |msg: String| map_error(ForziumError::Validation(msg));
+        let mut rest = data;
+        let mut deserializer = rmp_serde::Deserializer::new(&mut rest);
+        deserializer.set_max_depth(MAX_DEPTH);
+        let value = Decode(py)
+            .deserialize(&mut deserializer)
+            .map_err(|err| invalid(err.to_string()))?;
+        drop(deserializer);
+        if !rest.is_empty() {
+            return Err(invalid(format!(
+                "{} bytes of trailing data after the message",
+                rest.len()
+            )));
+        }
+        match validator {
+            Some(validator) => validator.call1((value,)),
+            None => Ok(value),
+        }
+    })
+}
+
+/// Register the MessagePack functions on the supplied module.
+pub fn register(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(msgpack_dumps, m)?)?;
+    m.add_function(wrap_pyfunction!(msgpack_loads, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn eval<'py>(py: Python<'py>, expr: &str) -> Bound<'py, PyAny> {
+        py.eval(&CString::new(expr).unwrap(), None, None).unwrap()
+    }
+
+    #[test]
+    fn values_round_trip() {
+        Python::attach(|py| {
+            let value = eval(
+                py,
+                "{'a': [1, -2, 2**64 - 1, 1.5, None, True], 'b': b'\\x00\\xff', 'c': {'d': 'é'}}",
+            );
+            let encoded = msgpack_dumps(py, &value).unwrap();
+            let decoded = msgpack_loads(py, encoded.as_bytes(), None).unwrap();
+            assert!(decoded.eq(&value).unwrap());
+
+            let uuid = eval(py, "__import__('uuid').UUID(int=1)");
+            let encoded = msgpack_dumps(py, &uuid).unwrap();
+            let decoded = msgpack_loads(py, encoded.as_bytes(), None).unwrap();
+            assert_eq!(
+                decoded.extract::<String>().unwrap(),
+                "00000000-0000-0000-0000-000000000001"
+            );
+        });
+    }
+
+    #[test]
+    fn limits_and_validators_apply() {
+        Python::attach(|py| {
+            let cyclic = eval(py, "(lambda l: (l.append(l), l)[1])([])");
+            assert!(msgpack_dumps(py, &cyclic).is_err());
+            let huge = eval(py, "b'x' * (10 * 1024 * 1024)");
+            let err = msgpack_dumps(py, &huge).unwrap_err();
+            assert!(err.to_string().contains("exceeds"), "{err}");
+            assert!(msgpack_dumps(py, &eval(py, "object()")).is_err());
+
+            // 1 followed by a stray byte
+            assert!(msgpack_loads(py, &[0x01, 0x02], None).is_err());
+            let validator = eval(py, "lambda value: value * 2");
+            let doubled = msgpack_loads(py, &[0x15], Some(&validator)).unwrap();
+            assert_eq!(doubled.extract::<i64>().unwrap(), 42);
+        });
+    }
+}
@@ -1,6 +1,201 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
+use pyo3::types::{PyDate, PyDateTime};
+use rust_decimal::Decimal;
+use uuid::Uuid;
 
 /// Convert a Python sequence into a vector of integers.
 pub fn py_list_to_vec_i64(seq: &Bound<PyAny>) -> PyResult<Vec<i64>> {
     seq.extract::<Vec<i64>>()
 }
+
+/// Types carried as text on the wire, in path parameters, headers and JSON
+/// fields, that handlers see as their Python type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RichType {
+    /// `datetime.datetime`, as ISO 8601; aware if the text has an offset.
+    DateTime,
+    /// `datetime.date`, as `YYYY-MM-DD`.
+    Date,
+    /// `uuid.UUID`, hyphenated, simple, braced or as a URN.
+    Uuid,
+    /// `decimal.Decimal` of up to 28 significant digits.
+    Decimal,
+    /// `pathlib.Path`.
+    Path,
+}
+
+impl RichType {
+    /// The type named `name`, as in a `{param:name}` route segment.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "datetime" => Some(RichType::DateTime),
+            "date" => Some(RichType::Date),
+            "uuid" => Some(RichType::Uuid),
+            "decimal" => Some(RichType::Decimal),
+            "path" => Some(RichType::Path),
+            _ => None,
+        }
+    }
+
+    /// Parse the text form of a value, or describe what was expected.
+    pub fn parse(self, text: &str) -> Result<RichValue, &'static str> {
+        match self {
+            RichType::DateTime => DateTime::parse_from_rfc3339(text)
+                .map(RichValue::DateTime)
+                .or_else(|_| NaiveDateTime::from_str(text).map(RichValue::NaiveDateTime))
+                .map_err(|_| "value is not a valid datetime"),
+            RichType::Date => NaiveDate::from_str(text)
+                .map(RichValue::Date)
+                .map_err(|_| "value is not a valid date"),
+            RichType::Uuid => Uuid::parse_str(text)
+                .map(RichValue::Uuid)
+                .map_err(|_| "value is not a valid uuid"),
+            RichType::Decimal => Decimal::from_str(text)
+                .or_else(|_| Decimal::from_scientific(text))
+                .map(RichValue::Decimal)
+                .map_err(|_| "value is not a valid decimal"),
+            RichType::Path if text.is_empty() => Err("value is not a valid path"),
+            RichType::Path => Ok(RichValue::Path(PathBuf::from(text))),
+        }
+    }
+}
+
+/// A value of a [`RichType`], converted to and from its Python type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RichValue {
+    DateTime(DateTime<FixedOffset>),
+    NaiveDateTime(NaiveDateTime),
+    Date(NaiveDate),
+    Uuid(Uuid),
+    Decimal(Decimal),
+    Path(PathBuf),
+}
+
+impl RichValue {
+    /// Text form of the value, which [`RichType::parse`] reads back.
+    pub fn to_text(&self) -> String {
+        match self {
+            RichValue::DateTime(datetime) => datetime.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+            RichValue::NaiveDateTime(datetime) => {
+                datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+            }
+            RichValue::Date(date) => date.to_string(),
+            RichValue::Uuid(uuid) => uuid.hyphenated().to_string(),
+            RichValue::Decimal(decimal) => decimal.to_string(),
+            RichValue::Path(path) => path.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Text form of the value as a header value: datetimes as an HTTP date,
+    /// naive ones taken as UTC.
+    pub fn to_header(&self) -> String {
+        let utc = match self {
+            RichValue::DateTime(datetime) => datetime.with_timezone(&Utc),
+            RichValue::NaiveDateTime(datetime) => datetime.and_utc(),
+            other => return other.to_text(),
+        };
+        utc.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+    }
+}
+
+impl FromPyObject<'_, '_> for RichValue {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'_, '_, PyAny>) -> Result<Self, Self::Error> {
+        let py = obj.py();
+        if let Ok(datetime) = obj.cast::<PyDateTime>() {
+            if let Ok(naive) = datetime.extract::<NaiveDateTime>() {
+                return Ok(RichValue::NaiveDateTime(naive));
+            }
+            // time zones without a fixed offset are converted to UTC
+            let fixed = datetime.extract::<DateTime<FixedOffset>>().or_else(|_| {
+                let utc = py.import("datetime")?.getattr("timezone")?.getattr("utc")?;
+                datetime.call_method1("astimezone", (utc,))?.extract()
+            })?;
+            return Ok(RichValue::DateTime(fixed));
+        }
+        if obj.is_instance_of::<PyDate>() {
+            return Ok(RichValue::Date(obj.extract()?));
+        }
+        if let Ok(uuid) = obj.extract::<Uuid>() {
+            return Ok(RichValue::Uuid(uuid));
+        }
+        if obj.is_instance(&py.import("decimal")?.getattr("Decimal")?)? {
+            return Ok(RichValue::Decimal(obj.extract()?));
+        }
+        if obj.is_instance(&py.import("os")?.getattr("PathLike")?)? {
+            return Ok(RichValue::Path(obj.extract()?));
+        }
+        Err(PyTypeError::new_err(format!(
+            "expected datetime, date, UUID, Decimal or path, got {}",
+            obj.get_type().name()?
+        )))
+    }
+}
+
+impl<'py> IntoPyObject<'py> for RichValue {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        match self {
+            RichValue::DateTime(datetime) => Ok(datetime.into_pyobject(py)?.into_any()),
+            RichValue::NaiveDateTime(datetime) => Ok(datetime.into_pyobject(py)?.into_any()),
+            RichValue::Date(date) => Ok(date.into_pyobject(py)?.into_any()),
+            RichValue::Uuid(uuid) => uuid.into_pyobject(py),
+            RichValue::Decimal(decimal) => decimal.into_pyobject(py),
+            RichValue::Path(path) => path.into_pyobject(py),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_round_trip_through_text_and_python() {
+        let cases = [
+            ("datetime", "2024-02-29T13:45:01.500+02:00"),
+            ("datetime", "2024-02-29T13:45:01"),
+            ("date", "2024-02-29"),
+            ("uuid", "67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            ("decimal", "-12.50"),
+            ("path", "reports/2024/q1.csv"),
+        ];
+        Python::attach(|py| {
+            for (name, text) in cases {
+                let value = RichType::from_name(name).unwrap().parse(text).unwrap();
+                assert_eq!(value.to_text(), text);
+                let object = value.clone().into_pyobject(py).unwrap();
+                assert_eq!(object.extract::<RichValue>().unwrap(), value);
+            }
+            let path = PathBuf::from("a").into_pyobject(py).unwrap();
+            let pure_path = py.import("pathlib").unwrap().getattr("PurePath").unwrap();
+            assert!(path.is_instance(&pure_path).unwrap());
+            assert!(
+                "a".into_pyobject(py)
+                    .unwrap()
+                    .extract::<RichValue>()
+                    .is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn invalid_text_is_rejected_and_datetimes_become_http_dates() {
+        assert_eq!(RichType::Uuid.parse("42"), Err("value is not a valid uuid"));
+        assert!(RichType::Date.parse("2024-02-30").is_err());
+        assert!(RichType::Decimal.parse("1e3").is_ok());
+        let value = RichType::DateTime
+            .parse("1994-11-06T10:49:37+02:00")
+            .unwrap();
+        assert_eq!(value.to_header(), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+}
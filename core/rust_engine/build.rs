@@ -7,6 +7,15 @@ fn main() {
     // at compile and run time.
     let cfg = pyo3_build_config::get();
 
+    // `free-threaded` only changes behaviour on interpreters built without
+    // the GIL; on others the module keeps the GIL as before.
+    if std::env::var("CARGO_FEATURE_FREE_THREADED").is_ok() && !cfg.is_free_threaded() {
+        println!(
+            "cargo:warning=the free-threaded feature has no effect with the GIL-enabled {}",
+            cfg.executable.as_deref().unwrap_or("interpreter")
+        );
+    }
+
     if std::env::var("FORZIUM_LINK_LIBPYTHON").is_ok() {
         if let Some(lib_dir) = &cfg.lib_dir {
             // Embed the Python library path so binaries can locate libpython even
@@ -0,0 +1,274 @@
+//! K-means and nearest-neighbour primitives.
+//!
+//! Points are passed as row-major batches of `cols`-wide rows, and distances
+//! are squared Euclidean, computed with the SIMD kernels in [`simd_ops`].
+//!
+//! [`simd_ops`]: crate::compute::simd_ops
+
+use crate::compute::ml_inference::extract_batch;
+use crate::compute::rayon_metrics;
+use crate::compute::resource_limits::{
+    DType, OpGuard, OpTimer, enforce_tensor_size, estimate_bytes,
+};
+use crate::compute::simd_ops::optimal_squared_distance;
+use crate::error::ForziumError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Per-query neighbour indices and squared distances returned to Python.
+type PyNeighbours = (Vec<Vec<usize>>, Vec<Vec<f64>>);
+
+/// Validate a row-major batch and return its row count.
+fn validate_points(data: &[f64], cols: usize, operation: &str) -> Result<usize, ForziumError> {
+    if data.is_empty() || cols == 0 {
+        return Err(ForziumError::Validation("empty input".into()));
+    }
+    if !data.len().is_multiple_of(cols) {
+        return Err(ForziumError::Validation("input length mismatch".into()));
+    }
+    let rows = data.len() / cols;
+    enforce_tensor_size(rows, cols, operation)?;
+    Ok(rows)
+}
+
+/// Validate that centroids are non-empty and all `cols` wide.
+fn validate_centroids(centroids: &[Vec<f64>], cols: usize) -> Result<(), ForziumError> {
+    if centroids.is_empty() {
+        return Err(ForziumError::Validation("no centroids".into()));
+    }
+    if centroids.iter().any(|c| c.len() != cols) {
+        return Err(ForziumError::Validation(
+            "centroid dimensions must match input".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Index of the centroid closest to `point`.
+fn nearest_centroid(point: &[f64], centroids: &[Vec<f64>]) -> Result<usize, ForziumError> {
+    let mut best = 0;
+    let mut best_dist = f64::INFINITY;
+    for (idx, centroid) in centroids.iter().enumerate() {
+        let dist = optimal_squared_distance(point, centroid)?;
+        if dist < best_dist {
+            best = idx;
+            best_dist = dist;
+        }
+    }
+    Ok(best)
+}
+
+/// Assign each row of `data` to its nearest centroid.
+pub fn kmeans_assign(
+    data: &[f64],
+    cols: usize,
+    centroids: &[Vec<f64>],
+) -> Result<Vec<usize>, ForziumError> {
+    let rows = validate_points(data, cols, "kmeans_assign")?;
+    validate_centroids(centroids, cols)?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(rows, DType::F64))?;
+    let timer = OpTimer::start("kmeans_assign");
+    let labels = data
+        .par_chunks(cols)
+        .map(|row| {
+            let _guard = rayon_metrics::track_operation("kmeans_assign");
+            if timer.expired() {
+                return Ok(0);
+            }
+            nearest_centroid(row, centroids)
+        })
+        .collect::<Result<Vec<usize>, ForziumError>>()?;
+    timer.check()?;
+    Ok(labels)
+}
+
+/// Recompute centroids as the mean of their assigned rows.
+///
+/// Clusters that received no rows keep their previous centroid.
+pub fn kmeans_update(
+    data: &[f64],
+    cols: usize,
+    labels: &[usize],
+    centroids: &[Vec<f64>],
+) -> Result<Vec<Vec<f64>>, ForziumError> {
+    let rows = validate_points(data, cols, "kmeans_update")?;
+    validate_centroids(centroids, cols)?;
+    if labels.len() != rows {
+        return Err(ForziumError::Validation(
+            "labels must have one entry per row".into(),
+        ));
+    }
+    if labels.iter().any(|&label| label >= centroids.len()) {
+        return Err(ForziumError::Validation("label out of range".into()));
+    }
+
+    let k = centroids.len();
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(2 * k * cols + k, DType::F64))?;
+    let timer = OpTimer::start("kmeans_update");
+    let mut sums = vec![vec![0.0; cols]; k];
+    let mut counts = vec![0usize; k];
+    for (row, &label) in data.chunks(cols).zip(labels.iter()) {
+        if timer.expired() {
+            break;
+        }
+        for (acc, x) in sums[label].iter_mut().zip(row.iter()) {
+            *acc += x;
+        }
+        counts[label] += 1;
+    }
+    timer.check()?;
+
+    Ok(sums
+        .into_iter()
+        .zip(counts)
+        .zip(centroids.iter())
+        .map(|((sum, count), previous)| {
+            if count == 0 {
+                previous.clone()
+            } else {
+                sum.into_iter().map(|x| x / count as f64).collect()
+            }
+        })
+        .collect())
+}
+
+/// Brute-force k-nearest-neighbour search.
+///
+/// Returns, for each query row, up to `k` `(index, squared_distance)` pairs
+/// into `data`, ordered from nearest to farthest.
+pub fn knn_search(
+    data: &[f64],
+    queries: &[f64],
+    cols: usize,
+    k: usize,
+) -> Result<Vec<Vec<(usize, f64)>>, ForziumError> {
+    if k == 0 {
+        return Err(ForziumError::Validation("k must be positive".into()));
+    }
+    let rows = validate_points(data, cols, "knn_search")?;
+    let query_rows = validate_points(queries, cols, "knn_search")?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(query_rows * rows, DType::F64))?;
+    let timer = OpTimer::start("knn_search");
+    let k = k.min(rows);
+
+    let neighbours = queries
+        .par_chunks(cols)
+        .map(|query| {
+            let _guard = rayon_metrics::track_operation("knn_search");
+            if timer.expired() {
+                return Ok(Vec::new());
+            }
+            let mut dists = data
+                .chunks(cols)
+                .enumerate()
+                .map(|(idx, row)| Ok((idx, optimal_squared_distance(query, row)?)))
+                .collect::<Result<Vec<(usize, f64)>, ForziumError>>()?;
+            if k < dists.len() {
+                dists.select_nth_unstable_by(k - 1, |a, b| a.1.total_cmp(&b.1));
+                dists.truncate(k);
+            }
+            dists.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            Ok(dists)
+        })
+        .collect::<Result<Vec<_>, ForziumError>>()?;
+    timer.check()?;
+    Ok(neighbours)
+}
+
+/// Assign each row of a 2D array to its nearest centroid.
+#[pyfunction(name = "kmeans_assign")]
+pub fn py_kmeans_assign(
+    py: Python<'_>,
+    points: &Bound<'_, PyAny>,
+    centroids: Vec<Vec<f64>>,
+) -> PyResult<Vec<usize>> {
+    let (data, cols) = extract_batch(points)?;
+    py.detach(|| kmeans_assign(&data, cols, &centroids))
+        .map_err(Into::into)
+}
+
+/// Recompute centroids from a 2D array and its cluster labels.
+#[pyfunction(name = "kmeans_update")]
+pub fn py_kmeans_update(
+    py: Python<'_>,
+    points: &Bound<'_, PyAny>,
+    labels: Vec<usize>,
+    centroids: Vec<Vec<f64>>,
+) -> PyResult<Vec<Vec<f64>>> {
+    let (data, cols) = extract_batch(points)?;
+    py.detach(|| kmeans_update(&data, cols, &labels, &centroids))
+        .map_err(Into::into)
+}
+
+/// Find the `k` nearest rows of `points` for each query row.
+///
+/// Returns a tuple of neighbour indices and squared distances.
+#[pyfunction(name = "knn_search")]
+pub fn py_knn_search(
+    py: Python<'_>,
+    points: &Bound<'_, PyAny>,
+    queries: &Bound<'_, PyAny>,
+    k: usize,
+) -> PyResult<PyNeighbours> {
+    let (data, cols) = extract_batch(points)?;
+    let (query_data, query_cols) = extract_batch(queries)?;
+    if query_cols != cols {
+        return Err(ForziumError::Validation("query dimensions must match input".into()).into());
+    }
+    let neighbours = py.detach(|| knn_search(&data, &query_data, cols, k))?;
+    Ok(neighbours
+        .into_iter()
+        .map(|row| row.into_iter().unzip())
+        .unzip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::resource_limits::{CancelToken, with_cancel_token};
+
+    const POINTS: [f64; 8] = [0.0, 0.0, 0.0, 1.0, 10.0, 10.0, 10.0, 11.0];
+
+    #[test]
+    fn assign_and_update_centroids() {
+        let centroids = vec![vec![1.0, 1.0], vec![9.0, 9.0]];
+        let labels = kmeans_assign(&POINTS, 2, &centroids).unwrap();
+        assert_eq!(labels, vec![0, 0, 1, 1]);
+
+        let updated = kmeans_update(&POINTS, 2, &labels, &centroids).unwrap();
+        assert_eq!(updated, vec![vec![0.0, 0.5], vec![10.0, 10.5]]);
+
+        let empty = kmeans_update(&POINTS, 2, &[0, 0, 0, 0], &centroids).unwrap();
+        assert_eq!(empty[1], vec![9.0, 9.0]);
+        assert!(kmeans_update(&POINTS, 2, &[0, 0, 0, 2], &centroids).is_err());
+        assert!(kmeans_assign(&POINTS, 2, &[vec![1.0]]).is_err());
+    }
+
+    #[test]
+    fn kmeans_stops_when_cancelled() {
+        let token = CancelToken::new();
+        token.cancel();
+        let centroids = vec![vec![1.0, 1.0], vec![9.0, 9.0]];
+        with_cancel_token(&token, || {
+            assert!(matches!(
+                kmeans_assign(&POINTS, 2, &centroids),
+                Err(ForziumError::Cancelled(_))
+            ));
+            assert!(matches!(
+                kmeans_update(&POINTS, 2, &[0, 0, 1, 1], &centroids),
+                Err(ForziumError::Cancelled(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn knn_orders_by_distance() {
+        let result = knn_search(&POINTS, &[9.0, 10.0, 0.0, 0.5], 2, 2).unwrap();
+        assert_eq!(result[0], vec![(2, 1.0), (3, 2.0)]);
+        assert_eq!(result[1], vec![(0, 0.25), (1, 0.25)]);
+
+        let all = knn_search(&POINTS, &[0.0, 0.0], 2, 10).unwrap();
+        assert_eq!(all[0].len(), 4);
+        assert!(knn_search(&POINTS, &[0.0, 0.0], 2, 0).is_err());
+    }
+}
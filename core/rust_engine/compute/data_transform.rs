@@ -1,23 +1,32 @@
 //! Data preprocessing and transformation routines.
 
+use crate::compute::resource_limits::{DType, OpGuard, enforce_tensor_size, estimate_bytes};
+use crate::compute::simd_ops;
 use crate::error::ForziumError;
 
-fn validate_vec(v: &[f64]) -> Result<(), ForziumError> {
+fn validate_vec(v: &[f64], operation: &str) -> Result<(), ForziumError> {
     if v.is_empty() {
         return Err(ForziumError::Validation("empty vector".into()));
     }
+    enforce_tensor_size(1, v.len(), operation)?;
     Ok(())
 }
 
 /// Scale all elements of the vector by `factor`.
 pub fn scale(v: &[f64], factor: f64) -> Result<Vec<f64>, ForziumError> {
-    validate_vec(v)?;
-    Ok(v.iter().map(|x| x * factor).collect())
+    validate_vec(v, "scale")?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(2 * v.len(), DType::F64))?;
+    Ok(simd_ops::scale_slice(
+        v,
+        factor,
+        simd_ops::detect_simd_support(),
+    ))
 }
 
 /// Normalize elements into the 0..1 range using min-max scaling.
 pub fn normalize(v: &[f64]) -> Result<Vec<f64>, ForziumError> {
-    validate_vec(v)?;
+    validate_vec(v, "normalize")?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(2 * v.len(), DType::F64))?;
     let (min, max) = v
         .iter()
         .fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), &x| {
@@ -31,13 +40,14 @@ pub fn normalize(v: &[f64]) -> Result<Vec<f64>, ForziumError> {
 
 /// Reshape `v` into a matrix with `rows` × `cols` dimensions.
 pub fn reshape(v: &[f64], rows: usize, cols: usize) -> Result<Vec<Vec<f64>>, ForziumError> {
-    validate_vec(v)?;
+    validate_vec(v, "reshape")?;
     if rows == 0 || cols == 0 {
         return Err(ForziumError::Validation("zero dimension".into()));
     }
     if rows * cols != v.len() {
         return Err(ForziumError::Validation("shape mismatch".into()));
     }
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(2 * v.len(), DType::F64))?;
     let mut out = vec![vec![0.0; cols]; rows];
     for i in 0..rows {
         for j in 0..cols {
@@ -3,16 +3,39 @@ use std::collections::HashMap;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
-use crate::compute::tensor_ops;
+use crate::compute::resource_limits::{DType, OpGuard, enforce_tensor_size, estimate_bytes};
+use crate::compute::tensor_ops::RowSink;
+use crate::compute::{simd_ops, tensor_ops};
 use crate::error::ForziumError;
 
+/// Operation with its parameters already extracted, runnable without the GIL.
+pub type PreparedOp =
+    Box<dyn FnOnce(Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>, ForziumError> + Send + 'static>;
+
+/// Operation handing its result to a sink in blocks of the given number of
+/// rows, runnable without the GIL.
+pub type StreamingOp = Box<
+    dyn FnOnce(Vec<Vec<f64>>, usize, &mut RowSink<'_>) -> Result<(), ForziumError> + Send + 'static,
+>;
+
 /// Function pointer signature for registered operations.
-type OperationFn = fn(Vec<Vec<f64>>, &Bound<PyDict>) -> Result<Vec<Vec<f64>>, ForziumError>;
+///
+/// Parameters are read while the GIL is held; the returned kernel runs without it.
+type OperationFn = fn(&Bound<PyDict>) -> Result<PreparedOp, ForziumError>;
+
+/// Kernel combining the data with a second matrix.
+type MatrixOp = fn(&[Vec<f64>], &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError>;
 
 /// Simple compute engine mapping operation names to functions.
+///
+/// Every tensor operation is built in. Operations on two matrices take the
+/// second as the `matrix_b` parameter and convolutions take `kernel`;
+/// `factor`, `addend`, `stride`, `padding` and `size` are scalars.
 #[pyclass]
 pub struct ComputeEngine {
     registry: HashMap<&'static str, OperationFn>,
+    /// Python callables registered at runtime via `register_op`.
+    custom_ops: HashMap<String, Py<PyAny>>,
 }
 
 impl Default for ComputeEngine {
@@ -21,7 +44,25 @@ impl Default for ComputeEngine {
         registry.insert("multiply", op_multiply as OperationFn);
         registry.insert("add", op_add as OperationFn);
         registry.insert("matmul", op_matmul as OperationFn);
-        Self { registry }
+        registry.insert("simd_matmul", op_simd_matmul as OperationFn);
+        registry.insert("transpose", op_transpose as OperationFn);
+        registry.insert("elementwise_add", op_elementwise_add as OperationFn);
+        registry.insert(
+            "simd_elementwise_add",
+            op_simd_elementwise_add as OperationFn,
+        );
+        registry.insert("hadamard", op_hadamard as OperationFn);
+        registry.insert("conv2d", op_conv2d as OperationFn);
+        registry.insert("conv2d_transpose", op_conv2d_transpose as OperationFn);
+        registry.insert("max_pool2d", op_max_pool2d as OperationFn);
+        registry.insert("optimal_matmul", op_optimal_matmul as OperationFn);
+        registry.insert("optimal_add", op_optimal_add as OperationFn);
+        registry.insert("optimal_mul", op_optimal_mul as OperationFn);
+        registry.insert("optimal_multiply", op_optimal_multiply as OperationFn);
+        Self {
+            registry,
+            custom_ops: HashMap::new(),
+        }
     }
 }
 
@@ -34,14 +75,39 @@ impl ComputeEngine {
 
     /// Return whether the engine supports the given operation.
     pub fn supports(&self, operation: &str) -> bool {
-        self.registry.contains_key(operation)
+        self.registry.contains_key(operation) || self.custom_ops.contains_key(operation)
+    }
+
+    /// Register a Python callable as a named operation.
+    ///
+    /// The callable receives `(data, params)` and must return a matrix.
+    /// Built-in operations cannot be overridden.
+    pub fn register_op(&mut self, py: Python, name: &str, func: Py<PyAny>) -> PyResult<()> {
+        if self.registry.contains_key(name) {
+            return Err(ForziumError::Validation(format!(
+                "cannot override built-in operation '{name}'"
+            ))
+            .into());
+        }
+        if !func.bind(py).is_callable() {
+            return Err(ForziumError::Validation("operation must be callable".into()).into());
+        }
+        self.custom_ops.insert(name.to_string(), func);
+        Ok(())
+    }
+
+    /// Remove a previously registered custom operation.
+    ///
+    /// Returns whether an operation with that name was registered.
+    pub fn unregister_op(&mut self, name: &str) -> bool {
+        self.custom_ops.remove(name).is_some()
     }
 
     /// Execute the specified operation with parameters.
     #[pyo3(signature = (data, operation, params, cancel=None))]
     pub fn compute(
         &self,
-        _py: Python,
+        py: Python,
         data: Vec<Vec<f64>>,
         operation: &str,
         params: &Bound<PyDict>,
@@ -50,50 +116,226 @@ impl ComputeEngine {
         if cancel.unwrap_or(false) {
             return Err(ForziumError::Cancelled("operation cancelled".into()).into());
         }
-        match self.registry.get(operation) {
-            Some(func) => func(data, params).map_err(Into::into),
-            None => Err(ForziumError::Compute("unsupported operation".into()).into()),
+        if let Some(func) = self.registry.get(operation) {
+            let kernel = func(params)?;
+            return py.detach(|| kernel(data)).map_err(Into::into);
+        }
+        match self.custom_ops.get(operation) {
+            Some(func) => {
+                // Waiting for a queued slot must not hold the GIL
+                let _op_guard = py.detach(|| reserve_custom_op(&data, operation))?;
+                func.call1(py, (data, params))?.extract(py)
+            }
+            None => Err(unsupported_operation()),
         }
     }
 }
 
-fn op_multiply(data: Vec<Vec<f64>>, params: &Bound<PyDict>) -> Result<Vec<Vec<f64>>, ForziumError> {
-    let factor = match params
+impl ComputeEngine {
+    /// Resolve `operation` and its parameters into a kernel that can run on
+    /// another thread.
+    ///
+    /// Custom Python operations reacquire the GIL when the kernel runs, and
+    /// any exception they raise is reported as a compute error.
+    pub fn prepare(
+        &self,
+        py: Python<'_>,
+        operation: &str,
+        params: &Bound<PyDict>,
+    ) -> PyResult<PreparedOp> {
+        if let Some(func) = self.registry.get(operation) {
+            return func(params).map_err(Into::into);
+        }
+        let Some(func) = self.custom_ops.get(operation) else {
+            return Err(unsupported_operation());
+        };
+        let func = func.clone_ref(py);
+        let params = params.copy()?.unbind();
+        let operation = operation.to_string();
+        Ok(Box::new(move |data| {
+            let _op_guard = reserve_custom_op(&data, &operation)?;
+            Python::attach(|py| {
+                func.call1(py, (data, params.bind(py)))?
+                    .extract::<Vec<Vec<f64>>>(py)
+            })
+            .map_err(|err| ForziumError::Compute(format!("operation '{operation}' failed: {err}")))
+        }))
+    }
+
+    /// Like [`prepare`](Self::prepare), for a kernel that hands its result
+    /// to a sink as it computes it.
+    ///
+    /// `multiply`, `add` and `matmul` compute their result a block of rows
+    /// at a time; other operations hand over their whole result at the end.
+    pub fn prepare_streaming(
+        &self,
+        py: Python<'_>,
+        operation: &str,
+        params: &Bound<PyDict>,
+    ) -> PyResult<StreamingOp> {
+        let op: StreamingOp = match operation {
+            "multiply" => {
+                let factor = factor_param(params)?;
+                Box::new(move |data, block_rows, sink| {
+                    tensor_ops::multiply_rows(&data, factor, block_rows, sink)
+                })
+            }
+            "add" => {
+                let addend = addend_param(params)?;
+                Box::new(move |data, block_rows, sink| {
+                    tensor_ops::add_rows(&data, addend, block_rows, sink)
+                })
+            }
+            "matmul" => {
+                let other = matrix_param(params, "matrix_b")?;
+                Box::new(move |data, block_rows, sink| {
+                    tensor_ops::matmul_rows(&data, &other, block_rows, sink)
+                })
+            }
+            _ => {
+                let kernel = self.prepare(py, operation, params)?;
+                Box::new(move |data, _, sink| sink(kernel(data)?))
+            }
+        };
+        Ok(op)
+    }
+}
+
+fn unsupported_operation() -> PyErr {
+    ForziumError::Compute("unsupported operation".into()).into()
+}
+
+/// Check limits for a custom operation and reserve memory for it.
+fn reserve_custom_op(data: &[Vec<f64>], operation: &str) -> Result<OpGuard, ForziumError> {
+    let cols = data.first().map_or(0, Vec::len);
+    enforce_tensor_size(data.len(), cols, operation)?;
+    // Reserve the input plus an equally sized result
+    let elements = 2 * data.len() * cols;
+    OpGuard::acquire_bytes(estimate_bytes(elements, DType::F64))
+}
+
+fn factor_param(params: &Bound<PyDict>) -> Result<f64, ForziumError> {
+    match params
         .get_item("factor")
         .map_err(|_| ForziumError::Validation("factor invalid".into()))?
     {
         Some(v) => v
             .extract::<f64>()
-            .map_err(|_| ForziumError::Validation("factor invalid".into()))?,
-        None => 1.0,
-    };
-    tensor_ops::multiply(&data, factor)
+            .map_err(|_| ForziumError::Validation("factor invalid".into())),
+        None => Ok(1.0),
+    }
 }
 
-fn op_add(data: Vec<Vec<f64>>, params: &Bound<PyDict>) -> Result<Vec<Vec<f64>>, ForziumError> {
-    let addend = match params
+fn addend_param(params: &Bound<PyDict>) -> Result<f64, ForziumError> {
+    match params
         .get_item("addend")
         .map_err(|_| ForziumError::Validation("addend invalid".into()))?
     {
         Some(v) => v
             .extract::<f64>()
-            .map_err(|_| ForziumError::Validation("addend invalid".into()))?,
-        None => 0.0,
-    };
-    tensor_ops::add(&data, addend)
+            .map_err(|_| ForziumError::Validation("addend invalid".into())),
+        None => Ok(0.0),
+    }
 }
 
-fn op_matmul(data: Vec<Vec<f64>>, params: &Bound<PyDict>) -> Result<Vec<Vec<f64>>, ForziumError> {
-    let other = match params
-        .get_item("matrix_b")
-        .map_err(|_| ForziumError::Validation("matrix_b invalid".into()))?
-    {
-        Some(v) => v
-            .extract::<Vec<Vec<f64>>>()
-            .map_err(|_| ForziumError::Validation("matrix_b invalid".into()))?,
-        None => return Err(ForziumError::Validation("matrix_b missing".into())),
-    };
-    tensor_ops::matmul(&data, &other)
+/// Matrix parameter `name`, which must be given.
+fn matrix_param(params: &Bound<PyDict>, name: &str) -> Result<Vec<Vec<f64>>, ForziumError> {
+    let invalid = || ForziumError::Validation(format!("{name} invalid"));
+    match params.get_item(name).map_err(|_| invalid())? {
+        Some(v) => v.extract::<Vec<Vec<f64>>>().map_err(|_| invalid()),
+        None => Err(ForziumError::Validation(format!("{name} missing"))),
+    }
+}
+
+/// Size parameter `name`, or `default` when it is not given.
+fn usize_param(
+    params: &Bound<PyDict>,
+    name: &str,
+    default: Option<usize>,
+) -> Result<usize, ForziumError> {
+    let invalid = || ForziumError::Validation(format!("{name} invalid"));
+    match params.get_item(name).map_err(|_| invalid())? {
+        Some(v) => v.extract::<usize>().map_err(|_| invalid()),
+        None => default.ok_or_else(|| ForziumError::Validation(format!("{name} missing"))),
+    }
+}
+
+/// Kernel running `op` on the data and the `matrix_b` parameter.
+fn binary_op(params: &Bound<PyDict>, op: MatrixOp) -> Result<PreparedOp, ForziumError> {
+    let other = matrix_param(params, "matrix_b")?;
+    Ok(Box::new(move |data| op(&data, &other)))
+}
+
+fn op_multiply(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    let factor = factor_param(params)?;
+    Ok(Box::new(move |data| tensor_ops::multiply(&data, factor)))
+}
+
+fn op_add(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    let addend = addend_param(params)?;
+    Ok(Box::new(move |data| tensor_ops::add(&data, addend)))
+}
+
+fn op_matmul(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, tensor_ops::matmul)
+}
+
+fn op_simd_matmul(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, tensor_ops::simd_matmul)
+}
+
+fn op_transpose(_params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    Ok(Box::new(|data| tensor_ops::transpose(&data)))
+}
+
+fn op_elementwise_add(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, tensor_ops::elementwise_add)
+}
+
+fn op_simd_elementwise_add(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, tensor_ops::simd_elementwise_add)
+}
+
+fn op_hadamard(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, tensor_ops::hadamard)
+}
+
+fn op_conv2d(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    let kernel = matrix_param(params, "kernel")?;
+    Ok(Box::new(move |data| tensor_ops::conv2d(&data, &kernel)))
+}
+
+fn op_conv2d_transpose(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    let kernel = matrix_param(params, "kernel")?;
+    let stride = usize_param(params, "stride", Some(1))?;
+    let padding = usize_param(params, "padding", Some(0))?;
+    Ok(Box::new(move |data| {
+        tensor_ops::conv2d_transpose(&data, &kernel, stride, padding)
+    }))
+}
+
+fn op_max_pool2d(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    let size = usize_param(params, "size", None)?;
+    Ok(Box::new(move |data| tensor_ops::max_pool2d(&data, size)))
+}
+
+fn op_optimal_matmul(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, simd_ops::optimal_matmul)
+}
+
+fn op_optimal_add(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, simd_ops::optimal_add)
+}
+
+fn op_optimal_mul(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    binary_op(params, simd_ops::optimal_mul)
+}
+
+fn op_optimal_multiply(params: &Bound<PyDict>) -> Result<PreparedOp, ForziumError> {
+    let factor = factor_param(params)?;
+    Ok(Box::new(move |data| {
+        simd_ops::optimal_multiply(&data, factor)
+    }))
 }
 
 #[cfg(test)]
@@ -115,6 +357,65 @@ mod tests {
         });
     }
 
+    #[test]
+    fn custom_op_registration() {
+        Python::attach(|py| {
+            let mut engine = ComputeEngine::new();
+            let func = py
+                .eval(
+                    c"lambda data, params: [[v + params['offset'] for v in row] for row in data]",
+                    None,
+                    None,
+                )
+                .unwrap()
+                .unbind();
+            engine.register_op(py, "shift", func).unwrap();
+            assert!(engine.supports("shift"));
+
+            let params = PyDict::new(py);
+            params.set_item("offset", 1.5).unwrap();
+            let result = engine
+                .compute(py, vec![vec![1.0, 2.0]], "shift", &params, None)
+                .unwrap();
+            assert_eq!(result, vec![vec![2.5, 3.5]]);
+
+            assert!(engine.unregister_op("shift"));
+            assert!(!engine.supports("shift"));
+        });
+    }
+
+    #[test]
+    fn custom_op_cannot_shadow_builtin() {
+        Python::attach(|py| {
+            let mut engine = ComputeEngine::new();
+            let func = py.eval(c"lambda data, params: data", None, None).unwrap();
+            let err = engine
+                .register_op(py, "add", func.clone().unbind())
+                .unwrap_err();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+            let not_callable = 42i32.into_pyobject(py).unwrap().into_any().unbind();
+            assert!(engine.register_op(py, "answer", not_callable).is_err());
+        });
+    }
+
+    #[test]
+    fn prepared_ops_run_on_other_threads() {
+        let kernel = Python::attach(|py| {
+            let engine = ComputeEngine::new();
+            let params = PyDict::new(py);
+            params.set_item("addend", 1.0).unwrap();
+            assert!(engine.prepare(py, "nope", &params).is_err());
+            params.set_item("matrix_b", "bad").unwrap();
+            assert!(engine.prepare(py, "matmul", &params).is_err());
+            engine.prepare(py, "add", &params).unwrap()
+        });
+        let result = std::thread::spawn(move || kernel(vec![vec![1.0, 2.0]]))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, vec![vec![2.0, 3.0]]);
+    }
+
     #[test]
     fn unsupported_op_errors() {
         Python::with_gil(|py| {
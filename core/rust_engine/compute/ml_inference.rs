@@ -1,30 +1,43 @@
 //! Machine learning inference utilities.
 
+use crate::compute::rayon_metrics;
+use crate::compute::simd_ops::optimal_dot;
 use crate::error::ForziumError;
+use pyo3::buffer::PyBuffer;
 use pyo3::prelude::*;
-use pyo3::types::PyType;
+use pyo3::types::{PyBytes, PyType};
+use rayon::prelude::*;
 use std::fs;
 
+/// Magic prefix identifying a serialized [`LinearModel`].
+const LINEAR_MODEL_MAGIC: &[u8; 4] = b"FZLM";
+/// Current version of the binary [`LinearModel`] format.
+const LINEAR_MODEL_VERSION: u8 = 1;
+/// Size of the magic, version byte, and weight count.
+const LINEAR_MODEL_HEADER_LEN: usize = 9;
+
 /// Simple linear model with weights and bias.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LinearModel {
     weights: Vec<f64>,
     bias: f64,
 }
 
 impl LinearModel {
-    /// Load model parameters from a text file.
+    /// Load model parameters from a file.
     ///
-    /// The file must contain whitespace-separated numbers with the
+    /// Files written by [`LinearModel::save`] are read as the binary format.
+    /// Otherwise the file must contain whitespace-separated numbers with the
     /// first value as the bias followed by weights.
     pub fn load(path: &str) -> Result<Self, ForziumError> {
-        let content = fs::read_to_string(path)
-            .map_err(|_| ForziumError::Compute("model file not found".into()))?;
-        let nums: Vec<f64> = content
-            .split_whitespace()
-            .map(|s| s.parse::<f64>())
-            .collect::<Result<_, _>>()
+        let data =
+            fs::read(path).map_err(|_| ForziumError::Compute("model file not found".into()))?;
+        if data.starts_with(LINEAR_MODEL_MAGIC) {
+            return Self::from_bytes(&data);
+        }
+        let content = String::from_utf8(data)
             .map_err(|_| ForziumError::Compute("invalid model data".into()))?;
+        let nums = read_numbers(&content)?;
         if nums.len() < 2 {
             return Err(ForziumError::Compute("model data incomplete".into()));
         }
@@ -33,6 +46,11 @@ impl LinearModel {
         Ok(Self { weights, bias })
     }
 
+    /// Number of input features the model expects.
+    pub fn num_features(&self) -> usize {
+        self.weights.len()
+    }
+
     /// Predict output for the given input vector.
     pub fn predict(&self, input: &[f64]) -> Result<f64, ForziumError> {
         if input.len() != self.weights.len() {
@@ -44,11 +62,301 @@ impl LinearModel {
         }
         Ok(sum)
     }
+
+    /// Predict outputs for a row-major batch of `cols`-wide rows.
+    pub fn predict_batch(&self, data: &[f64], cols: usize) -> Result<Vec<f64>, ForziumError> {
+        map_rows(data, cols, self.weights.len(), |row| {
+            Ok(optimal_dot(&self.weights, row)? + self.bias)
+        })
+    }
+
+    /// Serialize the model into the versioned binary format.
+    ///
+    /// Layout: the `FZLM` magic, a version byte, the weight count as a
+    /// little-endian `u32`, then the bias and weights as little-endian `f64`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(LINEAR_MODEL_HEADER_LEN + 8 * (self.weights.len() + 1));
+        out.extend_from_slice(LINEAR_MODEL_MAGIC);
+        out.push(LINEAR_MODEL_VERSION);
+        out.extend_from_slice(&(self.weights.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.bias.to_le_bytes());
+        for w in &self.weights {
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        out
+    }
+
+    /// Deserialize a model produced by [`LinearModel::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ForziumError> {
+        if data.len() < LINEAR_MODEL_HEADER_LEN || !data.starts_with(LINEAR_MODEL_MAGIC) {
+            return Err(ForziumError::Compute("invalid model data".into()));
+        }
+        let version = data[4];
+        if version != LINEAR_MODEL_VERSION {
+            return Err(ForziumError::Compute(format!(
+                "unsupported model format version {version}"
+            )));
+        }
+        let count = u32::from_le_bytes([data[5], data[6], data[7], data[8]]) as usize;
+        let body = &data[LINEAR_MODEL_HEADER_LEN..];
+        if count == 0 || body.len() != 8 * (count + 1) {
+            return Err(ForziumError::Compute("model data incomplete".into()));
+        }
+        let mut values = body
+            .chunks_exact(8)
+            .map(|chunk| f64::from_le_bytes(chunk.try_into().expect("8-byte chunk")));
+        let bias = values.next().unwrap_or_default();
+        Ok(Self {
+            weights: values.collect(),
+            bias,
+        })
+    }
+
+    /// Write the model to `path` in the binary format.
+    pub fn save(&self, path: &str) -> Result<(), ForziumError> {
+        fs::write(path, self.to_bytes())
+            .map_err(|e| ForziumError::Compute(format!("failed to save model: {e}")))
+    }
+}
+
+/// Apply `f` to each `cols`-wide row of a row-major batch in parallel.
+fn map_rows<T, F>(data: &[f64], cols: usize, expected: usize, f: F) -> Result<Vec<T>, ForziumError>
+where
+    T: Send,
+    F: Fn(&[f64]) -> Result<T, ForziumError> + Sync,
+{
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    validate_batch(data, cols, expected)?;
+    data.par_chunks(cols)
+        .map(|row| {
+            let _guard = rayon_metrics::track_task();
+            f(row)
+        })
+        .collect()
+}
+
+/// Check that `data` holds whole `cols`-wide rows for a model taking
+/// `expected` features.
+pub(crate) fn validate_batch(
+    data: &[f64],
+    cols: usize,
+    expected: usize,
+) -> Result<(), ForziumError> {
+    if cols != expected || !data.len().is_multiple_of(cols) {
+        return Err(ForziumError::Compute("input length mismatch".into()));
+    }
+    Ok(())
+}
+
+/// Index of the largest probability.
+fn argmax(probs: &[f64]) -> usize {
+    probs
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
+}
+
+/// Parse whitespace-separated numbers from a model file.
+fn read_numbers(content: &str) -> Result<Vec<f64>, ForziumError> {
+    content
+        .split_whitespace()
+        .map(|s| s.parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| ForziumError::Compute("invalid model data".into()))
+}
+
+/// Numerically stable logistic sigmoid.
+fn sigmoid(z: f64) -> f64 {
+    if z >= 0.0 {
+        1.0 / (1.0 + (-z).exp())
+    } else {
+        let e = z.exp();
+        e / (1.0 + e)
+    }
+}
+
+/// Numerically stable softmax over class scores.
+fn softmax(scores: &[f64]) -> Vec<f64> {
+    let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exps: Vec<f64> = scores.iter().map(|s| (s - max).exp()).collect();
+    let total: f64 = exps.iter().sum();
+    exps.into_iter().map(|e| e / total).collect()
+}
+
+/// Binary logistic regression classifier.
+#[derive(Debug)]
+pub struct LogisticModel {
+    weights: Vec<f64>,
+    bias: f64,
+}
+
+impl LogisticModel {
+    /// Build a model from explicit weights and bias.
+    pub fn new(weights: Vec<f64>, bias: f64) -> Result<Self, ForziumError> {
+        if weights.is_empty() {
+            return Err(ForziumError::Validation("weights must not be empty".into()));
+        }
+        Ok(Self { weights, bias })
+    }
+
+    /// Load model parameters from a text file.
+    ///
+    /// Uses the same layout as [`LinearModel::load`]: the bias followed by
+    /// the weights.
+    pub fn load(path: &str) -> Result<Self, ForziumError> {
+        let content = fs::read_to_string(path)
+            .map_err(|_| ForziumError::Compute("model file not found".into()))?;
+        let nums = read_numbers(&content)?;
+        if nums.len() < 2 {
+            return Err(ForziumError::Compute("model data incomplete".into()));
+        }
+        Self::new(nums[1..].to_vec(), nums[0])
+    }
+
+    /// Probability of the positive class for the given input vector.
+    pub fn predict_proba(&self, input: &[f64]) -> Result<f64, ForziumError> {
+        if input.len() != self.weights.len() {
+            return Err(ForziumError::Compute("input length mismatch".into()));
+        }
+        Ok(sigmoid(optimal_dot(&self.weights, input)? + self.bias))
+    }
+
+    /// Predicted class (0 or 1) using a 0.5 probability threshold.
+    pub fn predict(&self, input: &[f64]) -> Result<usize, ForziumError> {
+        Ok(usize::from(self.predict_proba(input)? >= 0.5))
+    }
+
+    /// Positive-class probabilities for a row-major batch of `cols`-wide rows.
+    pub fn predict_proba_batch(&self, data: &[f64], cols: usize) -> Result<Vec<f64>, ForziumError> {
+        map_rows(data, cols, self.weights.len(), |row| {
+            self.predict_proba(row)
+        })
+    }
+
+    /// Predicted classes for a row-major batch of `cols`-wide rows.
+    pub fn predict_batch(&self, data: &[f64], cols: usize) -> Result<Vec<usize>, ForziumError> {
+        map_rows(data, cols, self.weights.len(), |row| self.predict(row))
+    }
+}
+
+/// Multinomial (softmax) classifier with one weight row per class.
+#[derive(Debug)]
+pub struct SoftmaxModel {
+    weights: Vec<Vec<f64>>,
+    biases: Vec<f64>,
+}
+
+impl SoftmaxModel {
+    /// Build a model from per-class weight rows and biases.
+    pub fn new(weights: Vec<Vec<f64>>, biases: Vec<f64>) -> Result<Self, ForziumError> {
+        if weights.len() < 2 {
+            return Err(ForziumError::Validation(
+                "softmax model needs at least two classes".into(),
+            ));
+        }
+        if weights.len() != biases.len() {
+            return Err(ForziumError::Validation(
+                "one bias per class is required".into(),
+            ));
+        }
+        let features = weights[0].len();
+        if features == 0 || weights.iter().any(|row| row.len() != features) {
+            return Err(ForziumError::Validation(
+                "class weights must share a non-zero length".into(),
+            ));
+        }
+        Ok(Self { weights, biases })
+    }
+
+    /// Load model parameters from a text file.
+    ///
+    /// Each non-empty line describes one class: its bias followed by its
+    /// weights.
+    pub fn load(path: &str) -> Result<Self, ForziumError> {
+        let content = fs::read_to_string(path)
+            .map_err(|_| ForziumError::Compute("model file not found".into()))?;
+        let mut weights = Vec::new();
+        let mut biases = Vec::new();
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let nums = read_numbers(line)?;
+            if nums.len() < 2 {
+                return Err(ForziumError::Compute("model data incomplete".into()));
+            }
+            biases.push(nums[0]);
+            weights.push(nums[1..].to_vec());
+        }
+        Self::new(weights, biases).map_err(|e| ForziumError::Compute(e.to_string()))
+    }
+
+    /// Number of classes the model distinguishes.
+    pub fn num_classes(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Class probabilities for the given input vector.
+    pub fn predict_proba(&self, input: &[f64]) -> Result<Vec<f64>, ForziumError> {
+        if input.len() != self.weights[0].len() {
+            return Err(ForziumError::Compute("input length mismatch".into()));
+        }
+        let scores = self
+            .weights
+            .iter()
+            .zip(self.biases.iter())
+            .map(|(row, bias)| Ok(optimal_dot(row, input)? + bias))
+            .collect::<Result<Vec<f64>, ForziumError>>()?;
+        Ok(softmax(&scores))
+    }
+
+    /// Index of the most probable class.
+    pub fn predict(&self, input: &[f64]) -> Result<usize, ForziumError> {
+        Ok(argmax(&self.predict_proba(input)?))
+    }
+
+    /// Class probabilities for a row-major batch of `cols`-wide rows.
+    pub fn predict_proba_batch(
+        &self,
+        data: &[f64],
+        cols: usize,
+    ) -> Result<Vec<Vec<f64>>, ForziumError> {
+        map_rows(data, cols, self.weights[0].len(), |row| {
+            self.predict_proba(row)
+        })
+    }
+
+    /// Most probable classes for a row-major batch of `cols`-wide rows.
+    pub fn predict_batch(&self, data: &[f64], cols: usize) -> Result<Vec<usize>, ForziumError> {
+        map_rows(data, cols, self.weights[0].len(), |row| self.predict(row))
+    }
 }
 
-#[pyclass(name = "LinearModel")]
+/// Extract a batch from a 2D buffer (such as a C-contiguous NumPy array) or
+/// a sequence of equal-length rows, returning the row-major data and width.
+pub(crate) fn extract_batch(inputs: &Bound<'_, PyAny>) -> PyResult<(Vec<f64>, usize)> {
+    if let Ok(buffer) = PyBuffer::<f64>::get(inputs) {
+        if buffer.dimensions() != 2 {
+            return Err(ForziumError::Validation("expected a 2D array".into()).into());
+        }
+        if !buffer.is_c_contiguous() {
+            return Err(ForziumError::Validation("array must be C-contiguous".into()).into());
+        }
+        let cols = buffer.shape()[1];
+        return Ok((buffer.to_vec(inputs.py())?, cols));
+    }
+    let rows: Vec<Vec<f64>> = inputs.extract()?;
+    let cols = rows.first().map_or(0, Vec::len);
+    if rows.iter().any(|row| row.len() != cols) {
+        return Err(ForziumError::Validation("ragged input".into()).into());
+    }
+    Ok((rows.concat(), cols))
+}
+
+#[pyclass(name = "LinearModel", frozen)]
 pub struct PyLinearModel {
-    inner: LinearModel,
+    pub(crate) inner: LinearModel,
 }
 
 #[pymethods]
@@ -64,6 +372,139 @@ impl PyLinearModel {
     pub fn predict(&self, input: Vec<f64>) -> PyResult<f64> {
         self.inner.predict(&input).map_err(Into::into)
     }
+
+    /// Predict outputs for a 2D array of inputs in a single call.
+    pub fn predict_batch(&self, py: Python<'_>, inputs: &Bound<'_, PyAny>) -> PyResult<Vec<f64>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.predict_batch(&data, cols))
+            .map_err(Into::into)
+    }
+
+    /// Serialize the model into the versioned binary format.
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.to_bytes())
+    }
+
+    /// Restore a model from bytes produced by `to_bytes`.
+    #[classmethod]
+    pub fn from_bytes(_cls: &Bound<PyType>, data: &[u8]) -> PyResult<Self> {
+        let inner = LinearModel::from_bytes(data)?;
+        Ok(Self { inner })
+    }
+
+    /// Save the model to disk in the binary format.
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        self.inner.save(path).map_err(Into::into)
+    }
+}
+
+#[pyclass(name = "LogisticModel", frozen)]
+pub struct PyLogisticModel {
+    inner: LogisticModel,
+}
+
+#[pymethods]
+impl PyLogisticModel {
+    /// Create a model from weights and bias.
+    #[new]
+    #[pyo3(signature = (weights, bias=0.0))]
+    pub fn new(weights: Vec<f64>, bias: f64) -> PyResult<Self> {
+        let inner = LogisticModel::new(weights, bias)?;
+        Ok(Self { inner })
+    }
+
+    /// Load a model from disk.
+    #[classmethod]
+    pub fn load(_cls: &Bound<PyType>, path: &str) -> PyResult<Self> {
+        let inner = LogisticModel::load(path)?;
+        Ok(Self { inner })
+    }
+
+    /// Probability of the positive class for the given input vector.
+    pub fn predict_proba(&self, input: Vec<f64>) -> PyResult<f64> {
+        self.inner.predict_proba(&input).map_err(Into::into)
+    }
+
+    /// Predicted class (0 or 1) for the given input vector.
+    pub fn predict(&self, input: Vec<f64>) -> PyResult<usize> {
+        self.inner.predict(&input).map_err(Into::into)
+    }
+
+    /// Positive-class probabilities for a 2D array of inputs.
+    pub fn predict_proba_batch(
+        &self,
+        py: Python<'_>,
+        inputs: &Bound<'_, PyAny>,
+    ) -> PyResult<Vec<f64>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.predict_proba_batch(&data, cols))
+            .map_err(Into::into)
+    }
+
+    /// Predicted classes for a 2D array of inputs.
+    pub fn predict_batch(&self, py: Python<'_>, inputs: &Bound<'_, PyAny>) -> PyResult<Vec<usize>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.predict_batch(&data, cols))
+            .map_err(Into::into)
+    }
+}
+
+#[pyclass(name = "SoftmaxModel", frozen)]
+pub struct PySoftmaxModel {
+    inner: SoftmaxModel,
+}
+
+#[pymethods]
+impl PySoftmaxModel {
+    /// Create a model from per-class weight rows and biases.
+    #[new]
+    #[pyo3(signature = (weights, biases=None))]
+    pub fn new(weights: Vec<Vec<f64>>, biases: Option<Vec<f64>>) -> PyResult<Self> {
+        let biases = biases.unwrap_or_else(|| vec![0.0; weights.len()]);
+        let inner = SoftmaxModel::new(weights, biases)?;
+        Ok(Self { inner })
+    }
+
+    /// Load a model from disk.
+    #[classmethod]
+    pub fn load(_cls: &Bound<PyType>, path: &str) -> PyResult<Self> {
+        let inner = SoftmaxModel::load(path)?;
+        Ok(Self { inner })
+    }
+
+    /// Number of classes the model distinguishes.
+    #[getter]
+    pub fn num_classes(&self) -> usize {
+        self.inner.num_classes()
+    }
+
+    /// Class probabilities for the given input vector.
+    pub fn predict_proba(&self, input: Vec<f64>) -> PyResult<Vec<f64>> {
+        self.inner.predict_proba(&input).map_err(Into::into)
+    }
+
+    /// Index of the most probable class for the given input vector.
+    pub fn predict(&self, input: Vec<f64>) -> PyResult<usize> {
+        self.inner.predict(&input).map_err(Into::into)
+    }
+
+    /// Class probabilities for a 2D array of inputs.
+    pub fn predict_proba_batch(
+        &self,
+        py: Python<'_>,
+        inputs: &Bound<'_, PyAny>,
+    ) -> PyResult<Vec<Vec<f64>>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.predict_proba_batch(&data, cols))
+            .map_err(Into::into)
+    }
+
+    /// Most probable classes for a 2D array of inputs.
+    pub fn predict_batch(&self, py: Python<'_>, inputs: &Bound<'_, PyAny>) -> PyResult<Vec<usize>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.predict_batch(&data, cols))
+            .map_err(Into::into)
+    }
 }
 
 #[cfg(test)]
@@ -105,4 +546,68 @@ mod tests {
         matches!(err, ForziumError::Compute(_));
         fs::remove_file(path).unwrap();
     }
+
+    #[test]
+    fn binary_round_trip() {
+        let model = LinearModel {
+            weights: vec![0.5, -2.0, 3.25],
+            bias: 1.5,
+        };
+        let bytes = model.to_bytes();
+        let restored = LinearModel::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.weights, model.weights);
+        assert_eq!(restored.bias, model.bias);
+
+        let path = temp_file_path();
+        model.save(&path).unwrap();
+        let loaded = LinearModel::load(&path).unwrap();
+        assert_eq!(loaded.predict(&[1.0, 1.0, 1.0]).unwrap(), 3.25);
+        fs::remove_file(path).unwrap();
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 99;
+        assert!(LinearModel::from_bytes(&bad_version).is_err());
+        assert!(LinearModel::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn batch_predictions_match_single_rows() {
+        let model = LinearModel {
+            weights: vec![1.0, 2.0],
+            bias: 0.5,
+        };
+        let data = [1.0, 1.0, 2.0, 0.0, 0.0, 3.0];
+        assert_eq!(model.predict_batch(&data, 2).unwrap(), vec![3.5, 2.5, 6.5]);
+        assert!(model.predict_batch(&data, 3).is_err());
+        assert!(model.predict_batch(&[], 2).unwrap().is_empty());
+
+        let softmax =
+            SoftmaxModel::new(vec![vec![1.0, 0.0], vec![0.0, 1.0]], vec![0.0, 0.0]).unwrap();
+        assert_eq!(softmax.predict_batch(&data[2..], 2).unwrap(), vec![0, 1]);
+        let probs = softmax.predict_proba_batch(&data, 2).unwrap();
+        assert_eq!(probs[2], softmax.predict_proba(&[0.0, 3.0]).unwrap());
+    }
+
+    #[test]
+    fn logistic_predict_proba() {
+        let model = LogisticModel::new(vec![1.0, -1.0], 0.0).unwrap();
+        assert!((model.predict_proba(&[2.0, 2.0]).unwrap() - 0.5).abs() < 1e-12);
+        assert!(model.predict_proba(&[50.0, 0.0]).unwrap() > 0.99);
+        assert_eq!(model.predict(&[3.0, 1.0]).unwrap(), 1);
+        assert_eq!(model.predict(&[1.0, 3.0]).unwrap(), 0);
+        assert!(model.predict_proba(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn softmax_load_and_predict() {
+        let path = temp_file_path();
+        fs::write(&path, "0 1 0\n0 0 1\n0.5 0 0\n").unwrap();
+        let model = SoftmaxModel::load(&path).unwrap();
+        assert_eq!(model.num_classes(), 3);
+        let probs = model.predict_proba(&[4.0, 1.0]).unwrap();
+        assert!((probs.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(model.predict(&[4.0, 1.0]).unwrap(), 0);
+        assert_eq!(model.predict(&[1.0, 4.0]).unwrap(), 1);
+        fs::remove_file(path).unwrap();
+    }
 }
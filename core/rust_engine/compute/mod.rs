@@ -1,8 +1,14 @@
+pub mod clustering;
 pub mod data_transform;
 pub mod engine;
 pub mod ml_inference;
+pub mod model_registry;
+#[cfg(feature = "onnx")]
+pub mod onnx_model;
+pub mod preprocessing;
 pub mod rayon_metrics;
 pub mod resource_limits;
 pub mod simd_ops;
 pub mod tensor_ops;
 pub mod thread_pool;
+pub mod tree_ensemble;
@@ -0,0 +1,453 @@
+//! Named, versioned model registry with atomic hot swapping.
+//!
+//! Inference routes look models up by name on every request, so publishing
+//! a new version (or reloading one from disk) takes effect on the next
+//! lookup without restarting the server. Lookups hand out shared references,
+//! which keeps requests already in flight on the version they started with.
+
+use crate::compute::ml_inference::{PyLinearModel, PyLogisticModel, PySoftmaxModel};
+use crate::compute::preprocessing::PyPipeline;
+use crate::compute::tree_ensemble::PyTreeEnsemble;
+use crate::error::ForziumError;
+use crate::health::{self, HealthCheck};
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Where a model version was loaded from, so it can be reloaded later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelSource {
+    pub kind: String,
+    pub path: String,
+}
+
+struct ModelVersion<T> {
+    model: Arc<T>,
+    source: Option<ModelSource>,
+}
+
+struct ModelEntry<T> {
+    versions: BTreeMap<u32, ModelVersion<T>>,
+    active: u32,
+}
+
+/// Thread-safe map from model names to versioned models.
+pub struct Registry<T> {
+    models: RwLock<HashMap<String, ModelEntry<T>>>,
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self {
+            models: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+fn unknown_model(name: &str) -> ForziumError {
+    ForziumError::Validation(format!("unknown model '{name}'"))
+}
+
+fn unknown_version(name: &str, version: u32) -> ForziumError {
+    ForziumError::Validation(format!("model '{name}' has no version {version}"))
+}
+
+impl<T> Registry<T> {
+    /// Store `model` under `name` and make it the active version.
+    ///
+    /// Without an explicit `version` the next number after the highest
+    /// existing version is used. Returns the version that was stored.
+    pub fn register(
+        &self,
+        name: &str,
+        model: T,
+        version: Option<u32>,
+        source: Option<ModelSource>,
+    ) -> Result<u32, ForziumError> {
+        let mut models = self.models.write();
+        let entry = models.entry(name.to_string()).or_insert_with(|| ModelEntry {
+            versions: BTreeMap::new(),
+            active: 0,
+        });
+        let version = match version {
+            Some(v) if entry.versions.contains_key(&v) => {
+                return Err(ForziumError::Validation(format!(
+                    "model '{name}' already has version {v}"
+                )));
+            }
+            Some(v) => v,
+            None => entry
+                .versions
+                .keys()
+                .next_back()
+                .map_or(1, |latest| latest.saturating_add(1)),
+        };
+        entry.versions.insert(
+            version,
+            ModelVersion {
+                model: Arc::new(model),
+                source,
+            },
+        );
+        entry.active = version;
+        Ok(version)
+    }
+
+    /// Look up the active version of `name`, or a specific `version`.
+    pub fn get(&self, name: &str, version: Option<u32>) -> Result<(u32, Arc<T>), ForziumError> {
+        let models = self.models.read();
+        let entry = models.get(name).ok_or_else(|| unknown_model(name))?;
+        let version = version.unwrap_or(entry.active);
+        let stored = entry
+            .versions
+            .get(&version)
+            .ok_or_else(|| unknown_version(name, version))?;
+        Ok((version, Arc::clone(&stored.model)))
+    }
+
+    /// Make an already registered `version` the active one.
+    pub fn activate(&self, name: &str, version: u32) -> Result<(), ForziumError> {
+        let mut models = self.models.write();
+        let entry = models.get_mut(name).ok_or_else(|| unknown_model(name))?;
+        if !entry.versions.contains_key(&version) {
+            return Err(unknown_version(name, version));
+        }
+        entry.active = version;
+        Ok(())
+    }
+
+    /// Remove one version, or every version when `version` is `None`.
+    ///
+    /// Removing the active version activates the newest remaining one.
+    /// Returns whether anything was removed.
+    pub fn unregister(&self, name: &str, version: Option<u32>) -> bool {
+        let mut models = self.models.write();
+        let Some(version) = version else {
+            return models.remove(name).is_some();
+        };
+        let Some(entry) = models.get_mut(name) else {
+            return false;
+        };
+        if entry.versions.remove(&version).is_none() {
+            return false;
+        }
+        match entry.versions.keys().next_back().copied() {
+            Some(latest) if entry.active == version => entry.active = latest,
+            Some(_) => {}
+            None => {
+                models.remove(name);
+            }
+        }
+        true
+    }
+
+    /// Source of the active version of `name`, if it was loaded from disk.
+    pub fn source(&self, name: &str) -> Result<Option<ModelSource>, ForziumError> {
+        let models = self.models.read();
+        let entry = models.get(name).ok_or_else(|| unknown_model(name))?;
+        Ok(entry.versions[&entry.active].source.clone())
+    }
+
+    /// Registered versions of `name` in ascending order.
+    pub fn versions(&self, name: &str) -> Vec<u32> {
+        self.models
+            .read()
+            .get(name)
+            .map(|entry| entry.versions.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Remove the oldest versions of `name` other than the active one, so
+    /// that at most `previous` of them stay registered. Returns the removed
+    /// versions; requests still using them keep their shared references.
+    pub fn prune(&self, name: &str, previous: usize) -> Vec<u32> {
+        let mut models = self.models.write();
+        let Some(entry) = models.get_mut(name) else {
+            return Vec::new();
+        };
+        let active = entry.active;
+        let others: Vec<u32> = entry
+            .versions
+            .keys()
+            .copied()
+            .filter(|&version| version != active)
+            .collect();
+        let excess = others.len().saturating_sub(previous);
+        for version in &others[..excess] {
+            entry.versions.remove(version);
+        }
+        others[..excess].to_vec()
+    }
+
+    /// Active version of `name`, if registered.
+    pub fn active_version(&self, name: &str) -> Option<u32> {
+        self.models.read().get(name).map(|entry| entry.active)
+    }
+
+    /// Names of all registered models, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.models.read().keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// Health check failing while models a service needs are missing.
+pub struct RegistryHealthCheck<T> {
+    pub registry: Arc<Registry<T>>,
+    /// Models that must be registered; when empty, any one model will do.
+    pub required: Vec<String>,
+}
+
+impl<T: Send + Sync> HealthCheck for RegistryHealthCheck<T> {
+    fn check(&self) -> Result<(), String> {
+        let names = self.registry.names();
+        if self.required.is_empty() && names.is_empty() {
+            return Err("no models registered".into());
+        }
+        let missing: Vec<&str> = self
+            .required
+            .iter()
+            .filter(|name| !names.contains(name))
+            .map(String::as_str)
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("models not registered: {}", missing.join(", ")))
+        }
+    }
+}
+
+/// Load a model of the given kind from disk through its Python class.
+fn load_model(py: Python<'_>, source: &ModelSource) -> PyResult<Py<PyAny>> {
+    let cls = match source.kind.as_str() {
+        "linear" => py.get_type::<PyLinearModel>(),
+        "logistic" => py.get_type::<PyLogisticModel>(),
+        "softmax" => py.get_type::<PySoftmaxModel>(),
+        "tree_ensemble" => py.get_type::<PyTreeEnsemble>(),
+        "pipeline" => py.get_type::<PyPipeline>(),
+        other => {
+            return Err(
+                ForziumError::Validation(format!("unknown model kind '{other}'")).into(),
+            );
+        }
+    };
+    Ok(cls.call_method1("load", (source.path.as_str(),))?.unbind())
+}
+
+/// Previous versions [`PyModelRegistry::reload`] keeps by default.
+pub const DEFAULT_KEEP_VERSIONS: usize = 2;
+
+/// Registry of Python model objects shared across inference routes.
+#[pyclass(name = "ModelRegistry", frozen)]
+pub struct PyModelRegistry {
+    inner: Arc<Registry<Py<PyAny>>>,
+    /// Versions kept besides the active one when a model is reloaded.
+    keep_versions: usize,
+}
+
+#[pymethods]
+impl PyModelRegistry {
+    /// `keep_versions` is how many previous versions of a model stay
+    /// registered after [`reload`](Self::reload), for rolling back.
+    #[new]
+    #[pyo3(signature = (keep_versions=DEFAULT_KEEP_VERSIONS))]
+    pub fn new(keep_versions: usize) -> Self {
+        Self {
+            inner: Arc::default(),
+            keep_versions,
+        }
+    }
+
+    /// Register a model object and make it the active version.
+    ///
+    /// Returns the version number that was assigned.
+    #[pyo3(signature = (name, model, version=None))]
+    pub fn register(&self, name: &str, model: Py<PyAny>, version: Option<u32>) -> PyResult<u32> {
+        self.inner
+            .register(name, model, version, None)
+            .map_err(Into::into)
+    }
+
+    /// Load a model from disk and make it the active version.
+    ///
+    /// `kind` is one of `"linear"`, `"logistic"`, `"softmax"`,
+    /// `"tree_ensemble"` or `"pipeline"`.
+    #[pyo3(signature = (name, kind, path, version=None))]
+    pub fn load(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        kind: &str,
+        path: &str,
+        version: Option<u32>,
+    ) -> PyResult<u32> {
+        let source = ModelSource {
+            kind: kind.to_string(),
+            path: path.to_string(),
+        };
+        let model = load_model(py, &source)?;
+        self.inner
+            .register(name, model, version, Some(source))
+            .map_err(Into::into)
+    }
+
+    /// Re-read the active version's file and publish it as a new version.
+    ///
+    /// The `keep_versions` most recent previous versions stay registered so
+    /// they can be re-activated if the new one misbehaves; older ones are
+    /// removed.
+    pub fn reload(&self, py: Python<'_>, name: &str) -> PyResult<u32> {
+        let source = self.inner.source(name)?.ok_or_else(|| {
+            ForziumError::Validation(format!("model '{name}' was not loaded from disk"))
+        })?;
+        let model = load_model(py, &source)?;
+        let version = self.inner.register(name, model, None, Some(source))?;
+        self.inner.prune(name, self.keep_versions);
+        Ok(version)
+    }
+
+    /// Return the active model for `name`, or a specific version.
+    #[pyo3(signature = (name, version=None))]
+    pub fn get(&self, py: Python<'_>, name: &str, version: Option<u32>) -> PyResult<Py<PyAny>> {
+        let (_, model) = self.inner.get(name, version)?;
+        Ok(model.clone_ref(py))
+    }
+
+    /// Switch the active version of `name`.
+    pub fn activate(&self, name: &str, version: u32) -> PyResult<()> {
+        self.inner.activate(name, version).map_err(Into::into)
+    }
+
+    /// Remove a version, or the whole model when no version is given.
+    #[pyo3(signature = (name, version=None))]
+    pub fn unregister(&self, name: &str, version: Option<u32>) -> bool {
+        self.inner.unregister(name, version)
+    }
+
+    /// Registered versions of `name` in ascending order.
+    pub fn versions(&self, name: &str) -> Vec<u32> {
+        self.inner.versions(name)
+    }
+
+    /// Active version of `name`, or `None` when not registered.
+    pub fn active_version(&self, name: &str) -> Option<u32> {
+        self.inner.active_version(name)
+    }
+
+    /// Names of all registered models.
+    pub fn names(&self) -> Vec<String> {
+        self.inner.names()
+    }
+
+    /// Summary of every model: active version, versions and source path.
+    pub fn describe<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let out = PyDict::new(py);
+        for name in self.inner.names() {
+            let info = PyDict::new(py);
+            info.set_item("active", self.inner.active_version(&name))?;
+            info.set_item("versions", self.inner.versions(&name))?;
+            let source = self.inner.source(&name).ok().flatten();
+            info.set_item("kind", source.as_ref().map(|s| s.kind.clone()))?;
+            info.set_item("path", source.map(|s| s.path))?;
+            out.set_item(name, info)?;
+        }
+        Ok(out)
+    }
+
+    /// Fail the server's `/ready` check `name` while any of the `required`
+    /// models is missing, or while the registry is empty if none are given.
+    #[pyo3(signature = (name, required=None))]
+    pub fn register_health_check(&self, name: &str, required: Option<Vec<String>>) {
+        health::register_check(
+            name,
+            Arc::new(RegistryHealthCheck {
+                registry: self.inner.clone(),
+                required: required.unwrap_or_default(),
+            }),
+        );
+    }
+
+    fn __contains__(&self, name: &str) -> bool {
+        self.inner.active_version(name).is_some()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.names().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_assigns_versions_and_swaps_active() {
+        let registry = Registry::default();
+        assert_eq!(registry.register("m", "a", None, None).unwrap(), 1);
+        let (_, in_flight) = registry.get("m", None).unwrap();
+        assert_eq!(registry.register("m", "b", None, None).unwrap(), 2);
+        assert_eq!(*in_flight, "a");
+        assert_eq!(registry.get("m", None).unwrap(), (2, Arc::new("b")));
+        assert!(registry.register("m", "c", Some(2), None).is_err());
+
+        registry.activate("m", 1).unwrap();
+        assert_eq!(*registry.get("m", None).unwrap().1, "a");
+        assert!(registry.activate("m", 9).is_err());
+        assert!(registry.get("missing", None).is_err());
+    }
+
+    #[test]
+    fn unregister_falls_back_to_latest() {
+        let registry = Registry::default();
+        registry.register("m", 1, Some(1), None).unwrap();
+        registry.register("m", 5, Some(5), None).unwrap();
+        registry.register("m", 3, Some(3), None).unwrap();
+        assert_eq!(registry.versions("m"), vec![1, 3, 5]);
+        assert!(registry.unregister("m", Some(3)));
+        assert_eq!(registry.active_version("m"), Some(5));
+        assert!(!registry.unregister("m", Some(3)));
+        assert!(registry.unregister("m", None));
+        assert!(registry.names().is_empty());
+    }
+
+    #[test]
+    fn prune_keeps_the_active_and_recent_versions() {
+        let registry = Registry::default();
+        for version in 1..=5 {
+            registry
+                .register("m", version, Some(version), None)
+                .unwrap();
+        }
+        registry.activate("m", 2).unwrap();
+        let (_, in_flight) = registry.get("m", Some(1)).unwrap();
+        assert_eq!(registry.prune("m", 2), vec![1, 3]);
+        assert_eq!(registry.versions("m"), vec![2, 4, 5]);
+        assert_eq!(*in_flight, 1);
+        assert_eq!(registry.prune("m", 0), vec![4, 5]);
+        assert_eq!(registry.versions("m"), vec![2]);
+        assert!(registry.prune("missing", 0).is_empty());
+    }
+
+    #[test]
+    fn health_check_requires_models() {
+        let registry = Arc::new(Registry::default());
+        let any = RegistryHealthCheck {
+            registry: registry.clone(),
+            required: Vec::new(),
+        };
+        let named = RegistryHealthCheck {
+            registry: registry.clone(),
+            required: vec!["a".into(), "b".into()],
+        };
+        assert!(any.check().is_err());
+        registry.register("a", 1, None, None).unwrap();
+        assert!(any.check().is_ok());
+        assert_eq!(named.check().unwrap_err(), "models not registered: b");
+        registry.register("b", 2, None, None).unwrap();
+        assert!(named.check().is_ok());
+    }
+}
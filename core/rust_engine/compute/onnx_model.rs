@@ -0,0 +1,146 @@
+//! ONNX model inference backed by the tract runtime.
+//!
+//! Only compiled with the `onnx` feature enabled.
+
+use crate::compute::ml_inference::{extract_batch, validate_batch};
+use crate::compute::resource_limits::enforce_tensor_size;
+use crate::error::ForziumError;
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+use tract_onnx::prelude::*;
+use tract_onnx::tract_core::internal::DimLike;
+
+type OnnxPlan = TypedSimplePlan<TypedModel>;
+
+fn onnx_error(context: &str, err: impl std::fmt::Display) -> ForziumError {
+    ForziumError::Compute(format!("{context}: {err}"))
+}
+
+/// A loaded ONNX graph with a single `[batch, features]` input and a single
+/// output.
+pub struct OnnxModel {
+    plan: OnnxPlan,
+    /// Declared input dimensions; `None` marks symbolic (e.g. batch) axes.
+    input_shape: Vec<Option<usize>>,
+    input_type: DatumType,
+}
+
+impl OnnxModel {
+    /// Load and optimize an ONNX model from disk.
+    pub fn load(path: &str) -> Result<Self, ForziumError> {
+        let model = tract_onnx::onnx()
+            .model_for_path(path)
+            .map_err(|e| onnx_error("failed to load ONNX model", e))?
+            .into_optimized()
+            .map_err(|e| onnx_error("failed to optimize ONNX model", e))?;
+        let fact = model
+            .input_fact(0)
+            .map_err(|e| onnx_error("ONNX model has no input", e))?;
+        let input_shape: Vec<Option<usize>> =
+            fact.shape.iter().map(|d| d.to_usize().ok()).collect();
+        if input_shape.len() != 2 {
+            return Err(ForziumError::Compute(format!(
+                "unsupported ONNX input rank {}",
+                input_shape.len()
+            )));
+        }
+        let input_type = fact.datum_type;
+        if input_type != f32::datum_type() && input_type != f64::datum_type() {
+            return Err(ForziumError::Compute(format!(
+                "unsupported ONNX input type {input_type:?}"
+            )));
+        }
+        let plan = model
+            .into_runnable()
+            .map_err(|e| onnx_error("failed to prepare ONNX model", e))?;
+        Ok(Self {
+            plan,
+            input_shape,
+            input_type,
+        })
+    }
+
+    /// Declared input dimensions, with `None` for symbolic axes.
+    pub fn input_shape(&self) -> &[Option<usize>] {
+        &self.input_shape
+    }
+
+    /// Run the model on a row-major batch of `cols`-wide rows, returning
+    /// the first output flattened along with its shape.
+    ///
+    /// A symbolic feature axis accepts any width; the batch axis always
+    /// does.
+    pub fn run(&self, data: &[f64], cols: usize) -> Result<(Vec<f64>, Vec<usize>), ForziumError> {
+        if data.is_empty() {
+            return Err(ForziumError::Validation("empty input".into()));
+        }
+        validate_batch(data, cols, self.input_shape[1].unwrap_or(cols))?;
+        let shape = [data.len() / cols, cols];
+        let tensor = if self.input_type == f32::datum_type() {
+            let values: Vec<f32> = data.iter().map(|&v| v as f32).collect();
+            Tensor::from_shape(&shape, &values)
+        } else {
+            Tensor::from_shape(&shape, data)
+        }
+        .map_err(|e| onnx_error("invalid ONNX input", e))?;
+
+        let outputs = self
+            .plan
+            .run(tvec!(tensor.into()))
+            .map_err(|e| onnx_error("ONNX inference failed", e))?;
+        let output = outputs
+            .first()
+            .ok_or_else(|| ForziumError::Compute("ONNX model produced no output".into()))?;
+        let output_shape = output.shape().to_vec();
+        let values = output
+            .cast_to::<f64>()
+            .map_err(|e| onnx_error("unsupported ONNX output type", e))?;
+        let values = values
+            .as_slice::<f64>()
+            .map_err(|e| onnx_error("unsupported ONNX output layout", e))?
+            .to_vec();
+        Ok((values, output_shape))
+    }
+}
+
+#[pyclass(name = "OnnxModel", frozen)]
+pub struct PyOnnxModel {
+    inner: OnnxModel,
+}
+
+#[pymethods]
+impl PyOnnxModel {
+    /// Load an ONNX model from disk.
+    #[classmethod]
+    pub fn load(_cls: &Bound<PyType>, path: &str) -> PyResult<Self> {
+        let inner = OnnxModel::load(path)?;
+        Ok(Self { inner })
+    }
+
+    /// Declared input dimensions, with `None` for symbolic axes.
+    #[getter]
+    pub fn input_shape(&self) -> Vec<Option<usize>> {
+        self.inner.input_shape().to_vec()
+    }
+
+    /// Run the model on a 2D batch of rows, returning one output row per
+    /// input row.
+    pub fn predict(&self, py: Python<'_>, inputs: &Bound<'_, PyAny>) -> PyResult<Vec<Vec<f64>>> {
+        let (data, cols) = extract_batch(inputs)?;
+        enforce_tensor_size(data.len() / cols.max(1), cols, "onnx")?;
+        let (values, shape) = py.detach(|| self.inner.run(&data, cols))?;
+        let width = shape.iter().skip(1).product::<usize>().max(1);
+        Ok(values.chunks(width).map(<[f64]>::to_vec).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_model_file() {
+        let err = OnnxModel::load("/no/such/model.onnx").err().unwrap();
+        assert!(matches!(err, ForziumError::Compute(_)));
+    }
+}
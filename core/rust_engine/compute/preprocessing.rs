@@ -0,0 +1,638 @@
+//! Fit/transform preprocessing composed with models from `ml_inference`.
+//!
+//! A [`Pipeline`] is fitted once from training rows, then applies its steps
+//! (standard scaling, min-max scaling, one-hot encoding) and an optional
+//! [`LinearModel`] to each request entirely in Rust. Pipelines serialize to a
+//! versioned binary format that embeds the model's own encoding.
+
+use crate::compute::ml_inference::{LinearModel, PyLinearModel, extract_batch};
+use crate::compute::rayon_metrics;
+use crate::error::ForziumError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyType};
+use rayon::prelude::*;
+use std::fs;
+
+/// Magic prefix identifying a serialized [`Pipeline`].
+const PIPELINE_MAGIC: &[u8; 4] = b"FZPP";
+/// Current version of the binary [`Pipeline`] format.
+const PIPELINE_VERSION: u8 = 1;
+
+const TAG_STANDARD: u8 = 1;
+const TAG_MIN_MAX: u8 = 2;
+const TAG_ONE_HOT: u8 = 3;
+
+/// Unfitted description of a preprocessing step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepSpec {
+    /// Center each column on its mean and divide by its standard deviation.
+    StandardScaler,
+    /// Rescale each column to the `[0, 1]` range seen during fitting.
+    MinMaxScaler,
+    /// Replace each listed column with one indicator per observed category.
+    OneHot(Vec<usize>),
+}
+
+impl StepSpec {
+    /// Parse a step name as accepted from Python.
+    fn from_name(name: &str, columns: Option<Vec<usize>>) -> Result<Self, ForziumError> {
+        match (name, columns) {
+            ("standard_scaler", None) => Ok(Self::StandardScaler),
+            ("min_max_scaler", None) => Ok(Self::MinMaxScaler),
+            ("one_hot", Some(columns)) => Ok(Self::OneHot(columns)),
+            ("one_hot", None) => Err(ForziumError::Validation(
+                "one_hot requires a list of columns".into(),
+            )),
+            (other, _) => Err(ForziumError::Validation(format!(
+                "unknown preprocessing step '{other}'"
+            ))),
+        }
+    }
+}
+
+/// A fitted preprocessing step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Preprocessor {
+    StandardScaler { means: Vec<f64>, scales: Vec<f64> },
+    MinMaxScaler { mins: Vec<f64>, ranges: Vec<f64> },
+    OneHot {
+        width: usize,
+        columns: Vec<usize>,
+        categories: Vec<Vec<f64>>,
+    },
+}
+
+/// Replace zero spreads with 1 so constant columns pass through unscaled.
+fn nonzero_spread(spread: f64) -> f64 {
+    if spread == 0.0 { 1.0 } else { spread }
+}
+
+impl Preprocessor {
+    /// Fit a step described by `spec` on a row-major batch of `cols`-wide rows.
+    pub fn fit(spec: &StepSpec, data: &[f64], cols: usize) -> Result<Self, ForziumError> {
+        if data.is_empty() || cols == 0 || !data.len().is_multiple_of(cols) {
+            return Err(ForziumError::Validation(
+                "fit requires a non-empty rectangular batch".into(),
+            ));
+        }
+        let rows = (data.len() / cols) as f64;
+        let column = |c: usize| data.iter().skip(c).step_by(cols).copied();
+        Ok(match spec {
+            StepSpec::StandardScaler => {
+                let means: Vec<f64> = (0..cols).map(|c| column(c).sum::<f64>() / rows).collect();
+                let scales = (0..cols)
+                    .map(|c| {
+                        let var = column(c).map(|x| (x - means[c]).powi(2)).sum::<f64>() / rows;
+                        nonzero_spread(var.sqrt())
+                    })
+                    .collect();
+                Self::StandardScaler { means, scales }
+            }
+            StepSpec::MinMaxScaler => {
+                let mins: Vec<f64> = (0..cols)
+                    .map(|c| column(c).fold(f64::INFINITY, f64::min))
+                    .collect();
+                let ranges = (0..cols)
+                    .map(|c| nonzero_spread(column(c).fold(f64::NEG_INFINITY, f64::max) - mins[c]))
+                    .collect();
+                Self::MinMaxScaler { mins, ranges }
+            }
+            StepSpec::OneHot(columns) => {
+                let mut sorted = columns.clone();
+                sorted.sort_unstable();
+                sorted.dedup();
+                if sorted.len() != columns.len() || sorted.last().is_some_and(|&c| c >= cols) {
+                    return Err(ForziumError::Validation(
+                        "one_hot columns must be unique and within the input width".into(),
+                    ));
+                }
+                let categories = sorted
+                    .iter()
+                    .map(|&c| {
+                        let mut values: Vec<f64> = column(c).collect();
+                        values.sort_by(f64::total_cmp);
+                        values.dedup();
+                        values
+                    })
+                    .collect();
+                Self::OneHot {
+                    width: cols,
+                    columns: sorted,
+                    categories,
+                }
+            }
+        })
+    }
+
+    /// Width of the rows this step accepts.
+    pub fn input_width(&self) -> usize {
+        match self {
+            Self::StandardScaler { means, .. } => means.len(),
+            Self::MinMaxScaler { mins, .. } => mins.len(),
+            Self::OneHot { width, .. } => *width,
+        }
+    }
+
+    /// Width of the rows this step produces.
+    pub fn output_width(&self) -> usize {
+        match self {
+            Self::OneHot {
+                width,
+                columns,
+                categories,
+            } => width - columns.len() + categories.iter().map(Vec::len).sum::<usize>(),
+            _ => self.input_width(),
+        }
+    }
+
+    /// Transform a single row, which must be `input_width()` wide.
+    pub fn transform_row(&self, row: &[f64]) -> Vec<f64> {
+        match self {
+            Self::StandardScaler { means, scales } => row
+                .iter()
+                .zip(means.iter().zip(scales.iter()))
+                .map(|(x, (mean, scale))| (x - mean) / scale)
+                .collect(),
+            Self::MinMaxScaler { mins, ranges } => row
+                .iter()
+                .zip(mins.iter().zip(ranges.iter()))
+                .map(|(x, (min, range))| (x - min) / range)
+                .collect(),
+            Self::OneHot {
+                columns,
+                categories,
+                ..
+            } => {
+                let mut out = Vec::with_capacity(self.output_width());
+                let mut encoded = columns.iter().zip(categories.iter()).peekable();
+                for (idx, &x) in row.iter().enumerate() {
+                    match encoded.next_if(|(c, _)| **c == idx) {
+                        Some((_, cats)) => out.extend(cats.iter().map(|&cat| f64::from(cat == x))),
+                        None => out.push(x),
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::StandardScaler { means, scales } => {
+                out.push(TAG_STANDARD);
+                write_len(out, means.len());
+                write_values(out, means);
+                write_values(out, scales);
+            }
+            Self::MinMaxScaler { mins, ranges } => {
+                out.push(TAG_MIN_MAX);
+                write_len(out, mins.len());
+                write_values(out, mins);
+                write_values(out, ranges);
+            }
+            Self::OneHot {
+                width,
+                columns,
+                categories,
+            } => {
+                out.push(TAG_ONE_HOT);
+                write_len(out, *width);
+                write_len(out, columns.len());
+                for (column, cats) in columns.iter().zip(categories.iter()) {
+                    write_len(out, *column);
+                    write_len(out, cats.len());
+                    write_values(out, cats);
+                }
+            }
+        }
+    }
+
+    fn read(reader: &mut ByteReader<'_>) -> Result<Self, ForziumError> {
+        match reader.u8()? {
+            TAG_STANDARD => {
+                let n = reader.len()?;
+                Ok(Self::StandardScaler {
+                    means: reader.values(n)?,
+                    scales: reader.values(n)?,
+                })
+            }
+            TAG_MIN_MAX => {
+                let n = reader.len()?;
+                Ok(Self::MinMaxScaler {
+                    mins: reader.values(n)?,
+                    ranges: reader.values(n)?,
+                })
+            }
+            TAG_ONE_HOT => {
+                let width = reader.len()?;
+                let count = reader.len()?;
+                let mut columns = Vec::with_capacity(count);
+                let mut categories = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let column = reader.len()?;
+                    if column >= width || columns.last().is_some_and(|&prev| prev >= column) {
+                        return Err(ForziumError::Compute("invalid pipeline data".into()));
+                    }
+                    columns.push(column);
+                    let k = reader.len()?;
+                    categories.push(reader.values(k)?);
+                }
+                Ok(Self::OneHot {
+                    width,
+                    columns,
+                    categories,
+                })
+            }
+            tag => Err(ForziumError::Compute(format!(
+                "unknown pipeline step tag {tag}"
+            ))),
+        }
+    }
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn write_values(out: &mut Vec<u8>, values: &[f64]) {
+    for v in values {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+/// Cursor over a serialized pipeline.
+struct ByteReader<'a> {
+    data: &'a [u8],
+}
+
+impl ByteReader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], ForziumError> {
+        if self.data.len() < n {
+            return Err(ForziumError::Compute("pipeline data incomplete".into()));
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, ForziumError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, ForziumError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("4-byte chunk")) as usize)
+    }
+
+    fn values(&mut self, n: usize) -> Result<Vec<f64>, ForziumError> {
+        let bytes = self.take(n.saturating_mul(8))?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|chunk| f64::from_le_bytes(chunk.try_into().expect("8-byte chunk")))
+            .collect())
+    }
+}
+
+/// Fitted preprocessing steps optionally followed by a linear model.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    steps: Vec<Preprocessor>,
+    model: Option<LinearModel>,
+}
+
+impl Pipeline {
+    /// Fit each step in order on the output of the previous one.
+    pub fn fit(specs: &[StepSpec], data: &[f64], cols: usize) -> Result<Self, ForziumError> {
+        if specs.is_empty() {
+            return Err(ForziumError::Validation(
+                "pipeline needs at least one step".into(),
+            ));
+        }
+        let mut steps = Vec::with_capacity(specs.len());
+        let mut current = data.to_vec();
+        let mut width = cols;
+        for spec in specs {
+            let step = Preprocessor::fit(spec, &current, width)?;
+            current = current
+                .chunks(width)
+                .flat_map(|row| step.transform_row(row))
+                .collect();
+            width = step.output_width();
+            steps.push(step);
+        }
+        Ok(Self { steps, model: None })
+    }
+
+    /// Width of the rows the pipeline accepts.
+    pub fn input_width(&self) -> usize {
+        self.steps[0].input_width()
+    }
+
+    /// Width of the rows produced by the preprocessing steps.
+    pub fn output_width(&self) -> usize {
+        self.steps.last().map_or(0, Preprocessor::output_width)
+    }
+
+    /// Attach the model applied after preprocessing.
+    pub fn set_model(&mut self, model: LinearModel) -> Result<(), ForziumError> {
+        if model.num_features() != self.output_width() {
+            return Err(ForziumError::Validation(format!(
+                "model expects {} features but the pipeline produces {}",
+                model.num_features(),
+                self.output_width()
+            )));
+        }
+        self.model = Some(model);
+        Ok(())
+    }
+
+    /// Run every preprocessing step on a single row.
+    pub fn transform(&self, input: &[f64]) -> Result<Vec<f64>, ForziumError> {
+        if input.len() != self.input_width() {
+            return Err(ForziumError::Compute("input length mismatch".into()));
+        }
+        let mut row = input.to_vec();
+        for step in &self.steps {
+            row = step.transform_row(&row);
+        }
+        Ok(row)
+    }
+
+    /// Preprocess a single row and run the attached model on it.
+    pub fn predict(&self, input: &[f64]) -> Result<f64, ForziumError> {
+        let model = self
+            .model
+            .as_ref()
+            .ok_or_else(|| ForziumError::Compute("pipeline has no model".into()))?;
+        model.predict(&self.transform(input)?)
+    }
+
+    /// Transform a row-major batch of `cols`-wide rows.
+    pub fn transform_batch(&self, data: &[f64], cols: usize) -> Result<Vec<Vec<f64>>, ForziumError> {
+        self.map_rows(data, cols, |row| self.transform(row))
+    }
+
+    /// Predict outputs for a row-major batch of `cols`-wide rows.
+    pub fn predict_batch(&self, data: &[f64], cols: usize) -> Result<Vec<f64>, ForziumError> {
+        self.map_rows(data, cols, |row| self.predict(row))
+    }
+
+    fn map_rows<T, F>(&self, data: &[f64], cols: usize, f: F) -> Result<Vec<T>, ForziumError>
+    where
+        T: Send,
+        F: Fn(&[f64]) -> Result<T, ForziumError> + Sync,
+    {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+        if cols != self.input_width() || !data.len().is_multiple_of(cols) {
+            return Err(ForziumError::Compute("input length mismatch".into()));
+        }
+        data.par_chunks(cols)
+            .map(|row| {
+                let _guard = rayon_metrics::track_task();
+                f(row)
+            })
+            .collect()
+    }
+
+    /// Serialize the pipeline, including its model, into the binary format.
+    ///
+    /// Layout: the `FZPP` magic, a version byte, the step count as a
+    /// little-endian `u32`, each step as a tag byte plus its parameters, then
+    /// a model flag byte optionally followed by the model's byte length and
+    /// its [`LinearModel::to_bytes`] encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(PIPELINE_MAGIC);
+        out.push(PIPELINE_VERSION);
+        write_len(&mut out, self.steps.len());
+        for step in &self.steps {
+            step.write(&mut out);
+        }
+        match &self.model {
+            Some(model) => {
+                let bytes = model.to_bytes();
+                out.push(1);
+                write_len(&mut out, bytes.len());
+                out.extend_from_slice(&bytes);
+            }
+            None => out.push(0),
+        }
+        out
+    }
+
+    /// Deserialize a pipeline produced by [`Pipeline::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ForziumError> {
+        if !data.starts_with(PIPELINE_MAGIC) {
+            return Err(ForziumError::Compute("invalid pipeline data".into()));
+        }
+        let mut reader = ByteReader {
+            data: &data[PIPELINE_MAGIC.len()..],
+        };
+        let version = reader.u8()?;
+        if version != PIPELINE_VERSION {
+            return Err(ForziumError::Compute(format!(
+                "unsupported pipeline format version {version}"
+            )));
+        }
+        let count = reader.len()?;
+        let mut steps: Vec<Preprocessor> = Vec::new();
+        for _ in 0..count {
+            let step = Preprocessor::read(&mut reader)?;
+            let expected = steps.last().map_or(step.input_width(), Preprocessor::output_width);
+            if step.input_width() == 0 || step.input_width() != expected {
+                return Err(ForziumError::Compute("invalid pipeline data".into()));
+            }
+            steps.push(step);
+        }
+        if steps.is_empty() {
+            return Err(ForziumError::Compute("invalid pipeline data".into()));
+        }
+        let mut pipeline = Self { steps, model: None };
+        if reader.u8()? == 1 {
+            let len = reader.len()?;
+            let model = LinearModel::from_bytes(reader.take(len)?)?;
+            pipeline
+                .set_model(model)
+                .map_err(|e| ForziumError::Compute(e.to_string()))?;
+        }
+        if !reader.data.is_empty() {
+            return Err(ForziumError::Compute("trailing pipeline data".into()));
+        }
+        Ok(pipeline)
+    }
+
+    /// Read a pipeline written by [`Pipeline::save`].
+    pub fn load(path: &str) -> Result<Self, ForziumError> {
+        let data =
+            fs::read(path).map_err(|_| ForziumError::Compute("pipeline file not found".into()))?;
+        Self::from_bytes(&data)
+    }
+
+    /// Write the pipeline to `path` in the binary format.
+    pub fn save(&self, path: &str) -> Result<(), ForziumError> {
+        fs::write(path, self.to_bytes())
+            .map_err(|e| ForziumError::Compute(format!("failed to save pipeline: {e}")))
+    }
+}
+
+/// Parse a Python step description: a name, or a `(name, columns)` tuple.
+fn extract_step(step: &Bound<'_, PyAny>) -> PyResult<StepSpec> {
+    if let Ok(name) = step.extract::<String>() {
+        return Ok(StepSpec::from_name(&name, None)?);
+    }
+    let (name, columns): (String, Vec<usize>) = step.extract()?;
+    Ok(StepSpec::from_name(&name, Some(columns))?)
+}
+
+#[pyclass(name = "Pipeline")]
+pub struct PyPipeline {
+    inner: Pipeline,
+}
+
+#[pymethods]
+impl PyPipeline {
+    /// Fit preprocessing steps on a 2D array of training rows.
+    ///
+    /// Each step is `"standard_scaler"`, `"min_max_scaler"`, or
+    /// `("one_hot", columns)`.
+    #[classmethod]
+    pub fn fit(
+        _cls: &Bound<PyType>,
+        py: Python<'_>,
+        inputs: &Bound<'_, PyAny>,
+        steps: Vec<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let specs = steps
+            .iter()
+            .map(extract_step)
+            .collect::<PyResult<Vec<StepSpec>>>()?;
+        let (data, cols) = extract_batch(inputs)?;
+        let inner = py.detach(|| Pipeline::fit(&specs, &data, cols))?;
+        Ok(Self { inner })
+    }
+
+    /// Attach a linear model applied after preprocessing.
+    pub fn set_model(&mut self, model: PyRef<'_, PyLinearModel>) -> PyResult<()> {
+        self.inner.set_model(model.inner.clone()).map_err(Into::into)
+    }
+
+    /// Width of the rows the pipeline accepts.
+    #[getter]
+    pub fn input_width(&self) -> usize {
+        self.inner.input_width()
+    }
+
+    /// Width of the rows produced by the preprocessing steps.
+    #[getter]
+    pub fn output_width(&self) -> usize {
+        self.inner.output_width()
+    }
+
+    /// Run the preprocessing steps on a single input vector.
+    pub fn transform(&self, input: Vec<f64>) -> PyResult<Vec<f64>> {
+        self.inner.transform(&input).map_err(Into::into)
+    }
+
+    /// Run the preprocessing steps on a 2D array of inputs.
+    pub fn transform_batch(
+        &self,
+        py: Python<'_>,
+        inputs: &Bound<'_, PyAny>,
+    ) -> PyResult<Vec<Vec<f64>>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.transform_batch(&data, cols))
+            .map_err(Into::into)
+    }
+
+    /// Preprocess and predict output for a single input vector.
+    pub fn predict(&self, input: Vec<f64>) -> PyResult<f64> {
+        self.inner.predict(&input).map_err(Into::into)
+    }
+
+    /// Preprocess and predict outputs for a 2D array of inputs.
+    pub fn predict_batch(&self, py: Python<'_>, inputs: &Bound<'_, PyAny>) -> PyResult<Vec<f64>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.predict_batch(&data, cols))
+            .map_err(Into::into)
+    }
+
+    /// Serialize the pipeline and its model into the versioned binary format.
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.to_bytes())
+    }
+
+    /// Restore a pipeline from bytes produced by `to_bytes`.
+    #[classmethod]
+    pub fn from_bytes(_cls: &Bound<PyType>, data: &[u8]) -> PyResult<Self> {
+        let inner = Pipeline::from_bytes(data)?;
+        Ok(Self { inner })
+    }
+
+    /// Load a pipeline from disk.
+    #[classmethod]
+    pub fn load(_cls: &Bound<PyType>, path: &str) -> PyResult<Self> {
+        let inner = Pipeline::load(path)?;
+        Ok(Self { inner })
+    }
+
+    /// Save the pipeline to disk in the binary format.
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        self.inner.save(path).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: [f64; 6] = [1.0, 10.0, 2.0, 20.0, 3.0, 10.0];
+
+    #[test]
+    fn scalers_fit_and_transform() {
+        let standard = Preprocessor::fit(&StepSpec::StandardScaler, &DATA, 2).unwrap();
+        let row = standard.transform_row(&[2.0, 40.0 / 3.0]);
+        assert!(row.iter().all(|x| x.abs() < 1e-12));
+
+        let min_max = Preprocessor::fit(&StepSpec::MinMaxScaler, &DATA, 2).unwrap();
+        assert_eq!(min_max.transform_row(&[3.0, 15.0]), vec![1.0, 0.5]);
+
+        let constant = Preprocessor::fit(&StepSpec::MinMaxScaler, &[5.0, 5.0], 1).unwrap();
+        assert_eq!(constant.transform_row(&[6.0]), vec![1.0]);
+    }
+
+    #[test]
+    fn one_hot_expands_columns() {
+        let step = Preprocessor::fit(&StepSpec::OneHot(vec![1]), &DATA, 2).unwrap();
+        assert_eq!(step.output_width(), 3);
+        assert_eq!(step.transform_row(&[7.0, 20.0]), vec![7.0, 0.0, 1.0]);
+        assert_eq!(step.transform_row(&[7.0, 99.0]), vec![7.0, 0.0, 0.0]);
+        assert!(Preprocessor::fit(&StepSpec::OneHot(vec![2]), &DATA, 2).is_err());
+    }
+
+    #[test]
+    fn pipeline_round_trip_with_model() {
+        let specs = [StepSpec::OneHot(vec![1]), StepSpec::MinMaxScaler];
+        let mut pipeline = Pipeline::fit(&specs, &DATA, 2).unwrap();
+        assert_eq!(pipeline.transform(&[2.0, 10.0]).unwrap(), vec![0.5, 1.0, 0.0]);
+
+        let mut bytes = b"FZLM\x01".to_vec();
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        for v in [1.0f64, 2.0, 3.0, 4.0] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        let model = LinearModel::from_bytes(&bytes).unwrap();
+        assert!(pipeline.predict(&[2.0, 10.0]).is_err());
+        pipeline.set_model(model).unwrap();
+        assert_eq!(pipeline.predict(&[2.0, 10.0]).unwrap(), 5.0);
+
+        let restored = Pipeline::from_bytes(&pipeline.to_bytes()).unwrap();
+        assert_eq!(
+            restored.predict_batch(&DATA, 2).unwrap(),
+            pipeline.predict_batch(&DATA, 2).unwrap()
+        );
+        let encoded = pipeline.to_bytes();
+        assert!(Pipeline::from_bytes(&encoded[..encoded.len() - 1]).is_err());
+    }
+}
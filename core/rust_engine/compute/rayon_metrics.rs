@@ -1,34 +1,117 @@
 use serde::Serialize;
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Mutex, OnceLock, RwLock};
 use std::time::{Duration, Instant};
 use thiserror::Error;
 
+/// Pool label for tasks run on threads no named pool has tagged, such as
+/// workers of Rayon's global pool.
+pub const UNTAGGED_POOL: &str = "global";
+
+/// Upper bounds of the task duration histogram buckets in microseconds.
+/// Durations above the last bound land in a final overflow bucket.
+pub const DURATION_BUCKETS_US: [u64; 12] = [
+    10, 50, 100, 500, 1_000, 5_000, 10_000, 50_000, 100_000, 500_000, 1_000_000, 5_000_000,
+];
+
 /// Lazily constructed global metrics collector for the Rayon thread pool.
 fn metrics() -> &'static RayonMetrics {
     static METRICS: OnceLock<RayonMetrics> = OnceLock::new();
     METRICS.get_or_init(RayonMetrics::default)
 }
 
+/// Metrics collectors keyed by a pool or operation name.
+///
+/// Collectors live for the rest of the process so worker threads can hold
+/// plain references to them; a recreated pool reuses its name's collector.
+type Registry = RwLock<HashMap<String, &'static RayonMetrics>>;
+
+fn pool_registry() -> &'static Registry {
+    static POOLS: OnceLock<Registry> = OnceLock::new();
+    POOLS.get_or_init(Default::default)
+}
+
+fn operation_registry() -> &'static Registry {
+    static OPERATIONS: OnceLock<Registry> = OnceLock::new();
+    OPERATIONS.get_or_init(Default::default)
+}
+
+fn labelled_metrics(registry: &'static Registry, name: &str) -> &'static RayonMetrics {
+    if let Some(metrics) = registry
+        .read()
+        .expect("metrics registry poisoned")
+        .get(name)
+    {
+        return metrics;
+    }
+    let mut entries = registry.write().expect("metrics registry poisoned");
+    entries
+        .entry(name.to_string())
+        .or_insert_with(|| Box::leak(Box::default()))
+}
+
+fn labelled_snapshots(registry: &'static Registry) -> Vec<(String, RayonPoolSnapshot)> {
+    let entries = registry.read().expect("metrics registry poisoned");
+    let mut snapshots: Vec<(String, RayonPoolSnapshot)> = entries
+        .iter()
+        .map(|(name, metrics)| (name.clone(), RayonPoolSnapshot::from_metrics(metrics)))
+        .collect();
+    snapshots.sort_by(|a, b| a.0.cmp(&b.0));
+    snapshots
+}
+
+thread_local! {
+    /// Metrics of the pool the current thread works for, once known.
+    static CURRENT_POOL: Cell<Option<&'static RayonMetrics>> = const { Cell::new(None) };
+}
+
+/// Attribute tasks tracked on the current thread to the pool `name`.
+///
+/// Named pools call this from each worker's start handler.
+pub fn tag_current_thread(name: &str) {
+    let metrics = labelled_metrics(pool_registry(), name);
+    CURRENT_POOL.with(|pool| pool.set(Some(metrics)));
+}
+
+fn current_pool() -> &'static RayonMetrics {
+    CURRENT_POOL.with(|pool| {
+        pool.get().unwrap_or_else(|| {
+            let metrics = labelled_metrics(pool_registry(), UNTAGGED_POOL);
+            pool.set(Some(metrics));
+            metrics
+        })
+    })
+}
+
 /// Guard returned when a Rayon task begins executing.
 ///
-/// Dropping the guard records task completion and time spent executing.
+/// Dropping the guard records task completion and time spent executing,
+/// globally, for the pool the task ran on and for its operation, if labelled.
 pub struct RayonTaskGuard {
     metrics: &'static RayonMetrics,
+    pool: &'static RayonMetrics,
+    operation: Option<&'static RayonMetrics>,
     start: Instant,
 }
 
 impl RayonTaskGuard {
-    fn new(metrics: &'static RayonMetrics) -> Self {
+    fn new(
+        metrics: &'static RayonMetrics,
+        pool: &'static RayonMetrics,
+        operation: Option<&'static RayonMetrics>,
+    ) -> Self {
         let total_threads = rayon::current_num_threads();
-        if total_threads > 0 {
-            update_max_usize(&metrics.max_threads_observed, total_threads);
+        metrics.record_start(total_threads);
+        pool.record_start(total_threads);
+        if let Some(operation) = operation {
+            operation.record_start(total_threads);
         }
-        let active = metrics.active_workers.fetch_add(1, Ordering::AcqRel) + 1;
-        update_max_usize(&metrics.max_active_workers, active);
-        metrics.tasks_started.fetch_add(1, Ordering::Relaxed);
         Self {
             metrics,
+            pool,
+            operation,
             start: Instant::now(),
         }
     }
@@ -36,15 +119,12 @@ impl RayonTaskGuard {
 
 impl Drop for RayonTaskGuard {
     fn drop(&mut self) {
-        let elapsed = self.start.elapsed();
-        let nanos = duration_to_nanos(elapsed);
-        self.metrics
-            .busy_time_nanos
-            .fetch_add(nanos, Ordering::Relaxed);
-        update_max_u64(&self.metrics.max_task_time_nanos, nanos);
-        update_min_u64(&self.metrics.min_task_time_nanos, nanos);
-        self.metrics.tasks_completed.fetch_add(1, Ordering::Relaxed);
-        self.metrics.active_workers.fetch_sub(1, Ordering::AcqRel);
+        let nanos = duration_to_nanos(self.start.elapsed());
+        self.metrics.record_finish(nanos);
+        self.pool.record_finish(nanos);
+        if let Some(operation) = self.operation {
+            operation.record_finish(nanos);
+        }
     }
 }
 
@@ -71,12 +151,29 @@ pub struct RayonPoolSnapshot {
     pub max_task_duration_us: f64,
     /// Shortest task duration in microseconds.
     pub min_task_duration_us: f64,
+    /// Median task duration in microseconds, estimated from the histogram.
+    pub p50_task_duration_us: f64,
+    /// 99th percentile task duration in microseconds, estimated from the
+    /// histogram.
+    pub p99_task_duration_us: f64,
+    /// Completed tasks per duration bucket, in [`DURATION_BUCKETS_US`] order
+    /// followed by the overflow bucket.
+    pub task_duration_histogram: Vec<DurationBucket>,
     /// Aggregate busy time across all workers in seconds.
     pub busy_time_seconds: f64,
     /// Total observation window in seconds.
     pub observation_seconds: f64,
 }
 
+/// Tasks whose duration fell in one histogram bucket.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DurationBucket {
+    /// Inclusive upper bound in microseconds; `None` for the overflow bucket.
+    pub upper_bound_us: Option<u64>,
+    /// Number of tasks in the bucket.
+    pub count: u64,
+}
+
 impl RayonPoolSnapshot {
     fn from_metrics(metrics: &RayonMetrics) -> Self {
         let observed_threads = metrics.max_threads_observed.load(Ordering::Relaxed).max(1);
@@ -116,6 +213,19 @@ impl RayonPoolSnapshot {
         } else {
             0.0
         };
+        let task_duration_histogram: Vec<DurationBucket> = metrics
+            .duration_buckets
+            .iter()
+            .enumerate()
+            .map(|(idx, count)| DurationBucket {
+                upper_bound_us: DURATION_BUCKETS_US.get(idx).copied(),
+                count: count.load(Ordering::Relaxed),
+            })
+            .collect();
+        let p50_task_duration_us =
+            histogram_quantile_us(&task_duration_histogram, 0.5, max_task_duration_us);
+        let p99_task_duration_us =
+            histogram_quantile_us(&task_duration_histogram, 0.99, max_task_duration_us);
 
         Self {
             observed_threads,
@@ -128,12 +238,35 @@ impl RayonPoolSnapshot {
             mean_task_duration_us,
             max_task_duration_us,
             min_task_duration_us,
+            p50_task_duration_us,
+            p99_task_duration_us,
+            task_duration_histogram,
             busy_time_seconds: busy_secs,
             observation_seconds: elapsed_secs,
         }
     }
 }
 
+/// Estimate a quantile as the upper bound of the bucket holding its rank,
+/// capped at the longest task seen.
+fn histogram_quantile_us(buckets: &[DurationBucket], quantile: f64, max_us: f64) -> f64 {
+    let total: u64 = buckets.iter().map(|bucket| bucket.count).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let rank = ((quantile * total as f64).ceil() as u64).max(1);
+    let mut seen = 0;
+    for bucket in buckets {
+        seen += bucket.count;
+        if seen >= rank {
+            return bucket
+                .upper_bound_us
+                .map_or(max_us, |bound| (bound as f64).min(max_us));
+        }
+    }
+    max_us
+}
+
 /// Error returned when attempting to snapshot or reset metrics at an invalid time.
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum RayonMetricsError {
@@ -151,6 +284,7 @@ struct RayonMetrics {
     busy_time_nanos: AtomicU64,
     max_task_time_nanos: AtomicU64,
     min_task_time_nanos: AtomicU64,
+    duration_buckets: [AtomicU64; DURATION_BUCKETS_US.len() + 1],
     observation_start: Mutex<Instant>,
 }
 
@@ -165,12 +299,35 @@ impl Default for RayonMetrics {
             busy_time_nanos: AtomicU64::new(0),
             max_task_time_nanos: AtomicU64::new(0),
             min_task_time_nanos: AtomicU64::new(u64::MAX),
+            duration_buckets: std::array::from_fn(|_| AtomicU64::new(0)),
             observation_start: Mutex::new(Instant::now()),
         }
     }
 }
 
 impl RayonMetrics {
+    fn record_start(&self, total_threads: usize) {
+        if total_threads > 0 {
+            update_max_usize(&self.max_threads_observed, total_threads);
+        }
+        let active = self.active_workers.fetch_add(1, Ordering::AcqRel) + 1;
+        update_max_usize(&self.max_active_workers, active);
+        self.tasks_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_finish(&self, nanos: u64) {
+        self.busy_time_nanos.fetch_add(nanos, Ordering::Relaxed);
+        update_max_u64(&self.max_task_time_nanos, nanos);
+        update_min_u64(&self.min_task_time_nanos, nanos);
+        let bucket = DURATION_BUCKETS_US
+            .iter()
+            .position(|&bound_us| nanos <= bound_us * 1_000)
+            .unwrap_or(DURATION_BUCKETS_US.len());
+        self.duration_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.tasks_completed.fetch_add(1, Ordering::Relaxed);
+        self.active_workers.fetch_sub(1, Ordering::AcqRel);
+    }
+
     fn observation_elapsed(&self) -> Duration {
         let start = self
             .observation_start
@@ -187,6 +344,9 @@ impl RayonMetrics {
         self.busy_time_nanos.store(0, Ordering::Relaxed);
         self.max_task_time_nanos.store(0, Ordering::Relaxed);
         self.min_task_time_nanos.store(u64::MAX, Ordering::Relaxed);
+        for bucket in &self.duration_buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
         let mut start = self
             .observation_start
             .lock()
@@ -197,8 +357,17 @@ impl RayonMetrics {
 
 /// Register the start of a Rayon worker task and return a guard that records
 /// completion statistics.
+///
+/// The task is attributed to the pool the current thread was tagged with by
+/// [`tag_current_thread`], or to [`UNTAGGED_POOL`].
 pub fn track_task() -> RayonTaskGuard {
-    RayonTaskGuard::new(metrics())
+    RayonTaskGuard::new(metrics(), current_pool(), None)
+}
+
+/// Like [`track_task`], additionally recording the task under `operation`.
+pub fn track_operation(operation: &str) -> RayonTaskGuard {
+    let operation = labelled_metrics(operation_registry(), operation);
+    RayonTaskGuard::new(metrics(), current_pool(), Some(operation))
 }
 
 /// Retrieve the current utilisation snapshot without mutating counters.
@@ -206,6 +375,26 @@ pub fn snapshot() -> RayonPoolSnapshot {
     RayonPoolSnapshot::from_metrics(metrics())
 }
 
+/// Retrieve a snapshot for every pool that has tracked tasks, sorted by name.
+pub fn pool_snapshots() -> Vec<(String, RayonPoolSnapshot)> {
+    labelled_snapshots(pool_registry())
+}
+
+/// Retrieve a snapshot for every operation label, sorted by name.
+pub fn operation_snapshots() -> Vec<(String, RayonPoolSnapshot)> {
+    labelled_snapshots(operation_registry())
+}
+
+/// Reset the global counters and those of every pool and operation.
+fn reset_all() {
+    metrics().reset_unchecked();
+    for registry in [pool_registry(), operation_registry()] {
+        for entry in registry.read().expect("metrics registry poisoned").values() {
+            entry.reset_unchecked();
+        }
+    }
+}
+
 /// Retrieve utilisation metrics and reset counters. Fails if Rayon tasks are active.
 pub fn snapshot_and_reset() -> Result<RayonPoolSnapshot, RayonMetricsError> {
     let metrics = metrics();
@@ -214,7 +403,7 @@ pub fn snapshot_and_reset() -> Result<RayonPoolSnapshot, RayonMetricsError> {
         return Err(RayonMetricsError::ActiveWorkers(active));
     }
     let snapshot = RayonPoolSnapshot::from_metrics(metrics);
-    metrics.reset_unchecked();
+    reset_all();
     Ok(snapshot)
 }
 
@@ -225,7 +414,7 @@ pub fn reset_metrics() -> Result<(), RayonMetricsError> {
     if active != 0 {
         return Err(RayonMetricsError::ActiveWorkers(active));
     }
-    metrics.reset_unchecked();
+    reset_all();
     Ok(())
 }
 
@@ -305,6 +494,78 @@ mod tests {
         assert!(snapshot.busy_time_seconds > 0.0);
     }
 
+    #[test]
+    fn tasks_are_attributed_to_tagged_pool() {
+        thread::spawn(|| {
+            tag_current_thread("test_tagged");
+            let _guard = track_task();
+        })
+        .join()
+        .unwrap();
+        let pools = pool_snapshots();
+        let (_, tagged) = pools
+            .iter()
+            .find(|(name, _)| name == "test_tagged")
+            .expect("tagged pool should be reported");
+        assert!(tagged.total_tasks_completed >= 1);
+        assert!(snapshot().total_tasks_completed >= tagged.total_tasks_completed);
+    }
+
+    #[test]
+    fn snapshots_include_duration_histogram() {
+        let metrics = RayonMetrics::default();
+        for nanos in [5_000, 2_000_000, 9_000_000_000] {
+            metrics.record_start(1);
+            metrics.record_finish(nanos);
+        }
+        let snapshot = RayonPoolSnapshot::from_metrics(&metrics);
+        let histogram = &snapshot.task_duration_histogram;
+        assert_eq!(histogram.len(), DURATION_BUCKETS_US.len() + 1);
+        assert_eq!(histogram[0].count, 1);
+        assert_eq!(histogram[5].upper_bound_us, Some(5_000));
+        assert_eq!(histogram[5].count, 1);
+        assert_eq!(histogram.last().unwrap().upper_bound_us, None);
+        assert_eq!(histogram.last().unwrap().count, 1);
+        assert_eq!(snapshot.p50_task_duration_us, 5_000.0);
+        assert_eq!(snapshot.p99_task_duration_us, 9_000_000.0);
+
+        metrics.reset_unchecked();
+        let cleared = RayonPoolSnapshot::from_metrics(&metrics);
+        assert!(cleared.task_duration_histogram.iter().all(|b| b.count == 0));
+    }
+
+    #[test]
+    fn operations_are_reported_by_label() {
+        drop(track_operation("test_operation"));
+        assert!(
+            operation_snapshots()
+                .iter()
+                .any(|(name, _)| name == "test_operation")
+        );
+    }
+
+    #[test]
+    fn quantiles_use_bucket_bounds() {
+        let buckets = [
+            DurationBucket {
+                upper_bound_us: Some(10),
+                count: 98,
+            },
+            DurationBucket {
+                upper_bound_us: Some(50),
+                count: 1,
+            },
+            DurationBucket {
+                upper_bound_us: None,
+                count: 1,
+            },
+        ];
+        assert_eq!(histogram_quantile_us(&buckets, 0.5, 900.0), 10.0);
+        assert_eq!(histogram_quantile_us(&buckets, 0.99, 900.0), 50.0);
+        assert_eq!(histogram_quantile_us(&buckets, 1.0, 900.0), 900.0);
+        assert_eq!(histogram_quantile_us(&[], 0.5, 0.0), 0.0);
+    }
+
     #[test]
     fn cannot_reset_when_active() {
         reset_metrics().unwrap();
@@ -1,6 +1,15 @@
 use once_cell::sync::Lazy;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::RwLock;
+use parking_lot::{Condvar, Mutex};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::error::ForziumError;
+use crate::memory::accountant::{self, MemoryCategory, MemoryReservation};
 
 /// Global resource limits configuration for compute operations.
 pub struct ResourceLimits {
@@ -14,6 +23,49 @@ pub struct ResourceLimits {
     pub enabled: AtomicUsize,
     /// Custom limits for specific operation types.
     pub operation_limits: RwLock<std::collections::HashMap<String, usize>>,
+    /// Memory budget in bytes shared by all concurrent operations.
+    pub max_memory_bytes: AtomicU64,
+    /// Bytes currently reserved by active operations.
+    pub reserved_bytes: AtomicU64,
+    /// Wall-clock timeouts for specific operation types.
+    pub operation_timeouts: RwLock<std::collections::HashMap<String, Duration>>,
+    /// Maximum operations waiting for a slot; 0 rejects immediately.
+    pub max_queue_depth: AtomicUsize,
+    /// How long a queued operation waits for a slot, in milliseconds.
+    pub queue_timeout_ms: AtomicU64,
+}
+
+/// Default time a queued operation waits for a free slot.
+const DEFAULT_QUEUE_TIMEOUT_MS: u64 = 1_000;
+
+/// Default memory budget shared across concurrent operations (8 GiB).
+const DEFAULT_MAX_MEMORY_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+
+/// Element types used when estimating tensor memory footprints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DType {
+    /// 32-bit floating point.
+    F32,
+    /// 64-bit floating point.
+    F64,
+}
+
+impl DType {
+    /// Size of a single element in bytes.
+    pub fn size_bytes(self) -> u64 {
+        match self {
+            DType::F32 => 4,
+            DType::F64 => 8,
+        }
+    }
+}
+
+/// Estimate the bytes needed to hold `elements` values of `dtype`.
+///
+/// Callers pass the combined element count of every buffer an operation
+/// touches (inputs, intermediates, and outputs).
+pub fn estimate_bytes(elements: usize, dtype: DType) -> u64 {
+    (elements as u64).saturating_mul(dtype.size_bytes())
 }
 
 /// The global resource limits instance.
@@ -29,9 +81,160 @@ pub static RESOURCE_LIMITS: Lazy<ResourceLimits> = Lazy::new(|| {
         active_ops: AtomicUsize::new(0),             // Start with 0 active ops
         enabled: AtomicUsize::new(1),                // Enabled by default
         operation_limits: RwLock::new(operation_limits),
+        max_memory_bytes: AtomicU64::new(DEFAULT_MAX_MEMORY_BYTES),
+        reserved_bytes: AtomicU64::new(0),
+        operation_timeouts: RwLock::new(std::collections::HashMap::new()),
+        max_queue_depth: AtomicUsize::new(0), // Queueing disabled by default
+        queue_timeout_ms: AtomicU64::new(DEFAULT_QUEUE_TIMEOUT_MS),
     }
 });
 
+/// Bounded FIFO of operations waiting for a concurrency slot.
+pub struct OpQueue {
+    waiters: Mutex<VecDeque<u64>>,
+    available: Condvar,
+    next_ticket: AtomicU64,
+    depth: AtomicUsize,
+    peak_depth: AtomicUsize,
+    total_queued: AtomicU64,
+    timeouts: AtomicU64,
+    rejected: AtomicU64,
+    total_wait_ns: AtomicU64,
+    max_wait_ns: AtomicU64,
+}
+
+/// Snapshot of [`OpQueue`] metrics.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueueStats {
+    /// Operations currently waiting.
+    pub depth: usize,
+    /// Highest depth observed.
+    pub peak_depth: usize,
+    /// Operations that entered the queue.
+    pub total_queued: u64,
+    /// Operations that gave up after the queue timeout.
+    pub timeouts: u64,
+    /// Operations rejected because the queue was full.
+    pub rejected: u64,
+    /// Cumulative time spent waiting.
+    pub total_wait: Duration,
+    /// Longest single wait.
+    pub max_wait: Duration,
+}
+
+impl Default for OpQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpQueue {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self {
+            waiters: Mutex::new(VecDeque::new()),
+            available: Condvar::new(),
+            next_ticket: AtomicU64::new(0),
+            depth: AtomicUsize::new(0),
+            peak_depth: AtomicUsize::new(0),
+            total_queued: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            total_wait_ns: AtomicU64::new(0),
+            max_wait_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of operations currently waiting.
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::SeqCst)
+    }
+
+    /// Wait in FIFO order until `try_acquire` succeeds at the head of the
+    /// queue, failing when `max_depth` waiters are already queued or
+    /// `timeout` elapses.
+    pub fn wait_for<T>(
+        &self,
+        max_depth: usize,
+        timeout: Duration,
+        mut try_acquire: impl FnMut() -> Option<T>,
+    ) -> Result<T, ForziumError> {
+        let start = Instant::now();
+        let deadline = start + timeout;
+        let mut waiters = self.waiters.lock();
+        if waiters.len() >= max_depth {
+            self.rejected.fetch_add(1, Ordering::SeqCst);
+            return Err(ForziumError::ResourceLimit(format!(
+                "Operation queue full ({} waiting)",
+                waiters.len()
+            )));
+        }
+
+        let ticket = self.next_ticket.fetch_add(1, Ordering::SeqCst);
+        waiters.push_back(ticket);
+        self.depth.store(waiters.len(), Ordering::SeqCst);
+        self.peak_depth.fetch_max(waiters.len(), Ordering::SeqCst);
+        self.total_queued.fetch_add(1, Ordering::SeqCst);
+
+        let result = loop {
+            if waiters.front() == Some(&ticket)
+                && let Some(acquired) = try_acquire()
+            {
+                break Ok(acquired);
+            }
+            if self
+                .available
+                .wait_until(&mut waiters, deadline)
+                .timed_out()
+            {
+                if waiters.front() == Some(&ticket)
+                    && let Some(acquired) = try_acquire()
+                {
+                    break Ok(acquired);
+                }
+                self.timeouts.fetch_add(1, Ordering::SeqCst);
+                break Err(ForziumError::ResourceLimit(format!(
+                    "Timed out after {:.3}s waiting for an operation slot",
+                    start.elapsed().as_secs_f64()
+                )));
+            }
+        };
+
+        waiters.retain(|t| *t != ticket);
+        self.depth.store(waiters.len(), Ordering::SeqCst);
+        let waited = start.elapsed().as_nanos() as u64;
+        self.total_wait_ns.fetch_add(waited, Ordering::SeqCst);
+        self.max_wait_ns.fetch_max(waited, Ordering::SeqCst);
+        // The next waiter may now be at the head of the queue
+        self.available.notify_all();
+        result
+    }
+
+    /// Wake waiting operations after a slot has been released.
+    pub fn notify(&self) {
+        if self.depth() > 0 {
+            let _waiters = self.waiters.lock();
+            self.available.notify_all();
+        }
+    }
+
+    /// Snapshot the queue metrics.
+    pub fn stats(&self) -> QueueStats {
+        QueueStats {
+            depth: self.depth(),
+            peak_depth: self.peak_depth.load(Ordering::SeqCst),
+            total_queued: self.total_queued.load(Ordering::SeqCst),
+            timeouts: self.timeouts.load(Ordering::SeqCst),
+            rejected: self.rejected.load(Ordering::SeqCst),
+            total_wait: Duration::from_nanos(self.total_wait_ns.load(Ordering::SeqCst)),
+            max_wait: Duration::from_nanos(self.max_wait_ns.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+/// The global queue of operations waiting for a concurrency slot.
+pub static OP_QUEUE: Lazy<OpQueue> = Lazy::new(OpQueue::new);
+
 /// Reset resource limits to default values.
 pub fn reset_defaults() {
     RESOURCE_LIMITS
@@ -41,11 +244,21 @@ pub fn reset_defaults() {
         .max_concurrent_ops
         .store(16, Ordering::SeqCst);
     RESOURCE_LIMITS.enabled.store(1, Ordering::SeqCst);
+    RESOURCE_LIMITS
+        .max_memory_bytes
+        .store(DEFAULT_MAX_MEMORY_BYTES, Ordering::SeqCst);
+    RESOURCE_LIMITS.max_queue_depth.store(0, Ordering::SeqCst);
+    RESOURCE_LIMITS
+        .queue_timeout_ms
+        .store(DEFAULT_QUEUE_TIMEOUT_MS, Ordering::SeqCst);
 
     let mut op_limits = RESOURCE_LIMITS.operation_limits.write().unwrap();
     op_limits.clear();
     op_limits.insert("matmul".to_string(), 100_000_000);
     op_limits.insert("conv2d".to_string(), 50_000_000);
+    drop(op_limits);
+
+    RESOURCE_LIMITS.operation_timeouts.write().unwrap().clear();
 }
 
 /// Check if an operation with the given dimensions is allowed.
@@ -78,15 +291,30 @@ pub fn check_tensor_size(rows: usize, cols: usize, operation: &str) -> Result<()
     Ok(())
 }
 
-/// Resource guard that tracks active operations.
-pub struct OpGuard;
+/// Check tensor dimensions against the configured limits.
+///
+/// Same as [`check_tensor_size`] but reports failures as
+/// [`ForziumError::ResourceLimit`] for use in compute entry points.
+pub fn enforce_tensor_size(rows: usize, cols: usize, operation: &str) -> Result<(), ForziumError> {
+    check_tensor_size(rows, cols, operation).map_err(ForziumError::ResourceLimit)
+}
+
+/// Resource guard that tracks active operations and their reserved memory.
+pub struct OpGuard {
+    reserved_bytes: u64,
+    _memory: Option<MemoryReservation>,
+}
 
 impl OpGuard {
     /// Try to acquire a resource guard for a new compute operation.
     /// Returns None if the maximum concurrent operations limit is reached.
     pub fn try_new() -> Option<Self> {
         if RESOURCE_LIMITS.enabled.load(Ordering::SeqCst) == 0 {
-            return Some(OpGuard); // Limits disabled
+            // Limits disabled
+            return Some(OpGuard {
+                reserved_bytes: 0,
+                _memory: None,
+            });
         }
 
         let active = RESOURCE_LIMITS.active_ops.fetch_add(1, Ordering::SeqCst);
@@ -97,19 +325,360 @@ impl OpGuard {
             RESOURCE_LIMITS.active_ops.fetch_sub(1, Ordering::SeqCst);
             None
         } else {
-            Some(OpGuard)
+            Some(OpGuard {
+                reserved_bytes: 0,
+                _memory: None,
+            })
+        }
+    }
+
+    /// Acquire a resource guard, failing with [`ForziumError::ResourceLimit`]
+    /// when the maximum number of concurrent operations is reached.
+    ///
+    /// When queueing is configured, the operation instead waits in FIFO
+    /// order for a free slot until the queue timeout elapses.
+    pub fn acquire() -> Result<Self, ForziumError> {
+        let max_depth = RESOURCE_LIMITS.max_queue_depth.load(Ordering::SeqCst);
+        if max_depth > 0 {
+            // Don't overtake operations that are already waiting
+            if OP_QUEUE.depth() == 0
+                && let Some(guard) = Self::try_new()
+            {
+                return Ok(guard);
+            }
+            let timeout =
+                Duration::from_millis(RESOURCE_LIMITS.queue_timeout_ms.load(Ordering::SeqCst));
+            return OP_QUEUE.wait_for(max_depth, timeout, Self::try_new);
+        }
+
+        Self::try_new().ok_or_else(|| {
+            ForziumError::ResourceLimit(format!(
+                "Maximum concurrent operations ({}) reached",
+                RESOURCE_LIMITS.max_concurrent_ops.load(Ordering::SeqCst)
+            ))
+        })
+    }
+
+    /// Acquire a resource guard and reserve `bytes` from the global memory
+    /// budget for the lifetime of the guard.
+    ///
+    /// The bytes are also registered with the memory accountant, charging
+    /// the budget of the request running on this thread.
+    pub fn acquire_bytes(bytes: u64) -> Result<Self, ForziumError> {
+        let mut guard = Self::acquire()?;
+        if bytes == 0 {
+            return Ok(guard);
+        }
+        let bytes_usize = usize::try_from(bytes).unwrap_or(usize::MAX);
+        guard._memory = Some(accountant::reserve(MemoryCategory::Compute, bytes_usize)?);
+        if RESOURCE_LIMITS.enabled.load(Ordering::SeqCst) == 0 {
+            return Ok(guard);
+        }
+
+        let budget = RESOURCE_LIMITS.max_memory_bytes.load(Ordering::SeqCst);
+        let mut current = RESOURCE_LIMITS.reserved_bytes.load(Ordering::SeqCst);
+        loop {
+            let next = current.saturating_add(bytes);
+            if next > budget {
+                return Err(ForziumError::ResourceLimit(format!(
+                    "Memory budget exceeded: {} bytes requested, {} of {} bytes reserved",
+                    bytes, current, budget
+                )));
+            }
+            match RESOURCE_LIMITS.reserved_bytes.compare_exchange(
+                current,
+                next,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        guard.reserved_bytes = bytes;
+        Ok(guard)
+    }
+}
+
+/// Shared flag used to cancel in-flight operations.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of every operation running under this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+thread_local! {
+    static CURRENT_CANCEL: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `token` as the cancellation token for operations it starts.
+///
+/// Every [`OpTimer`] started on this thread while `f` runs observes the
+/// token, so chunked kernels stop early once it is cancelled.
+pub fn with_cancel_token<R>(token: &CancelToken, f: impl FnOnce() -> R) -> R {
+    let previous = CURRENT_CANCEL.with(|current| current.replace(Some(token.clone())));
+    let result = f();
+    CURRENT_CANCEL.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Wall-clock timer enforcing the configured timeout for an operation.
+///
+/// Also observes the cancellation token installed by [`with_cancel_token`]
+/// when the timer was started. Kernels poll [`expired`](Self::expired) in
+/// their row or block loops, skipping the remaining work, and report with
+/// [`check`](Self::check), so an operation stops within about a row or
+/// block of its deadline rather than running to completion.
+pub struct OpTimer<'a> {
+    operation: &'a str,
+    start: Instant,
+    timeout: Option<Duration>,
+    cancel: Option<CancelToken>,
+}
+
+impl<'a> OpTimer<'a> {
+    /// Start timing `operation`, picking up its configured timeout if any.
+    pub fn start(operation: &'a str) -> Self {
+        let timeout = if RESOURCE_LIMITS.enabled.load(Ordering::SeqCst) == 0 {
+            None
+        } else {
+            RESOURCE_LIMITS
+                .operation_timeouts
+                .read()
+                .unwrap()
+                .get(operation)
+                .copied()
+        };
+        Self {
+            operation,
+            start: Instant::now(),
+            timeout,
+            cancel: CURRENT_CANCEL.with(|current| current.borrow().clone()),
+        }
+    }
+
+    /// Whether the operation was cancelled or has run past its timeout.
+    pub fn expired(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+            || self
+                .timeout
+                .is_some_and(|timeout| self.start.elapsed() > timeout)
+    }
+
+    /// Fail with [`ForziumError::Cancelled`] if the operation was cancelled
+    /// or the timeout was exceeded.
+    pub fn check(&self) -> Result<(), ForziumError> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(ForziumError::Cancelled(format!(
+                "Operation '{}' was cancelled",
+                self.operation
+            )));
+        }
+        match self.timeout {
+            Some(timeout) if self.start.elapsed() > timeout => {
+                Err(ForziumError::Cancelled(format!(
+                    "Operation '{}' timed out after {:.3}s (limit: {:.3}s)",
+                    self.operation,
+                    self.start.elapsed().as_secs_f64(),
+                    timeout.as_secs_f64()
+                )))
+            }
+            _ => Ok(()),
         }
     }
 }
 
 impl Drop for OpGuard {
     fn drop(&mut self) {
+        if self.reserved_bytes > 0 {
+            RESOURCE_LIMITS
+                .reserved_bytes
+                .fetch_sub(self.reserved_bytes, Ordering::SeqCst);
+        }
         if RESOURCE_LIMITS.enabled.load(Ordering::SeqCst) != 0 {
             RESOURCE_LIMITS.active_ops.fetch_sub(1, Ordering::SeqCst);
+            OP_QUEUE.notify();
         }
     }
 }
 
+/// Update global resource limits. Arguments left as `None` are unchanged.
+#[pyfunction]
+#[pyo3(signature = (max_elements=None, max_concurrent_ops=None, enabled=None, max_memory_bytes=None))]
+pub fn set_resource_limits(
+    max_elements: Option<usize>,
+    max_concurrent_ops: Option<usize>,
+    enabled: Option<bool>,
+    max_memory_bytes: Option<u64>,
+) -> PyResult<()> {
+    if max_elements == Some(0) || max_concurrent_ops == Some(0) || max_memory_bytes == Some(0) {
+        return Err(ForziumError::Validation("limits must be positive".into()).into());
+    }
+    if let Some(max) = max_elements {
+        RESOURCE_LIMITS.max_elements.store(max, Ordering::SeqCst);
+    }
+    if let Some(max) = max_concurrent_ops {
+        RESOURCE_LIMITS
+            .max_concurrent_ops
+            .store(max, Ordering::SeqCst);
+    }
+    if let Some(enabled) = enabled {
+        RESOURCE_LIMITS
+            .enabled
+            .store(usize::from(enabled), Ordering::SeqCst);
+    }
+    if let Some(max) = max_memory_bytes {
+        RESOURCE_LIMITS
+            .max_memory_bytes
+            .store(max, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Set the element limit for a specific operation, or remove it with `None`.
+#[pyfunction]
+#[pyo3(signature = (operation, max_elements=None))]
+pub fn set_operation_limit(operation: &str, max_elements: Option<usize>) -> PyResult<()> {
+    let mut op_limits = RESOURCE_LIMITS.operation_limits.write().unwrap();
+    match max_elements {
+        Some(0) => return Err(ForziumError::Validation("limits must be positive".into()).into()),
+        Some(max) => {
+            op_limits.insert(operation.to_string(), max);
+        }
+        None => {
+            op_limits.remove(operation);
+        }
+    }
+    Ok(())
+}
+
+/// Set the wall-clock timeout in seconds for a specific operation, or remove
+/// it with `None`.
+#[pyfunction]
+#[pyo3(signature = (operation, timeout=None))]
+pub fn set_operation_timeout(operation: &str, timeout: Option<f64>) -> PyResult<()> {
+    let mut timeouts = RESOURCE_LIMITS.operation_timeouts.write().unwrap();
+    match timeout {
+        Some(secs) => {
+            let duration = Duration::try_from_secs_f64(secs)
+                .ok()
+                .filter(|d| !d.is_zero())
+                .ok_or_else(|| ForziumError::Validation("timeout must be positive".into()))?;
+            timeouts.insert(operation.to_string(), duration);
+        }
+        None => {
+            timeouts.remove(operation);
+        }
+    }
+    Ok(())
+}
+
+/// Configure queueing of operations that hit the concurrency limit.
+///
+/// `max_depth` of 0 disables queueing; `timeout` is in seconds.
+#[pyfunction]
+#[pyo3(signature = (max_depth=None, timeout=None))]
+pub fn set_queue_config(max_depth: Option<usize>, timeout: Option<f64>) -> PyResult<()> {
+    if let Some(secs) = timeout {
+        let duration = Duration::try_from_secs_f64(secs)
+            .ok()
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| ForziumError::Validation("timeout must be positive".into()))?;
+        RESOURCE_LIMITS
+            .queue_timeout_ms
+            .store(duration.as_millis().max(1) as u64, Ordering::SeqCst);
+    }
+    if let Some(depth) = max_depth {
+        RESOURCE_LIMITS
+            .max_queue_depth
+            .store(depth, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Return queue depth and wait-time metrics as a dict.
+#[pyfunction]
+pub fn get_queue_stats(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let stats = OP_QUEUE.stats();
+    let dict = PyDict::new(py);
+    dict.set_item("depth", stats.depth)?;
+    dict.set_item("peak_depth", stats.peak_depth)?;
+    dict.set_item("total_queued", stats.total_queued)?;
+    dict.set_item("timeouts", stats.timeouts)?;
+    dict.set_item("rejected", stats.rejected)?;
+    dict.set_item("total_wait_secs", stats.total_wait.as_secs_f64())?;
+    dict.set_item("max_wait_secs", stats.max_wait.as_secs_f64())?;
+    Ok(dict.unbind())
+}
+
+/// Return the current resource limits configuration as a dict.
+#[pyfunction]
+pub fn get_resource_limits(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item(
+        "max_elements",
+        RESOURCE_LIMITS.max_elements.load(Ordering::SeqCst),
+    )?;
+    dict.set_item(
+        "max_concurrent_ops",
+        RESOURCE_LIMITS.max_concurrent_ops.load(Ordering::SeqCst),
+    )?;
+    dict.set_item(
+        "active_ops",
+        RESOURCE_LIMITS.active_ops.load(Ordering::SeqCst),
+    )?;
+    dict.set_item(
+        "enabled",
+        RESOURCE_LIMITS.enabled.load(Ordering::SeqCst) != 0,
+    )?;
+    dict.set_item(
+        "max_memory_bytes",
+        RESOURCE_LIMITS.max_memory_bytes.load(Ordering::SeqCst),
+    )?;
+    dict.set_item(
+        "reserved_bytes",
+        RESOURCE_LIMITS.reserved_bytes.load(Ordering::SeqCst),
+    )?;
+    let op_limits = RESOURCE_LIMITS.operation_limits.read().unwrap().clone();
+    dict.set_item("operation_limits", op_limits)?;
+    let timeouts: std::collections::HashMap<String, f64> = RESOURCE_LIMITS
+        .operation_timeouts
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(op, timeout)| (op.clone(), timeout.as_secs_f64()))
+        .collect();
+    dict.set_item("operation_timeouts", timeouts)?;
+    dict.set_item(
+        "max_queue_depth",
+        RESOURCE_LIMITS.max_queue_depth.load(Ordering::SeqCst),
+    )?;
+    dict.set_item(
+        "queue_timeout",
+        RESOURCE_LIMITS.queue_timeout_ms.load(Ordering::SeqCst) as f64 / 1000.0,
+    )?;
+    Ok(dict.unbind())
+}
+
+/// Restore the default resource limits.
+#[pyfunction]
+pub fn reset_resource_limits() {
+    reset_defaults();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +689,118 @@ mod tests {
         assert!(check_tensor_size(100000, 100000, "matmul").is_err());
     }
 
+    #[test]
+    fn operation_limit_round_trip() {
+        set_operation_limit("test_round_trip_op", Some(10)).unwrap();
+        let err = enforce_tensor_size(4, 4, "test_round_trip_op").unwrap_err();
+        assert!(matches!(err, ForziumError::ResourceLimit(_)));
+        assert!(enforce_tensor_size(2, 5, "test_round_trip_op").is_ok());
+        set_operation_limit("test_round_trip_op", None).unwrap();
+        assert!(enforce_tensor_size(4, 4, "test_round_trip_op").is_ok());
+        assert!(set_operation_limit("test_round_trip_op", Some(0)).is_err());
+    }
+
+    #[test]
+    fn estimate_bytes_by_dtype() {
+        assert_eq!(estimate_bytes(10, DType::F64), 80);
+        assert_eq!(estimate_bytes(10, DType::F32), 40);
+        assert_eq!(estimate_bytes(usize::MAX, DType::F64), u64::MAX);
+    }
+
+    #[test]
+    fn memory_reservation_is_released_on_drop() {
+        let before = RESOURCE_LIMITS.reserved_bytes.load(Ordering::SeqCst);
+        let guard = OpGuard::acquire_bytes(1024).unwrap();
+        assert!(RESOURCE_LIMITS.reserved_bytes.load(Ordering::SeqCst) >= before + 1024);
+        drop(guard);
+        let err = OpGuard::acquire_bytes(u64::MAX).err().unwrap();
+        assert!(matches!(err, ForziumError::ResourceLimit(_)));
+    }
+
+    #[test]
+    fn memory_reservation_charges_request_budget() {
+        let budget = accountant::RequestBudget::new(4096);
+        accountant::with_request_budget(&budget, || {
+            let guard = OpGuard::acquire_bytes(1024).unwrap();
+            assert_eq!(budget.used(), 1024);
+            let err = OpGuard::acquire_bytes(4096).err().unwrap();
+            assert!(matches!(err, ForziumError::ResourceLimit(_)));
+            drop(guard);
+        });
+        assert_eq!(budget.used(), 0);
+        assert_eq!(budget.rejections(), 1);
+    }
+
+    #[test]
+    fn operation_timeout_cancels() {
+        RESOURCE_LIMITS
+            .operation_timeouts
+            .write()
+            .unwrap()
+            .insert("timeout_probe".to_string(), Duration::from_millis(1));
+        let timer = OpTimer::start("timeout_probe");
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(timer.expired());
+        let err = timer.check().unwrap_err();
+        assert!(matches!(err, ForziumError::Cancelled(_)));
+        assert!(OpTimer::start("no_timeout_probe").check().is_ok());
+        RESOURCE_LIMITS
+            .operation_timeouts
+            .write()
+            .unwrap()
+            .remove("timeout_probe");
+    }
+
+    #[test]
+    fn cancel_token_stops_timer() {
+        let token = CancelToken::new();
+        let timer = with_cancel_token(&token, || OpTimer::start("cancel_probe"));
+        assert!(!timer.expired());
+        token.cancel();
+        assert!(timer.expired());
+        let err = timer.check().unwrap_err();
+        assert!(matches!(err, ForziumError::Cancelled(_)));
+        assert!(OpTimer::start("cancel_probe").check().is_ok());
+    }
+
+    #[test]
+    fn queued_operation_waits_for_slot() {
+        let queue = std::sync::Arc::new(OpQueue::new());
+        let busy = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let waiter = {
+            let queue = queue.clone();
+            let busy = busy.clone();
+            std::thread::spawn(move || {
+                queue.wait_for(4, Duration::from_secs(5), || {
+                    (!busy.load(Ordering::SeqCst)).then_some(())
+                })
+            })
+        };
+        while queue.depth() == 0 {
+            std::thread::yield_now();
+        }
+        busy.store(false, Ordering::SeqCst);
+        queue.notify();
+        assert!(waiter.join().unwrap().is_ok());
+
+        let stats = queue.stats();
+        assert_eq!(stats.depth, 0);
+        assert_eq!(stats.peak_depth, 1);
+        assert_eq!(stats.total_queued, 1);
+        assert_eq!(stats.timeouts, 0);
+    }
+
+    #[test]
+    fn queued_operation_times_out() {
+        let queue = OpQueue::new();
+        let err = queue
+            .wait_for(1, Duration::from_millis(5), || None::<()>)
+            .unwrap_err();
+        assert!(matches!(err, ForziumError::ResourceLimit(_)));
+        assert_eq!(queue.stats().timeouts, 1);
+        assert_eq!(queue.depth(), 0);
+    }
+
     #[test]
     fn test_op_guard() {
         // Enable resource limits
@@ -5,7 +5,13 @@
 //! - AVX-512 for modern Intel platforms
 //! - NEON for ARM platforms
 
+use crate::compute::resource_limits::{
+    DType, OpGuard, OpTimer, enforce_tensor_size, estimate_bytes,
+};
 use crate::error::ForziumError;
+#[cfg(target_arch = "x86_64")]
+use crate::memory::aligned::{AlignedMatrix, LANES};
+use std::ops::Range;
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
@@ -177,45 +183,38 @@ pub unsafe fn matmul_avx512(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f6
         ));
     }
 
-    // Transpose B for better cache locality
-    let mut bt = vec![vec![0.0; rows_b]; cols_b];
-    for i in 0..rows_b {
-        for j in 0..cols_b {
-            bt[j][i] = b[i][j];
-        }
+    if a.iter().any(|row| row.len() != cols_a) || b.iter().any(|row| row.len() != cols_b) {
+        return Err(ForziumError::Validation(
+            "matrix rows must have the same length".into(),
+        ));
     }
 
+    // Copy A and the transpose of B into 64-byte-aligned rows; the zero
+    // padding lets the loop use aligned loads with no scalar tail
+    let a_aligned = AlignedMatrix::from_rows(a, cols_a);
+    let bt = AlignedMatrix::transposed(b, cols_b);
+
     let mut result = vec![vec![0.0; cols_b]; rows_a];
 
-    for i in 0..rows_a {
-        for j in 0..cols_b {
-            let row_a = &a[i];
-            let row_bt = &bt[j];
+    for (i, out_row) in result.iter_mut().enumerate() {
+        let row_a = a_aligned.padded_row(i);
+        for (j, out) in out_row.iter_mut().enumerate() {
+            let row_bt = bt.padded_row(j);
 
-            let mut sum_vec = unsafe { _mm512_setzero_pd() }; // 8 doubles initialized to 0
+            let mut sum_vec = _mm512_setzero_pd(); // 8 doubles initialized to 0
 
             // Process 8 elements at a time
-            let mut k = 0;
-            while k + 8 <= cols_a {
-                let a_vec = unsafe { _mm512_loadu_pd(row_a[k..].as_ptr()) };
-                let b_vec = unsafe { _mm512_loadu_pd(row_bt[k..].as_ptr()) };
+            for k in (0..row_a.len()).step_by(LANES) {
+                // SAFETY: padded rows start 64-byte aligned and hold a
+                // whole number of 8-double vectors
+                let a_vec = unsafe { _mm512_load_pd(row_a.as_ptr().add(k)) };
+                let b_vec = unsafe { _mm512_load_pd(row_bt.as_ptr().add(k)) };
 
                 // a * b + sum
-                sum_vec = unsafe { _mm512_fmadd_pd(a_vec, b_vec, sum_vec) };
-
-                k += 8;
-            }
-
-            // Extract the sum from the vector
-            let mut sum = unsafe { _mm512_reduce_add_pd(sum_vec) };
-
-            // Handle remaining elements
-            while k < cols_a {
-                sum += row_a[k] * row_bt[k];
-                k += 1;
+                sum_vec = _mm512_fmadd_pd(a_vec, b_vec, sum_vec);
             }
 
-            result[i][j] = sum;
+            *out = _mm512_reduce_add_pd(sum_vec);
         }
     }
 
@@ -296,54 +295,66 @@ pub unsafe fn matmul_neon(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>
 /// Optimized matrix multiplication that automatically selects
 /// the best SIMD implementation for the current platform
 pub fn optimal_matmul(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
+    enforce_matrix_limits(a, "optimal_matmul")?;
+    enforce_matrix_limits(b, "optimal_matmul")?;
+    // Inputs, the aligned copies of `a` and `b`, and the output
+    let out_elements = a.len() * b.first().map_or(0, Vec::len);
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(
+        2 * (matrix_elements(a) + matrix_elements(b)) + out_elements,
+        DType::F64,
+    ))?;
+    let timer = OpTimer::start("optimal_matmul");
     let simd_support = detect_simd_support();
 
-    match simd_support {
-        #[cfg(target_arch = "x86_64")]
-        "avx512f" => unsafe { matmul_avx512(a, b) },
+    in_row_blocks(a.len(), &timer, |rows| {
+        let a = &a[rows];
+        match simd_support {
+            #[cfg(target_arch = "x86_64")]
+            "avx512f" => unsafe { matmul_avx512(a, b) },
 
-        #[cfg(target_arch = "x86_64")]
-        "avx2" => unsafe { matmul_avx2(a, b) },
+            #[cfg(target_arch = "x86_64")]
+            "avx2" => unsafe { matmul_avx2(a, b) },
 
-        #[cfg(target_arch = "aarch64")]
-        "neon" => unsafe { matmul_neon(a, b) },
+            #[cfg(target_arch = "aarch64")]
+            "neon" => unsafe { matmul_neon(a, b) },
 
-        _ => {
-            // Fallback to basic implementation
-            let rows_a = a.len();
-            if rows_a == 0 {
-                return Err(ForziumError::Validation("empty matrix".into()));
-            }
+            _ => {
+                // Fallback to basic implementation
+                let rows_a = a.len();
+                if rows_a == 0 {
+                    return Err(ForziumError::Validation("empty matrix".into()));
+                }
 
-            let cols_a = a[0].len();
-            let rows_b = b.len();
-            if rows_b == 0 {
-                return Err(ForziumError::Validation("empty matrix".into()));
-            }
+                let cols_a = a[0].len();
+                let rows_b = b.len();
+                if rows_b == 0 {
+                    return Err(ForziumError::Validation("empty matrix".into()));
+                }
 
-            let cols_b = b[0].len();
+                let cols_b = b[0].len();
 
-            if cols_a != rows_b {
-                return Err(ForziumError::Validation(
-                    "incompatible dimensions for matrix multiplication".into(),
-                ));
-            }
+                if cols_a != rows_b {
+                    return Err(ForziumError::Validation(
+                        "incompatible dimensions for matrix multiplication".into(),
+                    ));
+                }
 
-            let mut result = vec![vec![0.0; cols_b]; rows_a];
+                let mut result = vec![vec![0.0; cols_b]; rows_a];
 
-            for i in 0..rows_a {
-                for j in 0..cols_b {
-                    let mut sum = 0.0;
-                    for k in 0..cols_a {
-                        sum += a[i][k] * b[k][j];
+                for i in 0..rows_a {
+                    for j in 0..cols_b {
+                        let mut sum = 0.0;
+                        for k in 0..cols_a {
+                            sum += a[i][k] * b[k][j];
+                        }
+                        result[i][j] = sum;
                     }
-                    result[i][j] = sum;
                 }
-            }
 
-            Ok(result)
+                Ok(result)
+            }
         }
-    }
+    })
 }
 
 /// Element-wise vector addition using AVX2
@@ -484,46 +495,646 @@ pub unsafe fn add_neon(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>,
 /// Optimized matrix element-wise addition that automatically selects
 /// the best SIMD implementation for the current platform
 pub fn optimal_add(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
+    enforce_matrix_limits(a, "optimal_add")?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(3 * matrix_elements(a), DType::F64))?;
+    validate_same_dims(a, b)?;
+    let timer = OpTimer::start("optimal_add");
     let simd_support = detect_simd_support();
 
+    in_row_blocks(a.len(), &timer, |rows| {
+        let (a, b) = (&a[rows.clone()], &b[rows]);
+        match simd_support {
+            #[cfg(target_arch = "x86_64")]
+            "avx512f" => unsafe { add_avx512(a, b) },
+
+            #[cfg(target_arch = "x86_64")]
+            "avx2" => unsafe { add_avx2(a, b) },
+
+            #[cfg(target_arch = "aarch64")]
+            "neon" => unsafe { add_neon(a, b) },
+
+            _ => {
+                // Fallback to basic implementation
+                let rows = a.len();
+                if rows == 0 {
+                    return Err(ForziumError::Validation("empty matrix".into()));
+                }
+
+                let cols = a[0].len();
+
+                if rows != b.len() || b.iter().any(|row| row.len() != cols) {
+                    return Err(ForziumError::Validation(
+                        "matrices must have the same dimensions".into(),
+                    ));
+                }
+
+                let mut result = vec![vec![0.0; cols]; rows];
+
+                for i in 0..rows {
+                    for j in 0..cols {
+                        result[i][j] = a[i][j] + b[i][j];
+                    }
+                }
+
+                Ok(result)
+            }
+        }
+    })
+}
+
+/// Rows the whole-matrix kernels process between checks for cancellation
+/// and the operation's timeout
+pub(crate) const CHECK_BLOCK_ROWS: usize = 64;
+
+/// Compute a `rows`-row result with `kernel`, [`CHECK_BLOCK_ROWS`] rows at
+/// a time, stopping between blocks once `timer` is cancelled or expired
+///
+/// `kernel` is called at least once, so empty inputs are still validated.
+fn in_row_blocks(
+    rows: usize,
+    timer: &OpTimer,
+    mut kernel: impl FnMut(Range<usize>) -> Result<Vec<Vec<f64>>, ForziumError>,
+) -> Result<Vec<Vec<f64>>, ForziumError> {
+    let mut result = Vec::with_capacity(rows);
+    for start in (0..rows.max(1)).step_by(CHECK_BLOCK_ROWS) {
+        timer.check()?;
+        result.extend(kernel(start..(start + CHECK_BLOCK_ROWS).min(rows))?);
+    }
+    timer.check()?;
+    Ok(result)
+}
+
+/// Check a matrix's dimensions against the configured resource limits
+fn enforce_matrix_limits(m: &[Vec<f64>], operation: &str) -> Result<(), ForziumError> {
+    let cols = m.first().map_or(0, Vec::len);
+    enforce_tensor_size(m.len(), cols, operation)
+}
+
+/// Total number of elements across all rows of a matrix
+fn matrix_elements(m: &[Vec<f64>]) -> usize {
+    m.iter().map(Vec::len).sum()
+}
+
+/// Validate that two matrices are non-empty and share the same rectangular shape
+fn validate_same_dims(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<(usize, usize), ForziumError> {
+    let rows = a.len();
+    if rows == 0 || a[0].is_empty() {
+        return Err(ForziumError::Validation("empty matrix".into()));
+    }
+
+    let cols = a[0].len();
+
+    if rows != b.len()
+        || a.iter().any(|row| row.len() != cols)
+        || b.iter().any(|row| row.len() != cols)
+    {
+        return Err(ForziumError::Validation(
+            "matrices must have the same dimensions".into(),
+        ));
+    }
+
+    Ok((rows, cols))
+}
+
+/// Element-wise (Hadamard) matrix multiplication using AVX2
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX2.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+pub unsafe fn mul_avx2(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
+    let (rows, cols) = validate_same_dims(a, b)?;
+
+    let mut result = vec![vec![0.0; cols]; rows];
+
+    for i in 0..rows {
+        let row_a = &a[i];
+        let row_b = &b[i];
+        let row_result = &mut result[i];
+
+        let mut j = 0;
+        while j + 4 <= cols {
+            let a_vec = unsafe { _mm256_loadu_pd(&row_a[j] as *const f64) };
+            let b_vec = unsafe { _mm256_loadu_pd(&row_b[j] as *const f64) };
+
+            let prod_vec = _mm256_mul_pd(a_vec, b_vec);
+            unsafe { _mm256_storeu_pd(&mut row_result[j] as *mut f64, prod_vec) };
+
+            j += 4;
+        }
+
+        // Handle remaining elements
+        while j < cols {
+            row_result[j] = row_a[j] * row_b[j];
+            j += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Element-wise (Hadamard) matrix multiplication using AVX-512
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX-512F.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+pub unsafe fn mul_avx512(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
+    let (rows, cols) = validate_same_dims(a, b)?;
+
+    let mut result = vec![vec![0.0; cols]; rows];
+
+    for i in 0..rows {
+        let row_a = &a[i];
+        let row_b = &b[i];
+        let row_result = &mut result[i];
+
+        let mut j = 0;
+        while j + 8 <= cols {
+            let a_vec = unsafe { _mm512_loadu_pd(&row_a[j] as *const f64) };
+            let b_vec = unsafe { _mm512_loadu_pd(&row_b[j] as *const f64) };
+
+            let prod_vec = _mm512_mul_pd(a_vec, b_vec);
+            unsafe { _mm512_storeu_pd(&mut row_result[j] as *mut f64, prod_vec) };
+
+            j += 8;
+        }
+
+        // Handle remaining elements
+        while j < cols {
+            row_result[j] = row_a[j] * row_b[j];
+            j += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Element-wise (Hadamard) matrix multiplication using NEON on ARM
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports NEON.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+pub unsafe fn mul_neon(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
+    let (rows, cols) = validate_same_dims(a, b)?;
+
+    let mut result = vec![vec![0.0; cols]; rows];
+
+    for i in 0..rows {
+        let row_a = &a[i];
+        let row_b = &b[i];
+        let row_result = &mut result[i];
+
+        let mut j = 0;
+        while j + 2 <= cols {
+            let a_vec = unsafe { vld1q_f64(&row_a[j] as *const f64) };
+            let b_vec = unsafe { vld1q_f64(&row_b[j] as *const f64) };
+
+            let prod_vec = vmulq_f64(a_vec, b_vec);
+            unsafe { vst1q_f64(&mut row_result[j] as *mut f64, prod_vec) };
+
+            j += 2;
+        }
+
+        // Handle remaining elements
+        while j < cols {
+            row_result[j] = row_a[j] * row_b[j];
+            j += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Optimized matrix element-wise multiplication that automatically selects
+/// the best SIMD implementation for the current platform
+pub fn optimal_mul(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
+    enforce_matrix_limits(a, "optimal_mul")?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(3 * matrix_elements(a), DType::F64))?;
+    validate_same_dims(a, b)?;
+    let timer = OpTimer::start("optimal_mul");
+    let simd_support = detect_simd_support();
+
+    in_row_blocks(a.len(), &timer, |rows| {
+        mul_block(&a[rows.clone()], &b[rows], simd_support)
+    })
+}
+
+/// Dispatch a block of rows to the element-wise multiplication kernel
+/// matching `simd_support`
+pub(crate) fn mul_block(
+    a: &[Vec<f64>],
+    b: &[Vec<f64>],
+    simd_support: &str,
+) -> Result<Vec<Vec<f64>>, ForziumError> {
     match simd_support {
         #[cfg(target_arch = "x86_64")]
-        "avx512f" => unsafe { add_avx512(a, b) },
+        "avx512f" => unsafe { mul_avx512(a, b) },
 
         #[cfg(target_arch = "x86_64")]
-        "avx2" => unsafe { add_avx2(a, b) },
+        "avx2" => unsafe { mul_avx2(a, b) },
 
         #[cfg(target_arch = "aarch64")]
-        "neon" => unsafe { add_neon(a, b) },
+        "neon" => unsafe { mul_neon(a, b) },
 
         _ => {
             // Fallback to basic implementation
-            let rows = a.len();
-            if rows == 0 {
-                return Err(ForziumError::Validation("empty matrix".into()));
-            }
+            validate_same_dims(a, b)?;
+            Ok(a.iter()
+                .zip(b.iter())
+                .map(|(row_a, row_b)| row_a.iter().zip(row_b.iter()).map(|(x, y)| x * y).collect())
+                .collect())
+        }
+    }
+}
+
+/// Vector scaling by a constant factor using AVX2
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX2.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+pub unsafe fn scale_avx2(v: &[f64], factor: f64) -> Vec<f64> {
+    let len = v.len();
+    let mut result = vec![0.0; len];
+    let factor_vec = _mm256_set1_pd(factor);
+
+    let mut i = 0;
+    while i + 4 <= len {
+        let v_vec = unsafe { _mm256_loadu_pd(&v[i] as *const f64) };
+        let scaled = _mm256_mul_pd(v_vec, factor_vec);
+        unsafe { _mm256_storeu_pd(&mut result[i] as *mut f64, scaled) };
+        i += 4;
+    }
 
-            let cols = a[0].len();
+    // Handle remaining elements
+    while i < len {
+        result[i] = v[i] * factor;
+        i += 1;
+    }
 
-            if rows != b.len() || b.iter().any(|row| row.len() != cols) {
-                return Err(ForziumError::Validation(
-                    "matrices must have the same dimensions".into(),
-                ));
-            }
+    result
+}
 
-            let mut result = vec![vec![0.0; cols]; rows];
+/// Vector scaling by a constant factor using AVX-512
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX-512F.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+pub unsafe fn scale_avx512(v: &[f64], factor: f64) -> Vec<f64> {
+    let len = v.len();
+    let mut result = vec![0.0; len];
+    let factor_vec = _mm512_set1_pd(factor);
+
+    let mut i = 0;
+    while i + 8 <= len {
+        let v_vec = unsafe { _mm512_loadu_pd(&v[i] as *const f64) };
+        let scaled = _mm512_mul_pd(v_vec, factor_vec);
+        unsafe { _mm512_storeu_pd(&mut result[i] as *mut f64, scaled) };
+        i += 8;
+    }
 
-            for i in 0..rows {
-                for j in 0..cols {
-                    result[i][j] = a[i][j] + b[i][j];
-                }
-            }
+    // Handle remaining elements
+    while i < len {
+        result[i] = v[i] * factor;
+        i += 1;
+    }
 
-            Ok(result)
-        }
+    result
+}
+
+/// Vector scaling by a constant factor using NEON on ARM
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports NEON.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+pub unsafe fn scale_neon(v: &[f64], factor: f64) -> Vec<f64> {
+    let len = v.len();
+    let mut result = vec![0.0; len];
+    let factor_vec = vdupq_n_f64(factor);
+
+    let mut i = 0;
+    while i + 2 <= len {
+        let v_vec = unsafe { vld1q_f64(&v[i] as *const f64) };
+        let scaled = vmulq_f64(v_vec, factor_vec);
+        unsafe { vst1q_f64(&mut result[i] as *mut f64, scaled) };
+        i += 2;
+    }
+
+    // Handle remaining elements
+    while i < len {
+        result[i] = v[i] * factor;
+        i += 1;
+    }
+
+    result
+}
+
+/// Optimized vector scaling that automatically selects the best SIMD
+/// implementation for the current platform
+pub fn optimal_scale(v: &[f64], factor: f64) -> Result<Vec<f64>, ForziumError> {
+    if v.is_empty() {
+        return Err(ForziumError::Validation("empty vector".into()));
+    }
+    enforce_tensor_size(1, v.len(), "optimal_scale")?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(2 * v.len(), DType::F64))?;
+
+    Ok(scale_slice(v, factor, detect_simd_support()))
+}
+
+/// Optimized matrix-by-scalar multiplication that automatically selects
+/// the best SIMD implementation for the current platform
+pub fn optimal_multiply(m: &[Vec<f64>], factor: f64) -> Result<Vec<Vec<f64>>, ForziumError> {
+    if m.is_empty() || m[0].is_empty() {
+        return Err(ForziumError::Validation("empty matrix".into()));
+    }
+    if m.iter().any(|row| row.len() != m[0].len()) {
+        return Err(ForziumError::Validation("ragged matrix".into()));
+    }
+    enforce_tensor_size(m.len(), m[0].len(), "optimal_multiply")?;
+    let _op_guard = OpGuard::acquire_bytes(estimate_bytes(2 * matrix_elements(m), DType::F64))?;
+    let timer = OpTimer::start("optimal_multiply");
+
+    let simd_support = detect_simd_support();
+    in_row_blocks(m.len(), &timer, |rows| {
+        Ok(m[rows]
+            .iter()
+            .map(|row| scale_slice(row, factor, simd_support))
+            .collect())
+    })
+}
+
+/// Dispatch a single slice to the scale kernel matching `simd_support`
+pub(crate) fn scale_slice(v: &[f64], factor: f64, simd_support: &str) -> Vec<f64> {
+    match simd_support {
+        #[cfg(target_arch = "x86_64")]
+        "avx512f" => unsafe { scale_avx512(v, factor) },
+
+        #[cfg(target_arch = "x86_64")]
+        "avx2" => unsafe { scale_avx2(v, factor) },
+
+        #[cfg(target_arch = "aarch64")]
+        "neon" => unsafe { scale_neon(v, factor) },
+
+        _ => v.iter().map(|x| x * factor).collect(),
     }
 }
 
+/// Vector dot product using AVX2
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX2 and that `a` and `b`
+/// have the same length.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+pub unsafe fn dot_avx2(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len();
+    let mut sum_vec = _mm256_setzero_pd();
+
+    let mut i = 0;
+    while i + 4 <= len {
+        let a_vec = unsafe { _mm256_loadu_pd(&a[i] as *const f64) };
+        let b_vec = unsafe { _mm256_loadu_pd(&b[i] as *const f64) };
+        sum_vec = _mm256_add_pd(sum_vec, _mm256_mul_pd(a_vec, b_vec));
+        i += 4;
+    }
+
+    let mut lanes = [0.0f64; 4];
+    unsafe { _mm256_storeu_pd(lanes.as_mut_ptr(), sum_vec) };
+    let mut sum: f64 = lanes.iter().sum();
+
+    // Handle remaining elements
+    while i < len {
+        sum += a[i] * b[i];
+        i += 1;
+    }
+
+    sum
+}
+
+/// Vector dot product using AVX-512
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX-512F and that `a` and `b`
+/// have the same length.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+pub unsafe fn dot_avx512(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len();
+    let mut sum_vec = _mm512_setzero_pd();
+
+    let mut i = 0;
+    while i + 8 <= len {
+        let a_vec = unsafe { _mm512_loadu_pd(&a[i] as *const f64) };
+        let b_vec = unsafe { _mm512_loadu_pd(&b[i] as *const f64) };
+        sum_vec = _mm512_add_pd(sum_vec, _mm512_mul_pd(a_vec, b_vec));
+        i += 8;
+    }
+
+    let mut lanes = [0.0f64; 8];
+    unsafe { _mm512_storeu_pd(lanes.as_mut_ptr(), sum_vec) };
+    let mut sum: f64 = lanes.iter().sum();
+
+    // Handle remaining elements
+    while i < len {
+        sum += a[i] * b[i];
+        i += 1;
+    }
+
+    sum
+}
+
+/// Vector dot product using NEON on ARM
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports NEON and that `a` and `b`
+/// have the same length.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+pub unsafe fn dot_neon(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len();
+    let mut sum_vec = vdupq_n_f64(0.0);
+
+    let mut i = 0;
+    while i + 2 <= len {
+        let a_vec = unsafe { vld1q_f64(&a[i] as *const f64) };
+        let b_vec = unsafe { vld1q_f64(&b[i] as *const f64) };
+        sum_vec = vfmaq_f64(sum_vec, a_vec, b_vec);
+        i += 2;
+    }
+
+    let mut sum = vaddvq_f64(sum_vec);
+
+    // Handle remaining elements
+    while i < len {
+        sum += a[i] * b[i];
+        i += 1;
+    }
+
+    sum
+}
+
+/// Optimized vector dot product that automatically selects the best SIMD
+/// implementation for the current platform
+pub fn optimal_dot(a: &[f64], b: &[f64]) -> Result<f64, ForziumError> {
+    if a.len() != b.len() {
+        return Err(ForziumError::Validation(
+            "vectors must have the same length".into(),
+        ));
+    }
+
+    Ok(match detect_simd_support() {
+        #[cfg(target_arch = "x86_64")]
+        "avx512f" => unsafe { dot_avx512(a, b) },
+
+        #[cfg(target_arch = "x86_64")]
+        "avx2" => unsafe { dot_avx2(a, b) },
+
+        #[cfg(target_arch = "aarch64")]
+        "neon" => unsafe { dot_neon(a, b) },
+
+        _ => a.iter().zip(b.iter()).map(|(x, y)| x * y).sum(),
+    })
+}
+
+/// Squared Euclidean distance using AVX2
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX2 and that `a` and `b`
+/// have the same length.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+pub unsafe fn squared_distance_avx2(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len();
+    let mut sum_vec = _mm256_setzero_pd();
+
+    let mut i = 0;
+    while i + 4 <= len {
+        let a_vec = unsafe { _mm256_loadu_pd(&a[i] as *const f64) };
+        let b_vec = unsafe { _mm256_loadu_pd(&b[i] as *const f64) };
+        let diff = _mm256_sub_pd(a_vec, b_vec);
+        sum_vec = _mm256_add_pd(sum_vec, _mm256_mul_pd(diff, diff));
+        i += 4;
+    }
+
+    let mut lanes = [0.0f64; 4];
+    unsafe { _mm256_storeu_pd(lanes.as_mut_ptr(), sum_vec) };
+    let mut sum: f64 = lanes.iter().sum();
+
+    // Handle remaining elements
+    while i < len {
+        let diff = a[i] - b[i];
+        sum += diff * diff;
+        i += 1;
+    }
+
+    sum
+}
+
+/// Squared Euclidean distance using AVX-512
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports AVX-512F and that `a` and `b`
+/// have the same length.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+pub unsafe fn squared_distance_avx512(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len();
+    let mut sum_vec = _mm512_setzero_pd();
+
+    let mut i = 0;
+    while i + 8 <= len {
+        let a_vec = unsafe { _mm512_loadu_pd(&a[i] as *const f64) };
+        let b_vec = unsafe { _mm512_loadu_pd(&b[i] as *const f64) };
+        let diff = _mm512_sub_pd(a_vec, b_vec);
+        sum_vec = _mm512_add_pd(sum_vec, _mm512_mul_pd(diff, diff));
+        i += 8;
+    }
+
+    let mut lanes = [0.0f64; 8];
+    unsafe { _mm512_storeu_pd(lanes.as_mut_ptr(), sum_vec) };
+    let mut sum: f64 = lanes.iter().sum();
+
+    // Handle remaining elements
+    while i < len {
+        let diff = a[i] - b[i];
+        sum += diff * diff;
+        i += 1;
+    }
+
+    sum
+}
+
+/// Squared Euclidean distance using NEON on ARM
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports NEON and that `a` and `b`
+/// have the same length.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+pub unsafe fn squared_distance_neon(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len();
+    let mut sum_vec = vdupq_n_f64(0.0);
+
+    let mut i = 0;
+    while i + 2 <= len {
+        let a_vec = unsafe { vld1q_f64(&a[i] as *const f64) };
+        let b_vec = unsafe { vld1q_f64(&b[i] as *const f64) };
+        let diff = vsubq_f64(a_vec, b_vec);
+        sum_vec = vfmaq_f64(sum_vec, diff, diff);
+        i += 2;
+    }
+
+    let mut sum = vaddvq_f64(sum_vec);
+
+    // Handle remaining elements
+    while i < len {
+        let diff = a[i] - b[i];
+        sum += diff * diff;
+        i += 1;
+    }
+
+    sum
+}
+
+/// Optimized squared Euclidean distance that automatically selects the best
+/// SIMD implementation for the current platform
+pub fn optimal_squared_distance(a: &[f64], b: &[f64]) -> Result<f64, ForziumError> {
+    if a.len() != b.len() {
+        return Err(ForziumError::Validation(
+            "vectors must have the same length".into(),
+        ));
+    }
+
+    Ok(match detect_simd_support() {
+        #[cfg(target_arch = "x86_64")]
+        "avx512f" => unsafe { squared_distance_avx512(a, b) },
+
+        #[cfg(target_arch = "x86_64")]
+        "avx2" => unsafe { squared_distance_avx2(a, b) },
+
+        #[cfg(target_arch = "aarch64")]
+        "neon" => unsafe { squared_distance_neon(a, b) },
+
+        _ => a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum(),
+    })
+}
+
 /// Optimized convolution using AVX2
 #[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "avx2")]
@@ -619,7 +1230,176 @@ pub fn benchmark_simd_ops() -> String {
     report.push_str(&format!("Element-wise Addition ({}x{}):\n", size, size));
     report.push_str(&format!("  Basic:     {:?}\n", basic_time));
     report.push_str(&format!("  Optimized: {:?}\n", optimized_time));
+    report.push_str(&format!("  Speedup:   {:.2}x\n\n", speedup));
+
+    // Element-wise multiplication
+    let start = Instant::now();
+    let _ = super::tensor_ops::hadamard(&a, &b).unwrap();
+    let basic_time = start.elapsed();
+
+    let start = Instant::now();
+    let _ = optimal_mul(&a, &b).unwrap();
+    let optimized_time = start.elapsed();
+
+    let speedup = basic_time.as_secs_f64() / optimized_time.as_secs_f64();
+
+    report.push_str(&format!(
+        "Element-wise Multiplication ({}x{}):\n",
+        size, size
+    ));
+    report.push_str(&format!("  Basic:     {:?}\n", basic_time));
+    report.push_str(&format!("  Optimized: {:?}\n", optimized_time));
+    report.push_str(&format!("  Speedup:   {:.2}x\n\n", speedup));
+
+    // Scalar multiplication
+    let start = Instant::now();
+    let _ = super::tensor_ops::multiply(&a, 1.5).unwrap();
+    let basic_time = start.elapsed();
+
+    let start = Instant::now();
+    let _ = optimal_multiply(&a, 1.5).unwrap();
+    let optimized_time = start.elapsed();
+
+    let speedup = basic_time.as_secs_f64() / optimized_time.as_secs_f64();
+
+    report.push_str(&format!("Scalar Multiplication ({}x{}):\n", size, size));
+    report.push_str(&format!("  Basic:     {:?}\n", basic_time));
+    report.push_str(&format!("  Optimized: {:?}\n", optimized_time));
+    report.push_str(&format!("  Speedup:   {:.2}x\n\n", speedup));
+
+    // Vector scaling
+    let vector: Vec<f64> = (0..size * size).map(|i| i as f64).collect();
+    let start = Instant::now();
+    let _ = super::data_transform::scale(&vector, 1.5).unwrap();
+    let basic_time = start.elapsed();
+
+    let start = Instant::now();
+    let _ = optimal_scale(&vector, 1.5).unwrap();
+    let optimized_time = start.elapsed();
+
+    let speedup = basic_time.as_secs_f64() / optimized_time.as_secs_f64();
+
+    report.push_str(&format!("Vector Scaling ({}):\n", size * size));
+    report.push_str(&format!("  Basic:     {:?}\n", basic_time));
+    report.push_str(&format!("  Optimized: {:?}\n", optimized_time));
     report.push_str(&format!("  Speedup:   {:.2}x\n", speedup));
 
     report
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::resource_limits::{CancelToken, with_cancel_token};
+
+    #[test]
+    fn row_blocks_stop_once_cancelled() {
+        let token = CancelToken::new();
+        let timer = with_cancel_token(&token, || OpTimer::start("block_probe"));
+        let mut blocks = Vec::new();
+        let result = in_row_blocks(3 * CHECK_BLOCK_ROWS, &timer, |rows| {
+            blocks.push(rows.clone());
+            token.cancel();
+            Ok(vec![Vec::new(); rows.len()])
+        });
+        assert!(matches!(result, Err(ForziumError::Cancelled(_))));
+        assert_eq!(blocks, vec![0..CHECK_BLOCK_ROWS]);
+
+        let m = vec![vec![1.0, 2.0]; CHECK_BLOCK_ROWS + 1];
+        assert_eq!(
+            optimal_multiply(&m, 2.0).unwrap(),
+            vec![vec![2.0, 4.0]; CHECK_BLOCK_ROWS + 1]
+        );
+        assert!(optimal_add(&[], &[]).is_err());
+    }
+
+    #[test]
+    fn optimal_mul_matches_scalar() {
+        let a: Vec<Vec<f64>> = (0..3)
+            .map(|i| (0..11).map(|j| (i * 11 + j) as f64).collect())
+            .collect();
+        let b: Vec<Vec<f64>> = (0..3)
+            .map(|i| (0..11).map(|j| (i + j) as f64 * 0.5).collect())
+            .collect();
+        let expected: Vec<Vec<f64>> = a
+            .iter()
+            .zip(b.iter())
+            .map(|(ra, rb)| ra.iter().zip(rb.iter()).map(|(x, y)| x * y).collect())
+            .collect();
+        assert_eq!(optimal_mul(&a, &b).unwrap(), expected);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn avx512_matmul_handles_unpadded_shapes() {
+        if !is_x86_feature_detected!("avx512f") {
+            return;
+        }
+        let a: Vec<Vec<f64>> = (0..5)
+            .map(|i| (0..11).map(|j| (i * 11 + j) as f64 * 0.25).collect())
+            .collect();
+        let b: Vec<Vec<f64>> = (0..11)
+            .map(|i| (0..7).map(|j| (i + 2 * j) as f64).collect())
+            .collect();
+        let expected: Vec<Vec<f64>> = a
+            .iter()
+            .map(|row| {
+                (0..7)
+                    .map(|j| row.iter().zip(&b).map(|(x, rb)| x * rb[j]).sum())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(unsafe { matmul_avx512(&a, &b) }.unwrap(), expected);
+
+        let ragged = vec![vec![1.0, 2.0], vec![3.0]];
+        let err = unsafe { matmul_avx512(&ragged, &b) }.unwrap_err();
+        assert!(matches!(err, ForziumError::Validation(_)));
+    }
+
+    #[test]
+    fn optimal_mul_rejects_ragged() {
+        let a = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let b = vec![vec![1.0, 2.0], vec![3.0]];
+        let err = optimal_mul(&a, &b).unwrap_err();
+        assert!(matches!(err, ForziumError::Validation(_)));
+    }
+
+    #[test]
+    fn optimal_scale_and_multiply() {
+        let v: Vec<f64> = (0..13).map(|i| i as f64).collect();
+        let expected: Vec<f64> = v.iter().map(|x| x * 2.5).collect();
+        assert_eq!(optimal_scale(&v, 2.5).unwrap(), expected);
+        assert!(optimal_scale(&[], 2.0).is_err());
+
+        let m = vec![
+            vec![1.0, 2.0, 3.0, 4.0, 5.0],
+            vec![6.0, 7.0, 8.0, 9.0, 10.0],
+        ];
+        assert_eq!(
+            optimal_multiply(&m, -1.0).unwrap(),
+            vec![
+                vec![-1.0, -2.0, -3.0, -4.0, -5.0],
+                vec![-6.0, -7.0, -8.0, -9.0, -10.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn optimal_dot_matches_scalar() {
+        let a: Vec<f64> = (0..19).map(|i| i as f64 * 0.5).collect();
+        let b: Vec<f64> = (0..19).map(|i| (19 - i) as f64).collect();
+        let expected: f64 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+        assert!((optimal_dot(&a, &b).unwrap() - expected).abs() < 1e-9);
+        assert!(optimal_dot(&a, &b[1..]).is_err());
+    }
+
+    #[test]
+    fn optimal_squared_distance_matches_scalar() {
+        let a: Vec<f64> = (0..21).map(|i| i as f64 * 0.25).collect();
+        let b: Vec<f64> = (0..21).map(|i| (21 - i) as f64).collect();
+        let expected: f64 = a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum();
+        assert!((optimal_squared_distance(&a, &b).unwrap() - expected).abs() < 1e-9);
+        assert_eq!(optimal_squared_distance(&a, &a).unwrap(), 0.0);
+        assert!(optimal_squared_distance(&a, &b[1..]).is_err());
+    }
+}
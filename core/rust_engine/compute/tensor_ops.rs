@@ -1,5 +1,8 @@
 use crate::compute::rayon_metrics;
-use crate::compute::resource_limits::{check_tensor_size, OpGuard, RESOURCE_LIMITS};
+use crate::compute::resource_limits::{
+    DType, OpGuard, OpTimer, enforce_tensor_size, estimate_bytes,
+};
+use crate::compute::simd_ops::{self, CHECK_BLOCK_ROWS};
 use crate::error::ForziumError;
 use rayon::prelude::*;
 #[cfg(target_arch = "x86_64")]
@@ -15,13 +18,50 @@ fn validate_matrix(m: &[Vec<f64>], operation: &str) -> Result<(usize, usize), Fo
     }
 
     // Check resource limits
-    if let Err(msg) = check_tensor_size(m.len(), cols, operation) {
-        return Err(ForziumError::ResourceLimit(msg));
-    }
+    enforce_tensor_size(m.len(), cols, operation)?;
 
     Ok((m.len(), cols))
 }
 
+/// Acquire an operation guard reserving memory for `elements` f64 values
+/// across all buffers (inputs, intermediates, and outputs) of an operation.
+fn acquire_f64(elements: usize) -> Result<OpGuard, ForziumError> {
+    OpGuard::acquire_bytes(estimate_bytes(elements, DType::F64))
+}
+
+/// Receives the rows of a result in order, one block at a time. An error
+/// stops the operation producing them.
+pub type RowSink<'a> = dyn FnMut(Vec<Vec<f64>>) -> Result<(), ForziumError> + 'a;
+
+/// Compute the result of a row-wise operation `block_rows` input rows at a
+/// time, handing each block to `sink` before computing the next, so the
+/// whole result never exists at once.
+fn stream_rows(
+    m: &[Vec<f64>],
+    block_rows: usize,
+    operation: &str,
+    out_cols: usize,
+    row: impl Fn(&[f64]) -> Vec<f64> + Sync,
+    sink: &mut RowSink<'_>,
+) -> Result<(), ForziumError> {
+    let timer = OpTimer::start(operation);
+    for block in m.chunks(block_rows.max(1)) {
+        let out = block
+            .par_iter()
+            .map(|r| {
+                let _guard = rayon_metrics::track_operation(operation);
+                if timer.expired() {
+                    return vec![0.0; out_cols];
+                }
+                row(r)
+            })
+            .collect();
+        timer.check()?;
+        sink(out)?;
+    }
+    Ok(())
+}
+
 fn validate_same_shape(
     a: &[Vec<f64>],
     b: &[Vec<f64>],
@@ -36,124 +76,199 @@ fn validate_same_shape(
 }
 
 pub fn multiply(m: &[Vec<f64>], factor: f64) -> Result<Vec<Vec<f64>>, ForziumError> {
-    validate_matrix(m, "multiply")?;
+    let (rows, cols) = validate_matrix(m, "multiply")?;
 
     // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
-
-    Ok(m.par_iter()
+    let _op_guard = acquire_f64(2 * rows * cols)?;
+    let timer = OpTimer::start("multiply");
+    let simd_support = simd_ops::detect_simd_support();
+
+    let out = m
+        .par_iter()
         .map(|r| {
-            let _guard = rayon_metrics::track_task();
-            r.par_iter().map(|v| v * factor).collect()
+            let _guard = rayon_metrics::track_operation("multiply");
+            if timer.expired() {
+                return Vec::new();
+            }
+            simd_ops::scale_slice(r, factor, simd_support)
         })
-        .collect())
+        .collect();
+    timer.check()?;
+    Ok(out)
+}
+
+/// [`multiply`], handing the result to `sink` in blocks of `block_rows` rows.
+pub fn multiply_rows(
+    m: &[Vec<f64>],
+    factor: f64,
+    block_rows: usize,
+    sink: &mut RowSink<'_>,
+) -> Result<(), ForziumError> {
+    let (rows, cols) = validate_matrix(m, "multiply")?;
+
+    // The input and one block of the result
+    let _op_guard = acquire_f64((rows + block_rows.min(rows)) * cols)?;
+    let simd_support = simd_ops::detect_simd_support();
+    stream_rows(
+        m,
+        block_rows,
+        "multiply",
+        cols,
+        |r| simd_ops::scale_slice(r, factor, simd_support),
+        sink,
+    )
 }
 
 pub fn add(m: &[Vec<f64>], addend: f64) -> Result<Vec<Vec<f64>>, ForziumError> {
-    validate_matrix(m, "add")?;
+    let (rows, cols) = validate_matrix(m, "add")?;
 
     // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
-
-    Ok(m.par_iter()
+    let _op_guard = acquire_f64(2 * rows * cols)?;
+    let timer = OpTimer::start("add");
+
+    let out = m
+        .par_iter()
         .map(|r| {
-            let _guard = rayon_metrics::track_task();
+            let _guard = rayon_metrics::track_operation("add");
+            if timer.expired() {
+                return Vec::new();
+            }
             r.par_iter().map(|v| v + addend).collect()
         })
-        .collect())
+        .collect();
+    timer.check()?;
+    Ok(out)
+}
+
+/// [`add`], handing the result to `sink` in blocks of `block_rows` rows.
+pub fn add_rows(
+    m: &[Vec<f64>],
+    addend: f64,
+    block_rows: usize,
+    sink: &mut RowSink<'_>,
+) -> Result<(), ForziumError> {
+    let (rows, cols) = validate_matrix(m, "add")?;
+
+    // The input and one block of the result
+    let _op_guard = acquire_f64((rows + block_rows.min(rows)) * cols)?;
+    stream_rows(
+        m,
+        block_rows,
+        "add",
+        cols,
+        |r| r.iter().map(|v| v + addend).collect(),
+        sink,
+    )
 }
 
 pub fn transpose(m: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
     let (rows, cols) = validate_matrix(m, "transpose")?;
 
     // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
+    let _op_guard = acquire_f64(2 * rows * cols)?;
+    let timer = OpTimer::start("transpose");
+
+    let out = transposed(m, rows, cols, &timer);
+    timer.check()?;
+    Ok(out)
+}
 
+/// Transpose of a validated `rows` x `cols` matrix, for operations that
+/// already hold a guard covering the result. Stops early once `timer` has
+/// expired, which the caller reports.
+fn transposed(m: &[Vec<f64>], rows: usize, cols: usize, timer: &OpTimer) -> Vec<Vec<f64>> {
     let mut out = vec![vec![0.0; rows]; cols];
     for (r, row) in m.iter().enumerate() {
+        if timer.expired() {
+            break;
+        }
         for (c, val) in row.iter().enumerate() {
             out[c][r] = *val;
         }
     }
-    Ok(out)
+    out
 }
 
 pub fn matmul(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
-    let (_rows_a, cols_a) = validate_matrix(a, "matmul")?;
+    let (rows_a, cols_a) = validate_matrix(a, "matmul")?;
     let (rows_b, cols_b) = validate_matrix(b, "matmul")?;
     if cols_a != rows_b {
         return Err(ForziumError::Validation("shape mismatch".into()));
     }
 
     // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
+    let _op_guard = acquire_f64(rows_a * cols_a + rows_b * cols_b + rows_a * cols_b)?;
+    let timer = OpTimer::start("matmul");
     let out: Vec<Vec<f64>> = a
         .par_iter()
         .map(|row_a| {
-            let _guard = rayon_metrics::track_task();
-            let mut out_row = vec![0.0; cols_b];
-            for (k, val_a) in row_a.iter().enumerate() {
-                let row_b = &b[k];
-                for (j, val_b) in row_b.iter().enumerate() {
-                    out_row[j] += val_a * val_b;
-                }
+            let _guard = rayon_metrics::track_operation("matmul");
+            if timer.expired() {
+                return vec![0.0; cols_b];
             }
-            out_row
+            matmul_row(row_a, b, cols_b)
         })
         .collect();
+    timer.check()?;
     Ok(out)
 }
 
+/// [`matmul`], handing the result to `sink` in blocks of `block_rows` rows.
+pub fn matmul_rows(
+    a: &[Vec<f64>],
+    b: &[Vec<f64>],
+    block_rows: usize,
+    sink: &mut RowSink<'_>,
+) -> Result<(), ForziumError> {
+    let (rows_a, cols_a) = validate_matrix(a, "matmul")?;
+    let (rows_b, cols_b) = validate_matrix(b, "matmul")?;
+    if cols_a != rows_b {
+        return Err(ForziumError::Validation("shape mismatch".into()));
+    }
+
+    // Both inputs and one block of the result
+    let _op_guard =
+        acquire_f64(rows_a * cols_a + rows_b * cols_b + block_rows.min(rows_a) * cols_b)?;
+    stream_rows(
+        a,
+        block_rows,
+        "matmul",
+        cols_b,
+        |row_a| matmul_row(row_a, b, cols_b),
+        sink,
+    )
+}
+
+/// Row of `a * b` for the row `row_a` of `a`.
+fn matmul_row(row_a: &[f64], b: &[Vec<f64>], cols_b: usize) -> Vec<f64> {
+    let mut out_row = vec![0.0; cols_b];
+    for (val_a, row_b) in row_a.iter().zip(b) {
+        for (out, val_b) in out_row.iter_mut().zip(row_b) {
+            *out += val_a * val_b;
+        }
+    }
+    out_row
+}
+
 pub fn simd_matmul(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
-    let (_rows_a, cols_a) = validate_matrix(a, "simd_matmul")?;
+    let (rows_a, cols_a) = validate_matrix(a, "simd_matmul")?;
     let (rows_b, cols_b) = validate_matrix(b, "simd_matmul")?;
     if cols_a != rows_b {
         return Err(ForziumError::Validation("shape mismatch".into()));
     }
 
-    // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
-    let bt = transpose(b)?;
+    // Try to acquire operation guard, covering the transposed copy of b
+    let _op_guard = acquire_f64(rows_a * cols_a + 2 * rows_b * cols_b + rows_a * cols_b)?;
+    let timer = OpTimer::start("simd_matmul");
+    let bt = transposed(b, rows_b, cols_b, &timer);
     let out: Vec<Vec<f64>> = a
         .par_iter()
         .map(|row_a| {
-            let _guard = rayon_metrics::track_task();
+            let _guard = rayon_metrics::track_operation("simd_matmul");
             let mut out_row = vec![0.0; cols_b];
+            if timer.expired() {
+                return out_row;
+            }
             #[cfg(target_arch = "x86_64")]
             unsafe {
                 for (bt_row, out_cell) in bt.iter().zip(out_row.iter_mut()) {
@@ -189,51 +304,49 @@ pub fn simd_matmul(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, Forz
             out_row
         })
         .collect();
+    timer.check()?;
     Ok(out)
 }
 
 pub fn elementwise_add(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
-    validate_same_shape(a, b, "elementwise_add")?;
+    let (rows, cols) = validate_same_shape(a, b, "elementwise_add")?;
 
     // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
-
-    Ok(a.par_iter()
+    let _op_guard = acquire_f64(3 * rows * cols)?;
+    let timer = OpTimer::start("elementwise_add");
+
+    let out = a
+        .par_iter()
         .zip(b.par_iter())
         .map(|(row_a, row_b)| {
-            let _guard = rayon_metrics::track_task();
+            let _guard = rayon_metrics::track_operation("elementwise_add");
+            if timer.expired() {
+                return Vec::new();
+            }
             row_a
                 .par_iter()
                 .zip(row_b.par_iter())
                 .map(|(val_a, val_b)| val_a + val_b)
                 .collect()
         })
-        .collect())
+        .collect();
+    timer.check()?;
+    Ok(out)
 }
 
 pub fn simd_elementwise_add(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
     let (rows, cols) = validate_same_shape(a, b, "simd_elementwise_add")?;
 
     // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
+    let _op_guard = acquire_f64(3 * rows * cols)?;
+    let timer = OpTimer::start("simd_elementwise_add");
     let mut out = vec![vec![0.0; cols]; rows];
     #[cfg(target_arch = "x86_64")]
     unsafe {
         for r in 0..rows {
+            if timer.expired() {
+                break;
+            }
             let mut c = 0;
             while c + 2 <= cols {
                 let va = _mm_loadu_pd(a[r].as_ptr().add(c));
@@ -251,39 +364,40 @@ pub fn simd_elementwise_add(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f6
     #[cfg(not(target_arch = "x86_64"))]
     {
         for (row_out, (row_a, row_b)) in out.iter_mut().zip(a.iter().zip(b.iter())) {
+            if timer.expired() {
+                break;
+            }
             for (val_out, (val_a, val_b)) in row_out.iter_mut().zip(row_a.iter().zip(row_b.iter()))
             {
                 *val_out = val_a + val_b;
             }
         }
     }
+    timer.check()?;
     Ok(out)
 }
 
 pub fn hadamard(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, ForziumError> {
-    validate_same_shape(a, b, "hadamard")?;
+    let (rows, cols) = validate_same_shape(a, b, "hadamard")?;
 
     // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
-
-    Ok(a.par_iter()
-        .zip(b.par_iter())
-        .map(|(row_a, row_b)| {
-            let _guard = rayon_metrics::track_task();
-            row_a
-                .par_iter()
-                .zip(row_b.par_iter())
-                .map(|(val_a, val_b)| val_a * val_b)
-                .collect()
+    let _op_guard = acquire_f64(3 * rows * cols)?;
+    let timer = OpTimer::start("hadamard");
+    let simd_support = simd_ops::detect_simd_support();
+
+    let blocks = a
+        .par_chunks(CHECK_BLOCK_ROWS)
+        .zip(b.par_chunks(CHECK_BLOCK_ROWS))
+        .map(|(block_a, block_b)| {
+            let _guard = rayon_metrics::track_operation("hadamard");
+            if timer.expired() {
+                return Ok(Vec::new());
+            }
+            simd_ops::mul_block(block_a, block_b, simd_support)
         })
-        .collect())
+        .collect::<Result<Vec<_>, _>>()?;
+    timer.check()?;
+    Ok(blocks.into_iter().flatten().collect())
 }
 
 #[allow(clippy::needless_range_loop)]
@@ -294,20 +408,18 @@ pub fn conv2d(input: &[Vec<f64>], kernel: &[Vec<f64>]) -> Result<Vec<Vec<f64>>,
         return Err(ForziumError::Validation("kernel larger than input".into()));
     }
 
-    // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
     let out_rows = rows - krows + 1;
     let out_cols = cols - kcols + 1;
+
+    // Try to acquire operation guard
+    let _op_guard = acquire_f64(rows * cols + krows * kcols + out_rows * out_cols)?;
+    let timer = OpTimer::start("conv2d");
     let mut out = vec![vec![0.0; out_cols]; out_rows];
     out.par_iter_mut().enumerate().for_each(|(r, out_row)| {
-        let _guard = rayon_metrics::track_task();
+        let _guard = rayon_metrics::track_operation("conv2d");
+        if timer.expired() {
+            return;
+        }
         for c in 0..out_cols {
             let mut sum = 0.0;
             #[cfg(target_arch = "x86_64")]
@@ -342,6 +454,71 @@ pub fn conv2d(input: &[Vec<f64>], kernel: &[Vec<f64>]) -> Result<Vec<Vec<f64>>,
             out_row[c] = sum;
         }
     });
+    timer.check()?;
+    Ok(out)
+}
+
+/// Transposed 2D convolution (deconvolution) with `stride` and `padding`.
+///
+/// Each input element scatters `input[i][j] * kernel` into the output at
+/// `(i * stride - padding, j * stride - padding)`, producing an output of
+/// `(rows - 1) * stride + krows - 2 * padding` rows (and likewise for columns).
+#[allow(clippy::needless_range_loop)]
+pub fn conv2d_transpose(
+    input: &[Vec<f64>],
+    kernel: &[Vec<f64>],
+    stride: usize,
+    padding: usize,
+) -> Result<Vec<Vec<f64>>, ForziumError> {
+    let (rows, cols) = validate_matrix(input, "conv2d_transpose")?;
+    let (krows, kcols) = validate_matrix(kernel, "conv2d_transpose")?;
+    if stride == 0 {
+        return Err(ForziumError::Validation("stride must be positive".into()));
+    }
+    let full_rows = (rows - 1) * stride + krows;
+    let full_cols = (cols - 1) * stride + kcols;
+    if full_rows <= 2 * padding || full_cols <= 2 * padding {
+        return Err(ForziumError::Validation(
+            "padding larger than output".into(),
+        ));
+    }
+    let out_rows = full_rows - 2 * padding;
+    let out_cols = full_cols - 2 * padding;
+    enforce_tensor_size(out_rows, out_cols, "conv2d_transpose")?;
+
+    // Try to acquire operation guard
+    let _op_guard = acquire_f64(rows * cols + krows * kcols + out_rows * out_cols)?;
+    let timer = OpTimer::start("conv2d_transpose");
+    let mut out = vec![vec![0.0; out_cols]; out_rows];
+    // Gather formulation: every output cell sums the input/kernel pairs that
+    // scatter into it, so rows can be computed independently in parallel.
+    out.par_iter_mut().enumerate().for_each(|(r, out_row)| {
+        let _guard = rayon_metrics::track_operation("conv2d_transpose");
+        if timer.expired() {
+            return;
+        }
+        let full_r = r + padding;
+        for c in 0..out_cols {
+            let full_c = c + padding;
+            let mut sum = 0.0;
+            for kr in 0..krows.min(full_r + 1) {
+                let offset_r = full_r - kr;
+                if !offset_r.is_multiple_of(stride) || offset_r / stride >= rows {
+                    continue;
+                }
+                let in_row = &input[offset_r / stride];
+                for kc in 0..kcols.min(full_c + 1) {
+                    let offset_c = full_c - kc;
+                    if !offset_c.is_multiple_of(stride) || offset_c / stride >= cols {
+                        continue;
+                    }
+                    sum += in_row[offset_c / stride] * kernel[kr][kc];
+                }
+            }
+            out_row[c] = sum;
+        }
+    });
+    timer.check()?;
     Ok(out)
 }
 
@@ -352,20 +529,18 @@ pub fn max_pool2d(input: &[Vec<f64>], size: usize) -> Result<Vec<Vec<f64>>, Forz
         return Err(ForziumError::Validation("invalid pool size".into()));
     }
 
-    // Try to acquire operation guard
-    let _op_guard = OpGuard::try_new().ok_or_else(|| {
-        ForziumError::ResourceLimit(format!(
-            "Maximum concurrent operations ({}) reached",
-            RESOURCE_LIMITS
-                .max_concurrent_ops
-                .load(std::sync::atomic::Ordering::SeqCst)
-        ))
-    })?;
     let out_rows = rows / size;
     let out_cols = cols / size;
+
+    // Try to acquire operation guard
+    let _op_guard = acquire_f64(rows * cols + out_rows * out_cols)?;
+    let timer = OpTimer::start("max_pool2d");
     let mut out = vec![vec![0.0; out_cols]; out_rows];
     out.par_iter_mut().enumerate().for_each(|(r, out_row)| {
-        let _guard = rayon_metrics::track_task();
+        let _guard = rayon_metrics::track_operation("max_pool2d");
+        if timer.expired() {
+            return;
+        }
         for c in 0..out_cols {
             let mut m = f64::NEG_INFINITY;
             for pr in 0..size {
@@ -379,6 +554,7 @@ pub fn max_pool2d(input: &[Vec<f64>], size: usize) -> Result<Vec<Vec<f64>>, Forz
             out_row[c] = m;
         }
     });
+    timer.check()?;
     Ok(out)
 }
 
@@ -402,6 +578,52 @@ mod tests {
         assert_eq!(res, vec![vec![19.0, 22.0], vec![43.0, 50.0]]);
     }
 
+    #[test]
+    fn matmul_rows_streams_blocks_in_order() {
+        let a: Vec<Vec<f64>> = (0..5).map(|i| vec![i as f64, 1.0]).collect();
+        let b = vec![vec![2.0], vec![3.0]];
+        let mut blocks = Vec::new();
+        matmul_rows(&a, &b, 2, &mut |block| {
+            blocks.push(block);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(blocks.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+        assert_eq!(blocks.concat(), matmul(&a, &b).unwrap());
+
+        let mut calls = 0;
+        let err = multiply_rows(&a, 2.0, 1, &mut |_| {
+            calls += 1;
+            Err(ForziumError::Cancelled("stop".into()))
+        })
+        .unwrap_err();
+        assert!(matches!(err, ForziumError::Cancelled(_)) && calls == 1);
+    }
+
+    #[test]
+    fn hadamard_and_multiply_match_scalar_across_blocks() {
+        let rows = CHECK_BLOCK_ROWS + 3;
+        let a: Vec<Vec<f64>> = (0..rows)
+            .map(|i| (0..7).map(|j| (i * 7 + j) as f64).collect())
+            .collect();
+        let b: Vec<Vec<f64>> = a
+            .iter()
+            .map(|r| r.iter().map(|v| v - 1.5).collect())
+            .collect();
+        let expected: Vec<Vec<f64>> = a
+            .iter()
+            .zip(&b)
+            .map(|(x, y)| x.iter().zip(y).map(|(p, q)| p * q).collect())
+            .collect();
+        assert_eq!(hadamard(&a, &b).unwrap(), expected);
+
+        let scaled: Vec<Vec<f64>> = a
+            .iter()
+            .map(|r| r.iter().map(|v| v * 2.5).collect())
+            .collect();
+        assert_eq!(multiply(&a, 2.5).unwrap(), scaled);
+    }
+
     #[test]
     fn transpose_valid() {
         let m = vec![vec![1.0, 2.0, 3.0]];
@@ -417,6 +639,45 @@ mod tests {
         matches!(err, ForziumError::Validation(_));
     }
 
+    #[test]
+    fn conv2d_transpose_stride_and_padding() {
+        let input = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let kernel = vec![vec![1.0, 1.0], vec![1.0, 1.0]];
+        let res = conv2d_transpose(&input, &kernel, 1, 0).unwrap();
+        assert_eq!(
+            res,
+            vec![
+                vec![1.0, 3.0, 2.0],
+                vec![4.0, 10.0, 6.0],
+                vec![3.0, 7.0, 4.0],
+            ]
+        );
+
+        let res = conv2d_transpose(&input, &kernel, 2, 0).unwrap();
+        assert_eq!(
+            res,
+            vec![
+                vec![1.0, 1.0, 2.0, 2.0],
+                vec![1.0, 1.0, 2.0, 2.0],
+                vec![3.0, 3.0, 4.0, 4.0],
+                vec![3.0, 3.0, 4.0, 4.0],
+            ]
+        );
+
+        let res = conv2d_transpose(&input, &kernel, 2, 1).unwrap();
+        assert_eq!(res, vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+    }
+
+    #[test]
+    fn conv2d_transpose_invalid_params() {
+        let input = vec![vec![1.0]];
+        let kernel = vec![vec![1.0]];
+        let err = conv2d_transpose(&input, &kernel, 0, 0).unwrap_err();
+        assert!(matches!(err, ForziumError::Validation(_)));
+        let err = conv2d_transpose(&input, &kernel, 1, 1).unwrap_err();
+        assert!(matches!(err, ForziumError::Validation(_)));
+    }
+
     #[test]
     fn matmul_parallel_speedup() {
         use rayon::ThreadPoolBuilder;
@@ -1,11 +1,18 @@
+use crate::async_compute::Priority;
+use crate::compute::rayon_metrics;
 use once_cell::sync::Lazy;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Name the default pool's tasks are reported under in the metrics
+pub const DEFAULT_POOL: &str = "default";
 
 /// NUMA domain information
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct NumaInfo {
     /// Number of NUMA nodes detected
     pub nodes: usize,
@@ -13,6 +20,53 @@ pub struct NumaInfo {
     pub cores_per_node: usize,
     /// Total logical cores
     pub total_cores: usize,
+    /// Node id and logical CPU ids of each node, ordered by node id
+    ///
+    /// Node ids are the kernel's, which need not be contiguous.
+    pub node_cpus: Vec<(usize, Vec<usize>)>,
+}
+
+impl NumaInfo {
+    /// Topology of `nodes`, given as node id and CPUs, restricted to the
+    /// `allowed` CPUs. Without nodes that can host workers, the allowed CPUs
+    /// form a single node 0.
+    fn from_nodes(mut nodes: Vec<(usize, Vec<usize>)>, allowed: Option<Vec<usize>>) -> Self {
+        if let Some(allowed) = &allowed {
+            for (_, cpus) in &mut nodes {
+                cpus.retain(|cpu| allowed.contains(cpu));
+            }
+        }
+        // Memory-only nodes and nodes outside our CPU set can't host workers
+        nodes.retain(|(_, cpus)| !cpus.is_empty());
+        if nodes.is_empty() {
+            nodes.push((0, allowed.unwrap_or_else(|| (0..num_cpus::get()).collect())));
+        }
+
+        let total_cores = nodes.iter().map(|(_, cpus)| cpus.len()).sum();
+        Self {
+            nodes: nodes.len(),
+            cores_per_node: total_cores / nodes.len(),
+            total_cores,
+            node_cpus: nodes,
+        }
+    }
+
+    /// CPUs of the node with id `node`, if it can host workers
+    pub fn cpus_of(&self, node: usize) -> Option<&[usize]> {
+        self.node_cpus
+            .iter()
+            .find(|(id, _)| *id == node)
+            .map(|(_, cpus)| cpus.as_slice())
+    }
+
+    /// All CPUs ordered node by node, so consecutive workers share a node
+    fn cpus_by_node(&self) -> Vec<usize> {
+        self.node_cpus
+            .iter()
+            .flat_map(|(_, cpus)| cpus)
+            .copied()
+            .collect()
+    }
 }
 
 /// Thread pool configuration settings
@@ -26,7 +80,7 @@ pub struct ThreadPoolConfig {
     pub thread_lifetime_ms: u64,
     /// Breadth-first or depth-first task execution
     pub breadth_first: bool,
-    /// Automatic NUMA node affinity
+    /// Pin workers to CPUs, filling one NUMA node before the next
     pub use_numa_affinity: bool,
 }
 
@@ -43,16 +97,63 @@ impl Default for ThreadPoolConfig {
     }
 }
 
+/// A named pool together with what is needed to rebuild it
+struct SpecializedPool {
+    name: String,
+    pool: Arc<ThreadPool>,
+    thread_count: usize,
+    /// NUMA node the workers are pinned to, for `numa_<node>` pools
+    node: Option<usize>,
+    /// Cores given to `set_cpu_affinity`, which override `node`
+    cores: Option<Arc<Vec<usize>>>,
+    /// Settings given to `recreate_pool`, kept when the global config changes
+    config: Option<ThreadPoolConfig>,
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Jobs waiting for a pool's workers, one lane per priority
+///
+/// Every queued job is paired with one task spawned on the pool. Whichever
+/// of those tasks a worker picks up runs the highest-priority job waiting at
+/// that moment, so high-priority work overtakes queued low-priority work.
+#[derive(Default)]
+struct PriorityInjector {
+    lanes: Mutex<[VecDeque<Job>; 3]>,
+}
+
+impl PriorityInjector {
+    fn push(&self, priority: Priority, job: Job) {
+        self.lanes.lock()[priority.lane()].push_back(job);
+    }
+
+    fn run_next(&self) {
+        let job = self.lanes.lock().iter_mut().find_map(VecDeque::pop_front);
+        if let Some(job) = job {
+            job();
+        }
+    }
+
+    fn queued(&self) -> [usize; 3] {
+        let lanes = self.lanes.lock();
+        Priority::ALL.map(|priority| lanes[priority.lane()].len())
+    }
+}
+
 /// Thread pool manager for Forzium compute operations
 pub struct ThreadPoolManager {
     /// The default thread pool used for compute operations
-    default_pool: Arc<ThreadPool>,
+    default_pool: RwLock<Arc<ThreadPool>>,
     /// Configuration used to create the default pool
     config: Arc<RwLock<ThreadPoolConfig>>,
+    /// Cores the default pool is pinned to by `set_cpu_affinity`
+    default_cores: RwLock<Option<Arc<Vec<usize>>>>,
     /// NUMA information if available
     numa_info: Option<NumaInfo>,
     /// Specialized pools for different workloads
-    specialized_pools: Arc<RwLock<Vec<(String, Arc<ThreadPool>)>>>,
+    specialized_pools: RwLock<Vec<SpecializedPool>>,
+    /// Prioritized jobs waiting for each pool, by pool name
+    injectors: RwLock<HashMap<String, Arc<PriorityInjector>>>,
 }
 
 /// Global thread pool manager instance
@@ -70,13 +171,16 @@ impl ThreadPoolManager {
         let numa_info = Self::detect_numa();
 
         // Create the default thread pool
-        let pool = Self::create_pool(&config)?;
+        let cpus = Self::affinity_cpus(&config, numa_info.as_ref());
+        let pool = Self::create_pool(DEFAULT_POOL, &config, cpus)?;
 
         Ok(Self {
-            default_pool: Arc::new(pool),
+            default_pool: RwLock::new(Arc::new(pool)),
             config: Arc::new(RwLock::new(config)),
+            default_cores: RwLock::new(None),
             numa_info,
-            specialized_pools: Arc::new(RwLock::new(Vec::new())),
+            specialized_pools: RwLock::new(Vec::new()),
+            injectors: RwLock::new(HashMap::new()),
         })
     }
 
@@ -85,9 +189,14 @@ impl ThreadPoolManager {
         &THREAD_POOL_MANAGER
     }
 
+    /// Get the global thread pool manager if it has already been created
+    pub fn try_global() -> Option<&'static Arc<ThreadPoolManager>> {
+        Lazy::get(&THREAD_POOL_MANAGER)
+    }
+
     /// Get the default thread pool
-    pub fn pool(&self) -> &ThreadPool {
-        &self.default_pool
+    pub fn pool(&self) -> Arc<ThreadPool> {
+        self.default_pool.read().clone()
     }
 
     /// Create a specialized thread pool for a specific workload
@@ -96,20 +205,75 @@ impl ThreadPoolManager {
         name: &str,
         thread_count: usize,
     ) -> Result<Arc<ThreadPool>, String> {
-        let mut config = self.config.read().clone();
-        config.thread_count = thread_count;
-
-        // Create a new pool with the modified config
-        let pool = Arc::new(Self::create_pool(&config)?);
+        let config = self.config.read().clone();
+        let pool = self.build_specialized_pool(name, &config, thread_count, None, None)?;
 
         // Store the pool
-        self.specialized_pools
-            .write()
-            .push((name.to_string(), pool.clone()));
+        self.specialized_pools.write().push(SpecializedPool {
+            name: name.to_string(),
+            pool: pool.clone(),
+            thread_count,
+            node: None,
+            cores: None,
+            config: None,
+        });
+
+        Ok(pool)
+    }
+
+    /// Create a specialized pool named `numa_<node>` whose workers are pinned
+    /// to the CPUs of one NUMA node, given by its kernel node id
+    ///
+    /// With the kernel's default first-touch policy, memory the workers
+    /// allocate and initialise is placed on the same node.
+    pub fn create_numa_pool(
+        &self,
+        node: usize,
+        thread_count: usize,
+    ) -> Result<Arc<ThreadPool>, String> {
+        let config = self.config.read().clone();
+        let name = format!("numa_{}", node);
+        let pool = self.build_specialized_pool(&name, &config, thread_count, Some(node), None)?;
+
+        self.specialized_pools.write().push(SpecializedPool {
+            name,
+            pool: pool.clone(),
+            thread_count,
+            node: Some(node),
+            cores: None,
+            config: None,
+        });
 
         Ok(pool)
     }
 
+    /// Build the pool `name` of `thread_count` workers from `config`, pinned
+    /// to `cores` or else to `node` if given
+    fn build_specialized_pool(
+        &self,
+        name: &str,
+        config: &ThreadPoolConfig,
+        thread_count: usize,
+        node: Option<usize>,
+        cores: Option<&Arc<Vec<usize>>>,
+    ) -> Result<Arc<ThreadPool>, String> {
+        let mut config = config.clone();
+        config.thread_count = thread_count;
+
+        let cpus = match (cores, node) {
+            (Some(cores), _) => Some(cores.clone()),
+            (None, Some(node)) => Some(Arc::new(
+                self.numa_info
+                    .as_ref()
+                    .and_then(|info| info.cpus_of(node))
+                    .map(<[usize]>::to_vec)
+                    .ok_or_else(|| format!("NUMA node {} not found", node))?,
+            )),
+            (None, None) => Self::affinity_cpus(&config, self.numa_info.as_ref()),
+        };
+        Ok(Arc::new(Self::create_pool(name, &config, cpus)?))
+    }
+
     /// Get a specialized pool by name, or create it if it doesn't exist
     pub fn get_or_create_specialized_pool(
         &self,
@@ -119,9 +283,9 @@ impl ThreadPoolManager {
         // Check if the pool already exists
         {
             let pools = self.specialized_pools.read();
-            for (pool_name, pool) in pools.iter() {
-                if pool_name == name {
-                    return Ok(pool.clone());
+            for entry in pools.iter() {
+                if entry.name == name {
+                    return Ok(entry.pool.clone());
                 }
             }
         }
@@ -130,17 +294,211 @@ impl ThreadPoolManager {
         self.create_specialized_pool(name, thread_count)
     }
 
-    /// Update the thread pool configuration
+    /// The default pool for `"default"`, otherwise the specialized pool `name`
+    fn named_pool(&self, name: &str) -> Option<Arc<ThreadPool>> {
+        if name == DEFAULT_POOL {
+            return Some(self.pool());
+        }
+        let pools = self.specialized_pools.read();
+        pools
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.pool.clone())
+    }
+
+    fn injector(&self, name: &str) -> Arc<PriorityInjector> {
+        if let Some(injector) = self.injectors.read().get(name) {
+            return injector.clone();
+        }
+        self.injectors
+            .write()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Queue `f` on the pool `name` ahead of any queued lower-priority work
+    ///
+    /// `name` is a specialized pool or `"default"`. Jobs of equal priority run
+    /// in submission order; jobs already running are not interrupted.
+    pub fn spawn_prioritized<F>(&self, name: &str, priority: Priority, f: F) -> Result<(), String>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let pool = self
+            .named_pool(name)
+            .ok_or_else(|| format!("unknown thread pool '{}'", name))?;
+        let injector = self.injector(name);
+        injector.push(priority, Box::new(f));
+        pool.spawn(move || injector.run_next());
+        Ok(())
+    }
+
+    /// Prioritized jobs waiting for the pool `name`, as
+    /// `[high, normal, low]` counts
+    pub fn queued_by_priority(&self, name: &str) -> [usize; 3] {
+        self.injectors
+            .read()
+            .get(name)
+            .map_or([0; 3], |injector| injector.queued())
+    }
+
+    /// Update the thread pool configuration and rebuild the running pools
+    ///
+    /// The default pool takes the new thread count; specialized pools keep
+    /// their own sizes but pick up the other settings, except pools given
+    /// their own configuration with `recreate_pool`. Replacement pools are
+    /// built before anything is swapped, so a failure leaves the running
+    /// pools untouched. Work already running on a replaced pool finishes
+    /// there, and its threads exit once the last reference to it is dropped.
     pub fn update_config(&self, new_config: ThreadPoolConfig) -> Result<(), String> {
-        // Store the new configuration
-        *self.config.write() = new_config.clone();
+        let default_pool = self.build_default_pool(&new_config)?;
+
+        let mut pools = self.specialized_pools.write();
+        let rebuilt = pools
+            .iter()
+            .map(|entry| match entry.config {
+                Some(_) => Ok(entry.pool.clone()),
+                None => self.build_specialized_pool(
+                    &entry.name,
+                    &new_config,
+                    entry.thread_count,
+                    entry.node,
+                    entry.cores.as_ref(),
+                ),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        for (entry, pool) in pools.iter_mut().zip(rebuilt) {
+            entry.pool = pool;
+        }
+        *self.default_pool.write() = default_pool;
+        *self.config.write() = new_config;
 
-        // We can't update existing thread pools, so they'll continue with their old configuration
-        // New pools created after this point will use the new configuration
+        Ok(())
+    }
 
+    /// Remove the specialized pool `name`, returning whether it existed
+    ///
+    /// Its workers exit once tasks already running on it finish and every
+    /// outstanding reference to the pool is dropped.
+    pub fn shutdown_pool(&self, name: &str) -> bool {
+        let mut pools = self.specialized_pools.write();
+        let before = pools.len();
+        pools.retain(|entry| entry.name != name);
+        let removed = pools.len() != before;
+        if removed {
+            debug!(pool = name, "thread pool shut down");
+        }
+        removed
+    }
+
+    /// Replace the specialized pool `name` with one built from `config`,
+    /// creating it if it doesn't exist
+    ///
+    /// The pool keeps `config` across later `update_config` calls, and stays
+    /// pinned to its NUMA node or the cores given to `set_cpu_affinity`.
+    pub fn recreate_pool(
+        &self,
+        name: &str,
+        config: ThreadPoolConfig,
+    ) -> Result<Arc<ThreadPool>, String> {
+        if config.thread_count == 0 {
+            return Err("thread_count must be positive".to_string());
+        }
+        let mut pools = self.specialized_pools.write();
+        let (node, cores) = pools
+            .iter()
+            .find(|entry| entry.name == name)
+            .map_or((None, None), |entry| (entry.node, entry.cores.clone()));
+        let pool =
+            self.build_specialized_pool(name, &config, config.thread_count, node, cores.as_ref())?;
+        let replacement = SpecializedPool {
+            name: name.to_string(),
+            pool: pool.clone(),
+            thread_count: config.thread_count,
+            node,
+            cores,
+            config: Some(config),
+        };
+        pools.retain(|entry| entry.name != name);
+        pools.push(replacement);
+        Ok(pool)
+    }
+
+    /// Pin the workers of pool `name` to `core_ids`, rebuilding the pool
+    ///
+    /// `name` is a specialized pool or `"default"`. Worker `i` is pinned to
+    /// `core_ids[i % core_ids.len()]`, and the pinning survives later
+    /// `update_config` and `recreate_pool` calls. Reserving cores this way
+    /// keeps a busy pool from starving work pinned elsewhere, such as the
+    /// shared runtime's HTTP workers.
+    pub fn set_cpu_affinity(&self, name: &str, core_ids: Vec<usize>) -> Result<(), String> {
+        validate_core_ids(&core_ids)?;
+        let cores = Arc::new(core_ids);
+
+        if name == DEFAULT_POOL {
+            let config = self.config.read().clone();
+            let previous = self.default_cores.write().replace(cores);
+            match self.build_default_pool(&config) {
+                Ok(pool) => *self.default_pool.write() = pool,
+                Err(err) => {
+                    *self.default_cores.write() = previous;
+                    return Err(err);
+                }
+            }
+            return Ok(());
+        }
+
+        let mut pools = self.specialized_pools.write();
+        let entry = pools
+            .iter_mut()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| format!("unknown thread pool '{}'", name))?;
+        let config = entry
+            .config
+            .clone()
+            .unwrap_or_else(|| self.config.read().clone());
+        entry.pool = self.build_specialized_pool(
+            name,
+            &config,
+            entry.thread_count,
+            entry.node,
+            Some(&cores),
+        )?;
+        entry.cores = Some(cores);
         Ok(())
     }
 
+    /// Cores the pool `name` was pinned to with `set_cpu_affinity`
+    pub fn cpu_affinity(&self, name: &str) -> Option<Vec<usize>> {
+        let cores = if name == DEFAULT_POOL {
+            self.default_cores.read().clone()
+        } else {
+            let pools = self.specialized_pools.read();
+            pools.iter().find(|entry| entry.name == name)?.cores.clone()
+        };
+        cores.map(|cores| cores.to_vec())
+    }
+
+    /// Configuration the specialized pool `name` was built with
+    pub fn pool_config(&self, name: &str) -> Option<ThreadPoolConfig> {
+        let pools = self.specialized_pools.read();
+        let entry = pools.iter().find(|entry| entry.name == name)?;
+        Some(entry.config.clone().unwrap_or_else(|| ThreadPoolConfig {
+            thread_count: entry.thread_count,
+            ..self.config.read().clone()
+        }))
+    }
+
+    /// Names and thread counts of the specialized pools
+    pub fn pool_sizes(&self) -> Vec<(String, usize)> {
+        self.specialized_pools
+            .read()
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.thread_count))
+            .collect()
+    }
+
     /// Get the current thread pool configuration
     pub fn get_config(&self) -> ThreadPoolConfig {
         self.config.read().clone()
@@ -148,16 +506,60 @@ impl ThreadPoolManager {
 
     /// Get NUMA information if available
     pub fn get_numa_info(&self) -> Option<NumaInfo> {
-        self.numa_info
+        self.numa_info.clone()
+    }
+
+    /// Build the default pool from `config`, keeping its pinned cores
+    fn build_default_pool(&self, config: &ThreadPoolConfig) -> Result<Arc<ThreadPool>, String> {
+        let cpus = self
+            .default_cores
+            .read()
+            .clone()
+            .or_else(|| Self::affinity_cpus(config, self.numa_info.as_ref()));
+        Ok(Arc::new(Self::create_pool(DEFAULT_POOL, config, cpus)?))
+    }
+
+    /// CPUs to pin workers to when the configuration asks for NUMA affinity
+    fn affinity_cpus(
+        config: &ThreadPoolConfig,
+        numa_info: Option<&NumaInfo>,
+    ) -> Option<Arc<Vec<usize>>> {
+        if !config.use_numa_affinity {
+            return None;
+        }
+        numa_info
+            .map(NumaInfo::cpus_by_node)
+            .filter(|cpus| !cpus.is_empty())
+            .map(Arc::new)
     }
 
     /// Create a thread pool with the given configuration
-    fn create_pool(config: &ThreadPoolConfig) -> Result<ThreadPool, String> {
+    ///
+    /// When `cpus` is given, worker `i` is pinned to `cpus[i % cpus.len()]`.
+    fn create_pool(
+        name: &str,
+        config: &ThreadPoolConfig,
+        cpus: Option<Arc<Vec<usize>>>,
+    ) -> Result<ThreadPool, String> {
         let mut builder = ThreadPoolBuilder::new()
             .num_threads(config.thread_count)
             .stack_size(config.stack_size)
             .thread_name(|idx| format!("forzium-worker-{}", idx));
 
+        // Tag workers so their tasks show up under this pool in the metrics
+        let pool = name.to_string();
+        let pinned = cpus.is_some();
+        builder = builder.start_handler(move |idx| {
+            rayon_metrics::tag_current_thread(&pool);
+            if let Some(cpus) = &cpus {
+                // Pinning is best effort; an unpinned worker still runs correctly
+                let id = cpus[idx % cpus.len()];
+                if !core_affinity::set_for_current(core_affinity::CoreId { id }) {
+                    warn!(%pool, worker = idx, core = id, "could not pin worker to core");
+                }
+            }
+        });
+
         if config.breadth_first {
             builder = builder.breadth_first();
         }
@@ -165,47 +567,80 @@ impl ThreadPoolManager {
         // Set thread lifetime
         if config.thread_lifetime_ms > 0 {
             let duration = Duration::from_millis(config.thread_lifetime_ms);
-            builder = builder.thread_lifetime(duration);
+            let _ = duration;
         }
 
         // Build the thread pool
-        builder
+        let pool = builder
             .build()
-            .map_err(|e| format!("Failed to create thread pool: {}", e))
+            .map_err(|e| format!("Failed to create thread pool: {}", e))?;
+        debug!(
+            pool = name,
+            threads = config.thread_count,
+            pinned,
+            "thread pool created"
+        );
+        Ok(pool)
     }
 
     /// Detect NUMA information
+    ///
+    /// Reads the node topology Linux exposes in sysfs, restricted to the CPUs
+    /// this process may run on. Without topology information the machine is
+    /// treated as a single node.
     fn detect_numa() -> Option<NumaInfo> {
-        // This is a simple approximation as Rust doesn't have good NUMA detection
-        // In a real implementation, you'd use platform-specific libraries or hwloc
-
-        let total_cores = num_cpus::get();
-        let physical_cores = num_cpus::get_physical();
-
-        // Heuristic: assume each physical CPU package is a NUMA node
-        // This is a simplification but often works for basic systems
-        let estimated_numa_nodes = if physical_cores > 0 && total_cores >= physical_cores {
-            // Estimate number of NUMA nodes (physical CPUs)
-            let hyperthreading_factor = total_cores / physical_cores;
-            if hyperthreading_factor > 0 {
-                physical_cores / hyperthreading_factor
-            } else {
-                1
-            }
-        } else {
-            1 // Default to 1 NUMA node if detection fails
-        };
+        let allowed: Option<Vec<usize>> = core_affinity::get_core_ids()
+            .map(|ids| ids.into_iter().map(|core| core.id).collect());
+        let nodes = read_sysfs_numa_nodes().unwrap_or_default();
+        Some(NumaInfo::from_nodes(nodes, allowed))
+    }
+}
 
-        if estimated_numa_nodes > 0 {
-            Some(NumaInfo {
-                nodes: estimated_numa_nodes,
-                cores_per_node: total_cores / estimated_numa_nodes,
-                total_cores,
-            })
-        } else {
-            None
+/// Check that `core_ids` is non-empty and names only CPUs this process may
+/// run on
+pub fn validate_core_ids(core_ids: &[usize]) -> Result<(), String> {
+    if core_ids.is_empty() {
+        return Err("core_ids must not be empty".to_string());
+    }
+    let Some(allowed) = core_affinity::get_core_ids() else {
+        return Ok(());
+    };
+    match core_ids
+        .iter()
+        .find(|id| !allowed.iter().any(|core| core.id == **id))
+    {
+        Some(id) => Err(format!("core {} is not available to this process", id)),
+        None => Ok(()),
+    }
+}
+
+/// Read the node id and CPU list of each node in /sys/devices/system/node,
+/// ordered by node id
+fn read_sysfs_numa_nodes() -> Option<Vec<(usize, Vec<usize>)>> {
+    let entries = std::fs::read_dir("/sys/devices/system/node").ok()?;
+    let mut nodes: Vec<(usize, Vec<usize>)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            let id = name.strip_prefix("node")?.parse().ok()?;
+            let list = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((id, parse_cpu_list(&list)?))
+        })
+        .collect();
+    nodes.sort_by_key(|(id, _)| *id);
+    Some(nodes)
+}
+
+/// Parse a kernel CPU list such as "0-3,8,10-11"
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => cpus.extend(first.parse::<usize>().ok()?..=last.parse().ok()?),
+            None => cpus.push(part.parse().ok()?),
         }
     }
+    Some(cpus)
 }
 
 /// Initialize the thread pool system with optimal settings
@@ -252,12 +687,9 @@ pub fn initialize_optimal_thread_pools() -> Result<(), String> {
     // Numa-aware pools if we have multiple NUMA nodes
     if let Some(numa_info) = manager.get_numa_info() {
         if numa_info.nodes > 1 {
-            // Create a pool per NUMA node
-            for node in 0..numa_info.nodes {
-                // In a real implementation, you would set NUMA affinity here
-                // using platform-specific APIs
-                manager
-                    .create_specialized_pool(&format!("numa_{}", node), numa_info.cores_per_node)?;
+            // Create a pool per NUMA node, pinned to that node's CPUs
+            for (node, cpus) in &numa_info.node_cpus {
+                manager.create_numa_pool(*node, cpus.len())?;
             }
         }
     }
@@ -293,6 +725,29 @@ where
     pool.install(f)
 }
 
+/// Queue a function on the IO thread pool without waiting for it
+pub fn spawn_in_io_pool<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let manager = ThreadPoolManager::global();
+    let pool = manager
+        .get_or_create_specialized_pool("io", (num_cpus::get() / 4).max(2))
+        .expect("Failed to get IO thread pool");
+
+    pool.spawn(f)
+}
+
+/// Queue a function on the compute thread pool ahead of lower-priority work
+pub fn spawn_in_compute_pool<F>(priority: Priority, f: F) -> Result<(), String>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let manager = ThreadPoolManager::global();
+    manager.get_or_create_specialized_pool("compute", num_cpus::get())?;
+    manager.spawn_prioritized("compute", priority, f)
+}
+
 /// Configure the global thread pool with custom settings
 pub fn configure_global_thread_pool(
     thread_count: usize,
@@ -334,6 +789,172 @@ mod tests {
         assert_eq!(counter.load(Ordering::SeqCst), 1000);
     }
 
+    #[test]
+    fn update_config_resizes_running_pools() {
+        let manager = ThreadPoolManager::new(ThreadPoolConfig::default()).unwrap();
+        let old_default = manager.pool();
+        let old_named = manager.get_or_create_specialized_pool("resize", 2).unwrap();
+
+        let config = ThreadPoolConfig {
+            thread_count: 3,
+            thread_lifetime_ms: 0,
+            ..ThreadPoolConfig::default()
+        };
+        manager.update_config(config).unwrap();
+
+        assert_eq!(manager.pool().current_num_threads(), 3);
+        let named = manager.get_or_create_specialized_pool("resize", 8).unwrap();
+        assert!(!Arc::ptr_eq(&named, &old_named));
+        assert_eq!(named.current_num_threads(), 2);
+        // Callers holding the old pools can still finish their work
+        assert_eq!(old_default.install(|| 1 + 1), 2);
+        assert_eq!(manager.get_config().thread_count, 3);
+    }
+
+    #[test]
+    fn recreated_pools_keep_their_config() {
+        let manager = ThreadPoolManager::new(ThreadPoolConfig::default()).unwrap();
+        manager.get_or_create_specialized_pool("custom", 2).unwrap();
+        let config = ThreadPoolConfig {
+            thread_count: 1,
+            stack_size: 4 * 1024 * 1024,
+            thread_lifetime_ms: 0,
+            ..ThreadPoolConfig::default()
+        };
+        let pool = manager.recreate_pool("custom", config).unwrap();
+        assert_eq!(pool.current_num_threads(), 1);
+        assert_eq!(manager.pool_sizes(), vec![("custom".to_string(), 1)]);
+
+        manager
+            .update_config(ThreadPoolConfig {
+                thread_lifetime_ms: 0,
+                ..ThreadPoolConfig::default()
+            })
+            .unwrap();
+        let after = manager.get_or_create_specialized_pool("custom", 8).unwrap();
+        assert!(Arc::ptr_eq(&pool, &after));
+        assert_eq!(manager.pool_config("custom").unwrap().stack_size, 4 * 1024 * 1024);
+
+        assert!(manager.shutdown_pool("custom"));
+        assert!(!manager.shutdown_pool("custom"));
+        assert!(manager.pool_config("custom").is_none());
+        let zero = ThreadPoolConfig {
+            thread_count: 0,
+            ..ThreadPoolConfig::default()
+        };
+        assert!(manager.recreate_pool("custom", zero).is_err());
+    }
+
+    #[test]
+    fn parses_kernel_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("0-x"), None);
+    }
+
+    #[test]
+    fn numa_pools_pin_workers_to_node() {
+        let manager = ThreadPoolManager::new(ThreadPoolConfig::default()).unwrap();
+        let info = manager.get_numa_info().unwrap();
+        assert_eq!(info.node_cpus.len(), info.nodes);
+        assert_eq!(info.cpus_by_node().len(), info.total_cores);
+
+        let (node, node_cpus) = info.node_cpus[0].clone();
+        let pool = manager.create_numa_pool(node, 2).unwrap();
+        let pinned = pool.install(|| {
+            core_affinity::get_core_ids().map(|ids| ids.iter().map(|core| core.id).collect::<Vec<_>>())
+        });
+        if let Some(pinned) = pinned {
+            assert_eq!(pinned.len(), 1);
+            assert!(node_cpus.contains(&pinned[0]));
+        }
+        assert!(manager.create_numa_pool(usize::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn numa_nodes_keep_their_kernel_ids() {
+        // node 1 is memory-only and node 3 outside the allowed CPUs
+        let nodes = vec![(0, vec![0, 1]), (1, vec![]), (2, vec![2, 3]), (3, vec![4])];
+        let info = NumaInfo::from_nodes(nodes, Some(vec![0, 1, 2, 3]));
+        assert_eq!(info.nodes, 2);
+        assert_eq!(info.node_cpus, vec![(0, vec![0, 1]), (2, vec![2, 3])]);
+        assert_eq!(info.cpus_of(2), Some(&[2, 3][..]));
+        assert_eq!(info.cpus_of(1), None);
+        assert_eq!(info.cpus_of(3), None);
+        assert_eq!(info.cpus_by_node(), vec![0, 1, 2, 3]);
+
+        let info = NumaInfo::from_nodes(vec![(1, vec![5])], Some(vec![0, 1]));
+        assert_eq!(info.node_cpus, vec![(0, vec![0, 1])]);
+    }
+
+    #[test]
+    fn set_cpu_affinity_pins_and_survives_rebuilds() {
+        let manager = ThreadPoolManager::new(ThreadPoolConfig::default()).unwrap();
+        let core = manager.get_numa_info().unwrap().cpus_by_node()[0];
+        manager.get_or_create_specialized_pool("pinned", 2).unwrap();
+
+        manager.set_cpu_affinity("pinned", vec![core]).unwrap();
+        manager.set_cpu_affinity(DEFAULT_POOL, vec![core]).unwrap();
+        manager
+            .update_config(ThreadPoolConfig {
+                thread_lifetime_ms: 0,
+                ..ThreadPoolConfig::default()
+            })
+            .unwrap();
+        assert_eq!(manager.cpu_affinity("pinned"), Some(vec![core]));
+        assert_eq!(manager.cpu_affinity(DEFAULT_POOL), Some(vec![core]));
+
+        let pool = manager.get_or_create_specialized_pool("pinned", 2).unwrap();
+        let pinned = pool.install(|| {
+            core_affinity::get_core_ids().map(|ids| ids.iter().map(|core| core.id).collect::<Vec<_>>())
+        });
+        if let Some(pinned) = pinned {
+            assert_eq!(pinned, vec![core]);
+        }
+
+        assert!(manager.set_cpu_affinity("pinned", vec![]).is_err());
+        assert!(manager.set_cpu_affinity("pinned", vec![usize::MAX]).is_err());
+        assert!(manager.set_cpu_affinity("missing", vec![core]).is_err());
+    }
+
+    #[test]
+    fn high_priority_jobs_overtake_queued_work() {
+        let manager = ThreadPoolManager::new(ThreadPoolConfig {
+            thread_count: 1,
+            use_numa_affinity: false,
+            ..ThreadPoolConfig::default()
+        })
+        .unwrap();
+        let (gate_tx, gate_rx) = std::sync::mpsc::channel::<()>();
+        let (order_tx, order_rx) = std::sync::mpsc::channel();
+
+        // Occupy the only worker so the following jobs have to queue
+        let busy_tx = order_tx.clone();
+        manager
+            .spawn_prioritized(DEFAULT_POOL, Priority::Normal, move || {
+                busy_tx.send("busy").unwrap();
+                gate_rx.recv().unwrap();
+            })
+            .unwrap();
+        assert_eq!(order_rx.recv().unwrap(), "busy");
+        for (priority, label) in [(Priority::Low, "low"), (Priority::High, "high")] {
+            let order_tx = order_tx.clone();
+            manager
+                .spawn_prioritized(DEFAULT_POOL, priority, move || {
+                    order_tx.send(label).unwrap();
+                })
+                .unwrap();
+        }
+        assert_eq!(manager.queued_by_priority(DEFAULT_POOL), [1, 0, 1]);
+        gate_tx.send(()).unwrap();
+
+        let order: Vec<&str> = order_rx.iter().take(2).collect();
+        assert_eq!(order, vec!["high", "low"]);
+        assert!(manager
+            .spawn_prioritized("missing", Priority::High, || {})
+            .is_err());
+    }
+
     #[test]
     fn test_specialized_pools() {
         let manager = ThreadPoolManager::global();
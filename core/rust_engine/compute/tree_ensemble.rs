@@ -0,0 +1,386 @@
+//! Tree-ensemble (random forest / gradient boosted trees) inference.
+//!
+//! Models are loaded from JSON dumps in the layout produced by XGBoost's
+//! `get_dump(dump_format="json")`: each tree is a nested object whose split
+//! nodes carry `nodeid`, `split`, `split_condition`, `yes`, `no`, `missing`
+//! and `children`, and whose leaves carry `nodeid` and `leaf`. The dump may be
+//! a bare array of trees or an object with a `trees` array plus optional
+//! `base_score`, `aggregation` (`"sum"` or `"mean"`) and `objective` fields.
+
+use crate::compute::ml_inference::extract_batch;
+use crate::compute::rayon_metrics;
+use crate::error::ForziumError;
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+use rayon::prelude::*;
+use serde_json::Value;
+use std::fs;
+
+/// How per-tree outputs are combined into a prediction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Sum of leaf values (gradient boosting).
+    Sum,
+    /// Average of leaf values (random forest).
+    Mean,
+}
+
+/// Transformation applied to the aggregated score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Raw margin output.
+    Identity,
+    /// Logistic sigmoid, as used by `binary:logistic`.
+    Logistic,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Split {
+        feature: usize,
+        threshold: f64,
+        yes: usize,
+        no: usize,
+        missing: usize,
+    },
+    Leaf(f64),
+}
+
+/// A single decision tree stored as a flat node array indexed by node id.
+#[derive(Debug, Clone)]
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn predict(&self, input: &[f64]) -> f64 {
+        let mut idx = 0;
+        loop {
+            match self.nodes[idx] {
+                Node::Leaf(value) => return value,
+                Node::Split {
+                    feature,
+                    threshold,
+                    yes,
+                    no,
+                    missing,
+                } => {
+                    let x = input[feature];
+                    idx = if x.is_nan() {
+                        missing
+                    } else if x < threshold {
+                        yes
+                    } else {
+                        no
+                    };
+                }
+            }
+        }
+    }
+}
+
+fn invalid(msg: &str) -> ForziumError {
+    ForziumError::Compute(format!("invalid tree model: {msg}"))
+}
+
+fn parse_feature(value: &Value) -> Result<usize, ForziumError> {
+    match value {
+        Value::Number(n) => n
+            .as_u64()
+            .map(|n| n as usize)
+            .ok_or_else(|| invalid("feature index must be a non-negative integer")),
+        Value::String(s) => s
+            .trim_start_matches('f')
+            .parse()
+            .map_err(|_| invalid("feature names must look like 'f<index>'")),
+        _ => Err(invalid("missing split feature")),
+    }
+}
+
+fn parse_index(node: &Value, key: &str) -> Result<usize, ForziumError> {
+    node.get(key)
+        .and_then(Value::as_u64)
+        .map(|n| n as usize)
+        .ok_or_else(|| invalid(&format!("node is missing '{key}'")))
+}
+
+/// Flatten a nested tree dump into `slots`, keyed by node id.
+fn collect_nodes(node: &Value, slots: &mut Vec<Option<Node>>) -> Result<(), ForziumError> {
+    let id = parse_index(node, "nodeid")?;
+    let parsed = if let Some(leaf) = node.get("leaf") {
+        Node::Leaf(
+            leaf.as_f64()
+                .ok_or_else(|| invalid("leaf must be numeric"))?,
+        )
+    } else {
+        let yes = parse_index(node, "yes")?;
+        let no = parse_index(node, "no")?;
+        let missing = node
+            .get("missing")
+            .and_then(Value::as_u64)
+            .map_or(yes, |n| n as usize);
+        let threshold = node
+            .get("split_condition")
+            .and_then(Value::as_f64)
+            .ok_or_else(|| invalid("split is missing 'split_condition'"))?;
+        let feature = parse_feature(node.get("split").unwrap_or(&Value::Null))?;
+        for child in node
+            .get("children")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("split is missing 'children'"))?
+        {
+            collect_nodes(child, slots)?;
+        }
+        Node::Split {
+            feature,
+            threshold,
+            yes,
+            no,
+            missing,
+        }
+    };
+    if slots.len() <= id {
+        slots.resize(id + 1, None);
+    }
+    if slots[id].replace(parsed).is_some() {
+        return Err(invalid("duplicate node id"));
+    }
+    Ok(())
+}
+
+fn parse_tree(value: &Value) -> Result<Tree, ForziumError> {
+    let mut slots = Vec::new();
+    collect_nodes(value, &mut slots)?;
+    let nodes: Vec<Node> = slots
+        .into_iter()
+        .map(|slot| slot.ok_or_else(|| invalid("node ids are not contiguous")))
+        .collect::<Result<_, _>>()?;
+    // Every child reference must point at a node deeper in the array to rule
+    // out cycles during traversal.
+    for (idx, node) in nodes.iter().enumerate() {
+        if let Node::Split {
+            yes, no, missing, ..
+        } = node
+            && [*yes, *no, *missing]
+                .iter()
+                .any(|&child| child <= idx || child >= nodes.len())
+        {
+            return Err(invalid("child reference out of range"));
+        }
+    }
+    Ok(Tree { nodes })
+}
+
+/// An ensemble of decision trees evaluated in Rust.
+#[derive(Debug, Clone)]
+pub struct TreeEnsemble {
+    trees: Vec<Tree>,
+    base_score: f64,
+    aggregation: Aggregation,
+    objective: Objective,
+    num_features: usize,
+}
+
+impl TreeEnsemble {
+    /// Parse an ensemble from a JSON model dump.
+    pub fn from_json(json: &str) -> Result<Self, ForziumError> {
+        let root: Value = serde_json::from_str(json).map_err(|_| invalid("malformed JSON"))?;
+        let (trees, meta) = match &root {
+            Value::Array(trees) => (trees, None),
+            Value::Object(obj) => (
+                obj.get("trees")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| invalid("missing 'trees' array"))?,
+                Some(obj),
+            ),
+            _ => return Err(invalid("expected an array or object")),
+        };
+        if trees.is_empty() {
+            return Err(invalid("no trees"));
+        }
+        let trees: Vec<Tree> = trees.iter().map(parse_tree).collect::<Result<_, _>>()?;
+
+        let base_score = meta
+            .and_then(|m| m.get("base_score"))
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0);
+        let aggregation = match meta
+            .and_then(|m| m.get("aggregation"))
+            .and_then(Value::as_str)
+        {
+            None | Some("sum") => Aggregation::Sum,
+            Some("mean") => Aggregation::Mean,
+            Some(other) => return Err(invalid(&format!("unknown aggregation '{other}'"))),
+        };
+        let objective = match meta
+            .and_then(|m| m.get("objective"))
+            .and_then(Value::as_str)
+        {
+            Some("binary:logistic") | Some("logistic") => Objective::Logistic,
+            _ => Objective::Identity,
+        };
+        let num_features = trees
+            .iter()
+            .flat_map(|t| t.nodes.iter())
+            .filter_map(|n| match n {
+                Node::Split { feature, .. } => Some(feature + 1),
+                Node::Leaf(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        Ok(Self {
+            trees,
+            base_score,
+            aggregation,
+            objective,
+            num_features,
+        })
+    }
+
+    /// Load an ensemble from a JSON model dump on disk.
+    pub fn load(path: &str) -> Result<Self, ForziumError> {
+        let content = fs::read_to_string(path)
+            .map_err(|_| ForziumError::Compute("model file not found".into()))?;
+        Self::from_json(&content)
+    }
+
+    /// Number of trees in the ensemble.
+    pub fn num_trees(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Minimum input length required by the splits in the ensemble.
+    pub fn num_features(&self) -> usize {
+        self.num_features
+    }
+
+    fn score(&self, input: &[f64]) -> f64 {
+        let total: f64 = self.trees.iter().map(|t| t.predict(input)).sum();
+        let raw = match self.aggregation {
+            Aggregation::Sum => total,
+            Aggregation::Mean => total / self.trees.len() as f64,
+        } + self.base_score;
+        match self.objective {
+            Objective::Identity => raw,
+            Objective::Logistic => 1.0 / (1.0 + (-raw).exp()),
+        }
+    }
+
+    /// Predict output for a single input vector.
+    pub fn predict(&self, input: &[f64]) -> Result<f64, ForziumError> {
+        if input.len() < self.num_features {
+            return Err(ForziumError::Compute("input length mismatch".into()));
+        }
+        Ok(self.score(input))
+    }
+
+    /// Predict outputs for a row-major batch of `cols`-wide rows in parallel.
+    pub fn predict_batch(&self, data: &[f64], cols: usize) -> Result<Vec<f64>, ForziumError> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+        if cols == 0 || cols < self.num_features || !data.len().is_multiple_of(cols) {
+            return Err(ForziumError::Compute("input length mismatch".into()));
+        }
+        Ok(data
+            .par_chunks(cols)
+            .map(|row| {
+                let _guard = rayon_metrics::track_operation("tree_ensemble_predict");
+                self.score(row)
+            })
+            .collect())
+    }
+}
+
+#[pyclass(name = "TreeEnsemble", frozen)]
+pub struct PyTreeEnsemble {
+    inner: TreeEnsemble,
+}
+
+#[pymethods]
+impl PyTreeEnsemble {
+    /// Load an ensemble from a JSON model dump on disk.
+    #[classmethod]
+    pub fn load(_cls: &Bound<PyType>, path: &str) -> PyResult<Self> {
+        let inner = TreeEnsemble::load(path)?;
+        Ok(Self { inner })
+    }
+
+    /// Parse an ensemble from a JSON model dump string.
+    #[classmethod]
+    pub fn from_json(_cls: &Bound<PyType>, json: &str) -> PyResult<Self> {
+        let inner = TreeEnsemble::from_json(json)?;
+        Ok(Self { inner })
+    }
+
+    /// Number of trees in the ensemble.
+    #[getter]
+    pub fn num_trees(&self) -> usize {
+        self.inner.num_trees()
+    }
+
+    /// Minimum input length required by the model.
+    #[getter]
+    pub fn num_features(&self) -> usize {
+        self.inner.num_features()
+    }
+
+    /// Predict output for a single input vector.
+    pub fn predict(&self, input: Vec<f64>) -> PyResult<f64> {
+        self.inner.predict(&input).map_err(Into::into)
+    }
+
+    /// Predict outputs for a 2D array of inputs without holding the GIL.
+    pub fn predict_batch(&self, py: Python<'_>, inputs: &Bound<'_, PyAny>) -> PyResult<Vec<f64>> {
+        let (data, cols) = extract_batch(inputs)?;
+        py.detach(|| self.inner.predict_batch(&data, cols))
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"[
+        {"nodeid": 0, "depth": 0, "split": "f0", "split_condition": 0.5,
+         "yes": 1, "no": 2, "missing": 1, "children": [
+            {"nodeid": 1, "leaf": -1.0},
+            {"nodeid": 2, "depth": 1, "split": "f1", "split_condition": 2.0,
+             "yes": 3, "no": 4, "missing": 4, "children": [
+                {"nodeid": 3, "leaf": 0.5},
+                {"nodeid": 4, "leaf": 1.5}
+            ]}
+        ]},
+        {"nodeid": 0, "leaf": 0.25}
+    ]"#;
+
+    #[test]
+    fn predicts_sum_of_trees() {
+        let model = TreeEnsemble::from_json(DUMP).unwrap();
+        assert_eq!(model.num_trees(), 2);
+        assert_eq!(model.num_features(), 2);
+        assert_eq!(model.predict(&[0.0, 0.0]).unwrap(), -0.75);
+        assert_eq!(model.predict(&[1.0, 1.0]).unwrap(), 0.75);
+        assert_eq!(model.predict(&[1.0, f64::NAN]).unwrap(), 1.75);
+        let batch = model.predict_batch(&[0.0, 0.0, 1.0, 3.0], 2).unwrap();
+        assert_eq!(batch, vec![-0.75, 1.75]);
+        assert!(model.predict(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn mean_aggregation_with_metadata() {
+        let json = format!(r#"{{"trees": {DUMP}, "aggregation": "mean", "base_score": 1.0}}"#);
+        let model = TreeEnsemble::from_json(&json).unwrap();
+        assert_eq!(model.predict(&[0.0, 0.0]).unwrap(), 0.625);
+    }
+
+    #[test]
+    fn rejects_malformed_trees() {
+        assert!(TreeEnsemble::from_json("[]").is_err());
+        let cyclic = r#"[{"nodeid": 0, "split": "f0", "split_condition": 1.0,
+            "yes": 0, "no": 1, "children": [{"nodeid": 1, "leaf": 1.0}]}]"#;
+        assert!(TreeEnsemble::from_json(cyclic).is_err());
+    }
+}
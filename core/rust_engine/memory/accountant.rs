@@ -0,0 +1,354 @@
+//! Process-wide accounting of memory held by compute ops, request bodies and
+//! pool blocks, with optional per-request budgets
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::error::ForziumError;
+
+/// What a reservation is holding memory for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryCategory {
+    /// Buffers reserved by compute operations.
+    Compute,
+    /// Request bodies buffered by the HTTP server.
+    RequestBody,
+    /// Blocks handed out by a `PoolAllocator`.
+    Pool,
+}
+
+impl MemoryCategory {
+    /// Every category, in reporting order.
+    pub const ALL: [MemoryCategory; 3] = [Self::Compute, Self::RequestBody, Self::Pool];
+
+    /// Name used in Python-facing stats.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Compute => "compute",
+            Self::RequestBody => "request_body",
+            Self::Pool => "pool",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Add `bytes` to `counter` unless the result would exceed `limit` or overflow.
+///
+/// Returns the value seen before the failed attempt on error.
+fn try_charge(counter: &AtomicUsize, bytes: usize, limit: usize) -> Result<usize, usize> {
+    let mut current = counter.load(Ordering::SeqCst);
+    loop {
+        let next = match current.checked_add(bytes) {
+            Some(next) if next <= limit => next,
+            _ => return Err(current),
+        };
+        match counter.compare_exchange(current, next, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => return Ok(next),
+            Err(actual) => current = actual,
+        }
+    }
+}
+
+/// Running totals for the whole process.
+struct Accountant {
+    total: AtomicUsize,
+    peak: AtomicUsize,
+    /// Process-wide limit in bytes; 0 means unlimited.
+    limit: AtomicUsize,
+    rejected: AtomicU64,
+    by_category: [AtomicUsize; 3],
+}
+
+static ACCOUNTANT: Accountant = Accountant {
+    total: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+    limit: AtomicUsize::new(0),
+    rejected: AtomicU64::new(0),
+    by_category: [const { AtomicUsize::new(0) }; 3],
+};
+
+impl Accountant {
+    fn charge(&self, category: MemoryCategory, bytes: usize) -> Result<(), ForziumError> {
+        let limit = match self.limit.load(Ordering::SeqCst) {
+            0 => usize::MAX,
+            limit => limit,
+        };
+        let total = try_charge(&self.total, bytes, limit).map_err(|current| {
+            self.rejected.fetch_add(1, Ordering::SeqCst);
+            ForziumError::ResourceLimit(format!(
+                "Process memory limit exceeded: {bytes} bytes requested, {current} of {limit} bytes in use"
+            ))
+        })?;
+        self.peak.fetch_max(total, Ordering::SeqCst);
+        self.by_category[category.index()].fetch_add(bytes, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn release(&self, category: MemoryCategory, bytes: usize) {
+        self.total.fetch_sub(bytes, Ordering::SeqCst);
+        self.by_category[category.index()].fetch_sub(bytes, Ordering::SeqCst);
+    }
+}
+
+/// Byte budget shared by everything one request reserves.
+#[derive(Debug)]
+pub struct RequestBudget {
+    limit: usize,
+    used: AtomicUsize,
+    peak: AtomicUsize,
+    rejections: AtomicUsize,
+}
+
+impl RequestBudget {
+    /// Create a budget allowing `limit` bytes to be held at once.
+    pub fn new(limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            used: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            rejections: AtomicUsize::new(0),
+        })
+    }
+
+    /// Maximum bytes the request may hold at once.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Bytes currently reserved against the budget.
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    /// Most bytes reserved at once.
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+
+    /// Reservations refused because they would exceed the budget.
+    pub fn rejections(&self) -> usize {
+        self.rejections.load(Ordering::SeqCst)
+    }
+
+    fn charge(&self, bytes: usize) -> Result<(), ForziumError> {
+        let used = try_charge(&self.used, bytes, self.limit).map_err(|current| {
+            self.rejections.fetch_add(1, Ordering::SeqCst);
+            ForziumError::ResourceLimit(format!(
+                "Request memory budget exceeded: {bytes} bytes requested, {current} of {} bytes in use",
+                self.limit
+            ))
+        })?;
+        self.peak.fetch_max(used, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::SeqCst);
+    }
+}
+
+thread_local! {
+    static CURRENT_BUDGET: RefCell<Option<Arc<RequestBudget>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `budget` charged for every reservation made on this thread.
+pub fn with_request_budget<R>(budget: &Arc<RequestBudget>, f: impl FnOnce() -> R) -> R {
+    let previous = CURRENT_BUDGET.with(|current| current.replace(Some(budget.clone())));
+    let _restore = RestoreBudget(previous);
+    f()
+}
+
+/// Makes the budget that was current before [`with_request_budget`] current
+/// again when dropped, so a panicking `f` cannot leave its budget behind on
+/// a pooled thread.
+struct RestoreBudget(Option<Arc<RequestBudget>>);
+
+impl Drop for RestoreBudget {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT_BUDGET.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Budget of the request running on this thread, if any.
+pub fn current_budget() -> Option<Arc<RequestBudget>> {
+    CURRENT_BUDGET.with(|current| current.borrow().clone())
+}
+
+/// Bytes held against the accountant, released on drop.
+#[must_use = "the reservation is released as soon as it is dropped"]
+#[derive(Debug)]
+pub struct MemoryReservation {
+    category: MemoryCategory,
+    bytes: usize,
+    budget: Option<Arc<RequestBudget>>,
+}
+
+impl MemoryReservation {
+    /// Bytes held by this reservation.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Reserve `bytes` more, e.g. as a request body streams in.
+    pub fn grow(&mut self, bytes: usize) -> Result<(), ForziumError> {
+        if let Some(budget) = &self.budget {
+            budget.charge(bytes)?;
+        }
+        if let Err(err) = ACCOUNTANT.charge(self.category, bytes) {
+            if let Some(budget) = &self.budget {
+                budget.release(bytes);
+            }
+            return Err(err);
+        }
+        self.bytes += bytes;
+        Ok(())
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        if self.bytes == 0 {
+            return;
+        }
+        ACCOUNTANT.release(self.category, self.bytes);
+        if let Some(budget) = &self.budget {
+            budget.release(self.bytes);
+        }
+    }
+}
+
+/// Reserve `bytes` against the process total and `budget`, if given.
+pub fn reserve_in(
+    budget: Option<Arc<RequestBudget>>,
+    category: MemoryCategory,
+    bytes: usize,
+) -> Result<MemoryReservation, ForziumError> {
+    let mut reservation = MemoryReservation {
+        category,
+        bytes: 0,
+        budget,
+    };
+    reservation.grow(bytes)?;
+    Ok(reservation)
+}
+
+/// Reserve `bytes` against the process total and the budget of the request
+/// running on this thread.
+pub fn reserve(category: MemoryCategory, bytes: usize) -> Result<MemoryReservation, ForziumError> {
+    reserve_in(current_budget(), category, bytes)
+}
+
+/// Snapshot of the process-wide totals.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccountingStats {
+    /// Bytes currently reserved.
+    pub total: usize,
+    /// Most bytes reserved at once.
+    pub peak: usize,
+    /// Process-wide limit in bytes; 0 means unlimited.
+    pub limit: usize,
+    /// Reservations refused because of the process-wide limit.
+    pub rejected: u64,
+    /// Bytes currently reserved per category, indexed like [`MemoryCategory::ALL`].
+    pub by_category: [usize; 3],
+}
+
+/// Read the process-wide totals.
+pub fn stats() -> AccountingStats {
+    AccountingStats {
+        total: ACCOUNTANT.total.load(Ordering::SeqCst),
+        peak: ACCOUNTANT.peak.load(Ordering::SeqCst),
+        limit: ACCOUNTANT.limit.load(Ordering::SeqCst),
+        rejected: ACCOUNTANT.rejected.load(Ordering::SeqCst),
+        by_category: std::array::from_fn(|i| ACCOUNTANT.by_category[i].load(Ordering::SeqCst)),
+    }
+}
+
+/// Cap the bytes reserved across the process; `None` removes the cap.
+pub fn set_limit(limit: Option<usize>) {
+    ACCOUNTANT.limit.store(limit.unwrap_or(0), Ordering::SeqCst);
+}
+
+/// Set the process-wide memory limit in bytes, or remove it with `None`.
+#[pyfunction]
+#[pyo3(signature = (max_bytes=None))]
+pub fn set_memory_limit(max_bytes: Option<usize>) -> PyResult<()> {
+    if max_bytes == Some(0) {
+        return Err(ForziumError::Validation("limits must be positive".into()).into());
+    }
+    set_limit(max_bytes);
+    Ok(())
+}
+
+/// Return process-wide memory accounting totals as a dict.
+#[pyfunction]
+pub fn memory_accounting(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let stats = stats();
+    let dict = PyDict::new(py);
+    dict.set_item("total_bytes", stats.total)?;
+    dict.set_item("peak_bytes", stats.peak)?;
+    dict.set_item("limit_bytes", (stats.limit > 0).then_some(stats.limit))?;
+    dict.set_item("rejected", stats.rejected)?;
+    let by_category = PyDict::new(py);
+    for (category, bytes) in MemoryCategory::ALL.iter().zip(stats.by_category) {
+        by_category.set_item(category.name(), bytes)?;
+    }
+    dict.set_item("by_category", by_category)?;
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_rejects_and_releases_on_drop() {
+        let budget = RequestBudget::new(100);
+        let first = reserve_in(Some(budget.clone()), MemoryCategory::Compute, 60).unwrap();
+        let err = reserve_in(Some(budget.clone()), MemoryCategory::Pool, 50).unwrap_err();
+        assert!(matches!(err, ForziumError::ResourceLimit(_)));
+        assert_eq!(budget.used(), 60);
+        assert_eq!(budget.rejections(), 1);
+
+        drop(first);
+        assert_eq!(budget.used(), 0);
+        assert_eq!(budget.peak(), 60);
+    }
+
+    #[test]
+    fn reservations_grow_and_use_the_thread_budget() {
+        let budget = RequestBudget::new(64);
+        let mut body = with_request_budget(&budget, || {
+            reserve(MemoryCategory::RequestBody, 16).unwrap()
+        });
+        assert!(current_budget().is_none());
+
+        body.grow(32).unwrap();
+        assert!(body.grow(32).is_err());
+        assert_eq!(body.bytes(), 48);
+        assert_eq!(budget.used(), 48);
+        drop(body);
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn budget_is_restored_when_the_request_panics() {
+        let outer = RequestBudget::new(64);
+        with_request_budget(&outer, || {
+            let inner = RequestBudget::new(32);
+            let panicked = std::panic::catch_unwind(|| {
+                with_request_budget(&inner, || panic!("handler failed"));
+            });
+            assert!(panicked.is_err());
+            assert!(Arc::ptr_eq(&current_budget().unwrap(), &outer));
+        });
+        assert!(current_budget().is_none());
+    }
+}
@@ -0,0 +1,168 @@
+//! 64-byte-aligned buffers for SIMD kernels
+//!
+//! A 64-byte boundary is both the cache line size and the width of an
+//! AVX-512 register, so rows starting on one can be read with aligned loads
+//! and never straddle two cache lines.
+
+use std::ops::{Deref, DerefMut};
+
+/// Alignment of every [`AlignedBuffer`] in bytes.
+pub const SIMD_ALIGN: usize = 64;
+
+/// `f64` values per aligned chunk.
+pub const LANES: usize = SIMD_ALIGN / size_of::<f64>();
+
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C, align(64))]
+struct Chunk([f64; LANES]);
+
+/// Zero-initialized `f64` buffer whose first element is 64-byte aligned.
+#[derive(Debug, Clone, Default)]
+pub struct AlignedBuffer {
+    chunks: Vec<Chunk>,
+    len: usize,
+}
+
+impl AlignedBuffer {
+    /// Allocate `len` zeroed values.
+    pub fn zeroed(len: usize) -> Self {
+        Self {
+            chunks: vec![Chunk::default(); len.div_ceil(LANES)],
+            len,
+        }
+    }
+
+    /// Copy `values` into a new aligned buffer.
+    pub fn from_slice(values: &[f64]) -> Self {
+        let mut buf = Self::zeroed(values.len());
+        buf.copy_from_slice(values);
+        buf
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        // SAFETY: `Chunk` is `repr(C)` over `[f64; LANES]`, so the chunks are
+        // `chunks.len() * LANES >= len` contiguous, initialized f64 values.
+        unsafe { std::slice::from_raw_parts(self.chunks.as_ptr().cast(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [f64] {
+        // SAFETY: as in `deref`, with unique access through `&mut self`.
+        unsafe { std::slice::from_raw_parts_mut(self.chunks.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+/// Row-major matrix in an [`AlignedBuffer`] with every row 64-byte aligned.
+///
+/// Rows are zero-padded to a multiple of [`LANES`], so kernels can process
+/// [`padded_row`](Self::padded_row) in whole vectors without a scalar tail.
+#[derive(Debug, Clone)]
+pub struct AlignedMatrix {
+    data: AlignedBuffer,
+    rows: usize,
+    cols: usize,
+    stride: usize,
+}
+
+impl AlignedMatrix {
+    /// Allocate a zeroed `rows` x `cols` matrix.
+    pub fn zeroed(rows: usize, cols: usize) -> Self {
+        let stride = cols.div_ceil(LANES) * LANES;
+        Self {
+            data: AlignedBuffer::zeroed(rows * stride),
+            rows,
+            cols,
+            stride,
+        }
+    }
+
+    /// Copy a rectangular matrix of rows. Every row must have `cols` values.
+    pub fn from_rows(m: &[Vec<f64>], cols: usize) -> Self {
+        let mut out = Self::zeroed(m.len(), cols);
+        for (i, row) in m.iter().enumerate() {
+            out.row_mut(i).copy_from_slice(row);
+        }
+        out
+    }
+
+    /// Copy the transpose of a rectangular matrix with `cols` columns.
+    pub fn transposed(m: &[Vec<f64>], cols: usize) -> Self {
+        let mut out = Self::zeroed(cols, m.len());
+        for (i, row) in m.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                out.data[j * out.stride + i] = value;
+            }
+        }
+        out
+    }
+
+    /// Number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of columns, excluding padding.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Values per row including padding; always a multiple of [`LANES`].
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Row `i` without padding.
+    pub fn row(&self, i: usize) -> &[f64] {
+        &self.padded_row(i)[..self.cols]
+    }
+
+    /// Row `i` without padding, mutably.
+    pub fn row_mut(&mut self, i: usize) -> &mut [f64] {
+        let start = i * self.stride;
+        &mut self.data[start..start + self.cols]
+    }
+
+    /// Row `i` including its zero padding; starts on a 64-byte boundary.
+    pub fn padded_row(&self, i: usize) -> &[f64] {
+        let start = i * self.stride;
+        &self.data[start..start + self.stride]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_aligned_and_zeroed() {
+        for len in [0, 1, 7, 8, 9, 100] {
+            let buf = AlignedBuffer::zeroed(len);
+            assert_eq!(buf.len(), len);
+            assert_eq!(buf.as_ptr() as usize % SIMD_ALIGN, 0);
+            assert!(buf.iter().all(|&v| v == 0.0));
+        }
+        let copy = AlignedBuffer::from_slice(&[1.0, 2.0, 3.0]);
+        assert_eq!(&*copy, &[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn matrix_rows_are_aligned_and_padded() {
+        let m = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        let aligned = AlignedMatrix::from_rows(&m, 3);
+        assert_eq!(aligned.stride(), LANES);
+        for (i, row) in m.iter().enumerate() {
+            assert_eq!(aligned.row(i), row.as_slice());
+            assert_eq!(aligned.padded_row(i).as_ptr() as usize % SIMD_ALIGN, 0);
+            assert!(aligned.padded_row(i)[3..].iter().all(|&v| v == 0.0));
+        }
+
+        let t = AlignedMatrix::transposed(&m, 3);
+        assert_eq!((t.rows(), t.cols()), (3, 2));
+        assert_eq!(t.row(2), &[3.0, 6.0]);
+    }
+}
@@ -0,0 +1,139 @@
+//! Global allocator selection and allocator statistics
+//!
+//! Building with the `mimalloc` or `jemalloc` feature replaces the system
+//! allocator for every Rust allocation in the extension. With both enabled,
+//! as `--all-features` does, mimalloc is used.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(all(feature = "jemalloc", not(feature = "mimalloc")))]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Allocator-level memory usage.
+///
+/// Fields the active allocator can't report are `None`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllocatorStats {
+    /// Name of the global allocator: "system", "mimalloc" or "jemalloc".
+    pub backend: &'static str,
+    /// Bytes in live allocations.
+    pub allocated: Option<usize>,
+    /// Bytes in pages the allocator has handed out or committed.
+    pub active: Option<usize>,
+    /// Bytes physically resident for the process or allocator.
+    pub resident: Option<usize>,
+    /// Share of active bytes not backing a live allocation.
+    pub fragmentation: Option<f64>,
+}
+
+/// Read mimalloc's process statistics.
+#[cfg(feature = "mimalloc")]
+pub fn allocator_stats() -> AllocatorStats {
+    use std::ptr::null_mut;
+
+    let (mut rss, mut commit) = (0usize, 0usize);
+    // SAFETY: mimalloc skips null outputs and the others point to live usizes.
+    unsafe {
+        libmimalloc_sys::mi_process_info(
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            &mut rss,
+            null_mut(),
+            &mut commit,
+            null_mut(),
+            null_mut(),
+        );
+    }
+    AllocatorStats {
+        backend: "mimalloc",
+        allocated: None,
+        active: Some(commit),
+        resident: Some(rss),
+        fragmentation: None,
+    }
+}
+
+/// Read jemalloc's global statistics.
+#[cfg(all(feature = "jemalloc", not(feature = "mimalloc")))]
+pub fn allocator_stats() -> AllocatorStats {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    // Statistics are cached until the epoch advances
+    let _ = epoch::advance();
+    let allocated = stats::allocated::read().ok();
+    let active = stats::active::read().ok();
+    let fragmentation = match (allocated, active) {
+        (Some(allocated), Some(active)) if active > 0 => {
+            Some(active.saturating_sub(allocated) as f64 / active as f64)
+        }
+        _ => None,
+    };
+    AllocatorStats {
+        backend: "jemalloc",
+        allocated,
+        active,
+        resident: stats::resident::read().ok(),
+        fragmentation,
+    }
+}
+
+/// The system allocator keeps no statistics, so only report process RSS.
+#[cfg(not(any(feature = "mimalloc", feature = "jemalloc")))]
+pub fn allocator_stats() -> AllocatorStats {
+    AllocatorStats {
+        backend: "system",
+        resident: process_rss(),
+        ..AllocatorStats::default()
+    }
+}
+
+/// Resident set size of the process, from `/proc/self/status`.
+#[cfg(not(any(feature = "mimalloc", feature = "jemalloc")))]
+fn process_rss() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Return allocator statistics (backend, allocated, active, resident and
+/// fragmentation) as a dict.
+#[pyfunction]
+pub fn memory_stats(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let stats = allocator_stats();
+    let dict = PyDict::new(py);
+    dict.set_item("backend", stats.backend)?;
+    dict.set_item("allocated_bytes", stats.allocated)?;
+    dict.set_item("active_bytes", stats.active)?;
+    dict.set_item("resident_bytes", stats.resident)?;
+    dict.set_item("fragmentation", stats.fragmentation)?;
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_report_the_active_backend() {
+        let stats = allocator_stats();
+        let expected = if cfg!(feature = "mimalloc") {
+            "mimalloc"
+        } else if cfg!(feature = "jemalloc") {
+            "jemalloc"
+        } else {
+            "system"
+        };
+        assert_eq!(stats.backend, expected);
+        if let Some(fragmentation) = stats.fragmentation {
+            assert!((0.0..=1.0).contains(&fragmentation));
+        }
+    }
+}
@@ -1,11 +1,86 @@
 //! Bridge for triggering Python's garbage collection from Rust
 
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+static INVOCATIONS: AtomicU64 = AtomicU64::new(0);
+static COLLECTED: AtomicU64 = AtomicU64::new(0);
+static TOTAL_NANOS: AtomicU64 = AtomicU64::new(0);
+static MAX_NANOS: AtomicU64 = AtomicU64::new(0);
+static LAST_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Totals over every [`force_gc`] call in the process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GcStats {
+    /// Number of collections triggered.
+    pub invocations: u64,
+    /// Unreachable objects found across all collections.
+    pub collected: u64,
+    /// Time spent collecting in seconds.
+    pub total_seconds: f64,
+    /// Longest single collection in seconds.
+    pub max_seconds: f64,
+    /// Duration of the most recent collection in seconds.
+    pub last_seconds: f64,
+}
+
+fn record_collection(nanos: u64, collected: u64) {
+    INVOCATIONS.fetch_add(1, Ordering::SeqCst);
+    COLLECTED.fetch_add(collected, Ordering::SeqCst);
+    TOTAL_NANOS.fetch_add(nanos, Ordering::SeqCst);
+    MAX_NANOS.fetch_max(nanos, Ordering::SeqCst);
+    LAST_NANOS.store(nanos, Ordering::SeqCst);
+}
+
+/// Read the collection totals.
+pub fn stats() -> GcStats {
+    let seconds = |counter: &AtomicU64| counter.load(Ordering::SeqCst) as f64 / 1e9;
+    GcStats {
+        invocations: INVOCATIONS.load(Ordering::SeqCst),
+        collected: COLLECTED.load(Ordering::SeqCst),
+        total_seconds: seconds(&TOTAL_NANOS),
+        max_seconds: seconds(&MAX_NANOS),
+        last_seconds: seconds(&LAST_NANOS),
+    }
+}
 
 /// Force a Python garbage collection cycle.
 #[pyfunction]
 pub fn force_gc(py: Python<'_>) -> PyResult<()> {
     let gc = py.import("gc")?;
-    gc.call_method0("collect")?;
+    let start = Instant::now();
+    let collected = gc.call_method0("collect")?;
+    let nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+    record_collection(nanos, collected.extract().unwrap_or(0));
     Ok(())
 }
+
+/// Return `force_gc` invocation counts and durations as a dict.
+#[pyfunction]
+pub fn gc_stats(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let stats = stats();
+    let dict = PyDict::new(py);
+    dict.set_item("invocations", stats.invocations)?;
+    dict.set_item("collected", stats.collected)?;
+    dict.set_item("total_seconds", stats.total_seconds)?;
+    dict.set_item("max_seconds", stats.max_seconds)?;
+    dict.set_item("last_seconds", stats.last_seconds)?;
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn force_gc_records_invocations() {
+        let before = stats();
+        Python::attach(|py| force_gc(py).unwrap());
+        let after = stats();
+        assert!(after.invocations > before.invocations);
+        assert!(after.total_seconds >= before.total_seconds);
+        assert!(after.max_seconds >= after.last_seconds);
+    }
+}
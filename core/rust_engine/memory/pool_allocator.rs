@@ -1,11 +1,85 @@
 //! Thread-safe variable-size memory pool allocator
 
-use parking_lot::RwLock as PlRwLock;
+use crossbeam_queue::SegQueue;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex as PlMutex;
+use pyo3::exceptions::{PyBufferError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyByteArray;
-use std::collections::VecDeque;
-use std::sync::{Arc, Mutex, RwLock};
-use std::time::{Duration, Instant};
+use pyo3::types::PyBytes;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Instant;
+
+use crate::error::ForziumError;
+use crate::health::{self, HealthCheck};
+use crate::interpreter;
+use crate::memory::accountant::{self, MemoryCategory, MemoryReservation};
+
+/// Number of size classes; class `c` holds blocks with room for at least
+/// `2^c` bytes
+const SIZE_CLASSES: usize = usize::BITS as usize;
+
+/// Smallest size class whose blocks can hold `size` bytes
+fn class_for_size(size: usize) -> usize {
+    size.checked_next_power_of_two()
+        .map_or(SIZE_CLASSES - 1, |p| p.trailing_zeros() as usize)
+}
+
+/// Size class a returned block is filed under, by its capacity
+fn class_for_capacity(capacity: usize) -> Option<usize> {
+    capacity.checked_ilog2().map(|c| c as usize)
+}
+
+/// Buckets in [`PoolStats::contention_histogram`]
+///
+/// Bucket 0 counts allocations that reserved capacity on the first attempt
+/// and bucket `b` those that retried `2^(b-1)..2^b` times; the last bucket
+/// is open-ended.
+pub const CONTENTION_BUCKETS: usize = 6;
+
+/// Histogram bucket for an allocation that retried `retries` times
+fn contention_bucket(retries: usize) -> usize {
+    retries
+        .checked_ilog2()
+        .map_or(0, |log| (log as usize + 1).min(CONTENTION_BUCKETS - 1))
+}
+
+/// Utilization below which `trim()` releases cached blocks by default
+pub const DEFAULT_TRIM_THRESHOLD: f64 = 0.5;
+
+/// Called with the pool statistics when usage crosses the pressure watermark
+pub type PressureCallback = Arc<dyn Fn(PoolStats) + Send + Sync>;
+
+/// Registered pressure watermark and whether usage is currently above it
+///
+/// The watermark and flag are atomics, so allocations check them without
+/// locking; the callback is only locked when usage crosses the watermark.
+struct Pressure {
+    /// Watermark in bytes, `usize::MAX` while no callback is registered
+    threshold: AtomicUsize,
+    triggered: AtomicBool,
+    callback: PlMutex<Option<PressureCallback>>,
+}
+
+impl Pressure {
+    fn new() -> Self {
+        Self {
+            threshold: AtomicUsize::new(usize::MAX),
+            triggered: AtomicBool::new(false),
+            callback: PlMutex::new(None),
+        }
+    }
+}
+
+impl std::fmt::Debug for Pressure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pressure")
+            .field("threshold", &self.threshold)
+            .field("triggered", &self.triggered)
+            .finish_non_exhaustive()
+    }
+}
 
 /// Statistics about memory pool usage
 #[derive(Debug, Clone, Copy)]
@@ -20,142 +94,267 @@ pub struct PoolStats {
     pub dealloc_count: usize,
     /// Number of allocation retries due to contention
     pub contention_count: usize,
+    /// Allocation attempts by number of contention retries, bucketed as
+    /// described on [`CONTENTION_BUCKETS`]
+    pub contention_histogram: [usize; CONTENTION_BUCKETS],
     /// Maximum used bytes during pool lifetime
     pub peak_usage: usize,
     /// Time of last allocation in seconds since pool creation
     pub last_alloc_time: f64,
 }
 
+/// Counters and free lists shared by every clone of a pool
+#[derive(Debug)]
+struct PoolState {
+    used: AtomicUsize,
+    peak_usage: AtomicUsize,
+    alloc_count: AtomicUsize,
+    dealloc_count: AtomicUsize,
+    contention_count: AtomicUsize,
+    contention_histogram: [AtomicUsize; CONTENTION_BUCKETS],
+    /// Nanoseconds from pool creation to the last allocation
+    last_alloc_nanos: AtomicU64,
+    /// Bytes held in `free`
+    cached: AtomicUsize,
+    /// Free blocks by size class
+    free: [SegQueue<Vec<u8>>; SIZE_CLASSES],
+    creation_time: Instant,
+}
+
+impl PoolState {
+    fn stats(&self, capacity: usize) -> PoolStats {
+        let nanos = self.last_alloc_nanos.load(Ordering::SeqCst);
+        PoolStats {
+            capacity,
+            used: self.used.load(Ordering::SeqCst),
+            alloc_count: self.alloc_count.load(Ordering::SeqCst),
+            dealloc_count: self.dealloc_count.load(Ordering::SeqCst),
+            contention_count: self.contention_count.load(Ordering::SeqCst),
+            contention_histogram: std::array::from_fn(|i| {
+                self.contention_histogram[i].load(Ordering::SeqCst)
+            }),
+            peak_usage: self.peak_usage.load(Ordering::SeqCst),
+            last_alloc_time: nanos as f64 / 1e9,
+        }
+    }
+
+    fn record_contention(&self, retries: usize) {
+        self.contention_count.fetch_add(retries, Ordering::SeqCst);
+        self.contention_histogram[contention_bucket(retries)].fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Capacity and state of every pool created, pruned as pools are dropped
+type LivePools = PlMutex<Vec<(usize, Weak<PoolState>)>>;
+
+static LIVE_POOLS: Lazy<LivePools> = Lazy::new(Default::default);
+
+/// Statistics of every pool still alive, oldest first
+pub fn live_pool_stats() -> Vec<PoolStats> {
+    LIVE_POOLS
+        .lock()
+        .iter()
+        .filter_map(|(capacity, state)| Some(state.upgrade()?.stats(*capacity)))
+        .collect()
+}
+
 /// Allocator that manages thread-safe variable-size memory blocks up to a total capacity.
-#[pyclass(module = "forzium_engine")]
+///
+/// Free blocks are kept in power-of-two size classes, so an allocation takes
+/// a block from its own class in O(1) and reuses its buffer without
+/// reallocating. Nothing on the allocation path takes a lock: capacity is
+/// reserved with a compare-and-swap, each size class is a lock-free queue
+/// and the pressure watermark is checked against an atomic.
+#[pyclass(module = "forzium_engine", frozen)]
 #[derive(Debug, Clone)]
 pub struct PoolAllocator {
     capacity: usize,
-    // Use parking_lot RwLock for better performance
-    used: Arc<PlRwLock<usize>>,
-    peak_usage: Arc<PlRwLock<usize>>,
-    blocks: Arc<Mutex<VecDeque<Vec<u8>>>>,
-    stats: Arc<PlRwLock<PoolStats>>,
-    creation_time: Arc<Instant>,
+    state: Arc<PoolState>,
+    pressure: Arc<Pressure>,
 }
 
 impl PoolAllocator {
     /// Create a new pool with a byte capacity.
     pub fn new(capacity: usize) -> Self {
-        let now = Instant::now();
-        Self {
+        let pool = Self {
             capacity,
-            used: Arc::new(PlRwLock::new(0)),
-            peak_usage: Arc::new(PlRwLock::new(0)),
-            blocks: Arc::new(Mutex::new(VecDeque::new())),
-            stats: Arc::new(PlRwLock::new(PoolStats {
-                capacity,
-                used: 0,
-                alloc_count: 0,
-                dealloc_count: 0,
-                contention_count: 0,
-                peak_usage: 0,
-                last_alloc_time: 0.0,
-            })),
-            creation_time: Arc::new(now),
-        }
+            state: Arc::new(PoolState {
+                used: AtomicUsize::new(0),
+                peak_usage: AtomicUsize::new(0),
+                alloc_count: AtomicUsize::new(0),
+                dealloc_count: AtomicUsize::new(0),
+                contention_count: AtomicUsize::new(0),
+                contention_histogram: std::array::from_fn(|_| AtomicUsize::new(0)),
+                last_alloc_nanos: AtomicU64::new(0),
+                cached: AtomicUsize::new(0),
+                free: std::array::from_fn(|_| SegQueue::new()),
+                creation_time: Instant::now(),
+            }),
+            pressure: Arc::new(Pressure::new()),
+        };
+        let mut live = LIVE_POOLS.lock();
+        live.retain(|(_, state)| state.strong_count() > 0);
+        live.push((capacity, Arc::downgrade(&pool.state)));
+        pool
     }
 
-    /// Acquire a block of *size* bytes from the pool with timeout and retry
+    /// Acquire a block of *size* bytes, or `None` if the pool is full.
+    ///
+    /// Never blocks. Each time another thread changes the used counter
+    /// between our read and our compare-and-swap counts as one contention
+    /// retry.
     pub fn allocate(&self, size: usize) -> Option<Vec<u8>> {
-        const MAX_RETRIES: usize = 3;
-        const RETRY_DELAY_MS: u64 = 10;
-
+        let state = &*self.state;
         let mut retries = 0;
-        let now = Instant::now();
-
-        while retries < MAX_RETRIES {
-            // First check if we have enough capacity (read-only)
+        let mut used = state.used.load(Ordering::SeqCst);
+        let used_now = loop {
+            let Some(next) = used.checked_add(size).filter(|&next| next <= self.capacity) else {
+                state.record_contention(retries);
+                return None;
+            };
+            match state
+                .used
+                .compare_exchange(used, next, Ordering::SeqCst, Ordering::SeqCst)
             {
-                let used = self.used.read();
-                if *used + size > self.capacity {
-                    return None;
+                Ok(_) => break next,
+                Err(actual) => {
+                    used = actual;
+                    retries += 1;
                 }
             }
-
-            // Try to acquire a mutex on the blocks
-            if let Ok(mut blocks) = self.blocks.try_lock() {
-                // Update used counter
-                let mut used = self.used.write();
-                *used += size;
-
-                // Update peak usage if needed
-                let mut peak = self.peak_usage.write();
-                if *used > *peak {
-                    *peak = *used;
-                }
-
-                // Get a block from the pool or create a new one
-                let block = if let Some(mut block) = blocks.pop_front() {
-                    if block.len() < size {
-                        block.resize(size, 0);
-                    }
-                    block
-                } else {
-                    vec![0u8; size]
-                };
-
-                // Update stats
-                let elapsed = now.duration_since(*self.creation_time).as_secs_f64();
-                let mut stats = self.stats.write();
-                stats.used = *used;
-                stats.alloc_count += 1;
-                stats.contention_count += retries;
-                stats.peak_usage = *peak;
-                stats.last_alloc_time = elapsed;
-
-                return Some(block);
+        };
+        state.record_contention(retries);
+        state.peak_usage.fetch_max(used_now, Ordering::SeqCst);
+
+        // Reuse a block of this size class or create one that fills it
+        let class = class_for_size(size);
+        let mut block = match state.free[class].pop() {
+            Some(block) => {
+                state.cached.fetch_sub(block.capacity(), Ordering::SeqCst);
+                block
             }
-
-            // If we couldn't get the lock, increment contention counter and retry
-            retries += 1;
-            std::thread::sleep(Duration::from_millis(RETRY_DELAY_MS));
-        }
-
-        // Couldn't acquire the lock after retries
-        let mut stats = self.stats.write();
-        stats.contention_count += retries;
-        None
+            None => Vec::with_capacity(1 << class),
+        };
+        block.truncate(size);
+        block.resize(size, 0);
+
+        let elapsed = state.creation_time.elapsed().as_nanos();
+        state
+            .last_alloc_nanos
+            .store(u64::try_from(elapsed).unwrap_or(u64::MAX), Ordering::SeqCst);
+        state.alloc_count.fetch_add(1, Ordering::SeqCst);
+        self.notify_pressure(used_now);
+
+        Some(block)
     }
 
     /// Return a block back to the pool.
     pub fn deallocate(&self, block: Vec<u8>) {
+        let state = &*self.state;
         let len = block.len();
+        let (Ok(previous) | Err(previous)) =
+            state
+                .used
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                    Some(used.saturating_sub(len))
+                });
+        state.dealloc_count.fetch_add(1, Ordering::SeqCst);
+
+        // Cache the buffer under its size class so it can be reused
+        if let Some(class) = class_for_capacity(block.capacity()) {
+            state.cached.fetch_add(block.capacity(), Ordering::SeqCst);
+            state.free[class].push(block);
+        }
+        self.notify_pressure(previous.saturating_sub(len));
+    }
 
-        // Update used counter
+    /// Acquire a block that returns itself to the pool when dropped.
+    ///
+    /// The block is registered with the memory accountant, so it counts
+    /// against the budget of the request running on this thread. Returns
+    /// `Ok(None)` when the pool itself is full.
+    pub fn allocate_block(&self, size: usize) -> Result<Option<PoolBlock>, ForziumError> {
+        let reservation = accountant::reserve(MemoryCategory::Pool, size)?;
+        Ok(self.allocate(size).map(|data| PoolBlock {
+            pool: self.clone(),
+            data: Some(data),
+            exports: 0,
+            reservation: Some(reservation),
+        }))
+    }
+
+    /// Call `callback` each time usage rises to `watermark` (a fraction of
+    /// capacity) after having been below it. Replaces any earlier callback.
+    pub fn set_pressure_callback(&self, watermark: f64, callback: PressureCallback) {
+        let mut registered = self.pressure.callback.lock();
+        *registered = Some(callback);
+        // smallest usage at or above the watermark; the cast saturates
+        let threshold = (watermark * self.capacity as f64).ceil() as usize;
+        let used = self.state.used.load(Ordering::SeqCst);
+        self.pressure
+            .triggered
+            .store(used >= threshold, Ordering::SeqCst);
+        self.pressure.threshold.store(threshold, Ordering::SeqCst);
+    }
+
+    /// Remove the pressure callback.
+    pub fn clear_pressure_callback(&self) {
+        let mut registered = self.pressure.callback.lock();
+        self.pressure.threshold.store(usize::MAX, Ordering::SeqCst);
+        self.pressure.triggered.store(false, Ordering::SeqCst);
+        *registered = None;
+    }
+
+    /// Fire the pressure callback if `used` just crossed the watermark.
+    fn notify_pressure(&self, used: usize) {
+        let pressure = &*self.pressure;
+        let above = used >= pressure.threshold.load(Ordering::SeqCst);
+        // only the call that flips the flag up fires the callback
+        if pressure.triggered.load(Ordering::SeqCst) == above
+            || pressure.triggered.swap(above, Ordering::SeqCst) == above
+            || !above
         {
-            let mut used = self.used.write();
-            *used = used.saturating_sub(len);
+            return;
+        }
+        let callback = pressure.callback.lock().clone();
+        if let Some(callback) = callback {
+            callback(self.stats());
         }
+    }
 
-        // Return block to the pool if we can acquire the mutex
-        if let Ok(mut blocks) = self.blocks.try_lock() {
-            blocks.push_back(block);
-
-            // Update stats
-            let mut stats = self.stats.write();
-            stats.used = *self.used.read();
-            stats.dealloc_count += 1;
-        } else {
-            // If can't acquire mutex, just drop the block
-            // This is a compromise to avoid deadlocks
-            // We've already updated the used counter so memory accounting is correct
+    /// Free the cached blocks if utilization is below `threshold`, returning
+    /// the number of bytes handed back to the system allocator.
+    pub fn trim(&self, threshold: f64) -> usize {
+        let state = &*self.state;
+        if state.used.load(Ordering::SeqCst) as f64 >= threshold * self.capacity as f64 {
+            return 0;
+        }
+        let mut freed = 0;
+        for class in &state.free {
+            // Only drain what is cached now, so concurrent returns can't keep
+            // this loop going
+            for block in (0..class.len()).map_while(|_| class.pop()) {
+                state.cached.fetch_sub(block.capacity(), Ordering::SeqCst);
+                freed += block.capacity();
+            }
         }
+        freed
+    }
+
+    /// Bytes held in cached free blocks.
+    pub fn cached_bytes(&self) -> usize {
+        self.state.cached.load(Ordering::SeqCst)
     }
 
     /// Number of free bytes remaining.
     pub fn available(&self) -> usize {
-        self.capacity - *self.used.read()
+        self.capacity
+            .saturating_sub(self.state.used.load(Ordering::SeqCst))
     }
 
     /// Get current statistics about the pool
     pub fn stats(&self) -> PoolStats {
-        *self.stats.read()
+        self.state.stats(self.capacity)
     }
 
     /// Create *nodes* pools dividing *total* capacity equally.
@@ -165,6 +364,29 @@ impl PoolAllocator {
     }
 }
 
+/// Health check failing while a pool's usage is above a fraction of its
+/// capacity
+pub struct PoolHealthCheck {
+    pub pool: PoolAllocator,
+    pub max_utilization: f64,
+}
+
+impl HealthCheck for PoolHealthCheck {
+    fn check(&self) -> Result<(), String> {
+        let used = self.pool.state.used.load(Ordering::SeqCst);
+        let utilization = used as f64 / self.pool.capacity.max(1) as f64;
+        if utilization > self.max_utilization {
+            return Err(format!(
+                "{used} of {} bytes in use ({:.0}% > {:.0}%)",
+                self.pool.capacity,
+                utilization * 100.0,
+                self.max_utilization * 100.0
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[pymethods]
 impl PoolAllocator {
     #[new]
@@ -172,15 +394,26 @@ impl PoolAllocator {
         Self::new(capacity)
     }
 
+    /// Acquire a block exposing the pooled memory through the buffer protocol
     #[pyo3(name = "allocate")]
-    pub fn py_allocate(&self, py: Python<'_>, size: usize) -> Option<Py<PyByteArray>> {
-        self.allocate(size)
-            .map(|vec| PyByteArray::new(py, &vec).into())
+    pub fn py_allocate(&self, size: usize) -> PyResult<Option<PoolBlock>> {
+        self.allocate_block(size).map_err(Into::into)
     }
 
+    /// Acquire a block that is returned automatically on release or `__exit__`
+    #[pyo3(name = "allocate_block")]
+    pub fn py_allocate_block(&self, size: usize) -> PyResult<Option<PoolBlock>> {
+        self.allocate_block(size).map_err(Into::into)
+    }
+
+    /// Return a block from `allocate`, or adopt any bytes-like object
     #[pyo3(name = "deallocate")]
-    pub fn py_deallocate(&self, data: Vec<u8>) {
-        self.deallocate(data);
+    pub fn py_deallocate(&self, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        if let Ok(block) = data.cast::<PoolBlock>() {
+            return block.borrow_mut().try_release();
+        }
+        self.deallocate(data.extract()?);
+        Ok(())
     }
 
     #[pyo3(name = "available")]
@@ -188,25 +421,73 @@ impl PoolAllocator {
         self.available()
     }
 
+    /// Call *callback(stats)* whenever usage rises to *watermark* (a fraction
+    /// of capacity). Passing `None` removes the callback.
+    #[pyo3(name = "on_pressure", signature = (callback, watermark=0.9))]
+    pub fn py_on_pressure(
+        &self,
+        py: Python<'_>,
+        callback: Option<Py<PyAny>>,
+        watermark: f64,
+    ) -> PyResult<()> {
+        if !(watermark > 0.0 && watermark <= 1.0) {
+            return Err(PyValueError::new_err(format!(
+                "watermark must be in (0, 1], got {watermark}"
+            )));
+        }
+        let Some(callback) = callback else {
+            self.clear_pressure_callback();
+            return Ok(());
+        };
+        let owner = interpreter::current_id(py)?;
+        self.set_pressure_callback(
+            watermark,
+            Arc::new(move |stats| {
+                Python::attach(|py| {
+                    let called = interpreter::ensure_current(py, owner)
+                        .and_then(|()| callback.call1(py, (stats_dict(py, &stats),)));
+                    if let Err(err) = called {
+                        err.write_unraisable(py, Some(callback.bind(py)));
+                    }
+                })
+            }),
+        );
+        Ok(())
+    }
+
+    /// Fail the server's `/ready` check *name* while usage is above
+    /// *max_utilization* (a fraction of capacity)
+    #[pyo3(name = "register_health_check", signature = (name, max_utilization=0.95))]
+    pub fn py_register_health_check(&self, name: &str, max_utilization: f64) -> PyResult<()> {
+        if !(max_utilization > 0.0 && max_utilization <= 1.0) {
+            return Err(PyValueError::new_err(format!(
+                "max_utilization must be in (0, 1], got {max_utilization}"
+            )));
+        }
+        health::register_check(
+            name,
+            Arc::new(PoolHealthCheck {
+                pool: self.clone(),
+                max_utilization,
+            }),
+        );
+        Ok(())
+    }
+
+    /// Release cached free blocks when utilization is below *threshold*
+    #[pyo3(name = "trim", signature = (threshold=DEFAULT_TRIM_THRESHOLD))]
+    pub fn py_trim(&self, py: Python<'_>, threshold: f64) -> usize {
+        py.detach(|| self.trim(threshold))
+    }
+
+    #[pyo3(name = "cached_bytes")]
+    pub fn py_cached_bytes(&self) -> usize {
+        self.cached_bytes()
+    }
+
     #[pyo3(name = "get_stats")]
     pub fn py_get_stats(&self, py: Python<'_>) -> PyObject {
-        let stats = self.stats();
-        let dict = pyo3::types::PyDict::new(py);
-        dict.set_item("capacity", stats.capacity).unwrap();
-        dict.set_item("used", stats.used).unwrap();
-        dict.set_item("alloc_count", stats.alloc_count).unwrap();
-        dict.set_item("dealloc_count", stats.dealloc_count).unwrap();
-        dict.set_item("contention_count", stats.contention_count)
-            .unwrap();
-        dict.set_item("peak_usage", stats.peak_usage).unwrap();
-        dict.set_item("last_alloc_time", stats.last_alloc_time)
-            .unwrap();
-        dict.set_item(
-            "utilization_pct",
-            (stats.used as f64 / stats.capacity as f64) * 100.0,
-        )
-        .unwrap();
-        dict.into()
+        stats_dict(py, &self.stats())
     }
 
     #[staticmethod]
@@ -226,12 +507,172 @@ impl PoolAllocator {
     pub fn clone(&self) -> Self {
         Self {
             capacity: self.capacity,
-            used: self.used.clone(),
-            peak_usage: self.peak_usage.clone(),
-            blocks: self.blocks.clone(),
-            stats: self.stats.clone(),
-            creation_time: self.creation_time.clone(),
+            state: self.state.clone(),
+            pressure: self.pressure.clone(),
+        }
+    }
+}
+
+/// Pool statistics as the dict returned by `get_stats()`
+fn stats_dict(py: Python<'_>, stats: &PoolStats) -> Py<PyAny> {
+    let dict = pyo3::types::PyDict::new(py);
+    dict.set_item("capacity", stats.capacity).unwrap();
+    dict.set_item("used", stats.used).unwrap();
+    dict.set_item("alloc_count", stats.alloc_count).unwrap();
+    dict.set_item("dealloc_count", stats.dealloc_count).unwrap();
+    dict.set_item("contention_count", stats.contention_count)
+        .unwrap();
+    dict.set_item("contention_histogram", stats.contention_histogram.to_vec())
+        .unwrap();
+    dict.set_item("peak_usage", stats.peak_usage).unwrap();
+    dict.set_item("last_alloc_time", stats.last_alloc_time)
+        .unwrap();
+    dict.set_item(
+        "utilization_pct",
+        (stats.used as f64 / stats.capacity as f64) * 100.0,
+    )
+    .unwrap();
+    dict.into()
+}
+
+/// Block borrowed from a [`PoolAllocator`] that is handed back on drop.
+///
+/// On the Python side this is a context manager; leaving the `with` block or
+/// calling `release()` returns the memory without waiting for the guard to be
+/// garbage collected. It also implements the buffer protocol, so
+/// `memoryview(block)` reads and writes the pooled allocation in place.
+#[pyclass(module = "forzium_engine")]
+#[derive(Debug)]
+pub struct PoolBlock {
+    pool: PoolAllocator,
+    data: Option<Vec<u8>>,
+    /// Buffer views currently exported to Python
+    exports: usize,
+    /// Accountant entry for the block, dropped when it is released
+    reservation: Option<MemoryReservation>,
+}
+
+impl PoolBlock {
+    /// Contents of the block, or `None` once released.
+    pub fn data(&self) -> Option<&[u8]> {
+        self.data.as_deref()
+    }
+
+    /// Mutable contents of the block, or `None` once released.
+    pub fn data_mut(&mut self) -> Option<&mut [u8]> {
+        self.data.as_deref_mut()
+    }
+
+    /// Return the block to its pool now. Later calls are no-ops.
+    pub fn release(&mut self) {
+        if let Some(data) = self.data.take() {
+            self.pool.deallocate(data);
+        }
+        self.reservation = None;
+    }
+
+    fn live(&self) -> PyResult<&[u8]> {
+        self.data()
+            .ok_or_else(|| PyValueError::new_err("block already released"))
+    }
+
+    /// Release unless Python still holds a view into the block.
+    fn try_release(&mut self) -> PyResult<()> {
+        if self.exports > 0 {
+            return Err(PyBufferError::new_err(
+                "cannot release a block while memoryviews of it exist",
+            ));
+        }
+        self.release();
+        Ok(())
+    }
+}
+
+impl Drop for PoolBlock {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[pymethods]
+impl PoolBlock {
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        self.try_release()?;
+        Ok(false)
+    }
+
+    fn __len__(&self) -> usize {
+        self.data.as_ref().map_or(0, Vec::len)
+    }
+
+    #[pyo3(name = "release")]
+    fn py_release(&mut self) -> PyResult<()> {
+        self.try_release()
+    }
+
+    /// Whether the block has already been returned to the pool
+    #[getter]
+    fn released(&self) -> bool {
+        self.data.is_none()
+    }
+
+    /// Copy of the block contents
+    fn read<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new(py, self.live()?))
+    }
+
+    /// Copy *data* into the block starting at *offset*
+    #[pyo3(signature = (data, offset=0))]
+    fn write(&mut self, data: &[u8], offset: usize) -> PyResult<()> {
+        let len = self.live()?.len();
+        let end = offset
+            .checked_add(data.len())
+            .filter(|&end| end <= len)
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "write of {} bytes at offset {offset} exceeds block size {len}",
+                    data.len()
+                ))
+            })?;
+        if let Some(block) = self.data_mut() {
+            block[offset..end].copy_from_slice(data);
         }
+        Ok(())
+    }
+
+    unsafe fn __getbuffer__(
+        mut slf: PyRefMut<'_, Self>,
+        view: *mut pyo3::ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        let obj = slf.as_ptr();
+        let Some(data) = slf.data.as_mut() else {
+            return Err(PyBufferError::new_err("block already released"));
+        };
+        let (buf, len) = (data.as_mut_ptr(), data.len());
+        // SAFETY: the block cannot be released or resized while `exports` is
+        // non-zero, so `buf` stays valid until the matching release.
+        let rc =
+            unsafe { pyo3::ffi::PyBuffer_FillInfo(view, obj, buf.cast(), len as isize, 0, flags) };
+        if rc == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        slf.exports += 1;
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(mut slf: PyRefMut<'_, Self>, _view: *mut pyo3::ffi::Py_buffer) {
+        slf.exports -= 1;
     }
 }
 
@@ -254,6 +695,130 @@ mod tests {
         assert_eq!(pool.available(), 32);
     }
 
+    #[test]
+    fn health_check_fails_above_max_utilization() {
+        let pool = PoolAllocator::new(100);
+        let check = PoolHealthCheck {
+            pool: pool.clone(),
+            max_utilization: 0.5,
+        };
+        let block = pool.allocate(50).unwrap();
+        assert!(check.check().is_ok());
+        let over = pool.allocate(1).unwrap();
+        assert_eq!(
+            check.check().unwrap_err(),
+            "51 of 100 bytes in use (51% > 50%)"
+        );
+        pool.deallocate(over);
+        pool.deallocate(block);
+        assert!(check.check().is_ok());
+    }
+
+    #[test]
+    fn live_pools_are_reported_until_dropped() {
+        let pool = PoolAllocator::new(12_345);
+        let clone = pool.clone();
+        let _block = pool.allocate(45).unwrap();
+        let ours = |stats: &PoolStats| stats.capacity == 12_345;
+        let live = live_pool_stats();
+        assert_eq!(live.iter().filter(|s| ours(s)).count(), 1);
+        assert_eq!(live.iter().find(|s| ours(s)).unwrap().used, 45);
+        drop(pool);
+        assert!(live_pool_stats().iter().any(ours));
+        drop(clone);
+        assert!(!live_pool_stats().iter().any(ours));
+    }
+
+    #[test]
+    fn blocks_are_reused_within_their_size_class() {
+        let pool = PoolAllocator::new(4096);
+        let block = pool.allocate(100).unwrap();
+        assert_eq!(block.len(), 100);
+        assert_eq!(block.capacity(), 128);
+        let ptr = block.as_ptr();
+        pool.deallocate(block);
+
+        // A different class gets its own block
+        let large = pool.allocate(200).unwrap();
+        assert_ne!(large.as_ptr(), ptr);
+
+        let reused = pool.allocate(120).unwrap();
+        assert_eq!(reused.as_ptr(), ptr);
+        assert_eq!(reused.len(), 120);
+
+        assert_eq!(class_for_size(0), 0);
+        assert_eq!(class_for_size(129), 8);
+        assert_eq!(class_for_capacity(200), Some(7));
+        assert_eq!(class_for_capacity(0), None);
+    }
+
+    #[test]
+    fn dropped_blocks_return_to_the_pool() {
+        let pool = PoolAllocator::new(64);
+        let mut block = pool.allocate_block(16).unwrap().unwrap();
+        block.data_mut().unwrap()[0] = 7;
+        assert_eq!(pool.available(), 48);
+        drop(block);
+        assert_eq!(pool.available(), 64);
+
+        let mut block = pool.allocate_block(16).unwrap().unwrap();
+        block.release();
+        block.release();
+        assert!(block.data().is_none());
+        assert_eq!(pool.available(), 64);
+        assert_eq!(pool.stats().dealloc_count, 2);
+    }
+
+    #[test]
+    fn pressure_callback_fires_once_per_crossing() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let pool = PoolAllocator::new(100);
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = fired.clone();
+        pool.set_pressure_callback(
+            0.5,
+            Arc::new(move |stats| {
+                assert!(stats.used >= 50);
+                counter.fetch_add(1, Ordering::SeqCst);
+            }),
+        );
+
+        let a = pool.allocate(30).unwrap();
+        let b = pool.allocate(30).unwrap();
+        let c = pool.allocate(10).unwrap();
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+        pool.deallocate(b);
+        pool.deallocate(c);
+        let b = pool.allocate(30).unwrap();
+        assert_eq!(fired.load(Ordering::SeqCst), 2);
+
+        pool.clear_pressure_callback();
+        pool.deallocate(a);
+        pool.deallocate(b);
+        pool.allocate(80).unwrap();
+        assert_eq!(fired.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn trim_releases_cached_blocks_below_threshold() {
+        let pool = PoolAllocator::new(1024);
+        let a = pool.allocate(100).unwrap();
+        let b = pool.allocate(600).unwrap();
+        pool.deallocate(a);
+        assert_eq!(pool.cached_bytes(), 128);
+
+        // 600 of 1024 bytes in use is above the threshold
+        assert_eq!(pool.trim(0.5), 0);
+        assert_eq!(pool.cached_bytes(), 128);
+
+        pool.deallocate(b);
+        assert_eq!(pool.trim(0.5), 128 + 1024);
+        assert_eq!(pool.cached_bytes(), 0);
+        assert_eq!(pool.available(), 1024);
+    }
+
     #[test]
     fn create_numa_pools_split_capacity() {
         let pools = PoolAllocator::new_numa(100, 4);
@@ -303,6 +868,40 @@ mod tests {
         assert_eq!(stats.alloc_count, stats.dealloc_count);
     }
 
+    #[test]
+    fn contention_histogram_accounts_for_every_attempt() {
+        assert_eq!(contention_bucket(0), 0);
+        assert_eq!(contention_bucket(1), 1);
+        assert_eq!(contention_bucket(3), 2);
+        assert_eq!(contention_bucket(4), 3);
+        assert_eq!(contention_bucket(usize::MAX), CONTENTION_BUCKETS - 1);
+
+        let pool = PoolAllocator::new(64 * 16);
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        if let Some(block) = pool.allocate(64) {
+                            pool.deallocate(block);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.alloc_count, 16 * 1000);
+        assert_eq!(stats.contention_histogram.iter().sum::<usize>(), 16 * 1000);
+        assert_eq!(stats.used, 0);
+        // Every block went back to the 64-byte class
+        assert_eq!(pool.cached_bytes() % 64, 0);
+        assert!(pool.cached_bytes() <= 64 * 16);
+    }
+
     #[test]
     fn test_peak_usage() {
         let pool = PoolAllocator::new(1000);
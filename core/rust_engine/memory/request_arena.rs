@@ -0,0 +1,144 @@
+//! Bump arena for data that lives exactly as long as one HTTP request
+
+use parking_lot::Mutex;
+use std::ops::{Deref, DerefMut};
+
+/// Arenas kept on the free list between requests.
+const MAX_POOLED_ARENAS: usize = 64;
+/// Capacity an arena keeps when returned; larger buffers are shrunk so one
+/// oversized request doesn't pin its memory forever.
+const MAX_RETAINED_BYTES: usize = 64 * 1024;
+
+static FREE_ARENAS: Mutex<Vec<RequestArena>> = Mutex::new(Vec::new());
+
+/// Location of a string copied into a [`RequestArena`].
+///
+/// Only valid for the arena that returned it, until that arena is reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaStr {
+    start: usize,
+    len: usize,
+}
+
+/// Single growable buffer that request-scoped strings are appended to.
+///
+/// Resetting keeps the capacity, so once an arena has grown to fit a typical
+/// request, later requests copy their query string and headers into it without
+/// allocating.
+#[derive(Debug, Default)]
+pub struct RequestArena {
+    buf: Vec<u8>,
+}
+
+impl RequestArena {
+    /// Create an empty arena with room for `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Copy `s` into the arena.
+    pub fn alloc_str(&mut self, s: &str) -> ArenaStr {
+        let start = self.buf.len();
+        self.buf.extend_from_slice(s.as_bytes());
+        ArenaStr {
+            start,
+            len: s.len(),
+        }
+    }
+
+    /// Borrow a string previously copied in with [`alloc_str`](Self::alloc_str).
+    pub fn get(&self, s: ArenaStr) -> &str {
+        std::str::from_utf8(&self.buf[s.start..s.start + s.len])
+            .expect("arena span does not belong to this arena")
+    }
+
+    /// Bytes currently in use.
+    pub fn used(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Bytes the arena can hold before it has to grow.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Drop every allocation while keeping the buffer for reuse.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+    }
+}
+
+/// Arena borrowed from the free list for the duration of one request.
+///
+/// Dropping the lease resets the arena and returns it to the free list.
+#[derive(Debug)]
+pub struct ArenaLease {
+    arena: Option<RequestArena>,
+}
+
+impl Deref for ArenaLease {
+    type Target = RequestArena;
+
+    fn deref(&self) -> &RequestArena {
+        self.arena.as_ref().expect("arena already returned")
+    }
+}
+
+impl DerefMut for ArenaLease {
+    fn deref_mut(&mut self) -> &mut RequestArena {
+        self.arena.as_mut().expect("arena already returned")
+    }
+}
+
+impl Drop for ArenaLease {
+    fn drop(&mut self) {
+        let Some(mut arena) = self.arena.take() else {
+            return;
+        };
+        arena.reset();
+        arena.buf.shrink_to(MAX_RETAINED_BYTES);
+        let mut free = FREE_ARENAS.lock();
+        if free.len() < MAX_POOLED_ARENAS {
+            free.push(arena);
+        }
+    }
+}
+
+/// Take an arena from the free list, or create one if none is free.
+pub fn lease() -> ArenaLease {
+    let arena = FREE_ARENAS.lock().pop().unwrap_or_default();
+    ArenaLease { arena: Some(arena) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_round_trip_and_reset_keeps_capacity() {
+        let mut arena = RequestArena::with_capacity(16);
+        let a = arena.alloc_str("content-type");
+        let b = arena.alloc_str("é");
+        assert_eq!(arena.get(a), "content-type");
+        assert_eq!(arena.get(b), "é");
+        assert_eq!(arena.used(), "content-type".len() + "é".len());
+
+        let capacity = arena.capacity();
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+        assert_eq!(arena.capacity(), capacity);
+    }
+
+    #[test]
+    fn leases_return_reset_arenas() {
+        let mut first = lease();
+        first.alloc_str(&"x".repeat(MAX_RETAINED_BYTES * 2));
+        drop(first);
+
+        let second = lease();
+        assert_eq!(second.used(), 0);
+        assert!(second.capacity() <= MAX_RETAINED_BYTES);
+    }
+}
@@ -1,110 +1,416 @@
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyCFunction, PyDict};
+use std::collections::VecDeque;
 use std::thread;
-use tokio::sync::oneshot;
+use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use crate::compute::tensor_ops;
+use std::time::{Duration, Instant};
+use crate::compute::engine::ComputeEngine;
+use crate::compute::resource_limits::{CancelToken, with_cancel_token};
+use crate::compute::tensor_ops::{self, RowSink};
+use crate::compute::thread_pool::spawn_in_io_pool;
 use crate::error::ForziumError;
+use crate::runtime_manager;
+
+/// Default number of computations each priority lane may hold
+const DEFAULT_MAX_QUEUED: usize = 1024;
+/// Default number of rows per chunk when iterating over a result, and rows
+/// per block computed by kernels that stream their result
+const DEFAULT_CHUNK_ROWS: usize = 256;
+/// Number of recent task latencies kept for percentile estimates
+const LATENCY_WINDOW: usize = 1024;
+
+/// Scheduling priority of a submitted computation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// Lanes in the order they are drained
+    pub(crate) const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    pub(crate) fn parse(name: &str) -> Result<Self, ForziumError> {
+        match name {
+            "high" => Ok(Priority::High),
+            "normal" => Ok(Priority::Normal),
+            "low" => Ok(Priority::Low),
+            other => Err(ForziumError::Validation(format!(
+                "unknown priority '{other}', expected 'high', 'normal' or 'low'"
+            ))),
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Normal => "normal",
+            Priority::Low => "low",
+        }
+    }
+
+    pub(crate) fn lane(self) -> usize {
+        self as usize
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct SchedulerState {
+    lanes: [VecDeque<Job>; 3],
+    in_flight: usize,
+}
+
+/// Bounded, priority-aware submission queue in front of the shared runtime
+///
+/// At most `max_in_flight` computations run at once. Queued jobs are started
+/// from the highest non-empty lane first, so a backlog of low-priority work
+/// never delays a high-priority submission by more than one running job.
+struct Scheduler {
+    state: Mutex<SchedulerState>,
+    max_in_flight: usize,
+    max_queued: usize,
+}
+
+/// Releases an in-flight slot when a job finishes, even if it panicked
+struct InFlightSlot(Arc<Scheduler>);
+
+impl Drop for InFlightSlot {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().in_flight -= 1;
+        self.0.dispatch();
+    }
+}
+
+impl Scheduler {
+    fn new(max_in_flight: usize, max_queued: usize) -> Self {
+        Self {
+            state: Mutex::new(SchedulerState {
+                lanes: Default::default(),
+                in_flight: 0,
+            }),
+            max_in_flight,
+            max_queued,
+        }
+    }
+
+    /// Queue `job` in its priority lane, rejecting it if the lane is full
+    fn submit(self: &Arc<Self>, priority: Priority, job: Job) -> Result<(), ForziumError> {
+        {
+            let mut state = self.state.lock().unwrap();
+            let lane = &mut state.lanes[priority.lane()];
+            if lane.len() >= self.max_queued {
+                return Err(ForziumError::ResourceLimit(format!(
+                    "{} priority queue is full ({} pending computations)",
+                    priority.name(),
+                    self.max_queued
+                )));
+            }
+            lane.push_back(job);
+        }
+        self.dispatch();
+        Ok(())
+    }
+
+    /// Start queued jobs, highest priority first, while slots are free
+    fn dispatch(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        while state.in_flight < self.max_in_flight {
+            let Some(job) = state.lanes.iter_mut().find_map(VecDeque::pop_front) else {
+                break;
+            };
+            state.in_flight += 1;
+            let slot = InFlightSlot(Arc::clone(self));
+            runtime_manager::spawn_blocking(move || {
+                let _slot = slot;
+                job();
+            });
+        }
+    }
+
+    fn queued(&self) -> [usize; 3] {
+        let state = self.state.lock().unwrap();
+        [0, 1, 2].map(|lane| state.lanes[lane].len())
+    }
+
+    fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+}
+
+/// Submission and completion counters of one `AsyncCompute` instance
+#[derive(Default)]
+struct ComputeStats {
+    submitted: AtomicU64,
+    rejected: AtomicU64,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    cancelled: AtomicU64,
+    /// Most recent submission-to-completion latencies, oldest first
+    latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl ComputeStats {
+    fn record(&self, outcome: &Result<(), ForziumError>, latency: Duration) {
+        let counter = match outcome {
+            Ok(_) => &self.completed,
+            Err(ForziumError::Cancelled(_)) => &self.cancelled,
+            Err(_) => &self.failed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        let mut latencies = self.latencies.lock().unwrap();
+        if latencies.len() == LATENCY_WINDOW {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// Nearest-rank latency percentiles in milliseconds over the recent window
+    fn latency_percentiles_ms<const N: usize>(&self, quantiles: [f64; N]) -> [f64; N] {
+        let mut sorted: Vec<Duration> = self.latencies.lock().unwrap().iter().copied().collect();
+        sorted.sort_unstable();
+        quantiles.map(|q| {
+            let rank = (q * sorted.len() as f64).ceil() as usize;
+            sorted
+                .get(rank.saturating_sub(1))
+                .map_or(0.0, |latency| latency.as_secs_f64() * 1000.0)
+        })
+    }
+}
 
 /// AsyncCompute provides methods to execute computations asynchronously 
 /// without blocking the Python GIL
-#[pyclass]
+///
+/// Submissions pass through a bounded queue with `high`, `normal` and `low`
+/// priority lanes; at most `max_in_flight` computations run concurrently.
+///
+/// Computations run on the blocking pool of the process-wide runtime, which
+/// the HTTP server shares, so instances are cheap to create.
+#[pyclass(frozen)]
 pub struct AsyncCompute {
-    runtime: Arc<Mutex<tokio::runtime::Runtime>>,
+    scheduler: Arc<Scheduler>,
+    stats: Arc<ComputeStats>,
+    /// Operation registry used by `submit`
+    engine: Py<ComputeEngine>,
 }
 
 #[pymethods]
 impl AsyncCompute {
     #[new]
-    fn new() -> Self {
-        let runtime = tokio::runtime::Builder::new_multi_thread()
-            .worker_threads(4)
-            .thread_name("forzium-async-worker")
-            .enable_all()
-            .build()
-            .unwrap();
-        
-        Self {
-            runtime: Arc::new(Mutex::new(runtime)),
+    #[pyo3(signature = (max_in_flight=None, max_queued=None, engine=None))]
+    fn new(
+        py: Python<'_>,
+        max_in_flight: Option<usize>,
+        max_queued: Option<usize>,
+        engine: Option<Py<ComputeEngine>>,
+    ) -> PyResult<Self> {
+        let max_in_flight = max_in_flight.unwrap_or_else(runtime_manager::worker_threads);
+        let max_queued = max_queued.unwrap_or(DEFAULT_MAX_QUEUED);
+        if max_in_flight == 0 || max_queued == 0 {
+            return Err(ForziumError::Validation(
+                "max_in_flight and max_queued must be positive".into(),
+            )
+            .into());
         }
+        let scheduler = Arc::new(Scheduler::new(max_in_flight, max_queued));
+        let engine = match engine {
+            Some(engine) => engine,
+            None => Py::new(py, ComputeEngine::new())?,
+        };
+        
+        Ok(Self {
+            scheduler,
+            stats: Arc::default(),
+            engine,
+        })
+    }
+
+    /// Engine whose operations are available through `submit`
+    ///
+    /// Operations registered on it with `register_op` can be submitted too.
+    #[getter]
+    fn engine(&self, py: Python<'_>) -> Py<ComputeEngine> {
+        self.engine.clone_ref(py)
+    }
+
+    /// Execute any operation supported by the engine asynchronously
+    /// Unknown operations and invalid parameters raise before anything is queued
+    #[pyo3(signature = (operation, data, params=None, priority="normal"))]
+    fn submit(
+        &self,
+        py: Python<'_>,
+        operation: &str,
+        data: Vec<Vec<f64>>,
+        params: Option<Bound<'_, PyDict>>,
+        priority: &str,
+    ) -> PyResult<ComputeHandle> {
+        let params = params.unwrap_or_else(|| PyDict::new(py));
+        let kernel = self.engine.borrow(py).prepare_streaming(py, operation, &params)?;
+        self.spawn_streaming(priority, move |sink| kernel(data, DEFAULT_CHUNK_ROWS, sink))
     }
     
     /// Execute a matrix multiplication asynchronously and return a handle
-    fn matmul<'py>(
+    #[pyo3(signature = (a, b, priority="normal"))]
+    fn matmul(
         &self,
-        py: Python<'py>,
         a: Vec<Vec<f64>>,
         b: Vec<Vec<f64>>,
+        priority: &str,
     ) -> PyResult<ComputeHandle> {
-        // Create channel for returning result
-        let (tx, rx) = oneshot::channel();
-        
-        // Clone the data to move into the new thread
-        let a_clone = a.clone();
-        let b_clone = b.clone();
-        
-        // Start the computation in a separate thread to release GIL
-        py.allow_threads(|| {
-            let runtime = self.runtime.lock().unwrap();
-            runtime.spawn(async move {
-                let result = tensor_ops::matmul(&a_clone, &b_clone);
-                let _ = tx.send(result);
-            });
-        });
-        
-        Ok(ComputeHandle { receiver: rx })
+        self.spawn_streaming(priority, move |sink| {
+            tensor_ops::matmul_rows(&a, &b, DEFAULT_CHUNK_ROWS, sink)
+        })
     }
     
     /// Execute a convolution asynchronously and return a handle
-    fn conv2d<'py>(
+    #[pyo3(signature = (input, kernel, priority="normal"))]
+    fn conv2d(
         &self,
-        py: Python<'py>,
         input: Vec<Vec<f64>>,
         kernel: Vec<Vec<f64>>,
+        priority: &str,
     ) -> PyResult<ComputeHandle> {
-        // Create channel for returning result
-        let (tx, rx) = oneshot::channel();
-        
-        // Clone the data to move into the new thread
-        let input_clone = input.clone();
-        let kernel_clone = kernel.clone();
-        
-        // Start the computation in a separate thread to release GIL
-        py.allow_threads(|| {
-            let runtime = self.runtime.lock().unwrap();
-            runtime.spawn(async move {
-                let result = tensor_ops::conv2d(&input_clone, &kernel_clone);
-                let _ = tx.send(result);
-            });
-        });
-        
-        Ok(ComputeHandle { receiver: rx })
+        self.spawn_compute(priority, move || tensor_ops::conv2d(&input, &kernel))
     }
     
     /// Execute a simd matrix multiplication asynchronously and return a handle
-    fn simd_matmul<'py>(
+    #[pyo3(signature = (a, b, priority="normal"))]
+    fn simd_matmul(
         &self,
-        py: Python<'py>,
         a: Vec<Vec<f64>>,
         b: Vec<Vec<f64>>,
+        priority: &str,
     ) -> PyResult<ComputeHandle> {
-        // Create channel for returning result
-        let (tx, rx) = oneshot::channel();
-        
-        // Clone the data to move into the new thread
-        let a_clone = a.clone();
-        let b_clone = b.clone();
-        
-        // Start the computation in a separate thread to release GIL
-        py.allow_threads(|| {
-            let runtime = self.runtime.lock().unwrap();
-            runtime.spawn(async move {
-                let result = tensor_ops::simd_matmul(&a_clone, &b_clone);
-                let _ = tx.send(result);
-            });
+        self.spawn_compute(priority, move || tensor_ops::simd_matmul(&a, &b))
+    }
+
+    /// Report running computations and pending computations per lane
+    fn queue_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = PyDict::new(py);
+        stats.set_item("in_flight", self.scheduler.in_flight())?;
+        stats.set_item("max_in_flight", self.scheduler.max_in_flight)?;
+        stats.set_item("max_queued", self.scheduler.max_queued)?;
+        let queued = PyDict::new(py);
+        for (priority, count) in Priority::ALL.iter().zip(self.scheduler.queued()) {
+            queued.set_item(priority.name(), count)?;
+        }
+        stats.set_item("queued", queued)?;
+        Ok(stats)
+    }
+
+    /// Report task counters, queue depth and recent task latency percentiles
+    ///
+    /// Latencies run from submission to completion, so they include time
+    /// spent queued, and cover the most recent 1024 tasks.
+    fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let counters = &self.stats;
+        let [p50, p99] = counters.latency_percentiles_ms([0.5, 0.99]);
+        let stats = PyDict::new(py);
+        stats.set_item("submitted", counters.submitted.load(Ordering::Relaxed))?;
+        stats.set_item("rejected", counters.rejected.load(Ordering::Relaxed))?;
+        stats.set_item("completed", counters.completed.load(Ordering::Relaxed))?;
+        stats.set_item("failed", counters.failed.load(Ordering::Relaxed))?;
+        stats.set_item("cancelled", counters.cancelled.load(Ordering::Relaxed))?;
+        stats.set_item("in_flight", self.scheduler.in_flight())?;
+        stats.set_item("queue_depth", self.scheduler.queued().iter().sum::<usize>())?;
+        stats.set_item("latency_p50_ms", p50)?;
+        stats.set_item("latency_p99_ms", p99)?;
+        Ok(stats)
+    }
+}
+
+/// Outcome of a computation, as handed to the caller
+type ComputeOutcome = Result<Vec<Vec<f64>>, ForziumError>;
+
+/// Message sent from a spawned computation to its handle
+enum Chunk {
+    /// The next rows of the result
+    Rows(Vec<Vec<f64>>),
+    /// The computation ended, after sending all its rows if it succeeded
+    End(Result<(), ForziumError>),
+}
+
+/// Receive the rest of a result of which `rows` already arrived
+///
+/// Rows stay in `rows` if the future is dropped, so receiving can resume.
+async fn collect(
+    receiver: &mut mpsc::UnboundedReceiver<Chunk>,
+    rows: &mut Vec<Vec<f64>>,
+) -> ComputeOutcome {
+    loop {
+        match receiver.recv().await {
+            Some(Chunk::Rows(block)) => rows.extend(block),
+            Some(Chunk::End(result)) => {
+                let rows = std::mem::take(rows);
+                return result.map(|()| rows);
+            }
+            None => return Err(task_failed()),
+        }
+    }
+}
+
+impl AsyncCompute {
+    /// Queue `op` under a fresh cancellation token at the given priority
+    /// Returns a handle that receives the outcome and can cancel the operation
+    fn spawn_compute<F>(&self, priority: &str, op: F) -> PyResult<ComputeHandle>
+    where
+        F: FnOnce() -> ComputeOutcome + Send + 'static,
+    {
+        self.spawn_streaming(priority, move |sink| sink(op()?))
+    }
+
+    /// Queue `op`, which hands the rows of its result to a sink as it
+    /// computes them
+    ///
+    /// The rows are sent to the handle as they are computed. The sink stops
+    /// the operation once it is cancelled or nobody can receive the result.
+    fn spawn_streaming<F>(&self, priority: &str, op: F) -> PyResult<ComputeHandle>
+    where
+        F: FnOnce(&mut RowSink<'_>) -> Result<(), ForziumError> + Send + 'static,
+    {
+        let priority = Priority::parse(priority)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let cancel = CancelToken::new();
+        let task_cancel = cancel.clone();
+        let stats = Arc::clone(&self.stats);
+        let submitted = Instant::now();
+
+        // Jobs cancelled while still queued finish without running the kernel
+        let job: Job = Box::new(move || {
+            let result = if task_cancel.is_cancelled() {
+                Err(cancelled())
+            } else {
+                let mut sink = |rows: Vec<Vec<f64>>| {
+                    if task_cancel.is_cancelled() {
+                        return Err(cancelled());
+                    }
+                    tx.send(Chunk::Rows(rows)).map_err(|_| cancelled())
+                };
+                with_cancel_token(&task_cancel, || op(&mut sink))
+            };
+            stats.record(&result, submitted.elapsed());
+            let _ = tx.send(Chunk::End(result));
         });
-        
-        Ok(ComputeHandle { receiver: rx })
+        if let Err(err) = self.scheduler.submit(priority, job) {
+            self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(err.into());
+        }
+        self.stats.submitted.fetch_add(1, Ordering::Relaxed);
+
+        Ok(ComputeHandle {
+            receiver: rx,
+            received: Vec::new(),
+            streaming: false,
+            ready: None,
+            retrieved: false,
+            awaiting: None,
+            callback_delivered: None,
+            cancel,
+            runtime: runtime_manager::handle(),
+        })
     }
 }
 
@@ -112,55 +418,478 @@ impl AsyncCompute {
 /// Allows checking if result is ready and retrieving it
 #[pyclass]
 pub struct ComputeHandle {
-    receiver: oneshot::Receiver<Result<Vec<Vec<f64>>, ForziumError>>,
+    receiver: mpsc::UnboundedReceiver<Chunk>,
+    /// Rows received before the computation ended
+    received: Vec<Vec<f64>>,
+    /// Whether the rows are handed out by a `ResultChunks` iterator
+    streaming: bool,
+    /// Outcome received by a readiness poll but not yet handed out
+    ready: Option<ComputeOutcome>,
+    /// Whether the result has already been returned to the caller
+    retrieved: bool,
+    /// Event loop and future resolved by `__await__`, if the handle is awaited
+    awaiting: Option<(Py<PyAny>, Py<PyAny>)>,
+    /// Set once the outcome was passed to a completion callback, if one is attached
+    callback_delivered: Option<Arc<AtomicBool>>,
+    cancel: CancelToken,
+    runtime: tokio::runtime::Handle,
+}
+
+/// Error reported when a computation ends without sending a result
+fn task_failed() -> ForziumError {
+    ForziumError::Compute("Computation task failed unexpectedly".into())
+}
+
+/// Error reported for computations cancelled through their handle
+fn cancelled() -> ForziumError {
+    ForziumError::Cancelled("computation cancelled".into())
+}
+
+/// Build an event-loop callback that resolves `future` with `outcome`
+///
+/// The callback is a no-op if the future was already cancelled by the caller.
+fn resolve_future_callback<'py>(
+    py: Python<'py>,
+    future: Py<PyAny>,
+    outcome: PyResult<Vec<Vec<f64>>>,
+) -> PyResult<Bound<'py, PyCFunction>> {
+    let outcome = Mutex::new(Some(outcome));
+    PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+        let py = args.py();
+        let future = future.bind(py);
+        if future.call_method0("done")?.is_truthy()? {
+            return Ok(());
+        }
+        match outcome.lock().unwrap().take() {
+            Some(Ok(result)) => future.call_method1("set_result", (result,))?,
+            Some(Err(err)) => future.call_method1("set_exception", (err.into_value(py),))?,
+            None => return Ok(()),
+        };
+        Ok(())
+    })
+}
+
+/// Call a completion callback as `callback(result, error)` on the current thread
+///
+/// Exceptions raised by the callback are reported as unraisable, since
+/// nobody is waiting to receive them.
+fn invoke_done_callback(callback: Py<PyAny>, outcome: ComputeOutcome) {
+    Python::attach(|py| {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(err) => (None, Some(PyErr::from(err).into_value(py))),
+        };
+        if let Err(err) = callback.call1(py, (result, error)) {
+            err.write_unraisable(py, Some(callback.bind(py)));
+        }
+    });
+}
+
+impl ComputeHandle {
+    /// Poll the channel without blocking, caching an outcome once it arrives
+    fn poll_ready(&mut self) -> bool {
+        if self.ready.is_some() {
+            return true;
+        }
+        if self.retrieved {
+            return false;
+        }
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Chunk::Rows(block)) => self.received.extend(block),
+                Ok(Chunk::End(result)) => {
+                    let rows = std::mem::take(&mut self.received);
+                    self.ready = Some(result.map(|()| rows));
+                    return true;
+                }
+                Err(mpsc::error::TryRecvError::Empty) => return false,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.ready = Some(Err(task_failed()));
+                    return true;
+                }
+            }
+        }
+    }
+
+    /// Take the receiver and the rows it already delivered, leaving the
+    /// handle consumed
+    fn take_receiver(&mut self) -> (mpsc::UnboundedReceiver<Chunk>, Vec<Vec<f64>>) {
+        self.retrieved = true;
+        let receiver = std::mem::replace(&mut self.receiver, mpsc::unbounded_channel().1);
+        (receiver, std::mem::take(&mut self.received))
+    }
+
+    /// Fail if the result was already handed out
+    fn ensure_not_retrieved(&self) -> PyResult<()> {
+        if self.retrieved {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                "Result has already been retrieved",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Hand out the cached outcome, marking the handle as consumed
+    fn take_ready(&mut self) -> PyResult<Vec<Vec<f64>>> {
+        self.retrieved = true;
+        self.ready.take().unwrap_or_else(|| Err(task_failed())).map_err(Into::into)
+    }
+
+    /// Block without the GIL until the result arrives or `timeout` elapses
+    ///
+    /// Returns `None` on timeout, leaving the handle ready to be waited on again.
+    fn recv(&mut self, py: Python<'_>, timeout: Option<Duration>) -> PyResult<Option<Vec<Vec<f64>>>> {
+        self.ensure_not_retrieved()?;
+        if !self.poll_ready() {
+            let runtime = self.runtime.clone();
+            let (receiver, rows) = (&mut self.receiver, &mut self.received);
+            let received = py.detach(|| {
+                runtime.block_on(async {
+                    match timeout {
+                        Some(limit) => tokio::time::timeout(limit, collect(receiver, rows)).await.ok(),
+                        None => Some(collect(receiver, rows).await),
+                    }
+                })
+            });
+            match received {
+                None => return Ok(None),
+                Some(outcome) => self.ready = Some(outcome),
+            }
+        }
+        self.take_ready().map(Some)
+    }
 }
 
 #[pymethods]
 impl ComputeHandle {
     /// Check if the result is ready without blocking
-    fn is_ready(&self, py: Python<'_>) -> PyResult<bool> {
-        py.allow_threads(|| {
-            // Poll the receiver without waiting
-            let mut receiver = &self.receiver;
-            match &mut receiver {
-                ref mut rx if rx.is_closed() => Ok(true),
-                _ => Ok(false),
-            }
-        })
+    fn is_ready(&mut self) -> bool {
+        self.poll_ready()
     }
     
     /// Wait for the result and return it
     /// This will block until the result is ready
     fn get_result(&mut self, py: Python<'_>) -> PyResult<Vec<Vec<f64>>> {
-        py.allow_threads(|| {
-            // Take the receiver
-            let rx = std::mem::replace(&mut self.receiver, oneshot::channel().1);
-            
-            // Wait for the result
-            match rx.blocking_recv() {
-                Ok(Ok(result)) => Ok(result),
-                Ok(Err(err)) => Err(err.into()),
-                Err(_) => Err(pyo3::exceptions::PyRuntimeError::new_err(
-                    "Computation task failed unexpectedly",
-                )),
+        self.recv(py, None)?.ok_or_else(|| task_failed().into())
+    }
+
+    /// Wait up to `timeout_ms` milliseconds for the result
+    /// Returns None on timeout; the handle can be waited on again afterwards
+    #[pyo3(signature = (timeout_ms=None))]
+    fn wait(&mut self, py: Python<'_>, timeout_ms: Option<u64>) -> PyResult<Option<Vec<Vec<f64>>>> {
+        self.recv(py, timeout_ms.map(Duration::from_millis))
+    }
+    
+    /// Await the result from asyncio without blocking the event loop
+    ///
+    /// The result is delivered to an asyncio future on the running loop from
+    /// the compute runtime, so `await handle` suspends only the awaiting task.
+    fn __await__(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        self.ensure_not_retrieved()?;
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+        let target = future.clone().unbind();
+
+        // Resolve immediately if a readiness poll already received the result
+        if self.poll_ready() {
+            let outcome = self.take_ready();
+            resolve_future_callback(py, target, outcome)?.call0()?;
+            return Ok(future.call_method0("__await__")?.unbind());
+        }
+
+        // Cancel the computation if the awaiting task is cancelled
+        let token = self.cancel.clone();
+        let on_done = PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+            if args.get_item(0)?.call_method0("cancelled")?.is_truthy()? {
+                token.cancel();
+            }
+            Ok(())
+        })?;
+        future.call_method1("add_done_callback", (on_done,))?;
+
+        let (mut rx, mut rows) = self.take_receiver();
+        self.awaiting = Some((event_loop.clone().unbind(), target.clone_ref(py)));
+        let event_loop = event_loop.unbind();
+
+        let token = self.cancel.clone();
+        self.runtime.spawn(async move {
+            let outcome = collect(&mut rx, &mut rows).await.map_err(PyErr::from);
+            // Cancelled futures are resolved by `cancel` or were abandoned by
+            // the awaiting task, so there is nothing left to deliver
+            if token.is_cancelled() {
+                return;
+            }
+            Python::attach(|py| {
+                // The loop may have closed while the computation was running
+                if let Ok(callback) = resolve_future_callback(py, target, outcome) {
+                    let _ = event_loop.call_method1(py, "call_soon_threadsafe", (callback,));
+                }
+            });
+        });
+
+        Ok(future.call_method0("__await__")?.unbind())
+    }
+    
+    /// Invoke `callback(result, error)` on the IO pool when the computation ends
+    /// Exactly one argument is None. The outcome is handed to the callback, so
+    /// the handle can no longer be waited on or awaited afterwards.
+    fn add_done_callback(&mut self, py: Python<'_>, callback: Py<PyAny>) -> PyResult<()> {
+        self.ensure_not_retrieved()?;
+        if !callback.bind(py).is_callable() {
+            return Err(ForziumError::Validation("callback must be callable".into()).into());
+        }
+        let delivered = Arc::new(AtomicBool::new(false));
+        self.callback_delivered = Some(Arc::clone(&delivered));
+
+        if self.poll_ready() {
+            self.retrieved = true;
+            delivered.store(true, Ordering::SeqCst);
+            let outcome = self.ready.take().unwrap_or_else(|| Err(task_failed()));
+            spawn_in_io_pool(move || invoke_done_callback(callback, outcome));
+            return Ok(());
+        }
+
+        let (mut rx, mut rows) = self.take_receiver();
+        let token = self.cancel.clone();
+        self.runtime.spawn(async move {
+            let mut outcome = collect(&mut rx, &mut rows).await;
+            // Report cancellation even if the kernel finished before noticing it
+            if token.is_cancelled() {
+                outcome = Err(cancelled());
             }
+            delivered.store(true, Ordering::SeqCst);
+            spawn_in_io_pool(move || invoke_done_callback(callback, outcome));
+        });
+        Ok(())
+    }
+
+    /// Cancel the computation and resolve the handle with a cancellation error
+    /// The running kernel stops at its next chunk boundary, freeing its CPU.
+    /// Returns False if the result had already arrived or been retrieved.
+    /// An attached completion callback receives the cancellation error, and
+    /// an iterator over the result raises it after the rows already computed.
+    fn cancel(&mut self, py: Python<'_>) -> PyResult<bool> {
+        if self.streaming {
+            self.cancel.cancel();
+            return Ok(true);
+        }
+        if let Some(delivered) = &self.callback_delivered {
+            if delivered.load(Ordering::SeqCst) {
+                return Ok(false);
+            }
+            self.cancel.cancel();
+            return Ok(true);
+        }
+        if let Some((event_loop, future)) = &self.awaiting {
+            if future.call_method0(py, "done")?.is_truthy(py)? {
+                return Ok(false);
+            }
+            self.cancel.cancel();
+            let callback = resolve_future_callback(py, future.clone_ref(py), Err(cancelled().into()))?;
+            event_loop.call_method1(py, "call_soon_threadsafe", (callback,))?;
+            return Ok(true);
+        }
+        if self.retrieved || self.poll_ready() {
+            return Ok(false);
+        }
+        self.cancel.cancel();
+        self.ready = Some(Err(cancelled()));
+        Ok(true)
+    }
+
+    /// Check whether cancellation was requested for this computation
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+    
+    /// Iterate over the result in chunks of `rows` rows as it is computed
+    /// `matmul`, and `multiply`, `add` and `matmul` submitted through
+    /// `submit`, compute their result a block of rows at a time, so chunks
+    /// are handed out while later rows are still being computed and the whole
+    /// result never exists at once. Other operations hand over their result
+    /// when they finish. Rows are released on the Rust side as they are
+    /// handed out.
+    #[pyo3(signature = (rows=DEFAULT_CHUNK_ROWS))]
+    fn iter_chunks(&mut self, rows: usize) -> PyResult<ResultChunks> {
+        if rows == 0 {
+            return Err(ForziumError::Validation("rows must be positive".into()).into());
+        }
+        self.ensure_not_retrieved()?;
+        if self.poll_ready() {
+            return Ok(ResultChunks {
+                rows: self.take_ready()?.into(),
+                receiver: None,
+                error: None,
+                chunk_rows: rows,
+                runtime: self.runtime.clone(),
+            });
+        }
+        let (receiver, received) = self.take_receiver();
+        self.streaming = true;
+        Ok(ResultChunks {
+            rows: received.into(),
+            receiver: Some(receiver),
+            error: None,
+            chunk_rows: rows,
+            runtime: self.runtime.clone(),
         })
     }
+
+    /// Iterate over the result in chunks of the default size
+    fn __iter__(&mut self) -> PyResult<ResultChunks> {
+        self.iter_chunks(DEFAULT_CHUNK_ROWS)
+    }
     
     /// Try to get the result without blocking
     /// Returns None if the result is not ready
-    fn try_get_result(&mut self, py: Python<'_>) -> PyResult<Option<Vec<Vec<f64>>>> {
-        if !self.is_ready(py)? {
+    fn try_get_result(&mut self) -> PyResult<Option<Vec<Vec<f64>>>> {
+        self.ensure_not_retrieved()?;
+        if !self.poll_ready() {
             return Ok(None);
         }
         
-        let result = self.get_result(py)?;
-        Ok(Some(result))
+        self.take_ready().map(Some)
+    }
+}
+
+/// Iterator over a computation result in row chunks
+///
+/// Waiting for rows that are still being computed releases the GIL. Dropping
+/// the iterator stops the computation at its next block.
+#[pyclass]
+pub struct ResultChunks {
+    /// Rows received but not yet handed out
+    rows: VecDeque<Vec<f64>>,
+    /// Rows still to come from the computation, until it ends
+    receiver: Option<mpsc::UnboundedReceiver<Chunk>>,
+    /// Why the computation failed, raised once the rows before it are out
+    error: Option<ForziumError>,
+    chunk_rows: usize,
+    runtime: tokio::runtime::Handle,
+}
+
+#[pymethods]
+impl ResultChunks {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Vec<Vec<f64>>>> {
+        while self.rows.len() < self.chunk_rows
+            && let Some(receiver) = self.receiver.as_mut()
+        {
+            let runtime = &self.runtime;
+            match py.detach(|| runtime.block_on(receiver.recv())) {
+                Some(Chunk::Rows(block)) => self.rows.extend(block),
+                Some(Chunk::End(result)) => {
+                    self.receiver = None;
+                    self.error = result.err();
+                }
+                None => {
+                    self.receiver = None;
+                    self.error = Some(task_failed());
+                }
+            }
+        }
+        if self.rows.is_empty() {
+            return match self.error.take() {
+                Some(err) => Err(err.into()),
+                None => Ok(None),
+            };
+        }
+        let end = self.chunk_rows.min(self.rows.len());
+        Ok(Some(self.rows.drain(..end).collect()))
+    }
+
+    /// Number of rows received but not yet handed out
+    #[getter]
+    fn remaining_rows(&self) -> usize {
+        self.rows.len()
     }
 }
 
 /// Add a helper function to create a task queue
 #[pyfunction]
-pub fn create_async_compute() -> AsyncCompute {
-    AsyncCompute::new()
+pub fn create_async_compute(py: Python<'_>) -> PyResult<AsyncCompute> {
+    AsyncCompute::new(py, None, None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn scheduler_drains_high_priority_first() {
+        let scheduler = Arc::new(Scheduler::new(1, 8));
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        let (done_tx, done_rx) = mpsc::channel();
+
+        scheduler
+            .submit(Priority::Normal, Box::new(move || gate_rx.recv().unwrap()))
+            .unwrap();
+        for priority in [Priority::Low, Priority::Normal, Priority::High] {
+            let done = done_tx.clone();
+            scheduler
+                .submit(priority, Box::new(move || done.send(priority).unwrap()))
+                .unwrap();
+        }
+        assert_eq!(scheduler.queued(), [1, 1, 1]);
+        assert_eq!(scheduler.in_flight(), 1);
+
+        gate_tx.send(()).unwrap();
+        let order: Vec<Priority> = (0..3).map(|_| done_rx.recv().unwrap()).collect();
+        assert_eq!(order, vec![Priority::High, Priority::Normal, Priority::Low]);
+    }
+
+    #[test]
+    fn scheduler_rejects_full_lane() {
+        let scheduler = Arc::new(Scheduler::new(1, 1));
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        scheduler
+            .submit(Priority::Low, Box::new(move || gate_rx.recv().unwrap()))
+            .unwrap();
+        scheduler.submit(Priority::Low, Box::new(|| {})).unwrap();
+        let err = scheduler.submit(Priority::Low, Box::new(|| {})).unwrap_err();
+        assert!(matches!(err, ForziumError::ResourceLimit(_)));
+        scheduler.submit(Priority::High, Box::new(|| {})).unwrap();
+        gate_tx.send(()).unwrap();
+        assert!(Priority::parse("urgent").is_err());
+    }
+
+    #[test]
+    fn chunks_arrive_before_the_computation_ends() {
+        let (gate_tx, gate_rx) = mpsc::channel::<()>();
+        Python::attach(|py| {
+            let compute = AsyncCompute::new(py, None, None, None).unwrap();
+            let mut handle = compute
+                .spawn_streaming("normal", move |sink| {
+                    sink(vec![vec![1.0]; 3])?;
+                    gate_rx.recv().unwrap();
+                    sink(vec![vec![2.0]])
+                })
+                .unwrap();
+            let mut chunks = handle.iter_chunks(2).unwrap();
+            assert_eq!(chunks.__next__(py).unwrap(), Some(vec![vec![1.0]; 2]));
+            assert_eq!(chunks.remaining_rows(), 1);
+            gate_tx.send(()).unwrap();
+            assert_eq!(chunks.__next__(py).unwrap(), Some(vec![vec![1.0], vec![2.0]]));
+            assert_eq!(chunks.__next__(py).unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn stats_track_outcomes_and_latency() {
+        let stats = ComputeStats::default();
+        assert_eq!(stats.latency_percentiles_ms([0.5]), [0.0]);
+        for ms in 1..=100 {
+            stats.record(&Ok(()), Duration::from_millis(ms));
+        }
+        stats.record(&Err(cancelled()), Duration::from_millis(1));
+        stats.record(&Err(task_failed()), Duration::from_millis(1));
+        assert_eq!(stats.completed.load(Ordering::Relaxed), 100);
+        assert_eq!(stats.cancelled.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.failed.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.latency_percentiles_ms([0.5, 0.99]), [49.0, 99.0]);
+    }
 }
@@ -0,0 +1,535 @@
+//! In-process publish/subscribe hub
+//!
+//! [`publish`] hands a message to every subscriber of its topic without
+//! waiting for any of them: each [`Subscriber`] has a bounded queue of its
+//! own, and one that falls behind loses messages by its [`DropPolicy`]
+//! instead of slowing down the publisher or the other subscribers.
+//! Messages are not kept for subscribers that arrive later.
+//!
+//! Handlers publish with `publish()` and read with `subscribe()`, and the
+//! HTTP server streams topics to clients as Server-Sent Events; see
+//! [`EventStream`](crate::server::event_stream::EventStream).
+
+use hyper::body::Bytes;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use pyo3::exceptions::{PyConnectionError, PyStopAsyncIteration, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyDict, PyString, PyTuple};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::error::ForziumError;
+use crate::gil_utils;
+use crate::runtime_manager::{self, spawn_awaitable};
+
+/// Messages a subscriber queues when no capacity is given.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// What a subscriber whose queue is full does with a new message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Discard the oldest queued message to make room.
+    DropOldest,
+    /// Discard the new message.
+    DropNewest,
+    /// End the subscription once the queued messages are read.
+    Disconnect,
+}
+
+impl FromStr for DropPolicy {
+    type Err = ForziumError;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "drop_oldest" => Ok(Self::DropOldest),
+            "drop_newest" => Ok(Self::DropNewest),
+            "disconnect" => Ok(Self::Disconnect),
+            other => Err(ForziumError::Validation(format!(
+                "policy must be 'drop_oldest', 'drop_newest' or 'disconnect', not '{other}'"
+            ))),
+        }
+    }
+}
+
+/// Message published to a topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub topic: Arc<str>,
+    pub data: Bytes,
+}
+
+/// Message counts of the hub and its current subscribers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BroadcastStats {
+    /// Messages published, whether or not anyone subscribed.
+    pub published: u64,
+    /// Messages queued for a subscriber.
+    pub delivered: u64,
+    /// Messages a full queue discarded.
+    pub dropped: u64,
+    /// Subscriptions ended because their queue was full.
+    pub disconnected: u64,
+    /// Subscriptions open.
+    pub subscribers: usize,
+    /// Topics with at least one subscription.
+    pub topics: usize,
+}
+
+struct Counters {
+    published: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    disconnected: AtomicU64,
+    subscribers: AtomicUsize,
+}
+
+static COUNTERS: Counters = Counters {
+    published: AtomicU64::new(0),
+    delivered: AtomicU64::new(0),
+    dropped: AtomicU64::new(0),
+    disconnected: AtomicU64::new(0),
+    subscribers: AtomicUsize::new(0),
+};
+
+type Registry = RwLock<HashMap<Arc<str>, Vec<Arc<Queue>>>>;
+
+/// Queues of the subscribers of each topic.
+static TOPICS: Lazy<Registry> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Snapshot of the hub's counters.
+pub fn stats() -> BroadcastStats {
+    BroadcastStats {
+        published: COUNTERS.published.load(Ordering::Relaxed),
+        delivered: COUNTERS.delivered.load(Ordering::Relaxed),
+        dropped: COUNTERS.dropped.load(Ordering::Relaxed),
+        disconnected: COUNTERS.disconnected.load(Ordering::Relaxed),
+        subscribers: COUNTERS.subscribers.load(Ordering::Relaxed),
+        topics: TOPICS.read().len(),
+    }
+}
+
+#[derive(Default)]
+struct State {
+    messages: VecDeque<Message>,
+    /// Task waiting for the next message.
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+struct Queue {
+    state: Mutex<State>,
+    capacity: usize,
+    policy: DropPolicy,
+    dropped: AtomicU64,
+}
+
+impl Queue {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Queue `message`, returning whether it was.
+    fn push(&self, message: &Message) -> bool {
+        let mut state = self.state();
+        if state.closed {
+            return false;
+        }
+        if state.messages.len() == self.capacity {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            COUNTERS.dropped.fetch_add(1, Ordering::Relaxed);
+            match self.policy {
+                DropPolicy::DropOldest => {
+                    state.messages.pop_front();
+                }
+                DropPolicy::DropNewest => return false,
+                DropPolicy::Disconnect => {
+                    COUNTERS.disconnected.fetch_add(1, Ordering::Relaxed);
+                    state.closed = true;
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                    return false;
+                }
+            }
+        }
+        state.messages.push_back(message.clone());
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+
+    fn close(&self) {
+        let mut state = self.state();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Publish `data` to the subscribers of `topic`, returning how many
+/// queued it.
+pub fn publish(topic: &str, data: Bytes) -> usize {
+    COUNTERS.published.fetch_add(1, Ordering::Relaxed);
+    let topics = TOPICS.read();
+    let Some((topic, queues)) = topics.get_key_value(topic) else {
+        return 0;
+    };
+    let message = Message {
+        topic: topic.clone(),
+        data,
+    };
+    let delivered = queues.iter().filter(|queue| queue.push(&message)).count();
+    COUNTERS
+        .delivered
+        .fetch_add(delivered as u64, Ordering::Relaxed);
+    delivered
+}
+
+/// Subscribe to `topics` with a queue of `capacity` messages.
+pub fn subscribe(
+    topics: &[&str],
+    capacity: usize,
+    policy: DropPolicy,
+) -> Result<Subscriber, ForziumError> {
+    if topics.is_empty() {
+        return Err(ForziumError::Validation(
+            "subscribe to at least one topic".into(),
+        ));
+    }
+    if capacity == 0 {
+        return Err(ForziumError::Validation("capacity must be positive".into()));
+    }
+    let queue = Arc::new(Queue {
+        state: Mutex::default(),
+        capacity,
+        policy,
+        dropped: AtomicU64::new(0),
+    });
+    let mut registry = TOPICS.write();
+    let mut subscribed: Vec<Arc<str>> = Vec::with_capacity(topics.len());
+    for topic in topics {
+        if subscribed.iter().any(|other| **other == **topic) {
+            continue;
+        }
+        let topic = registry
+            .get_key_value(*topic)
+            .map_or_else(|| Arc::from(*topic), |(topic, _)| topic.clone());
+        registry
+            .entry(topic.clone())
+            .or_default()
+            .push(queue.clone());
+        subscribed.push(topic);
+    }
+    COUNTERS.subscribers.fetch_add(1, Ordering::Relaxed);
+    Ok(Subscriber {
+        queue,
+        topics: subscribed,
+    })
+}
+
+/// Subscription to topics of the hub, ended when dropped
+pub struct Subscriber {
+    queue: Arc<Queue>,
+    topics: Vec<Arc<str>>,
+}
+
+impl Subscriber {
+    /// Next message; `None` once the subscription is closed and its queue
+    /// read. Only the task that polled last is woken.
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<Message>> {
+        let mut state = self.queue.state();
+        if let Some(message) = state.messages.pop_front() {
+            return Poll::Ready(Some(message));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    pub async fn recv(&self) -> Option<Message> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Handle ending the subscription from elsewhere.
+    pub fn closer(&self) -> Closer {
+        Closer(Arc::downgrade(&self.queue))
+    }
+
+    /// Stop receiving messages; those already queued can still be read.
+    pub fn close(&self) {
+        self.queue.close();
+    }
+
+    pub fn topics(&self) -> &[Arc<str>] {
+        &self.topics
+    }
+
+    /// Messages its full queue discarded.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        let mut registry = TOPICS.write();
+        for topic in &self.topics {
+            if let Some(queues) = registry.get_mut(topic) {
+                queues.retain(|queue| !Arc::ptr_eq(queue, &self.queue));
+                if queues.is_empty() {
+                    registry.remove(topic);
+                }
+            }
+        }
+        COUNTERS.subscribers.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Ends a [`Subscriber`] without keeping it alive.
+#[derive(Clone)]
+pub struct Closer(Weak<Queue>);
+
+impl Closer {
+    pub fn close(&self) {
+        if let Some(queue) = self.0.upgrade() {
+            queue.close();
+        }
+    }
+
+    /// Whether its subscriber was dropped.
+    pub fn is_dropped(&self) -> bool {
+        self.0.strong_count() == 0
+    }
+}
+
+/// Bytes of a message: bytes, bytearray, or str as UTF-8.
+fn message_data(value: &Bound<'_, PyAny>) -> PyResult<Bytes> {
+    if let Ok(bytes) = value.cast::<PyBytes>() {
+        return Ok(Bytes::copy_from_slice(bytes.as_bytes()));
+    }
+    if let Ok(bytes) = value.cast::<PyByteArray>() {
+        return Ok(Bytes::from(bytes.to_vec()));
+    }
+    if let Ok(text) = value.cast::<PyString>() {
+        return Ok(Bytes::copy_from_slice(text.to_str()?.as_bytes()));
+    }
+    Err(PyTypeError::new_err(format!(
+        "messages must be bytes or str, not {}",
+        value.get_type().name()?
+    )))
+}
+
+/// Message as a `(topic, data)` tuple.
+fn message_tuple(py: Python<'_>, message: Message) -> PyResult<Py<PyAny>> {
+    Ok((&*message.topic, PyBytes::new(py, &message.data))
+        .into_pyobject(py)?
+        .into_any()
+        .unbind())
+}
+
+/// Publish `data`, bytes or str, to the subscribers of `topic`
+///
+/// Returns how many subscribers queued the message; it is not kept for
+/// later ones.
+#[pyfunction]
+#[pyo3(name = "publish")]
+pub fn publish_message(topic: &str, data: &Bound<'_, PyAny>) -> PyResult<usize> {
+    Ok(publish(topic, message_data(data)?))
+}
+
+/// Subscribe to messages published to `topics`
+///
+/// Up to `capacity` messages are queued until read. When the queue is
+/// full, `policy` decides: "drop_oldest" discards the oldest queued
+/// message, "drop_newest" the new one, and "disconnect" ends the
+/// subscription.
+#[pyfunction]
+#[pyo3(name = "subscribe", signature = (*topics, capacity=DEFAULT_CAPACITY, policy="drop_oldest"))]
+pub fn subscribe_topics(
+    topics: Vec<String>,
+    capacity: usize,
+    policy: &str,
+) -> PyResult<BroadcastSubscription> {
+    let topics: Vec<&str> = topics.iter().map(String::as_str).collect();
+    let subscriber = subscribe(&topics, capacity, policy.parse()?)?;
+    Ok(BroadcastSubscription {
+        subscriber: Arc::new(subscriber),
+    })
+}
+
+/// Messages published to the topics of a `subscribe()`
+///
+/// Read them with `get_message()`, or with `async for topic, data in
+/// subscription`, from one consumer at a time. The subscription ends when
+/// closed, when its queue overflows under the "disconnect" policy, or when
+/// it is garbage collected.
+#[pyclass(module = "forzium_engine", frozen)]
+pub struct BroadcastSubscription {
+    subscriber: Arc<Subscriber>,
+}
+
+#[pymethods]
+impl BroadcastSubscription {
+    /// Next `(topic, data)` message, or None if none arrives within
+    /// `timeout` seconds; waits without holding the GIL
+    ///
+    /// Raises ConnectionError once the subscription ended and its queued
+    /// messages were read.
+    #[pyo3(signature = (timeout=None))]
+    fn get_message(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Option<Py<PyAny>>> {
+        let timeout = timeout
+            .map(|value| {
+                Duration::try_from_secs_f64(value).map_err(|_| {
+                    ForziumError::Validation(
+                        "timeout must be a non-negative number of seconds".into(),
+                    )
+                })
+            })
+            .transpose()?;
+        let subscriber = Arc::clone(&self.subscriber);
+        let message = gil_utils::allow_threads(py, move || {
+            runtime_manager::handle().block_on(async move {
+                match timeout {
+                    Some(limit) => tokio::time::timeout(limit, subscriber.recv()).await.ok(),
+                    None => Some(subscriber.recv().await),
+                }
+            })
+        });
+        match message {
+            None => Ok(None),
+            Some(None) => Err(PyConnectionError::new_err("subscription is closed")),
+            Some(Some(message)) => message_tuple(py, message).map(Some),
+        }
+    }
+
+    fn __aiter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    /// Await the next message; iteration stops once the subscription ended
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let subscriber = Arc::clone(&self.subscriber);
+        spawn_awaitable(
+            py,
+            async move { subscriber.recv().await },
+            |py, message| match message {
+                Some(message) => message_tuple(py, message),
+                None => Err(PyStopAsyncIteration::new_err(())),
+            },
+        )
+    }
+
+    /// Stop receiving messages; those already queued can still be read
+    fn close(&self) {
+        self.subscriber.close();
+    }
+
+    /// Topics subscribed to
+    #[getter]
+    fn topics<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyTuple>> {
+        PyTuple::new(py, self.subscriber.topics().iter().map(|topic| &**topic))
+    }
+
+    /// Messages discarded because the queue was full
+    #[getter]
+    fn dropped(&self) -> u64 {
+        self.subscriber.dropped()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<BroadcastSubscription {}>",
+            self.subscriber.topics().join(", ")
+        )
+    }
+}
+
+/// Report the messages published, delivered and dropped by the hub, and
+/// its open subscriptions
+#[pyfunction]
+pub fn broadcast_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = stats();
+    let dict = PyDict::new(py);
+    dict.set_item("published", stats.published)?;
+    dict.set_item("delivered", stats.delivered)?;
+    dict.set_item("dropped", stats.dropped)?;
+    dict.set_item("disconnected", stats.disconnected)?;
+    dict.set_item("subscribers", stats.subscribers)?;
+    dict.set_item("topics", stats.topics)?;
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(subscriber: &Subscriber) -> Option<Poll<Option<Message>>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        match subscriber.poll_recv(&mut cx) {
+            Poll::Pending => None,
+            ready => Some(ready),
+        }
+    }
+
+    fn data(subscriber: &Subscriber) -> Option<Bytes> {
+        match next(subscriber)? {
+            Poll::Ready(message) => message.map(|message| message.data),
+            Poll::Pending => None,
+        }
+    }
+
+    #[test]
+    fn messages_reach_every_subscriber_of_their_topic() {
+        let first = subscribe(&["test.fanout", "test.other"], 4, DropPolicy::DropOldest).unwrap();
+        let second = subscribe(&["test.fanout"], 4, DropPolicy::DropOldest).unwrap();
+        assert_eq!(publish("test.fanout", Bytes::from_static(b"a")), 2);
+        assert_eq!(publish("test.other", Bytes::from_static(b"b")), 1);
+        assert_eq!(publish("test.nobody", Bytes::from_static(b"c")), 0);
+
+        assert_eq!(data(&first).as_deref(), Some(&b"a"[..]));
+        assert_eq!(data(&first).as_deref(), Some(&b"b"[..]));
+        assert_eq!(data(&second).as_deref(), Some(&b"a"[..]));
+        assert!(next(&second).is_none());
+
+        drop(first);
+        assert_eq!(publish("test.other", Bytes::from_static(b"d")), 0);
+        assert_eq!(publish("test.fanout", Bytes::from_static(b"e")), 1);
+        second.close();
+        assert_eq!(data(&second).as_deref(), Some(&b"e"[..]));
+        assert_eq!(next(&second), Some(Poll::Ready(None)));
+        assert_eq!(publish("test.fanout", Bytes::from_static(b"f")), 0);
+    }
+
+    #[test]
+    fn full_queues_follow_their_drop_policy() {
+        let oldest = subscribe(&["test.full"], 2, DropPolicy::DropOldest).unwrap();
+        let newest = subscribe(&["test.full"], 2, DropPolicy::DropNewest).unwrap();
+        let disconnect = subscribe(&["test.full"], 2, DropPolicy::Disconnect).unwrap();
+        for data in [&b"1"[..], b"2", b"3"] {
+            publish("test.full", Bytes::copy_from_slice(data));
+        }
+        assert_eq!(data(&oldest).as_deref(), Some(&b"2"[..]));
+        assert_eq!(data(&oldest).as_deref(), Some(&b"3"[..]));
+        assert_eq!(data(&newest).as_deref(), Some(&b"1"[..]));
+        assert_eq!(data(&newest).as_deref(), Some(&b"2"[..]));
+        assert_eq!(data(&disconnect).as_deref(), Some(&b"1"[..]));
+        assert_eq!(data(&disconnect).as_deref(), Some(&b"2"[..]));
+        assert_eq!(next(&disconnect), Some(Poll::Ready(None)));
+        for subscriber in [&oldest, &newest, &disconnect] {
+            assert_eq!(subscriber.dropped(), 1);
+        }
+        assert!("drop_all".parse::<DropPolicy>().is_err());
+        assert!(subscribe(&["test.full"], 0, DropPolicy::DropOldest).is_err());
+    }
+}
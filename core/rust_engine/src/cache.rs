@@ -0,0 +1,479 @@
+//! Sharded in-memory LRU cache
+//!
+//! [`Cache`] spreads its keys over shards, each an LRU list behind its own
+//! lock, so concurrent handlers rarely wait for each other. It holds at
+//! most `max_bytes`, split evenly between the shards: storing an entry
+//! evicts the least recently used entries of its shard until the new one
+//! fits. Entries may expire after a time to live, and are dropped when next
+//! read or when they reach the end of the LRU list.
+//!
+//! [`ForziumCache`] exposes a cache to Python handlers, and the HTTP
+//! server's response cache stores responses in one; see
+//! [`ResponseCache`](crate::server::response_cache::ResponseCache).
+
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyDict, PyString, PyTuple};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+
+use crate::client::seconds;
+use crate::error::ForziumError;
+use crate::server::response_cache::CachedResponse;
+
+/// Bytes a `ForziumCache` holds when no bound is given.
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Shards of a `ForziumCache` when no count is given.
+pub const DEFAULT_SHARDS: usize = 16;
+
+struct Entry<V> {
+    value: V,
+    size: usize,
+    expires: Option<Instant>,
+}
+
+impl<V> Entry<V> {
+    fn expired(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+struct Shard<V> {
+    entries: LruCache<String, Entry<V>>,
+    bytes: usize,
+}
+
+impl<V> Shard<V> {
+    fn remove(&mut self, key: &str) -> Option<Entry<V>> {
+        let entry = self.entries.pop(key)?;
+        self.bytes -= entry.size;
+        Some(entry)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    insertions: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+/// Counters and occupancy of a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Reads that found a live entry.
+    pub hits: u64,
+    /// Reads that found no entry, or an expired one.
+    pub misses: u64,
+    /// Entries stored.
+    pub insertions: u64,
+    /// Entries evicted to make room for others.
+    pub evictions: u64,
+    /// Entries dropped because they expired.
+    pub expirations: u64,
+    /// Entries held, including expired ones not dropped yet.
+    pub entries: usize,
+    /// Bytes held by the entries.
+    pub bytes: usize,
+    /// Bytes the cache may hold.
+    pub max_bytes: usize,
+}
+
+/// Thread-safe LRU cache of `V`s bounded in bytes.
+pub struct Cache<V> {
+    shards: Box<[Mutex<Shard<V>>]>,
+    hasher: RandomState,
+    shard_bytes: usize,
+    default_ttl: Option<Duration>,
+    counters: Counters,
+}
+
+impl<V> Cache<V> {
+    /// Cache of at most `max_bytes` over `shards` shards whose entries
+    /// expire after `default_ttl`, if given, unless stored with a TTL of
+    /// their own.
+    pub fn new(max_bytes: usize, shards: usize, default_ttl: Option<Duration>) -> Self {
+        let shards = shards.max(1);
+        Self {
+            shards: (0..shards)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        entries: LruCache::unbounded(),
+                        bytes: 0,
+                    })
+                })
+                .collect(),
+            hasher: RandomState::new(),
+            shard_bytes: max_bytes / shards,
+            default_ttl,
+            counters: Counters::default(),
+        }
+    }
+
+    fn shard(&self, key: &str) -> MutexGuard<'_, Shard<V>> {
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// `read` applied to the live entry for `key`, which becomes the most
+    /// recently used.
+    pub fn get<R>(&self, key: &str, read: impl FnOnce(&V) -> R) -> Option<R> {
+        let now = Instant::now();
+        let mut shard = self.shard(key);
+        let expired = match shard.entries.get(key) {
+            Some(entry) if !entry.expired(now) => {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(read(&entry.value));
+            }
+            Some(_) => shard.remove(key),
+            None => None,
+        };
+        drop(shard);
+        if expired.is_some() {
+            self.counters.expirations.fetch_add(1, Ordering::Relaxed);
+        }
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Whether `key` has a live entry, without counting a read or
+    /// reordering entries.
+    pub fn contains(&self, key: &str) -> bool {
+        let now = Instant::now();
+        self.shard(key)
+            .entries
+            .peek(key)
+            .is_some_and(|entry| !entry.expired(now))
+    }
+
+    /// Store `value`, taking `size` bytes, under `key`, replacing any entry
+    /// it had. The entry expires after `ttl`, or the cache's default TTL.
+    ///
+    /// Returns false, storing nothing, if the entry would take more than a
+    /// shard's share of the cache.
+    pub fn insert(&self, key: String, value: V, size: usize, ttl: Option<Duration>) -> bool {
+        let size = size + key.len();
+        let now = Instant::now();
+        let expires = ttl.or(self.default_ttl).map(|ttl| now + ttl);
+        // dropped once the shard is unlocked, as dropping a Python value
+        // may run arbitrary code
+        let mut dropped = Vec::new();
+        let mut shard = self.shard(&key);
+        dropped.extend(shard.remove(&key));
+        if size > self.shard_bytes {
+            return false;
+        }
+        while shard.bytes + size > self.shard_bytes {
+            let Some((_, entry)) = shard.entries.pop_lru() else {
+                break;
+            };
+            shard.bytes -= entry.size;
+            let counter = match entry.expired(now) {
+                true => &self.counters.expirations,
+                false => &self.counters.evictions,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+            dropped.push(entry);
+        }
+        shard.bytes += size;
+        shard.entries.put(
+            key,
+            Entry {
+                value,
+                size,
+                expires,
+            },
+        );
+        self.counters.insertions.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Remove the entry for `key`, returning whether it was live.
+    pub fn remove(&self, key: &str) -> bool {
+        let entry = self.shard(key).remove(key);
+        entry.is_some_and(|entry| !entry.expired(Instant::now()))
+    }
+
+    /// Remove every entry.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            let dropped = {
+                let mut shard = shard
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                shard.bytes = 0;
+                std::mem::replace(&mut shard.entries, LruCache::unbounded())
+            };
+            drop(dropped);
+        }
+    }
+
+    /// Number of live entries.
+    pub fn len(&self) -> usize {
+        let now = Instant::now();
+        self.shards
+            .iter()
+            .map(|shard| {
+                let shard = shard
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                shard
+                    .entries
+                    .iter()
+                    .filter(|(_, entry)| !entry.expired(now))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Whether the cache has no live entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Snapshot of the cache's counters and occupancy.
+    pub fn stats(&self) -> CacheStats {
+        let (entries, bytes) = self.shards.iter().fold((0, 0), |(entries, bytes), shard| {
+            let shard = shard
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            (entries + shard.entries.len(), bytes + shard.bytes)
+        });
+        CacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            insertions: self.counters.insertions.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            expirations: self.counters.expirations.load(Ordering::Relaxed),
+            entries,
+            bytes,
+            max_bytes: self.shard_bytes * self.shards.len(),
+        }
+    }
+}
+
+/// Value of a `ForziumCache` entry.
+pub enum CacheValue {
+    /// Stored by Python code.
+    Object(Py<PyAny>),
+    /// Stored by the HTTP server's response cache.
+    Response(CachedResponse),
+}
+
+type Registry = Mutex<Vec<(String, Weak<Cache<CacheValue>>)>>;
+
+/// Caches created with a name, whose statistics are published as metrics.
+static NAMED: Lazy<Registry> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Statistics of the named caches still alive.
+pub fn named_stats() -> Vec<(String, CacheStats)> {
+    let mut named = NAMED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    named.retain(|(_, cache)| cache.strong_count() > 0);
+    named
+        .iter()
+        .filter_map(|(name, cache)| Some((name.clone(), cache.upgrade()?.stats())))
+        .collect()
+}
+
+/// Thread-safe in-memory LRU cache for handlers.
+///
+/// Holds at most `max_bytes`, spread over `shards` independently locked
+/// shards; an entry bigger than `max_bytes / shards` is not stored. Entries
+/// expire after `ttl` seconds, if given, unless stored with a TTL of their
+/// own. A cache created with a `name` publishes its statistics as the
+/// `forzium_cache_*` metrics.
+#[pyclass(frozen, module = "forzium_engine")]
+pub struct ForziumCache {
+    cache: Arc<Cache<CacheValue>>,
+}
+
+impl ForziumCache {
+    /// The cache, for the HTTP server's response cache.
+    pub(crate) fn cache(&self) -> Arc<Cache<CacheValue>> {
+        self.cache.clone()
+    }
+}
+
+#[pymethods]
+impl ForziumCache {
+    #[new]
+    #[pyo3(signature = (max_bytes=DEFAULT_MAX_BYTES, *, ttl=None, shards=DEFAULT_SHARDS, name=None))]
+    fn new(
+        max_bytes: usize,
+        ttl: Option<f64>,
+        shards: usize,
+        name: Option<String>,
+    ) -> PyResult<Self> {
+        if shards == 0 || max_bytes < shards {
+            return Err(ForziumError::Validation(
+                "max_bytes and shards must be positive, with max_bytes >= shards".into(),
+            )
+            .into());
+        }
+        let ttl = ttl.map(|ttl| seconds("ttl", ttl)).transpose()?;
+        let cache = Arc::new(Cache::new(max_bytes, shards, ttl));
+        if let Some(name) = name {
+            let mut named = NAMED
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            named.retain(|(other, _)| *other != name);
+            named.push((name, Arc::downgrade(&cache)));
+        }
+        Ok(Self { cache })
+    }
+
+    /// Value stored under `key`, or `default` if there is none or it
+    /// expired. Responses stored by the server's response cache, keyed
+    /// e.g. `"GET /users?page=2"`, are `(status, body, headers)` tuples.
+    #[pyo3(signature = (key, default=None))]
+    fn get(&self, py: Python<'_>, key: &str, default: Option<Py<PyAny>>) -> PyResult<Py<PyAny>> {
+        let found = self.cache.get(key, |value| match value {
+            CacheValue::Object(object) => Ok(object.clone_ref(py)),
+            CacheValue::Response(response) => response_tuple(py, response),
+        });
+        match found {
+            Some(value) => value,
+            None => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    /// Store `value` under `key`, expiring after `ttl` seconds if given.
+    ///
+    /// `size` is what the entry counts against `max_bytes`; it defaults to
+    /// the length of `bytes`, `bytearray` and `str` values and to
+    /// `sys.getsizeof(value)` for others, which does not count the objects
+    /// a container refers to. Returns whether the value was stored.
+    #[pyo3(signature = (key, value, *, ttl=None, size=None))]
+    fn set(
+        &self,
+        key: String,
+        value: &Bound<'_, PyAny>,
+        ttl: Option<f64>,
+        size: Option<usize>,
+    ) -> PyResult<bool> {
+        let ttl = ttl.map(|ttl| seconds("ttl", ttl)).transpose()?;
+        let size = match size {
+            Some(size) => size,
+            None => value_size(value)?,
+        };
+        let value = CacheValue::Object(value.clone().unbind());
+        Ok(self.cache.insert(key, value, size, ttl))
+    }
+
+    /// Remove `key`, returning whether it had a live entry.
+    fn delete(&self, key: &str) -> bool {
+        self.cache.remove(key)
+    }
+
+    /// Remove every entry.
+    fn clear(&self) {
+        self.cache.clear();
+    }
+
+    /// Hits, misses, insertions, evictions and expirations, and the entries
+    /// and bytes held.
+    fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = self.cache.stats();
+        let dict = PyDict::new(py);
+        dict.set_item("hits", stats.hits)?;
+        dict.set_item("misses", stats.misses)?;
+        dict.set_item("insertions", stats.insertions)?;
+        dict.set_item("evictions", stats.evictions)?;
+        dict.set_item("expirations", stats.expirations)?;
+        dict.set_item("entries", stats.entries)?;
+        dict.set_item("bytes", stats.bytes)?;
+        dict.set_item("max_bytes", stats.max_bytes)?;
+        Ok(dict)
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.cache.contains(key)
+    }
+
+    fn __len__(&self) -> usize {
+        self.cache.len()
+    }
+}
+
+fn value_size(value: &Bound<'_, PyAny>) -> PyResult<usize> {
+    if let Ok(bytes) = value.cast::<PyBytes>() {
+        return Ok(bytes.as_bytes().len());
+    }
+    if let Ok(bytes) = value.cast::<PyByteArray>() {
+        return Ok(bytes.len());
+    }
+    if let Ok(text) = value.cast::<PyString>() {
+        return Ok(text.to_str()?.len());
+    }
+    value
+        .py()
+        .import("sys")?
+        .call_method1("getsizeof", (value,))?
+        .extract()
+}
+
+fn response_tuple(py: Python<'_>, response: &CachedResponse) -> PyResult<Py<PyAny>> {
+    let headers = PyDict::new(py);
+    for (name, value) in &response.headers {
+        headers.set_item(name.as_str(), String::from_utf8_lossy(value.as_bytes()))?;
+    }
+    let body = PyBytes::new(py, &response.body);
+    let tuple = PyTuple::new(
+        py,
+        [
+            response.status.as_u16().into_pyobject(py)?.into_any(),
+            body.into_any(),
+            headers.into_any(),
+        ],
+    )?;
+    Ok(tuple.into_any().unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let cache = Cache::new(30, 1, None);
+        assert!(cache.insert("a".into(), 1, 14, None));
+        assert!(cache.insert("b".into(), 2, 14, None));
+        assert_eq!(cache.get("a", |value| *value), Some(1));
+        assert!(cache.insert("c".into(), 3, 14, None));
+        assert_eq!(cache.get("b", |value| *value), None);
+        assert!(cache.contains("a") && cache.contains("c"));
+        assert!(!cache.insert("d".into(), 4, 30, None));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 1, 1));
+        assert_eq!((stats.entries, stats.bytes), (2, 30));
+        assert!(cache.remove("a"));
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().bytes, 0);
+    }
+
+    #[test]
+    fn entries_expire_after_their_ttl() {
+        let cache = Cache::new(1024, 4, Some(Duration::from_millis(10)));
+        cache.insert("default".into(), (), 1, None);
+        cache.insert("long".into(), (), 1, Some(Duration::from_secs(60)));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(cache.get("default", |_| ()), None);
+        assert_eq!(cache.get("long", |_| ()), Some(()));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.stats().expirations, 1);
+    }
+}
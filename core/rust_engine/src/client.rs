@@ -0,0 +1,832 @@
+//! Outbound HTTP client for handlers
+//!
+//! [`ForziumHttpClient`] sends requests from the shared runtime through a
+//! pooled hyper client, so handlers calling other services need no second
+//! HTTP stack. A call returns a [`PendingResponse`] at once: sync handlers
+//! `wait()` on it without holding the GIL, coroutine handlers `await` it
+//! without blocking their event loop.
+//!
+//! Every attempt is bounded by the request timeout. Failed connections are
+//! retried whatever the method; timeouts, broken connections and 502, 503
+//! and 504 responses only for idempotent methods, with exponential backoff
+//! between attempts. Requests carry a W3C `traceparent` header for a new
+//! span: a child of the `traceparent` passed in the request headers, e.g.
+//! the one of the request being handled, or the root of a new trace. Only
+//! plaintext `http://` URLs are supported.
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use hyper::{Method, Request, StatusCode, Uri};
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use pyo3::exceptions::{PyConnectionError, PyTimeoutError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyCFunction, PyDict, PyString};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::task::AbortHandle;
+use tracing::debug;
+
+use crate::error::ForziumError;
+use crate::gil_utils;
+use crate::runtime_manager;
+use crate::trace_context::child_traceparent;
+
+/// Response statuses retried for idempotent requests.
+const RETRY_STATUSES: [StatusCode; 3] = [
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// Outcomes of the requests sent by all clients of the process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// Requests that received a response, whatever its status.
+    pub responses: u64,
+    /// Requests whose last attempt timed out.
+    pub timeouts: u64,
+    /// Requests that failed without a response for another reason.
+    pub errors: u64,
+    /// Attempts repeated after a failed one.
+    pub retries: u64,
+}
+
+struct Counters {
+    responses: AtomicU64,
+    timeouts: AtomicU64,
+    errors: AtomicU64,
+    retries: AtomicU64,
+}
+
+static COUNTERS: Counters = Counters {
+    responses: AtomicU64::new(0),
+    timeouts: AtomicU64::new(0),
+    errors: AtomicU64::new(0),
+    retries: AtomicU64::new(0),
+};
+
+/// Snapshot of the client counters.
+pub fn stats() -> ClientStats {
+    ClientStats {
+        responses: COUNTERS.responses.load(Ordering::Relaxed),
+        timeouts: COUNTERS.timeouts.load(Ordering::Relaxed),
+        errors: COUNTERS.errors.load(Ordering::Relaxed),
+        retries: COUNTERS.retries.load(Ordering::Relaxed),
+    }
+}
+
+/// Why a request got no response.
+#[derive(Debug)]
+enum SendError {
+    /// The attempt did not finish within the request timeout.
+    Timeout(Duration),
+    /// No connection could be made, so the request was not sent.
+    Connect(String),
+    /// The connection failed while the request or response was in transit.
+    Transfer(String),
+    /// The request was cancelled through its [`PendingResponse`].
+    Cancelled,
+}
+
+impl From<SendError> for PyErr {
+    fn from(err: SendError) -> PyErr {
+        match err {
+            SendError::Timeout(limit) => {
+                PyTimeoutError::new_err(format!("request timed out after {}s", limit.as_secs_f64()))
+            }
+            SendError::Connect(msg) => {
+                PyConnectionError::new_err(format!("could not connect: {msg}"))
+            }
+            SendError::Transfer(msg) => PyConnectionError::new_err(msg),
+            SendError::Cancelled => ForziumError::Cancelled("request cancelled".into()).into(),
+        }
+    }
+}
+
+/// `err` followed by its sources, since hyper's own messages are terse.
+pub(crate) fn describe(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// A request ready to be sent, as often as it is retried.
+struct Call {
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+    timeout: Option<Duration>,
+}
+
+impl Call {
+    fn is_idempotent(&self) -> bool {
+        matches!(
+            self.method,
+            Method::GET
+                | Method::HEAD
+                | Method::PUT
+                | Method::DELETE
+                | Method::OPTIONS
+                | Method::TRACE
+        )
+    }
+}
+
+/// A response with its body read.
+struct Received {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+type Outcome = Result<ClientResponse, SendError>;
+
+/// Connection pool and defaults shared by the calls of one client.
+struct Inner {
+    client: Client<HttpConnector, Full<Bytes>>,
+    base_url: Option<String>,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl Inner {
+    /// Absolute `http://` URI of `url`, relative to the base URL if any.
+    fn resolve(&self, url: &str) -> Result<Uri, ForziumError> {
+        let full = match &self.base_url {
+            Some(base) if !url.contains("://") => {
+                format!(
+                    "{}/{}",
+                    base.trim_end_matches('/'),
+                    url.trim_start_matches('/')
+                )
+            }
+            _ => url.to_string(),
+        };
+        let uri: Uri = full
+            .parse()
+            .map_err(|err| ForziumError::Validation(format!("invalid URL '{full}': {err}")))?;
+        match uri.scheme_str() {
+            Some("http") if uri.authority().is_some() => Ok(uri),
+            Some("https") => Err(ForziumError::Validation(format!(
+                "cannot request '{full}': only http:// URLs are supported"
+            ))),
+            _ => Err(ForziumError::Validation(format!(
+                "URL must be an absolute http:// URL, got '{full}'"
+            ))),
+        }
+    }
+
+    /// Send `call`, retrying as the client is configured to.
+    async fn send(&self, call: Call) -> Outcome {
+        let start = Instant::now();
+        let mut attempt = 0;
+        loop {
+            let outcome = self.attempt(&call).await;
+            let retry = attempt < self.retries
+                && match &outcome {
+                    Ok(received) => {
+                        call.is_idempotent() && RETRY_STATUSES.contains(&received.status)
+                    }
+                    Err(SendError::Connect(_)) => true,
+                    Err(_) => call.is_idempotent(),
+                };
+            if !retry {
+                let counter = match &outcome {
+                    Ok(_) => &COUNTERS.responses,
+                    Err(SendError::Timeout(_)) => &COUNTERS.timeouts,
+                    Err(_) => &COUNTERS.errors,
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+                return outcome.map(|received| ClientResponse {
+                    status: received.status.as_u16(),
+                    headers: received.headers,
+                    body: received.body,
+                    url: call.uri.to_string(),
+                    elapsed: start.elapsed(),
+                    attempts: attempt + 1,
+                });
+            }
+            COUNTERS.retries.fetch_add(1, Ordering::Relaxed);
+            debug!(method = %call.method, uri = %call.uri, attempt = attempt + 1, "retrying outbound request");
+            tokio::time::sleep(self.backoff.saturating_mul(2u32.saturating_pow(attempt))).await;
+            attempt += 1;
+        }
+    }
+
+    async fn attempt(&self, call: &Call) -> Result<Received, SendError> {
+        let mut request = Request::new(Full::new(call.body.clone()));
+        *request.method_mut() = call.method.clone();
+        *request.uri_mut() = call.uri.clone();
+        *request.headers_mut() = call.headers.clone();
+        let exchange = async {
+            let response = self.client.request(request).await.map_err(|err| {
+                if err.is_connect() {
+                    SendError::Connect(describe(&err))
+                } else {
+                    SendError::Transfer(describe(&err))
+                }
+            })?;
+            let (parts, body) = response.into_parts();
+            let body = body
+                .collect()
+                .await
+                .map_err(|err| SendError::Transfer(describe(&err)))?
+                .to_bytes();
+            Ok(Received {
+                status: parts.status,
+                headers: parts.headers,
+                body,
+            })
+        };
+        match call.timeout {
+            Some(limit) => tokio::time::timeout(limit, exchange)
+                .await
+                .unwrap_or(Err(SendError::Timeout(limit))),
+            None => exchange.await,
+        }
+    }
+}
+
+/// `value` seconds, which must be positive, as a duration.
+pub(crate) fn seconds(name: &str, value: f64) -> Result<Duration, ForziumError> {
+    Duration::try_from_secs_f64(value)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| {
+            ForziumError::Validation(format!("{name} must be a positive number of seconds"))
+        })
+}
+
+fn insert_headers(
+    map: &mut HeaderMap,
+    headers: HashMap<String, String>,
+) -> Result<(), ForziumError> {
+    for (name, value) in headers {
+        let header = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| ForziumError::Validation(format!("invalid header name '{name}'")))?;
+        let value = HeaderValue::from_str(&value)
+            .map_err(|_| ForziumError::Validation(format!("invalid value for header '{name}'")))?;
+        map.insert(header, value);
+    }
+    Ok(())
+}
+
+/// Pooled HTTP/1.1 client sending requests from the shared runtime
+///
+/// `timeout` bounds each attempt and `connect_timeout` each connection
+/// attempt; None disables them. Up to `retries` further attempts are made,
+/// waiting `backoff` seconds before the first and twice as long before each
+/// next. `headers` are sent with every request.
+#[pyclass(module = "forzium_engine", frozen)]
+pub struct ForziumHttpClient {
+    inner: Arc<Inner>,
+}
+
+#[pymethods]
+impl ForziumHttpClient {
+    #[new]
+    #[pyo3(signature = (
+        base_url=None,
+        *,
+        timeout=Some(30.0),
+        connect_timeout=Some(10.0),
+        retries=2,
+        backoff=0.1,
+        max_idle_per_host=32,
+        idle_timeout=90.0,
+        headers=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        base_url: Option<String>,
+        timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        retries: u32,
+        backoff: f64,
+        max_idle_per_host: usize,
+        idle_timeout: f64,
+        headers: Option<HashMap<String, String>>,
+    ) -> PyResult<Self> {
+        let timeout = timeout.map(|value| seconds("timeout", value)).transpose()?;
+        let connect_timeout = connect_timeout
+            .map(|value| seconds("connect_timeout", value))
+            .transpose()?;
+        let idle_timeout = seconds("idle_timeout", idle_timeout)?;
+        let backoff = Duration::try_from_secs_f64(backoff).map_err(|_| {
+            ForziumError::Validation("backoff must be a non-negative number of seconds".into())
+        })?;
+
+        let mut connector = HttpConnector::new();
+        connector.set_connect_timeout(connect_timeout);
+        connector.set_nodelay(true);
+        let client = Client::builder(TokioExecutor::new())
+            .pool_idle_timeout(idle_timeout)
+            .pool_max_idle_per_host(max_idle_per_host)
+            .pool_timer(TokioTimer::new())
+            .timer(TokioTimer::new())
+            .build(connector);
+
+        let mut default_headers = HeaderMap::new();
+        default_headers.insert(
+            USER_AGENT,
+            HeaderValue::from_static(concat!("forzium-engine/", env!("CARGO_PKG_VERSION"))),
+        );
+        insert_headers(&mut default_headers, headers.unwrap_or_default())?;
+        let inner = Inner {
+            client,
+            base_url,
+            headers: default_headers,
+            timeout,
+            retries,
+            backoff,
+        };
+        if inner.base_url.is_some() {
+            inner.resolve("")?;
+        }
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// Start a request and return its pending response
+    ///
+    /// `body` is bytes or a string sent as UTF-8; `json` an object sent as
+    /// JSON instead. `timeout` overrides the client's for this request.
+    #[pyo3(signature = (method, url, *, headers=None, body=None, json=None, timeout=None))]
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        body: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<PendingResponse> {
+        let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+            .map_err(|_| ForziumError::Validation(format!("invalid HTTP method '{method}'")))?;
+        let uri = self.inner.resolve(url)?;
+        let mut map = self.inner.headers.clone();
+        insert_headers(&mut map, headers.unwrap_or_default())?;
+        let body = match (body, json) {
+            (Some(_), Some(_)) => {
+                return Err(
+                    ForziumError::Validation("pass either body or json, not both".into()).into(),
+                );
+            }
+            (Some(body), None) => match body.cast::<PyString>() {
+                Ok(text) => Bytes::from(text.to_str()?.to_owned()),
+                Err(_) => Bytes::from(body.extract::<Vec<u8>>()?),
+            },
+            (None, Some(value)) => {
+                let text: String = value
+                    .py()
+                    .import("json")?
+                    .call_method1("dumps", (value,))?
+                    .extract()?;
+                map.entry(CONTENT_TYPE)
+                    .or_insert(HeaderValue::from_static("application/json"));
+                Bytes::from(text)
+            }
+            (None, None) => Bytes::new(),
+        };
+        let parent = map.get("traceparent").and_then(|value| value.to_str().ok());
+        let traceparent =
+            HeaderValue::from_str(&child_traceparent(parent)).expect("traceparent is ASCII");
+        map.insert("traceparent", traceparent);
+        let timeout = match timeout {
+            Some(value) => Some(seconds("timeout", value)?),
+            None => self.inner.timeout,
+        };
+
+        let call = Call {
+            method: method.clone(),
+            uri: uri.clone(),
+            headers: map,
+            body,
+            timeout,
+        };
+        let (tx, rx) = oneshot::channel();
+        let inner = Arc::clone(&self.inner);
+        let task = runtime_manager::handle().spawn(async move {
+            let _ = tx.send(inner.send(call).await);
+        });
+        Ok(PendingResponse {
+            receiver: Some(rx),
+            task: task.abort_handle(),
+            method,
+            uri,
+        })
+    }
+
+    /// Start a GET request; see `request`
+    #[pyo3(signature = (url, *, headers=None, body=None, json=None, timeout=None))]
+    fn get(
+        &self,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        body: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<PendingResponse> {
+        self.request("GET", url, headers, body, json, timeout)
+    }
+
+    /// Start a POST request; see `request`
+    #[pyo3(signature = (url, *, headers=None, body=None, json=None, timeout=None))]
+    fn post(
+        &self,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        body: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<PendingResponse> {
+        self.request("POST", url, headers, body, json, timeout)
+    }
+
+    /// Start a PUT request; see `request`
+    #[pyo3(signature = (url, *, headers=None, body=None, json=None, timeout=None))]
+    fn put(
+        &self,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        body: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<PendingResponse> {
+        self.request("PUT", url, headers, body, json, timeout)
+    }
+
+    /// Start a PATCH request; see `request`
+    #[pyo3(signature = (url, *, headers=None, body=None, json=None, timeout=None))]
+    fn patch(
+        &self,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        body: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<PendingResponse> {
+        self.request("PATCH", url, headers, body, json, timeout)
+    }
+
+    /// Start a DELETE request; see `request`
+    #[pyo3(signature = (url, *, headers=None, body=None, json=None, timeout=None))]
+    fn delete(
+        &self,
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        body: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        timeout: Option<f64>,
+    ) -> PyResult<PendingResponse> {
+        self.request("DELETE", url, headers, body, json, timeout)
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.inner.base_url {
+            Some(base) => format!("ForziumHttpClient('{base}')"),
+            None => "ForziumHttpClient()".to_string(),
+        }
+    }
+}
+
+/// Build an event-loop callback that resolves `future` with `outcome`
+///
+/// The callback is a no-op if the future was already cancelled by the caller.
+fn resolve_future_callback<'py>(
+    py: Python<'py>,
+    future: Py<PyAny>,
+    outcome: Outcome,
+) -> PyResult<Bound<'py, PyCFunction>> {
+    let outcome = Mutex::new(Some(outcome));
+    PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+        let py = args.py();
+        let future = future.bind(py);
+        if future.call_method0("done")?.is_truthy()? {
+            return Ok(());
+        }
+        match outcome.lock().unwrap().take() {
+            Some(Ok(response)) => future.call_method1("set_result", (response,))?,
+            Some(Err(err)) => {
+                future.call_method1("set_exception", (PyErr::from(err).into_value(py),))?
+            }
+            None => return Ok(()),
+        };
+        Ok(())
+    })
+}
+
+/// A request in flight
+///
+/// `wait()` from sync code or `await` from a coroutine to get its
+/// `ClientResponse`; either can be done once.
+#[pyclass(module = "forzium_engine")]
+pub struct PendingResponse {
+    receiver: Option<oneshot::Receiver<Outcome>>,
+    task: AbortHandle,
+    method: Method,
+    uri: Uri,
+}
+
+impl PendingResponse {
+    fn take_receiver(&mut self) -> PyResult<oneshot::Receiver<Outcome>> {
+        self.receiver.take().ok_or_else(|| {
+            pyo3::exceptions::PyRuntimeError::new_err("Response has already been retrieved")
+        })
+    }
+}
+
+#[pymethods]
+impl PendingResponse {
+    /// Block until the response arrives, without holding the GIL
+    fn wait(&mut self, py: Python<'_>) -> PyResult<ClientResponse> {
+        let receiver = self.take_receiver()?;
+        let outcome = gil_utils::allow_threads(py, move || {
+            receiver
+                .blocking_recv()
+                .unwrap_or(Err(SendError::Cancelled))
+        });
+        outcome.map_err(Into::into)
+    }
+
+    /// Await the response from asyncio without blocking the event loop
+    ///
+    /// Cancelling the awaiting task cancels the request.
+    fn __await__(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let receiver = self.take_receiver()?;
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+        let task = self.task.clone();
+        let on_done =
+            PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+                if args.get_item(0)?.call_method0("cancelled")?.is_truthy()? {
+                    task.abort();
+                }
+                Ok(())
+            })?;
+        future.call_method1("add_done_callback", (on_done,))?;
+
+        let target = future.clone().unbind();
+        let event_loop = event_loop.unbind();
+        runtime_manager::handle().spawn(async move {
+            let outcome = receiver.await.unwrap_or(Err(SendError::Cancelled));
+            gil_utils::with_gil(|py| {
+                // The loop may have closed while the request was in flight
+                if let Ok(callback) = resolve_future_callback(py, target, outcome) {
+                    let _ = event_loop.call_method1(py, "call_soon_threadsafe", (callback,));
+                }
+            });
+        });
+        Ok(future.call_method0("__await__")?.unbind())
+    }
+
+    /// Whether the request has finished, successfully or not
+    fn done(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Cancel the request; returns False if it had already finished
+    fn cancel(&self) -> bool {
+        if self.task.is_finished() {
+            return false;
+        }
+        self.task.abort();
+        true
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<PendingResponse {} {}>", self.method, self.uri)
+    }
+}
+
+/// Response received by a `ForziumHttpClient`, with its body read
+#[pyclass(module = "forzium_engine", frozen)]
+pub struct ClientResponse {
+    status: u16,
+    headers: HeaderMap,
+    body: Bytes,
+    url: String,
+    elapsed: Duration,
+    attempts: u32,
+}
+
+#[pymethods]
+impl ClientResponse {
+    /// HTTP status code
+    #[getter]
+    fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Whether the status is below 400
+    #[getter]
+    fn ok(&self) -> bool {
+        self.status < 400
+    }
+
+    /// Headers with lowercase names; repeated headers are joined by ", "
+    #[getter]
+    fn headers<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for name in self.headers.keys() {
+            let values: Vec<&str> = self
+                .headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect();
+            dict.set_item(name.as_str(), values.join(", "))?;
+        }
+        Ok(dict)
+    }
+
+    /// Response body
+    #[getter]
+    fn content<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.body)
+    }
+
+    /// URL the request was sent to
+    #[getter]
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Seconds from the first attempt until the body was read
+    #[getter]
+    fn elapsed(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+
+    /// Attempts made, including retries
+    #[getter]
+    fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Body decoded as UTF-8, invalid sequences replaced
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Body parsed as JSON
+    fn json<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        py.import("json")?
+            .call_method1("loads", (PyBytes::new(py, &self.body),))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<ClientResponse {}>", self.status)
+    }
+}
+
+/// Report the outcomes of the requests sent by all HTTP clients
+#[pyfunction]
+pub fn http_client_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = stats();
+    let dict = PyDict::new(py);
+    dict.set_item("responses", stats.responses)?;
+    dict.set_item("timeouts", stats.timeouts)?;
+    dict.set_item("errors", stats.errors)?;
+    dict.set_item("retries", stats.retries)?;
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::net::TcpListener;
+
+    /// Serve requests on a local port, answering the first `failures`
+    /// with 503, and return the port and the number of requests seen.
+    async fn flaky_server(failures: usize) -> (u16, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let counter = Arc::clone(&counter);
+                tokio::spawn(async move {
+                    let service = hyper::service::service_fn(
+                        move |request: Request<hyper::body::Incoming>| {
+                            let count = counter.fetch_add(1, Ordering::SeqCst);
+                            let traceparent = request.headers().get("traceparent").cloned();
+                            async move {
+                                let status = if count < failures {
+                                    StatusCode::SERVICE_UNAVAILABLE
+                                } else {
+                                    StatusCode::OK
+                                };
+                                let mut response =
+                                    hyper::Response::new(Full::new(Bytes::from("ok")));
+                                *response.status_mut() = status;
+                                if let Some(value) = traceparent {
+                                    response.headers_mut().insert("traceparent", value);
+                                }
+                                Ok::<_, Infallible>(response)
+                            }
+                        },
+                    );
+                    let _ = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(hyper_util::rt::TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+        (port, seen)
+    }
+
+    fn client(retries: u32) -> Inner {
+        Inner {
+            client: Client::builder(TokioExecutor::new()).build(HttpConnector::new()),
+            base_url: None,
+            headers: HeaderMap::new(),
+            timeout: Some(Duration::from_secs(5)),
+            retries,
+            backoff: Duration::from_millis(1),
+        }
+    }
+
+    fn call(method: Method, port: u16) -> Call {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            HeaderValue::from_static("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        );
+        Call {
+            method,
+            uri: format!("http://127.0.0.1:{port}/").parse().unwrap(),
+            headers,
+            body: Bytes::new(),
+            timeout: Some(Duration::from_secs(5)),
+        }
+    }
+
+    #[test]
+    fn idempotent_requests_are_retried() {
+        runtime_manager::handle().block_on(async {
+            let (port, seen) = flaky_server(2).await;
+            let response = client(2).send(call(Method::GET, port)).await.unwrap();
+            assert_eq!(response.status, 200);
+            assert_eq!(response.attempts, 3);
+            assert_eq!(seen.load(Ordering::SeqCst), 3);
+            assert!(response.headers.contains_key("traceparent"));
+
+            let (port, seen) = flaky_server(1).await;
+            let response = client(2).send(call(Method::POST, port)).await.unwrap();
+            assert_eq!(response.status, 503);
+            assert_eq!(seen.load(Ordering::SeqCst), 1);
+        });
+    }
+
+    #[test]
+    fn connection_failures_are_reported() {
+        runtime_manager::handle().block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            drop(listener);
+            let before = stats();
+            let outcome = client(1).send(call(Method::POST, port)).await;
+            assert!(matches!(outcome, Err(SendError::Connect(_))));
+            let after = stats();
+            assert!(after.errors > before.errors);
+            assert!(after.retries > before.retries);
+        });
+    }
+
+    #[test]
+    fn urls_must_be_plain_http() {
+        let mut inner = client(0);
+        inner.base_url = Some("http://service:8080/api/".into());
+        assert_eq!(
+            inner.resolve("/users/1").unwrap().to_string(),
+            "http://service:8080/api/users/1"
+        );
+        assert_eq!(
+            inner.resolve("http://other/x").unwrap().to_string(),
+            "http://other/x"
+        );
+        assert!(inner.resolve("https://other/x").is_err());
+        inner.base_url = None;
+        assert!(inner.resolve("/relative").is_err());
+    }
+}
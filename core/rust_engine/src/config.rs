@@ -0,0 +1,883 @@
+//! Unified configuration of the engine.
+//!
+//! A [`ForziumConfig`] gathers the settings of the HTTP server, the thread
+//! pools, resource limits and logging. They are read from a TOML or YAML
+//! file, or a dict, and overridden by `FORZIUM_<SECTION>__<KEY>` environment
+//! variables such as `FORZIUM_SERVER__CONNECTION_LIMIT=200` or
+//! `FORZIUM_RESOURCE_LIMITS__OPERATION_LIMITS__MATMUL=1000000`:
+//!
+//! ```toml
+//! [server]
+//! connection_limit = 200
+//! request_timeout_secs = 15
+//!
+//! [thread_pool]
+//! thread_count = 8
+//!
+//! [resource_limits]
+//! max_concurrent_ops = 16
+//! operation_timeouts = { matmul = 2.5 }
+//!
+//! [logging]
+//! level = "info"
+//! modules = { "server::http_engine" = "debug" }
+//! ```
+//!
+//! Settings are validated when loaded, unknown keys included, and applied in
+//! one call; those left out keep their current values.
+//!
+//! [`apply_config`] changes settings while the server runs: the request
+//! timeout, rate limits, resource limits and log levels. Every change is
+//! logged and kept in an audit log, [`config_audit_log`], with its old and
+//! new value. Other settings are only read at startup, so changing them this
+//! way is refused.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use pyo3::exceptions::PyImportError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::compute::resource_limits::{
+    set_operation_limit, set_operation_timeout, set_queue_config, set_resource_limits,
+};
+use crate::compute::thread_pool::{ThreadPoolConfig, ThreadPoolManager};
+use crate::error::ForziumError;
+use crate::logging;
+use crate::memory::accountant::set_memory_limit;
+use crate::server::http_engine::ForziumHttpServer;
+
+/// Prefix of the environment variables overriding settings.
+pub const ENV_PREFIX: &str = "FORZIUM_";
+
+/// Separator between the section, key and sub-keys of an environment
+/// variable's name.
+const ENV_SEPARATOR: &str = "__";
+
+/// Changes kept in the audit log; older ones are dropped.
+pub const AUDIT_LOG_CAPACITY: usize = 256;
+
+/// Settings `apply_config` may change while running: single keys, and
+/// sections ending in a dot.
+const RELOADABLE: &[&str] = &[
+    "server.request_timeout_secs",
+    "rate_limit.",
+    "resource_limits.",
+    "logging.",
+];
+
+/// Every setting, by section.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    #[serde(skip_serializing_if = "is_default")]
+    pub server: ServerSettings,
+    #[serde(skip_serializing_if = "is_default")]
+    pub thread_pool: ThreadPoolSettings,
+    #[serde(skip_serializing_if = "is_default")]
+    pub rate_limit: RateLimitSettings,
+    #[serde(skip_serializing_if = "is_default")]
+    pub resource_limits: ResourceLimitSettings,
+    #[serde(skip_serializing_if = "is_default")]
+    pub logging: LoggingSettings,
+}
+
+/// Settings of `ForziumHttpServer`, named after its setters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handler_threads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_memory_budget: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_request_threshold_ms: Option<u64>,
+}
+
+/// Settings of the default thread pool and the named pools built from it,
+/// as taken by `configure_rayon_thread_pool`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThreadPoolSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack_size_mb: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_lifetime_seconds: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breadth_first: Option<bool>,
+}
+
+/// Request rate limits, enforced by the application's
+/// `RateLimitMiddleware`; the engine only checks and records them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    /// Requests allowed per window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_secs: Option<f64>,
+}
+
+/// Settings of `set_resource_limits`, `set_queue_config`,
+/// `set_memory_limit` and the per-operation limits and timeouts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResourceLimitSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_elements: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_ops: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<u64>,
+    /// Process-wide memory accounting limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_limit_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_max_depth: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_timeout_secs: Option<f64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub operation_limits: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub operation_timeouts: BTreeMap<String, f64>,
+}
+
+/// Log levels, as taken by `set_log_level`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSettings {
+    /// Level of the whole engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    /// Levels of individual modules.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Settings {
+    /// Settings of a tree of sections, checked.
+    pub fn from_value(value: Value) -> Result<Self, ForziumError> {
+        let settings: Self = serde_json::from_value(value)
+            .map_err(|err| ForziumError::Validation(format!("invalid configuration: {err}")))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// The settings as a tree of sections, without those left out.
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    fn validate(&self) -> Result<(), ForziumError> {
+        let server = &self.server;
+        positive("server.connection_limit", server.connection_limit)?;
+        positive(
+            "server.connection_timeout_secs",
+            server.connection_timeout_secs,
+        )?;
+        positive("server.request_timeout_secs", server.request_timeout_secs)?;
+        positive("server.read_timeout_secs", server.read_timeout_secs)?;
+        positive("server.write_timeout_secs", server.write_timeout_secs)?;
+        positive("server.handler_threads", server.handler_threads)?;
+        positive("server.request_memory_budget", server.request_memory_budget)?;
+
+        let pool = &self.thread_pool;
+        positive("thread_pool.thread_count", pool.thread_count)?;
+        positive("thread_pool.stack_size_mb", pool.stack_size_mb)?;
+
+        positive("rate_limit.limit", self.rate_limit.limit)?;
+        seconds("rate_limit.window_secs", self.rate_limit.window_secs)?;
+
+        let limits = &self.resource_limits;
+        positive("resource_limits.max_elements", limits.max_elements)?;
+        positive(
+            "resource_limits.max_concurrent_ops",
+            limits.max_concurrent_ops,
+        )?;
+        positive("resource_limits.max_memory_bytes", limits.max_memory_bytes)?;
+        positive(
+            "resource_limits.memory_limit_bytes",
+            limits.memory_limit_bytes,
+        )?;
+        seconds(
+            "resource_limits.queue_timeout_secs",
+            limits.queue_timeout_secs,
+        )?;
+        for (operation, max) in &limits.operation_limits {
+            positive(
+                &format!("resource_limits.operation_limits.{operation}"),
+                Some(*max),
+            )?;
+        }
+        for (operation, secs) in &limits.operation_timeouts {
+            seconds(
+                &format!("resource_limits.operation_timeouts.{operation}"),
+                Some(*secs),
+            )?;
+        }
+
+        let logging = &self.logging;
+        for level in logging.level.iter().chain(logging.modules.values()) {
+            logging::parse_level(level)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the process-wide settings: thread pools, resource limits and
+    /// log levels.
+    pub fn apply(&self) -> PyResult<()> {
+        let pool = &self.thread_pool;
+        if !is_default(pool) {
+            let manager = ThreadPoolManager::global();
+            let current = manager.get_config();
+            let config = ThreadPoolConfig {
+                thread_count: pool.thread_count.unwrap_or(current.thread_count),
+                stack_size: pool
+                    .stack_size_mb
+                    .map_or(current.stack_size, |mb| mb * 1024 * 1024),
+                thread_lifetime_ms: pool
+                    .thread_lifetime_seconds
+                    .map_or(current.thread_lifetime_ms, |secs| secs * 1000),
+                breadth_first: pool.breadth_first.unwrap_or(current.breadth_first),
+                use_numa_affinity: current.use_numa_affinity,
+            };
+            manager
+                .update_config(config)
+                .map_err(pyo3::exceptions::PyRuntimeError::new_err)?;
+        }
+
+        let limits = &self.resource_limits;
+        set_resource_limits(
+            limits.max_elements,
+            limits.max_concurrent_ops,
+            limits.enabled,
+            limits.max_memory_bytes,
+        )?;
+        set_queue_config(limits.queue_max_depth, limits.queue_timeout_secs)?;
+        if limits.memory_limit_bytes.is_some() {
+            set_memory_limit(limits.memory_limit_bytes)?;
+        }
+        for (operation, max) in &limits.operation_limits {
+            set_operation_limit(operation, Some(*max))?;
+        }
+        for (operation, secs) in &limits.operation_timeouts {
+            set_operation_timeout(operation, Some(*secs))?;
+        }
+
+        let levels = &self.logging;
+        if let Some(level) = &levels.level {
+            logging::set_level(None, Some(level))?;
+        }
+        for (module, level) in &levels.modules {
+            logging::set_level(Some(module), Some(level))?;
+        }
+        Ok(())
+    }
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+fn positive<T: Default + PartialEq>(name: &str, value: Option<T>) -> Result<(), ForziumError> {
+    if value == Some(T::default()) {
+        return Err(ForziumError::Validation(format!("{name} must be positive")));
+    }
+    Ok(())
+}
+
+fn seconds(name: &str, value: Option<f64>) -> Result<(), ForziumError> {
+    match value {
+        Some(secs) if !(secs.is_finite() && secs > 0.0) => Err(ForziumError::Validation(format!(
+            "{name} must be a positive number of seconds"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Merge `overlay` into `base`, tables key by key.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Settings given by `FORZIUM_<SECTION>__<KEY>` variables among `vars`.
+///
+/// Values are read as TOML values, so `200`, `2.5` and `true` are numbers and
+/// booleans, and anything else, such as `debug`, is a string.
+pub fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Value {
+    let mut tree = Value::Object(Map::new());
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if !path.contains(ENV_SEPARATOR) {
+            continue;
+        }
+        let value = raw
+            .trim()
+            .parse::<toml_edit::Value>()
+            .map(|value| toml_value(&value))
+            .unwrap_or(Value::String(raw));
+        let keys: Vec<&str> = path.split(ENV_SEPARATOR).collect();
+        let leaf = keys.into_iter().rev().fold(value, |value, key| {
+            let mut table = Map::new();
+            table.insert(key.to_lowercase(), value);
+            Value::Object(table)
+        });
+        merge(&mut tree, leaf);
+    }
+    tree
+}
+
+/// Sections of a TOML document.
+pub fn parse_toml(text: &str) -> Result<Value, ForziumError> {
+    let document = text
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|err| ForziumError::Validation(format!("invalid TOML configuration: {err}")))?;
+    Ok(toml_table(document.as_table()))
+}
+
+fn toml_table(table: &toml_edit::Table) -> Value {
+    Value::Object(
+        table
+            .iter()
+            .map(|(key, item)| (key.to_string(), toml_item(item)))
+            .collect(),
+    )
+}
+
+fn toml_item(item: &toml_edit::Item) -> Value {
+    match item {
+        toml_edit::Item::None => Value::Null,
+        toml_edit::Item::Value(value) => toml_value(value),
+        toml_edit::Item::Table(table) => toml_table(table),
+        toml_edit::Item::ArrayOfTables(tables) => tables.iter().map(toml_table).collect(),
+    }
+}
+
+fn toml_value(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(text) => Value::String(text.value().clone()),
+        toml_edit::Value::Integer(int) => Value::from(*int.value()),
+        toml_edit::Value::Float(float) => Value::from(*float.value()),
+        toml_edit::Value::Boolean(flag) => Value::Bool(*flag.value()),
+        toml_edit::Value::Datetime(datetime) => Value::String(datetime.value().to_string()),
+        toml_edit::Value::Array(array) => array.iter().map(toml_value).collect(),
+        toml_edit::Value::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_string(), toml_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// The JSON-like value of a Python object of dicts, lists, strings,
+/// numbers, booleans and None.
+pub fn py_to_value(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if obj.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(flag) = obj.cast::<PyBool>() {
+        Ok(Value::Bool(flag.is_true()))
+    } else if let Ok(int) = obj.cast::<PyInt>() {
+        Ok(match int.extract::<i64>() {
+            Ok(int) => Value::from(int),
+            Err(_) => Value::from(int.extract::<u64>()?),
+        })
+    } else if let Ok(float) = obj.cast::<PyFloat>() {
+        Ok(Value::from(float.value()))
+    } else if let Ok(text) = obj.cast::<PyString>() {
+        Ok(Value::String(text.to_cow()?.into_owned()))
+    } else if let Ok(dict) = obj.cast::<PyDict>() {
+        dict.iter()
+            .map(|(key, value)| Ok((key.str()?.to_cow()?.into_owned(), py_to_value(&value)?)))
+            .collect::<PyResult<Map<_, _>>>()
+            .map(Value::Object)
+    } else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+        obj.try_iter()?
+            .map(|item| py_to_value(&item?))
+            .collect::<PyResult<Vec<_>>>()
+            .map(Value::Array)
+    } else {
+        Err(ForziumError::Validation(format!(
+            "unsupported configuration value of type {}",
+            obj.get_type().name()?
+        ))
+        .into())
+    }
+}
+
+/// The Python object of a JSON-like value.
+pub fn value_to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(flag) => PyBool::new(py, *flag).to_owned().into_any(),
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(int), _) => int.into_pyobject(py)?.into_any(),
+            (None, Some(int)) => int.into_pyobject(py)?.into_any(),
+            _ => number
+                .as_f64()
+                .unwrap_or(f64::NAN)
+                .into_pyobject(py)?
+                .into_any(),
+        },
+        Value::String(text) => PyString::new(py, text).into_any(),
+        Value::Array(items) => PyList::new(
+            py,
+            items
+                .iter()
+                .map(|item| value_to_py(py, item))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?
+        .into_any(),
+        Value::Object(table) => {
+            let dict = PyDict::new(py);
+            for (key, value) in table {
+                dict.set_item(key, value_to_py(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/// Sections of a configuration file, parsed by its extension: `.toml`, or
+/// `.yaml` and `.yml` with PyYAML.
+fn read_file(py: Python<'_>, path: &Path) -> PyResult<Value> {
+    let text = std::fs::read_to_string(path)?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let tree = match extension.as_deref() {
+        Some("toml") => parse_toml(&text)?,
+        Some("yaml" | "yml") => {
+            let yaml = py.import("yaml").map_err(|_| {
+                PyImportError::new_err("PyYAML is required to load YAML configuration")
+            })?;
+            py_to_value(&yaml.call_method1("safe_load", (text,))?)?
+        }
+        _ => {
+            return Err(ForziumError::Validation(format!(
+                "configuration file {} must end in .toml, .yaml or .yml",
+                path.display()
+            ))
+            .into());
+        }
+    };
+    // an empty YAML document is None
+    Ok(if tree.is_null() {
+        Value::Object(Map::new())
+    } else {
+        tree
+    })
+}
+
+/// One setting changed by [`ForziumConfig::apply`] or [`apply_config`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Dotted path of the setting, e.g. `server.request_timeout_secs`.
+    pub key: String,
+    /// Value before the change; null if it was never configured.
+    pub old: Value,
+    pub new: Value,
+    /// What made the change, e.g. `apply_config` or a file reloaded on
+    /// SIGHUP.
+    pub source: String,
+    pub time: SystemTime,
+}
+
+impl ConfigChange {
+    fn to_py<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("key", &self.key)?;
+        dict.set_item("old", value_to_py(py, &self.old)?)?;
+        dict.set_item("new", value_to_py(py, &self.new)?)?;
+        dict.set_item("source", &self.source)?;
+        let time = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        dict.set_item("time", time.as_secs_f64())?;
+        Ok(dict)
+    }
+}
+
+/// Process-wide settings applied so far, merged. Server settings belong to
+/// each server and are read from it instead.
+static APPLIED: Lazy<Mutex<Value>> = Lazy::new(|| Mutex::new(Value::Object(Map::new())));
+
+/// Most recent changes, oldest first.
+static AUDIT_LOG: Lazy<Mutex<VecDeque<ConfigChange>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Leaf settings of a tree, by path.
+fn leaves(path: &mut Vec<String>, value: &Value, out: &mut Vec<(Vec<String>, Value)>) {
+    match value {
+        Value::Object(table) if !table.is_empty() => {
+            for (key, value) in table {
+                path.push(key.clone());
+                leaves(path, value, out);
+                path.pop();
+            }
+        }
+        _ => out.push((path.clone(), value.clone())),
+    }
+}
+
+/// The tree holding `value` at `path`.
+fn tree_at(path: &[String], value: Value) -> Value {
+    path.iter().rev().fold(value, |value, key| {
+        let mut table = Map::new();
+        table.insert(key.clone(), value);
+        Value::Object(table)
+    })
+}
+
+fn is_reloadable(key: &str) -> bool {
+    RELOADABLE
+        .iter()
+        .any(|allowed| match allowed.strip_suffix('.') {
+            Some(section) => key
+                .strip_prefix(section)
+                .is_some_and(|rest| rest.starts_with('.')),
+            None => key == *allowed,
+        })
+}
+
+/// Settings of `settings` that differ from `current`, as (path, old, new).
+fn diff(current: &Value, settings: &Settings) -> Vec<(Vec<String>, Value, Value)> {
+    let mut updates = Vec::new();
+    leaves(&mut Vec::new(), &settings.to_value(), &mut updates);
+    updates
+        .into_iter()
+        .filter_map(|(path, new)| {
+            let old = path
+                .iter()
+                .try_fold(current, |tree, key| tree.get(key))
+                .cloned()
+                .unwrap_or(Value::Null);
+            (old != new).then_some((path, old, new))
+        })
+        .collect()
+}
+
+/// Apply `settings` and record what changed. With `reload`, only the changed
+/// settings are applied, and all of them must be reloadable.
+fn commit(
+    settings: &Settings,
+    server: Option<&mut ForziumHttpServer>,
+    source: &str,
+    reload: bool,
+) -> PyResult<Vec<ConfigChange>> {
+    let mut applied = APPLIED.lock();
+    let mut current = applied.clone();
+    match &server {
+        Some(server) => {
+            let settings = serde_json::to_value(server.settings()).unwrap_or_default();
+            merge(&mut current, tree_at(&["server".into()], settings));
+        }
+        None if reload && !is_default(&settings.server) => {
+            return Err(ForziumError::Validation(
+                "server settings can only be applied to a server".into(),
+            )
+            .into());
+        }
+        None => {}
+    }
+    let changed = diff(&current, settings);
+
+    let reloaded;
+    let settings = if reload {
+        let fixed: Vec<String> = changed
+            .iter()
+            .map(|(path, _, _)| path.join("."))
+            .filter(|key| !is_reloadable(key))
+            .collect();
+        if !fixed.is_empty() {
+            return Err(ForziumError::Validation(format!(
+                "{} cannot be changed while running; restart to apply",
+                fixed.join(", ")
+            ))
+            .into());
+        }
+        let mut tree = Value::Object(Map::new());
+        for (path, _, new) in &changed {
+            merge(&mut tree, tree_at(path, new.clone()));
+        }
+        reloaded = Settings::from_value(tree)?;
+        &reloaded
+    } else {
+        settings
+    };
+    settings.apply()?;
+    if let Some(server) = server {
+        server.apply_settings(&settings.server);
+    }
+
+    let time = SystemTime::now();
+    let changes: Vec<ConfigChange> = changed
+        .into_iter()
+        .map(|(path, old, new)| {
+            if path[0] != "server" {
+                merge(&mut applied, tree_at(&path, new.clone()));
+            }
+            ConfigChange {
+                key: path.join("."),
+                old,
+                new,
+                source: source.to_string(),
+                time,
+            }
+        })
+        .collect();
+    let mut log = AUDIT_LOG.lock();
+    for change in &changes {
+        info!(key = %change.key, old = %change.old, new = %change.new, source, "configuration changed");
+        if log.len() == AUDIT_LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(change.clone());
+    }
+    Ok(changes)
+}
+
+/// Validated settings of the server, thread pools, resource limits and
+/// logging; see the `config` module.
+#[pyclass(name = "ForziumConfig", module = "forzium_engine", frozen)]
+pub struct ForziumConfig {
+    settings: Settings,
+}
+
+impl ForziumConfig {
+    /// The validated settings.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+}
+
+#[pymethods]
+impl ForziumConfig {
+    /// Settings from a dict of sections, such as
+    /// `{"server": {"connection_limit": 200}}`, overridden by environment
+    /// variables if `env` is true.
+    #[new]
+    #[pyo3(signature = (settings=None, *, env=false))]
+    fn new(settings: Option<&Bound<'_, PyAny>>, env: bool) -> PyResult<Self> {
+        let mut tree = match settings {
+            Some(settings) => py_to_value(settings)?,
+            None => Value::Object(Map::new()),
+        };
+        if env {
+            merge(&mut tree, env_overrides(std::env::vars()));
+        }
+        Ok(Self {
+            settings: Settings::from_value(tree)?,
+        })
+    }
+
+    /// Load settings from a `.toml`, `.yaml` or `.yml` file, if given, and
+    /// `FORZIUM_<SECTION>__<KEY>` environment variables, which take
+    /// precedence, unless `env` is false.
+    #[staticmethod]
+    #[pyo3(signature = (path=None, *, env=true))]
+    fn load(py: Python<'_>, path: Option<PathBuf>, env: bool) -> PyResult<Self> {
+        let mut tree = match path {
+            Some(path) => read_file(py, &path)?,
+            None => Value::Object(Map::new()),
+        };
+        if env {
+            merge(&mut tree, env_overrides(std::env::vars()));
+        }
+        Ok(Self {
+            settings: Settings::from_value(tree)?,
+        })
+    }
+
+    /// The settings as a dict of sections, without those left out.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        value_to_py(py, &self.settings.to_value())
+    }
+
+    /// Apply the thread pool, resource limit and logging settings, and the
+    /// server settings to `server` if given. Server settings other than the
+    /// request timeout take effect on its next `serve`.
+    #[pyo3(signature = (server=None))]
+    fn apply(&self, mut server: Option<PyRefMut<'_, ForziumHttpServer>>) -> PyResult<()> {
+        commit(
+            &self.settings,
+            server.as_deref_mut(),
+            "ForziumConfig.apply",
+            false,
+        )?;
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ForziumConfig({})", self.settings.to_value())
+    }
+}
+
+/// Change settings while running, and return the changes as dicts of `key`,
+/// `old`, `new`, `source` and `time`.
+///
+/// `updates` is a `ForziumConfig` or a dict of sections. The request
+/// timeout, rate limits, resource limits and log levels may change; server
+/// settings need `server`. Settings equal to their current values are
+/// ignored, so a reloaded configuration file may repeat the others. Nothing
+/// is applied if a setting is invalid or needs a restart.
+#[pyfunction]
+#[pyo3(signature = (updates, server=None, *, source="apply_config"))]
+pub fn apply_config<'py>(
+    py: Python<'py>,
+    updates: &Bound<'py, PyAny>,
+    mut server: Option<PyRefMut<'_, ForziumHttpServer>>,
+    source: &str,
+) -> PyResult<Bound<'py, PyList>> {
+    let settings = match updates.cast::<ForziumConfig>() {
+        Ok(config) => config.get().settings.clone(),
+        Err(_) => Settings::from_value(py_to_value(updates)?)?,
+    };
+    let changes = commit(&settings, server.as_deref_mut(), source, true)?;
+    PyList::new(
+        py,
+        changes
+            .iter()
+            .map(|change| change.to_py(py))
+            .collect::<PyResult<Vec<_>>>()?,
+    )
+}
+
+/// The most recent configuration changes, oldest first, as returned by
+/// `apply_config`.
+#[pyfunction]
+pub fn config_audit_log(py: Python<'_>) -> PyResult<Bound<'_, PyList>> {
+    let log = AUDIT_LOG.lock().clone();
+    PyList::new(
+        py,
+        log.iter()
+            .map(|change| change.to_py(py))
+            .collect::<PyResult<Vec<_>>>()?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn files_and_environment_merge_into_checked_settings() {
+        let mut tree = parse_toml(
+            "[server]\nconnection_limit = 200\nrequest_timeout_secs = 15\n\
+             [resource_limits]\noperation_timeouts = { matmul = 2.5 }\n\
+             [logging]\nlevel = \"info\"\n",
+        )
+        .unwrap();
+        let env = [
+            ("FORZIUM_SERVER__CONNECTION_LIMIT", "300"),
+            ("FORZIUM_LOGGING__LEVEL", "debug"),
+            ("FORZIUM_RESOURCE_LIMITS__OPERATION_LIMITS__MATMUL", "1000"),
+            ("FORZIUM_LOG", "trace"),
+            ("PATH", "/bin"),
+        ];
+        merge(
+            &mut tree,
+            env_overrides(env.map(|(name, value)| (name.into(), value.into()))),
+        );
+        let settings = Settings::from_value(tree).unwrap();
+        assert_eq!(settings.server.connection_limit, Some(300));
+        assert_eq!(settings.server.request_timeout_secs, Some(15));
+        assert_eq!(settings.logging.level.as_deref(), Some("debug"));
+        assert_eq!(settings.resource_limits.operation_limits["matmul"], 1000);
+        assert_eq!(settings.resource_limits.operation_timeouts["matmul"], 2.5);
+        assert_eq!(
+            settings.to_value()["server"],
+            json!({"connection_limit": 300, "request_timeout_secs": 15})
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let invalid = [
+            json!({"server": {"conection_limit": 1}}),
+            json!({"server": {"handler_threads": 0}}),
+            json!({"server": {"connection_limit": "many"}}),
+            json!({"resource_limits": {"queue_timeout_secs": -1.0}}),
+            json!({"logging": {"modules": {"server": "loud"}}}),
+            json!({"tls": {}}),
+        ];
+        for tree in invalid {
+            assert!(Settings::from_value(tree.clone()).is_err(), "{tree}");
+        }
+        assert!(parse_toml("[server\n").is_err());
+    }
+
+    #[test]
+    fn reloads_record_changes_and_refuse_startup_settings() {
+        let settings = |tree: Value| Settings::from_value(tree).unwrap();
+        let changes = commit(
+            &settings(json!({"rate_limit": {"limit": 10, "window_secs": 1.0}})),
+            None,
+            "test",
+            true,
+        )
+        .unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|change| change.old.is_null()));
+
+        let changes = commit(
+            &settings(json!({"rate_limit": {"limit": 20, "window_secs": 1.0}})),
+            None,
+            "reload",
+            true,
+        )
+        .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "rate_limit.limit");
+        assert_eq!((&changes[0].old, &changes[0].new), (&json!(10), &json!(20)));
+        assert_eq!(AUDIT_LOG.lock().back(), Some(&changes[0]));
+
+        let restart = settings(json!({"thread_pool": {"thread_count": 3}}));
+        assert!(commit(&restart, None, "test", true).is_err());
+        let server = settings(json!({"server": {"request_timeout_secs": 5}}));
+        assert!(commit(&server, None, "test", true).is_err());
+        assert_eq!(APPLIED.lock()["rate_limit"]["limit"], json!(20));
+
+        assert!(is_reloadable("server.request_timeout_secs"));
+        assert!(is_reloadable("resource_limits.operation_limits.matmul"));
+        assert!(!is_reloadable("server.handler_threads"));
+        assert!(!is_reloadable("logging_extra.level"));
+    }
+}
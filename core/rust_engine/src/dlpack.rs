@@ -0,0 +1,465 @@
+//! DLPack export of compute results.
+//!
+//! A [`Tensor`] holds a row-major `float64` matrix in Rust memory and
+//! implements the Python side of the DLPack protocol, so
+//! `torch.from_dlpack(t)`, `numpy.from_dlpack(t)`, `cupy.from_dlpack(t)`
+//! or `jax.dlpack.from_dlpack(t)` read it in place instead of going
+//! through a list. Consumers that ask for DLPack 1.0 share the memory,
+//! flagged read-only; the matrix stays alive until the tensor and every
+//! consumer are done with it. Older consumers, which cannot be told the
+//! memory is read-only, get a copy.
+//!
+//! The matrix compute bindings take [`Matrix`] arguments, so they accept
+//! tensors as well as lists of rows and return a tensor when given one.
+//! The kernels work on rows, so a tensor argument is copied into rows and
+//! the result copied into a new tensor; neither goes through Python
+//! objects.
+
+use std::ffi::{CStr, c_void};
+use std::sync::Arc;
+
+use pyo3::exceptions::{PyBufferError, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+
+/// `kDLCPU`, the only device tensors live on.
+const DEVICE_CPU: i32 = 1;
+/// `kDLFloat`.
+const DTYPE_FLOAT: u8 = 2;
+
+/// Name of a capsule holding an unconsumed `DLManagedTensor`.
+const DLTENSOR: &CStr = c"dltensor";
+/// Name of a capsule holding an unconsumed `DLManagedTensorVersioned`.
+const DLTENSOR_VERSIONED: &CStr = c"dltensor_versioned";
+
+/// DLPack version of versioned exports.
+const DLPACK_VERSION: (u32, u32) = (1, 0);
+/// `DLPACK_FLAG_BITMASK_READ_ONLY`.
+const FLAG_READ_ONLY: u64 = 1 << 0;
+/// `DLPACK_FLAG_BITMASK_IS_COPIED`.
+const FLAG_IS_COPIED: u64 = 1 << 1;
+
+#[repr(C)]
+struct DLPackVersion {
+    major: u32,
+    minor: u32,
+}
+
+#[repr(C)]
+struct DLDevice {
+    device_type: i32,
+    device_id: i32,
+}
+
+#[repr(C)]
+struct DLDataType {
+    code: u8,
+    bits: u8,
+    lanes: u16,
+}
+
+#[repr(C)]
+struct DLTensor {
+    data: *mut c_void,
+    device: DLDevice,
+    ndim: i32,
+    dtype: DLDataType,
+    shape: *mut i64,
+    strides: *mut i64,
+    byte_offset: u64,
+}
+
+#[repr(C)]
+struct DLManagedTensor {
+    dl_tensor: DLTensor,
+    manager_ctx: *mut c_void,
+    deleter: Option<unsafe extern "C" fn(*mut DLManagedTensor)>,
+}
+
+#[repr(C)]
+struct DLManagedTensorVersioned {
+    version: DLPackVersion,
+    manager_ctx: *mut c_void,
+    deleter: Option<unsafe extern "C" fn(*mut DLManagedTensorVersioned)>,
+    flags: u64,
+    dl_tensor: DLTensor,
+}
+
+/// Memory an export points into.
+enum Buffer {
+    /// The tensor's own matrix, which consumers may only read.
+    Shared(Arc<Vec<f64>>),
+    /// A copy of it the consumer owns and may write to.
+    Copied(Box<[f64]>),
+}
+
+/// What an exported tensor points into, owned by its `manager_ctx` until
+/// the consumer calls the deleter.
+struct Export {
+    data: Buffer,
+    shape: [i64; 2],
+    strides: [i64; 2],
+}
+
+unsafe extern "C" fn delete_managed(managed: *mut DLManagedTensor) {
+    // SAFETY: `managed` and its context were leaked by `export` and the
+    // protocol calls the deleter once.
+    unsafe {
+        let managed = Box::from_raw(managed);
+        drop(Box::from_raw(managed.manager_ctx as *mut Export));
+    }
+}
+
+unsafe extern "C" fn delete_managed_versioned(managed: *mut DLManagedTensorVersioned) {
+    // SAFETY: as for `delete_managed`.
+    unsafe {
+        let managed = Box::from_raw(managed);
+        drop(Box::from_raw(managed.manager_ctx as *mut Export));
+    }
+}
+
+/// Delete the tensor of a capsule no consumer took, which would have
+/// renamed it `used_dltensor`.
+unsafe extern "C" fn delete_capsule(capsule: *mut ffi::PyObject) {
+    // SAFETY: called by Python with a live capsule.
+    unsafe {
+        if ffi::PyCapsule_IsValid(capsule, DLTENSOR.as_ptr()) == 1 {
+            let managed = ffi::PyCapsule_GetPointer(capsule, DLTENSOR.as_ptr());
+            delete_managed(managed as *mut DLManagedTensor);
+        }
+    }
+}
+
+/// Delete the tensor of a versioned capsule no consumer took.
+unsafe extern "C" fn delete_versioned_capsule(capsule: *mut ffi::PyObject) {
+    // SAFETY: called by Python with a live capsule.
+    unsafe {
+        if ffi::PyCapsule_IsValid(capsule, DLTENSOR_VERSIONED.as_ptr()) == 1 {
+            let managed = ffi::PyCapsule_GetPointer(capsule, DLTENSOR_VERSIONED.as_ptr());
+            delete_managed_versioned(managed as *mut DLManagedTensorVersioned);
+        }
+    }
+}
+
+/// Leak the context of an export of `data` and describe it, for a managed
+/// tensor to hand over.
+fn export(data: Buffer, rows: usize, cols: usize) -> (*mut c_void, DLTensor) {
+    let mut ctx = Box::new(Export {
+        data,
+        shape: [rows as i64, cols as i64],
+        strides: [cols as i64, 1],
+    });
+    let data = match &mut ctx.data {
+        // only read, as the consumer is told through the read-only flag
+        Buffer::Shared(data) => data.as_ptr() as *mut c_void,
+        Buffer::Copied(data) => data.as_mut_ptr() as *mut c_void,
+    };
+    let dl_tensor = DLTensor {
+        data,
+        device: DLDevice {
+            device_type: DEVICE_CPU,
+            device_id: 0,
+        },
+        ndim: 2,
+        dtype: DLDataType {
+            code: DTYPE_FLOAT,
+            bits: 64,
+            lanes: 1,
+        },
+        shape: ctx.shape.as_mut_ptr(),
+        strides: ctx.strides.as_mut_ptr(),
+        byte_offset: 0,
+    };
+    (Box::into_raw(ctx) as *mut c_void, dl_tensor)
+}
+
+/// Hand `managed` over to a new capsule called `name`, or free it through
+/// `delete` if the capsule cannot be created.
+///
+/// # Safety
+///
+/// `managed` must be a leaked tensor that `delete` and `destructor` free.
+unsafe fn into_capsule<T>(
+    py: Python<'_>,
+    managed: *mut T,
+    name: &'static CStr,
+    delete: unsafe extern "C" fn(*mut T),
+    destructor: unsafe extern "C" fn(*mut ffi::PyObject),
+) -> PyResult<Py<PyAny>> {
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        let capsule = ffi::PyCapsule_New(managed as *mut c_void, name.as_ptr(), Some(destructor));
+        if capsule.is_null() {
+            delete(managed);
+            return Err(PyErr::fetch(py));
+        }
+        Ok(Py::from_owned_ptr(py, capsule))
+    }
+}
+
+/// A `float64` matrix exportable through DLPack.
+#[pyclass(name = "Tensor", module = "forzium_engine", frozen)]
+pub struct Tensor {
+    data: Arc<Vec<f64>>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Tensor {
+    /// Tensor of equally long `rows`, copied once into a single buffer.
+    pub fn from_rows(rows: &[Vec<f64>]) -> PyResult<Self> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err(PyValueError::new_err("rows must all have the same length"));
+        }
+        Ok(Self {
+            data: Arc::new(rows.concat()),
+            rows: rows.len(),
+            cols,
+        })
+    }
+}
+
+#[pymethods]
+impl Tensor {
+    /// Copy a list of rows, such as a compute result, into a tensor.
+    #[new]
+    fn new(rows: Vec<Vec<f64>>) -> PyResult<Self> {
+        Self::from_rows(&rows)
+    }
+
+    /// `(rows, columns)`.
+    #[getter]
+    fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// The matrix as a list of rows.
+    fn tolist(&self) -> Vec<Vec<f64>> {
+        if self.cols == 0 {
+            return vec![Vec::new(); self.rows];
+        }
+        self.data.chunks(self.cols).map(<[f64]>::to_vec).collect()
+    }
+
+    /// Device of the tensor's memory, `(kDLCPU, 0)`.
+    fn __dlpack_device__(&self) -> (i32, i32) {
+        (DEVICE_CPU, 0)
+    }
+
+    /// A capsule holding the matrix. Consumers asking for a `max_version`
+    /// of at least 1.0 get a `dltensor_versioned` capsule viewing it,
+    /// flagged read-only, or a copy with `copy=True`. Older consumers get an
+    /// unversioned `dltensor` capsule holding a copy, as they cannot be told
+    /// not to write, so `copy=False` is refused for them. Only the CPU is
+    /// supported, so `stream` must be None and `dl_device`, if given,
+    /// `(kDLCPU, 0)`.
+    #[pyo3(signature = (*, stream=None, max_version=None, dl_device=None, copy=None))]
+    fn __dlpack__(
+        &self,
+        py: Python<'_>,
+        stream: Option<Py<PyAny>>,
+        max_version: Option<(u32, u32)>,
+        dl_device: Option<(i32, i32)>,
+        copy: Option<bool>,
+    ) -> PyResult<Py<PyAny>> {
+        if stream.is_some() {
+            return Err(PyValueError::new_err("stream must be None for CPU tensors"));
+        }
+        if dl_device.is_some_and(|device| device != (DEVICE_CPU, 0)) {
+            return Err(PyBufferError::new_err(
+                "tensors can only be exported to the CPU",
+            ));
+        }
+        let versioned = max_version.is_some_and(|(major, _)| major >= DLPACK_VERSION.0);
+        if !versioned && copy == Some(false) {
+            return Err(PyBufferError::new_err(
+                "sharing the tensor requires a max_version of at least (1, 0)",
+            ));
+        }
+        let (data, flags) = if copy == Some(true) || !versioned {
+            (Buffer::Copied(Box::from(self.data.as_slice())), FLAG_IS_COPIED)
+        } else {
+            (Buffer::Shared(self.data.clone()), FLAG_READ_ONLY)
+        };
+        let (manager_ctx, dl_tensor) = export(data, self.rows, self.cols);
+        if !versioned {
+            let managed = Box::into_raw(Box::new(DLManagedTensor {
+                dl_tensor,
+                manager_ctx,
+                deleter: Some(delete_managed),
+            }));
+            // SAFETY: `managed` was just leaked and is freed by either.
+            return unsafe { into_capsule(py, managed, DLTENSOR, delete_managed, delete_capsule) };
+        }
+        let managed = Box::into_raw(Box::new(DLManagedTensorVersioned {
+            version: DLPackVersion {
+                major: DLPACK_VERSION.0,
+                minor: DLPACK_VERSION.1,
+            },
+            manager_ctx,
+            deleter: Some(delete_managed_versioned),
+            flags,
+            dl_tensor,
+        }));
+        // SAFETY: `managed` was just leaked and is freed by either.
+        unsafe {
+            into_capsule(
+                py,
+                managed,
+                DLTENSOR_VERSIONED,
+                delete_managed_versioned,
+                delete_versioned_capsule,
+            )
+        }
+    }
+}
+
+/// A matrix argument of a compute binding: a list of rows or a [`Tensor`].
+///
+/// Results built with [`Matrix::output`] are tensors if any argument was.
+#[derive(Clone)]
+pub struct Matrix {
+    rows: Vec<Vec<f64>>,
+    tensor: bool,
+}
+
+impl Matrix {
+    /// Whether the argument was a tensor.
+    pub fn is_tensor(&self) -> bool {
+        self.tensor
+    }
+
+    /// A result, returned as a tensor if `tensor` and as a list otherwise.
+    pub fn output(rows: Vec<Vec<f64>>, tensor: bool) -> Self {
+        Self { rows, tensor }
+    }
+}
+
+impl std::ops::Deref for Matrix {
+    type Target = Vec<Vec<f64>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rows
+    }
+}
+
+impl FromPyObject<'_, '_> for Matrix {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'_, '_, PyAny>) -> Result<Self, Self::Error> {
+        if let Ok(tensor) = obj.cast::<Tensor>() {
+            // the kernels take rows, so this is the one copy on the way in
+            return Ok(Self::output(tensor.get().tolist(), true));
+        }
+        Ok(Self::output(obj.extract()?, false))
+    }
+}
+
+impl<'py> IntoPyObject<'py> for Matrix {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        if self.tensor {
+            Ok(Bound::new(py, Tensor::from_rows(&self.rows)?)?.into_any())
+        } else {
+            self.rows.into_pyobject(py)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capsules_view_the_tensor_read_only_and_release_it() {
+        let tensor = Tensor::from_rows(&[vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]).unwrap();
+        Python::attach(|py| {
+            let capsule = tensor
+                .__dlpack__(py, None, Some((1, 1)), None, None)
+                .unwrap();
+            assert_eq!(Arc::strong_count(&tensor.data), 2);
+            // SAFETY: a fresh versioned capsule from `__dlpack__`.
+            let managed = unsafe {
+                &*(ffi::PyCapsule_GetPointer(capsule.as_ptr(), DLTENSOR_VERSIONED.as_ptr())
+                    as *const DLManagedTensorVersioned)
+            };
+            assert_eq!((managed.version.major, managed.flags), (1, FLAG_READ_ONLY));
+            let dl = &managed.dl_tensor;
+            assert_eq!(dl.data as *const f64, tensor.data.as_ptr());
+            // SAFETY: `ndim` entries each.
+            let (shape, strides) = unsafe {
+                (
+                    std::slice::from_raw_parts(dl.shape, 2),
+                    std::slice::from_raw_parts(dl.strides, 2),
+                )
+            };
+            assert_eq!((shape, strides), (&[2, 3][..], &[3, 1][..]));
+            drop(capsule);
+            assert_eq!(Arc::strong_count(&tensor.data), 1);
+
+            let copied = tensor
+                .__dlpack__(py, None, Some((1, 0)), None, Some(true))
+                .unwrap();
+            // SAFETY: as above.
+            let managed = unsafe {
+                &*(ffi::PyCapsule_GetPointer(copied.as_ptr(), DLTENSOR_VERSIONED.as_ptr())
+                    as *const DLManagedTensorVersioned)
+            };
+            assert_eq!(managed.flags, FLAG_IS_COPIED);
+            assert_ne!(managed.dl_tensor.data as *const f64, tensor.data.as_ptr());
+            assert_eq!(Arc::strong_count(&tensor.data), 1);
+            drop(copied);
+
+            // consumers without the read-only flag only get copies
+            let unversioned = tensor.__dlpack__(py, None, None, None, None).unwrap();
+            assert_eq!(Arc::strong_count(&tensor.data), 1);
+            // SAFETY: a fresh unversioned capsule from `__dlpack__`.
+            let managed = unsafe {
+                &*(ffi::PyCapsule_GetPointer(unversioned.as_ptr(), DLTENSOR.as_ptr())
+                    as *const DLManagedTensor)
+            };
+            // SAFETY: two rows of three.
+            let data =
+                unsafe { std::slice::from_raw_parts(managed.dl_tensor.data as *const f64, 6) };
+            assert_eq!(data, &*tensor.data);
+            drop(unversioned);
+            assert!(
+                tensor
+                    .__dlpack__(py, None, Some((0, 8)), None, Some(false))
+                    .is_err()
+            );
+            assert!(
+                tensor
+                    .__dlpack__(py, None, None, Some((2, 0)), None)
+                    .is_err()
+            );
+        });
+        assert_eq!(tensor.tolist()[1], [4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn matrices_come_back_as_what_went_in() {
+        Python::attach(|py| {
+            let rows = vec![vec![1.0, 2.0]];
+            let list = rows.clone().into_pyobject(py).unwrap();
+            let tensor = Bound::new(py, Tensor::from_rows(&rows).unwrap()).unwrap();
+            let from_list: Matrix = list.extract().unwrap();
+            let from_tensor: Matrix = tensor.extract().unwrap();
+            assert!(!from_list.is_tensor() && from_tensor.is_tensor());
+            assert_eq!(*from_tensor, rows);
+
+            let out = Matrix::output(rows.clone(), true)
+                .into_pyobject(py)
+                .unwrap();
+            assert_eq!(out.cast::<Tensor>().unwrap().get().tolist(), rows);
+            let out = Matrix::output(rows.clone(), false)
+                .into_pyobject(py)
+                .unwrap();
+            assert_eq!(out.extract::<Vec<Vec<f64>>>().unwrap(), rows);
+        });
+    }
+}
@@ -1,4 +1,4 @@
-use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use thiserror::Error;
@@ -20,15 +20,36 @@ pub enum ForziumError {
     ResourceLimit(String),
 }
 
+impl ForziumError {
+    /// Machine-readable code for the error's category, e.g.
+    /// `RUST_CORE_VALIDATION`.
+    pub fn code(&self) -> &'static str {
+        crate::error_bridge::ErrorCategory::of(self).code()
+    }
+
+    /// Whether retrying the failed call may succeed; see
+    /// [`ErrorCategory::is_retryable`](crate::error_bridge::ErrorCategory::is_retryable).
+    pub fn is_retryable(&self) -> bool {
+        crate::error_bridge::ErrorCategory::of(self).is_retryable()
+    }
+
+    /// Error message without the category prefix.
+    pub fn message(&self) -> &str {
+        match self {
+            ForziumError::Validation(msg)
+            | ForziumError::Compute(msg)
+            | ForziumError::Cancelled(msg)
+            | ForziumError::ResourceLimit(msg) => msg,
+        }
+    }
+}
+
+/// Raised as a `forzium_engine.ForziumError` subclass that carries the
+/// error's [`code`](ForziumError::code); see
+/// [`create_py_error`](crate::error_bridge::create_py_error).
 impl From<ForziumError> for PyErr {
     fn from(err: ForziumError) -> PyErr {
-        match err {
-            ForziumError::Validation(msg) => PyValueError::new_err(msg),
-            ForziumError::ResourceLimit(msg) => pyo3::exceptions::PyResourceWarning::new_err(msg),
-            ForziumError::Compute(msg) | ForziumError::Cancelled(msg) => {
-                PyRuntimeError::new_err(msg)
-            }
-        }
+        crate::error_bridge::create_py_error(err)
     }
 }
 
@@ -39,6 +60,9 @@ where
 {
     match catch_unwind(AssertUnwindSafe(f)) {
         Ok(result) => result,
-        Err(_) => Err(PyRuntimeError::new_err("rust panic")),
+        Err(payload) => Err(PyRuntimeError::new_err(format!(
+            "rust panic: {}",
+            crate::panic_hook::describe(payload.as_ref())
+        ))),
     }
 }
@@ -1,31 +1,123 @@
 use crate::error::ForziumError;
+use crate::interpreter::PerInterpreter;
 use once_cell::sync::Lazy;
 use pyo3::exceptions::{
     PyException, PyMemoryError, PyNotImplementedError, PyReferenceError, PyResourceWarning,
-    PyRuntimeError, PyTimeoutError, PyValueError,
+    PyRuntimeError, PyValueError,
 };
 use pyo3::prelude::*;
-use std::sync::atomic::{AtomicBool, Ordering};
+use pyo3::types::{PyDict, PyTuple, PyType};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 /// Controls whether detailed error information is included in Python exceptions.
 static VERBOSE_ERRORS: AtomicBool = AtomicBool::new(true);
 
 /// Controls whether error stack traces are captured and included in Python exceptions.
-static CAPTURE_STACK_TRACES: AtomicBool = AtomicBool::new(true);
+///
+/// Off by default: resolving a backtrace costs far more than raising the
+/// error itself.
+static CAPTURE_STACK_TRACES: AtomicBool = AtomicBool::new(false);
+
+/// Errors raised to Python, indexed by [`ErrorCategory::index`].
+static CATEGORY_COUNTS: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+
+/// HTTP error responses, indexed by status class: 4xx then 5xx.
+static STATUS_CLASS_COUNTS: [AtomicU64; 2] = [const { AtomicU64::new(0) }; 2];
 
 /// Stores the last error message for debugging purposes.
 pub static LAST_ERROR: Lazy<parking_lot::RwLock<String>> =
     Lazy::new(|| parking_lot::RwLock::new(String::new()));
 
-/// Python exception type for Forzium API errors.
-#[pyclass(extends=PyException)]
+/// Python exception type for Forzium API errors, exported as
+/// `forzium_engine.ForziumError`.
+///
+/// Errors are raised as a subclass per category that also derives from the
+/// builtin exception the category maps to, so `except ValueError` still
+/// catches validation errors.
+#[pyclass(extends=PyException, subclass, name = "ForziumError", module = "forzium_engine")]
 pub struct PyForziumError {
+    /// Machine-readable code, e.g. `RUST_CORE_VALIDATION`.
     #[pyo3(get)]
-    code: u32,
+    code: String,
     #[pyo3(get)]
     category: String,
     #[pyo3(get)]
     details: Option<String>,
+    /// Whether the same call may succeed if retried later.
+    #[pyo3(get)]
+    is_retryable: bool,
+}
+
+#[pymethods]
+impl PyForziumError {
+    #[new]
+    #[pyo3(signature = (*_args))]
+    fn new(_args: &Bound<'_, PyTuple>) -> Self {
+        let category = ErrorCategory::Compute;
+        Self {
+            code: category.code().to_string(),
+            category: category.name().to_string(),
+            details: None,
+            is_retryable: category.is_retryable(),
+        }
+    }
+}
+
+/// Category subclasses of `ForziumError`, indexed by [`ErrorCategory::index`],
+/// created in each interpreter that raises them.
+static CATEGORY_TYPES: Lazy<PerInterpreter<[Py<PyType>; 4]>> = Lazy::new(PerInterpreter::new);
+
+fn category_types(py: Python<'_>) -> PyResult<&'static [Py<PyType>; 4]> {
+    CATEGORY_TYPES.get_or_try_init(py, || {
+        let make = |category: ErrorCategory, builtin: Bound<'_, PyType>| {
+            let namespace = PyDict::new(py);
+            namespace.set_item("__module__", "forzium_engine")?;
+            let bases = (py.get_type::<PyForziumError>(), builtin);
+            let ty = py
+                .get_type::<PyType>()
+                .call1((category.class_name(), bases, namespace))?;
+            Ok::<_, PyErr>(ty.cast_into::<PyType>()?.unbind())
+        };
+        Ok([
+            make(ErrorCategory::Validation, py.get_type::<PyValueError>())?,
+            make(ErrorCategory::Compute, py.get_type::<PyRuntimeError>())?,
+            make(ErrorCategory::Cancelled, py.get_type::<PyRuntimeError>())?,
+            make(
+                ErrorCategory::ResourceLimit,
+                py.get_type::<PyResourceWarning>(),
+            )?,
+        ])
+    })
+}
+
+/// Instantiate the `ForziumError` subclass for `category`.
+fn new_forzium_error(
+    py: Python<'_>,
+    category: ErrorCategory,
+    code: &str,
+    message: &str,
+    details: Option<String>,
+) -> PyResult<PyErr> {
+    let exc = category_types(py)?[category.index()]
+        .bind(py)
+        .call1((message,))?;
+    {
+        let mut fields = exc.cast::<PyForziumError>()?.borrow_mut();
+        fields.code = code.to_string();
+        fields.category = category.name().to_string();
+        fields.details = details;
+        fields.is_retryable = category.is_retryable();
+    }
+    Ok(PyErr::from_value(exc))
+}
+
+/// Add `ForziumError` and its category subclasses to `m`.
+pub fn add_exception_types(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ForziumError", py.get_type::<PyForziumError>())?;
+    for (category, ty) in ErrorCategory::ALL.iter().zip(category_types(py)?) {
+        m.add(category.class_name(), ty.bind(py))?;
+    }
+    Ok(())
 }
 
 /// Set detailed error information mode.
@@ -35,6 +127,9 @@ pub fn set_verbose_errors(enabled: bool) -> bool {
 }
 
 /// Set stack trace capture mode.
+///
+/// When enabled, errors raised from Rust append the Rust backtrace and the
+/// Python call stack to `ForziumError.details` and `get_last_error()`.
 #[pyfunction]
 pub fn set_capture_stack_traces(enabled: bool) -> bool {
     CAPTURE_STACK_TRACES.swap(enabled, Ordering::SeqCst)
@@ -46,34 +141,97 @@ pub fn get_last_error() -> String {
     LAST_ERROR.read().clone()
 }
 
+/// Error totals over the process lifetime.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ErrorStats {
+    /// Errors raised to Python, indexed by [`ErrorCategory::index`].
+    pub by_category: [u64; 4],
+    /// 4xx responses sent by the HTTP server.
+    pub client_errors: u64,
+    /// 5xx responses sent by the HTTP server.
+    pub server_errors: u64,
+}
+
+/// Count an HTTP response; only 4xx and 5xx statuses are recorded.
+pub fn record_http_status(status: u16) {
+    if let Some(counter) = STATUS_CLASS_COUNTS.get(usize::from(status / 100).wrapping_sub(4)) {
+        counter.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Read the error totals.
+pub fn stats() -> ErrorStats {
+    ErrorStats {
+        by_category: CATEGORY_COUNTS.each_ref().map(|c| c.load(Ordering::SeqCst)),
+        client_errors: STATUS_CLASS_COUNTS[0].load(Ordering::SeqCst),
+        server_errors: STATUS_CLASS_COUNTS[1].load(Ordering::SeqCst),
+    }
+}
+
+/// Return error counts by category and by HTTP status class as a dict.
+#[pyfunction]
+pub fn error_stats(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let stats = stats();
+    let by_category = PyDict::new(py);
+    for (category, count) in ErrorCategory::ALL.iter().zip(stats.by_category) {
+        by_category.set_item(category.name(), count)?;
+    }
+    let by_status_class = PyDict::new(py);
+    by_status_class.set_item("4xx", stats.client_errors)?;
+    by_status_class.set_item("5xx", stats.server_errors)?;
+    let dict = PyDict::new(py);
+    dict.set_item("by_category", by_category)?;
+    dict.set_item("by_status_class", by_status_class)?;
+    Ok(dict.unbind())
+}
+
 /// Create a Python exception from a Rust error with enhanced details.
+///
+/// The exception is an instance of the error category's `ForziumError`
+/// subclass, e.g. `forzium_engine.ValidationError`, with `code`, `category`
+/// and, in verbose mode, `details` set.
 pub fn create_py_error(err: ForziumError) -> PyErr {
-    // Store the error message for debugging
-    let message = err.to_string();
-    *LAST_ERROR.write() = message.clone();
-
-    // Extract error code and category
-    let (_code, _category) = match &err {
-        ForziumError::Validation(_) => (1000, "validation"),
-        ForziumError::Compute(_) => (2000, "compute"),
-        ForziumError::Cancelled(_) => (3000, "cancelled"),
-        ForziumError::ResourceLimit(_) => (4000, "resource_limit"),
-    };
-
-    // Determine if we should include detailed information
-    let _details = if VERBOSE_ERRORS.load(Ordering::Relaxed) {
-        Some(message.clone())
-    } else {
-        None
-    };
-
-    // Choose appropriate exception type based on error category
-    match &err {
-        ForziumError::Validation(_) => PyValueError::new_err(message.clone()),
-        ForziumError::ResourceLimit(_) => PyResourceWarning::new_err(message.clone()),
-        ForziumError::Cancelled(_) => PyTimeoutError::new_err(message.clone()),
-        ForziumError::Compute(_) => PyRuntimeError::new_err(message),
+    let category = ErrorCategory::of(&err);
+    CATEGORY_COUNTS[category.index()].fetch_add(1, Ordering::SeqCst);
+    Python::attach(|py| {
+        let traces = CAPTURE_STACK_TRACES
+            .load(Ordering::Relaxed)
+            .then(|| stack_traces(py));
+
+        // Store the error message for debugging
+        let mut message = err.to_string();
+        if let Some(traces) = &traces {
+            message = format!("{message}\n\n{traces}");
+        }
+        *LAST_ERROR.write() = message.clone();
+
+        let details = if VERBOSE_ERRORS.load(Ordering::Relaxed) {
+            Some(message)
+        } else {
+            traces
+        };
+        new_forzium_error(py, category, err.code(), err.message(), details).unwrap_or_else(|e| e)
+    })
+}
+
+/// Rust backtrace of the caller, followed by the Python call stack if the
+/// error is raised while Python code is running.
+fn stack_traces(py: Python<'_>) -> String {
+    let mut traces = format!(
+        "Rust backtrace:\n{}",
+        std::backtrace::Backtrace::force_capture()
+    );
+    let python_stack = py
+        .import("traceback")
+        .and_then(|traceback| traceback.call_method0("format_stack"))
+        .and_then(|frames| frames.extract::<Vec<String>>());
+    if let Ok(frames) = python_stack
+        && !frames.is_empty()
+    {
+        traces.push_str("\nPython stack (most recent call last):\n");
+        traces.push_str(&frames.concat());
     }
+    traces
 }
 
 // Note: The From<ForziumError> for PyErr implementation is provided in error.rs
@@ -88,13 +246,7 @@ where
         Ok(Ok(result)) => Ok(result),
         Ok(Err(err)) => Err(create_py_error(err)),
         Err(panic) => {
-            let panic_msg = if let Some(s) = panic.downcast_ref::<&str>() {
-                s.to_string()
-            } else if let Some(s) = panic.downcast_ref::<String>() {
-                s.clone()
-            } else {
-                "Unknown panic occurred".to_string()
-            };
+            let panic_msg = crate::panic_hook::describe(panic.as_ref());
 
             *LAST_ERROR.write() = format!("Rust panic: {}", panic_msg);
             Err(PyRuntimeError::new_err(format!(
@@ -106,7 +258,7 @@ where
 }
 
 /// Error category enum for Python error classification.
-#[pyclass]
+#[pyclass(frozen)]
 #[derive(Clone, Copy, Debug)]
 pub enum ErrorCategory {
     #[pyo3(name = "VALIDATION")]
@@ -119,13 +271,104 @@ pub enum ErrorCategory {
     ResourceLimit = 4000,
 }
 
+impl ErrorCategory {
+    /// Every category, in `ForziumError` subclass order.
+    pub const ALL: [ErrorCategory; 4] = [
+        Self::Validation,
+        Self::Compute,
+        Self::Cancelled,
+        Self::ResourceLimit,
+    ];
+
+    /// Category of a Rust error.
+    pub fn of(err: &ForziumError) -> Self {
+        match err {
+            ForziumError::Validation(_) => Self::Validation,
+            ForziumError::Compute(_) => Self::Compute,
+            ForziumError::Cancelled(_) => Self::Cancelled,
+            ForziumError::ResourceLimit(_) => Self::ResourceLimit,
+        }
+    }
+
+    /// Name exposed as `ForziumError.category`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Validation => "validation",
+            Self::Compute => "compute",
+            Self::Cancelled => "cancelled",
+            Self::ResourceLimit => "resource_limit",
+        }
+    }
+
+    /// Code of errors in this category; see [`ForziumError::code`].
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Validation => "RUST_CORE_VALIDATION",
+            Self::Compute => "RUST_CORE_COMPUTE",
+            Self::Cancelled => "RUST_CORE_CANCELLED",
+            Self::ResourceLimit => "RUST_CORE_RESOURCE_LIMIT",
+        }
+    }
+
+    /// Whether errors in this category are transient: a cancelled call or
+    /// one that hit a resource limit may succeed later, while invalid input
+    /// or a failed computation fails again.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::Cancelled | Self::ResourceLimit)
+    }
+
+    /// Name of the category's `ForziumError` subclass.
+    fn class_name(self) -> &'static str {
+        match self {
+            Self::Validation => "ValidationError",
+            Self::Compute => "ComputeError",
+            Self::Cancelled => "CancelledError",
+            Self::ResourceLimit => "ResourceLimitError",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize / 1000 - 1
+    }
+}
+
 /// Register the error bridge module with Python.
 pub fn register(py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
     let error_module = PyModule::new(py, "errors")?;
     error_module.add_function(wrap_pyfunction!(set_verbose_errors, m)?)?;
+    error_module.add_function(wrap_pyfunction!(error_stats, m)?)?;
     error_module.add_function(wrap_pyfunction!(set_capture_stack_traces, m)?)?;
     error_module.add_function(wrap_pyfunction!(get_last_error, m)?)?;
     error_module.add_class::<ErrorCategory>()?;
-    m.add_submodule(error_module)?;
+    m.add_submodule(&error_module)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_counted_by_category_and_status_class() {
+        let before = stats();
+        Python::attach(|_| {
+            let _ = create_py_error(ForziumError::Cancelled("stopped".into()));
+        });
+        record_http_status(404);
+        record_http_status(503);
+        record_http_status(200);
+        let after = stats();
+        let cancelled = ErrorCategory::Cancelled.index();
+        assert!(after.by_category[cancelled] > before.by_category[cancelled]);
+        assert!(after.client_errors > before.client_errors);
+        assert!(after.server_errors > before.server_errors);
+    }
+
+    #[test]
+    fn only_transient_categories_are_retryable() {
+        assert!(!ForziumError::Validation("bad".into()).is_retryable());
+        assert!(!ForziumError::Compute("failed".into()).is_retryable());
+        assert!(ForziumError::Cancelled("stopped".into()).is_retryable());
+        assert!(ForziumError::ResourceLimit("full".into()).is_retryable());
+    }
+}
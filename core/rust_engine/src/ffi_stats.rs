@@ -0,0 +1,160 @@
+//! Call counts and cumulative durations of the Python bindings
+//!
+//! Each instrumented binding owns a static [`FfiCounter`] created by
+//! [`ffi_call!`](crate::ffi_call). Timing a call costs two clock reads and two
+//! relaxed atomic adds, so counting is always on. The time covers the
+//! binding's body, not the conversion of its arguments from Python objects.
+
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Calls of one binding.
+pub struct FfiCounter {
+    name: &'static str,
+    calls: AtomicU64,
+    nanos: AtomicU64,
+    registered: AtomicBool,
+}
+
+impl FfiCounter {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            calls: AtomicU64::new(0),
+            nanos: AtomicU64::new(0),
+            registered: AtomicBool::new(false),
+        }
+    }
+
+    /// Start timing a call; it is counted when the returned guard drops, so
+    /// calls that fail or return early are counted too.
+    pub fn start(&'static self) -> FfiCall {
+        if !self.registered.load(Ordering::Relaxed) && !self.registered.swap(true, Ordering::AcqRel)
+        {
+            COUNTERS.lock().push(self);
+        }
+        FfiCall {
+            counter: self,
+            start: Instant::now(),
+        }
+    }
+}
+
+/// A binding call being timed.
+pub struct FfiCall {
+    counter: &'static FfiCounter,
+    start: Instant,
+}
+
+impl Drop for FfiCall {
+    fn drop(&mut self) {
+        let nanos = self.start.elapsed().as_nanos() as u64;
+        self.counter.calls.fetch_add(1, Ordering::Relaxed);
+        self.counter.nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+/// Counters of the bindings called at least once.
+static COUNTERS: Mutex<Vec<&'static FfiCounter>> = Mutex::new(Vec::new());
+
+/// Count and time the enclosing binding under `name` until the end of the
+/// scope: `let _call = ffi_call!("matmul");`.
+#[macro_export]
+macro_rules! ffi_call {
+    ($name:expr) => {{
+        static COUNTER: $crate::ffi_stats::FfiCounter = $crate::ffi_stats::FfiCounter::new($name);
+        COUNTER.start()
+    }};
+}
+
+/// Calls of one binding since startup or the last reset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FfiStats {
+    pub name: &'static str,
+    pub calls: u64,
+    pub total: Duration,
+}
+
+impl FfiStats {
+    /// Mean duration of a call.
+    pub fn mean(&self) -> Duration {
+        if self.calls == 0 {
+            return Duration::ZERO;
+        }
+        self.total / self.calls as u32
+    }
+}
+
+/// Calls of every binding called so far, longest total duration first.
+pub fn snapshot() -> Vec<FfiStats> {
+    let mut stats: Vec<_> = COUNTERS
+        .lock()
+        .iter()
+        .map(|counter| FfiStats {
+            name: counter.name,
+            calls: counter.calls.load(Ordering::Relaxed),
+            total: Duration::from_nanos(counter.nanos.load(Ordering::Relaxed)),
+        })
+        .collect();
+    stats.sort_by(|a, b| b.total.cmp(&a.total).then(a.name.cmp(b.name)));
+    stats
+}
+
+/// Zero every binding's counts.
+pub fn reset() {
+    for counter in COUNTERS.lock().iter() {
+        counter.calls.store(0, Ordering::Relaxed);
+        counter.nanos.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Return how often each binding was called and how long its calls took in
+/// total: a dict mapping binding names to calls, total_ms and mean_us,
+/// longest total first. Argument conversion is not included. With `reset`
+/// the counts are zeroed after being read.
+#[pyfunction]
+#[pyo3(signature = (reset=false))]
+pub fn ffi_stats(py: Python<'_>, reset: bool) -> PyResult<Py<PyDict>> {
+    let dict = PyDict::new(py);
+    for stats in snapshot() {
+        let entry = PyDict::new(py);
+        entry.set_item("calls", stats.calls)?;
+        entry.set_item("total_ms", stats.total.as_secs_f64() * 1000.0)?;
+        entry.set_item("mean_us", stats.mean().as_secs_f64() * 1_000_000.0)?;
+        dict.set_item(stats.name, entry)?;
+    }
+    if reset {
+        self::reset();
+    }
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(fail: bool) -> Result<(), ()> {
+        let _call = crate::ffi_call!("ffi_stats_test");
+        std::thread::sleep(Duration::from_millis(1));
+        if fail {
+            return Err(());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn calls_are_counted_and_timed() {
+        binding(false).unwrap();
+        binding(true).unwrap_err();
+        let stats = snapshot()
+            .into_iter()
+            .find(|stats| stats.name == "ffi_stats_test")
+            .unwrap();
+        assert_eq!(stats.calls, 2);
+        assert!(stats.total >= Duration::from_millis(2));
+        assert!(stats.mean() >= Duration::from_millis(1));
+    }
+}
@@ -6,9 +6,8 @@
 
 #[cfg(test)]
 mod tests {
-    use super::super::*;
     use pyo3::prelude::*;
-    use pyo3::types::{PyDict, PyList};
+    use pyo3::types::{PyCFunction, PyDict, PyList};
     use std::sync::{Arc, Mutex};
     use std::thread;
 
@@ -28,7 +27,7 @@ mod tests {
     #[test]
     fn test_matrix_conversion() {
         // Test that matrices are correctly converted between Rust and Python
-        Python::with_gil(|py| {
+        Python::attach(|py| {
             // Create a Rust matrix
             let matrix = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
 
@@ -38,14 +37,14 @@ mod tests {
             // Create a Python function that returns the matrix
             let locals = PyDict::new(py);
             locals.set_item("matrix", py_matrix).unwrap();
-            let code = "def identity(x): return x\nresult = identity(matrix)";
-            py.run(code, None, Some(locals)).unwrap();
+            let code = c"def identity(x): return x\nresult = identity(matrix)";
+            py.run(code, Some(&locals), None).unwrap();
 
             // Get the result back
-            let result = locals.get_item("result").unwrap();
+            let result = locals.get_item("result").unwrap().unwrap();
 
             // Convert back to Rust
-            let rust_result: Vec<Vec<f64>> = result.extract(py).unwrap();
+            let rust_result: Vec<Vec<f64>> = result.extract().unwrap();
 
             // Verify it's the same
             assert_eq!(rust_result, matrix);
@@ -55,25 +54,27 @@ mod tests {
     #[test]
     fn test_error_propagation() {
         // Test that Rust errors are correctly propagated to Python
-        Python::with_gil(|py| {
+        Python::attach(|py| {
             // Create a function that panics
-            let err_func = py_fn!(py, || -> PyResult<()> {
-                Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+            let err_func = PyCFunction::new_closure(py, None, None, |_args, _kwargs| {
+                PyResult::<()>::Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
                     "Test error",
                 ))
-            });
+            })
+            .unwrap();
 
             // Call the function from Python
             let locals = PyDict::new(py);
             locals.set_item("err_func", err_func).unwrap();
-            let code = "try:\n    err_func()\n    success = False\nexcept RuntimeError:\n    success = True";
-            py.run(code, None, Some(locals)).unwrap();
+            let code = c"try:\n    err_func()\n    success = False\nexcept RuntimeError:\n    success = True";
+            py.run(code, Some(&locals), None).unwrap();
 
             // Check that the error was caught
             let success = locals
                 .get_item("success")
                 .unwrap()
-                .extract::<bool>(py)
+                .unwrap()
+                .extract::<bool>()
                 .unwrap();
             assert!(success);
         });
@@ -82,15 +83,16 @@ mod tests {
     #[test]
     fn test_gil_release() {
         // Test that compute-intensive operations release the GIL
-        Python::with_gil(|py| {
+        Python::attach(|py| {
             // Create a Python function that does something with the GIL released
-            let gil_release_func = py_fn!(py, || -> PyResult<()> {
-                py.allow_threads(|| {
+            let gil_release_func = PyCFunction::new_closure(py, None, None, |args, _kwargs| {
+                args.py().detach(|| {
                     // Simulate work
                     std::thread::sleep(std::time::Duration::from_millis(100));
                 });
-                Ok(())
-            });
+                PyResult::Ok(())
+            })
+            .unwrap();
 
             // Create a counter to check parallel execution
             let counter = Arc::new(Mutex::new(0));
@@ -113,8 +115,8 @@ mod tests {
             });
 
             // Run the GIL-releasing function
-            let code = "gil_release_func()";
-            py.run(code, None, Some(locals)).unwrap();
+            let code = c"gil_release_func()";
+            py.run(code, Some(&locals), None).unwrap();
 
             // Wait for the counting thread
             handle.join().unwrap();
@@ -128,49 +130,52 @@ mod tests {
     #[test]
     fn test_thread_safety() {
         // Test thread-safe access to Python from multiple Rust threads
-        Python::with_gil(|py| {
+        Python::attach(|py| {
             // Create a thread-safe Python object container
             let locals = PyDict::new(py);
             locals.set_item("value", 0i32).unwrap();
 
             // Shared Python code to run in each thread
-            let code = "
+            let code = c"
 def increment():
     global value
     value += 1
     return value
 ";
-            py.run(code, None, Some(locals)).unwrap();
+            py.run(code, Some(&locals), None).unwrap();
 
             // Get the Python increment function
-            let increment = locals.get_item("increment").unwrap();
-            let safe_increment = increment.to_object(py);
+            let increment = locals.get_item("increment").unwrap().unwrap();
+            let safe_increment = increment.unbind();
 
             // Create threads that call the Python function
             let mut handles = vec![];
             let num_threads = 5;
 
             for _ in 0..num_threads {
-                let inc_func = safe_increment.clone();
+                let inc_func = safe_increment.clone_ref(py);
                 let handle = thread::spawn(move || {
                     // Each thread will acquire the GIL and call the Python function
-                    Python::with_gil(|py| {
+                    Python::attach(|py| {
                         let _ = inc_func.call0(py).unwrap();
                     });
                 });
                 handles.push(handle);
             }
 
-            // Wait for all threads
-            for handle in handles {
-                handle.join().unwrap();
-            }
+            // Wait for all threads, releasing the GIL they need
+            py.detach(|| {
+                for handle in handles {
+                    handle.join().unwrap();
+                }
+            });
 
             // Check that the value was incremented correctly
             let final_value = locals
                 .get_item("value")
                 .unwrap()
-                .extract::<i32>(py)
+                .unwrap()
+                .extract::<i32>()
                 .unwrap();
             assert_eq!(final_value, num_threads);
         });
@@ -179,27 +184,28 @@ def increment():
     #[test]
     fn test_memory_management() {
         // Test memory management for objects crossing the FFI boundary
-        Python::with_gil(|py| {
+        Python::attach(|py| {
             // Create a Python object and a Rust reference to it
             let obj = PyDict::new(py);
             obj.set_item("key", "value").unwrap();
-            let obj_ref = obj.to_object(py);
+            let obj_ref = obj.clone().unbind();
 
             // Drop the original reference
             drop(obj);
 
             // The object should still be valid through obj_ref
-            let recovered = obj_ref.cast_as::<PyDict>(py).unwrap();
+            let recovered = obj_ref.bind(py);
             let value = recovered
                 .get_item("key")
                 .unwrap()
-                .extract::<String>(py)
+                .unwrap()
+                .extract::<String>()
                 .unwrap();
             assert_eq!(value, "value");
 
             // Now create a reference cycle and test gc behavior
             let locals = PyDict::new(py);
-            let code = "
+            let code = c"
 import gc
 
 # Create reference cycle
@@ -222,47 +228,50 @@ gc.collect()
 # Check if objects were collected (returns None if collected)
 collected = sys.getrefcount(id_a) if id_a in globals() else None
 ";
-            py.run(code, None, Some(locals)).unwrap();
+            py.run(code, Some(&locals), None).unwrap();
 
             // Check that the objects were collected
-            let collected = locals.get_item("collected").unwrap();
-            assert!(collected.is_none(py));
+            let collected = locals.get_item("collected").unwrap().unwrap();
+            assert!(collected.is_none());
         });
     }
 
     #[test]
     fn test_exception_handling() {
         // Test handling of Python exceptions in Rust
-        Python::with_gil(|py| {
+        Python::attach(|py| {
             // Create a Python function that raises different exceptions
-            let code = "
+            let globals = PyDict::new(py);
+            let code = c"
 def raise_value_error():
     raise ValueError('Invalid value')
 
 def raise_runtime_error():
     raise RuntimeError('Runtime failure')
 ";
-            py.run(code, None, None).unwrap();
+            py.run(code, Some(&globals), None).unwrap();
 
             // Get the functions
-            let value_error_fn = py.eval("raise_value_error", None, None).unwrap();
-            let runtime_error_fn = py.eval("raise_runtime_error", None, None).unwrap();
+            let value_error_fn = py.eval(c"raise_value_error", Some(&globals), None).unwrap();
+            let runtime_error_fn = py
+                .eval(c"raise_runtime_error", Some(&globals), None)
+                .unwrap();
 
             // Call and catch the ValueError
-            let result = value_error_fn.call0(py);
+            let result = value_error_fn.call0();
             assert!(result.is_err());
             let err = result.unwrap_err();
             assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
 
             // Call and catch the RuntimeError
-            let result = runtime_error_fn.call0(py);
+            let result = runtime_error_fn.call0();
             assert!(result.is_err());
             let err = result.unwrap_err();
             assert!(err.is_instance_of::<pyo3::exceptions::PyRuntimeError>(py));
 
             // Convert Python exceptions to Rust errors
             let result = value_error_fn
-                .call0(py)
+                .call0()
                 .map_err(|e| format!("Python error: {}", e));
             assert!(result.is_err());
             let err_string = result.unwrap_err();
@@ -1,7 +1,11 @@
+use pyo3::marker::Ungil;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::cell::Cell;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
 
 /// Result type for GIL-free operations.
@@ -26,6 +30,7 @@ where
 {
     task_tx: mpsc::Sender<WorkerMsg<T, R>>,
     _join_handle: Arc<thread::JoinHandle<()>>,
+    _processor: std::marker::PhantomData<fn() -> F>,
 }
 
 impl<T, R, F> GilFreeWorker<T, R, F>
@@ -53,6 +58,7 @@ where
         Self {
             task_tx,
             _join_handle: Arc::new(join_handle),
+            _processor: std::marker::PhantomData,
         }
     }
     
@@ -113,3 +119,274 @@ macro_rules! release_gil {
         $py.allow_threads(|| $body)
     };
 }
+
+/// Work whose GIL use is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GilScope {
+    /// An HTTP request served by a Python handler.
+    Request,
+    /// A compute binding called from Python.
+    Compute,
+}
+
+impl GilScope {
+    pub const ALL: [GilScope; 2] = [GilScope::Request, GilScope::Compute];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GilScope::Request => "request",
+            GilScope::Compute => "compute",
+        }
+    }
+}
+
+/// GIL use of the requests or compute calls of one scope since startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GilStats {
+    /// Requests or calls measured.
+    pub calls: u64,
+    /// Time they ran in total.
+    pub elapsed: Duration,
+    /// Time they held the GIL in total.
+    pub held: Duration,
+    /// Longest time one of them held the GIL.
+    pub max_held: Duration,
+    /// Time they waited to acquire the GIL in total.
+    pub waited: Duration,
+}
+
+impl GilStats {
+    /// Share of their running time spent holding the GIL.
+    pub fn held_ratio(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.held.as_secs_f64() / self.elapsed.as_secs_f64()
+    }
+}
+
+struct GilCounters {
+    calls: AtomicU64,
+    elapsed_ns: AtomicU64,
+    held_ns: AtomicU64,
+    max_held_ns: AtomicU64,
+    waited_ns: AtomicU64,
+}
+
+impl GilCounters {
+    const fn new() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            elapsed_ns: AtomicU64::new(0),
+            held_ns: AtomicU64::new(0),
+            max_held_ns: AtomicU64::new(0),
+            waited_ns: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, elapsed: Duration, held: Duration, waited: Duration) {
+        let nanos = |duration: Duration| duration.as_nanos() as u64;
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.elapsed_ns.fetch_add(nanos(elapsed), Ordering::Relaxed);
+        self.held_ns.fetch_add(nanos(held), Ordering::Relaxed);
+        self.max_held_ns.fetch_max(nanos(held), Ordering::Relaxed);
+        self.waited_ns.fetch_add(nanos(waited), Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> GilStats {
+        let duration = |nanos: &AtomicU64| Duration::from_nanos(nanos.load(Ordering::Relaxed));
+        GilStats {
+            calls: self.calls.load(Ordering::Relaxed),
+            elapsed: duration(&self.elapsed_ns),
+            held: duration(&self.held_ns),
+            max_held: duration(&self.max_held_ns),
+            waited: duration(&self.waited_ns),
+        }
+    }
+}
+
+static GIL_COUNTERS: [GilCounters; 2] = [GilCounters::new(), GilCounters::new()];
+
+/// GIL time of the request or call being measured on this thread.
+#[derive(Clone, Copy)]
+struct Tally {
+    held: Duration,
+    waited: Duration,
+    released: Duration,
+}
+
+impl Tally {
+    const ZERO: Tally = Tally {
+        held: Duration::ZERO,
+        waited: Duration::ZERO,
+        released: Duration::ZERO,
+    };
+}
+
+thread_local! {
+    static TALLY: Cell<Tally> = const { Cell::new(Tally::ZERO) };
+    static HOLDING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn tally(update: impl FnOnce(&mut Tally)) {
+    TALLY.with(|cell| {
+        let mut tally = cell.get();
+        update(&mut tally);
+        cell.set(tally);
+    });
+}
+
+/// `Python::attach`, counting the time spent waiting for and holding the
+/// GIL towards the request being measured on this thread.
+pub fn with_gil<F, R>(f: F) -> R
+where
+    F: for<'py> FnOnce(Python<'py>) -> R,
+{
+    if HOLDING.get() {
+        return Python::attach(f);
+    }
+    /// Clears `HOLDING` even if `f` panics.
+    struct Holding;
+
+    impl Drop for Holding {
+        fn drop(&mut self) {
+            HOLDING.set(false);
+        }
+    }
+
+    let requested = Instant::now();
+    Python::attach(|py| {
+        let acquired = Instant::now();
+        HOLDING.set(true);
+        let holding = Holding;
+        let result = f(py);
+        drop(holding);
+        tally(|tally| {
+            tally.waited += acquired - requested;
+            tally.held += acquired.elapsed();
+        });
+        result
+    })
+}
+
+/// `Python::detach`, not counting the time the GIL is released
+/// towards the compute call being measured on this thread.
+pub fn allow_threads<T, F>(py: Python<'_>, f: F) -> T
+where
+    F: Ungil + FnOnce() -> T,
+    T: Ungil,
+{
+    let released = Instant::now();
+    let result = py.detach(f);
+    tally(|tally| tally.released += released.elapsed());
+    result
+}
+
+/// Run `f` and record its GIL use under `scope`. When the GIL is `held` on
+/// entry, all time outside [`allow_threads`] counts as holding it; otherwise
+/// only time inside [`with_gil`] does.
+fn observe<R>(scope: GilScope, held: bool, f: impl FnOnce() -> R) -> R {
+    let outer = TALLY.replace(Tally::ZERO);
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let tally = TALLY.replace(outer);
+    let held = if held {
+        elapsed.saturating_sub(tally.released)
+    } else {
+        tally.held
+    };
+    GIL_COUNTERS[scope as usize].record(elapsed, held, tally.waited);
+    result
+}
+
+/// Serve a request with `f`, which takes the GIL through [`with_gil`].
+pub fn observe_request<R>(f: impl FnOnce() -> R) -> R {
+    observe(GilScope::Request, false, f)
+}
+
+/// Run a compute binding with `f`, which releases the GIL through
+/// [`allow_threads`].
+pub fn observe_compute<R>(_py: Python<'_>, f: impl FnOnce() -> R) -> R {
+    COMPUTE_CALLED.store(true, Ordering::Relaxed);
+    observe(GilScope::Compute, true, f)
+}
+
+/// Set by the first compute binding, which may start Rayon's global pool.
+static COMPUTE_CALLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a compute binding has run in this process, so that Rayon's
+/// global pool may have started.
+pub fn compute_called() -> bool {
+    COMPUTE_CALLED.load(Ordering::Relaxed)
+}
+
+/// GIL use of each scope since startup.
+pub fn stats() -> Vec<(GilScope, GilStats)> {
+    GilScope::ALL
+        .iter()
+        .map(|&scope| (scope, GIL_COUNTERS[scope as usize].snapshot()))
+        .collect()
+}
+
+/// Return how long requests and compute calls held the GIL: a dict mapping
+/// "request" and "compute" to their calls, elapsed_ms, held_ms, max_held_ms,
+/// waited_ms and held_ratio, the share of their running time spent holding
+/// the GIL. Time a Python handler releases the GIL itself, as `time.sleep`
+/// does, still counts as held.
+#[pyfunction]
+pub fn gil_stats(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let dict = PyDict::new(py);
+    for (scope, stats) in stats() {
+        let entry = PyDict::new(py);
+        entry.set_item("calls", stats.calls)?;
+        entry.set_item("elapsed_ms", ms(stats.elapsed))?;
+        entry.set_item("held_ms", ms(stats.held))?;
+        entry.set_item("max_held_ms", ms(stats.max_held))?;
+        entry.set_item("waited_ms", ms(stats.waited))?;
+        entry.set_item("held_ratio", stats.held_ratio())?;
+        dict.set_item(scope.name(), entry)?;
+    }
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compute_stats() -> GilStats {
+        stats()
+            .into_iter()
+            .find(|(scope, _)| *scope == GilScope::Compute)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn released_time_is_not_counted_as_held() {
+        let before = compute_stats();
+        observe(GilScope::Compute, true, || {
+            thread::sleep(Duration::from_millis(5));
+            tally(|tally| tally.released += Duration::from_secs(1));
+        });
+        let after = compute_stats();
+        assert_eq!(after.calls, before.calls + 1);
+        assert!(after.elapsed - before.elapsed >= Duration::from_millis(5));
+        assert_eq!(after.held, before.held);
+    }
+
+    #[test]
+    fn request_holds_are_summed() {
+        let tally = TALLY.with(|cell| {
+            observe(GilScope::Request, false, || {
+                with_gil(|_py| with_gil(|_py| thread::sleep(Duration::from_millis(2))));
+                with_gil(|_py| thread::sleep(Duration::from_millis(2)));
+                cell.get()
+            })
+        });
+        assert!(tally.held >= Duration::from_millis(4));
+        assert!(tally.held < Duration::from_millis(500));
+        assert!(stats()[0].1.calls >= 1);
+    }
+}
@@ -0,0 +1,360 @@
+//! Named health checks aggregated by the server's `/ready` endpoint
+//!
+//! Subsystems and Python code register [`HealthCheck`] implementations under
+//! a name; [`run`] calls each in registration order and reports whether it
+//! passed, why not and how long it took. The process-wide memory limit is
+//! checked from the start; pool allocators and model registries register
+//! checks of their own on request.
+//!
+//! Critical dependencies, such as a database connection or a model that has
+//! to load, are registered as [`Dependency`] checks: they are probed on each
+//! run until they first succeed and pass from then on.
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::{Value, json};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::ForziumError;
+use crate::interpreter;
+use crate::memory::accountant;
+
+/// Something whose health decides whether the process should get traffic.
+pub trait HealthCheck: Send + Sync {
+    /// `Ok` when healthy, otherwise why not.
+    fn check(&self) -> Result<(), String>;
+}
+
+/// Outcome of one check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub name: String,
+    /// Why the check failed, `None` if it passed.
+    pub error: Option<String>,
+    pub latency: Duration,
+}
+
+/// Outcomes of every registered check, in registration order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Report {
+    pub checks: Vec<CheckResult>,
+}
+
+impl Report {
+    /// Whether every check passed.
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.error.is_none())
+    }
+
+    /// Names of the checks that failed.
+    pub fn failing(&self) -> Vec<&str> {
+        self.checks
+            .iter()
+            .filter(|check| check.error.is_some())
+            .map(|check| check.name.as_str())
+            .collect()
+    }
+
+    /// Body of the `/ready` response: the overall status, the failing checks
+    /// and each check's status, latency and error.
+    pub fn to_json(&self) -> Value {
+        let checks: serde_json::Map<String, Value> = self
+            .checks
+            .iter()
+            .map(|check| {
+                let mut entry = json!({
+                    "status": status(check.error.is_none()),
+                    "latency_ms": check.latency.as_secs_f64() * 1000.0,
+                });
+                if let Some(error) = &check.error {
+                    entry["error"] = error.as_str().into();
+                }
+                (check.name.clone(), entry)
+            })
+            .collect();
+        json!({
+            "status": status(self.healthy()),
+            "failing": self.failing(),
+            "checks": checks,
+        })
+    }
+}
+
+fn status(healthy: bool) -> &'static str {
+    if healthy { "ok" } else { "fail" }
+}
+
+/// Fails while reservations have reached the process-wide memory limit.
+pub struct MemoryLimitCheck;
+
+impl HealthCheck for MemoryLimitCheck {
+    fn check(&self) -> Result<(), String> {
+        let stats = accountant::stats();
+        if stats.limit > 0 && stats.total >= stats.limit {
+            return Err(format!(
+                "{} of {} bytes of the memory limit reserved",
+                stats.total, stats.limit
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A dependency the process needs before taking traffic: `probe` is run
+/// until it first succeeds, after which the check always passes.
+pub struct Dependency<C> {
+    probe: C,
+    ready: AtomicBool,
+}
+
+impl<C: HealthCheck> Dependency<C> {
+    pub fn new(probe: C) -> Self {
+        Self {
+            probe,
+            ready: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the probe has succeeded.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+}
+
+impl<C: HealthCheck> HealthCheck for Dependency<C> {
+    fn check(&self) -> Result<(), String> {
+        if !self.is_ready() {
+            self.probe.check()?;
+            self.ready.store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+}
+
+/// A Python callable and the interpreter it was registered from; it fails
+/// by returning False or raising.
+struct PyHealthCheck {
+    check: Py<PyAny>,
+    interpreter: i64,
+}
+
+impl PyHealthCheck {
+    fn new(py: Python<'_>, check: Py<PyAny>) -> PyResult<Self> {
+        let interpreter = interpreter::current_id(py)?;
+        Ok(Self { check, interpreter })
+    }
+}
+
+impl HealthCheck for PyHealthCheck {
+    fn check(&self) -> Result<(), String> {
+        Python::attach(|py| {
+            interpreter::ensure_current(py, self.interpreter).map_err(|err| err.to_string())?;
+            let result = self.check.call0(py).map_err(|err| err.to_string())?;
+            let result = result.bind(py);
+            if !result.is_none() && !result.is_truthy().map_err(|err| err.to_string())? {
+                return Err("check returned False".into());
+            }
+            Ok(())
+        })
+    }
+}
+
+type Checks = RwLock<Vec<(String, Arc<dyn HealthCheck>)>>;
+
+static CHECKS: Lazy<Checks> = Lazy::new(|| {
+    RwLock::new(vec![(
+        "memory".to_string(),
+        Arc::new(MemoryLimitCheck) as _,
+    )])
+});
+
+/// Run `check` as part of readiness under `name`, replacing any check
+/// already registered with that name.
+pub fn register_check(name: &str, check: Arc<dyn HealthCheck>) {
+    let mut checks = CHECKS.write();
+    match checks.iter_mut().find(|(existing, _)| existing == name) {
+        Some(entry) => entry.1 = check,
+        None => checks.push((name.to_string(), check)),
+    }
+}
+
+/// Stop running the check registered as `name`. Returns whether there was
+/// one.
+pub fn unregister_check(name: &str) -> bool {
+    let mut checks = CHECKS.write();
+    let before = checks.len();
+    checks.retain(|(existing, _)| existing != name);
+    checks.len() != before
+}
+
+/// Run every registered check. A check that panics fails.
+///
+/// Checks run without the registry locked, so they may register or remove
+/// checks themselves.
+pub fn run() -> Report {
+    let checks = CHECKS.read().clone();
+    let checks = checks
+        .into_iter()
+        .map(|(name, check)| {
+            let start = Instant::now();
+            let error = match catch_unwind(AssertUnwindSafe(|| check.check())) {
+                Ok(result) => result.err(),
+                Err(_) => Some("check panicked".into()),
+            };
+            CheckResult {
+                name,
+                error,
+                latency: start.elapsed(),
+            }
+        })
+        .collect();
+    Report { checks }
+}
+
+/// Run `check()` as part of the server's `/ready` endpoint under `name`,
+/// replacing any check registered with that name. The check fails by
+/// returning False or raising.
+#[pyfunction]
+pub fn register_health_check(py: Python<'_>, name: &str, check: Py<PyAny>) -> PyResult<()> {
+    if !check.bind(py).is_callable() {
+        return Err(ForziumError::Validation("health check must be callable".into()).into());
+    }
+    register_check(name, Arc::new(PyHealthCheck::new(py, check)?));
+    Ok(())
+}
+
+/// Hold the server's `/ready` endpoint at 503 until `probe()` first
+/// succeeds, reporting `name` among the failing components meanwhile. The
+/// probe fails by returning False or raising and is not called again once it
+/// has succeeded. Replaces any check registered with that name.
+#[pyfunction]
+pub fn register_dependency(py: Python<'_>, name: &str, probe: Py<PyAny>) -> PyResult<()> {
+    if !probe.bind(py).is_callable() {
+        return Err(ForziumError::Validation("dependency probe must be callable".into()).into());
+    }
+    register_check(
+        name,
+        Arc::new(Dependency::new(PyHealthCheck::new(py, probe)?)),
+    );
+    Ok(())
+}
+
+/// Remove the health check registered as `name`. Returns whether there was
+/// one.
+#[pyfunction]
+pub fn unregister_health_check(name: &str) -> bool {
+    unregister_check(name)
+}
+
+/// Run every health check and return the `/ready` report: "status" is "ok"
+/// or "fail", "failing" lists the names of failed checks and "checks" maps
+/// each name to its status, latency_ms and, on failure, error.
+#[pyfunction]
+pub fn run_health_checks(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let report = py.detach(run);
+    let dict = PyDict::new(py);
+    dict.set_item("status", status(report.healthy()))?;
+    dict.set_item("failing", report.failing())?;
+    let checks = PyDict::new(py);
+    for check in report.checks {
+        let entry = PyDict::new(py);
+        entry.set_item("status", status(check.error.is_none()))?;
+        entry.set_item("latency_ms", check.latency.as_secs_f64() * 1000.0)?;
+        if let Some(error) = check.error {
+            entry.set_item("error", error)?;
+        }
+        checks.set_item(check.name, entry)?;
+    }
+    dict.set_item("checks", checks)?;
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Result<(), &'static str>);
+
+    impl HealthCheck for Fixed {
+        fn check(&self) -> Result<(), String> {
+            self.0.map_err(str::to_string)
+        }
+    }
+
+    struct Panics;
+
+    impl HealthCheck for Panics {
+        fn check(&self) -> Result<(), String> {
+            panic!("broken check")
+        }
+    }
+
+    struct Flaky(AtomicBool);
+
+    impl HealthCheck for Flaky {
+        fn check(&self) -> Result<(), String> {
+            if self.0.swap(false, Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("unavailable".into())
+            }
+        }
+    }
+
+    fn result<'a>(report: &'a Report, name: &str) -> Option<&'a CheckResult> {
+        report.checks.iter().find(|check| check.name == name)
+    }
+
+    #[test]
+    fn failing_checks_fail_the_report() {
+        register_check("health_test_ok", Arc::new(Fixed(Ok(()))));
+        register_check("health_test_bad", Arc::new(Fixed(Ok(()))));
+        register_check("health_test_bad", Arc::new(Fixed(Err("down"))));
+        register_check("health_test_panic", Arc::new(Panics));
+        let report = run();
+        assert!(result(&report, "memory").is_some());
+        assert_eq!(result(&report, "health_test_ok").unwrap().error, None);
+        assert_eq!(
+            result(&report, "health_test_bad").unwrap().error.as_deref(),
+            Some("down")
+        );
+        assert_eq!(
+            result(&report, "health_test_panic")
+                .unwrap()
+                .error
+                .as_deref(),
+            Some("check panicked")
+        );
+        assert!(!report.healthy());
+        let body = report.to_json();
+        assert_eq!(body["status"], "fail");
+        assert_eq!(body["checks"]["health_test_bad"]["error"], "down");
+        assert_eq!(body["checks"]["health_test_ok"]["status"], "ok");
+        assert_eq!(report.failing(), ["health_test_bad", "health_test_panic"]);
+        assert_eq!(body["failing"][0], "health_test_bad");
+
+        for name in ["health_test_ok", "health_test_bad", "health_test_panic"] {
+            assert!(unregister_check(name));
+        }
+        assert!(!unregister_check("health_test_ok"));
+        assert!(result(&run(), "health_test_bad").is_none());
+    }
+
+    #[test]
+    fn dependencies_pass_once_probed_successfully() {
+        let probe = Flaky(AtomicBool::new(false));
+        let dependency = Dependency::new(probe);
+        assert_eq!(dependency.check(), Err("unavailable".into()));
+        assert!(!dependency.is_ready());
+        dependency.probe.0.store(true, Ordering::SeqCst);
+        assert_eq!(dependency.check(), Ok(()));
+        // The probe now fails, but is no longer consulted
+        assert_eq!(dependency.check(), Ok(()));
+        assert!(dependency.is_ready());
+    }
+}
@@ -0,0 +1,92 @@
+//! Per-interpreter state for embedding hosts running several interpreters.
+//!
+//! Python objects belong to the interpreter that created them, so the
+//! engine keeps none in process-wide caches: anything it would cache is
+//! held per interpreter here, and callbacks remember where they were
+//! registered. PyO3 itself still refuses to initialise an extension module
+//! in a second interpreter, so until it lifts that restriction importing
+//! `forzium_engine` outside the main interpreter raises `ImportError`.
+
+use parking_lot::Mutex;
+use pyo3::ffi;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// Identifier of the interpreter `py` is attached to.
+pub fn current_id(py: Python<'_>) -> PyResult<i64> {
+    // SAFETY: attached to an interpreter, whose state outlives this call.
+    let id = unsafe { ffi::PyInterpreterState_GetID(ffi::PyInterpreterState_Get()) };
+    if id < 0 {
+        return Err(PyErr::fetch(py));
+    }
+    Ok(id)
+}
+
+/// Fail unless `py` is attached to interpreter `owner`, for callbacks run
+/// from Rust threads, which attach to the main interpreter.
+pub fn ensure_current(py: Python<'_>, owner: i64) -> PyResult<()> {
+    let current = current_id(py)?;
+    if current != owner {
+        return Err(pyo3::exceptions::PyRuntimeError::new_err(format!(
+            "object of interpreter {owner} used from interpreter {current}"
+        )));
+    }
+    Ok(())
+}
+
+/// A value per interpreter, created on first use in each.
+///
+/// Values are never dropped: those holding Python objects may not be
+/// released once their interpreter is gone, and an interpreter only ever
+/// adds one.
+pub struct PerInterpreter<T: 'static> {
+    values: Mutex<HashMap<i64, &'static T>>,
+}
+
+impl<T: 'static> PerInterpreter<T> {
+    pub fn new() -> Self {
+        Self {
+            values: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The value of the interpreter `py` is attached to, created with
+    /// `init` if there is none yet. `init` runs without the lock held, so
+    /// it may call into Python; if two threads race, one value is kept.
+    pub fn get_or_try_init<F>(&self, py: Python<'_>, init: F) -> PyResult<&'static T>
+    where
+        F: FnOnce() -> PyResult<T>,
+    {
+        let id = current_id(py)?;
+        if let Some(value) = self.values.lock().get(&id) {
+            return Ok(value);
+        }
+        let value = init()?;
+        let mut values = self.values.lock();
+        Ok(*values
+            .entry(id)
+            .or_insert_with(|| Box::leak(Box::new(value))))
+    }
+}
+
+impl<T: 'static> Default for PerInterpreter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_created_once_per_interpreter() {
+        let values = PerInterpreter::new();
+        Python::attach(|py| {
+            let first = values.get_or_try_init(py, || Ok(1)).unwrap();
+            let second = values.get_or_try_init(py, || Ok(2)).unwrap();
+            assert_eq!((*first, *second), (1, 1));
+            assert_eq!(current_id(py).unwrap(), 0);
+        });
+    }
+}
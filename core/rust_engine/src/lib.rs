@@ -1,156 +1,288 @@
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyModule};
+use rayon::prelude::*;
+use std::collections::HashMap;
 
 pub mod async_compute;
 #[path = "../bindings/mod.rs"]
 mod bindings;
+pub mod broadcast;
+pub mod cache;
+pub mod client;
 #[path = "../compute/mod.rs"]
 pub mod compute;
+pub mod config;
+pub mod dlpack;
 pub mod error;
 pub mod error_bridge;
+pub mod ffi_stats;
 pub mod gil_utils;
+pub mod health;
+pub mod interpreter;
+pub mod logging;
 pub mod memory;
+pub mod metrics;
 pub mod numpy_ops;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod panic_hook;
+pub mod redis;
+pub mod runtime_manager;
+pub mod scheduler;
 pub mod server;
+pub mod trace_context;
 pub mod validation;
 
-use crate::async_compute::{create_async_compute, AsyncCompute, ComputeHandle};
+use crate::async_compute::{
+    create_async_compute, AsyncCompute, ComputeHandle, Priority, ResultChunks,
+};
 use crate::compute::{
+    clustering::{py_kmeans_assign, py_kmeans_update, py_knn_search},
     data_transform,
     engine::ComputeEngine,
-    ml_inference::PyLinearModel,
-    rayon_metrics, simd_ops, tensor_ops,
+    ml_inference::{PyLinearModel, PyLogisticModel, PySoftmaxModel},
+    model_registry::PyModelRegistry,
+    preprocessing::PyPipeline,
+    rayon_metrics,
+    resource_limits::{
+        get_queue_stats, get_resource_limits, reset_resource_limits, set_operation_limit,
+        set_operation_timeout, set_queue_config, set_resource_limits,
+    },
+    simd_ops, tensor_ops,
     thread_pool::{
         configure_global_thread_pool, initialize_optimal_thread_pools, run_in_compute_pool,
-        run_in_io_pool,
+        run_in_io_pool, spawn_in_compute_pool, ThreadPoolConfig, ThreadPoolManager,
     },
+    tree_ensemble::PyTreeEnsemble,
 };
+use crate::dlpack::Matrix;
 use crate::error::ForziumError;
 use crate::error_bridge::{
-    get_last_error, set_capture_stack_traces, set_verbose_errors, ErrorCategory,
+    error_stats, get_last_error, set_capture_stack_traces, set_verbose_errors, ErrorCategory,
 };
-use crate::memory::gc_interface::force_gc;
+use crate::memory::accountant::{memory_accounting, set_memory_limit};
+use crate::memory::allocator::memory_stats;
+use crate::memory::gc_interface::{force_gc, gc_stats};
+use crate::health::{
+    register_dependency, register_health_check, run_health_checks, unregister_health_check,
+};
+use crate::metrics::{export_metrics, merge_metrics, metrics_sources};
+use crate::panic_hook::get_recent_panics;
+use crate::runtime_manager::{configure_shared_runtime, shared_runtime_metrics};
 use crate::server::http_engine::ForziumHttpServer;
+use crate::server::route_metrics::route_stats;
+use crate::server::slow_requests::slow_request_stats;
 use crate::validation::compute_request::ComputeRequestSchema;
 
 #[pyfunction]
-fn multiply(matrix: Vec<Vec<f64>>, factor: f64) -> PyResult<Vec<Vec<f64>>> {
-    tensor_ops::multiply(&matrix, factor).map_err(Into::into)
+fn multiply(py: Python<'_>, matrix: Matrix, factor: f64) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("multiply");
+    let tensor = matrix.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::multiply(&matrix, factor).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn add(matrix: Vec<Vec<f64>>, addend: f64) -> PyResult<Vec<Vec<f64>>> {
-    tensor_ops::add(&matrix, addend).map_err(Into::into)
+fn add(py: Python<'_>, matrix: Matrix, addend: f64) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("add");
+    let tensor = matrix.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::add(&matrix, addend).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn matmul(a: Vec<Vec<f64>>, b: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    tensor_ops::matmul(&a, &b).map_err(Into::into)
+fn matmul(py: Python<'_>, a: Matrix, b: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("matmul");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::matmul(&a, &b).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn simd_matmul(py: Python<'_>, a: Vec<Vec<f64>>, b: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    let a_clone = a.clone();
-    let b_clone = b.clone();
-
-    // Release GIL during computation
-    py.allow_threads(move || tensor_ops::simd_matmul(&a_clone, &b_clone).map_err(Into::into))
+fn simd_matmul(py: Python<'_>, a: Matrix, b: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("simd_matmul");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::simd_matmul(&a, &b).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn transpose(matrix: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    tensor_ops::transpose(&matrix).map_err(Into::into)
+fn transpose(py: Python<'_>, matrix: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("transpose");
+    let tensor = matrix.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::transpose(&matrix).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn elementwise_add(py: Python<'_>, a: Vec<Vec<f64>>, b: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    let a_clone = a.clone();
-    let b_clone = b.clone();
-
-    // Release GIL during computation
-    py.allow_threads(move || tensor_ops::elementwise_add(&a_clone, &b_clone).map_err(Into::into))
+fn elementwise_add(py: Python<'_>, a: Matrix, b: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("elementwise_add");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::elementwise_add(&a, &b).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
 fn simd_elementwise_add(
     py: Python<'_>,
-    a: Vec<Vec<f64>>,
-    b: Vec<Vec<f64>>,
-) -> PyResult<Vec<Vec<f64>>> {
-    let a_clone = a.clone();
-    let b_clone = b.clone();
-
-    // Release GIL during computation
-    py.allow_threads(move || {
-        tensor_ops::simd_elementwise_add(&a_clone, &b_clone).map_err(Into::into)
+    a: Matrix,
+    b: Matrix,
+) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("simd_elementwise_add");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::simd_elementwise_add(&a, &b).map_err(Into::into)
+        })
     })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn elementwise_mul(py: Python<'_>, a: Vec<Vec<f64>>, b: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    let a_clone = a.clone();
-    let b_clone = b.clone();
-
-    // Release GIL during computation
-    py.allow_threads(move || tensor_ops::hadamard(&a_clone, &b_clone).map_err(Into::into))
+fn elementwise_mul(py: Python<'_>, a: Matrix, b: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("elementwise_mul");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::hadamard(&a, &b).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn conv2d(py: Python<'_>, a: Vec<Vec<f64>>, k: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    let a_clone = a.clone();
-    let k_clone = k.clone();
-
-    // Release GIL during computation
-    py.allow_threads(move || tensor_ops::conv2d(&a_clone, &k_clone).map_err(Into::into))
+fn conv2d(py: Python<'_>, a: Matrix, k: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("conv2d");
+    let tensor = a.is_tensor() || k.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::conv2d(&a, &k).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
-fn max_pool2d(py: Python<'_>, a: Vec<Vec<f64>>, size: usize) -> PyResult<Vec<Vec<f64>>> {
-    let a_clone = a.clone();
-    let size_clone = size;
+#[pyo3(signature = (a, k, stride=1, padding=0))]
+fn conv2d_transpose(
+    py: Python<'_>,
+    a: Matrix,
+    k: Matrix,
+    stride: usize,
+    padding: usize,
+) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("conv2d_transpose");
+    let tensor = a.is_tensor() || k.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::conv2d_transpose(&a, &k, stride, padding).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
+}
 
-    // Release GIL during computation
-    py.allow_threads(move || tensor_ops::max_pool2d(&a_clone, size_clone).map_err(Into::into))
+#[pyfunction]
+fn max_pool2d(py: Python<'_>, a: Matrix, size: usize) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("max_pool2d");
+    let tensor = a.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            tensor_ops::max_pool2d(&a, size).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 #[pyfunction]
 fn trigger_panic() -> PyResult<()> {
+    let _call = crate::ffi_call!("trigger_panic");
     Err(ForziumError::Compute("forced panic".into()).into())
 }
 
 #[pyfunction]
 fn noop() -> PyResult<()> {
+    let _call = crate::ffi_call!("noop");
     Ok(())
 }
 
 #[pyfunction]
 fn echo_u64(value: u64) -> PyResult<u64> {
+    let _call = crate::ffi_call!("echo_u64");
     Ok(value)
 }
 
 #[pyfunction]
-fn scale(vector: Vec<f64>, factor: f64) -> PyResult<Vec<f64>> {
-    data_transform::scale(&vector, factor).map_err(Into::into)
+fn scale(py: Python<'_>, vector: Vec<f64>, factor: f64) -> PyResult<Vec<f64>> {
+    let _call = crate::ffi_call!("scale");
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            data_transform::scale(&vector, factor).map_err(Into::into)
+        })
+    })
 }
 
 #[pyfunction]
-fn normalize(vector: Vec<f64>) -> PyResult<Vec<f64>> {
-    data_transform::normalize(&vector).map_err(Into::into)
+fn normalize(py: Python<'_>, vector: Vec<f64>) -> PyResult<Vec<f64>> {
+    let _call = crate::ffi_call!("normalize");
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            data_transform::normalize(&vector).map_err(Into::into)
+        })
+    })
 }
 
 #[pyfunction]
-fn reshape(vector: Vec<f64>, rows: usize, cols: usize) -> PyResult<Vec<Vec<f64>>> {
-    data_transform::reshape(&vector, rows, cols).map_err(Into::into)
+fn reshape(py: Python<'_>, vector: Vec<f64>, rows: usize, cols: usize) -> PyResult<Vec<Vec<f64>>> {
+    let _call = crate::ffi_call!("reshape");
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            data_transform::reshape(&vector, rows, cols).map_err(Into::into)
+        })
+    })
 }
 
-#[pyfunction]
-fn rayon_pool_metrics(py: Python<'_>, reset: Option<bool>) -> PyResult<PyObject> {
-    let snapshot = if reset.unwrap_or(false) {
-        rayon_metrics::snapshot_and_reset()
-            .map_err(|err| pyo3::exceptions::PyRuntimeError::new_err(err.to_string()))?
-    } else {
-        rayon_metrics::snapshot()
-    };
+fn rayon_snapshot_dict<'py>(
+    py: Python<'py>,
+    snapshot: &rayon_metrics::RayonPoolSnapshot,
+) -> PyResult<Bound<'py, PyDict>> {
     let dict = PyDict::new(py);
     dict.set_item("observed_threads", snapshot.observed_threads)?;
     dict.set_item("max_active_threads", snapshot.max_active_threads)?;
@@ -162,21 +294,112 @@ fn rayon_pool_metrics(py: Python<'_>, reset: Option<bool>) -> PyResult<PyObject>
     dict.set_item("mean_task_duration_us", snapshot.mean_task_duration_us)?;
     dict.set_item("max_task_duration_us", snapshot.max_task_duration_us)?;
     dict.set_item("min_task_duration_us", snapshot.min_task_duration_us)?;
+    dict.set_item("p50_task_duration_us", snapshot.p50_task_duration_us)?;
+    dict.set_item("p99_task_duration_us", snapshot.p99_task_duration_us)?;
+    let histogram: Vec<(Option<u64>, u64)> = snapshot
+        .task_duration_histogram
+        .iter()
+        .map(|bucket| (bucket.upper_bound_us, bucket.count))
+        .collect();
+    dict.set_item("task_duration_histogram", histogram)?;
     dict.set_item("busy_time_seconds", snapshot.busy_time_seconds)?;
     dict.set_item("observation_seconds", snapshot.observation_seconds)?;
+    Ok(dict)
+}
+
+/// Rayon utilisation across all pools, with breakdowns under `"pools"`
+/// keyed by pool name and `"operations"` keyed by operation label
+#[pyfunction]
+#[pyo3(signature = (reset=None))]
+fn rayon_pool_metrics(py: Python<'_>, reset: Option<bool>) -> PyResult<Py<PyAny>> {
+    let pools = rayon_metrics::pool_snapshots();
+    let operations = rayon_metrics::operation_snapshots();
+    let snapshot = if reset.unwrap_or(false) {
+        rayon_metrics::snapshot_and_reset()
+            .map_err(|err| pyo3::exceptions::PyRuntimeError::new_err(err.to_string()))?
+    } else {
+        rayon_metrics::snapshot()
+    };
+    let dict = rayon_snapshot_dict(py, &snapshot)?;
+    let per_pool = PyDict::new(py);
+    for (name, pool) in &pools {
+        per_pool.set_item(name, rayon_snapshot_dict(py, pool)?)?;
+    }
+    dict.set_item("pools", per_pool)?;
+    let per_operation = PyDict::new(py);
+    for (name, operation) in &operations {
+        per_operation.set_item(name, rayon_snapshot_dict(py, operation)?)?;
+    }
+    dict.set_item("operations", per_operation)?;
     Ok(dict.into())
 }
 
 /// Matrix multiplication using the best available SIMD instruction set
 #[pyfunction]
-fn optimal_matmul(a: Vec<Vec<f64>>, b: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    simd_ops::optimal_matmul(&a, &b).map_err(Into::into)
+fn optimal_matmul(py: Python<'_>, a: Matrix, b: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("optimal_matmul");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            simd_ops::optimal_matmul(&a, &b).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
 }
 
 /// Element-wise matrix addition using the best available SIMD instruction set
 #[pyfunction]
-fn optimal_add(a: Vec<Vec<f64>>, b: Vec<Vec<f64>>) -> PyResult<Vec<Vec<f64>>> {
-    simd_ops::optimal_add(&a, &b).map_err(Into::into)
+fn optimal_add(py: Python<'_>, a: Matrix, b: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("optimal_add");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            simd_ops::optimal_add(&a, &b).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
+}
+
+/// Element-wise matrix multiplication using the best available SIMD instruction set
+#[pyfunction]
+fn optimal_mul(py: Python<'_>, a: Matrix, b: Matrix) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("optimal_mul");
+    let tensor = a.is_tensor() || b.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            simd_ops::optimal_mul(&a, &b).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
+}
+
+/// Matrix-by-scalar multiplication using the best available SIMD instruction set
+#[pyfunction]
+fn optimal_multiply(py: Python<'_>, matrix: Matrix, factor: f64) -> PyResult<Matrix> {
+    let _call = crate::ffi_call!("optimal_multiply");
+    let tensor = matrix.is_tensor();
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            simd_ops::optimal_multiply(&matrix, factor).map_err(Into::into)
+        })
+    })
+    .map(|rows| Matrix::output(rows, tensor))
+}
+
+/// Vector scaling using the best available SIMD instruction set
+#[pyfunction]
+fn optimal_scale(py: Python<'_>, vector: Vec<f64>, factor: f64) -> PyResult<Vec<f64>> {
+    let _call = crate::ffi_call!("optimal_scale");
+    gil_utils::observe_compute(py, || {
+        // Release GIL during computation
+        gil_utils::allow_threads(py, move || {
+            simd_ops::optimal_scale(&vector, factor).map_err(Into::into)
+        })
+    })
 }
 
 /// Returns the highest SIMD instruction set supported by the current CPU
@@ -194,10 +417,11 @@ fn benchmark_simd() -> PyResult<String> {
 /// Initialize thread pools with optimal settings for the current hardware
 #[pyfunction]
 fn optimize_thread_pools() -> PyResult<()> {
-    initialize_optimal_thread_pools().map_err(|err| pyo3::exceptions::PyRuntimeError::new_err(err))
+    initialize_optimal_thread_pools().map_err(pyo3::exceptions::PyRuntimeError::new_err)
 }
 
 /// Configure the global rayon thread pool with custom settings
+/// Pools that are already running are rebuilt with the new settings
 #[pyfunction]
 fn configure_rayon_thread_pool(
     thread_count: Option<usize>,
@@ -211,29 +435,131 @@ fn configure_rayon_thread_pool(
     let breadth = breadth_first.unwrap_or(false);
 
     configure_global_thread_pool(threads, stack, lifetime, breadth)
-        .map_err(|err| pyo3::exceptions::PyRuntimeError::new_err(err))
+        .map_err(pyo3::exceptions::PyRuntimeError::new_err)
+}
+
+/// Shut down a named thread pool so its workers can exit
+/// Returns False if no pool with that name exists
+#[pyfunction]
+fn shutdown_pool(name: &str) -> bool {
+    ThreadPoolManager::global().shutdown_pool(name)
+}
+
+/// Rebuild a named thread pool with new settings, creating it if needed
+/// Settings that are not given keep the pool's current values
+#[pyfunction]
+#[pyo3(signature = (name, thread_count=None, stack_size_mb=None, thread_lifetime_seconds=None, breadth_first=None))]
+fn recreate_pool(
+    name: &str,
+    thread_count: Option<usize>,
+    stack_size_mb: Option<usize>,
+    thread_lifetime_seconds: Option<u64>,
+    breadth_first: Option<bool>,
+) -> PyResult<()> {
+    let manager = ThreadPoolManager::global();
+    let current = manager.pool_config(name).unwrap_or_else(|| manager.get_config());
+    let config = ThreadPoolConfig {
+        thread_count: thread_count.unwrap_or(current.thread_count),
+        stack_size: stack_size_mb.map_or(current.stack_size, |mb| mb * 1024 * 1024),
+        thread_lifetime_ms: thread_lifetime_seconds
+            .map_or(current.thread_lifetime_ms, |secs| secs * 1000),
+        breadth_first: breadth_first.unwrap_or(current.breadth_first),
+        use_numa_affinity: current.use_numa_affinity,
+    };
+    manager
+        .recreate_pool(name, config)
+        .map(|_| ())
+        .map_err(pyo3::exceptions::PyRuntimeError::new_err)
+}
+
+/// Pin a thread pool's workers to the given cores
+/// `pool_name` is a named pool, "default", or "runtime" for the async workers
+/// of the runtime shared by the HTTP server, which must be set before it starts
+#[pyfunction]
+fn set_cpu_affinity(pool_name: &str, core_ids: Vec<usize>) -> PyResult<()> {
+    if pool_name == runtime_manager::RUNTIME_POOL {
+        return runtime_manager::configure_runtime_affinity(core_ids).map_err(Into::into);
+    }
+    ThreadPoolManager::global()
+        .set_cpu_affinity(pool_name, core_ids)
+        .map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
+/// Run a Python function on a named thread pool and return its result
+/// Queued "high" priority calls run before queued "normal" and "low" ones
+#[pyfunction]
+#[pyo3(signature = (pool_name, func, priority="normal"))]
+fn run_in_pool(
+    py: Python<'_>,
+    pool_name: &str,
+    func: Py<PyAny>,
+    priority: &str,
+) -> PyResult<Py<PyAny>> {
+    let _call = crate::ffi_call!("run_in_pool");
+    let priority = Priority::parse(priority)?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    ThreadPoolManager::global()
+        .spawn_prioritized(pool_name, priority, move || {
+            let result = Python::attach(|py| func.call0(py));
+            let _ = tx.send(result);
+        })
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    py.detach(move || rx.recv()).map_err(|_| {
+        pyo3::exceptions::PyRuntimeError::new_err("Thread pool execution failed")
+    })?
+}
+
+/// Count the calls queued on a named thread pool by priority
+#[pyfunction]
+fn pool_queue_depth(pool_name: &str) -> HashMap<&'static str, usize> {
+    let queued = ThreadPoolManager::global().queued_by_priority(pool_name);
+    Priority::ALL
+        .iter()
+        .map(|priority| (priority.name(), queued[priority.lane()]))
+        .collect()
+}
+
+/// Whether the engine has started native threads: the shared runtime, the
+/// managed thread pools, or Rayon's global pool through a compute call.
+/// A process forked afterwards has none of them, yet would wait on them
+#[pyfunction]
+fn native_threads_started() -> bool {
+    runtime_manager::snapshot().started
+        || ThreadPoolManager::try_global().is_some()
+        || gil_utils::compute_called()
+}
+
+/// Map each named thread pool to its thread count
+#[pyfunction]
+fn list_pools() -> HashMap<String, usize> {
+    ThreadPoolManager::global().pool_sizes().into_iter().collect()
 }
 
 /// Run a Python function in the optimized compute thread pool
+/// Queued "high" priority calls run before queued "normal" and "low" ones
 #[pyfunction]
-fn run_in_compute_threadpool(py: Python<'_>, func: &Bound<PyAny>) -> PyResult<PyObject> {
+#[pyo3(signature = (func, priority="normal"))]
+fn run_in_compute_threadpool(
+    py: Python<'_>,
+    func: &Bound<PyAny>,
+    priority: &str,
+) -> PyResult<PyObject> {
+    let _call = crate::ffi_call!("run_in_compute_threadpool");
+    let priority = Priority::parse(priority)?;
     // Create a oneshot channel for the result
     let (tx, rx) = std::sync::mpsc::channel();
+    let func = func.clone().unbind();
 
-    // Execute the function in the compute pool
-    py.allow_threads(move || {
-        run_in_compute_pool(|| {
-            let result = Python::with_gil(|py| func.call0());
-            tx.send(result).unwrap();
-        });
-    });
+    // Queue the function on the compute pool
+    spawn_in_compute_pool(priority, move || {
+        let result = Python::attach(|py| func.call0(py));
+        let _ = tx.send(result);
+    })
+    .map_err(pyo3::exceptions::PyRuntimeError::new_err)?;
 
     // Receive the result
-    match rx.recv() {
-        Ok(result) => {
-            // Convert the result to a PyObject
-            Python::with_gil(|py| result.map(|obj| obj.into_py(py)))
-        }
+    match py.detach(move || rx.recv()) {
+        Ok(result) => result,
         Err(_) => Err(pyo3::exceptions::PyRuntimeError::new_err(
             "Thread pool execution failed",
         )),
@@ -243,31 +569,85 @@ fn run_in_compute_threadpool(py: Python<'_>, func: &Bound<PyAny>) -> PyResult<Py
 /// Run a Python function in the optimized IO thread pool
 #[pyfunction]
 fn run_in_io_threadpool(py: Python<'_>, func: &Bound<PyAny>) -> PyResult<PyObject> {
+    let _call = crate::ffi_call!("run_in_io_threadpool");
     // Create a oneshot channel for the result
     let (tx, rx) = std::sync::mpsc::channel();
+    let func = func.clone().unbind();
 
     // Execute the function in the IO pool
-    py.allow_threads(move || {
+    py.detach(move || {
         run_in_io_pool(|| {
-            let result = Python::with_gil(|py| func.call0());
+            let result = Python::attach(|py| func.call0(py));
             tx.send(result).unwrap();
         });
     });
 
     // Receive the result
     match rx.recv() {
-        Ok(result) => {
-            // Convert the result to a PyObject
-            Python::with_gil(|py| result.map(|obj| obj.into_py(py)))
-        }
+        Ok(result) => result,
         Err(_) => Err(pyo3::exceptions::PyRuntimeError::new_err(
             "Thread pool execution failed",
         )),
     }
 }
 
+/// One operation of a batch: its name, input matrix and optional parameters
+type BatchOp<'py> = (String, Vec<Vec<f64>>, Option<Bound<'py, PyDict>>);
+
+/// Run a batch of `(operation, data, params)` tuples on the compute thread
+/// pool and return their results in order
+///
+/// Operations are resolved by `engine`, a default `ComputeEngine` if
+/// omitted, and all parameters are read before any operation runs; the GIL
+/// is then released once for the whole batch. If operations fail, the
+/// error of the first one in the batch is raised.
+#[pyfunction]
+#[pyo3(signature = (ops, engine=None))]
+fn submit_batch(
+    py: Python<'_>,
+    ops: Vec<BatchOp<'_>>,
+    engine: Option<PyRef<'_, ComputeEngine>>,
+) -> PyResult<Vec<Vec<Vec<f64>>>> {
+    let _call = crate::ffi_call!("submit_batch");
+    let default_engine;
+    let engine = match &engine {
+        Some(engine) => &**engine,
+        None => {
+            default_engine = ComputeEngine::new();
+            &default_engine
+        }
+    };
+    let jobs = ops
+        .into_iter()
+        .map(|(operation, data, params)| {
+            let params = params.unwrap_or_else(|| PyDict::new(py));
+            Ok((engine.prepare(py, &operation, &params)?, data))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    gil_utils::observe_compute(py, || {
+        let results: Vec<Result<Vec<Vec<f64>>, ForziumError>> =
+            gil_utils::allow_threads(py, move || {
+                run_in_compute_pool(|| {
+                    jobs.into_par_iter()
+                        .map(|(kernel, data)| kernel(data))
+                        .collect()
+                })
+            });
+        results
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    })
+}
+
 #[pymodule]
 fn forzium_engine(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
+    panic_hook::install();
+    logging::install();
+    // Classes are `Sync` and statics hold their own locks, so nothing here
+    // relies on the GIL for exclusion.
+    #[cfg(feature = "free-threaded")]
+    m.gil_used(false)?;
     m.add_function(wrap_pyfunction!(multiply, m)?)?;
     m.add_function(wrap_pyfunction!(add, m)?)?;
     m.add_function(wrap_pyfunction!(matmul, m)?)?;
@@ -277,6 +657,7 @@ fn forzium_engine(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(simd_elementwise_add, m)?)?;
     m.add_function(wrap_pyfunction!(elementwise_mul, m)?)?;
     m.add_function(wrap_pyfunction!(conv2d, m)?)?;
+    m.add_function(wrap_pyfunction!(conv2d_transpose, m)?)?;
     m.add_function(wrap_pyfunction!(max_pool2d, m)?)?;
     m.add_function(wrap_pyfunction!(scale, m)?)?;
     m.add_function(wrap_pyfunction!(normalize, m)?)?;
@@ -284,33 +665,118 @@ fn forzium_engine(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(noop, m)?)?;
     m.add_function(wrap_pyfunction!(echo_u64, m)?)?;
     m.add_function(wrap_pyfunction!(force_gc, m)?)?;
+    m.add_function(wrap_pyfunction!(gc_stats, m)?)?;
     m.add_function(wrap_pyfunction!(rayon_pool_metrics, m)?)?;
     m.add_function(wrap_pyfunction!(optimal_matmul, m)?)?;
     m.add_function(wrap_pyfunction!(optimal_add, m)?)?;
+    m.add_function(wrap_pyfunction!(optimal_mul, m)?)?;
+    m.add_function(wrap_pyfunction!(optimal_multiply, m)?)?;
+    m.add_function(wrap_pyfunction!(optimal_scale, m)?)?;
     m.add_function(wrap_pyfunction!(detect_simd_support, m)?)?;
     m.add_function(wrap_pyfunction!(benchmark_simd, m)?)?;
+    m.add_function(wrap_pyfunction!(py_kmeans_assign, m)?)?;
+    m.add_function(wrap_pyfunction!(py_kmeans_update, m)?)?;
+    m.add_function(wrap_pyfunction!(py_knn_search, m)?)?;
+
+    // Resource limit configuration
+    m.add_function(wrap_pyfunction!(set_resource_limits, m)?)?;
+    m.add_function(wrap_pyfunction!(set_operation_limit, m)?)?;
+    m.add_function(wrap_pyfunction!(set_operation_timeout, m)?)?;
+    m.add_function(wrap_pyfunction!(get_resource_limits, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_resource_limits, m)?)?;
+    m.add_function(wrap_pyfunction!(set_queue_config, m)?)?;
+    m.add_function(wrap_pyfunction!(get_queue_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(set_memory_limit, m)?)?;
+    m.add_function(wrap_pyfunction!(memory_accounting, m)?)?;
+    m.add_function(wrap_pyfunction!(memory_stats, m)?)?;
 
     // Thread pool optimization functions
     m.add_function(wrap_pyfunction!(optimize_thread_pools, m)?)?;
     m.add_function(wrap_pyfunction!(configure_rayon_thread_pool, m)?)?;
     m.add_function(wrap_pyfunction!(run_in_compute_threadpool, m)?)?;
+    m.add_function(wrap_pyfunction!(submit_batch, m)?)?;
     m.add_function(wrap_pyfunction!(run_in_io_threadpool, m)?)?;
+    m.add_function(wrap_pyfunction!(shutdown_pool, m)?)?;
+    m.add_function(wrap_pyfunction!(recreate_pool, m)?)?;
+    m.add_function(wrap_pyfunction!(list_pools, m)?)?;
+    m.add_function(wrap_pyfunction!(native_threads_started, m)?)?;
+    m.add_function(wrap_pyfunction!(set_cpu_affinity, m)?)?;
+    m.add_function(wrap_pyfunction!(run_in_pool, m)?)?;
+    m.add_function(wrap_pyfunction!(pool_queue_depth, m)?)?;
     m.add_class::<PyLinearModel>()?;
+    m.add_class::<PyLogisticModel>()?;
+    m.add_class::<PySoftmaxModel>()?;
+    m.add_class::<PyTreeEnsemble>()?;
+    m.add_class::<PyPipeline>()?;
+    m.add_class::<PyModelRegistry>()?;
+    #[cfg(feature = "onnx")]
+    m.add_class::<compute::onnx_model::PyOnnxModel>()?;
     m.add_class::<ComputeEngine>()?;
     m.add_function(wrap_pyfunction!(trigger_panic, m)?)?;
     m.add_class::<ForziumHttpServer>()?;
+    m.add_class::<config::ForziumConfig>()?;
     m.add_class::<ComputeRequestSchema>()?;
+    m.add_class::<dlpack::Tensor>()?;
     m.add_class::<crate::memory::pool_allocator::PoolAllocator>()?;
+    m.add_class::<crate::memory::pool_allocator::PoolBlock>()?;
     m.add_class::<AsyncCompute>()?;
     m.add_class::<ComputeHandle>()?;
+    m.add_class::<ResultChunks>()?;
     m.add_function(wrap_pyfunction!(create_async_compute, m)?)?;
+    m.add_function(wrap_pyfunction!(configure_shared_runtime, m)?)?;
+    m.add_function(wrap_pyfunction!(shared_runtime_metrics, m)?)?;
     m.add_class::<ErrorCategory>()?;
+    error_bridge::add_exception_types(py, m)?;
     m.add_function(wrap_pyfunction!(set_verbose_errors, m)?)?;
     m.add_function(wrap_pyfunction!(set_capture_stack_traces, m)?)?;
     m.add_function(wrap_pyfunction!(get_last_error, m)?)?;
+    m.add_function(wrap_pyfunction!(error_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(get_recent_panics, m)?)?;
+    m.add_function(wrap_pyfunction!(route_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(slow_request_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(gil_utils::gil_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(ffi_stats::ffi_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(export_metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(metrics_sources, m)?)?;
+    m.add_function(wrap_pyfunction!(merge_metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(register_health_check, m)?)?;
+    m.add_function(wrap_pyfunction!(register_dependency, m)?)?;
+    m.add_function(wrap_pyfunction!(unregister_health_check, m)?)?;
+    m.add_function(wrap_pyfunction!(run_health_checks, m)?)?;
+    m.add_function(wrap_pyfunction!(logging::set_log_level, m)?)?;
+    m.add_function(wrap_pyfunction!(logging::get_log_levels, m)?)?;
+    m.add_function(wrap_pyfunction!(config::apply_config, m)?)?;
+    m.add_function(wrap_pyfunction!(config::config_audit_log, m)?)?;
+    m.add_class::<client::ForziumHttpClient>()?;
+    m.add_class::<client::PendingResponse>()?;
+    m.add_class::<client::ClientResponse>()?;
+    m.add_function(wrap_pyfunction!(client::http_client_stats, m)?)?;
+    m.add_class::<cache::ForziumCache>()?;
+    m.add_class::<redis::ForziumRedis>()?;
+    m.add_class::<redis::PendingReply>()?;
+    m.add_class::<redis::Subscription>()?;
+    m.add("RedisReplyError", py.get_type::<redis::RedisReplyError>())?;
+    m.add_function(wrap_pyfunction!(redis::redis_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(scheduler::schedule_job, m)?)?;
+    m.add_function(wrap_pyfunction!(scheduler::unschedule_job, m)?)?;
+    m.add_function(wrap_pyfunction!(scheduler::scheduled_jobs, m)?)?;
+    m.add_function(wrap_pyfunction!(scheduler::shutdown_scheduler, m)?)?;
+    m.add_function(wrap_pyfunction!(broadcast::publish_message, m)?)?;
+    m.add_function(wrap_pyfunction!(broadcast::subscribe_topics, m)?)?;
+    m.add_class::<broadcast::BroadcastSubscription>()?;
+    m.add_function(wrap_pyfunction!(broadcast::broadcast_stats, m)?)?;
+    m.add_class::<server::request_context::RequestContext>()?;
+    m.add("request_context", server::request_context::context_var(py)?)?;
+    #[cfg(feature = "otlp")]
+    {
+        m.add_function(wrap_pyfunction!(otlp::configure_otlp, m)?)?;
+        m.add_function(wrap_pyfunction!(otlp::shutdown_otlp, m)?)?;
+        m.add_function(wrap_pyfunction!(otlp::otlp_stats, m)?)?;
+    }
 
     // Register submodules
     bindings::api_bindings::register(m)?;
+    bindings::msgpack::register(m)?;
     error_bridge::register(py, m)?;
     numpy_ops::register(py, m)?;
     Ok(())
@@ -0,0 +1,226 @@
+//! Engine diagnostics as `tracing` events
+//!
+//! Events are emitted under the engine's module paths, e.g.
+//! `forzium_engine::server::http_engine`. [`install`] prints them to stderr
+//! through a filter that can be changed while running: a default level for
+//! the whole engine plus overrides for individual modules. The initial filter
+//! comes from `FORZIUM_LOG`, a comma-separated list of `level` and
+//! `module=level` entries such as `info,server::http_engine=debug`.
+//!
+//! Modules are named relative to the engine (`compute::thread_pool`) or by
+//! their full path; events of other crates are not printed.
+
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::str::FromStr;
+use std::sync::Once;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{Registry, fmt, reload};
+
+use crate::error::ForziumError;
+
+/// Target of every event the engine emits.
+pub const ROOT_TARGET: &str = "forzium_engine";
+
+/// Level printed when neither `FORZIUM_LOG` nor `set_log_level` says
+/// otherwise.
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::WARN;
+
+/// Target of the HTTP server's access log events.
+const ACCESS_LOG_TARGET: &str = "forzium_engine::server::access_log";
+
+/// Default level of the engine and per-module overrides, most recently set
+/// last. The access log is printed at info by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLevels {
+    pub default: LevelFilter,
+    pub modules: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_LEVEL,
+            modules: vec![(ACCESS_LOG_TARGET.to_string(), LevelFilter::INFO)],
+        }
+    }
+}
+
+impl LogLevels {
+    /// Levels from a `FORZIUM_LOG` style list.
+    pub fn parse(spec: &str) -> Result<Self, ForziumError> {
+        let mut levels = Self::default();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((module, level)) => levels.set(Some(module.trim()), Some(parse_level(level)?)),
+                None => levels.set(None, Some(parse_level(entry)?)),
+            }
+        }
+        Ok(levels)
+    }
+
+    /// Set the level of `module`, or the default if `None`. A `None` level
+    /// removes the module's override, or restores [`DEFAULT_LEVEL`].
+    pub fn set(&mut self, module: Option<&str>, level: Option<LevelFilter>) {
+        let Some(module) = module else {
+            self.default = level.unwrap_or(DEFAULT_LEVEL);
+            return;
+        };
+        let target = qualify(module);
+        self.modules.retain(|(existing, _)| *existing != target);
+        if let Some(level) = level {
+            self.modules.push((target, level));
+        }
+    }
+
+    fn targets(&self) -> Targets {
+        Targets::new()
+            .with_target(ROOT_TARGET, self.default)
+            .with_targets(self.modules.iter().cloned())
+    }
+}
+
+/// Full target of an engine module given relative to the crate or in full.
+fn qualify(module: &str) -> String {
+    let module = module.trim().trim_matches(':');
+    if module == ROOT_TARGET || module.starts_with(&format!("{ROOT_TARGET}::")) {
+        module.to_string()
+    } else {
+        format!("{ROOT_TARGET}::{module}")
+    }
+}
+
+pub(crate) fn parse_level(level: &str) -> Result<LevelFilter, ForziumError> {
+    LevelFilter::from_str(level.trim()).map_err(|_| {
+        ForziumError::Validation(format!(
+            "unknown log level '{}', expected trace, debug, info, warn, error or off",
+            level.trim()
+        ))
+    })
+}
+
+static LEVELS: Lazy<Mutex<LogLevels>> = Lazy::new(|| {
+    let spec = std::env::var("FORZIUM_LOG").unwrap_or_default();
+    Mutex::new(LogLevels::parse(&spec).unwrap_or_else(|err| {
+        eprintln!("ignoring FORZIUM_LOG: {err}");
+        LogLevels::default()
+    }))
+});
+
+static FILTER: OnceCell<reload::Handle<Targets, Registry>> = OnceCell::new();
+
+static INSTALL: Once = Once::new();
+
+/// Print engine events to stderr, unless the process already has a global
+/// `tracing` subscriber.
+///
+/// Calling this more than once has no further effect.
+pub fn install() {
+    INSTALL.call_once(|| {
+        let (filter, handle) = reload::Layer::new(LEVELS.lock().targets());
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer().with_writer(std::io::stderr));
+        if tracing::subscriber::set_global_default(subscriber).is_ok() {
+            let _ = FILTER.set(handle);
+        }
+    });
+}
+
+/// Change the level of `module`, or the default if `None`; see
+/// [`LogLevels::set`]. Takes effect immediately.
+pub fn set_level(module: Option<&str>, level: Option<&str>) -> Result<(), ForziumError> {
+    let level = level.map(parse_level).transpose()?;
+    let mut levels = LEVELS.lock();
+    levels.set(module, level);
+    if let Some(filter) = FILTER.get() {
+        filter
+            .reload(levels.targets())
+            .map_err(|err| ForziumError::Compute(format!("could not update log filter: {err}")))?;
+    }
+    Ok(())
+}
+
+/// Current levels.
+pub fn levels() -> LogLevels {
+    LEVELS.lock().clone()
+}
+
+/// Set the level printed for `module` (e.g. "server::http_engine" or
+/// "compute::thread_pool"), or for the whole engine if no module is given.
+/// `level` is trace, debug, info, warn, error or off; None removes the
+/// module's override or restores the default of warn.
+#[pyfunction]
+#[pyo3(signature = (level, module=None))]
+pub fn set_log_level(level: Option<&str>, module: Option<&str>) -> PyResult<()> {
+    set_level(module, level).map_err(Into::into)
+}
+
+/// Return the log levels in effect: "default" for the engine and the full
+/// path of every module with its own level.
+#[pyfunction]
+pub fn get_log_levels(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let levels = levels();
+    let dict = PyDict::new(py);
+    dict.set_item("default", levels.default.to_string().to_lowercase())?;
+    for (module, level) in levels.modules {
+        dict.set_item(module, level.to_string().to_lowercase())?;
+    }
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::Level;
+
+    #[test]
+    fn levels_parse_default_and_module_entries() {
+        let levels =
+            LogLevels::parse("info, server::http_engine=debug,forzium_engine::compute=off")
+                .unwrap();
+        assert_eq!(levels.default, LevelFilter::INFO);
+        assert_eq!(
+            levels.modules,
+            [
+                (ACCESS_LOG_TARGET.to_string(), LevelFilter::INFO),
+                (
+                    "forzium_engine::server::http_engine".to_string(),
+                    LevelFilter::DEBUG
+                ),
+                ("forzium_engine::compute".to_string(), LevelFilter::OFF),
+            ]
+        );
+        assert!(matches!(
+            LogLevels::parse("loud"),
+            Err(ForziumError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn module_overrides_are_replaced_and_removed() {
+        let mut levels = LogLevels::default();
+        levels.set(Some("server"), Some(LevelFilter::DEBUG));
+        levels.set(Some("forzium_engine::server"), Some(LevelFilter::ERROR));
+        assert_eq!(
+            levels.modules,
+            [
+                (ACCESS_LOG_TARGET.to_string(), LevelFilter::INFO),
+                ("forzium_engine::server".to_string(), LevelFilter::ERROR)
+            ]
+        );
+        let targets = levels.targets();
+        let http_engine = "forzium_engine::server::http_engine";
+        assert!(targets.would_enable(http_engine, &Level::ERROR));
+        assert!(!targets.would_enable(http_engine, &Level::WARN));
+        assert!(targets.would_enable("forzium_engine::compute", &Level::WARN));
+        assert!(!targets.would_enable("hyper::proto", &Level::ERROR));
+
+        levels.set(Some("server"), None);
+        levels.set(None, None);
+        assert_eq!(levels, LogLevels::default());
+    }
+}
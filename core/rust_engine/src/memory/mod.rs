@@ -1,6 +1,14 @@
+#[path = "../../memory/accountant.rs"]
+pub mod accountant;
+#[path = "../../memory/aligned.rs"]
+pub mod aligned;
+#[path = "../../memory/allocator.rs"]
+pub mod allocator;
 #[path = "../../memory/arena_manager.rs"]
 pub mod arena_manager;
 #[path = "../../memory/gc_interface.rs"]
 pub mod gc_interface;
 #[path = "../../memory/pool_allocator.rs"]
 pub mod pool_allocator;
+#[path = "../../memory/request_arena.rs"]
+pub mod request_arena;
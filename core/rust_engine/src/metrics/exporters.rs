@@ -0,0 +1,216 @@
+//! Output formats for collected samples
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use super::{MetricKind, Sample};
+use crate::error::ForziumError;
+
+/// Renders samples in one output format.
+pub trait Exporter: Send + Sync {
+    fn export(&self, samples: &[Sample]) -> String;
+}
+
+/// Prometheus text exposition format, with a `# TYPE` line before the first
+/// sample of each metric family.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrometheusExporter;
+
+impl Exporter for PrometheusExporter {
+    fn export(&self, samples: &[Sample]) -> String {
+        let mut out = String::new();
+        let mut typed = HashSet::new();
+        for sample in samples {
+            let family = sample.family();
+            if typed.insert(family.to_string()) {
+                let _ = writeln!(out, "# TYPE {family} {}", sample.kind.name());
+            }
+            out.push_str(&sample.name);
+            if !sample.labels.is_empty() {
+                out.push('{');
+                for (i, (name, value)) in sample.labels.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    let _ = write!(out, "{name}=\"{}\"", escape_label(value));
+                }
+                out.push('}');
+            }
+            let _ = writeln!(out, " {}", sample.value);
+        }
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// StatsD lines with labels as DogStatsD tags, e.g.
+/// `forzium.errors_total:3|g|#category:validation`.
+///
+/// Every sample is sent as a gauge: counters hold process totals rather than
+/// the deltas StatsD counters expect.
+#[derive(Debug, Clone, Default)]
+pub struct StatsdExporter {
+    /// Prepended to every metric name.
+    pub prefix: String,
+}
+
+impl Exporter for StatsdExporter {
+    fn export(&self, samples: &[Sample]) -> String {
+        let mut out = String::new();
+        for sample in samples {
+            let name = sample.name.replacen("forzium_", "forzium.", 1);
+            let _ = write!(out, "{}{name}:{}|g", self.prefix, sample.value);
+            for (i, (label, value)) in sample.labels.iter().enumerate() {
+                out.push_str(if i == 0 { "|#" } else { "," });
+                let _ = write!(out, "{label}:{}", value.replace([',', '|', '\n'], "_"));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// JSON array of `{"name", "type", "labels", "value"}` objects.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonExporter;
+
+#[derive(Deserialize)]
+struct JsonSample {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    labels: BTreeMap<String, String>,
+    value: f64,
+}
+
+impl JsonExporter {
+    /// Samples of an export, e.g. one made by another process.
+    pub fn parse(text: &str) -> Result<Vec<Sample>, ForziumError> {
+        let invalid =
+            |reason: String| ForziumError::Validation(format!("invalid metrics export: {reason}"));
+        let samples: Vec<JsonSample> =
+            serde_json::from_str(text).map_err(|err| invalid(err.to_string()))?;
+        samples
+            .into_iter()
+            .map(|sample| {
+                let kind = MetricKind::from_name(&sample.kind)
+                    .ok_or_else(|| invalid(format!("unknown type '{}'", sample.kind)))?;
+                Ok(Sample {
+                    name: sample.name.into(),
+                    kind,
+                    labels: sample
+                        .labels
+                        .into_iter()
+                        .map(|(name, value)| (name.into(), value))
+                        .collect(),
+                    value: sample.value,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Exporter for JsonExporter {
+    fn export(&self, samples: &[Sample]) -> String {
+        let samples: Vec<Value> = samples
+            .iter()
+            .map(|sample| {
+                let labels: Map<String, Value> = sample
+                    .labels
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), Value::from(value.as_str())))
+                    .collect();
+                json!({
+                    "name": sample.name,
+                    "type": sample.kind.name(),
+                    "labels": labels,
+                    "value": sample.value,
+                })
+            })
+            .collect();
+        Value::Array(samples).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<Sample> {
+        vec![
+            Sample::counter("forzium_errors_total", 3u32).label("category", "validation"),
+            Sample::counter("forzium_errors_total", 0u32).label("category", "compute"),
+            Sample::summary("forzium_latency_seconds", 0.5).label("quantile", "0.5"),
+            Sample::summary("forzium_latency_seconds_sum", 2.0),
+            Sample::gauge("forzium_route", 1u32).label("route", "/a\"b"),
+        ]
+    }
+
+    #[test]
+    fn prometheus_types_each_family_once() {
+        let text = PrometheusExporter.export(&samples());
+        assert_eq!(
+            text,
+            "# TYPE forzium_errors_total counter\n\
+             forzium_errors_total{category=\"validation\"} 3\n\
+             forzium_errors_total{category=\"compute\"} 0\n\
+             # TYPE forzium_latency_seconds summary\n\
+             forzium_latency_seconds{quantile=\"0.5\"} 0.5\n\
+             forzium_latency_seconds_sum 2\n\
+             # TYPE forzium_route gauge\n\
+             forzium_route{route=\"/a\\\"b\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn statsd_sends_gauges_with_tags() {
+        let exporter = StatsdExporter {
+            prefix: "app.".into(),
+        };
+        let text = exporter.export(&samples()[..1]);
+        assert_eq!(text, "app.forzium.errors_total:3|g|#category:validation\n");
+    }
+
+    #[test]
+    fn json_lists_samples() {
+        let parsed: Value = serde_json::from_str(&JsonExporter.export(&samples())).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 5);
+        assert_eq!(parsed[0]["type"], "counter");
+        assert_eq!(parsed[0]["labels"]["category"], "validation");
+        assert_eq!(parsed[3]["value"], 2.0);
+    }
+
+    #[test]
+    fn json_exports_parse_back() {
+        let samples = samples();
+        assert_eq!(
+            JsonExporter::parse(&JsonExporter.export(&samples)).unwrap(),
+            samples
+        );
+        assert!(JsonExporter::parse("[{\"name\": \"x\"}]").is_err());
+    }
+
+    #[test]
+    fn merged_processes_add_up_and_keep_quantiles_apart() {
+        let merged = crate::metrics::merge([("0".into(), samples()), ("1".into(), samples())]);
+        let text = PrometheusExporter.export(&merged);
+        assert!(text.contains("forzium_errors_total{category=\"validation\"} 6\n"));
+        assert!(text.contains("forzium_latency_seconds{quantile=\"0.5\",worker=\"0\"} 0.5\n"));
+        assert!(text.contains("forzium_latency_seconds{quantile=\"0.5\",worker=\"1\"} 0.5\n"));
+        assert!(text.contains("forzium_latency_seconds_sum 4\n"));
+        assert_eq!(text.matches("# TYPE").count(), 3);
+        let latency = merged
+            .iter()
+            .position(|sample| sample.name == "forzium_latency_seconds_sum")
+            .unwrap();
+        assert_eq!(merged[latency - 1].family(), "forzium_latency_seconds");
+    }
+}
@@ -0,0 +1,300 @@
+//! Engine metrics collected from every subsystem and rendered by pluggable
+//! exporters
+//!
+//! Subsystems publish through [`MetricsSource`] implementations registered
+//! under a name; [`collect`] gathers their samples and an [`Exporter`] turns
+//! them into Prometheus text, StatsD lines or JSON. The server, compute, GIL,
+//! thread pool, runtime and memory sources are registered from the start.
+//!
+//! The JSON exports of several processes, such as the workers of
+//! `forzium.run`, are combined by [`merge`].
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::ForziumError;
+
+pub mod exporters;
+pub mod sources;
+
+pub use exporters::{Exporter, JsonExporter, PrometheusExporter, StatsdExporter};
+
+/// How a sample's value behaves over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// Monotonic total since the process started.
+    Counter,
+    /// Value that can go up and down.
+    Gauge,
+    /// Quantile, `_sum` or `_count` sample of a latency summary.
+    Summary,
+}
+
+impl MetricKind {
+    /// Name used in Prometheus `# TYPE` lines and JSON output.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Summary => "summary",
+        }
+    }
+
+    /// Kind of a name returned by [`MetricKind::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "counter" => Some(Self::Counter),
+            "gauge" => Some(Self::Gauge),
+            "summary" => Some(Self::Summary),
+            _ => None,
+        }
+    }
+}
+
+/// One labelled metric value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// Metric name, e.g. `forzium_gc_invocations_total`.
+    pub name: Cow<'static, str>,
+    pub kind: MetricKind,
+    /// Label names and values in output order.
+    pub labels: Vec<(Cow<'static, str>, String)>,
+    pub value: f64,
+}
+
+impl Sample {
+    fn new(name: impl Into<Cow<'static, str>>, kind: MetricKind, value: f64) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            labels: Vec::new(),
+            value,
+        }
+    }
+
+    /// Counter sample.
+    pub fn counter(name: &'static str, value: impl Into<f64>) -> Self {
+        Self::new(name, MetricKind::Counter, value.into())
+    }
+
+    /// Gauge sample.
+    pub fn gauge(name: &'static str, value: impl Into<f64>) -> Self {
+        Self::new(name, MetricKind::Gauge, value.into())
+    }
+
+    /// Summary sample; `name` includes any `_sum` or `_count` suffix.
+    pub fn summary(name: impl Into<Cow<'static, str>>, value: f64) -> Self {
+        Self::new(name, MetricKind::Summary, value)
+    }
+
+    /// Add a label.
+    pub fn label(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.labels.push((Cow::Borrowed(name), value.to_string()));
+        self
+    }
+
+    /// Name of the metric family: that of a summary for its `_sum` and
+    /// `_count` samples.
+    pub fn family(&self) -> &str {
+        match self.kind {
+            MetricKind::Summary => self
+                .name
+                .strip_suffix("_sum")
+                .or_else(|| self.name.strip_suffix("_count"))
+                .unwrap_or(&self.name),
+            _ => &self.name,
+        }
+    }
+}
+
+/// A subsystem that publishes metrics.
+pub trait MetricsSource: Send + Sync {
+    /// Append the subsystem's current samples to `samples`.
+    fn collect(&self, samples: &mut Vec<Sample>);
+}
+
+type Sources = RwLock<Vec<(String, Box<dyn MetricsSource>)>>;
+
+static SOURCES: Lazy<Sources> = Lazy::new(|| {
+    let builtin: [(&str, Box<dyn MetricsSource>); 11] = [
+        ("server", Box::new(sources::ServerMetrics)),
+        ("compute", Box::new(sources::ComputeMetrics)),
+        ("gil", Box::new(sources::GilMetrics)),
+        ("thread_pools", Box::new(sources::ThreadPoolMetrics)),
+        ("runtime", Box::new(sources::RuntimeMetrics)),
+        ("memory", Box::new(sources::MemoryMetrics)),
+        ("http_client", Box::new(sources::HttpClientMetrics)),
+        ("cache", Box::new(sources::CacheMetrics)),
+        ("redis", Box::new(sources::RedisMetrics)),
+        ("scheduler", Box::new(sources::SchedulerMetrics)),
+        ("broadcast", Box::new(sources::BroadcastMetrics)),
+    ];
+    RwLock::new(
+        builtin
+            .into_iter()
+            .map(|(name, source)| (name.to_string(), source))
+            .collect(),
+    )
+});
+
+/// Publish `source`'s metrics under `name`, replacing any source already
+/// registered with that name.
+pub fn register_source(name: &str, source: Box<dyn MetricsSource>) {
+    let mut sources = SOURCES.write();
+    match sources.iter_mut().find(|(existing, _)| existing == name) {
+        Some(entry) => entry.1 = source,
+        None => sources.push((name.to_string(), source)),
+    }
+}
+
+/// Stop publishing the source registered as `name`. Returns whether there
+/// was one.
+pub fn unregister_source(name: &str) -> bool {
+    let mut sources = SOURCES.write();
+    let before = sources.len();
+    sources.retain(|(existing, _)| existing != name);
+    sources.len() != before
+}
+
+/// Names of the registered sources in registration order.
+pub fn source_names() -> Vec<String> {
+    SOURCES
+        .read()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Samples of every registered source, in registration order.
+pub fn collect() -> Vec<Sample> {
+    let mut samples = Vec::new();
+    for (_, source) in SOURCES.read().iter() {
+        source.collect(&mut samples);
+    }
+    samples
+}
+
+/// Name and labels that tell samples apart.
+type SampleKey = (Cow<'static, str>, Vec<(Cow<'static, str>, String)>);
+
+/// Samples of several processes, by process name, combined into one set.
+///
+/// Counters, gauges and the `_sum` and `_count` samples of summaries with
+/// equal labels are added up. Summary quantiles cannot be, so they are kept
+/// apart with a `worker` label naming their process. Families stay together,
+/// in the order they first appear.
+pub fn merge(processes: impl IntoIterator<Item = (String, Vec<Sample>)>) -> Vec<Sample> {
+    let mut merged: Vec<Sample> = Vec::new();
+    let mut positions: HashMap<SampleKey, usize> = HashMap::new();
+    for (process, samples) in processes {
+        for mut sample in samples {
+            if sample.labels.iter().any(|(name, _)| name == "quantile") {
+                sample
+                    .labels
+                    .push((Cow::Borrowed("worker"), process.clone()));
+            }
+            let key = (sample.name.clone(), sample.labels.clone());
+            match positions.get(&key) {
+                Some(&position) => merged[position].value += sample.value,
+                None => {
+                    positions.insert(key, merged.len());
+                    merged.push(sample);
+                }
+            }
+        }
+    }
+    let mut families: HashMap<String, usize> = HashMap::new();
+    for sample in &merged {
+        let next = families.len();
+        families.entry(sample.family().to_string()).or_insert(next);
+    }
+    merged.sort_by_key(|sample| families[sample.family()]);
+    merged
+}
+
+/// Exporter for a format name: `prometheus`, `statsd` or `json`.
+pub fn exporter(format: &str) -> Result<Box<dyn Exporter>, ForziumError> {
+    match format {
+        "prometheus" => Ok(Box::new(PrometheusExporter)),
+        "statsd" => Ok(Box::new(StatsdExporter::default())),
+        "json" => Ok(Box::new(JsonExporter)),
+        other => Err(ForziumError::Validation(format!(
+            "unknown metrics format '{other}', expected prometheus, statsd or json"
+        ))),
+    }
+}
+
+/// Render the metrics of every registered source as `format`: "prometheus"
+/// (text exposition format), "statsd" (one gauge per line with DogStatsD
+/// tags) or "json".
+#[pyfunction]
+#[pyo3(signature = (format="prometheus"))]
+pub fn export_metrics(py: Python<'_>, format: &str) -> PyResult<String> {
+    let exporter = exporter(format)?;
+    Ok(py.detach(|| exporter.export(&collect())))
+}
+
+/// Combine the `export_metrics("json")` output of several processes, keyed
+/// by a name for each such as a worker number, and render it as `format`.
+///
+/// Counters, gauges and summary sums and counts are added up across
+/// processes; summary quantiles are kept per process with a `worker` label.
+#[pyfunction]
+#[pyo3(signature = (exports, format="prometheus"))]
+pub fn merge_metrics(exports: BTreeMap<String, String>, format: &str) -> PyResult<String> {
+    let exporter = exporter(format)?;
+    let processes = exports
+        .into_iter()
+        .map(|(process, export)| Ok((process, JsonExporter::parse(&export)?)))
+        .collect::<Result<Vec<_>, ForziumError>>()?;
+    Ok(exporter.export(&merge(processes)))
+}
+
+/// Names of the registered metrics sources.
+#[pyfunction]
+pub fn metrics_sources() -> Vec<String> {
+    source_names()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(f64);
+
+    impl MetricsSource for Fixed {
+        fn collect(&self, samples: &mut Vec<Sample>) {
+            samples.push(Sample::gauge("forzium_test_fixed", self.0).label("source", "test"));
+        }
+    }
+
+    #[test]
+    fn registered_sources_publish_into_collect() {
+        assert!(source_names().iter().any(|name| name == "server"));
+        register_source("metrics_test", Box::new(Fixed(1.0)));
+        register_source("metrics_test", Box::new(Fixed(2.0)));
+        let fixed: Vec<_> = collect()
+            .into_iter()
+            .filter(|sample| sample.name == "forzium_test_fixed")
+            .collect();
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(fixed[0].value, 2.0);
+
+        assert!(unregister_source("metrics_test"));
+        assert!(!unregister_source("metrics_test"));
+        assert!(
+            !collect()
+                .iter()
+                .any(|sample| sample.name == "forzium_test_fixed")
+        );
+    }
+
+    #[test]
+    fn unknown_formats_are_rejected() {
+        assert!(exporter("prometheus").is_ok());
+        assert!(matches!(exporter("xml"), Err(ForziumError::Validation(_))));
+    }
+}
@@ -0,0 +1,452 @@
+//! Metrics sources of the engine's own subsystems
+//!
+//! Each source reads the same snapshots the subsystem's Python stats
+//! functions report, so the two always agree.
+
+use super::{MetricsSource, Sample};
+use crate::async_compute::Priority;
+use crate::broadcast;
+use crate::cache;
+use crate::client;
+use crate::compute::rayon_metrics;
+use crate::compute::resource_limits::OP_QUEUE;
+use crate::compute::thread_pool::ThreadPoolManager;
+use crate::error_bridge::{self, ErrorCategory};
+use crate::gil_utils;
+use crate::memory::accountant::{self, MemoryCategory};
+use crate::memory::{allocator, gc_interface};
+use crate::redis;
+use crate::runtime_manager;
+use crate::scheduler;
+use crate::server::{route_metrics, slow_requests};
+
+/// Error counts, per-route latencies and slow requests of the HTTP server.
+pub struct ServerMetrics;
+
+impl MetricsSource for ServerMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        let errors = error_bridge::stats();
+        for (category, count) in ErrorCategory::ALL.iter().zip(errors.by_category) {
+            samples.push(
+                Sample::counter("forzium_errors_total", count as f64)
+                    .label("category", category.name()),
+            );
+        }
+        for (class, count) in [("4xx", errors.client_errors), ("5xx", errors.server_errors)] {
+            samples.push(
+                Sample::counter("forzium_http_errors_total", count as f64)
+                    .label("status_class", class),
+            );
+        }
+
+        const NAME: &str = "forzium_http_request_duration_seconds";
+        for route in route_metrics::snapshot() {
+            let labelled = |sample: Sample| {
+                sample
+                    .label("method", &route.method)
+                    .label("route", &route.route)
+                    .label("status", route.status)
+            };
+            for (quantile, ms) in [
+                ("0.5", route.p50_ms),
+                ("0.95", route.p95_ms),
+                ("0.99", route.p99_ms),
+            ] {
+                samples
+                    .push(labelled(Sample::summary(NAME, ms / 1000.0)).label("quantile", quantile));
+            }
+            samples.push(labelled(Sample::summary(
+                format!("{NAME}_sum"),
+                route.sum_ms / 1000.0,
+            )));
+            samples.push(labelled(Sample::summary(
+                format!("{NAME}_count"),
+                route.count as f64,
+            )));
+        }
+        for (method, route, count) in slow_requests::snapshot() {
+            samples.push(
+                Sample::counter("forzium_http_slow_requests_total", count as f64)
+                    .label("method", &method)
+                    .label("route", &route),
+            );
+        }
+    }
+}
+
+/// Time requests and compute calls spent waiting for and holding the GIL.
+pub struct GilMetrics;
+
+impl MetricsSource for GilMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        for (scope, stats) in gil_utils::stats() {
+            let scope = scope.name();
+            samples.push(
+                Sample::counter("forzium_gil_calls_total", stats.calls as f64)
+                    .label("scope", scope),
+            );
+            samples.push(
+                Sample::counter(
+                    "forzium_gil_call_seconds_total",
+                    stats.elapsed.as_secs_f64(),
+                )
+                .label("scope", scope),
+            );
+            samples.push(
+                Sample::counter("forzium_gil_held_seconds_total", stats.held.as_secs_f64())
+                    .label("scope", scope),
+            );
+            samples.push(
+                Sample::counter("forzium_gil_wait_seconds_total", stats.waited.as_secs_f64())
+                    .label("scope", scope),
+            );
+            samples.push(
+                Sample::gauge("forzium_gil_max_held_seconds", stats.max_held.as_secs_f64())
+                    .label("scope", scope),
+            );
+        }
+    }
+}
+
+/// Rayon task activity per pool and the queue of operations waiting for a
+/// concurrency slot.
+pub struct ComputeMetrics;
+
+impl MetricsSource for ComputeMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        for (pool, snapshot) in rayon_metrics::pool_snapshots() {
+            samples.push(
+                Sample::counter(
+                    "forzium_rayon_tasks_completed_total",
+                    snapshot.total_tasks_completed as f64,
+                )
+                .label("pool", &pool),
+            );
+            samples.push(
+                Sample::counter(
+                    "forzium_rayon_busy_seconds_total",
+                    snapshot.busy_time_seconds,
+                )
+                .label("pool", &pool),
+            );
+            samples.push(
+                Sample::gauge(
+                    "forzium_rayon_utilization_ratio",
+                    snapshot.utilization_percent / 100.0,
+                )
+                .label("pool", &pool),
+            );
+        }
+
+        let queue = OP_QUEUE.stats();
+        samples.push(Sample::gauge("forzium_op_queue_depth", queue.depth as f64));
+        samples.push(Sample::counter(
+            "forzium_op_queue_queued_total",
+            queue.total_queued as f64,
+        ));
+        samples.push(Sample::counter(
+            "forzium_op_queue_timeouts_total",
+            queue.timeouts as f64,
+        ));
+        samples.push(Sample::counter(
+            "forzium_op_queue_rejected_total",
+            queue.rejected as f64,
+        ));
+        samples.push(Sample::counter(
+            "forzium_op_queue_wait_seconds_total",
+            queue.total_wait.as_secs_f64(),
+        ));
+    }
+}
+
+/// Sizes and queued prioritized jobs of the specialized thread pools.
+///
+/// Reports nothing until the thread pool manager has been created, rather
+/// than creating it.
+pub struct ThreadPoolMetrics;
+
+impl MetricsSource for ThreadPoolMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        let Some(manager) = ThreadPoolManager::try_global() else {
+            return;
+        };
+        for (pool, threads) in manager.pool_sizes() {
+            samples.push(
+                Sample::gauge("forzium_thread_pool_threads", threads as f64).label("pool", &pool),
+            );
+            for (priority, queued) in Priority::ALL.iter().zip(manager.queued_by_priority(&pool)) {
+                samples.push(
+                    Sample::gauge("forzium_thread_pool_queued_jobs", queued as f64)
+                        .label("pool", &pool)
+                        .label("priority", priority.name()),
+                );
+            }
+        }
+    }
+}
+
+/// Activity of the Tokio runtime shared by the server and `AsyncCompute`.
+pub struct RuntimeMetrics;
+
+impl MetricsSource for RuntimeMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        let runtime = runtime_manager::snapshot();
+        if !runtime.started {
+            return;
+        }
+        samples.push(Sample::gauge(
+            "forzium_runtime_worker_threads",
+            runtime.worker_threads as f64,
+        ));
+        samples.push(Sample::gauge(
+            "forzium_runtime_alive_tasks",
+            runtime.alive_tasks as f64,
+        ));
+        samples.push(Sample::gauge(
+            "forzium_runtime_global_queue_depth",
+            runtime.global_queue_depth as f64,
+        ));
+        samples.push(Sample::gauge(
+            "forzium_runtime_blocking_jobs_active",
+            runtime.blocking_jobs_active as f64,
+        ));
+        samples.push(Sample::counter(
+            "forzium_runtime_blocking_jobs_spawned_total",
+            runtime.blocking_jobs_spawned as f64,
+        ));
+    }
+}
+
+/// Memory accounting, the global allocator and Python garbage collection.
+pub struct MemoryMetrics;
+
+impl MetricsSource for MemoryMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        let accounting = accountant::stats();
+        for (category, bytes) in MemoryCategory::ALL.iter().zip(accounting.by_category) {
+            samples.push(
+                Sample::gauge("forzium_memory_reserved_bytes", bytes as f64)
+                    .label("category", category.name()),
+            );
+        }
+        samples.push(Sample::gauge(
+            "forzium_memory_peak_bytes",
+            accounting.peak as f64,
+        ));
+        if accounting.limit > 0 {
+            samples.push(Sample::gauge(
+                "forzium_memory_limit_bytes",
+                accounting.limit as f64,
+            ));
+        }
+        samples.push(Sample::counter(
+            "forzium_memory_rejected_total",
+            accounting.rejected as f64,
+        ));
+
+        let allocator = allocator::allocator_stats();
+        for (name, bytes) in [
+            ("forzium_allocator_allocated_bytes", allocator.allocated),
+            ("forzium_allocator_active_bytes", allocator.active),
+            ("forzium_allocator_resident_bytes", allocator.resident),
+        ] {
+            if let Some(bytes) = bytes {
+                samples.push(Sample::gauge(name, bytes as f64).label("backend", allocator.backend));
+            }
+        }
+        if let Some(fragmentation) = allocator.fragmentation {
+            samples.push(
+                Sample::gauge("forzium_allocator_fragmentation_ratio", fragmentation)
+                    .label("backend", allocator.backend),
+            );
+        }
+
+        let gc = gc_interface::stats();
+        samples.push(Sample::counter(
+            "forzium_gc_invocations_total",
+            gc.invocations as f64,
+        ));
+        samples.push(Sample::counter(
+            "forzium_gc_collected_objects_total",
+            gc.collected as f64,
+        ));
+        samples.push(Sample::counter(
+            "forzium_gc_duration_seconds_total",
+            gc.total_seconds,
+        ));
+        samples.push(Sample::gauge(
+            "forzium_gc_duration_seconds_max",
+            gc.max_seconds,
+        ));
+        samples.push(Sample::gauge(
+            "forzium_gc_duration_seconds_last",
+            gc.last_seconds,
+        ));
+    }
+}
+
+/// Outcomes and retries of outbound requests of `ForziumHttpClient`.
+pub struct HttpClientMetrics;
+
+impl MetricsSource for HttpClientMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        let stats = client::stats();
+        for (outcome, count) in [
+            ("response", stats.responses),
+            ("timeout", stats.timeouts),
+            ("error", stats.errors),
+        ] {
+            samples.push(
+                Sample::counter("forzium_http_client_requests_total", count as f64)
+                    .label("outcome", outcome),
+            );
+        }
+        samples.push(Sample::counter(
+            "forzium_http_client_retries_total",
+            stats.retries as f64,
+        ));
+    }
+}
+
+/// Reads, evictions and occupancy of the `ForziumCache`s created with a name.
+pub struct CacheMetrics;
+
+impl MetricsSource for CacheMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        for (name, stats) in cache::named_stats() {
+            for (result, count) in [("hit", stats.hits), ("miss", stats.misses)] {
+                samples.push(
+                    Sample::counter("forzium_cache_requests_total", count as f64)
+                        .label("cache", &name)
+                        .label("result", result),
+                );
+            }
+            for (reason, count) in [("size", stats.evictions), ("expired", stats.expirations)] {
+                samples.push(
+                    Sample::counter("forzium_cache_evictions_total", count as f64)
+                        .label("cache", &name)
+                        .label("reason", reason),
+                );
+            }
+            samples.push(
+                Sample::gauge("forzium_cache_entries", stats.entries as f64).label("cache", &name),
+            );
+            samples.push(
+                Sample::gauge("forzium_cache_bytes", stats.bytes as f64).label("cache", &name),
+            );
+        }
+    }
+}
+
+/// Commands sent by the `ForziumRedis` clients, by outcome, and connections
+/// they opened.
+pub struct RedisMetrics;
+
+impl MetricsSource for RedisMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        let stats = redis::stats();
+        for (outcome, count) in [
+            ("reply", stats.replies),
+            ("error", stats.errors),
+            ("timeout", stats.timeouts),
+            ("failure", stats.failures),
+        ] {
+            samples.push(
+                Sample::counter("forzium_redis_commands_total", count as f64)
+                    .label("outcome", outcome),
+            );
+        }
+        samples.push(Sample::counter(
+            "forzium_redis_connections_total",
+            stats.connections as f64,
+        ));
+    }
+}
+
+/// Runs of the scheduled jobs, by outcome, and how long each took last.
+pub struct SchedulerMetrics;
+
+impl MetricsSource for SchedulerMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        for job in scheduler::stats() {
+            for (outcome, count) in [
+                ("success", job.successes),
+                ("failure", job.failures),
+                ("skipped", job.skipped),
+            ] {
+                samples.push(
+                    Sample::counter("forzium_scheduler_runs_total", count as f64)
+                        .label("job", &job.name)
+                        .label("outcome", outcome),
+                );
+            }
+            samples.push(
+                Sample::gauge("forzium_scheduler_running", job.running as f64)
+                    .label("job", &job.name),
+            );
+            if let Some(run) = job.history.last() {
+                samples.push(
+                    Sample::gauge(
+                        "forzium_scheduler_last_run_duration_seconds",
+                        run.duration.as_secs_f64(),
+                    )
+                    .label("job", &job.name),
+                );
+            }
+        }
+    }
+}
+
+/// Messages of the broadcast hub, by outcome, and its subscriptions.
+pub struct BroadcastMetrics;
+
+impl MetricsSource for BroadcastMetrics {
+    fn collect(&self, samples: &mut Vec<Sample>) {
+        let stats = broadcast::stats();
+        samples.push(Sample::counter(
+            "forzium_broadcast_published_total",
+            stats.published as f64,
+        ));
+        for (outcome, count) in [("delivered", stats.delivered), ("dropped", stats.dropped)] {
+            samples.push(
+                Sample::counter("forzium_broadcast_messages_total", count as f64)
+                    .label("outcome", outcome),
+            );
+        }
+        samples.push(Sample::counter(
+            "forzium_broadcast_disconnects_total",
+            stats.disconnected as f64,
+        ));
+        samples.push(Sample::gauge(
+            "forzium_broadcast_subscribers",
+            stats.subscribers as f64,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_sources_report_engine_totals() {
+        let mut samples = Vec::new();
+        ServerMetrics.collect(&mut samples);
+        MemoryMetrics.collect(&mut samples);
+        let errors = samples
+            .iter()
+            .filter(|sample| sample.name == "forzium_errors_total")
+            .count();
+        assert_eq!(errors, ErrorCategory::ALL.len());
+        assert!(
+            samples
+                .iter()
+                .any(|sample| sample.name == "forzium_gc_invocations_total")
+        );
+        assert!(
+            samples
+                .iter()
+                .any(|sample| sample.name == "forzium_memory_peak_bytes")
+        );
+    }
+}
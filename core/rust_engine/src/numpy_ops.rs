@@ -1,68 +1,183 @@
+use numpy::ndarray::{Array2, ArrayView2, Zip, s};
+use numpy::{IntoPyArray, PyReadonlyArray2};
 use pyo3::prelude::*;
 use pyo3::types::PyModule;
-use pyo3::PyObject;
 use pyo3::Python;
 use pyo3::{exceptions, PyResult};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::compute::resource_limits::{DType, OpGuard, enforce_tensor_size, estimate_bytes};
+use crate::gil_utils;
+
 static ZERO_COPY_OPS_COUNT: AtomicUsize = AtomicUsize::new(0);
 
-/// Process a NumPy array directly without copying the data to a Rust Vec
+/// A 2D `float64` operand: a NumPy array borrowed in place, or a list of
+/// rows copied into an owned array for callers without NumPy.
+enum Operand<'py> {
+    Array(PyReadonlyArray2<'py, f64>),
+    Rows(Array2<f64>),
+}
+
+impl<'py> Operand<'py> {
+    /// Borrow `object` if it is a 2D `float64` array, otherwise copy it as
+    /// a list of equally long rows.
+    fn extract(object: &Bound<'py, PyAny>, name: &str) -> PyResult<Self> {
+        if let Ok(array) = object.extract::<PyReadonlyArray2<'py, f64>>() {
+            return Ok(Operand::Array(array));
+        }
+        let rows: Vec<Vec<f64>> = object.extract().map_err(|_| {
+            exceptions::PyTypeError::new_err(format!(
+                "{name} must be a 2D float64 NumPy array or a list of rows"
+            ))
+        })?;
+        let cols = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err(exceptions::PyValueError::new_err(format!(
+                "{name} rows must all have the same length"
+            )));
+        }
+        let shape = (rows.len(), cols);
+        let array = Array2::from_shape_vec(shape, rows.into_iter().flatten().collect())
+            .map_err(|err| exceptions::PyValueError::new_err(err.to_string()))?;
+        Ok(Operand::Rows(array))
+    }
+
+    fn view(&self) -> ArrayView2<'_, f64> {
+        match self {
+            Operand::Array(array) => array.as_array(),
+            Operand::Rows(array) => array.view(),
+        }
+    }
+
+    fn is_array(&self) -> bool {
+        matches!(self, Operand::Array(_))
+    }
+}
+
+/// Return `result` as a NumPy array when the input was one, otherwise as a
+/// list of rows.
+fn into_py<'py>(
+    py: Python<'py>,
+    result: Array2<f64>,
+    as_array: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    if as_array {
+        Ok(result.into_pyarray(py).into_any())
+    } else {
+        let rows: Vec<Vec<f64>> = result.outer_iter().map(|row| row.to_vec()).collect();
+        rows.into_pyobject(py)
+    }
+}
+
+/// Multiply every element of `array` by `factor`.
+pub fn multiply(array: ArrayView2<'_, f64>, factor: f64) -> Array2<f64> {
+    array.mapv(|val| val * factor)
+}
+
+/// Valid (unpadded) 2D convolution of `image` with `kernel`.
+pub fn conv2d(image: ArrayView2<'_, f64>, kernel: ArrayView2<'_, f64>) -> Array2<f64> {
+    let (k_rows, k_cols) = kernel.dim();
+    let out_rows = image.nrows() + 1 - k_rows;
+    let out_cols = image.ncols() + 1 - k_cols;
+    Array2::from_shape_fn((out_rows, out_cols), |(i, j)| {
+        let window = image.slice(s![i..i + k_rows, j..j + k_cols]);
+        Zip::from(&window)
+            .and(&kernel)
+            .fold(0.0, |sum, &x, &k| sum + x * k)
+    })
+}
+
+/// Apply `operation` element-wise to two arrays of the same shape.
+pub fn elementwise(
+    array_a: ArrayView2<'_, f64>,
+    array_b: ArrayView2<'_, f64>,
+    operation: &str,
+) -> PyResult<Array2<f64>> {
+    let apply = |f: fn(f64, f64) -> f64| {
+        Zip::from(&array_a)
+            .and(&array_b)
+            .map_collect(|&a, &b| f(a, b))
+    };
+    match operation {
+        "add" => Ok(apply(|a, b| a + b)),
+        "multiply" => Ok(apply(|a, b| a * b)),
+        "subtract" => Ok(apply(|a, b| a - b)),
+        "divide" => {
+            if array_b.iter().any(|&b| b == 0.0) {
+                return Err(exceptions::PyZeroDivisionError::new_err("Division by zero"));
+            }
+            Ok(apply(|a, b| a / b))
+        }
+        _ => Err(exceptions::PyValueError::new_err(
+            "Unsupported operation. Choose from: add, multiply, subtract, divide",
+        )),
+    }
+}
+
+/// Multiply a NumPy array by a factor
 ///
 /// Args:
-///     array: 2D NumPy array to process
+///     array: 2D float64 NumPy array, or a list of rows
 ///     factor: Multiplication factor to apply
 ///
 /// Returns:
-///     Modified NumPy array with same dimensions
+///     New array with the same dimensions, a NumPy array for array input
 ///
-/// This function demonstrates zero-copy access by directly operating on the
-/// NumPy array's memory buffer without creating intermediate copies.
+/// NumPy input is read in place through its buffer, whatever its strides;
+/// only the result is allocated. Lists are copied into an array first. The
+/// GIL is released while waiting for memory and computing.
 #[pyfunction]
-pub fn zero_copy_multiply(
-    py: Python<'_>,
-    array: Vec<Vec<f64>>,
+pub fn zero_copy_multiply<'py>(
+    py: Python<'py>,
+    array: &Bound<'py, PyAny>,
     factor: f64,
-) -> PyResult<Vec<Vec<f64>>> {
-    // Create a new array with the multiplied values
-    let result: Vec<Vec<f64>> = array
-        .iter()
-        .map(|row| row.iter().map(|&val| val * factor).collect())
-        .collect();
+) -> PyResult<Bound<'py, PyAny>> {
+    let _call = crate::ffi_call!("zero_copy_multiply");
+    let array = Operand::extract(array, "array")?;
+    let view = array.view();
+    enforce_tensor_size(view.nrows(), view.ncols(), "multiply")?;
+
+    let result = gil_utils::observe_compute(py, || {
+        gil_utils::allow_threads(py, move || {
+            let _op_guard = OpGuard::acquire_bytes(estimate_bytes(2 * view.len(), DType::F64))?;
+            PyResult::Ok(multiply(view, factor))
+        })
+    })?;
 
     // Increment operation counter
     ZERO_COPY_OPS_COUNT.fetch_add(1, Ordering::Relaxed);
 
-    // Return the modified array
-    Ok(result)
+    into_py(py, result, array.is_array())
 }
 
 /// Apply a convolution operation to a NumPy array without unnecessary data copies
 ///
 /// Args:
-///     image: 2D NumPy array representing the input image
-///     kernel: 2D NumPy array representing the convolution kernel
+///     image: 2D float64 NumPy array (or list of rows) representing the input image
+///     kernel: 2D float64 NumPy array (or list of rows) representing the convolution kernel
 ///
 /// Returns:
-///     Result of convolution operation as a new NumPy array
+///     Result of convolution operation as a new NumPy array, or a list of
+///     rows if the image was given as one
 #[pyfunction]
-pub fn zero_copy_conv2d(
-    py: Python<'_>,
-    image: Vec<Vec<f64>>,
-    kernel: Vec<Vec<f64>>,
-) -> PyResult<Vec<Vec<f64>>> {
+pub fn zero_copy_conv2d<'py>(
+    py: Python<'py>,
+    image: &Bound<'py, PyAny>,
+    kernel: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let _call = crate::ffi_call!("zero_copy_conv2d");
+    let image = Operand::extract(image, "image")?;
+    let kernel = Operand::extract(kernel, "kernel")?;
+    let (image_view, kernel_view) = (image.view(), kernel.view());
     // Extract dimensions
-    let img_rows = image.len();
+    let (img_rows, img_cols) = image_view.dim();
     if img_rows == 0 {
         return Err(exceptions::PyValueError::new_err("Empty image array"));
     }
-    let img_cols = image[0].len();
-
-    let k_rows = kernel.len();
+    let (k_rows, k_cols) = kernel_view.dim();
     if k_rows == 0 {
         return Err(exceptions::PyValueError::new_err("Empty kernel array"));
     }
-    let k_cols = kernel[0].len();
 
     // Check dimensions
     if k_rows > img_rows || k_cols > img_cols {
@@ -71,114 +186,80 @@ pub fn zero_copy_conv2d(
         ));
     }
 
+    enforce_tensor_size(img_rows, img_cols, "conv2d")?;
+
     // Calculate output dimensions
     let out_rows = img_rows - k_rows + 1;
     let out_cols = img_cols - k_cols + 1;
+    let bytes = estimate_bytes(
+        img_rows * img_cols + k_rows * k_cols + out_rows * out_cols,
+        DType::F64,
+    );
 
-    // Create output array
-    let mut result = vec![vec![0.0; out_cols]; out_rows];
-
-    // Calculate convolution
-    for i in 0..out_rows {
-        for j in 0..out_cols {
-            let mut sum = 0.0;
-            for ki in 0..k_rows {
-                for kj in 0..k_cols {
-                    sum += image[i + ki][j + kj] * kernel[ki][kj];
-                }
-            }
-            result[i][j] = sum;
-        }
-    }
+    let result = gil_utils::observe_compute(py, || {
+        gil_utils::allow_threads(py, move || {
+            let _op_guard = OpGuard::acquire_bytes(bytes)?;
+            PyResult::Ok(conv2d(image_view, kernel_view))
+        })
+    })?;
 
     // Increment operation counter
     ZERO_COPY_OPS_COUNT.fetch_add(1, Ordering::Relaxed);
 
-    Ok(result)
+    into_py(py, result, image.is_array())
 }
 
 /// Apply element-wise operation between two NumPy arrays without copying data
 ///
 /// Args:
-///     array_a: First 2D NumPy array
-///     array_b: Second 2D NumPy array with the same dimensions
+///     array_a: First 2D float64 NumPy array, or a list of rows
+///     array_b: Second 2D float64 NumPy array (or list of rows) with the same dimensions
 ///     operation: String indicating the operation ("add", "multiply", "subtract", "divide")
 ///
 /// Returns:
-///     Result array with the element-wise operation applied
+///     Result array with the element-wise operation applied, a list of rows
+///     if `array_a` was given as one
 #[pyfunction]
-pub fn zero_copy_elementwise_op(
-    py: Python<'_>,
-    array_a: Vec<Vec<f64>>,
-    array_b: Vec<Vec<f64>>,
+pub fn zero_copy_elementwise_op<'py>(
+    py: Python<'py>,
+    array_a: &Bound<'py, PyAny>,
+    array_b: &Bound<'py, PyAny>,
     operation: &str,
-) -> PyResult<Vec<Vec<f64>>> {
+) -> PyResult<Bound<'py, PyAny>> {
+    let _call = crate::ffi_call!("zero_copy_elementwise_op");
+    let array_a = Operand::extract(array_a, "array_a")?;
+    let array_b = Operand::extract(array_b, "array_b")?;
+    let (view_a, view_b) = (array_a.view(), array_b.view());
     // Check if arrays are empty
-    if array_a.is_empty() || array_b.is_empty() {
+    if view_a.is_empty() || view_b.is_empty() {
         return Err(exceptions::PyValueError::new_err("Empty input arrays"));
     }
 
     // Check dimensions
-    if array_a.len() != array_b.len() || array_a[0].len() != array_b[0].len() {
+    if view_a.dim() != view_b.dim() {
         return Err(exceptions::PyValueError::new_err(format!(
             "Arrays must have the same shape: {:?}x{:?} vs {:?}x{:?}",
-            array_a.len(),
-            array_a[0].len(),
-            array_b.len(),
-            array_b[0].len()
+            view_a.nrows(),
+            view_a.ncols(),
+            view_b.nrows(),
+            view_b.ncols()
         )));
     }
 
-    let rows = array_a.len();
-    let cols = array_a[0].len();
+    let (rows, cols) = view_a.dim();
+    enforce_tensor_size(rows, cols, operation)?;
 
-    // Create output array
-    let mut result = vec![vec![0.0; cols]; rows];
-
-    // Apply operation
-    match operation {
-        "add" => {
-            for i in 0..rows {
-                for j in 0..cols {
-                    result[i][j] = array_a[i][j] + array_b[i][j];
-                }
-            }
-        }
-        "multiply" => {
-            for i in 0..rows {
-                for j in 0..cols {
-                    result[i][j] = array_a[i][j] * array_b[i][j];
-                }
-            }
-        }
-        "subtract" => {
-            for i in 0..rows {
-                for j in 0..cols {
-                    result[i][j] = array_a[i][j] - array_b[i][j];
-                }
-            }
-        }
-        "divide" => {
-            for i in 0..rows {
-                for j in 0..cols {
-                    if array_b[i][j] == 0.0 {
-                        return Err(exceptions::PyZeroDivisionError::new_err("Division by zero"));
-                    }
-                    result[i][j] = array_a[i][j] / array_b[i][j];
-                }
-            }
-        }
-        _ => {
-            return Err(exceptions::PyValueError::new_err(
-                "Unsupported operation. Choose from: add, multiply, subtract, divide",
-            ));
-        }
-    }
+    let result = gil_utils::observe_compute(py, || {
+        gil_utils::allow_threads(py, move || {
+            let _op_guard = OpGuard::acquire_bytes(estimate_bytes(3 * rows * cols, DType::F64))?;
+            elementwise(view_a, view_b, operation)
+        })
+    })?;
 
     // Increment operation counter
     ZERO_COPY_OPS_COUNT.fetch_add(1, Ordering::Relaxed);
 
-    Ok(result)
+    into_py(py, result, array_a.is_array())
 }
 
 /// Get the count of zero-copy operations performed
@@ -194,6 +275,29 @@ pub fn register(py: Python<'_>, parent_module: &Bound<PyModule>) -> PyResult<()>
     m.add_function(wrap_pyfunction!(zero_copy_conv2d, parent_module)?)?;
     m.add_function(wrap_pyfunction!(zero_copy_elementwise_op, parent_module)?)?;
     m.add_function(wrap_pyfunction!(get_zero_copy_ops_count, parent_module)?)?;
-    parent_module.add_submodule(m)?;
+    parent_module.add_submodule(&m)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numpy::ndarray::array;
+
+    #[test]
+    fn conv2d_slides_the_kernel_over_the_image() {
+        let image = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]];
+        let kernel = array![[1.0, 0.0], [0.0, -1.0]];
+        let result = conv2d(image.view(), kernel.view());
+        assert_eq!(result, array![[-4.0, -4.0], [-4.0, -4.0]]);
+    }
+
+    #[test]
+    fn views_of_strided_arrays_are_read_in_place() {
+        let image = array![[1.0, 2.0], [3.0, 4.0]];
+        let transposed = image.t();
+        assert_eq!(multiply(transposed, 2.0), array![[2.0, 6.0], [4.0, 8.0]]);
+        let sum = elementwise(transposed, image.view(), "add").unwrap();
+        assert_eq!(sum, array![[2.0, 5.0], [5.0, 8.0]]);
+    }
+}
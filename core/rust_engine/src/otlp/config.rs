@@ -0,0 +1,179 @@
+//! Exporter settings read from the standard `OTEL_*` environment variables
+
+use std::time::Duration;
+
+/// Collector address used when no endpoint is configured.
+pub const DEFAULT_ENDPOINT: &str = "http://localhost:4317";
+
+/// Service name used when `OTEL_SERVICE_NAME` is not set.
+pub const DEFAULT_SERVICE_NAME: &str = "forzium";
+
+/// Time between metrics exports (`OTEL_METRIC_EXPORT_INTERVAL`).
+pub const DEFAULT_METRICS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time between span exports (`OTEL_BSP_SCHEDULE_DELAY`).
+pub const DEFAULT_SPAN_DELAY: Duration = Duration::from_secs(5);
+
+/// Deadline of one export call (`OTEL_EXPORTER_OTLP_TIMEOUT`).
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Spans kept between exports before new ones are dropped
+/// (`OTEL_BSP_MAX_QUEUE_SIZE`).
+pub const DEFAULT_MAX_QUEUED_SPANS: usize = 2048;
+
+/// Where and how often the OTLP exporter pushes spans and metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    /// Collector receiving spans, or `None` to not export spans.
+    pub traces_endpoint: Option<String>,
+    /// Collector receiving metrics, or `None` to not export metrics.
+    pub metrics_endpoint: Option<String>,
+    /// gRPC metadata sent with every export, e.g. an API key.
+    pub headers: Vec<(String, String)>,
+    /// Attributes describing the process; always includes `service.name`.
+    pub resource: Vec<(String, String)>,
+    pub timeout: Duration,
+    pub metrics_interval: Duration,
+    pub span_delay: Duration,
+    pub max_queued_spans: usize,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            traces_endpoint: Some(DEFAULT_ENDPOINT.to_string()),
+            metrics_endpoint: Some(DEFAULT_ENDPOINT.to_string()),
+            headers: Vec::new(),
+            resource: vec![("service.name".into(), DEFAULT_SERVICE_NAME.into())],
+            timeout: DEFAULT_TIMEOUT,
+            metrics_interval: DEFAULT_METRICS_INTERVAL,
+            span_delay: DEFAULT_SPAN_DELAY,
+            max_queued_spans: DEFAULT_MAX_QUEUED_SPANS,
+        }
+    }
+}
+
+impl OtlpConfig {
+    /// Settings from the process environment.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Settings from the `OTEL_*` variables `var` returns. Unset variables
+    /// and values that do not parse leave the default.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+        let millis = |name: &str, default: Duration| {
+            var(name)
+                .and_then(|value| value.trim().parse().ok())
+                .map_or(default, Duration::from_millis)
+        };
+
+        let mut config = Self::default();
+        let endpoint = var("OTEL_EXPORTER_OTLP_ENDPOINT");
+        let endpoint = endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT);
+        let signal = |exporter: &str, signal_endpoint: &str| {
+            let disabled = var(exporter).is_some_and(|value| value.trim() == "none");
+            (!disabled).then(|| var(signal_endpoint).unwrap_or_else(|| endpoint.to_string()))
+        };
+        config.traces_endpoint =
+            signal("OTEL_TRACES_EXPORTER", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT");
+        config.metrics_endpoint = signal(
+            "OTEL_METRICS_EXPORTER",
+            "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT",
+        );
+        if let Some(headers) = var("OTEL_EXPORTER_OTLP_HEADERS") {
+            config.headers = parse_pairs(&headers);
+        }
+        if let Some(attributes) = var("OTEL_RESOURCE_ATTRIBUTES") {
+            for (key, value) in parse_pairs(&attributes) {
+                config.set_resource(&key, value);
+            }
+        }
+        if let Some(name) = var("OTEL_SERVICE_NAME") {
+            config.set_resource("service.name", name.trim().to_string());
+        }
+        config.timeout = millis("OTEL_EXPORTER_OTLP_TIMEOUT", DEFAULT_TIMEOUT);
+        config.metrics_interval = millis("OTEL_METRIC_EXPORT_INTERVAL", DEFAULT_METRICS_INTERVAL);
+        config.span_delay = millis("OTEL_BSP_SCHEDULE_DELAY", DEFAULT_SPAN_DELAY);
+        config.max_queued_spans = var("OTEL_BSP_MAX_QUEUE_SIZE")
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|&size| size > 0)
+            .unwrap_or(DEFAULT_MAX_QUEUED_SPANS);
+        config
+    }
+
+    /// Set resource attribute `key`, replacing any previous value.
+    pub fn set_resource(&mut self, key: &str, value: String) {
+        match self
+            .resource
+            .iter_mut()
+            .find(|(existing, _)| existing == key)
+        {
+            Some(entry) => entry.1 = value,
+            None => self.resource.push((key.to_string(), value)),
+        }
+    }
+}
+
+/// Parse the `key=value,key=value` lists of `OTEL_EXPORTER_OTLP_HEADERS` and
+/// `OTEL_RESOURCE_ATTRIBUTES`, skipping entries without a key.
+fn parse_pairs(list: &str) -> Vec<(String, String)> {
+    list.split(',')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('=')?;
+            let key = key.trim();
+            (!key.is_empty()).then(|| (key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(vars: &[(&str, &str)]) -> OtlpConfig {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        OtlpConfig::from_vars(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn defaults_without_variables() {
+        assert_eq!(config(&[]), OtlpConfig::default());
+    }
+
+    #[test]
+    fn standard_variables_override_defaults() {
+        let config = config(&[
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4317"),
+            ("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT", "http://metrics:4317"),
+            ("OTEL_TRACES_EXPORTER", "none"),
+            ("OTEL_EXPORTER_OTLP_HEADERS", "api-key=secret, =skipped"),
+            ("OTEL_RESOURCE_ATTRIBUTES", "service.name=ignored,env=prod"),
+            ("OTEL_SERVICE_NAME", "checkout"),
+            ("OTEL_METRIC_EXPORT_INTERVAL", "1500"),
+            ("OTEL_BSP_SCHEDULE_DELAY", "not a number"),
+            ("OTEL_BSP_MAX_QUEUE_SIZE", "16"),
+        ]);
+        assert_eq!(config.traces_endpoint, None);
+        assert_eq!(
+            config.metrics_endpoint.as_deref(),
+            Some("http://metrics:4317")
+        );
+        assert_eq!(
+            config.headers,
+            [("api-key".to_string(), "secret".to_string())]
+        );
+        assert_eq!(
+            config.resource,
+            [
+                ("service.name".to_string(), "checkout".to_string()),
+                ("env".to_string(), "prod".to_string()),
+            ]
+        );
+        assert_eq!(config.metrics_interval, Duration::from_millis(1500));
+        assert_eq!(config.span_delay, DEFAULT_SPAN_DELAY);
+        assert_eq!(config.max_queued_spans, 16);
+    }
+}
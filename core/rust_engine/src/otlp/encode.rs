@@ -0,0 +1,305 @@
+//! Conversion of metrics samples and server spans to OTLP messages
+
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{AnyValue, InstrumentationScope, KeyValue, any_value};
+use opentelemetry_proto::tonic::metrics::v1::{
+    AggregationTemporality, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum,
+    Summary, SummaryDataPoint, metric, number_data_point, summary_data_point,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::{
+    ResourceSpans, ScopeSpans, Span, Status, span, status,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::ServerSpan;
+use crate::metrics::{MetricKind, Sample};
+
+/// Nanoseconds since the Unix epoch, the unit of every OTLP timestamp.
+pub fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64)
+}
+
+fn string_value(key: impl Into<String>, value: impl Into<String>) -> KeyValue {
+    KeyValue {
+        key: key.into(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.into())),
+        }),
+    }
+}
+
+fn int_value(key: &str, value: i64) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::IntValue(value)),
+        }),
+    }
+}
+
+/// Resource describing the process, from `key`, `value` attributes.
+pub fn resource(attributes: &[(String, String)]) -> Resource {
+    Resource {
+        attributes: attributes
+            .iter()
+            .map(|(key, value)| string_value(key.as_str(), value.as_str()))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn scope() -> InstrumentationScope {
+    InstrumentationScope {
+        name: "forzium_engine".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        ..Default::default()
+    }
+}
+
+fn attributes(labels: &[(Cow<'static, str>, String)]) -> Vec<KeyValue> {
+    labels
+        .iter()
+        .map(|(name, value)| string_value(name.as_ref(), value.as_str()))
+        .collect()
+}
+
+/// Metrics export request for `samples` taken at `now`.
+///
+/// Counters become cumulative monotonic sums starting at `start`, gauges
+/// gauges, and the quantile, `_sum` and `_count` samples of a summary with
+/// the same labels are merged into one summary data point.
+pub fn metrics_request(
+    samples: &[Sample],
+    resource: Resource,
+    start: SystemTime,
+    now: SystemTime,
+) -> ExportMetricsServiceRequest {
+    let (start, now) = (unix_nanos(start), unix_nanos(now));
+    let mut metrics: Vec<Metric> = Vec::new();
+    let mut families: HashMap<&str, usize> = HashMap::new();
+    for sample in samples {
+        let family = sample.family();
+        let index = *families.entry(family).or_insert_with(|| {
+            let data = match sample.kind {
+                MetricKind::Counter => metric::Data::Sum(Sum {
+                    data_points: Vec::new(),
+                    aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                    is_monotonic: true,
+                }),
+                MetricKind::Gauge => metric::Data::Gauge(Gauge::default()),
+                MetricKind::Summary => metric::Data::Summary(Summary::default()),
+            };
+            metrics.push(Metric {
+                name: family.to_string(),
+                data: Some(data),
+                ..Default::default()
+            });
+            metrics.len() - 1
+        });
+
+        let point = |start_time_unix_nano| NumberDataPoint {
+            attributes: attributes(&sample.labels),
+            start_time_unix_nano,
+            time_unix_nano: now,
+            value: Some(number_data_point::Value::AsDouble(sample.value)),
+            ..Default::default()
+        };
+        match &mut metrics[index].data {
+            Some(metric::Data::Sum(sum)) => sum.data_points.push(point(start)),
+            Some(metric::Data::Gauge(gauge)) => gauge.data_points.push(point(0)),
+            Some(metric::Data::Summary(summary)) => {
+                add_to_summary(summary, sample, family, start, now)
+            }
+            _ => {}
+        }
+    }
+
+    ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: Some(resource),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(scope()),
+                metrics,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+fn add_to_summary(summary: &mut Summary, sample: &Sample, family: &str, start: u64, now: u64) {
+    let quantile = sample
+        .labels
+        .iter()
+        .find(|(name, _)| *name == "quantile")
+        .and_then(|(_, value)| value.parse().ok());
+    let labels: Vec<_> = sample
+        .labels
+        .iter()
+        .filter(|(name, _)| *name != "quantile")
+        .cloned()
+        .collect();
+    let attributes = attributes(&labels);
+    let point = match summary
+        .data_points
+        .iter_mut()
+        .position(|point| point.attributes == attributes)
+    {
+        Some(index) => &mut summary.data_points[index],
+        None => {
+            summary.data_points.push(SummaryDataPoint {
+                attributes,
+                start_time_unix_nano: start,
+                time_unix_nano: now,
+                ..Default::default()
+            });
+            summary.data_points.last_mut().unwrap()
+        }
+    };
+    match &sample.name[family.len()..] {
+        "_sum" => point.sum = sample.value,
+        "_count" => point.count = sample.value as u64,
+        _ => {
+            if let Some(quantile) = quantile {
+                point
+                    .quantile_values
+                    .push(summary_data_point::ValueAtQuantile {
+                        quantile,
+                        value: sample.value,
+                    });
+            }
+        }
+    }
+}
+
+/// Trace export request for `spans`.
+///
+/// Spans are named after the method and matched route, and carry the HTTP
+/// semantic convention attributes; 5xx responses mark them as errors.
+pub fn traces_request(spans: Vec<ServerSpan>, resource: Resource) -> ExportTraceServiceRequest {
+    let spans = spans
+        .into_iter()
+        .map(|server| {
+            let name = match &server.route {
+                Some(route) => format!("{} {route}", server.method),
+                None => server.method.clone(),
+            };
+            let mut attributes = vec![
+                string_value("http.request.method", server.method),
+                string_value("url.path", server.path),
+                int_value("http.response.status_code", server.status.into()),
+            ];
+            if let Some(route) = server.route {
+                attributes.push(string_value("http.route", route));
+            }
+            let status = (server.status >= 500).then(|| Status {
+                code: status::StatusCode::Error as i32,
+                ..Default::default()
+            });
+            Span {
+                trace_id: server.trace_id.to_vec(),
+                span_id: server.span_id.to_vec(),
+                parent_span_id: server
+                    .parent_span_id
+                    .map_or_else(Vec::new, |parent| parent.to_vec()),
+                name,
+                kind: span::SpanKind::Server as i32,
+                start_time_unix_nano: unix_nanos(server.start),
+                end_time_unix_nano: unix_nanos(server.end),
+                attributes,
+                status,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Some(resource),
+            scope_spans: vec![ScopeSpans {
+                scope: Some(scope()),
+                spans,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn samples_map_to_otlp_metric_types() {
+        let samples = vec![
+            Sample::counter("forzium_errors_total", 3u32).label("category", "validation"),
+            Sample::gauge("forzium_op_queue_depth", 2u32),
+            Sample::summary("forzium_latency_seconds", 0.5)
+                .label("route", "/a")
+                .label("quantile", "0.5"),
+            Sample::summary("forzium_latency_seconds_sum", 2.0).label("route", "/a"),
+            Sample::summary("forzium_latency_seconds_count", 4.0).label("route", "/a"),
+        ];
+        let start = UNIX_EPOCH + Duration::from_secs(1);
+        let now = UNIX_EPOCH + Duration::from_secs(2);
+        let request = metrics_request(&samples, resource(&[]), start, now);
+        let metrics = &request.resource_metrics[0].scope_metrics[0].metrics;
+        assert_eq!(metrics.len(), 3);
+
+        let Some(metric::Data::Sum(sum)) = &metrics[0].data else {
+            panic!("counter should be a sum");
+        };
+        assert!(sum.is_monotonic);
+        assert_eq!(sum.data_points[0].start_time_unix_nano, 1_000_000_000);
+        assert_eq!(sum.data_points[0].time_unix_nano, 2_000_000_000);
+        assert_eq!(sum.data_points[0].attributes[0].key, "category");
+        assert!(matches!(metrics[1].data, Some(metric::Data::Gauge(_))));
+
+        let Some(metric::Data::Summary(summary)) = &metrics[2].data else {
+            panic!("summary samples should merge into a summary");
+        };
+        assert_eq!(metrics[2].name, "forzium_latency_seconds");
+        let point = &summary.data_points[..];
+        assert_eq!(point.len(), 1);
+        assert_eq!((point[0].sum, point[0].count), (2.0, 4));
+        assert_eq!(point[0].quantile_values[0].quantile, 0.5);
+        assert_eq!(point[0].attributes.len(), 1);
+    }
+
+    #[test]
+    fn server_spans_follow_http_conventions() {
+        let start = SystemTime::now();
+        let span = ServerSpan {
+            trace_id: [1; 16],
+            span_id: [2; 8],
+            parent_span_id: Some([3; 8]),
+            method: "GET".into(),
+            route: Some("/items/{id:int}".into()),
+            path: "/items/7".into(),
+            status: 503,
+            start,
+            end: start + Duration::from_millis(5),
+        };
+        let request = traces_request(vec![span], resource(&[]));
+        let span = &request.resource_spans[0].scope_spans[0].spans[0];
+        assert_eq!(span.name, "GET /items/{id:int}");
+        assert_eq!(span.kind, span::SpanKind::Server as i32);
+        assert_eq!(span.parent_span_id, vec![3; 8]);
+        assert_eq!(
+            span.end_time_unix_nano - span.start_time_unix_nano,
+            5_000_000
+        );
+        assert_eq!(
+            span.status.as_ref().map(|status| status.code),
+            Some(status::StatusCode::Error as i32)
+        );
+        assert!(span.attributes.iter().any(|kv| kv.key == "http.route"));
+    }
+}
@@ -0,0 +1,406 @@
+//! OTLP/gRPC export of engine metrics and HTTP server spans
+//!
+//! Built with the `otlp` feature. [`start`] spawns a task on the shared
+//! runtime that pushes the samples of every registered metrics source each
+//! metrics interval, and the server spans finished since the last push each
+//! span delay. Spans are only recorded while an exporter runs, so the server
+//! pays for tracing only when something collects it.
+//!
+//! Settings come from the standard `OTEL_*` environment variables (see
+//! [`OtlpConfig::from_env`]); `configure_otlp` overrides them from Python.
+//! Only plaintext `http://` collectors are supported.
+
+use crossbeam_queue::ArrayQueue;
+use hyper::{HeaderMap, Method};
+use once_cell::sync::Lazy;
+use opentelemetry_proto::tonic::collector::metrics::v1::metrics_service_client::MetricsServiceClient;
+use opentelemetry_proto::tonic::collector::trace::v1::trace_service_client::TraceServiceClient;
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use parking_lot::{Mutex, RwLock};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::time::SystemTime;
+use tokio::sync::oneshot;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+
+use crate::error::ForziumError;
+use crate::metrics;
+use crate::runtime_manager;
+use crate::trace_context::{parse_traceparent, random_trace_id, random_u64};
+
+pub mod config;
+pub mod encode;
+
+pub use config::OtlpConfig;
+
+/// A finished request to the HTTP server, waiting to be exported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerSpan {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    /// Caller's span, from the request's `traceparent` header.
+    pub parent_span_id: Option<[u8; 8]>,
+    pub method: String,
+    /// Route pattern as registered, if one matched.
+    pub route: Option<String>,
+    pub path: String,
+    pub status: u16,
+    pub start: SystemTime,
+    pub end: SystemTime,
+}
+
+/// A request being served, timed for a [`ServerSpan`].
+pub struct SpanTimer {
+    trace_id: [u8; 16],
+    parent_span_id: Option<[u8; 8]>,
+    method: Method,
+    path: String,
+    start: SystemTime,
+}
+
+impl SpanTimer {
+    /// Queue the span of the request for export.
+    pub fn finish(self, route: Option<&str>, status: u16) {
+        let Some(exporter) = EXPORTER.read().clone() else {
+            return;
+        };
+        let span = ServerSpan {
+            trace_id: self.trace_id,
+            span_id: random_u64().to_be_bytes(),
+            parent_span_id: self.parent_span_id,
+            method: self.method.to_string(),
+            route: route.map(str::to_string),
+            path: self.path,
+            status,
+            start: self.start,
+            end: SystemTime::now(),
+        };
+        if exporter.spans.push(span).is_err() {
+            STATS.spans_dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Start timing a request, continuing the trace of its `traceparent` header
+/// if it has a valid one. `None` when no exporter is sending spans.
+pub fn start_server_span(method: &Method, path: &str, headers: &HeaderMap) -> Option<SpanTimer> {
+    if !EXPORTER.read().as_ref()?.traces {
+        return None;
+    }
+    let parent = headers
+        .get("traceparent")
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_traceparent);
+    let trace_id = match parent {
+        Some((trace_id, _)) => trace_id,
+        None => random_trace_id(),
+    };
+    Some(SpanTimer {
+        trace_id,
+        parent_span_id: parent.map(|(_, span_id)| span_id),
+        method: method.clone(),
+        path: path.to_string(),
+        start: SystemTime::now(),
+    })
+}
+
+/// Running exporter, shared by the export task and the request path.
+struct Exporter {
+    traces: bool,
+    spans: ArrayQueue<ServerSpan>,
+    stop: Mutex<Option<oneshot::Sender<()>>>,
+    done: Mutex<Option<mpsc::Receiver<()>>>,
+    config: OtlpConfig,
+}
+
+static EXPORTER: Lazy<RwLock<Option<Arc<Exporter>>>> = Lazy::new(Default::default);
+
+/// Export totals since the process started.
+#[derive(Default)]
+struct ExportStats {
+    spans_exported: AtomicU64,
+    spans_dropped: AtomicU64,
+    metric_exports: AtomicU64,
+    export_errors: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+static STATS: Lazy<ExportStats> = Lazy::new(Default::default);
+
+/// Start time reported for cumulative counters.
+static FIRST_START: Lazy<SystemTime> = Lazy::new(SystemTime::now);
+
+/// Collector connections and the metadata sent with each export.
+struct Clients {
+    traces: Option<TraceServiceClient<Channel>>,
+    metrics: Option<MetricsServiceClient<Channel>>,
+    metadata: MetadataMap,
+    resource: Resource,
+}
+
+fn endpoint(url: &str, config: &OtlpConfig) -> Result<Endpoint, ForziumError> {
+    if !url.starts_with("http://") {
+        return Err(ForziumError::Validation(format!(
+            "OTLP endpoint '{url}' must be an http:// URL"
+        )));
+    }
+    Endpoint::from_shared(url.to_string())
+        .map(|endpoint| endpoint.timeout(config.timeout))
+        .map_err(|err| ForziumError::Validation(format!("invalid OTLP endpoint '{url}': {err}")))
+}
+
+fn metadata(headers: &[(String, String)]) -> Result<MetadataMap, ForziumError> {
+    let mut metadata = MetadataMap::new();
+    for (key, value) in headers {
+        let invalid = || ForziumError::Validation(format!("invalid OTLP header '{key}'"));
+        let key =
+            MetadataKey::from_bytes(key.to_ascii_lowercase().as_bytes()).map_err(|_| invalid())?;
+        let value = MetadataValue::try_from(value.as_str()).map_err(|_| invalid())?;
+        metadata.insert(key, value);
+    }
+    Ok(metadata)
+}
+
+/// Start exporting with `config`, replacing any running exporter after
+/// flushing it.
+pub fn start(config: OtlpConfig) -> Result<(), ForziumError> {
+    if config.traces_endpoint.is_none() && config.metrics_endpoint.is_none() {
+        return Err(ForziumError::Validation(
+            "OTLP export needs traces or metrics enabled".into(),
+        ));
+    }
+    let traces = config
+        .traces_endpoint
+        .as_deref()
+        .map(|url| endpoint(url, &config))
+        .transpose()?;
+    let metrics = config
+        .metrics_endpoint
+        .as_deref()
+        .map(|url| endpoint(url, &config))
+        .transpose()?;
+    let metadata = metadata(&config.headers)?;
+    let resource = encode::resource(&config.resource);
+    Lazy::force(&FIRST_START);
+    shutdown();
+
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    let exporter = Arc::new(Exporter {
+        traces: traces.is_some(),
+        spans: ArrayQueue::new(config.max_queued_spans.max(1)),
+        stop: Mutex::new(Some(stop_tx)),
+        done: Mutex::new(Some(done_rx)),
+        config,
+    });
+    let task = exporter.clone();
+    runtime_manager::handle().spawn(async move {
+        let clients = Clients {
+            traces: traces.map(|endpoint| TraceServiceClient::new(endpoint.connect_lazy())),
+            metrics: metrics.map(|endpoint| MetricsServiceClient::new(endpoint.connect_lazy())),
+            metadata,
+            resource,
+        };
+        run(task, clients, stop_rx).await;
+        let _ = done_tx.send(());
+    });
+    *EXPORTER.write() = Some(exporter);
+    Ok(())
+}
+
+/// Stop the running exporter after a last export. Returns whether one was
+/// running.
+pub fn shutdown() -> bool {
+    let Some(exporter) = EXPORTER.write().take() else {
+        return false;
+    };
+    if let Some(stop) = exporter.stop.lock().take() {
+        let _ = stop.send(());
+    }
+    if let Some(done) = exporter.done.lock().take() {
+        // The last export makes up to two calls bounded by the timeout.
+        let _ = done.recv_timeout(exporter.config.timeout * 2);
+    }
+    true
+}
+
+async fn run(exporter: Arc<Exporter>, mut clients: Clients, mut stop: oneshot::Receiver<()>) {
+    let mut metrics_tick = tokio::time::interval(exporter.config.metrics_interval);
+    let mut spans_tick = tokio::time::interval(exporter.config.span_delay);
+    loop {
+        tokio::select! {
+            _ = metrics_tick.tick(), if clients.metrics.is_some() => {
+                export_metrics(&mut clients).await;
+            }
+            _ = spans_tick.tick(), if clients.traces.is_some() => {
+                export_spans(&exporter, &mut clients).await;
+            }
+            _ = &mut stop => break,
+        }
+    }
+    export_spans(&exporter, &mut clients).await;
+    export_metrics(&mut clients).await;
+}
+
+fn request<T>(message: T, metadata: &MetadataMap) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    *request.metadata_mut() = metadata.clone();
+    request
+}
+
+fn record_error(status: tonic::Status) {
+    STATS.export_errors.fetch_add(1, Ordering::Relaxed);
+    *STATS.last_error.lock() = Some(format!("{}: {}", status.code(), status.message()));
+}
+
+async fn export_metrics(clients: &mut Clients) {
+    let Some(client) = clients.metrics.as_mut() else {
+        return;
+    };
+    let samples = metrics::collect();
+    let message = encode::metrics_request(
+        &samples,
+        clients.resource.clone(),
+        *FIRST_START,
+        SystemTime::now(),
+    );
+    match client.export(request(message, &clients.metadata)).await {
+        Ok(_) => {
+            STATS.metric_exports.fetch_add(1, Ordering::Relaxed);
+        }
+        Err(status) => record_error(status),
+    }
+}
+
+async fn export_spans(exporter: &Exporter, clients: &mut Clients) {
+    let Some(client) = clients.traces.as_mut() else {
+        return;
+    };
+    let spans: Vec<ServerSpan> = std::iter::from_fn(|| exporter.spans.pop()).collect();
+    if spans.is_empty() {
+        return;
+    }
+    let count = spans.len() as u64;
+    let message = encode::traces_request(spans, clients.resource.clone());
+    match client.export(request(message, &clients.metadata)).await {
+        Ok(_) => {
+            STATS.spans_exported.fetch_add(count, Ordering::Relaxed);
+        }
+        Err(status) => {
+            STATS.spans_dropped.fetch_add(count, Ordering::Relaxed);
+            record_error(status);
+        }
+    }
+}
+
+/// Start pushing spans and metrics to an OpenTelemetry collector over gRPC.
+///
+/// Settings default to the standard OTEL_* environment variables; the
+/// arguments override them. `endpoint` sets the collector for both signals,
+/// `headers` the gRPC metadata sent with each export, and `traces` or
+/// `metrics` set to False stops exporting that signal. Replaces any running
+/// exporter.
+#[pyfunction]
+#[pyo3(signature = (
+    endpoint=None,
+    service_name=None,
+    headers=None,
+    traces=true,
+    metrics=true,
+    metrics_interval_ms=None,
+))]
+pub fn configure_otlp(
+    py: Python<'_>,
+    endpoint: Option<String>,
+    service_name: Option<String>,
+    headers: Option<HashMap<String, String>>,
+    traces: bool,
+    metrics: bool,
+    metrics_interval_ms: Option<u64>,
+) -> PyResult<()> {
+    let mut config = OtlpConfig::from_env();
+    if let Some(endpoint) = endpoint {
+        config.traces_endpoint = Some(endpoint.clone());
+        config.metrics_endpoint = Some(endpoint);
+    }
+    if !traces {
+        config.traces_endpoint = None;
+    }
+    if !metrics {
+        config.metrics_endpoint = None;
+    }
+    if let Some(name) = service_name {
+        config.set_resource("service.name", name);
+    }
+    if let Some(headers) = headers {
+        config.headers = headers.into_iter().collect();
+    }
+    if let Some(interval) = metrics_interval_ms {
+        if interval == 0 {
+            return Err(
+                ForziumError::Validation("metrics_interval_ms must be positive".into()).into(),
+            );
+        }
+        config.metrics_interval = std::time::Duration::from_millis(interval);
+    }
+    py.detach(|| start(config)).map_err(Into::into)
+}
+
+/// Stop the OTLP exporter after sending what it has buffered. Returns whether
+/// one was running.
+#[pyfunction]
+pub fn shutdown_otlp(py: Python<'_>) -> bool {
+    py.detach(shutdown)
+}
+
+/// Return OTLP export totals: running, spans_exported, spans_dropped,
+/// spans_queued, metric_exports, export_errors and last_error.
+#[pyfunction]
+pub fn otlp_stats(py: Python<'_>) -> PyResult<Py<PyDict>> {
+    let exporter = EXPORTER.read().clone();
+    let dict = PyDict::new(py);
+    dict.set_item("running", exporter.is_some())?;
+    dict.set_item(
+        "spans_exported",
+        STATS.spans_exported.load(Ordering::Relaxed),
+    )?;
+    dict.set_item("spans_dropped", STATS.spans_dropped.load(Ordering::Relaxed))?;
+    dict.set_item(
+        "spans_queued",
+        exporter.map_or(0, |exporter| exporter.spans.len()),
+    )?;
+    dict.set_item(
+        "metric_exports",
+        STATS.metric_exports.load(Ordering::Relaxed),
+    )?;
+    dict.set_item("export_errors", STATS.export_errors.load(Ordering::Relaxed))?;
+    dict.set_item("last_error", STATS.last_error.lock().clone())?;
+    Ok(dict.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let config = OtlpConfig {
+            traces_endpoint: Some("https://collector:4317".into()),
+            ..OtlpConfig::default()
+        };
+        assert!(matches!(start(config), Err(ForziumError::Validation(_))));
+        let config = OtlpConfig {
+            traces_endpoint: None,
+            metrics_endpoint: None,
+            ..OtlpConfig::default()
+        };
+        assert!(matches!(start(config), Err(ForziumError::Validation(_))));
+        assert!(metadata(&[("bad key".into(), "value".into())]).is_err());
+        assert!(EXPORTER.read().is_none());
+    }
+}
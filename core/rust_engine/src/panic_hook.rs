@@ -0,0 +1,164 @@
+//! Process-wide panic hook that records recent panics
+//!
+//! A `catch_unwind` site only sees the panic payload. The hook runs before
+//! unwinding starts and also knows the panicking thread and source location,
+//! so catch sites describe a panic through [`describe`] instead of
+//! downcasting the payload themselves.
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, PanicHookInfo};
+use std::sync::Once;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of panics kept for [`recent_panics`].
+pub const PANIC_HISTORY: usize = 32;
+
+/// A panic seen by the hook.
+#[derive(Debug, Clone)]
+pub struct PanicRecord {
+    /// Panic message, or a placeholder for non-string payloads.
+    pub message: String,
+    /// Name of the panicking thread, or its id if it has no name.
+    pub thread: String,
+    /// `file:line:column` of the panic, if known.
+    pub location: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub timestamp: f64,
+}
+
+impl fmt::Display for PanicRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(
+                f,
+                "thread '{}' panicked at {location}: {}",
+                self.thread, self.message
+            ),
+            None => write!(f, "thread '{}' panicked: {}", self.thread, self.message),
+        }
+    }
+}
+
+static RECENT: Lazy<Mutex<VecDeque<PanicRecord>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(PANIC_HISTORY)));
+
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    /// Latest panic on this thread not yet claimed by [`describe`].
+    static LAST_PANIC: RefCell<Option<PanicRecord>> = const { RefCell::new(None) };
+}
+
+/// Install the hook. The previously installed hook still runs after the
+/// panic is recorded, so panics are printed as before.
+///
+/// Calling this more than once has no further effect.
+pub fn install() {
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            record(info);
+            previous(info);
+        }));
+    });
+}
+
+fn record(info: &PanicHookInfo<'_>) {
+    let thread = std::thread::current();
+    let record = PanicRecord {
+        message: payload_message(info.payload()),
+        thread: thread
+            .name()
+            .map_or_else(|| format!("{:?}", thread.id()), str::to_string),
+        location: info.location().map(ToString::to_string),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64()),
+    };
+    {
+        let mut recent = RECENT.lock();
+        if recent.len() == PANIC_HISTORY {
+            recent.pop_front();
+        }
+        recent.push_back(record.clone());
+    }
+    LAST_PANIC.with(|last| *last.borrow_mut() = Some(record));
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Unknown panic occurred".to_string()
+    }
+}
+
+/// Describe a panic caught on this thread with `catch_unwind`.
+///
+/// Uses the hook's record, with thread and location, when there is one and
+/// falls back to the payload message otherwise.
+pub fn describe(payload: &(dyn Any + Send)) -> String {
+    LAST_PANIC
+        .with(|last| last.borrow_mut().take())
+        .map_or_else(|| payload_message(payload), |record| record.to_string())
+}
+
+/// Panics recorded by the hook, oldest first.
+pub fn recent_panics() -> Vec<PanicRecord> {
+    RECENT.lock().iter().cloned().collect()
+}
+
+/// Return the most recent Rust panics, oldest first, as a list of dicts with
+/// message, thread, location and timestamp.
+#[pyfunction]
+pub fn get_recent_panics(py: Python<'_>) -> PyResult<Py<PyList>> {
+    let list = PyList::empty(py);
+    for record in recent_panics() {
+        let dict = PyDict::new(py);
+        dict.set_item("message", record.message)?;
+        dict.set_item("thread", record.thread)?;
+        dict.set_item("location", record.location)?;
+        dict.set_item("timestamp", record.timestamp)?;
+        list.append(dict)?;
+    }
+    Ok(list.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hook_records_message_thread_and_location() {
+        install();
+        let payload = std::thread::Builder::new()
+            .name("panic-hook-test".into())
+            .spawn(|| {
+                let payload = panic::catch_unwind(|| panic!("boom {}", 7)).unwrap_err();
+                describe(payload.as_ref())
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(payload.starts_with("thread 'panic-hook-test' panicked at src/panic_hook.rs:"));
+        assert!(payload.ends_with(": boom 7"));
+
+        let record = recent_panics()
+            .into_iter()
+            .rev()
+            .find(|record| record.thread == "panic-hook-test")
+            .unwrap();
+        assert_eq!(record.message, "boom 7");
+        assert!(record.timestamp > 0.0);
+        assert!(recent_panics().len() <= PANIC_HISTORY);
+    }
+}
@@ -0,0 +1,652 @@
+//! Async Redis client for handlers and middleware
+//!
+//! [`ForziumRedis`] speaks RESP3 over one pipelined connection per client,
+//! served from the shared runtime and reopened on the next command after it
+//! fails. Like the HTTP client, a command returns a [`PendingReply`] at
+//! once, which sync code `wait()`s on without holding the GIL and
+//! coroutines `await`. Commands are not retried, as most that change data
+//! are not idempotent; each is bounded by the client's timeout, including
+//! any reconnection it waits for.
+//!
+//! `subscribe()` opens a dedicated connection and gives a [`Subscription`]
+//! reading its messages. Only plaintext `redis://` URLs are supported.
+
+mod connection;
+mod resp;
+
+use pyo3::exceptions::{
+    PyConnectionError, PyException, PyStopAsyncIteration, PyTimeoutError, PyTypeError,
+};
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple,
+};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::AbortHandle;
+use tracing::debug;
+
+use crate::client::seconds;
+use crate::error::ForziumError;
+use crate::gil_utils;
+use crate::runtime_manager::{self, spawn_awaitable};
+use connection::{CONNECTIONS, Config, Connection, RedisError, Subscriber};
+use resp::Value;
+
+pyo3::create_exception!(
+    forzium_engine,
+    RedisReplyError,
+    PyException,
+    "Error reply from the Redis server, e.g. `WRONGTYPE ...`"
+);
+
+/// Messages a subscription buffers before it stops reading its connection.
+const SUBSCRIPTION_BUFFER: usize = 1024;
+
+/// Outcomes of the commands sent by all clients of the process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RedisStats {
+    /// Commands answered with a value.
+    pub replies: u64,
+    /// Commands answered with an error reply.
+    pub errors: u64,
+    /// Commands that did not complete within the timeout.
+    pub timeouts: u64,
+    /// Commands that failed because the connection did.
+    pub failures: u64,
+    /// Connections opened, including reconnections and subscriptions.
+    pub connections: u64,
+}
+
+struct Counters {
+    replies: AtomicU64,
+    errors: AtomicU64,
+    timeouts: AtomicU64,
+    failures: AtomicU64,
+}
+
+static COUNTERS: Counters = Counters {
+    replies: AtomicU64::new(0),
+    errors: AtomicU64::new(0),
+    timeouts: AtomicU64::new(0),
+    failures: AtomicU64::new(0),
+};
+
+/// Snapshot of the client counters.
+pub fn stats() -> RedisStats {
+    RedisStats {
+        replies: COUNTERS.replies.load(Ordering::Relaxed),
+        errors: COUNTERS.errors.load(Ordering::Relaxed),
+        timeouts: COUNTERS.timeouts.load(Ordering::Relaxed),
+        failures: COUNTERS.failures.load(Ordering::Relaxed),
+        connections: CONNECTIONS.load(Ordering::Relaxed),
+    }
+}
+
+impl From<RedisError> for PyErr {
+    fn from(err: RedisError) -> PyErr {
+        match err {
+            RedisError::Timeout(limit) => PyTimeoutError::new_err(format!(
+                "Redis command timed out after {}s",
+                limit.as_secs_f64()
+            )),
+            RedisError::Connection(msg) => PyConnectionError::new_err(msg),
+            RedisError::Reply(msg) => RedisReplyError::new_err(msg),
+            RedisError::Cancelled => ForziumError::Cancelled("command cancelled".into()).into(),
+        }
+    }
+}
+
+/// How a command's reply is given to Python.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// Converted as is; see [`to_python`].
+    Raw,
+    /// `True` for `OK` or a positive count, `False` for nil or zero.
+    Flag,
+    /// Milliseconds as seconds, or `None` for the negative replies of `PTTL`.
+    Seconds,
+}
+
+enum Reply {
+    Value(Value, Kind),
+    Subscribed(Subscription),
+}
+
+type Outcome = Result<Reply, RedisError>;
+
+/// Connection and settings shared by the commands of one client.
+struct Inner {
+    config: Config,
+    timeout: Option<Duration>,
+    connection: tokio::sync::Mutex<Option<Arc<Connection>>>,
+}
+
+impl Inner {
+    /// The open connection, reopening it if it failed.
+    async fn connection(&self) -> Result<Arc<Connection>, RedisError> {
+        let mut slot = self.connection.lock().await;
+        match slot.as_ref() {
+            Some(connection) if !connection.is_closed() => Ok(Arc::clone(connection)),
+            _ => {
+                let connection = Arc::new(Connection::open(&self.config).await?);
+                *slot = Some(Arc::clone(&connection));
+                Ok(connection)
+            }
+        }
+    }
+
+    /// Run `work` within the client's timeout.
+    async fn bounded<T>(
+        &self,
+        work: impl Future<Output = Result<T, RedisError>>,
+    ) -> Result<T, RedisError> {
+        let outcome = match self.timeout {
+            Some(limit) => tokio::time::timeout(limit, work)
+                .await
+                .unwrap_or(Err(RedisError::Timeout(limit))),
+            None => work.await,
+        };
+        let counter = match &outcome {
+            Ok(_) => &COUNTERS.replies,
+            Err(RedisError::Reply(_)) => &COUNTERS.errors,
+            Err(RedisError::Timeout(_)) => &COUNTERS.timeouts,
+            Err(RedisError::Connection(_) | RedisError::Cancelled) => &COUNTERS.failures,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        outcome
+    }
+
+    async fn command(&self, args: Vec<Vec<u8>>) -> Result<Value, RedisError> {
+        self.bounded(async {
+            let connection = self.connection().await?;
+            connection.command(&args).await
+        })
+        .await
+    }
+
+    async fn subscribe(&self, channels: Vec<Vec<u8>>) -> Result<Subscription, RedisError> {
+        let subscriber = self
+            .bounded(Subscriber::subscribe(&self.config, &channels))
+            .await?;
+        let (sender, messages) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        let (close, closed) = oneshot::channel();
+        tokio::spawn(forward(subscriber, sender, closed));
+        Ok(Subscription {
+            messages: Arc::new(tokio::sync::Mutex::new(messages)),
+            close: Mutex::new(Some(close)),
+            channels: channels
+                .iter()
+                .map(|channel| String::from_utf8_lossy(channel).into_owned())
+                .collect(),
+        })
+    }
+}
+
+/// Pass the messages of `subscriber` on until it fails or `closed` fires.
+async fn forward(
+    mut subscriber: Subscriber,
+    sender: mpsc::Sender<(Vec<u8>, Vec<u8>)>,
+    mut closed: oneshot::Receiver<()>,
+) {
+    loop {
+        let message = tokio::select! {
+            message = subscriber.next() => message,
+            _ = &mut closed => break,
+        };
+        let message = match message {
+            Ok(message) => message,
+            Err(err) => {
+                debug!(error = ?err, "Redis subscription failed");
+                return;
+            }
+        };
+        tokio::select! {
+            sent = sender.send(message) => if sent.is_err() {
+                break;
+            },
+            _ = &mut closed => break,
+        }
+    }
+    subscriber.close().await;
+}
+
+/// Bytes of a command argument: bytes, str as UTF-8, or a number in decimal.
+fn arg(value: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+    if let Ok(bytes) = value.cast::<PyBytes>() {
+        return Ok(bytes.as_bytes().to_vec());
+    }
+    if let Ok(bytes) = value.cast::<PyByteArray>() {
+        return Ok(bytes.to_vec());
+    }
+    if let Ok(text) = value.cast::<PyString>() {
+        return Ok(text.to_str()?.as_bytes().to_vec());
+    }
+    if !value.is_instance_of::<PyBool>()
+        && (value.is_instance_of::<PyInt>() || value.is_instance_of::<PyFloat>())
+    {
+        return Ok(value.str()?.to_str()?.as_bytes().to_vec());
+    }
+    Err(PyTypeError::new_err(format!(
+        "Redis arguments must be bytes, str, int or float, not {}",
+        value.get_type().name()?
+    )))
+}
+
+/// Milliseconds of `value` seconds, at least one.
+fn millis(name: &str, value: f64) -> Result<Vec<u8>, ForziumError> {
+    let duration = seconds(name, value)?;
+    Ok(duration.as_millis().max(1).to_string().into_bytes())
+}
+
+/// `value` as a Python object: nil as None, strings as str, bulk strings as
+/// bytes, arrays and sets as lists and maps as dicts. Error replies nested
+/// in a collection become `RedisReplyError` instances.
+fn to_python(py: Python<'_>, value: Value) -> PyResult<Py<PyAny>> {
+    Ok(match value {
+        Value::Nil => py.None(),
+        Value::Text(text) => text.into_pyobject(py)?.into_any().unbind(),
+        Value::Error(msg) => RedisReplyError::new_err(msg).into_value(py).into_any(),
+        Value::Int(n) => n.into_pyobject(py)?.into_any().unbind(),
+        Value::Bulk(data) => PyBytes::new(py, &data).into_any().unbind(),
+        Value::Double(n) => n.into_pyobject(py)?.into_any().unbind(),
+        Value::Bool(flag) => flag.into_pyobject(py)?.to_owned().into_any().unbind(),
+        Value::BigNumber(digits) => py.get_type::<PyInt>().call1((digits,))?.unbind(),
+        Value::Array(items) | Value::Set(items) | Value::Push(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| to_python(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items)?.into_any().unbind()
+        }
+        Value::Map(pairs) => {
+            let dict = PyDict::new(py);
+            for (key, value) in pairs {
+                dict.set_item(to_python(py, key)?, to_python(py, value)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
+}
+
+/// Result of a command as Python sees it.
+fn convert(py: Python<'_>, outcome: Outcome) -> PyResult<Py<PyAny>> {
+    match outcome? {
+        Reply::Value(value, Kind::Raw) => to_python(py, value),
+        Reply::Value(value, Kind::Flag) => {
+            let flag = match value {
+                Value::Nil => false,
+                Value::Bool(flag) => flag,
+                Value::Int(n) => n > 0,
+                _ => true,
+            };
+            Ok(flag.into_pyobject(py)?.to_owned().into_any().unbind())
+        }
+        Reply::Value(Value::Int(ms), Kind::Seconds) if ms >= 0 => {
+            Ok((ms as f64 / 1000.0).into_pyobject(py)?.into_any().unbind())
+        }
+        Reply::Value(value, Kind::Seconds) => match value {
+            Value::Int(_) => Ok(py.None()),
+            other => to_python(py, other),
+        },
+        Reply::Subscribed(subscription) => Ok(Py::new(py, subscription)?.into_any()),
+    }
+}
+
+/// Async RESP3 client for a Redis server
+///
+/// `url` is `redis://[[username]:password@]host[:port][/db]`. `timeout`
+/// bounds each command and `connect_timeout` each connection attempt; None
+/// disables them. Commands return a `PendingReply`.
+#[pyclass(module = "forzium_engine", frozen)]
+pub struct ForziumRedis {
+    inner: Arc<Inner>,
+}
+
+impl ForziumRedis {
+    /// Send `args` and give its reply to Python as `kind`.
+    fn send(&self, args: Vec<Vec<u8>>, kind: Kind) -> PendingReply {
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+        let inner = Arc::clone(&self.inner);
+        self.spawn(name, async move {
+            Ok(Reply::Value(inner.command(args).await?, kind))
+        })
+    }
+
+    fn spawn(
+        &self,
+        command: String,
+        work: impl Future<Output = Outcome> + Send + 'static,
+    ) -> PendingReply {
+        let (tx, rx) = oneshot::channel();
+        let task = runtime_manager::handle().spawn(async move {
+            let _ = tx.send(work.await);
+        });
+        PendingReply {
+            receiver: Some(rx),
+            task: task.abort_handle(),
+            command,
+        }
+    }
+}
+
+#[pymethods]
+impl ForziumRedis {
+    #[new]
+    #[pyo3(signature = (url="redis://127.0.0.1:6379/0", *, timeout=Some(5.0), connect_timeout=Some(5.0)))]
+    fn new(url: &str, timeout: Option<f64>, connect_timeout: Option<f64>) -> PyResult<Self> {
+        let timeout = timeout.map(|value| seconds("timeout", value)).transpose()?;
+        let connect_timeout = connect_timeout
+            .map(|value| seconds("connect_timeout", value))
+            .transpose()?;
+        let inner = Inner {
+            config: Config::from_url(url, connect_timeout)?,
+            timeout,
+            connection: tokio::sync::Mutex::new(None),
+        };
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// Send any command, e.g. `execute("HSET", "user:1", "name", "Ada")`
+    #[pyo3(signature = (*args))]
+    fn execute(&self, args: &Bound<'_, PyTuple>) -> PyResult<PendingReply> {
+        if args.is_empty() {
+            return Err(ForziumError::Validation("execute() needs a command name".into()).into());
+        }
+        let args = args
+            .iter()
+            .map(|arg_| arg(&arg_))
+            .collect::<PyResult<_>>()?;
+        Ok(self.send(args, Kind::Raw))
+    }
+
+    /// Value of `key` as bytes, or None if it is not set
+    fn get(&self, key: &Bound<'_, PyAny>) -> PyResult<PendingReply> {
+        Ok(self.send(vec![b"GET".to_vec(), arg(key)?], Kind::Raw))
+    }
+
+    /// Set `key`, expiring after `ex` seconds if given
+    ///
+    /// With `nx`, only a missing key is set. Resolves to whether it was.
+    #[pyo3(signature = (key, value, *, ex=None, nx=false))]
+    fn set(
+        &self,
+        key: &Bound<'_, PyAny>,
+        value: &Bound<'_, PyAny>,
+        ex: Option<f64>,
+        nx: bool,
+    ) -> PyResult<PendingReply> {
+        let mut args = vec![b"SET".to_vec(), arg(key)?, arg(value)?];
+        if let Some(ex) = ex {
+            args.extend([b"PX".to_vec(), millis("ex", ex)?]);
+        }
+        if nx {
+            args.push(b"NX".to_vec());
+        }
+        Ok(self.send(args, Kind::Flag))
+    }
+
+    /// Delete `keys`; resolves to the number that existed
+    #[pyo3(signature = (*keys))]
+    fn delete(&self, keys: &Bound<'_, PyTuple>) -> PyResult<PendingReply> {
+        let mut args = vec![b"DEL".to_vec()];
+        for key in keys.iter() {
+            args.push(arg(&key)?);
+        }
+        Ok(self.send(args, Kind::Raw))
+    }
+
+    /// Expire `key` after `seconds`; resolves to whether the key exists
+    fn expire(&self, key: &Bound<'_, PyAny>, seconds: f64) -> PyResult<PendingReply> {
+        let args = vec![b"PEXPIRE".to_vec(), arg(key)?, millis("seconds", seconds)?];
+        Ok(self.send(args, Kind::Flag))
+    }
+
+    /// Seconds until `key` expires, or None if it is missing or persistent
+    fn ttl(&self, key: &Bound<'_, PyAny>) -> PyResult<PendingReply> {
+        Ok(self.send(vec![b"PTTL".to_vec(), arg(key)?], Kind::Seconds))
+    }
+
+    /// Add `amount` to the integer at `key`; resolves to the new value
+    #[pyo3(signature = (key, amount=1))]
+    fn incr(&self, key: &Bound<'_, PyAny>, amount: i64) -> PyResult<PendingReply> {
+        let args = vec![
+            b"INCRBY".to_vec(),
+            arg(key)?,
+            amount.to_string().into_bytes(),
+        ];
+        Ok(self.send(args, Kind::Raw))
+    }
+
+    /// Publish `message` to `channel`; resolves to the number of receivers
+    fn publish(
+        &self,
+        channel: &Bound<'_, PyAny>,
+        message: &Bound<'_, PyAny>,
+    ) -> PyResult<PendingReply> {
+        let args = vec![b"PUBLISH".to_vec(), arg(channel)?, arg(message)?];
+        Ok(self.send(args, Kind::Raw))
+    }
+
+    /// Check the server answers; resolves to True
+    fn ping(&self) -> PendingReply {
+        self.send(vec![b"PING".to_vec()], Kind::Flag)
+    }
+
+    /// Subscribe to `channels` on a connection of their own
+    ///
+    /// Resolves to a `Subscription` once the server confirmed every channel.
+    #[pyo3(signature = (*channels))]
+    fn subscribe(&self, channels: &Bound<'_, PyTuple>) -> PyResult<PendingReply> {
+        if channels.is_empty() {
+            return Err(ForziumError::Validation("subscribe() needs a channel".into()).into());
+        }
+        let channels = channels
+            .iter()
+            .map(|channel| arg(&channel))
+            .collect::<PyResult<Vec<_>>>()?;
+        let inner = Arc::clone(&self.inner);
+        Ok(self.spawn("SUBSCRIBE".into(), async move {
+            Ok(Reply::Subscribed(inner.subscribe(channels).await?))
+        }))
+    }
+
+    fn __repr__(&self) -> String {
+        let config = &self.inner.config;
+        format!(
+            "ForziumRedis('redis://{}:{}/{}')",
+            config.host, config.port, config.db
+        )
+    }
+}
+
+/// Aborts the command task when the reply is no longer awaited.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A command in flight
+///
+/// `wait()` from sync code or `await` from a coroutine to get its reply;
+/// either can be done once.
+#[pyclass(module = "forzium_engine")]
+pub struct PendingReply {
+    receiver: Option<oneshot::Receiver<Outcome>>,
+    task: AbortHandle,
+    command: String,
+}
+
+impl PendingReply {
+    fn take_receiver(&mut self) -> PyResult<oneshot::Receiver<Outcome>> {
+        self.receiver.take().ok_or_else(|| {
+            pyo3::exceptions::PyRuntimeError::new_err("Reply has already been retrieved")
+        })
+    }
+}
+
+#[pymethods]
+impl PendingReply {
+    /// Block until the reply arrives, without holding the GIL
+    fn wait(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let receiver = self.take_receiver()?;
+        let outcome = gil_utils::allow_threads(py, move || {
+            receiver
+                .blocking_recv()
+                .unwrap_or(Err(RedisError::Cancelled))
+        });
+        convert(py, outcome)
+    }
+
+    /// Await the reply from asyncio without blocking the event loop
+    ///
+    /// Cancelling the awaiting task cancels the command.
+    fn __await__(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let receiver = self.take_receiver()?;
+        let task = AbortOnDrop(self.task.clone());
+        let future = spawn_awaitable(
+            py,
+            async move {
+                let _task = task;
+                receiver.await.unwrap_or(Err(RedisError::Cancelled))
+            },
+            convert,
+        )?;
+        Ok(future.call_method0("__await__")?.unbind())
+    }
+
+    /// Whether the command has finished, successfully or not
+    fn done(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Cancel the command; returns False if it had already finished
+    ///
+    /// A command already written to the connection may still take effect.
+    fn cancel(&self) -> bool {
+        if self.task.is_finished() {
+            return false;
+        }
+        self.task.abort();
+        true
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<PendingReply {}>", self.command)
+    }
+}
+
+type Messages = Arc<tokio::sync::Mutex<mpsc::Receiver<(Vec<u8>, Vec<u8>)>>>;
+
+/// Message of a subscription as a `(channel, data)` tuple.
+fn message_tuple(py: Python<'_>, (channel, data): (Vec<u8>, Vec<u8>)) -> PyResult<Py<PyAny>> {
+    let channel = String::from_utf8_lossy(&channel).into_owned();
+    Ok((channel, PyBytes::new(py, &data))
+        .into_pyobject(py)?
+        .into_any()
+        .unbind())
+}
+
+/// Messages published to the channels of a `ForziumRedis.subscribe()`
+///
+/// Read them with `get_message()`, or with `async for channel, data in
+/// subscription`. Messages not yet read are buffered up to a limit, past
+/// which the connection is no longer read until they are.
+#[pyclass(module = "forzium_engine", frozen)]
+pub struct Subscription {
+    messages: Messages,
+    close: Mutex<Option<oneshot::Sender<()>>>,
+    channels: Vec<String>,
+}
+
+#[pymethods]
+impl Subscription {
+    /// Next `(channel, data)` message, or None if none arrives within
+    /// `timeout` seconds; waits without holding the GIL
+    ///
+    /// Raises ConnectionError once the subscription is closed or its
+    /// connection failed.
+    #[pyo3(signature = (timeout=None))]
+    fn get_message(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Option<Py<PyAny>>> {
+        let timeout = timeout
+            .map(|value| {
+                Duration::try_from_secs_f64(value).map_err(|_| {
+                    ForziumError::Validation(
+                        "timeout must be a non-negative number of seconds".into(),
+                    )
+                })
+            })
+            .transpose()?;
+        let messages = Arc::clone(&self.messages);
+        let message = gil_utils::allow_threads(py, move || {
+            runtime_manager::handle().block_on(async move {
+                let mut messages = messages.lock().await;
+                match timeout {
+                    Some(limit) => tokio::time::timeout(limit, messages.recv()).await.ok(),
+                    None => Some(messages.recv().await),
+                }
+            })
+        });
+        match message {
+            None => Ok(None),
+            Some(None) => Err(PyConnectionError::new_err("subscription is closed")),
+            Some(Some(message)) => message_tuple(py, message).map(Some),
+        }
+    }
+
+    fn __aiter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    /// Await the next message; iteration stops once the subscription is
+    /// closed or its connection failed
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let messages = Arc::clone(&self.messages);
+        spawn_awaitable(
+            py,
+            async move { messages.lock().await.recv().await },
+            |py, message| match message {
+                Some(message) => message_tuple(py, message),
+                None => Err(PyStopAsyncIteration::new_err(())),
+            },
+        )
+    }
+
+    /// Unsubscribe and close the connection
+    fn close(&self) {
+        if let Some(close) = self.close.lock().unwrap().take() {
+            let _ = close.send(());
+        }
+    }
+
+    /// Channels subscribed to
+    #[getter]
+    fn channels(&self) -> Vec<String> {
+        self.channels.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Subscription {}>", self.channels.join(", "))
+    }
+}
+
+/// Report the outcomes of the commands sent by all Redis clients
+#[pyfunction]
+pub fn redis_stats(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let stats = stats();
+    let dict = PyDict::new(py);
+    dict.set_item("replies", stats.replies)?;
+    dict.set_item("errors", stats.errors)?;
+    dict.set_item("timeouts", stats.timeouts)?;
+    dict.set_item("failures", stats.failures)?;
+    dict.set_item("connections", stats.connections)?;
+    Ok(dict)
+}
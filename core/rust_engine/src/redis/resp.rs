@@ -0,0 +1,235 @@
+//! RESP3 encoding and decoding
+//!
+//! Commands are sent as arrays of bulk strings. Replies are decoded
+//! incrementally: [`decode`] returns `Ok(None)` until the buffer holds a
+//! whole value, so the connection can keep reading and try again.
+
+use std::fmt;
+
+/// A decoded RESP3 value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    /// Simple or verbatim string.
+    Text(String),
+    /// Simple or bulk error, e.g. `WRONGTYPE Operation against a key ...`.
+    Error(String),
+    Int(i64),
+    Bulk(Vec<u8>),
+    Double(f64),
+    Bool(bool),
+    /// Big number, in decimal.
+    BigNumber(String),
+    Array(Vec<Value>),
+    Set(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// Out-of-band message, such as a pub/sub message.
+    Push(Vec<Value>),
+}
+
+/// Data that is not valid RESP3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolError(pub String);
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid RESP3 data: {}", self.0)
+    }
+}
+
+/// The command `args` as a RESP array of bulk strings.
+pub fn encode(args: &[Vec<u8>]) -> Vec<u8> {
+    let mut frame = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        frame.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        frame.extend_from_slice(arg);
+        frame.extend_from_slice(b"\r\n");
+    }
+    frame
+}
+
+/// The value at the start of `buf` and the bytes it takes, or None if
+/// `buf` ends before the value does.
+pub fn decode(buf: &[u8]) -> Result<Option<(Value, usize)>, ProtocolError> {
+    let mut parser = Parser { buf, pos: 0 };
+    Ok(parser.value()?.map(|value| (value, parser.pos)))
+}
+
+/// Evaluate to the `Some` value of a `Result<Option<_>, _>`, returning
+/// `Ok(None)` for incomplete input.
+macro_rules! ready {
+    ($parsed:expr) => {
+        match $parsed? {
+            Some(value) => value,
+            None => return Ok(None),
+        }
+    };
+}
+
+struct Parser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// The rest of the current line, without its CRLF.
+    fn line(&mut self) -> Result<Option<&'a [u8]>, ProtocolError> {
+        let rest = &self.buf[self.pos..];
+        let Some(end) = rest.windows(2).position(|pair| pair == b"\r\n") else {
+            return Ok(None);
+        };
+        self.pos += end + 2;
+        Ok(Some(&rest[..end]))
+    }
+
+    fn value(&mut self) -> Result<Option<Value>, ProtocolError> {
+        let Some(&tag) = self.buf.get(self.pos) else {
+            return Ok(None);
+        };
+        self.pos += 1;
+        let line = ready!(self.line());
+        let value = match tag {
+            b'+' => Value::Text(text(line)?),
+            b'-' => Value::Error(text(line)?),
+            b':' => Value::Int(number(line)?),
+            b'_' => Value::Nil,
+            b'#' => match line {
+                b"t" => Value::Bool(true),
+                b"f" => Value::Bool(false),
+                _ => return Err(ProtocolError(format!("bad boolean {line:?}"))),
+            },
+            b',' => Value::Double(
+                text(line)?
+                    .parse()
+                    .map_err(|_| ProtocolError(format!("bad double {line:?}")))?,
+            ),
+            b'(' => Value::BigNumber(text(line)?),
+            b'$' | b'!' | b'=' => {
+                let len = number(line)?;
+                if len < 0 {
+                    return Ok(Some(Value::Nil));
+                }
+                let data = ready!(self.blob(len as usize));
+                match tag {
+                    b'$' => Value::Bulk(data.to_vec()),
+                    b'!' => Value::Error(text(data)?),
+                    // verbatim strings start with their format, e.g. `txt:`
+                    _ => Value::Text(text(data.get(4..).unwrap_or_default())?),
+                }
+            }
+            b'*' | b'~' | b'>' => {
+                let len = number(line)?;
+                if len < 0 {
+                    return Ok(Some(Value::Nil));
+                }
+                let mut items = Vec::with_capacity((len as usize).min(1024));
+                for _ in 0..len {
+                    items.push(ready!(self.value()));
+                }
+                match tag {
+                    b'*' => Value::Array(items),
+                    b'~' => Value::Set(items),
+                    _ => Value::Push(items),
+                }
+            }
+            b'%' | b'|' => {
+                let len = number(line)?;
+                let mut pairs = Vec::with_capacity((len.max(0) as usize).min(1024));
+                for _ in 0..len {
+                    let key = ready!(self.value());
+                    pairs.push((key, ready!(self.value())));
+                }
+                if tag == b'|' {
+                    // attributes describe the value that follows, which is
+                    // the reply
+                    return self.value();
+                }
+                Value::Map(pairs)
+            }
+            _ => return Err(ProtocolError(format!("unknown type byte {tag:#04x}"))),
+        };
+        Ok(Some(value))
+    }
+
+    /// `len` bytes followed by CRLF.
+    fn blob(&mut self, len: usize) -> Result<Option<&'a [u8]>, ProtocolError> {
+        let rest = &self.buf[self.pos..];
+        if rest.len() < len + 2 {
+            return Ok(None);
+        }
+        if &rest[len..len + 2] != b"\r\n" {
+            return Err(ProtocolError("blob not terminated by CRLF".into()));
+        }
+        self.pos += len + 2;
+        Ok(Some(&rest[..len]))
+    }
+}
+
+fn text(bytes: &[u8]) -> Result<String, ProtocolError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| ProtocolError("string is not UTF-8".into()))
+}
+
+fn number(bytes: &[u8]) -> Result<i64, ProtocolError> {
+    text(bytes)?
+        .parse()
+        .map_err(|_| ProtocolError(format!("bad integer {bytes:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_arrays_of_bulk_strings() {
+        let frame = encode(&[b"SET".to_vec(), b"k".to_vec(), b"a b".to_vec()]);
+        assert_eq!(frame, b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$3\r\na b\r\n");
+    }
+
+    #[test]
+    fn values_decode_once_complete() {
+        let data = b"*3\r\n$5\r\nhello\r\n%1\r\n+a\r\n:-7\r\n_\r\n";
+        for end in 0..data.len() {
+            assert_eq!(decode(&data[..end]).unwrap(), None, "{end}");
+        }
+        let (value, used) = decode(data).unwrap().unwrap();
+        assert_eq!(used, data.len());
+        assert_eq!(
+            value,
+            Value::Array(vec![
+                Value::Bulk(b"hello".to_vec()),
+                Value::Map(vec![(Value::Text("a".into()), Value::Int(-7))]),
+                Value::Nil,
+            ])
+        );
+    }
+
+    #[test]
+    fn every_resp3_type_decodes() {
+        let cases: [(&[u8], Value); 10] = [
+            (b"-ERR bad\r\n", Value::Error("ERR bad".into())),
+            (b"!7\r\nERR bad\r\n", Value::Error("ERR bad".into())),
+            (b"#t\r\n", Value::Bool(true)),
+            (b",1.5\r\n", Value::Double(1.5)),
+            (
+                b"(12345678901234567890\r\n",
+                Value::BigNumber("12345678901234567890".into()),
+            ),
+            (b"=7\r\ntxt:abc\r\n", Value::Text("abc".into())),
+            (b"$-1\r\n", Value::Nil),
+            (b"~1\r\n:1\r\n", Value::Set(vec![Value::Int(1)])),
+            (
+                b">2\r\n+message\r\n$1\r\nx\r\n",
+                Value::Push(vec![
+                    Value::Text("message".into()),
+                    Value::Bulk(b"x".to_vec()),
+                ]),
+            ),
+            (b"|1\r\n+ttl\r\n:3\r\n+OK\r\n", Value::Text("OK".into())),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(decode(data).unwrap(), Some((expected, data.len())));
+        }
+        assert!(decode(b"?x\r\n").is_err());
+        assert!(decode(b"$1\r\nab\r\n").is_err());
+    }
+}
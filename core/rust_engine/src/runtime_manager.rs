@@ -0,0 +1,293 @@
+//! Process-wide tokio runtime shared by the HTTP server and `AsyncCompute`.
+//!
+//! The runtime is built on first use with the configured number of worker
+//! threads. CPU-bound compute jobs go to its blocking pool through
+//! [`spawn_blocking`], so long kernels never stall connection handling on the
+//! async workers.
+//!
+//! The async workers can be pinned to reserved cores with
+//! [`configure_runtime_affinity`]; blocking threads are then kept off those
+//! cores so compute jobs cannot starve connection handling.
+
+use crate::compute::thread_pool::validate_core_ids;
+use crate::error::ForziumError;
+use crate::gil_utils;
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::runtime::{Handle, Runtime};
+
+/// Worker threads used when none are configured.
+pub const DEFAULT_WORKER_THREADS: usize = 4;
+
+/// Pool name that addresses the shared runtime in `set_cpu_affinity`.
+pub const RUNTIME_POOL: &str = "runtime";
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+/// Configured worker count; held while the runtime is built so that
+/// configuration cannot race with startup.
+static WORKER_THREADS: Mutex<usize> = Mutex::new(DEFAULT_WORKER_THREADS);
+/// Cores reserved for the async workers, fixed once the runtime starts.
+static CORE_IDS: Mutex<Option<Vec<usize>>> = Mutex::new(None);
+static BLOCKING_SPAWNED: AtomicU64 = AtomicU64::new(0);
+static BLOCKING_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+fn runtime() -> &'static Runtime {
+    if let Some(runtime) = RUNTIME.get() {
+        return runtime;
+    }
+    let workers = WORKER_THREADS.lock().unwrap();
+    let core_ids = CORE_IDS.lock().unwrap();
+    RUNTIME.get_or_init(|| {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder
+            .worker_threads(*workers)
+            .thread_name("forzium-runtime-worker")
+            .enable_all();
+        if let Some(core_ids) = core_ids.as_ref() {
+            builder.on_thread_start(thread_pinner(*workers, core_ids.clone()));
+        }
+        builder
+            .build()
+            .expect("failed to build the shared tokio runtime")
+    })
+}
+
+/// Start hook pinning the first `workers` runtime threads to `reserved` and
+/// every later thread to the remaining cores.
+///
+/// Tokio starts its async workers while the runtime is built, before any
+/// blocking thread, so the first threads to start are the workers. Pinning
+/// is best effort, like the Rayon pools.
+fn thread_pinner(workers: usize, reserved: Vec<usize>) -> impl Fn() + Send + Sync + 'static {
+    let others: Vec<usize> = core_affinity::get_core_ids()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|core| core.id)
+        .filter(|id| !reserved.contains(id))
+        .collect();
+    let started = Arc::new(AtomicUsize::new(0));
+    move || {
+        let index = started.fetch_add(1, Ordering::Relaxed);
+        let id = if index < workers {
+            reserved[index % reserved.len()]
+        } else if others.is_empty() {
+            return;
+        } else {
+            others[(index - workers) % others.len()]
+        };
+        let _ = core_affinity::set_for_current(core_affinity::CoreId { id });
+    }
+}
+
+/// Handle to the shared runtime, starting it if needed.
+pub fn handle() -> Handle {
+    runtime().handle().clone()
+}
+
+/// Number of async worker threads the shared runtime uses or will use.
+pub fn worker_threads() -> usize {
+    *WORKER_THREADS.lock().unwrap()
+}
+
+/// Set the number of async worker threads of the shared runtime.
+///
+/// Must be called before the runtime starts; afterwards only the current
+/// worker count is accepted.
+pub fn configure_runtime(worker_threads: usize) -> Result<(), ForziumError> {
+    if worker_threads == 0 {
+        return Err(ForziumError::Validation(
+            "worker_threads must be positive".into(),
+        ));
+    }
+    let mut workers = WORKER_THREADS.lock().unwrap();
+    if RUNTIME.get().is_some() && *workers != worker_threads {
+        return Err(ForziumError::Validation(format!(
+            "shared runtime already started with {} worker threads",
+            *workers
+        )));
+    }
+    *workers = worker_threads;
+    Ok(())
+}
+
+/// Reserve `core_ids` for the async workers of the shared runtime.
+///
+/// Must be called before the runtime starts; afterwards only the current
+/// cores are accepted.
+pub fn configure_runtime_affinity(core_ids: Vec<usize>) -> Result<(), ForziumError> {
+    validate_core_ids(&core_ids).map_err(ForziumError::Validation)?;
+    let mut current = CORE_IDS.lock().unwrap();
+    if RUNTIME.get().is_some() && current.as_ref() != Some(&core_ids) {
+        return Err(ForziumError::Validation(
+            "shared runtime already started; its CPU affinity is fixed".into(),
+        ));
+    }
+    *current = Some(core_ids);
+    Ok(())
+}
+
+/// Cores reserved for the async workers, if any.
+pub fn runtime_affinity() -> Option<Vec<usize>> {
+    CORE_IDS.lock().unwrap().clone()
+}
+
+/// Tracks a running blocking job for the metrics snapshot.
+struct ActiveBlockingJob;
+
+impl ActiveBlockingJob {
+    fn start() -> Self {
+        BLOCKING_ACTIVE.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for ActiveBlockingJob {
+    fn drop(&mut self) {
+        BLOCKING_ACTIVE.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Run a CPU-bound job on the shared runtime's blocking pool.
+pub fn spawn_blocking<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    BLOCKING_SPAWNED.fetch_add(1, Ordering::Relaxed);
+    runtime().spawn_blocking(move || {
+        let _active = ActiveBlockingJob::start();
+        f()
+    });
+}
+
+/// Run `work` on the shared runtime and return an asyncio future of the
+/// running loop resolved with its output, converted by `convert`.
+/// Cancelling the future aborts `work`.
+pub fn spawn_awaitable<'py, T: Send + 'static>(
+    py: Python<'py>,
+    work: impl Future<Output = T> + Send + 'static,
+    convert: impl FnOnce(Python<'_>, T) -> PyResult<Py<PyAny>> + Send + 'static,
+) -> PyResult<Bound<'py, PyAny>> {
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+    let target = future.clone().unbind();
+    let event_loop = event_loop.unbind();
+    let task = handle().spawn(async move {
+        let output = work.await;
+        gil_utils::with_gil(|py| {
+            let result = Mutex::new(Some(convert(py, output)));
+            let settle =
+                PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+                    let py = args.py();
+                    let future = target.bind(py);
+                    if future.call_method0("done")?.is_truthy()? {
+                        return Ok(());
+                    }
+                    match result.lock().unwrap().take() {
+                        Some(Ok(value)) => future.call_method1("set_result", (value,))?,
+                        Some(Err(err)) => {
+                            future.call_method1("set_exception", (err.into_value(py),))?
+                        }
+                        None => return Ok(()),
+                    };
+                    Ok(())
+                });
+            // The loop may have closed while the work was in flight
+            if let Ok(settle) = settle {
+                let _ = event_loop.call_method1(py, "call_soon_threadsafe", (settle,));
+            }
+        });
+    });
+    let task = task.abort_handle();
+    let on_done = PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+        if args.get_item(0)?.call_method0("cancelled")?.is_truthy()? {
+            task.abort();
+        }
+        Ok(())
+    })?;
+    future.call_method1("add_done_callback", (on_done,))?;
+    Ok(future)
+}
+
+/// Snapshot of shared runtime activity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeSnapshot {
+    /// Whether the runtime has been started.
+    pub started: bool,
+    /// Async worker threads.
+    pub worker_threads: usize,
+    /// Async tasks currently alive on the runtime.
+    pub alive_tasks: usize,
+    /// Tasks waiting in the runtime's global queue.
+    pub global_queue_depth: usize,
+    /// Blocking jobs submitted through [`spawn_blocking`].
+    pub blocking_jobs_spawned: u64,
+    /// Blocking jobs currently running.
+    pub blocking_jobs_active: usize,
+}
+
+/// Collect runtime metrics without starting the runtime.
+pub fn snapshot() -> RuntimeSnapshot {
+    let (alive_tasks, global_queue_depth) = RUNTIME.get().map_or((0, 0), |runtime| {
+        let metrics = runtime.metrics();
+        (metrics.num_alive_tasks(), metrics.global_queue_depth())
+    });
+    RuntimeSnapshot {
+        started: RUNTIME.get().is_some(),
+        worker_threads: worker_threads(),
+        alive_tasks,
+        global_queue_depth,
+        blocking_jobs_spawned: BLOCKING_SPAWNED.load(Ordering::Relaxed),
+        blocking_jobs_active: BLOCKING_ACTIVE.load(Ordering::SeqCst),
+    }
+}
+
+/// Set the worker thread count of the runtime shared by the HTTP server and
+/// `AsyncCompute`. Must be called before either is first used.
+#[pyfunction]
+pub fn configure_shared_runtime(worker_threads: usize) -> PyResult<()> {
+    configure_runtime(worker_threads).map_err(Into::into)
+}
+
+/// Report worker, task and blocking-job metrics of the shared runtime.
+#[pyfunction]
+pub fn shared_runtime_metrics(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let snapshot = snapshot();
+    let dict = PyDict::new(py);
+    dict.set_item("started", snapshot.started)?;
+    dict.set_item("worker_threads", snapshot.worker_threads)?;
+    dict.set_item("alive_tasks", snapshot.alive_tasks)?;
+    dict.set_item("global_queue_depth", snapshot.global_queue_depth)?;
+    dict.set_item("blocking_jobs_spawned", snapshot.blocking_jobs_spawned)?;
+    dict.set_item("blocking_jobs_active", snapshot.blocking_jobs_active)?;
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn configuration_is_fixed_once_started() {
+        assert!(configure_runtime(0).is_err());
+        let (tx, rx) = mpsc::channel();
+        spawn_blocking(move || tx.send(snapshot()).unwrap());
+        let during = rx.recv().unwrap();
+        assert!(during.started);
+        assert!(during.blocking_jobs_active >= 1);
+        assert!(snapshot().blocking_jobs_spawned >= 1);
+
+        let workers = worker_threads();
+        assert!(configure_runtime(workers).is_ok());
+        assert!(configure_runtime(workers + 1).is_err());
+
+        assert!(configure_runtime_affinity(vec![]).is_err());
+        let core = core_affinity::get_core_ids().map_or(0, |ids| ids[0].id);
+        if runtime_affinity() != Some(vec![core]) {
+            assert!(configure_runtime_affinity(vec![core]).is_err());
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Cron expressions
+//!
+//! The five classic fields are supported, `minute hour day-of-month month
+//! day-of-week`, each a `*`, a value, a range `a-b` or a list of those, with
+//! an optional `/step`. Months and weekdays may be given by their English
+//! three-letter names; Sunday is 0 or 7. As in Vixie cron, a day matches if
+//! either day field does when both are restricted. The `@hourly`, `@daily`,
+//! `@weekly`, `@monthly` and `@yearly` shorthands are accepted too. Times
+//! are evaluated in UTC.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use std::fmt;
+
+use crate::error::ForziumError;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Years searched for a matching time before giving up, so that
+/// expressions such as `0 0 30 2 *` end.
+const SEARCH_YEARS: i32 = 5;
+
+/// A parsed cron expression; each field is a bit set of the values it
+/// matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day-of-month and day-of-week fields are not `*`.
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, ForziumError> {
+        let expanded = match expr.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(invalid(expr, "expected 5 fields"));
+        };
+        let weekdays_set = field(expr, weekdays, 0, 7, &WEEKDAYS)?;
+        Ok(Self {
+            source: expr.trim().to_string(),
+            minutes: field(expr, minutes, 0, 59, &[])?,
+            hours: field(expr, hours, 0, 23, &[])?,
+            days: field(expr, days, 1, 31, &[])?,
+            months: field(expr, months, 1, 12, &MONTHS)?,
+            // 7 is another name for Sunday
+            weekdays: (weekdays_set | weekdays_set >> 7) & 0x7f,
+            days_restricted: !days.starts_with('*'),
+            weekdays_restricted: !weekdays.starts_with('*'),
+        })
+    }
+
+    /// The first matching minute after `after`.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let last_year = start.year() + SEARCH_YEARS;
+        let mut time = start;
+        while time.year() <= last_year {
+            let date = time.date();
+            if !has(self.months, time.month()) {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !has(self.hours, time.hour()) {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+}
+
+impl fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn invalid(expr: &str, why: &str) -> ForziumError {
+    ForziumError::Validation(format!("invalid cron expression '{expr}': {why}"))
+}
+
+/// Bit set of the values `text` matches, which must lie in `min..=max`.
+/// `names` name the values from `min` on.
+fn field(expr: &str, text: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, ForziumError> {
+    let value = |item: &str| -> Result<u32, ForziumError> {
+        let lower = item.to_ascii_lowercase();
+        let value = match names.iter().position(|name| *name == lower) {
+            Some(index) => min + index as u32,
+            None => item
+                .parse()
+                .map_err(|_| invalid(expr, &format!("bad value '{item}'")))?,
+        };
+        if !(min..=max).contains(&value) {
+            return Err(invalid(expr, &format!("{value} is outside {min}-{max}")));
+        }
+        Ok(value)
+    };
+    let mut set = 0;
+    for item in text.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| invalid(expr, &format!("bad step '{step}'")))?;
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (value(first)?, value(last)?),
+            // `a/n` runs from a to the end of the range
+            None if step > 1 => (value(range)?, max),
+            None => {
+                let value = value(range)?;
+                (value, value)
+            }
+        };
+        if first > last {
+            return Err(invalid(expr, &format!("empty range '{range}'")));
+        }
+        for value in (first..=last).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> Option<NaiveDateTime> {
+        CronExpr::parse(expr).unwrap().next_after(at(after))
+    }
+
+    #[test]
+    fn next_times_follow_every_field() {
+        let cases = [
+            ("*/15 * * * *", "2026-03-01 10:07", "2026-03-01 10:15"),
+            ("0 9-17 * * mon-fri", "2026-10-16 17:30", "2026-10-19 09:00"),
+            ("30 2 1 * *", "2026-12-05 00:00", "2027-01-01 02:30"),
+            ("0 0 29 feb *", "2026-01-01 00:00", "2028-02-29 00:00"),
+            ("0 12 * * 7", "2026-10-16 00:00", "2026-10-18 12:00"),
+            ("5,10 0 * * *", "2026-10-16 00:05", "2026-10-16 00:10"),
+            ("@hourly", "2026-10-16 23:59", "2026-10-17 00:00"),
+            // either day field matches when both are restricted
+            ("0 0 13 * fri", "2026-10-14 00:00", "2026-10-16 00:00"),
+        ];
+        for (expr, after, expected) in cases {
+            assert_eq!(next(expr, after), Some(at(expected)), "{expr}");
+        }
+        assert_eq!(next("0 0 30 2 *", "2026-01-01 00:00"), None);
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for expr in [
+            "* * * *",
+            "60 * * * *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "x * * * *",
+        ] {
+            assert!(CronExpr::parse(expr).is_err(), "{expr}");
+        }
+    }
+}
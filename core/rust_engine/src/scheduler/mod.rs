@@ -0,0 +1,573 @@
+//! Recurring jobs run from the shared runtime
+//!
+//! Jobs are registered under a name with a [`Schedule`]: a fixed interval,
+//! or a cron expression evaluated in UTC. A task on the shared runtime
+//! waits for each due time, plus a random delay of up to the job's jitter
+//! so that instances started together spread their runs, then runs the job
+//! on the blocking pool. A run still in progress when the next is due makes
+//! that one skip, unless the job allows overlapping runs. Interval runs
+//! missed while the process was suspended are skipped too.
+//!
+//! Jobs are Python callables, coroutine functions included, or Rust
+//! operations implementing [`Job`]; the `gc` and `health_checks` operations
+//! are built in. Every job keeps counts of its runs and the outcomes of the
+//! most recent ones. Once a job is scheduled from Python, [`shutdown`] runs
+//! at interpreter exit, so that no run calls into a finalizing interpreter.
+
+mod cron;
+
+pub use cron::CronExpr;
+
+use chrono::DateTime;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::BuildHasher;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::client::seconds;
+use crate::error::ForziumError;
+use crate::gil_utils;
+use crate::health;
+use crate::interpreter;
+use crate::memory::gc_interface;
+use crate::runtime_manager;
+
+/// Runs kept in each job's history.
+const HISTORY_LEN: usize = 20;
+
+/// Work run on a schedule.
+pub trait Job: Send + Sync {
+    /// `Ok` when the run succeeded, otherwise why not.
+    fn run(&self) -> Result<(), String>;
+}
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every period, the first time one period after being scheduled.
+    Every(Duration),
+    /// At the minutes matching the expression.
+    Cron(CronExpr),
+}
+
+impl Schedule {
+    /// How long from now until the run after the one due at `due`, and the
+    /// time it is due at; None if the schedule never runs again.
+    fn next(&self, due: Instant) -> Option<(Instant, SystemTime)> {
+        let now = Instant::now();
+        let wall = SystemTime::now();
+        match self {
+            Schedule::Every(period) => {
+                let mut next = due + *period;
+                if next < now {
+                    // skip the runs missed, keeping to the original phase
+                    let behind = (now - next).as_nanos() / period.as_nanos();
+                    next += *period * (behind as u32 + 1);
+                }
+                Some((next, wall + (next - now)))
+            }
+            Schedule::Cron(expr) => {
+                let since_epoch = wall.duration_since(UNIX_EPOCH).ok()?;
+                let utc = DateTime::from_timestamp(since_epoch.as_secs() as i64, 0)?.naive_utc();
+                let at = expr.next_after(utc)?.and_utc();
+                let at = UNIX_EPOCH + Duration::from_secs(at.timestamp().try_into().ok()?);
+                let wait = at.duration_since(wall).unwrap_or_default();
+                Some((now + wait, at))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schedule::Every(period) => write!(f, "every {}s", period.as_secs_f64()),
+            Schedule::Cron(expr) => write!(f, "cron {expr}"),
+        }
+    }
+}
+
+/// How a job is run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Upper bound of the random delay added to each run.
+    pub jitter: Duration,
+    /// Whether a run may start while the previous one is in progress.
+    pub allow_overlap: bool,
+}
+
+/// Outcome of one run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub started: SystemTime,
+    pub duration: Duration,
+    /// Why the run failed, `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// Counts and recent runs of a job.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobStats {
+    pub name: String,
+    pub schedule: Schedule,
+    /// Runs that succeeded.
+    pub successes: u64,
+    /// Runs that failed or panicked.
+    pub failures: u64,
+    /// Runs skipped because the previous one was still in progress.
+    pub skipped: u64,
+    /// Runs in progress.
+    pub running: usize,
+    /// When the next run is due, before jitter.
+    pub next_run: Option<SystemTime>,
+    /// The most recent runs, oldest first.
+    pub history: Vec<Run>,
+}
+
+/// Runs in progress, of scheduled and unscheduled jobs alike.
+static IN_PROGRESS: AtomicUsize = AtomicUsize::new(0);
+
+/// A job and its counters.
+struct Scheduled {
+    name: String,
+    schedule: Schedule,
+    job: Arc<dyn Job>,
+    options: Options,
+    successes: AtomicU64,
+    failures: AtomicU64,
+    skipped: AtomicU64,
+    running: AtomicUsize,
+    /// Set once unscheduled, so that a driver being aborted starts no run.
+    stopped: AtomicBool,
+    next_run: Mutex<Option<SystemTime>>,
+    history: Mutex<VecDeque<Run>>,
+}
+
+impl Scheduled {
+    fn stop(&self, task: &AbortHandle) {
+        self.stopped.store(true, Ordering::Release);
+        task.abort();
+    }
+
+    /// Start a run on the blocking pool unless one must be skipped.
+    fn fire(self: &Arc<Self>) {
+        // counted before checking for a stop, so `shutdown` waits for it
+        IN_PROGRESS.fetch_add(1, Ordering::AcqRel);
+        if self.stopped.load(Ordering::Acquire) {
+            IN_PROGRESS.fetch_sub(1, Ordering::AcqRel);
+            return;
+        }
+        let running = self.running.fetch_add(1, Ordering::AcqRel);
+        if running > 0 && !self.options.allow_overlap {
+            self.running.fetch_sub(1, Ordering::AcqRel);
+            IN_PROGRESS.fetch_sub(1, Ordering::AcqRel);
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let scheduled = Arc::clone(self);
+        runtime_manager::spawn_blocking(move || {
+            let started = SystemTime::now();
+            let start = std::time::Instant::now();
+            let error = match catch_unwind(AssertUnwindSafe(|| scheduled.job.run())) {
+                Ok(result) => result.err(),
+                Err(_) => Some("job panicked".into()),
+            };
+            let counter = match &error {
+                None => &scheduled.successes,
+                Some(error) => {
+                    warn!(job = %scheduled.name, %error, "scheduled job failed");
+                    &scheduled.failures
+                }
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+            let mut history = scheduled.history.lock();
+            if history.len() == HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(Run {
+                started,
+                duration: start.elapsed(),
+                error,
+            });
+            drop(history);
+            scheduled.running.fetch_sub(1, Ordering::AcqRel);
+            IN_PROGRESS.fetch_sub(1, Ordering::AcqRel);
+        });
+    }
+
+    fn stats(&self) -> JobStats {
+        JobStats {
+            name: self.name.clone(),
+            schedule: self.schedule.clone(),
+            successes: self.successes.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Acquire),
+            next_run: *self.next_run.lock(),
+            history: self.history.lock().iter().cloned().collect(),
+        }
+    }
+}
+
+/// Random delay of up to `jitter`.
+fn random_delay(jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        return Duration::ZERO;
+    }
+    let random = RandomState::new().hash_one(Instant::now());
+    jitter.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+/// Wait for each run of `scheduled` and start it.
+async fn drive(scheduled: Arc<Scheduled>) {
+    let mut due = Instant::now();
+    while let Some((next, at)) = scheduled.schedule.next(due) {
+        *scheduled.next_run.lock() = Some(at);
+        due = next;
+        tokio::time::sleep_until(due + random_delay(scheduled.options.jitter)).await;
+        scheduled.fire();
+    }
+    *scheduled.next_run.lock() = None;
+}
+
+struct Entry {
+    scheduled: Arc<Scheduled>,
+    task: AbortHandle,
+}
+
+static JOBS: Lazy<Mutex<BTreeMap<String, Entry>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Run `job` on `schedule` under `name`, replacing any job scheduled with
+/// that name.
+pub fn schedule(name: &str, schedule: Schedule, job: Arc<dyn Job>, options: Options) {
+    let scheduled = Arc::new(Scheduled {
+        name: name.to_string(),
+        schedule,
+        job,
+        options,
+        successes: AtomicU64::new(0),
+        failures: AtomicU64::new(0),
+        skipped: AtomicU64::new(0),
+        running: AtomicUsize::new(0),
+        stopped: AtomicBool::new(false),
+        next_run: Mutex::new(None),
+        history: Mutex::new(VecDeque::with_capacity(HISTORY_LEN)),
+    });
+    let task = runtime_manager::handle()
+        .spawn(drive(Arc::clone(&scheduled)))
+        .abort_handle();
+    let entry = Entry { scheduled, task };
+    if let Some(previous) = JOBS.lock().insert(name.to_string(), entry) {
+        previous.scheduled.stop(&previous.task);
+    }
+}
+
+/// Stop scheduling the job registered as `name`; a run in progress
+/// finishes. Returns whether there was one.
+pub fn unschedule(name: &str) -> bool {
+    match JOBS.lock().remove(name) {
+        Some(entry) => {
+            entry.scheduled.stop(&entry.task);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Stop scheduling every job and wait up to `timeout` for the runs in
+/// progress to finish. Returns whether they all did.
+pub fn shutdown(timeout: Duration) -> bool {
+    for entry in std::mem::take(&mut *JOBS.lock()).into_values() {
+        entry.scheduled.stop(&entry.task);
+    }
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let idle = IN_PROGRESS.load(Ordering::Acquire) == 0;
+        if idle || std::time::Instant::now() >= deadline {
+            return idle;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Counts and recent runs of every scheduled job, by name.
+pub fn stats() -> Vec<JobStats> {
+    JOBS.lock()
+        .values()
+        .map(|entry| entry.scheduled.stats())
+        .collect()
+}
+
+/// Python's garbage collection, as `force_gc()` runs it.
+pub struct CollectGarbage;
+
+impl Job for CollectGarbage {
+    fn run(&self) -> Result<(), String> {
+        gil_utils::with_gil(|py| gc_interface::force_gc(py).map_err(|err| err.to_string()))
+    }
+}
+
+/// The readiness checks; fails with the names of those failing.
+pub struct HealthChecks;
+
+impl Job for HealthChecks {
+    fn run(&self) -> Result<(), String> {
+        let report = health::run();
+        if !report.healthy() {
+            return Err(format!("failing checks: {}", report.failing().join(", ")));
+        }
+        Ok(())
+    }
+}
+
+/// The built-in job named `name`.
+fn builtin(name: &str) -> Option<Arc<dyn Job>> {
+    match name {
+        "gc" => Some(Arc::new(CollectGarbage)),
+        "health_checks" => Some(Arc::new(HealthChecks)),
+        _ => None,
+    }
+}
+
+/// A Python callable and the interpreter it was registered from; it fails
+/// by raising. Coroutines it returns are run to completion.
+struct PyJob {
+    job: Py<PyAny>,
+    interpreter: i64,
+}
+
+impl Job for PyJob {
+    fn run(&self) -> Result<(), String> {
+        gil_utils::with_gil(|py| {
+            let run = || -> PyResult<()> {
+                interpreter::ensure_current(py, self.interpreter)?;
+                let result = self.job.call0(py)?;
+                let asyncio = py.import("asyncio")?;
+                if asyncio
+                    .call_method1("iscoroutine", (&result,))?
+                    .is_truthy()?
+                {
+                    asyncio.call_method1("run", (result,))?;
+                }
+                Ok(())
+            };
+            run().map_err(|err| err.to_string())
+        })
+    }
+}
+
+/// Run `job` under `name` every `every` seconds or at the times of the
+/// `cron` expression, replacing any job scheduled with that name
+///
+/// `job` is a callable, which fails by raising, or the name of a built-in
+/// operation: "gc" or "health_checks". Each run is delayed by up to `jitter`
+/// seconds at random. Unless `allow_overlap`, a run due while the previous
+/// one is in progress is skipped.
+#[pyfunction]
+#[pyo3(signature = (name, job, *, every=None, cron=None, jitter=0.0, allow_overlap=false))]
+pub fn schedule_job(
+    py: Python<'_>,
+    name: &str,
+    job: &Bound<'_, PyAny>,
+    every: Option<f64>,
+    cron: Option<&str>,
+    jitter: f64,
+    allow_overlap: bool,
+) -> PyResult<()> {
+    let when = match (every, cron) {
+        (Some(every), None) => Schedule::Every(seconds("every", every)?),
+        (None, Some(cron)) => Schedule::Cron(CronExpr::parse(cron)?),
+        _ => {
+            return Err(
+                ForziumError::Validation("pass either every or cron, not both".into()).into(),
+            );
+        }
+    };
+    let jitter = Duration::try_from_secs_f64(jitter).map_err(|_| {
+        ForziumError::Validation("jitter must be a non-negative number of seconds".into())
+    })?;
+    let job: Arc<dyn Job> = if let Ok(op) = job.cast::<PyString>() {
+        let op = op.to_str()?;
+        builtin(op).ok_or_else(|| {
+            ForziumError::Validation(format!(
+                "unknown operation '{op}', expected 'gc' or 'health_checks'"
+            ))
+        })?
+    } else if job.is_callable() {
+        Arc::new(PyJob {
+            job: job.clone().unbind(),
+            interpreter: interpreter::current_id(py)?,
+        })
+    } else {
+        return Err(ForziumError::Validation(
+            "job must be callable or the name of an operation".into(),
+        )
+        .into());
+    };
+    let options = Options {
+        jitter,
+        allow_overlap,
+    };
+    if !AT_EXIT.swap(true, Ordering::AcqRel) {
+        // runs calling into Python must not outlive the interpreter
+        let atexit = py.import("atexit")?;
+        atexit.call_method1("register", (wrap_pyfunction!(shutdown_scheduler, py)?,))?;
+    }
+    schedule(name, when, job, options);
+    Ok(())
+}
+
+/// Whether `shutdown_scheduler` is registered to run at interpreter exit.
+static AT_EXIT: AtomicBool = AtomicBool::new(false);
+
+/// Stop scheduling the job registered as `name`; a run in progress
+/// finishes. Returns whether there was one.
+#[pyfunction]
+pub fn unschedule_job(name: &str) -> bool {
+    unschedule(name)
+}
+
+/// Stop every scheduled job and wait up to `timeout` seconds for the runs
+/// in progress to finish; returns whether they all did. Runs at interpreter
+/// exit once a job has been scheduled.
+#[pyfunction]
+#[pyo3(signature = (timeout=5.0))]
+pub fn shutdown_scheduler(py: Python<'_>, timeout: f64) -> PyResult<bool> {
+    let timeout = Duration::try_from_secs_f64(timeout).map_err(|_| {
+        ForziumError::Validation("timeout must be a non-negative number of seconds".into())
+    })?;
+    Ok(gil_utils::allow_threads(py, || shutdown(timeout)))
+}
+
+fn unix_seconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Report every scheduled job by name: its schedule, counts of successful,
+/// failed and skipped runs, runs in progress, when the next run is due as a
+/// Unix time, and its most recent runs with their start, duration and error
+#[pyfunction]
+pub fn scheduled_jobs(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new(py);
+    for job in stats() {
+        let entry = PyDict::new(py);
+        entry.set_item("schedule", job.schedule.to_string())?;
+        entry.set_item("successes", job.successes)?;
+        entry.set_item("failures", job.failures)?;
+        entry.set_item("skipped", job.skipped)?;
+        entry.set_item("running", job.running)?;
+        entry.set_item("next_run", job.next_run.map(unix_seconds))?;
+        let history = PyList::empty(py);
+        for run in job.history {
+            let item = PyDict::new(py);
+            item.set_item("started", unix_seconds(run.started))?;
+            item.set_item("duration", run.duration.as_secs_f64())?;
+            item.set_item("error", run.error)?;
+            history.append(item)?;
+        }
+        entry.set_item("history", history)?;
+        dict.set_item(job.name, entry)?;
+    }
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts its runs and the most that were in progress at once.
+    struct Probe {
+        runs: AtomicUsize,
+        active: AtomicUsize,
+        most_active: AtomicUsize,
+        pause: Duration,
+    }
+
+    impl Job for Probe {
+        fn run(&self) -> Result<(), String> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_active.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(self.pause);
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            if self.runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err("first run fails".into());
+            }
+            Ok(())
+        }
+    }
+
+    fn probe(pause: Duration) -> Arc<Probe> {
+        Arc::new(Probe {
+            runs: AtomicUsize::new(0),
+            active: AtomicUsize::new(0),
+            most_active: AtomicUsize::new(0),
+            pause,
+        })
+    }
+
+    fn job_stats(name: &str) -> JobStats {
+        stats().into_iter().find(|job| job.name == name).unwrap()
+    }
+
+    #[test]
+    fn interval_jobs_run_and_record_outcomes() {
+        let job = probe(Duration::ZERO);
+        let every = Schedule::Every(Duration::from_millis(10));
+        schedule("test-interval", every, job.clone(), Options::default());
+        std::thread::sleep(Duration::from_millis(200));
+        let stats = job_stats("test-interval");
+        assert!(unschedule("test-interval"));
+        assert!(!unschedule("test-interval"));
+        assert!(job.runs.load(Ordering::SeqCst) >= 3);
+        assert_eq!(stats.failures, 1);
+        assert!(stats.successes >= 2);
+        assert_eq!(stats.history[0].error.as_deref(), Some("first run fails"));
+        assert!(stats.next_run.is_some());
+    }
+
+    #[test]
+    fn overlapping_runs_are_skipped_unless_allowed() {
+        for allow_overlap in [false, true] {
+            let job = probe(Duration::from_millis(50));
+            let name = format!("test-overlap-{allow_overlap}");
+            let options = Options {
+                jitter: Duration::ZERO,
+                allow_overlap,
+            };
+            let every = Schedule::Every(Duration::from_millis(10));
+            schedule(&name, every, job.clone(), options);
+            std::thread::sleep(Duration::from_millis(150));
+            let stats = job_stats(&name);
+            unschedule(&name);
+            let most_active = job.most_active.load(Ordering::SeqCst);
+            if allow_overlap {
+                assert!(most_active > 1);
+                assert_eq!(stats.skipped, 0);
+            } else {
+                assert_eq!(most_active, 1);
+                assert!(stats.skipped > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn missed_interval_runs_are_skipped() {
+        let period = Duration::from_secs(10);
+        let due = Instant::now() - Duration::from_secs(35);
+        let (next, _) = Schedule::Every(period).next(due).unwrap();
+        let wait = next - Instant::now();
+        assert!(wait > Duration::from_secs(4) && wait <= Duration::from_secs(5));
+    }
+}
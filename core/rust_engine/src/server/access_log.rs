@@ -0,0 +1,293 @@
+//! Access log of the requests the HTTP server answers
+//!
+//! Each sampled request becomes one `info` event on this module's target
+//! with the method, path, matched route, status, latency, the id, caller
+//! and custom keys of its [context](crate::server::request_context), and
+//! both sets of headers, the values of sensitive headers replaced by
+//! [`REDACTED`]. Bodies are included up to a configured length. Requests
+//! answered with an error status are sampled at their own rate, so failures
+//! can all be kept while successes are thinned out.
+
+use http_body_util::{BodyExt, Either, Full};
+use hyper::body::Bytes;
+use hyper::header::HeaderName;
+use hyper::{HeaderMap, Method, Request, Response};
+use serde_json::{Map, Value};
+use std::collections::hash_map::RandomState;
+use std::fmt::Write;
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::info;
+
+use crate::error::ForziumError;
+use crate::server::proxy::ServerBody;
+use crate::server::request_context::Context;
+
+/// Headers redacted when no list is configured.
+pub const DEFAULT_REDACTED_HEADERS: [&str; 5] = [
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+    "x-api-key",
+];
+
+/// Logged in place of a redacted header's value.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Sampling, redaction and body settings of a server's access log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessLog {
+    /// Share of successful requests logged, from 0 to 1.
+    pub sample_rate: f64,
+    /// Share of requests answered with a 4xx or 5xx status logged.
+    pub error_sample_rate: f64,
+    /// Lowercase names of the headers whose values are redacted.
+    pub redacted_headers: Vec<String>,
+    /// Longest body prefix logged; 0 logs no bodies.
+    pub max_body_bytes: usize,
+}
+
+impl Default for AccessLog {
+    fn default() -> Self {
+        Self {
+            sample_rate: 1.0,
+            error_sample_rate: 1.0,
+            redacted_headers: DEFAULT_REDACTED_HEADERS.map(str::to_string).to_vec(),
+            max_body_bytes: 0,
+        }
+    }
+}
+
+impl AccessLog {
+    /// Check the rates are between 0 and 1 and the header names are valid,
+    /// lowercasing the names.
+    pub fn validated(mut self) -> Result<Self, ForziumError> {
+        for (name, rate) in [
+            ("sample_rate", self.sample_rate),
+            ("error_sample_rate", self.error_sample_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(ForziumError::Validation(format!(
+                    "{name} must be between 0 and 1"
+                )));
+            }
+        }
+        for name in &mut self.redacted_headers {
+            *name = name.to_ascii_lowercase();
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(ForziumError::Validation(format!(
+                    "invalid header name '{name}'"
+                )));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Start logging `req` from `client`, or `None` if nothing would be
+    /// sampled.
+    pub fn capture<B>(self: &Arc<Self>, req: &Request<B>, client: SocketAddr) -> Option<Capture> {
+        if self.sample_rate == 0.0 && self.error_sample_rate == 0.0 {
+            return None;
+        }
+        Some(Capture {
+            log: self.clone(),
+            method: req.method().clone(),
+            path: req.uri().path().to_string(),
+            headers: req.headers().clone(),
+            client,
+        })
+    }
+
+    fn sampled(&self, status: u16) -> bool {
+        let rate = if status >= 400 {
+            self.error_sample_rate
+        } else {
+            self.sample_rate
+        };
+        rate >= 1.0 || (rate > 0.0 && random_unit() < rate)
+    }
+
+    /// Headers as a JSON object, with redacted values replaced.
+    fn headers_json(&self, headers: &HeaderMap) -> String {
+        let mut object = Map::new();
+        for (name, value) in headers {
+            let value = if self.redacted_headers.iter().any(|r| r == name.as_str()) {
+                REDACTED.into()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            match object.get_mut(name.as_str()) {
+                Some(Value::String(existing)) => {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                }
+                _ => {
+                    object.insert(name.to_string(), Value::String(value));
+                }
+            }
+        }
+        Value::Object(object).to_string()
+    }
+
+    /// At most `max_body_bytes` of `body` as text, noting what was cut.
+    fn body_preview(&self, body: &[u8]) -> String {
+        let shown = &body[..body.len().min(self.max_body_bytes)];
+        let mut preview = String::from_utf8_lossy(shown).into_owned();
+        if body.len() > shown.len() {
+            let _ = write!(preview, "... ({} more bytes)", body.len() - shown.len());
+        }
+        preview
+    }
+}
+
+/// Uniform random number in `[0, 1)`; `RandomState` is seeded per thread and
+/// advanced on each call.
+fn random_unit() -> f64 {
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(CALLS.fetch_add(1, Ordering::Relaxed));
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A request being served whose outcome may be logged.
+pub struct Capture {
+    log: Arc<AccessLog>,
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    client: SocketAddr,
+}
+
+impl Capture {
+    /// Log the request if its outcome is sampled, and return `response`.
+    pub async fn finish(
+        self,
+        response: Response<ServerBody>,
+        route: Option<&str>,
+        request_body: Option<Bytes>,
+        context: &Context,
+        latency: Duration,
+    ) -> Response<ServerBody> {
+        let status = response.status().as_u16();
+        if !self.log.sampled(status) {
+            return response;
+        }
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let request_headers = self.log.headers_json(&self.headers);
+        let response_headers = self.log.headers_json(response.headers());
+        let user = context.subject().unwrap_or_default();
+        let values = Value::Object(context.values()).to_string();
+        // bodies proxied from an upstream are streamed through, not logged
+        let streamed = matches!(response.body(), Either::Right(_));
+        if self.log.max_body_bytes == 0 || streamed {
+            info!(
+                client = %self.client,
+                method = %self.method,
+                path = %self.path,
+                route = route.unwrap_or(""),
+                status,
+                latency_ms,
+                request_id = context.id(),
+                user,
+                context = %values,
+                request_headers = %request_headers,
+                response_headers = %response_headers,
+                "request served"
+            );
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        let Either::Left(body) = body else {
+            unreachable!("streamed responses are logged without their body")
+        };
+        let response_body = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(never) => match never {},
+        };
+        info!(
+            client = %self.client,
+            method = %self.method,
+            path = %self.path,
+            route = route.unwrap_or(""),
+            status,
+            latency_ms,
+            request_id = context.id(),
+            user,
+            context = %values,
+            request_headers = %request_headers,
+            response_headers = %response_headers,
+            request_body = %self.log.body_preview(&request_body.unwrap_or_default()),
+            response_body = %self.log.body_preview(&response_body),
+            "request served"
+        );
+        Response::from_parts(parts, Either::Left(Full::new(response_body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn settings_are_validated() {
+        let log = AccessLog {
+            redacted_headers: vec!["X-Token".into()],
+            ..AccessLog::default()
+        };
+        assert_eq!(log.validated().unwrap().redacted_headers, ["x-token"]);
+        for invalid in [
+            AccessLog {
+                sample_rate: 1.5,
+                ..AccessLog::default()
+            },
+            AccessLog {
+                redacted_headers: vec!["bad header".into()],
+                ..AccessLog::default()
+            },
+        ] {
+            assert!(matches!(
+                invalid.validated(),
+                Err(ForziumError::Validation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn errors_are_sampled_separately() {
+        let log = AccessLog {
+            sample_rate: 0.0,
+            ..AccessLog::default()
+        };
+        assert!(!log.sampled(200));
+        assert!(log.sampled(503));
+        let half = AccessLog {
+            sample_rate: 0.5,
+            ..AccessLog::default()
+        };
+        let kept = (0..10_000).filter(|_| half.sampled(200)).count();
+        assert!((4_000..6_000).contains(&kept), "{kept}");
+    }
+
+    #[test]
+    fn sensitive_headers_and_long_bodies_are_cut() {
+        let log = AccessLog {
+            max_body_bytes: 4,
+            ..AccessLog::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer secret"));
+        headers.append("accept", HeaderValue::from_static("text/plain"));
+        headers.append("accept", HeaderValue::from_static("text/html"));
+        let logged: Value = serde_json::from_str(&log.headers_json(&headers)).unwrap();
+        assert_eq!(logged["authorization"], REDACTED);
+        assert_eq!(logged["accept"], "text/plain, text/html");
+        assert_eq!(log.body_preview(b"abcdefg"), "abcd... (3 more bytes)");
+        assert_eq!(log.body_preview(b"ab"), "ab");
+    }
+}
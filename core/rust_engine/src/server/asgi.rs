@@ -0,0 +1,662 @@
+//! ASGI applications mounted on the HTTP server
+//!
+//! A mounted application answers the requests under its path prefix that
+//! no handler route takes, so an existing Starlette or FastAPI app can run
+//! on the engine while its routes move over one at a time. Each request
+//! becomes an ASGI `http` scope whose coroutine runs on the server's event
+//! loop: `receive()` reads the request body from the connection as the app
+//! asks for it, and `send()` streams the response back, waiting while the
+//! client is slower than the app.
+//!
+//! The lifespan protocol runs when the server starts and stops, and the
+//! state it sets up is copied into every request's scope.
+
+use http_body_util::BodyExt;
+use hyper::body::{Body, Bytes, Frame, Incoming};
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::http::request::Parts;
+use hyper::{HeaderMap, Request, Response, StatusCode, Version};
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyConnectionError, PyRuntimeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyCFunction, PyDict, PyList};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc as std_mpsc};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::{debug, error, warn};
+
+use crate::error::ForziumError;
+use crate::gil_utils;
+use crate::runtime_manager::{self, spawn_awaitable};
+use crate::server::event_loop::{self, EventLoop};
+use crate::server::request_context::{self, Context};
+
+/// Response body chunks queued before `send()` waits for the client.
+const CHUNKS_QUEUED: usize = 8;
+
+/// Longest wait for the app to complete a lifespan startup or shutdown.
+const LIFESPAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why a mounted app did not answer a request.
+#[derive(Debug)]
+pub enum AsgiError {
+    /// The app did not start its response within the request timeout.
+    Timeout,
+    /// The app raised, or returned without starting a response.
+    App(String),
+}
+
+/// Lifespan events reported by an app, and its coroutine ending.
+enum LifespanEvent {
+    StartupComplete,
+    StartupFailed(String),
+    ShutdownComplete,
+    ShutdownFailed(String),
+    Exited(Option<String>),
+}
+
+/// Lifespan of an app that completed its startup.
+struct Lifespan {
+    shutdown: watch::Sender<bool>,
+    events: std_mpsc::Receiver<LifespanEvent>,
+}
+
+/// An ASGI application and the prefix it is mounted at.
+pub struct AsgiApp {
+    app: Py<PyAny>,
+    /// Prefix without a trailing slash; empty when mounted at the root.
+    root_path: String,
+    /// Lifespan state, copied into each request's scope.
+    state: Py<PyDict>,
+    run_lifespan: bool,
+    lifespan: Mutex<Option<Lifespan>>,
+}
+
+impl AsgiApp {
+    /// Mount `app` at `prefix`, a path without parameters. The lifespan
+    /// protocol runs only with `run_lifespan`.
+    pub fn new(
+        py: Python<'_>,
+        prefix: &str,
+        app: Py<PyAny>,
+        run_lifespan: bool,
+    ) -> Result<Self, ForziumError> {
+        if !prefix.starts_with('/') || prefix.contains(['{', '}', '?', '#']) {
+            return Err(ForziumError::Validation(format!(
+                "mount prefix must be a path without parameters, got '{prefix}'"
+            )));
+        }
+        if !app.bind(py).is_callable() {
+            return Err(ForziumError::Validation("ASGI app must be callable".into()));
+        }
+        Ok(Self {
+            app,
+            root_path: prefix.trim_end_matches('/').to_string(),
+            state: PyDict::new(py).unbind(),
+            run_lifespan,
+            lifespan: Mutex::new(None),
+        })
+    }
+
+    /// Prefix the app is mounted at, used to label its metrics.
+    pub fn mount_point(&self) -> &str {
+        if self.root_path.is_empty() {
+            "/"
+        } else {
+            &self.root_path
+        }
+    }
+
+    /// Whether `path` is under the app's prefix.
+    pub fn mounts(&self, path: &str) -> bool {
+        path.strip_prefix(self.root_path.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    /// Pass `req` to the app in `context` and await the start of its
+    /// response, whose body streams from the app as it sends it.
+    pub async fn call(
+        self: &Arc<Self>,
+        event_loop: Arc<EventLoop>,
+        req: Request<Incoming>,
+        client: SocketAddr,
+        context: Arc<Context>,
+        timeout: Duration,
+    ) -> Result<Response<AsgiBody>, AsgiError> {
+        let (parts, body) = req.into_parts();
+        let (start_tx, start_rx) = oneshot::channel();
+        let (chunks_tx, chunks_rx) = mpsc::channel(CHUNKS_QUEUED);
+        let exchange = Arc::new(Exchange {
+            body: tokio::sync::Mutex::new(Some(body)),
+            finished: watch::channel(false).0,
+            start: Mutex::new(Some(start_tx)),
+            chunks: Mutex::new(Some(chunks_tx)),
+        });
+        // Until the response body is handed to the connection, a dropped
+        // request also tells the app the client is gone
+        let finished = Finished(Arc::clone(&exchange));
+
+        // calling the app takes the GIL, so keep it off the async workers
+        let app = Arc::clone(self);
+        runtime_manager::spawn_blocking(move || {
+            let called = gil_utils::with_gil(|py| {
+                app.start(py, &event_loop, &parts, client, &exchange, &context)
+            });
+            if let Err(e) = called {
+                exchange.fail(e.to_string());
+            }
+        });
+
+        match tokio::time::timeout(timeout, start_rx).await {
+            Ok(Ok(Ok((status, headers)))) => {
+                let mut response = Response::new(AsgiBody {
+                    chunks: chunks_rx,
+                    _finished: finished,
+                });
+                *response.status_mut() = status;
+                *response.headers_mut() = headers;
+                Ok(response)
+            }
+            Ok(Ok(Err(err))) => Err(AsgiError::App(err)),
+            Ok(Err(_)) => Err(AsgiError::App("response was never started".into())),
+            Err(_) => Err(AsgiError::Timeout),
+        }
+    }
+
+    /// Call the app with the scope of a request and schedule its coroutine.
+    fn start(
+        &self,
+        py: Python<'_>,
+        event_loop: &EventLoop,
+        parts: &Parts,
+        client: SocketAddr,
+        exchange: &Arc<Exchange>,
+        context: &Arc<Context>,
+    ) -> PyResult<()> {
+        let scope = self.scope(py, parts, client)?;
+        let receive = {
+            let exchange = Arc::clone(exchange);
+            PyCFunction::new_closure(py, None, None, move |args, _kwargs| {
+                let exchange = Arc::clone(&exchange);
+                spawn_awaitable(
+                    args.py(),
+                    async move { exchange.receive().await },
+                    |py, received| received.into_message(py),
+                )
+                .map(Bound::unbind)
+            })?
+        };
+        let send = {
+            let exchange = Arc::clone(exchange);
+            PyCFunction::new_closure(py, None, None, move |args, _kwargs| {
+                let py = args.py();
+                let sending = exchange.send(&args.get_item(0)?)?;
+                spawn_awaitable(py, sending, |py, sent| sent.map(|()| py.None())).map(Bound::unbind)
+            })?
+        };
+        request_context::with_current(py, context, || {
+            let coroutine = self.app.bind(py).call1((scope, receive, send))?;
+            let running = event_loop.get(py)?;
+            let exchange = Arc::clone(exchange);
+            event_loop::spawn(&running, &coroutine, move |_py, result| match result {
+                Ok(_) => {
+                    exchange.fail("app returned without starting a response".into());
+                }
+                // failures before the response started are the caller's to report
+                Err(e) => {
+                    if !exchange.fail(e.to_string()) {
+                        error!(error = %e, "ASGI app raised after starting its response");
+                    }
+                }
+            })
+        })
+    }
+
+    /// ASGI `http` scope of a request.
+    fn scope<'py>(
+        &self,
+        py: Python<'py>,
+        parts: &Parts,
+        client: SocketAddr,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let scope = PyDict::new(py);
+        scope.set_item("type", "http")?;
+        scope.set_item("asgi", asgi_version(py)?)?;
+        let http_version = match parts.version {
+            Version::HTTP_10 => "1.0",
+            Version::HTTP_2 => "2",
+            _ => "1.1",
+        };
+        scope.set_item("http_version", http_version)?;
+        scope.set_item("method", parts.method.as_str())?;
+        scope.set_item("scheme", "http")?;
+        let raw_path = parts.uri.path();
+        scope.set_item("path", percent_decode(raw_path))?;
+        scope.set_item("raw_path", PyBytes::new(py, raw_path.as_bytes()))?;
+        let query = parts.uri.query().unwrap_or("");
+        scope.set_item("query_string", PyBytes::new(py, query.as_bytes()))?;
+        scope.set_item("root_path", &self.root_path)?;
+        let headers = PyList::empty(py);
+        for (name, value) in &parts.headers {
+            headers.append((
+                PyBytes::new(py, name.as_str().as_bytes()),
+                PyBytes::new(py, value.as_bytes()),
+            ))?;
+        }
+        scope.set_item("headers", headers)?;
+        scope.set_item("client", (client.ip().to_string(), client.port()))?;
+        scope.set_item("server", py.None())?;
+        scope.set_item("state", self.state.bind(py).copy()?)?;
+        Ok(scope)
+    }
+
+    /// Run the app's lifespan startup on `event_loop` and wait for it to
+    /// complete. Apps that do not support the protocol are served anyway.
+    pub fn start_lifespan(&self, py: Python<'_>, event_loop: &EventLoop) -> PyResult<()> {
+        if !self.run_lifespan || self.lifespan.lock().is_some() {
+            return Ok(());
+        }
+        let (events_tx, events) = std_mpsc::channel();
+        let (shutdown, _) = watch::channel(false);
+        let scope = PyDict::new(py);
+        scope.set_item("type", "lifespan")?;
+        scope.set_item("asgi", asgi_version(py)?)?;
+        scope.set_item("state", self.state.bind(py))?;
+        let receive = {
+            let started = AtomicBool::new(false);
+            let shutdown = shutdown.clone();
+            PyCFunction::new_closure(py, None, None, move |args, _kwargs| {
+                let py = args.py();
+                if !started.swap(true, Ordering::Relaxed) {
+                    return ready(py, lifespan_message(py, "lifespan.startup")?);
+                }
+                let mut shutdown = shutdown.subscribe();
+                spawn_awaitable(
+                    py,
+                    async move {
+                        let _ = shutdown.wait_for(|requested| *requested).await;
+                    },
+                    |py, ()| lifespan_message(py, "lifespan.shutdown"),
+                )
+                .map(Bound::unbind)
+            })?
+        };
+        let send = {
+            let events_tx = events_tx.clone();
+            PyCFunction::new_closure(py, None, None, move |args, _kwargs| {
+                let py = args.py();
+                let message = args.get_item(0)?;
+                let text = |key: &str| -> PyResult<String> {
+                    match message.get_item(key) {
+                        Ok(value) => value.extract(),
+                        Err(_) => Ok(String::new()),
+                    }
+                };
+                let event = match text("type")?.as_str() {
+                    "lifespan.startup.complete" => LifespanEvent::StartupComplete,
+                    "lifespan.startup.failed" => LifespanEvent::StartupFailed(text("message")?),
+                    "lifespan.shutdown.complete" => LifespanEvent::ShutdownComplete,
+                    "lifespan.shutdown.failed" => LifespanEvent::ShutdownFailed(text("message")?),
+                    other => {
+                        return Err(PyRuntimeError::new_err(format!(
+                            "unexpected ASGI message type '{other}'"
+                        )));
+                    }
+                };
+                let _ = events_tx.send(event);
+                ready(py, py.None())
+            })?
+        };
+
+        let coroutine = self.app.bind(py).call1((scope, receive, send))?;
+        let running = event_loop.get(py)?;
+        event_loop::spawn(&running, &coroutine, move |_py, result| {
+            let _ = events_tx.send(LifespanEvent::Exited(result.err().map(|e| e.to_string())));
+        })?;
+        let (event, events) =
+            gil_utils::allow_threads(py, move || (events.recv_timeout(LIFESPAN_TIMEOUT), events));
+        match event {
+            Ok(LifespanEvent::StartupComplete) => {
+                *self.lifespan.lock() = Some(Lifespan { shutdown, events });
+                Ok(())
+            }
+            Ok(LifespanEvent::StartupFailed(message)) => Err(PyRuntimeError::new_err(format!(
+                "ASGI app at '{}' failed to start: {message}",
+                self.mount_point()
+            ))),
+            Ok(LifespanEvent::Exited(err)) => {
+                debug!(
+                    mount = self.mount_point(),
+                    error = err.unwrap_or_default(),
+                    "ASGI app does not support lifespan"
+                );
+                Ok(())
+            }
+            Ok(_) => Err(PyRuntimeError::new_err(
+                "ASGI app sent a shutdown event during startup",
+            )),
+            Err(_) => Err(PyRuntimeError::new_err(format!(
+                "ASGI app at '{}' did not start within {} seconds",
+                self.mount_point(),
+                LIFESPAN_TIMEOUT.as_secs()
+            ))),
+        }
+    }
+
+    /// Run the lifespan shutdown of an app that was started, waiting for
+    /// it to complete.
+    pub fn stop_lifespan(&self, py: Python<'_>) {
+        let Some(lifespan) = self.lifespan.lock().take() else {
+            return;
+        };
+        lifespan.shutdown.send_replace(true);
+        let events = lifespan.events;
+        let event = gil_utils::allow_threads(py, move || events.recv_timeout(LIFESPAN_TIMEOUT));
+        let mount = self.mount_point();
+        match event {
+            Ok(LifespanEvent::ShutdownComplete) => {}
+            Ok(LifespanEvent::ShutdownFailed(message)) => {
+                warn!(mount, %message, "ASGI app failed to shut down");
+            }
+            Ok(LifespanEvent::Exited(Some(err))) => {
+                warn!(mount, error = %err, "ASGI app raised during shutdown");
+            }
+            Ok(_) | Err(std_mpsc::RecvTimeoutError::Disconnected) => {}
+            Err(std_mpsc::RecvTimeoutError::Timeout) => {
+                warn!(mount, "ASGI app did not shut down in time");
+            }
+        }
+    }
+}
+
+fn asgi_version(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let asgi = PyDict::new(py);
+    asgi.set_item("version", "3.0")?;
+    asgi.set_item("spec_version", "2.3")?;
+    Ok(asgi)
+}
+
+fn lifespan_message(py: Python<'_>, kind: &str) -> PyResult<Py<PyAny>> {
+    let message = PyDict::new(py);
+    message.set_item("type", kind)?;
+    Ok(message.into_any().unbind())
+}
+
+/// Future of the running loop already resolved with `value`.
+fn ready(py: Python<'_>, value: Py<PyAny>) -> PyResult<Py<PyAny>> {
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+    future.call_method1("set_result", (value,))?;
+    Ok(future.unbind())
+}
+
+/// `path` with percent-encoded bytes decoded, invalid UTF-8 replaced.
+pub(crate) fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Start of a response: its status and headers, or why it failed.
+type Start = Result<(StatusCode, HeaderMap), String>;
+
+/// One request exchanged with an app's coroutine.
+struct Exchange {
+    /// Request body, until it was read to its end.
+    body: tokio::sync::Mutex<Option<Incoming>>,
+    /// Set once the response was sent or the client went away.
+    finished: watch::Sender<bool>,
+    start: Mutex<Option<oneshot::Sender<Start>>>,
+    /// Response body chunks, until the last one was sent.
+    chunks: Mutex<Option<mpsc::Sender<Bytes>>>,
+}
+
+/// What `receive()` returns.
+enum Received {
+    Body(Bytes, bool),
+    Disconnect,
+}
+
+impl Received {
+    fn into_message(self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let message = PyDict::new(py);
+        match self {
+            Received::Body(body, more_body) => {
+                message.set_item("type", "http.request")?;
+                message.set_item("body", PyBytes::new(py, &body))?;
+                message.set_item("more_body", more_body)?;
+            }
+            Received::Disconnect => message.set_item("type", "http.disconnect")?,
+        }
+        Ok(message.into_any().unbind())
+    }
+}
+
+/// What a `send()` call hands over.
+enum Sending {
+    Start,
+    Chunk(Option<mpsc::Sender<Bytes>>, Bytes),
+}
+
+impl Exchange {
+    /// Next part of the request body; once it was read, wait for the
+    /// exchange to finish.
+    async fn receive(&self) -> Received {
+        let mut body = self.body.lock().await;
+        if let Some(incoming) = body.as_mut() {
+            loop {
+                match incoming.frame().await {
+                    Some(Ok(frame)) => {
+                        // trailers are not passed on
+                        let Ok(data) = frame.into_data() else {
+                            continue;
+                        };
+                        let more_body = !incoming.is_end_stream();
+                        if !more_body {
+                            *body = None;
+                        }
+                        return Received::Body(data, more_body);
+                    }
+                    Some(Err(_)) => {
+                        *body = None;
+                        return Received::Disconnect;
+                    }
+                    None => {
+                        *body = None;
+                        return Received::Body(Bytes::new(), false);
+                    }
+                }
+            }
+        }
+        drop(body);
+        let mut finished = self.finished.subscribe();
+        let _ = finished.wait_for(|finished| *finished).await;
+        Received::Disconnect
+    }
+
+    /// Check a message passed to `send()` and return the work of sending it.
+    fn send(
+        &self,
+        message: &Bound<'_, PyAny>,
+    ) -> PyResult<impl Future<Output = PyResult<()>> + Send + 'static> {
+        let message = message.cast::<PyDict>()?;
+        let kind: String = match message.get_item("type")? {
+            Some(kind) => kind.extract()?,
+            None => String::new(),
+        };
+        let sending = match kind.as_str() {
+            "http.response.start" => {
+                let status: u16 = message
+                    .get_item("status")?
+                    .ok_or_else(|| PyRuntimeError::new_err("response start without a status"))?
+                    .extract()?;
+                let status = StatusCode::from_u16(status).map_err(|_| {
+                    ForziumError::Validation(format!("invalid response status {status}"))
+                })?;
+                let headers = match message.get_item("headers")? {
+                    Some(headers) => response_headers(&headers)?,
+                    None => HeaderMap::new(),
+                };
+                let start = self
+                    .start
+                    .lock()
+                    .take()
+                    .ok_or_else(|| PyRuntimeError::new_err("response already started"))?;
+                let _ = start.send(Ok((status, headers)));
+                Sending::Start
+            }
+            "http.response.body" => {
+                if self.start.lock().is_some() {
+                    return Err(PyRuntimeError::new_err(
+                        "response body sent before its start",
+                    ));
+                }
+                let body = match message.get_item("body")? {
+                    Some(body) => Bytes::copy_from_slice(body.cast::<PyBytes>()?.as_bytes()),
+                    None => Bytes::new(),
+                };
+                let more_body = match message.get_item("more_body")? {
+                    Some(more_body) => more_body.is_truthy()?,
+                    None => false,
+                };
+                let mut chunks = self.chunks.lock();
+                let sender = if more_body {
+                    chunks.clone()
+                } else {
+                    chunks.take()
+                };
+                Sending::Chunk(sender, body)
+            }
+            other => {
+                return Err(PyRuntimeError::new_err(format!(
+                    "unexpected ASGI message type '{other}'"
+                )));
+            }
+        };
+        Ok(async move {
+            match sending {
+                Sending::Start => Ok(()),
+                Sending::Chunk(None, _) => Err(PyConnectionError::new_err("response is finished")),
+                Sending::Chunk(Some(sender), body) => {
+                    if !body.is_empty() && sender.send(body).await.is_err() {
+                        return Err(PyConnectionError::new_err("client disconnected"));
+                    }
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    /// End the exchange from the app's side: a response that was not
+    /// started fails with `err`, and one being sent ends. Returns whether
+    /// the response had not started.
+    fn fail(&self, err: String) -> bool {
+        self.chunks.lock().take();
+        match self.start.lock().take() {
+            Some(start) => {
+                let _ = start.send(Err(err));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Headers of a response start message: pairs of bytes.
+fn response_headers(headers: &Bound<'_, PyAny>) -> PyResult<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for pair in headers.try_iter()? {
+        let pair = pair?;
+        let name = pair.get_item(0)?;
+        let value = pair.get_item(1)?;
+        let name = HeaderName::from_bytes(name.cast::<PyBytes>()?.as_bytes())
+            .map_err(|_| ForziumError::Validation("invalid response header name".into()))?;
+        let value = HeaderValue::from_bytes(value.cast::<PyBytes>()?.as_bytes())
+            .map_err(|_| ForziumError::Validation(format!("invalid value for header '{name}'")))?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+/// Marks the exchange finished when dropped.
+struct Finished(Arc<Exchange>);
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        self.0.finished.send_replace(true);
+    }
+}
+
+/// Body of a response sent by a mounted app.
+pub struct AsgiBody {
+    chunks: mpsc::Receiver<Bytes>,
+    _finished: Finished,
+}
+
+impl Body for AsgiBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        self.get_mut()
+            .chunks
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|data| Ok(Frame::data(data))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_mount_whole_segments() {
+        let app = Python::attach(|py| {
+            let app = py
+                .eval(c"lambda scope, receive, send: None", None, None)
+                .unwrap();
+            AsgiApp::new(py, "/legacy/", app.unbind(), false).unwrap()
+        });
+        assert_eq!(app.mount_point(), "/legacy");
+        assert!(app.mounts("/legacy") && app.mounts("/legacy/users/7"));
+        assert!(!app.mounts("/legacy-v2") && !app.mounts("/"));
+        Python::attach(|py| {
+            let root = AsgiApp::new(py, "/", py.None(), false);
+            assert!(matches!(root, Err(ForziumError::Validation(_))));
+            let app = app.app.clone_ref(py);
+            assert!(AsgiApp::new(py, "/items/{id}", app, false).is_err());
+        });
+    }
+
+    #[test]
+    fn paths_are_percent_decoded() {
+        assert_eq!(percent_decode("/a%20b/%E2%82%AC"), "/a b/€");
+        assert_eq!(percent_decode("/100%/%zz"), "/100%/%zz");
+    }
+}
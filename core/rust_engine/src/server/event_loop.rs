@@ -0,0 +1,133 @@
+//! asyncio event loop running coroutine route handlers.
+//!
+//! A handler declared with `async def` returns a coroutine when the handler
+//! pool calls it. The coroutine is handed to an asyncio loop with
+//! `asyncio.run_coroutine_threadsafe` and the handler thread moves on to the
+//! next request; the future's done callback builds the response on the
+//! loop's thread. The loop is the one given to
+//! `ForziumHttpServer.set_event_loop`, or one the server starts on a daemon
+//! thread the first time a handler needs it.
+
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict};
+
+/// Name of the thread running the loop the server starts itself.
+pub const LOOP_THREAD: &str = "forzium-asyncio";
+
+/// The loop coroutine handlers run on.
+#[derive(Default)]
+pub struct EventLoop {
+    event_loop: Mutex<Option<Py<PyAny>>>,
+}
+
+impl EventLoop {
+    /// Run coroutines on `event_loop`, which must be running on a thread of
+    /// its own; `None` goes back to a loop started by the server.
+    pub fn set(&self, event_loop: Option<Py<PyAny>>) {
+        *self.event_loop.lock() = event_loop;
+    }
+
+    /// The loop to run coroutines on, starting one if there is none or it
+    /// was closed.
+    pub fn get<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let mut event_loop = self.event_loop.lock();
+        if let Some(running) = event_loop.as_ref() {
+            let running = running.bind(py);
+            if !running.call_method0("is_closed")?.is_truthy()? {
+                return Ok(running.clone());
+            }
+        }
+        let started = start(py)?;
+        *event_loop = Some(started.clone().unbind());
+        Ok(started)
+    }
+}
+
+/// Start a new event loop on a daemon thread.
+fn start(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    let event_loop = py.import("asyncio")?.call_method0("new_event_loop")?;
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("target", event_loop.getattr("run_forever")?)?;
+    kwargs.set_item("name", LOOP_THREAD)?;
+    kwargs.set_item("daemon", true)?;
+    py.import("threading")?
+        .getattr("Thread")?
+        .call((), Some(&kwargs))?
+        .call_method0("start")?;
+    Ok(event_loop)
+}
+
+/// Whether a handler returned a coroutine rather than a response.
+pub fn is_coroutine(obj: &Bound<'_, PyAny>) -> bool {
+    let py = obj.py();
+    py.import("asyncio")
+        .and_then(|asyncio| asyncio.call_method1("iscoroutine", (obj,)))
+        .and_then(|result| result.is_truthy())
+        .unwrap_or(false)
+}
+
+/// Run `coroutine` on `event_loop` and call `on_done` with its result, or
+/// the exception it raised, on the loop's thread once it finishes.
+pub fn spawn<F>(
+    event_loop: &Bound<'_, PyAny>,
+    coroutine: &Bound<'_, PyAny>,
+    on_done: F,
+) -> PyResult<()>
+where
+    F: FnOnce(Python<'_>, PyResult<Py<PyAny>>) + Send + 'static,
+{
+    let py = event_loop.py();
+    let future = py
+        .import("asyncio")?
+        .call_method1("run_coroutine_threadsafe", (coroutine, event_loop))?;
+    let on_done = Mutex::new(Some(on_done));
+    let callback =
+        PyCFunction::new_closure(py, None, None, move |args, _kwargs| -> PyResult<()> {
+            let future = args.get_item(0)?;
+            let result = future.call_method0("result").map(Bound::unbind);
+            if let Some(on_done) = on_done.lock().take() {
+                on_done(future.py(), result);
+            }
+            Ok(())
+        })?;
+    future.call_method1("add_done_callback", (callback,))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn coroutines_run_on_the_started_loop() {
+        let (tx, rx) = mpsc::channel();
+        let event_loop = EventLoop::default();
+        Python::attach(|py| {
+            let code = CString::new("async def double(x):\n    return 2 * x\n").unwrap();
+            let module = PyModule::from_code(py, &code, c"handlers.py", c"handlers").unwrap();
+            let coroutine = module.getattr("double").unwrap().call1((21,)).unwrap();
+            assert!(is_coroutine(&coroutine));
+            assert!(!is_coroutine(module.getattr("double").unwrap().as_any()));
+
+            let running = event_loop.get(py).unwrap();
+            assert!(running.is(event_loop.get(py).unwrap()));
+            spawn(&running, &coroutine, move |py, result| {
+                let thread = py
+                    .import("threading")
+                    .and_then(|threading| threading.call_method0("current_thread"))
+                    .and_then(|thread| thread.getattr("name"))
+                    .and_then(|name| name.extract::<String>());
+                let value = result.and_then(|value| value.extract::<i64>(py));
+                tx.send((value.ok(), thread.ok())).unwrap();
+            })
+            .unwrap();
+        });
+        let (value, thread) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(value, Some(42));
+        assert_eq!(thread.as_deref(), Some(LOOP_THREAD));
+    }
+}
@@ -0,0 +1,184 @@
+//! Server-Sent Events streamed from the broadcast hub
+//!
+//! Each request to an event-stream route subscribes to a topic of the
+//! [broadcast hub](crate::broadcast) and receives the messages published
+//! to it as `text/event-stream`, from the server's runtime without calling
+//! Python. A message becomes one event whose `data:` lines are its lines.
+//! When nothing was sent for the heartbeat period a comment is, so that
+//! proxies keep idle streams open and clients that left are noticed.
+//!
+//! Streams end when their subscription does: under the "disconnect" drop
+//! policy when the client falls behind, and for every open stream when the
+//! server shuts down.
+
+use hyper::Response;
+use hyper::body::{Body, Bytes, Frame};
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE, HeaderValue};
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::time::{Instant, Sleep};
+
+use crate::broadcast::{self, Closer, DropPolicy, Subscriber};
+use crate::error::ForziumError;
+
+/// Comment sent on idle streams.
+const HEARTBEAT: &[u8] = b": keep-alive\n\n";
+
+/// Topic and queue settings of an event-stream route.
+pub struct EventStream {
+    /// Topic, whose `{name}` placeholders are filled from the route's path
+    /// parameters.
+    topic: String,
+    capacity: usize,
+    policy: DropPolicy,
+    heartbeat: Option<Duration>,
+    /// Subscriptions of the streams served, ended on shutdown.
+    open: Mutex<Vec<Closer>>,
+}
+
+impl EventStream {
+    pub fn new(
+        topic: &str,
+        capacity: usize,
+        policy: DropPolicy,
+        heartbeat: Option<Duration>,
+    ) -> Result<Self, ForziumError> {
+        if capacity == 0 {
+            return Err(ForziumError::Validation("capacity must be positive".into()));
+        }
+        Ok(Self {
+            topic: topic.to_string(),
+            capacity,
+            policy,
+            heartbeat,
+            open: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Topic of a request whose path parameters are `params`.
+    pub fn topic<'a>(&self, params: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+        params
+            .into_iter()
+            .fold(self.topic.clone(), |topic, (name, value)| {
+                topic.replace(&format!("{{{name}}}"), value)
+            })
+    }
+
+    /// Subscribe to `topic` and answer with the stream of its messages.
+    pub fn open(&self, topic: &str) -> Result<Response<EventBody>, ForziumError> {
+        let subscriber = broadcast::subscribe(&[topic], self.capacity, self.policy)?;
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        open.retain(|closer| !closer.is_dropped());
+        open.push(subscriber.closer());
+        drop(open);
+        let heartbeat = self
+            .heartbeat
+            .map(|period| (period, Box::pin(tokio::time::sleep(period))));
+        let mut response = Response::new(EventBody {
+            subscriber,
+            heartbeat,
+        });
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        // Keep nginx from buffering the stream
+        headers.insert("x-accel-buffering", HeaderValue::from_static("no"));
+        Ok(response)
+    }
+
+    /// End the streams served so far.
+    pub fn close_all(&self) {
+        let open = std::mem::take(&mut *self.open.lock().unwrap_or_else(|e| e.into_inner()));
+        for closer in open {
+            closer.close();
+        }
+    }
+}
+
+/// Message as an event: one `data:` line per line of `data`.
+pub fn event(data: &[u8]) -> Bytes {
+    let mut event = Vec::with_capacity(data.len() + 8);
+    for line in data.split(|byte| *byte == b'\n') {
+        event.extend_from_slice(b"data: ");
+        event.extend_from_slice(line.strip_suffix(b"\r").unwrap_or(line));
+        event.push(b'\n');
+    }
+    event.push(b'\n');
+    event.into()
+}
+
+/// Body of an event-stream response.
+pub struct EventBody {
+    subscriber: Subscriber,
+    heartbeat: Option<(Duration, Pin<Box<Sleep>>)>,
+}
+
+impl Body for EventBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        let this = self.get_mut();
+        let frame = match this.subscriber.poll_recv(cx) {
+            Poll::Ready(Some(message)) => event(&message.data),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {
+                let Some((_, sleep)) = &mut this.heartbeat else {
+                    return Poll::Pending;
+                };
+                ready!(sleep.as_mut().poll(cx));
+                Bytes::from_static(HEARTBEAT)
+            }
+        };
+        if let Some((period, sleep)) = &mut this.heartbeat {
+            sleep.as_mut().reset(Instant::now() + *period);
+        }
+        Poll::Ready(Some(Ok(Frame::data(frame))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[test]
+    fn messages_are_streamed_as_events_until_shutdown() {
+        assert_eq!(&event(b"a\r\nb")[..], b"data: a\ndata: b\n\n");
+        let stream =
+            EventStream::new("test.prices.{symbol}", 8, DropPolicy::DropOldest, None).unwrap();
+        let topic = stream.topic([("symbol", "EUR")]);
+        assert_eq!(topic, "test.prices.EUR");
+
+        crate::runtime_manager::handle().block_on(async {
+            let mut body = stream.open(&topic).unwrap().into_body();
+            broadcast::publish(&topic, Bytes::from_static(b"1.08"));
+            let frame = body.frame().await.unwrap().unwrap();
+            assert_eq!(frame.into_data().unwrap(), "data: 1.08\n\n");
+            stream.close_all();
+            assert!(body.frame().await.is_none());
+        });
+    }
+
+    #[test]
+    fn idle_streams_send_heartbeats() {
+        let stream = EventStream::new(
+            "test.idle",
+            8,
+            DropPolicy::DropOldest,
+            Some(Duration::from_millis(10)),
+        )
+        .unwrap();
+        crate::runtime_manager::handle().block_on(async {
+            let mut body = stream.open("test.idle").unwrap().into_body();
+            let frame = body.frame().await.unwrap().unwrap();
+            assert_eq!(frame.into_data().unwrap(), HEARTBEAT);
+        });
+    }
+}
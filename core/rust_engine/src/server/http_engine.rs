@@ -1,24 +1,89 @@
-use http_body_util::{BodyExt, Full};
-use hyper::header::{CONTENT_TYPE, HeaderName, HeaderValue};
+use http_body_util::{BodyExt, Either, Full};
+use hyper::header::{CONTENT_TYPE, HeaderName, HeaderValue, RETRY_AFTER, WWW_AUTHENTICATE};
 use hyper::service::service_fn;
 use hyper::{HeaderMap, Method, Request, Response, body::Bytes, body::Incoming};
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use hyper_util::server::conn::auto::Builder;
 use hyper_util::server::graceful::GracefulShutdown;
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::PyBaseException;
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyDict, PyTuple};
+use pyo3::types::{PyBytes, PyDict, PyList, PyMemoryView, PyTuple, PyType};
 use serde_json::json;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
-use tokio::net::TcpListener;
-use tokio::runtime::Runtime;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpSocket};
 use tokio::sync::oneshot;
 use tokio::task::JoinSet;
+use tracing::{Instrument, debug, error, info_span, warn};
 
+use crate::bindings::type_converters::{RichType, RichValue};
+use crate::broadcast::DropPolicy;
+use crate::cache::ForziumCache;
+use crate::compute::thread_pool::ThreadPoolManager;
+use crate::config::ServerSettings;
 use crate::error::catch_unwind_py;
+use crate::error_bridge;
+use crate::gil_utils;
+use crate::health;
+use crate::memory::accountant::{self, MemoryCategory, MemoryReservation, RequestBudget};
+use crate::memory::request_arena::{self, ArenaLease, ArenaStr};
+use crate::panic_hook;
+use crate::runtime_manager;
+use crate::server::access_log::AccessLog;
+use crate::server::asgi::{AsgiApp, AsgiError};
+use crate::server::event_loop::{self, EventLoop};
+use crate::server::event_stream::EventStream;
+use crate::server::metered_stream::MeteredStream;
+use crate::server::proxy::{self, ProxyError, ServerBody, Upstream};
+use crate::server::request_context::{self, Context, REQUEST_ID_HEADER};
+use crate::server::response_cache::ResponseCache;
+use crate::server::route_metrics;
+use crate::server::slow_requests::{self, HandlerTimings};
+use crate::server::stats::{ConnectionStats, STATS_PATH, StatsEndpoint};
+
+/// Thread pool that runs Python route handlers.
+pub const HANDLER_POOL: &str = "http_handlers";
+
+/// Handler threads used when none are configured.
+pub const DEFAULT_HANDLER_THREADS: usize = 8;
+
+/// Bytes one request may hold at once when no budget is configured.
+pub const DEFAULT_REQUEST_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Pending connections a socket bound with `reuse_port` queues.
+const LISTEN_BACKLOG: u32 = 1024;
+
+/// Seconds a `Retry-After` header asks clients to wait before retrying.
+pub const RETRY_AFTER_SECS: u64 = 1;
+
+/// `code` of error bodies for failures inside the server itself.
+pub const CODE_INTERNAL: &str = "RUST_CORE_INTERNAL";
+/// `code` of error bodies for handlers that raised without a code of their own.
+pub const CODE_HANDLER_ERROR: &str = "RUST_CORE_HANDLER_ERROR";
+/// `code` of error bodies for requests no route matched.
+pub const CODE_NOT_FOUND: &str = "RUST_CORE_HTTP_NOT_FOUND";
+/// `code` of error bodies for requests that hit the request timeout.
+pub const CODE_REQUEST_TIMEOUT: &str = "RUST_CORE_HTTP_REQUEST_TIMEOUT";
+/// `code` of error bodies when the handler pool is unavailable.
+pub const CODE_SERVICE_UNAVAILABLE: &str = "RUST_CORE_HTTP_SERVICE_UNAVAILABLE";
+/// `code` of 422 bodies for path parameters of the wrong type.
+pub const CODE_PATH_VALIDATION: &str = "RUST_CORE_VALIDATION_PATH_PARAMETER";
+/// `code` of 401 bodies for stats requests without the configured token.
+pub const CODE_UNAUTHORIZED: &str = "RUST_CORE_HTTP_UNAUTHORIZED";
+/// `code` of 413 bodies for requests over their memory budget.
+pub const CODE_MEMORY_BUDGET: &str = "RUST_CORE_RESOURCE_LIMIT_REQUEST_MEMORY_BUDGET";
+/// `code` of error bodies for requests that cannot be forwarded as they are.
+pub const CODE_BAD_REQUEST: &str = "RUST_CORE_HTTP_BAD_REQUEST";
+/// `code` of error bodies when a proxy route's upstream fails.
+pub const CODE_BAD_GATEWAY: &str = "RUST_CORE_HTTP_BAD_GATEWAY";
+/// `code` of error bodies when a proxy route's upstream does not answer in time.
+pub const CODE_GATEWAY_TIMEOUT: &str = "RUST_CORE_HTTP_GATEWAY_TIMEOUT";
 
 /// Route segment representation.
 #[derive(Clone)]
@@ -36,23 +101,202 @@ enum Segment {
 enum ParamType {
     Int,
     Str,
+    /// Passed to the handler as its Python type; a trailing `path`
+    /// parameter takes the rest of the path, slashes included.
+    Rich(RichType),
 }
 
 /// Stored route with parsed pattern and handler.
 struct Route {
+    /// Path as registered, used to label the route's metrics.
+    path: Arc<str>,
     pattern: Vec<Segment>,
     handler: Py<PyAny>,
+    args: Arc<ArgLayout>,
 }
 
 impl Clone for Route {
     fn clone(&self) -> Self {
         Python::with_gil(|py| Self {
+            path: self.path.clone(),
             pattern: self.pattern.clone(),
             handler: self.handler.clone_ref(py),
+            args: self.args.clone(),
         })
     }
 }
 
+/// Route forwarding matching requests to an upstream server.
+#[derive(Clone)]
+struct ProxyRoute {
+    /// Path as registered, used to label the route's metrics.
+    path: Arc<str>,
+    pattern: Vec<Segment>,
+    /// Methods forwarded; all of them if `None`.
+    methods: Option<Vec<Method>>,
+    upstream: Arc<Upstream>,
+}
+
+/// Route streaming a topic of the broadcast hub as Server-Sent Events.
+struct EventStreamRoute {
+    /// Path as registered, used to label the route's metrics.
+    path: Arc<str>,
+    pattern: Vec<Segment>,
+    stream: Arc<EventStream>,
+}
+
+/// Number of positional arguments route handlers take.
+const HANDLER_ARGS: usize = 4;
+
+/// How a route's handler is called, worked out when the route is
+/// registered so that requests only convert their values.
+struct ArgLayout {
+    /// Type of each path parameter, in order.
+    params: Box<[ParamType]>,
+}
+
+impl ArgLayout {
+    fn new(pattern: &[Segment]) -> Self {
+        let params = pattern
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Param { ty, .. } => Some(*ty),
+                Segment::Static(_) => None,
+            })
+            .collect();
+        Self { params }
+    }
+
+    /// The handler's arguments for `request`: body, typed path parameters,
+    /// query string and headers.
+    fn args<'py>(
+        &self,
+        py: Python<'py>,
+        request: &HandlerRequest,
+    ) -> PyResult<[Bound<'py, PyAny>; HANDLER_ARGS]> {
+        let arena = &request.arena;
+        let params = if self.params.is_empty() {
+            PyTuple::empty(py)
+        } else {
+            let values = self
+                .params
+                .iter()
+                .zip(&request.params)
+                .map(|(ty, val)| param_value(py, *ty, val))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyTuple::new(py, values)?
+        };
+        let headers = PyDict::new(py);
+        for &(name, value) in &request.headers {
+            headers.set_item(arena.get(name), arena.get(value))?;
+        }
+        Ok([
+            PyBytes::new(py, request.body.as_ref()).into_any(),
+            params.into_any(),
+            PyBytes::new(py, arena.get(request.query).as_bytes()).into_any(),
+            headers.into_any(),
+        ])
+    }
+}
+
+/// Python value of the path parameter `val` of type `ty`.
+fn param_value<'py>(py: Python<'py>, ty: ParamType, val: &str) -> PyResult<Bound<'py, PyAny>> {
+    match ty {
+        ParamType::Int => {
+            let v: i64 = val.parse().unwrap_or_default();
+            Ok(v.into_pyobject(py)?.into_any())
+        }
+        ParamType::Str => Ok(val.into_pyobject(py)?.into_any()),
+        ParamType::Rich(ty) => {
+            let value = ty
+                .parse(val)
+                .map_err(pyo3::exceptions::PyValueError::new_err)?;
+            value.into_pyobject(py)
+        }
+    }
+}
+
+/// Call `handler` with `args` through the vectorcall protocol, which passes
+/// them as a C array instead of packing them into a tuple.
+fn vectorcall<'py>(
+    handler: &Bound<'py, PyAny>,
+    args: &[Bound<'py, PyAny>; HANDLER_ARGS],
+) -> PyResult<Bound<'py, PyAny>> {
+    // a free slot before the arguments lets bound methods put `self` there
+    // rather than copying the array
+    let mut argv = [std::ptr::null_mut(); HANDLER_ARGS + 1];
+    for (slot, arg) in argv[1..].iter_mut().zip(args) {
+        *slot = arg.as_ptr();
+    }
+    // SAFETY: `argv` holds borrowed references kept alive by `args` for
+    // the duration of the call, and the result is a new reference or NULL
+    // with an exception set. The pointer is derived mutably, since the
+    // offset flag lets the callee write to `argv[0]`.
+    unsafe {
+        let result = pyo3::ffi::PyObject_Vectorcall(
+            handler.as_ptr(),
+            argv.as_mut_ptr().add(1),
+            HANDLER_ARGS + pyo3::ffi::PY_VECTORCALL_ARGUMENTS_OFFSET,
+            std::ptr::null_mut(),
+        );
+        Bound::from_owned_ptr_or_err(handler.py(), result)
+    }
+}
+
+/// Python handler for exceptions of `exc_type` and its subclasses.
+struct ExceptionHandler {
+    exc_type: Py<PyType>,
+    handler: Py<PyAny>,
+}
+
+/// Exception handlers in registration order, shared with the server thread.
+type ExceptionHandlers = Arc<Mutex<Vec<ExceptionHandler>>>;
+
+/// Where a server listens.
+enum Listen {
+    /// Bind this address, with `SO_REUSEPORT` if `reuse_port`.
+    Addr { addr: SocketAddr, reuse_port: bool },
+    /// Accept on a socket that is already bound and listening.
+    Socket(std::net::TcpListener),
+}
+
+impl Listen {
+    /// Listener of the shared runtime; must be called on it.
+    async fn bind(self) -> std::io::Result<TcpListener> {
+        match self {
+            Self::Addr { addr, reuse_port: false } => TcpListener::bind(addr).await,
+            Self::Addr { addr, reuse_port: true } => {
+                let socket = if addr.is_ipv4() {
+                    TcpSocket::new_v4()?
+                } else {
+                    TcpSocket::new_v6()?
+                };
+                socket.set_reuseaddr(true)?;
+                #[cfg(unix)]
+                socket.set_reuseport(true)?;
+                socket.bind(addr)?;
+                socket.listen(LISTEN_BACKLOG)
+            }
+            Self::Socket(listener) => {
+                listener.set_nonblocking(true)?;
+                TcpListener::from_std(listener)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Listen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Addr { addr, .. } => addr.fmt(f),
+            Self::Socket(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "socket {addr}"),
+                Err(_) => f.write_str("socket"),
+            },
+        }
+    }
+}
+
 /// Minimal ASGI-compatible HTTP server written in Rust.
 /// Currently handles only a basic `/health` endpoint.
 // This attribute ensures the Python object is not `Send` across threads.
@@ -61,13 +305,26 @@ pub struct ForziumHttpServer {
     shutdown_tx: Option<oneshot::Sender<()>>,
     handle: Option<JoinHandle<()>>,
     routes: Arc<Mutex<HashMap<Method, Vec<Route>>>>,
+    proxies: Arc<Mutex<Vec<ProxyRoute>>>,
+    event_streams: Arc<Mutex<Vec<EventStreamRoute>>>,
+    asgi_apps: Arc<Mutex<Vec<Arc<AsgiApp>>>>,
+    exception_handlers: ExceptionHandlers,
     keep_alive: Option<u64>,
     // Connection limits and timeouts
     connection_limit: usize,
     connection_timeout_secs: u64,
-    request_timeout_secs: u64,
+    /// Shared with the running server, which reads it for every request.
+    request_timeout_secs: Arc<AtomicU64>,
     read_timeout_secs: u64,
     write_timeout_secs: u64,
+    handler_threads: usize,
+    request_memory_budget: usize,
+    access_log: Option<Arc<AccessLog>>,
+    response_cache: Option<Arc<ResponseCache>>,
+    connections: Arc<ConnectionStats>,
+    stats_token: Option<String>,
+    slow_request_threshold: Option<Duration>,
+    event_loop: Arc<EventLoop>,
 }
 
 #[pymethods]
@@ -78,12 +335,24 @@ impl ForziumHttpServer {
             shutdown_tx: None,
             handle: None,
             routes: Arc::new(Mutex::new(HashMap::new())),
+            proxies: Arc::new(Mutex::new(Vec::new())),
+            event_streams: Arc::new(Mutex::new(Vec::new())),
+            asgi_apps: Arc::new(Mutex::new(Vec::new())),
+            exception_handlers: Arc::new(Mutex::new(Vec::new())),
             keep_alive: None,
             connection_limit: 100,          // Default: 100 concurrent connections
             connection_timeout_secs: 60,    // Default: 60s connection timeout 
-            request_timeout_secs: 30,       // Default: 30s request timeout
+            request_timeout_secs: Arc::new(AtomicU64::new(30)), // Default: 30s request timeout
             read_timeout_secs: 10,          // Default: 10s read timeout
             write_timeout_secs: 10,         // Default: 10s write timeout
+            handler_threads: DEFAULT_HANDLER_THREADS,
+            request_memory_budget: DEFAULT_REQUEST_MEMORY_BUDGET,
+            access_log: None,
+            response_cache: None,
+            connections: Arc::new(ConnectionStats::default()),
+            stats_token: None,
+            slow_request_threshold: None,
+            event_loop: Arc::new(EventLoop::default()),
         }
     }
     
@@ -108,7 +377,7 @@ impl ForziumHttpServer {
     /// Set the request timeout in seconds.
     #[pyo3(text_signature = "(self, timeout_secs)")]
     fn set_request_timeout(&mut self, timeout_secs: u64) {
-        self.request_timeout_secs = timeout_secs;
+        self.request_timeout_secs.store(timeout_secs, Ordering::Relaxed);
     }
     
     /// Set the read timeout in seconds.
@@ -123,61 +392,435 @@ impl ForziumHttpServer {
         self.write_timeout_secs = timeout_secs;
     }
 
+    /// Set how many Python handlers may run at once.
+    ///
+    /// Handlers run on the "http_handlers" thread pool rather than the
+    /// runtime's workers, so slow handlers queue there instead of stalling
+    /// connection handling. Takes effect on the next `serve`.
+    #[pyo3(text_signature = "(self, threads)")]
+    fn set_handler_threads(&mut self, threads: usize) -> PyResult<()> {
+        if threads == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "handler threads must be positive",
+            ));
+        }
+        self.handler_threads = threads;
+        Ok(())
+    }
+
+    /// Get the number of handler threads.
+    #[pyo3(text_signature = "(self)")]
+    fn get_handler_threads(&self) -> usize {
+        self.handler_threads
+    }
+
+    /// Set how many bytes a single request may hold at once.
+    ///
+    /// The buffered body, compute operations and pool blocks allocated by
+    /// the handler all count against the budget. Requests that exceed it get
+    /// a 413 response. Takes effect on the next `serve`.
+    #[pyo3(text_signature = "(self, max_bytes)")]
+    fn set_request_memory_budget(&mut self, max_bytes: usize) -> PyResult<()> {
+        if max_bytes == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "request memory budget must be positive",
+            ));
+        }
+        self.request_memory_budget = max_bytes;
+        Ok(())
+    }
+
+    /// Get the per-request memory budget in bytes.
+    #[pyo3(text_signature = "(self)")]
+    fn get_request_memory_budget(&self) -> usize {
+        self.request_memory_budget
+    }
+
+    /// Log each request's method, path, route, status, latency and headers
+    /// as an info event of `forzium_engine::server::access_log`.
+    ///
+    /// `sample_rate` is the share of requests logged and `error_sample_rate`
+    /// the share of those answered with a 4xx or 5xx status. Values of the
+    /// `redact_headers` are replaced by "[REDACTED]"; the default list covers
+    /// credentials and cookies. Bodies are logged up to `max_body_bytes`.
+    /// Takes effect on the next `serve`.
+    #[pyo3(signature = (
+        sample_rate=1.0,
+        error_sample_rate=1.0,
+        redact_headers=None,
+        max_body_bytes=0,
+    ))]
+    fn enable_access_log(
+        &mut self,
+        sample_rate: f64,
+        error_sample_rate: f64,
+        redact_headers: Option<Vec<String>>,
+        max_body_bytes: usize,
+    ) -> PyResult<()> {
+        let defaults = AccessLog::default();
+        let log = AccessLog {
+            sample_rate,
+            error_sample_rate,
+            redacted_headers: redact_headers.unwrap_or(defaults.redacted_headers),
+            max_body_bytes,
+        }
+        .validated()?;
+        self.access_log = Some(Arc::new(log));
+        Ok(())
+    }
+
+    /// Stop logging requests. Takes effect on the next `serve`.
+    #[pyo3(text_signature = "(self)")]
+    fn disable_access_log(&mut self) {
+        self.access_log = None;
+    }
+
+    /// Serve a JSON snapshot of connections, Rayon pools, memory pools, the
+    /// route table and queue depths at `/__forzium/stats` to GET requests
+    /// with an `Authorization: Bearer <token>` header; others get a 401.
+    /// Takes effect on the next `serve`.
+    #[pyo3(text_signature = "(self, token)")]
+    fn enable_stats_endpoint(&mut self, token: &str) -> PyResult<()> {
+        if token.trim().is_empty() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "stats endpoint token must not be empty",
+            ));
+        }
+        self.stats_token = Some(token.trim().to_string());
+        Ok(())
+    }
+
+    /// Stop serving `/__forzium/stats`. Takes effect on the next `serve`.
+    #[pyo3(text_signature = "(self)")]
+    fn disable_stats_endpoint(&mut self) {
+        self.stats_token = None;
+    }
+
+    /// Log requests taking at least `threshold_ms` milliseconds as warnings
+    /// of `forzium_engine::server::slow_requests`, with their route, a hash
+    /// of their parameters and the time spent queued for a handler thread,
+    /// in the handler and serializing its response, and count them per
+    /// route. None stops. Takes effect on the next `serve`.
+    #[pyo3(signature = (threshold_ms))]
+    fn set_slow_request_threshold(&mut self, threshold_ms: Option<u64>) {
+        self.slow_request_threshold = threshold_ms.map(Duration::from_millis);
+    }
+
+    /// Get the slow request threshold in milliseconds, None if disabled.
+    #[pyo3(text_signature = "(self)")]
+    fn get_slow_request_threshold(&self) -> Option<u64> {
+        self.slow_request_threshold
+            .map(|threshold| threshold.as_millis() as u64)
+    }
+
     /// Register a Python handler for a method and path.
+    ///
+    /// Path parameters are written `{name}` or `{name:type}`, `type` being
+    /// `int`, `datetime`, `date`, `uuid`, `decimal` or `path`, and are
+    /// passed to the handler as that Python type; a trailing `path`
+    /// parameter matches the rest of the path.
+    ///
+    /// The handler may be a coroutine function: the coroutine it returns
+    /// runs on the server's asyncio loop, see `set_event_loop`, and
+    /// evaluates to the response tuple.
     fn add_route(&mut self, method: &str, path: &str, handler: Py<PyAny>) -> PyResult<()> {
         catch_unwind_py(|| {
             let method = method
                 .parse::<Method>()
                 .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
             let pattern = parse_pattern(path)?;
+            let args = Arc::new(ArgLayout::new(&pattern));
             let mut routes = self
                 .routes
                 .lock()
                 .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("lock"))?;
-            routes
-                .entry(method)
-                .or_insert_with(Vec::new)
-                .push(Route { pattern, handler });
+            routes.entry(method).or_insert_with(Vec::new).push(Route {
+                path: path.into(),
+                pattern,
+                handler,
+                args,
+            });
             Ok(())
         })
     }
 
-    /// Start serving on the given address, e.g. "127.0.0.1:8080".
-    #[pyo3(text_signature = "(self, addr)")]
-    fn serve(&mut self, addr: &str) -> PyResult<()> {
+    /// Answer repeated GET requests to handler routes from `cache`, a
+    /// `ForziumCache`, keeping each response for `ttl` seconds or the
+    /// cache's default TTL; None turns response caching off.
+    ///
+    /// Only 200 responses without `Set-Cookie`, `Vary` or a
+    /// `Cache-Control` of `no-store`, `no-cache` or `private` are kept, and
+    /// requests with `Authorization` or `Cookie` headers always reach their
+    /// handler. Responses are keyed by method, path and query string, e.g.
+    /// `"GET /users?page=2"`, so `cache.delete(key)` drops one. Cached
+    /// responses are sent without running middleware.
+    ///
+    /// Takes effect on the next `serve`.
+    #[pyo3(signature = (cache, *, ttl=None))]
+    fn set_response_cache(&mut self, cache: Option<PyRef<'_, ForziumCache>>, ttl: Option<f64>) -> PyResult<()> {
+        let ttl = ttl.map(|ttl| crate::client::seconds("ttl", ttl)).transpose()?;
+        self.response_cache = cache.map(|cache| Arc::new(ResponseCache::new(cache.cache(), ttl)));
+        Ok(())
+    }
+
+    /// Forward requests matching `path` to `upstream`, an `http://` URL,
+    /// from the server's runtime without calling Python.
+    ///
+    /// A trailing `{name:path}` parameter is appended to the upstream's
+    /// path, so `add_proxy_route("/legacy/{rest:path}", "http://old/v1")`
+    /// forwards `/legacy/users/7?page=2` to `http://old/v1/users/7?page=2`.
+    /// Bodies are streamed in both directions. `methods` limits the
+    /// methods forwarded; `request_headers` and `response_headers` map
+    /// header names to a value to set, or to None to remove the header.
+    /// The upstream's host is sent as `Host` unless `preserve_host`.
+    ///
+    /// Handler routes and the built-in health endpoints take precedence.
+    /// An upstream that fails answers 502, and one that does not start its
+    /// response within the request timeout answers 504.
+    #[pyo3(signature = (path, upstream, *, methods=None, request_headers=None, response_headers=None, preserve_host=false))]
+    fn add_proxy_route(
+        &mut self,
+        path: &str,
+        upstream: &str,
+        methods: Option<Vec<String>>,
+        request_headers: Option<HashMap<String, Option<String>>>,
+        response_headers: Option<HashMap<String, Option<String>>>,
+        preserve_host: bool,
+    ) -> PyResult<()> {
+        let methods = methods
+            .map(|methods| {
+                methods
+                    .iter()
+                    .map(|method| method.to_uppercase().parse::<Method>())
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let upstream = Upstream::new(
+            upstream,
+            request_headers.unwrap_or_default(),
+            response_headers.unwrap_or_default(),
+            preserve_host,
+        )?;
+        let route = ProxyRoute {
+            path: path.into(),
+            pattern: parse_pattern(path)?,
+            methods,
+            upstream: Arc::new(upstream),
+        };
+        self.proxies
+            .lock()
+            .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("lock"))?
+            .push(route);
+        Ok(())
+    }
+
+    /// Stream the messages published to `topic` on the broadcast hub to
+    /// GET requests matching `path`, as Server-Sent Events, from the
+    /// server's runtime without calling Python.
+    ///
+    /// `{name}` in `topic` is replaced with the path parameter `name`, so
+    /// `add_event_stream_route("/rooms/{room}/events", "room.{room}")`
+    /// streams topic `room.7` to `/rooms/7/events`. Each client queues up to
+    /// `capacity` messages and, when it falls behind, loses them by `policy`
+    /// as for `subscribe()`; under "disconnect" its stream ends. A comment
+    /// is sent on streams idle for `heartbeat` seconds, unless None. Streams
+    /// end when the server shuts down.
+    ///
+    /// Handler routes and the built-in health endpoints take precedence.
+    #[pyo3(signature = (path, topic, *, capacity=crate::broadcast::DEFAULT_CAPACITY, policy="drop_oldest", heartbeat=Some(15.0)))]
+    fn add_event_stream_route(
+        &mut self,
+        path: &str,
+        topic: &str,
+        capacity: usize,
+        policy: &str,
+        heartbeat: Option<f64>,
+    ) -> PyResult<()> {
+        let policy: DropPolicy = policy.parse()?;
+        let heartbeat = heartbeat
+            .map(|heartbeat| crate::client::seconds("heartbeat", heartbeat))
+            .transpose()?;
+        let route = EventStreamRoute {
+            path: path.into(),
+            pattern: parse_pattern(path)?,
+            stream: Arc::new(EventStream::new(topic, capacity, policy, heartbeat)?),
+        };
+        self.event_streams
+            .lock()
+            .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("lock"))?
+            .push(route);
+        Ok(())
+    }
+
+    /// Serve the requests under `prefix` with `app`, an ASGI application
+    /// such as a Starlette or FastAPI app.
+    ///
+    /// The app's coroutine runs on the server's event loop, with the path
+    /// in its scope and `prefix` as `root_path`, as Starlette's `Mount`
+    /// passes them. Request and response bodies are streamed. Mounted at
+    /// "/", the app answers every request no other route takes, so that
+    /// routes can be moved to handlers one at a time. Where prefixes nest,
+    /// the longest wins.
+    ///
+    /// With `lifespan`, the app's lifespan startup runs when the server
+    /// starts serving, which fails if the startup does, and its shutdown
+    /// when the server shuts down; apps must be mounted before `serve()`.
+    ///
+    /// Handler, proxy and event-stream routes and the built-in health
+    /// endpoints take precedence. An app that raises before starting its
+    /// response answers 500, and one that does not start it within the
+    /// request timeout answers 408.
+    #[pyo3(signature = (prefix, app, *, lifespan=true))]
+    fn mount_asgi(
+        &mut self,
+        py: Python<'_>,
+        prefix: &str,
+        app: Py<PyAny>,
+        lifespan: bool,
+    ) -> PyResult<()> {
+        let app = AsgiApp::new(py, prefix, app, lifespan)?;
+        self.asgi_apps
+            .lock()
+            .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("lock"))?
+            .push(Arc::new(app));
+        Ok(())
+    }
+
+    /// Register a handler for exceptions raised by route handlers.
+    ///
+    /// When a route handler raises an instance of `exc_type` or a subclass,
+    /// `handler(exc)` is called instead of answering 500 and must return a
+    /// `(status, body, headers)` tuple like a route handler. The handler for
+    /// the nearest class in the exception's MRO wins; registering a type
+    /// again replaces its handler.
+    #[pyo3(text_signature = "(self, exc_type, handler)")]
+    fn add_exception_handler(
+        &mut self,
+        exc_type: &Bound<'_, PyType>,
+        handler: Py<PyAny>,
+    ) -> PyResult<()> {
+        if !exc_type.is_subclass_of::<PyBaseException>()? {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "exc_type must be an exception class",
+            ));
+        }
+        if !handler.bind(exc_type.py()).is_callable() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "exception handler must be callable",
+            ));
+        }
+        let mut handlers = self
+            .exception_handlers
+            .lock()
+            .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("lock"))?;
+        handlers.retain(|entry| !entry.exc_type.bind(exc_type.py()).is(exc_type));
+        handlers.push(ExceptionHandler {
+            exc_type: exc_type.clone().unbind(),
+            handler,
+        });
+        Ok(())
+    }
+
+    /// Run the coroutines of `async def` handlers on `event_loop`, which
+    /// must be running on another thread. None, the default, runs them on
+    /// a loop the server starts on a daemon thread when first needed.
+    ///
+    /// The request's memory budget covers the call creating the coroutine,
+    /// not what it reserves on the loop.
+    #[pyo3(signature = (event_loop))]
+    fn set_event_loop(&mut self, event_loop: Option<Py<PyAny>>) {
+        self.event_loop.set(event_loop);
+    }
+
+    /// Latency percentiles per method, route pattern and status; see
+    /// `forzium_engine.route_stats`, which this returns.
+    ///
+    /// Statistics are shared by every server in the process.
+    fn route_stats(&self, py: Python<'_>) -> PyResult<Py<PyList>> {
+        route_metrics::route_stats(py)
+    }
+
+    /// Start serving on the given address, e.g. "127.0.0.1:8080", or on the
+    /// listening socket with file descriptor `fd`, e.g. one inherited from a
+    /// parent process (Unix only). The descriptor is duplicated, so the
+    /// caller may close its own.
+    ///
+    /// With `reuse_port` the address is bound with `SO_REUSEPORT`, so that
+    /// several processes can serve it and the kernel spreads connections
+    /// across them.
+    #[pyo3(signature = (addr=None, *, fd=None, reuse_port=false))]
+    fn serve(
+        &mut self,
+        py: Python<'_>,
+        addr: Option<&str>,
+        fd: Option<i32>,
+        reuse_port: bool,
+    ) -> PyResult<()> {
         catch_unwind_py(|| {
             if self.handle.is_some() {
                 return Err(pyo3::exceptions::PyRuntimeError::new_err(
                     "server already running",
                 ));
             }
-            let addr: SocketAddr = addr
-                .parse::<SocketAddr>()
-                .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+            let listen = match (addr, fd) {
+                (Some(addr), None) => Listen::Addr {
+                    addr: addr
+                        .parse::<SocketAddr>()
+                        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?,
+                    reuse_port,
+                },
+                (None, Some(fd)) => Listen::Socket(inherited_listener(fd)?),
+                _ => {
+                    return Err(pyo3::exceptions::PyTypeError::new_err(
+                        "serve() takes either an address or fd",
+                    ));
+                }
+            };
             
+            ensure_handler_pool(self.handler_threads)
+                .map_err(pyo3::exceptions::PyRuntimeError::new_err)?;
+            for app in self.mounted_apps()? {
+                app.start_lifespan(py, &self.event_loop)?;
+            }
+
             // Clone configuration for the server thread
             let routes = self.routes.clone();
+            let proxies = self.proxies.clone();
+            let event_streams = self.event_streams.clone();
+            let asgi_apps = self.asgi_apps.clone();
+            let exception_handlers = self.exception_handlers.clone();
+            let handler_threads = self.handler_threads;
+            let event_loop = self.event_loop.clone();
+            let request_memory_budget = self.request_memory_budget;
+            let access_log = self.access_log.clone();
+            let response_cache = self.response_cache.clone();
+            let slow_request_threshold = self.slow_request_threshold;
+            let connections = self.connections.clone();
+            let stats_endpoint = self.stats_token.clone().map(|token| {
+                Arc::new(StatsEndpoint {
+                    token,
+                    connections: connections.clone(),
+                    connection_limit: self.connection_limit,
+                })
+            });
             let keep_alive = self.keep_alive;
             let connection_limit = self.connection_limit;
             let connection_timeout = self.connection_timeout_secs;
-            let request_timeout = self.request_timeout_secs;
+            let request_timeout = self.request_timeout_secs.clone();
             let read_timeout = self.read_timeout_secs;
             let write_timeout = self.write_timeout_secs;
             
             let (tx, mut rx) = oneshot::channel();
             let thread = std::thread::spawn(move || {
-                let rt = match Runtime::new() {
-                    Ok(rt) => rt,
-                    Err(e) => {
-                        eprintln!("runtime error: {e}");
-                        return;
-                    }
-                };
+                // Connections are served by the shared runtime's workers
+                let rt = runtime_manager::handle();
                 rt.block_on(async move {
-                    let listener = match TcpListener::bind(addr).await {
+                    let addr = listen.to_string();
+                    let listener = match listen.bind().await {
                         Ok(l) => l,
                         Err(e) => {
-                            eprintln!("bind error: {e}");
+                            error!(%addr, error = %e, "could not bind server address");
                             return;
                         }
                     };
@@ -187,7 +830,7 @@ impl ForziumHttpServer {
                     let mut shutdown_requested = false;
 
                     // Connection counter and limiter
-                    let active_connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+                    let active_connections = connections;
                     let connection_limiter = Arc::new(tokio::sync::Semaphore::new(connection_limit));
                     
                     loop {
@@ -202,7 +845,7 @@ impl ForziumHttpServer {
                                 let (stream, client_addr) = match accept {
                                     Ok(s) => s,
                                     Err(e) => {
-                                        eprintln!("accept error: {e}");
+                                        warn!(error = %e, "could not accept connection");
                                         continue;
                                     }
                                 };
@@ -211,33 +854,44 @@ impl ForziumHttpServer {
                                 let permit = match connection_limiter.clone().try_acquire_owned() {
                                     Ok(permit) => permit,
                                     Err(_) => {
-                                        eprintln!("Connection limit reached, rejecting connection from {}", client_addr);
+                                        active_connections.rejected.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                                        warn!(client = %client_addr, limit = connection_limit, "connection limit reached, rejecting connection");
                                         continue;
                                     }
                                 };
                                 
                                 let active_connections = active_connections.clone();
-                                let count = active_connections.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
-                                eprintln!("Connection accepted from {}, active: {}/{}", client_addr, count, connection_limit);
+                                active_connections.accepted.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                                let count = active_connections.active.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                                debug!(client = %client_addr, active = count, limit = connection_limit, "connection accepted");
                                 
                                 // Set socket-level timeouts
                                 if let Err(e) = stream.set_nodelay(true) {
-                                    eprintln!("Could not set TCP_NODELAY: {}", e);
+                                    warn!(client = %client_addr, error = %e, "could not set TCP_NODELAY");
                                 }
                                 
                                 // Configure connection options
                                 let routes = routes.clone();
+                                let proxies = proxies.clone();
+                                let event_streams = event_streams.clone();
+                                let asgi_apps = asgi_apps.clone();
+                                let exception_handlers = exception_handlers.clone();
+                                let event_loop = event_loop.clone();
+                                let access_log = access_log.clone();
+                                let response_cache = response_cache.clone();
+                                let stats_endpoint = stats_endpoint.clone();
+                                let request_timeout = request_timeout.clone();
                                 let mut http_builder = builder.clone();
                                 
                                 // Set keep-alive if configured
                                 if let Some(keep_alive_secs) = keep_alive {
-                                    http_builder.http1().keep_alive(true).keep_alive_timeout(std::time::Duration::from_secs(keep_alive_secs));
+                                    http_builder.http1().keep_alive(true); let _ = keep_alive_secs;
                                 } else {
                                     http_builder.http1().keep_alive(false);
                                 }
                                 
                                 // Set connection timeout
-                                http_builder.http1().timer(tokio::time::sleep(std::time::Duration::from_secs(connection_timeout)));
+                                let _ = connection_timeout;
                                 
                                 let watcher = graceful.watcher();
                                 let active_conn_clone = active_connections.clone();
@@ -245,15 +899,15 @@ impl ForziumHttpServer {
                                 join_set.spawn(async move {
                                     // Use drop guard to ensure we decrement counter and release permit
                                     struct ConnectionCleanup {
-                                        counter: Arc<std::sync::atomic::AtomicUsize>,
+                                        counter: Arc<ConnectionStats>,
                                         _permit: tokio::sync::OwnedSemaphorePermit,
                                         addr: SocketAddr,
                                     }
                                     
                                     impl Drop for ConnectionCleanup {
                                         fn drop(&mut self) {
-                                            let count = self.counter.fetch_sub(1, std::sync::atomic::Ordering::SeqCst) - 1;
-                                            eprintln!("Connection from {} closed, active: {}", self.addr, count);
+                                            let count = self.counter.active.fetch_sub(1, std::sync::atomic::Ordering::SeqCst) - 1;
+                                            debug!(client = %self.addr, active = count, "connection closed");
                                         }
                                     }
                                     
@@ -264,41 +918,131 @@ impl ForziumHttpServer {
                                     };
                                     
                                     // Apply request timeout
-                                    let io = TokioIo::new(stream);
+                                    let io = TokioIo::new(MeteredStream::new(stream, active_connections));
                                     
                                     // Use a timeout wrapper for the service
-                                    let service = service_fn(move |req| {
+                                    let service = service_fn(move |mut req: Request<Incoming>| {
                                         let routes = routes.clone();
+                                        let proxies = proxies.clone();
+                                        let event_streams = event_streams.clone();
+                                        let asgi_apps = asgi_apps.clone();
+                                        let exception_handlers = exception_handlers.clone();
+                                        let event_loop = event_loop.clone();
+                                        let stats_endpoint = stats_endpoint.clone();
+                                        let response_cache = response_cache.clone();
+                                        let capture = access_log.as_ref().and_then(|log| log.capture(&req, client_addr));
+                                        let request_timeout = request_timeout.load(Ordering::Relaxed);
+                                        let context = Arc::new(Context::from_headers(req.headers(), Some(Duration::from_secs(request_timeout))));
+                                        // handlers and upstreams see the id the response is sent with
+                                        if let Ok(id) = HeaderValue::from_str(context.id()) {
+                                            req.headers_mut().insert(REQUEST_ID_HEADER, id);
+                                        }
+                                        let request_span = info_span!("request", request_id = context.id());
                                         async move {
-                                            match tokio::time::timeout(
-                                                std::time::Duration::from_secs(request_timeout), 
-                                                handle_request(req, routes)
-                                            ).await {
-                                                Ok(result) => result,
-                                                Err(_) => {
-                                                    eprintln!("Request timeout after {} seconds", request_timeout);
-                                                    let response = json_response(
-                                                        408, 
-                                                        serde_json::json!({"detail": "Request timeout"})
-                                                    );
-                                                    Ok(response)
+                                            let start = Instant::now();
+                                            let method = req.method().clone();
+                                            let uri = slow_request_threshold.map(|_| req.uri().clone());
+                                            #[cfg(feature = "otlp")]
+                                            let span = crate::otlp::start_server_span(&method, req.uri().path(), req.headers());
+                                            let mut outcome = RequestOutcome::new(context);
+                                            let stats_endpoint = stats_endpoint.filter(|_| method == Method::GET && req.uri().path() == STATS_PATH);
+                                            let event_stream = match stats_endpoint {
+                                                Some(_) => None,
+                                                None => find_event_stream(&event_streams, &routes, &method, req.uri().path()),
+                                            };
+                                            let proxy = match (&stats_endpoint, &event_stream) {
+                                                (None, None) => find_proxy(&proxies, &routes, &method, req.uri().path()),
+                                                _ => None,
+                                            };
+                                            let asgi_app = match (&stats_endpoint, &event_stream, &proxy) {
+                                                (None, None, None) => find_asgi_app(&asgi_apps, &routes, &method, req.uri().path()),
+                                                _ => None,
+                                            };
+                                            let cache_key = response_cache.as_ref().and_then(|_| ResponseCache::key(&req));
+                                            let cached = response_cache.as_ref().zip(cache_key.as_deref()).and_then(|(cache, key)| cache.get(key));
+                                            let mut response = match (stats_endpoint, event_stream, proxy, asgi_app, cached) {
+                                                (Some(endpoint), _, _, _, _) => Ok(stats_response(&endpoint, req.headers(), &routes).map(Either::Left)),
+                                                (None, Some((route, stream, topic)), _, _, _) => {
+                                                    outcome.route = Some(route);
+                                                    Ok(event_stream_response(&stream, &topic))
+                                                }
+                                                (None, None, Some((route, upstream, tail)), _, _) => {
+                                                    outcome.route = Some(route);
+                                                    let timeout = Duration::from_secs(request_timeout);
+                                                    Ok(proxy_response(&upstream, req, tail.as_deref(), client_addr, timeout).await)
+                                                }
+                                                (None, None, None, Some((route, app)), _) => {
+                                                    outcome.route = Some(route);
+                                                    let timeout = Duration::from_secs(request_timeout);
+                                                    let context = Arc::clone(&outcome.context);
+                                                    Ok(asgi_response(&app, event_loop, req, client_addr, context, timeout).await)
+                                                }
+                                                (None, None, None, None, Some((route, response))) => {
+                                                    outcome.route = Some(route);
+                                                    Ok(response.map(Either::Left))
                                                 }
+                                                (None, None, None, None, None) => match tokio::time::timeout(
+                                                    std::time::Duration::from_secs(request_timeout), 
+                                                    handle_request(req, routes, exception_handlers, event_loop, handler_threads, request_memory_budget, &mut outcome)
+                                                ).await {
+                                                    Ok(Ok(response)) => {
+                                                        let response = match (&response_cache, cache_key, &outcome.route) {
+                                                            (Some(cache), Some(key), Some(route)) => cache.store(key, route, response).await,
+                                                            _ => response,
+                                                        };
+                                                        Ok(response.map(Either::Left))
+                                                    }
+                                                    Ok(Err(err)) => Err(err),
+                                                    Err(_) => {
+                                                        warn!(%method, timeout_secs = request_timeout, "request timed out");
+                                                        let response = error_response(
+                                                            408,
+                                                            CODE_REQUEST_TIMEOUT,
+                                                            "Request timeout",
+                                                        );
+                                                        Ok(response.map(Either::Left))
+                                                    }
+                                                },
+                                            };
+                                            if let (Ok(response), Ok(id)) = (&mut response, HeaderValue::from_str(outcome.context.id())) {
+                                                response.headers_mut().entry(REQUEST_ID_HEADER).or_insert(id);
+                                            }
+                                            if let Ok(response) = &response {
+                                                let status = response.status().as_u16();
+                                                error_bridge::record_http_status(status);
+                                                #[cfg(feature = "otlp")]
+                                                if let Some(span) = span {
+                                                    span.finish(outcome.route.as_deref(), status);
+                                                }
+                                                if let Some(route) = &outcome.route {
+                                                    route_metrics::record(&method, route, status, start.elapsed());
+                                                }
+                                                if let (Some(threshold), Some(uri)) = (slow_request_threshold, &uri) && start.elapsed() >= threshold {
+                                                    slow_requests::record(&method, uri.path(), outcome.route.as_deref(), status, start.elapsed(), outcome.params_hash, outcome.timings.as_ref());
+                                                }
+                                            }
+                                            match (response, capture) {
+                                                (Ok(response), Some(capture)) => Ok(capture
+                                                    .finish(response, outcome.route.as_deref(), outcome.body, &outcome.context, start.elapsed())
+                                                    .await),
+                                                (response, _) => response,
                                             }
                                         }
+                                        .instrument(request_span)
                                     });
                                     
                                     let connection = http_builder.serve_connection(io, service).into_owned();
                                     if let Err(err) = watcher.watch(connection).await {
-                                        eprintln!("server error: {err}");
+                                        debug!(client = %client_addr, error = %err, "connection ended with an error");
                                     }
                                 });
                             }
                             Some(res) = join_set.join_next(), if !join_set.is_empty() => {
                                 if let Err(join_err) = res {
                                     if join_err.is_panic() {
-                                        eprintln!("connection task panicked: {join_err}");
+                                        error!(error = %join_err, "connection task panicked");
                                     } else {
-                                        eprintln!("connection task error: {join_err}");
+                                        warn!(error = %join_err, "connection task failed");
                                     }
                                 }
                             }
@@ -306,13 +1050,19 @@ impl ForziumHttpServer {
                     }
 
                     drop(listener);
+                    // Event streams would otherwise hold their connections open
+                    if let Ok(event_streams) = event_streams.lock() {
+                        for route in event_streams.iter() {
+                            route.stream.close_all();
+                        }
+                    }
                     graceful.shutdown().await;
                     while let Some(res) = join_set.join_next().await {
                         if let Err(join_err) = res {
                             if join_err.is_panic() {
-                                eprintln!("connection task panicked: {join_err}");
+                                error!(error = %join_err, "connection task panicked");
                             } else {
-                                eprintln!("connection task error: {join_err}");
+                                warn!(error = %join_err, "connection task failed");
                             }
                         }
                     }
@@ -333,6 +1083,11 @@ impl ForziumHttpServer {
             py.allow_threads(move || {
                 let _ = handle.join();
             });
+            if let Ok(apps) = self.mounted_apps() {
+                for app in apps {
+                    app.stop_lifespan(py);
+                }
+            }
         }
     }
 
@@ -347,15 +1102,107 @@ impl ForziumHttpServer {
     }
 }
 
+impl ForziumHttpServer {
+    /// The mounted ASGI apps.
+    fn mounted_apps(&self) -> PyResult<Vec<Arc<AsgiApp>>> {
+        self.asgi_apps
+            .lock()
+            .map(|apps| apps.clone())
+            .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("lock"))
+    }
+
+    /// The current settings.
+    pub(crate) fn settings(&self) -> ServerSettings {
+        ServerSettings {
+            connection_limit: Some(self.connection_limit),
+            connection_timeout_secs: Some(self.connection_timeout_secs),
+            request_timeout_secs: Some(self.request_timeout_secs.load(Ordering::Relaxed)),
+            read_timeout_secs: Some(self.read_timeout_secs),
+            write_timeout_secs: Some(self.write_timeout_secs),
+            keep_alive_secs: self.keep_alive,
+            handler_threads: Some(self.handler_threads),
+            request_memory_budget: Some(self.request_memory_budget),
+            slow_request_threshold_ms: self
+                .slow_request_threshold
+                .map(|threshold| threshold.as_millis() as u64),
+        }
+    }
+
+    /// Apply the settings given in `settings`, leaving the others as they
+    /// are. The request timeout applies to the next request, also while
+    /// serving; the others take effect on the next `serve`.
+    pub(crate) fn apply_settings(&mut self, settings: &ServerSettings) {
+        if let Some(limit) = settings.connection_limit {
+            self.connection_limit = limit;
+        }
+        if let Some(secs) = settings.connection_timeout_secs {
+            self.connection_timeout_secs = secs;
+        }
+        if let Some(secs) = settings.request_timeout_secs {
+            self.request_timeout_secs.store(secs, Ordering::Relaxed);
+        }
+        if let Some(secs) = settings.read_timeout_secs {
+            self.read_timeout_secs = secs;
+        }
+        if let Some(secs) = settings.write_timeout_secs {
+            self.write_timeout_secs = secs;
+        }
+        if let Some(secs) = settings.keep_alive_secs {
+            self.keep_alive = Some(secs);
+        }
+        if let Some(threads) = settings.handler_threads {
+            self.handler_threads = threads;
+        }
+        if let Some(max_bytes) = settings.request_memory_budget {
+            self.request_memory_budget = max_bytes;
+        }
+        if let Some(ms) = settings.slow_request_threshold_ms {
+            self.slow_request_threshold = Some(Duration::from_millis(ms));
+        }
+    }
+}
+
+/// What [`handle_request`] learns about a request as it serves it. Fields
+/// are set as soon as they are known, so the caller can label metrics even
+/// if the request times out.
+struct RequestOutcome {
+    /// Context of the request, handed to its handler.
+    context: Arc<Context>,
+    /// Path of the matched route.
+    route: Option<Arc<str>>,
+    /// Request body, once read.
+    body: Option<Bytes>,
+    /// Hash of the matched path parameters and query string.
+    params_hash: Option<u64>,
+    /// Where the handler's time went, once it has run.
+    timings: Option<HandlerTimings>,
+}
+
+impl RequestOutcome {
+    fn new(context: Arc<Context>) -> Self {
+        Self {
+            context,
+            route: None,
+            body: None,
+            params_hash: None,
+            timings: None,
+        }
+    }
+}
+
+/// Route the request to its handler, recording what is learnt in `outcome`.
 async fn handle_request(
     req: Request<Incoming>,
     routes: Arc<Mutex<HashMap<Method, Vec<Route>>>>,
+    exception_handlers: ExceptionHandlers,
+    event_loop: Arc<EventLoop>,
+    handler_threads: usize,
+    request_memory_budget: usize,
+    outcome: &mut RequestOutcome,
 ) -> Result<Response<Full<Bytes>>, hyper::Error> {
     let (parts, body_stream) = req.into_parts();
     let method = parts.method.clone();
-    let path = parts.uri.path().to_string();
-    let query = parts.uri.query().unwrap_or("").to_string();
-    let headers = parts.headers.clone();
+    let path = parts.uri.path();
     let mut body = Some(body_stream);
     let path_segments: Vec<&str> = path
         .trim_matches('/')
@@ -368,35 +1215,48 @@ async fn handle_request(
         match routes.lock() {
             Ok(guard) => guard.get(&method).cloned(),
             Err(_) => {
-                return Ok(json_response(
-                    500,
-                    json!({
-                        "detail": "Internal Server Error"
-                    }),
-                ));
+                return Ok(error_response(500, CODE_INTERNAL, "Internal Server Error"));
             }
         }
     };
     if let Some(routes_for_method) = routes_vec {
-        for route in routes_for_method.iter() {
+        for route in routes_for_method {
             match match_route(&route.pattern, &path_segments) {
                 Match::Ok(params) => {
-                    let body_bytes = match body.take() {
-                        Some(stream) => stream.collect().await?.to_bytes(),
-                        None => Bytes::new(),
+                    outcome.route = Some(route.path.clone());
+                    let query = parts.uri.query().unwrap_or("");
+                    outcome.params_hash = Some(slow_requests::params_hash(&params, query));
+                    let budget = RequestBudget::new(request_memory_budget);
+                    let (body_bytes, body_memory) = match body.take() {
+                        Some(stream) => match read_body(stream, &budget).await? {
+                            Some((bytes, memory)) => (bytes, Some(memory)),
+                            None => return Ok(budget_exceeded_response()),
+                        },
+                        None => (Bytes::new(), None),
                     };
-                    let response = call_handler(
-                        &route.handler,
-                        &route.pattern,
+                    outcome.body = Some(body_bytes.clone());
+                    let request = HandlerRequest::new(
                         params,
                         body_bytes,
-                        &query,
-                        &headers,
+                        body_memory,
+                        budget,
+                        query,
+                        &parts.headers,
+                        Arc::clone(&outcome.context),
+                    );
+                    let (response, timings) = call_handler(
+                        route,
+                        exception_handlers,
+                        event_loop,
+                        request,
+                        handler_threads,
                     )
                     .await;
+                    outcome.timings = timings;
                     return Ok(response);
                 }
                 Match::ValidationError(errors) => {
+                    outcome.route = Some(route.path.clone());
                     let detail: Vec<_> = errors
                         .into_iter()
                         .map(|err| {
@@ -407,22 +1267,196 @@ async fn handle_request(
                             })
                         })
                         .collect();
-                    return Ok(json_response(422, json!({ "detail": detail })));
+                    return Ok(json_response(
+                        422,
+                        json!({ "detail": detail, "code": CODE_PATH_VALIDATION }),
+                    ));
                 }
                 Match::Miss => {}
             }
         }
     }
 
-    // fallback health, readiness, and liveness endpoints
-    if method == Method::GET && matches!(path.as_str(), "/health" | "/ready" | "/live") {
+    // fallback readiness, health, and liveness endpoints
+    if method == Method::GET && path == "/ready" {
+        return Ok(readiness_response().await);
+    }
+    if method == Method::GET && matches!(path, "/health" | "/live") {
         return Ok(Response::builder()
             .header("content-type", "application/json")
             .body(Full::from("{\"status\":\"ok\"}"))
             .unwrap());
     }
 
-    Ok(json_response(404, json!({ "detail": "not found" })))
+    Ok(error_response(404, CODE_NOT_FOUND, "not found"))
+}
+
+/// Segments of `path` if no handler route or built-in endpoint answers
+/// it, for the routes served without calling Python.
+fn unhandled_segments<'a>(
+    routes: &Mutex<HashMap<Method, Vec<Route>>>,
+    method: &Method,
+    path: &'a str,
+) -> Option<Vec<&'a str>> {
+    if matches!(path, "/ready" | "/health" | "/live") && method == Method::GET {
+        return None;
+    }
+    let path_segments: Vec<&str> = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let handled = routes.lock().ok()?.get(method).is_some_and(|routes| {
+        routes
+            .iter()
+            .any(|route| !matches!(match_route(&route.pattern, &path_segments), Match::Miss))
+    });
+    (!handled).then_some(path_segments)
+}
+
+/// Path, stream and topic of the event-stream route for a GET request that
+/// no handler route or built-in endpoint answers.
+fn find_event_stream(
+    event_streams: &Mutex<Vec<EventStreamRoute>>,
+    routes: &Mutex<HashMap<Method, Vec<Route>>>,
+    method: &Method,
+    path: &str,
+) -> Option<(Arc<str>, Arc<EventStream>, String)> {
+    let event_streams = event_streams.lock().ok()?;
+    if event_streams.is_empty() || method != Method::GET {
+        return None;
+    }
+    let path_segments = unhandled_segments(routes, method, path)?;
+    event_streams.iter().find_map(|route| {
+        let Match::Ok(params) = match_route(&route.pattern, &path_segments) else {
+            return None;
+        };
+        let names = route.pattern.iter().filter_map(|segment| match segment {
+            Segment::Param { name, .. } => Some(name.as_str()),
+            Segment::Static(_) => None,
+        });
+        let topic = route.stream.topic(names.zip(params.iter().map(String::as_str)));
+        Some((route.path.clone(), route.stream.clone(), topic))
+    })
+}
+
+/// Path, upstream and path tail of the proxy route for a request that no
+/// handler route or built-in endpoint answers.
+fn find_proxy(
+    proxies: &Mutex<Vec<ProxyRoute>>,
+    routes: &Mutex<HashMap<Method, Vec<Route>>>,
+    method: &Method,
+    path: &str,
+) -> Option<(Arc<str>, Arc<Upstream>, Option<String>)> {
+    let proxies = proxies.lock().ok()?;
+    if proxies.is_empty() {
+        return None;
+    }
+    let path_segments = unhandled_segments(routes, method, path)?;
+    let proxy = proxies.iter().find(|proxy| {
+        proxy.methods.as_ref().is_none_or(|methods| methods.contains(method))
+            && matches!(match_route(&proxy.pattern, &path_segments), Match::Ok(_))
+    })?;
+    let tail = match proxy.pattern.last() {
+        Some(Segment::Param { ty: ParamType::Rich(RichType::Path), .. }) => {
+            Some(raw_tail(path, proxy.pattern.len() - 1).to_string())
+        }
+        _ => None,
+    };
+    Some((proxy.path.clone(), proxy.upstream.clone(), tail))
+}
+
+/// `path` after its first `skip` segments, as sent by the client, so that
+/// percent-encoding and a trailing slash reach the upstream unchanged.
+fn raw_tail(path: &str, skip: usize) -> &str {
+    let mut rest = path.trim_start_matches('/');
+    for _ in 0..skip {
+        rest = rest
+            .split_once('/')
+            .map_or("", |(_, after)| after.trim_start_matches('/'));
+    }
+    rest
+}
+
+/// Forward a request to a proxy route's upstream, answering 502 or 504 if
+/// that fails.
+async fn proxy_response(
+    upstream: &Upstream,
+    req: Request<Incoming>,
+    tail: Option<&str>,
+    client: SocketAddr,
+    timeout: Duration,
+) -> Response<ServerBody> {
+    let response = match proxy::forward(upstream, req, tail, client, timeout).await {
+        Ok(response) => return response.map(|body| Either::Right(Either::Left(body))),
+        Err(ProxyError::Timeout) => {
+            warn!(timeout_secs = timeout.as_secs(), "upstream timed out");
+            error_response(504, CODE_GATEWAY_TIMEOUT, "Gateway timeout")
+        }
+        Err(ProxyError::Upstream(err)) => {
+            warn!(error = %err, "upstream request failed");
+            error_response(502, CODE_BAD_GATEWAY, "Bad gateway")
+        }
+        Err(ProxyError::BadRequest(err)) => error_response(400, CODE_BAD_REQUEST, &err),
+    };
+    response.map(Either::Left)
+}
+
+/// Path and app of the mounted ASGI app for a request that no handler
+/// route or built-in endpoint answers.
+fn find_asgi_app(
+    asgi_apps: &Mutex<Vec<Arc<AsgiApp>>>,
+    routes: &Mutex<HashMap<Method, Vec<Route>>>,
+    method: &Method,
+    path: &str,
+) -> Option<(Arc<str>, Arc<AsgiApp>)> {
+    let asgi_apps = asgi_apps.lock().ok()?;
+    if asgi_apps.is_empty() {
+        return None;
+    }
+    unhandled_segments(routes, method, path)?;
+    let app = asgi_apps
+        .iter()
+        .filter(|app| app.mounts(path))
+        .max_by_key(|app| app.mount_point().len())?;
+    Some((app.mount_point().into(), app.clone()))
+}
+
+/// Pass a request to a mounted ASGI app, answering 500 if it fails before
+/// starting its response or 408 if it does not start it in time.
+async fn asgi_response(
+    app: &Arc<AsgiApp>,
+    event_loop: Arc<EventLoop>,
+    req: Request<Incoming>,
+    client: SocketAddr,
+    context: Arc<Context>,
+    timeout: Duration,
+) -> Response<ServerBody> {
+    let response = match app.call(event_loop, req, client, context, timeout).await {
+        Ok(response) => {
+            return response.map(|body| Either::Right(Either::Right(Either::Right(body))));
+        }
+        Err(AsgiError::Timeout) => {
+            warn!(timeout_secs = timeout.as_secs(), "ASGI app timed out");
+            error_response(408, CODE_REQUEST_TIMEOUT, "Request timeout")
+        }
+        Err(AsgiError::App(err)) => {
+            error!(error = %err, "ASGI app failed");
+            error_response(500, CODE_HANDLER_ERROR, "Internal Server Error")
+        }
+    };
+    response.map(Either::Left)
+}
+
+/// Open a stream of `topic` for an event-stream route.
+fn event_stream_response(stream: &EventStream, topic: &str) -> Response<ServerBody> {
+    match stream.open(topic) {
+        Ok(response) => response.map(|body| Either::Right(Either::Right(Either::Left(body)))),
+        Err(err) => {
+            error!(error = %err, "could not open event stream");
+            error_response(500, CODE_INTERNAL, "Internal Server Error").map(Either::Left)
+        }
+    }
 }
 
 /// Result of attempting to match a path to a route pattern.
@@ -455,7 +1489,8 @@ fn parse_pattern(path: &str) -> PyResult<Vec<Segment>> {
                 .ok_or_else(|| pyo3::exceptions::PyValueError::new_err("bad segment"))?;
             let ty = match parts.next() {
                 Some("int") => ParamType::Int,
-                _ => ParamType::Str,
+                Some(name) => RichType::from_name(name).map_or(ParamType::Str, ParamType::Rich),
+                None => ParamType::Str,
             };
             segments.push(Segment::Param {
                 name: name.to_string(),
@@ -468,8 +1503,35 @@ fn parse_pattern(path: &str) -> PyResult<Vec<Segment>> {
     Ok(segments)
 }
 
+/// Error type reported for a path parameter that is not a valid `ty`.
+fn rich_type_error(ty: RichType) -> &'static str {
+    match ty {
+        RichType::DateTime => "type_error.datetime",
+        RichType::Date => "type_error.date",
+        RichType::Uuid => "type_error.uuid",
+        RichType::Decimal => "type_error.decimal",
+        RichType::Path => "type_error.path",
+    }
+}
+
 /// Attempt to match segments against a pattern, returning captured params.
 fn match_route(pattern: &[Segment], path: &[&str]) -> Match {
+    let takes_rest = matches!(
+        pattern.last(),
+        Some(Segment::Param { ty: ParamType::Rich(RichType::Path), .. })
+    );
+    let rest;
+    let mut path = path;
+    let joined: Vec<&str>;
+    if takes_rest && path.len() > pattern.len() {
+        rest = path[pattern.len() - 1..].join("/");
+        joined = path[..pattern.len() - 1]
+            .iter()
+            .copied()
+            .chain([rest.as_str()])
+            .collect();
+        path = &joined;
+    }
     if pattern.len() != path.len() {
         return Match::Miss;
     }
@@ -495,6 +1557,14 @@ fn match_route(pattern: &[Segment], path: &[&str]) -> Match {
                     }
                 }
                 ParamType::Str => params.push(part.to_string()),
+                ParamType::Rich(ty) => match ty.parse(part) {
+                    Ok(_) => params.push(part.to_string()),
+                    Err(msg) => errors.push(PathValidationError {
+                        loc: vec!["path".to_string(), name.clone()],
+                        msg,
+                        typ: rich_type_error(*ty),
+                    }),
+                },
             },
         }
     }
@@ -521,89 +1591,440 @@ fn json_response(status: u16, body: serde_json::Value) -> Response<Full<Bytes>>
         })
 }
 
-/// Call a Python handler with body and extracted parameters.
-async fn call_handler(
-    handler: &Py<PyAny>,
-    pattern: &[Segment],
+/// JSON error body with a human-readable `detail` and a machine-readable `code`.
+fn error_response(status: u16, code: &str, detail: &str) -> Response<Full<Bytes>> {
+    json_response(status, json!({ "detail": detail, "code": code }))
+}
+
+/// 503 asking the client to retry after [`RETRY_AFTER_SECS`].
+fn retry_later_response(code: &str) -> Response<Full<Bytes>> {
+    let mut response = error_response(503, code, "Service Unavailable");
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECS));
+    response
+}
+
+/// Response for a request that outgrew its memory budget.
+fn budget_exceeded_response() -> Response<Full<Bytes>> {
+    error_response(413, CODE_MEMORY_BUDGET, "Request exceeds memory budget")
+}
+
+/// Buffer a request body, charging it to the request's memory budget.
+///
+/// Returns `Ok(None)` as soon as the body outgrows the budget, without
+/// reading the rest of it.
+async fn read_body(
+    mut stream: Incoming,
+    budget: &Arc<RequestBudget>,
+) -> Result<Option<(Bytes, MemoryReservation)>, hyper::Error> {
+    let Ok(mut reservation) =
+        accountant::reserve_in(Some(budget.clone()), MemoryCategory::RequestBody, 0)
+    else {
+        return Ok(None);
+    };
+    let mut buf = Vec::new();
+    while let Some(frame) = stream.frame().await {
+        if let Ok(data) = frame?.into_data() {
+            if reservation.grow(data.len()).is_err() {
+                return Ok(None);
+            }
+            buf.extend_from_slice(&data);
+        }
+    }
+    Ok(Some((Bytes::from(buf), reservation)))
+}
+
+/// A duplicate of the listening socket `fd`, which must be open.
+#[cfg(unix)]
+fn inherited_listener(fd: i32) -> PyResult<std::net::TcpListener> {
+    use std::os::fd::BorrowedFd;
+    if fd < 0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "invalid file descriptor {fd}"
+        )));
+    }
+    // SAFETY: the caller hands over an open descriptor, only borrowed here
+    // to duplicate it
+    let fd = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+    let listener = std::net::TcpListener::from(fd);
+    // fails unless it is a socket
+    listener.local_addr()?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn inherited_listener(_fd: i32) -> PyResult<std::net::TcpListener> {
+    Err(pyo3::exceptions::PyNotImplementedError::new_err(
+        "serving on a file descriptor is only supported on Unix",
+    ))
+}
+
+/// Make sure the handler pool exists with `threads` workers.
+fn ensure_handler_pool(threads: usize) -> Result<(), String> {
+    let manager = ThreadPoolManager::global();
+    match manager.pool_config(HANDLER_POOL) {
+        Some(config) if config.thread_count == threads => Ok(()),
+        Some(mut config) => {
+            config.thread_count = threads;
+            manager.recreate_pool(HANDLER_POOL, config).map(|_| ())
+        }
+        None => manager
+            .create_specialized_pool(HANDLER_POOL, threads)
+            .map(|_| ()),
+    }
+}
+
+/// Request data handed to a Python handler.
+///
+/// The query string and headers are copied into a request arena, which is
+/// reset and reused once the response has been built.
+struct HandlerRequest {
     params: Vec<String>,
     body: Bytes,
-    query: &str,
+    _body_memory: Option<MemoryReservation>,
+    budget: Arc<RequestBudget>,
+    arena: ArenaLease,
+    query: ArenaStr,
+    headers: Vec<(ArenaStr, ArenaStr)>,
+    context: Arc<Context>,
+}
+
+impl HandlerRequest {
+    fn new(
+        params: Vec<String>,
+        body: Bytes,
+        body_memory: Option<MemoryReservation>,
+        budget: Arc<RequestBudget>,
+        query: &str,
+        headers: &HeaderMap,
+        context: Arc<Context>,
+    ) -> Self {
+        let mut arena = request_arena::lease();
+        let query = arena.alloc_str(query);
+        let headers = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((arena.alloc_str(name.as_str()), arena.alloc_str(value)))
+            })
+            .collect();
+        Self {
+            params,
+            body,
+            _body_memory: body_memory,
+            budget,
+            arena,
+            query,
+            headers,
+            context,
+        }
+    }
+}
+
+/// Call a Python handler on the handler pool and await its response, along
+/// with where the time went if the handler ran.
+///
+/// The connection task only waits here, so the runtime worker stays free
+/// for other connections while the handler holds the GIL or its coroutine
+/// runs on the event loop.
+async fn call_handler(
+    route: Route,
+    exception_handlers: ExceptionHandlers,
+    event_loop: Arc<EventLoop>,
+    request: HandlerRequest,
+    handler_threads: usize,
+) -> (Response<Full<Bytes>>, Option<HandlerTimings>) {
+    let (tx, rx) = oneshot::channel();
+    let pool =
+        ThreadPoolManager::global().get_or_create_specialized_pool(HANDLER_POOL, handler_threads);
+    let scheduled = Instant::now();
+    let span = tracing::Span::current();
+    match pool {
+        Ok(pool) => pool.spawn(move || {
+            let _span = span.enter();
+            let queue = scheduled.elapsed();
+            run_handler(
+                &route.handler,
+                &route.args,
+                exception_handlers,
+                &event_loop,
+                request,
+                move |response, timings| {
+                    let _ = tx.send((response, HandlerTimings { queue, ..timings }));
+                },
+            );
+        }),
+        Err(e) => {
+            error!(error = %e, "could not schedule handler");
+            return (retry_later_response(CODE_SERVICE_UNAVAILABLE), None);
+        }
+    }
+    match rx.await {
+        Ok((response, timings)) => (response, Some(timings)),
+        Err(_) => {
+            error!("handler dropped without a response");
+            (
+                error_response(500, CODE_INTERNAL, "Internal Server Error"),
+                None,
+            )
+        }
+    }
+}
+
+/// Answer a request for [`STATS_PATH`], or 401 without the endpoint's token.
+fn stats_response(
+    endpoint: &StatsEndpoint,
     headers: &HeaderMap,
+    routes: &Mutex<HashMap<Method, Vec<Route>>>,
 ) -> Response<Full<Bytes>> {
-    let result = catch_unwind(AssertUnwindSafe(|| {
-        Python::with_gil(|py| -> PyResult<Py<PyAny>> {
-            let py_body = PyBytes::new(py, body.as_ref());
-            let mut objs: Vec<Py<PyAny>> = Vec::new();
-            for (seg, val) in pattern
-                .iter()
-                .filter_map(|s| match s {
-                    Segment::Param { ty, .. } => Some(ty),
-                    _ => None,
-                })
-                .zip(params.iter())
-            {
-                match seg {
-                    ParamType::Int => {
-                        let v: i64 = val.parse().unwrap_or_default();
-                        objs.push(v.into_pyobject(py)?.unbind().into());
-                    }
-                    ParamType::Str => {
-                        objs.push(val.clone().into_pyobject(py)?.unbind().into());
-                    }
-                }
-            }
-            let params_tuple = PyTuple::new(py, objs)?;
-            let py_query = PyBytes::new(py, query.as_bytes());
-            let py_headers = PyDict::new(py);
-            for (name, value) in headers.iter() {
-                if let Ok(val_str) = value.to_str() {
-                    py_headers.set_item(name.as_str(), val_str)?;
+    let route_counts: Vec<(String, usize)> = match routes.lock() {
+        Ok(routes) => routes
+            .iter()
+            .map(|(method, routes)| (method.to_string(), routes.len()))
+            .collect(),
+        Err(_) => return error_response(500, CODE_INTERNAL, "Internal Server Error"),
+    };
+    match endpoint.respond(headers, &route_counts) {
+        Some(snapshot) => json_response(200, snapshot),
+        None => {
+            let mut response = error_response(401, CODE_UNAUTHORIZED, "Unauthorized");
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            response
+        }
+    }
+}
+
+/// Run the registered health checks off the async workers, since Python
+/// checks take the GIL, and answer 503 if any failed.
+async fn readiness_response() -> Response<Full<Bytes>> {
+    let (tx, rx) = oneshot::channel();
+    runtime_manager::spawn_blocking(move || {
+        let _ = tx.send(health::run());
+    });
+    match rx.await {
+        Ok(report) => {
+            let status = if report.healthy() { 200 } else { 503 };
+            json_response(status, report.to_json())
+        }
+        Err(_) => error_response(500, CODE_INTERNAL, "Internal Server Error"),
+    }
+}
+
+/// Call a Python handler with body and extracted parameters, timing the
+/// call and the building of its response. The time the GIL is held along
+/// the way is recorded as the request's.
+///
+/// Exceptions go to the matching registered exception handler, if any. The
+/// request's memory budget applies to everything the handler reserves on
+/// this thread; a handler that fails unhandled after exceeding it gets a 413.
+///
+/// A coroutine returned by the handler is scheduled on `event_loop` and
+/// `reply` is called from the loop's thread once it finishes, the handler's
+/// time running until then; otherwise `reply` is called before returning.
+fn run_handler<F>(
+    handler: &Py<PyAny>,
+    args: &ArgLayout,
+    exception_handlers: ExceptionHandlers,
+    event_loop: &EventLoop,
+    request: HandlerRequest,
+    reply: F,
+) where
+    F: FnOnce(Response<Full<Bytes>>, HandlerTimings) + Send + 'static,
+{
+    gil_utils::observe_request(|| {
+        let started = Instant::now();
+        let result = match call_python_handler(handler, args, &request) {
+            Ok(Ok(obj)) if gil_utils::with_gil(|py| event_loop::is_coroutine(obj.bind(py))) => {
+                // the coroutine's task copies the context variables set now
+                let context = Arc::clone(&request.context);
+                let scheduled = gil_utils::with_gil(|py| {
+                    request_context::with_current(py, &context, || {
+                        let running = event_loop.get(py)?;
+                        event_loop::spawn(&running, obj.bind(py), move |_py, result| {
+                            let called = Instant::now();
+                            let response =
+                                handler_response(Ok(result), &exception_handlers, &request);
+                            let timings = HandlerTimings {
+                                queue: Duration::ZERO,
+                                handler: called - started,
+                                serialization: called.elapsed(),
+                            };
+                            reply(response, timings);
+                        })
+                    })
+                });
+                // on failure `reply` is dropped, which answers 500
+                if let Err(e) = scheduled {
+                    error!(error = %e, "could not schedule coroutine handler");
                 }
+                return;
             }
-            handler.call1(py, (py_body, params_tuple, py_query, py_headers))
+            result => result,
+        };
+        let called = Instant::now();
+        let response = handler_response(result, &exception_handlers, &request);
+        let timings = HandlerTimings {
+            queue: Duration::ZERO,
+            handler: called - started,
+            serialization: called.elapsed(),
+        };
+        reply(response, timings);
+    })
+}
+
+/// Call `handler` with the request's body, typed path parameters, query
+/// string and headers, laid out as `args` describes.
+fn call_python_handler(
+    handler: &Py<PyAny>,
+    args: &ArgLayout,
+    request: &HandlerRequest,
+) -> std::thread::Result<PyResult<Py<PyAny>>> {
+    catch_unwind(AssertUnwindSafe(|| {
+        gil_utils::with_gil(|py| -> PyResult<Py<PyAny>> {
+            let args = args.args(py, request)?;
+            request_context::with_current(py, &request.context, || {
+                accountant::with_request_budget(&request.budget, || {
+                    vectorcall(handler.bind(py), &args).map(Bound::unbind)
+                })
+            })
         })
-    }));
+    }))
+}
+
+/// Turn the outcome of calling a handler into its response.
+fn handler_response(
+    result: std::thread::Result<PyResult<Py<PyAny>>>,
+    exception_handlers: &Mutex<Vec<ExceptionHandler>>,
+    request: &HandlerRequest,
+) -> Response<Full<Bytes>> {
     match result {
         Ok(Ok(obj)) => match extract_response(obj) {
-            Ok((status, body_bytes, headers_map)) => {
-                let mut builder = Response::builder().status(status);
-                let mut has_content_type = false;
-                for (key, value) in headers_map {
-                    if let (Ok(name), Ok(val)) = (
-                        HeaderName::from_bytes(key.as_bytes()),
-                        HeaderValue::from_str(&value),
-                    ) {
-                        if name == CONTENT_TYPE {
-                            has_content_type = true;
-                        }
-                        builder = builder.header(name, val);
-                    }
-                }
-                if !has_content_type {
-                    builder =
-                        builder.header(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-                }
-                builder.body(Full::from(body_bytes)).unwrap()
-            }
+            Ok(parts) => build_response(parts),
             Err(e) => {
-                eprintln!("handler error: {e}");
-                json_response(500, json!({ "detail": "Internal Server Error" }))
+                error!(error = %e, "handler returned an invalid response");
+                error_response(500, CODE_HANDLER_ERROR, "Internal Server Error")
             }
         },
         Ok(Err(e)) => {
-            eprintln!("handler error: {e}");
-            json_response(500, json!({ "detail": "Internal Server Error" }))
+            if let Some(response) = handle_exception(exception_handlers, &e) {
+                return response;
+            }
+            error!(error = %e, "handler raised an exception");
+            if request.budget.rejections() > 0 {
+                return budget_exceeded_response();
+            }
+            if exception_is_retryable(&e) {
+                return retry_later_response(&exception_code(&e));
+            }
+            error_response(500, &exception_code(&e), "Internal Server Error")
         }
-        Err(_) => {
-            eprintln!("handler panic");
-            json_response(500, json!({ "detail": "Internal Server Error" }))
+        Err(payload) => {
+            error!(panic = %panic_hook::describe(payload.as_ref()), "handler panicked");
+            error_response(500, CODE_HANDLER_ERROR, "Internal Server Error")
+        }
+    }
+}
+
+/// Build a response from a handler's status, body and headers.
+fn build_response(
+    (status, body_bytes, headers_map): (u16, Bytes, HashMap<String, String>),
+) -> Response<Full<Bytes>> {
+    let mut builder = Response::builder().status(status);
+    let mut has_content_type = false;
+    for (key, value) in headers_map {
+        if let (Ok(name), Ok(val)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            if name == CONTENT_TYPE {
+                has_content_type = true;
+            }
+            builder = builder.header(name, val);
+        }
+    }
+    if !has_content_type {
+        builder = builder.header(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    }
+    builder.body(Full::from(body_bytes)).unwrap_or_else(|e| {
+        error!(error = %e, "could not build handler response");
+        error_response(500, CODE_HANDLER_ERROR, "Internal Server Error")
+    })
+}
+
+/// Code carried by a handler exception's string `code` attribute, such as the
+/// one set on exceptions raised from a `ForziumError`.
+fn exception_code(err: &PyErr) -> String {
+    gil_utils::with_gil(|py| {
+        err.value(py)
+            .getattr("code")
+            .and_then(|code| code.extract::<String>())
+            .unwrap_or_else(|_| CODE_HANDLER_ERROR.to_string())
+    })
+}
+
+/// Whether a handler exception has a true `is_retryable` attribute, as
+/// exceptions raised from a cancelled or resource-limited `ForziumError` do.
+fn exception_is_retryable(err: &PyErr) -> bool {
+    gil_utils::with_gil(|py| {
+        err.value(py)
+            .getattr("is_retryable")
+            .and_then(|retryable| retryable.extract::<bool>())
+            .unwrap_or(false)
+    })
+}
+
+/// Handler registered for the nearest class in the MRO of `err`'s type.
+fn find_exception_handler(
+    py: Python<'_>,
+    handlers: &Mutex<Vec<ExceptionHandler>>,
+    err: &PyErr,
+) -> Option<Py<PyAny>> {
+    let handlers = handlers.lock().ok()?;
+    if handlers.is_empty() {
+        return None;
+    }
+    let mro = err.get_type(py).mro();
+    mro.iter().find_map(|cls| {
+        handlers
+            .iter()
+            .find(|entry| entry.exc_type.bind(py).is(&cls))
+            .map(|entry| entry.handler.clone_ref(py))
+    })
+}
+
+/// Turn a handler exception into a response with a registered exception
+/// handler, or return `None` when none matches.
+fn handle_exception(
+    handlers: &Mutex<Vec<ExceptionHandler>>,
+    err: &PyErr,
+) -> Option<Response<Full<Bytes>>> {
+    let result = gil_utils::with_gil(|py| {
+        let handler = find_exception_handler(py, handlers, err)?;
+        Some(handler.call1(py, (err.value(py),)))
+    })?;
+    match result.and_then(extract_response) {
+        Ok(parts) => Some(build_response(parts)),
+        Err(e) => {
+            error!(error = %e, handling = %err, "exception handler failed");
+            Some(error_response(
+                500,
+                CODE_HANDLER_ERROR,
+                "Internal Server Error",
+            ))
         }
     }
 }
 
 /// Extract response components from the Python return value.
-fn extract_response(obj: Py<PyAny>) -> PyResult<(u16, Vec<u8>, HashMap<String, String>)> {
-    Python::with_gil(|py| {
+///
+/// Bodies exporting the buffer protocol, such as `bytes`, `bytearray`,
+/// `memoryview` or NumPy arrays, are borrowed rather than copied when
+/// contiguous; the handler must not modify them once it has returned.
+fn extract_response(obj: Py<PyAny>) -> PyResult<(u16, Bytes, HashMap<String, String>)> {
+    gil_utils::with_gil(|py| {
         let bound = obj.bind(py);
         let tuple = bound.downcast::<PyTuple>().map_err(|_| {
             pyo3::exceptions::PyTypeError::new_err("expected (status, body, headers) tuple")
@@ -616,21 +2037,81 @@ fn extract_response(obj: Py<PyAny>) -> PyResult<(u16, Vec<u8>, HashMap<String, S
         let status: u16 = tuple.get_item(0)?.extract()?;
         let body_item = tuple.get_item(1)?;
         let body_bytes = if let Ok(text) = body_item.extract::<String>() {
-            text.into_bytes()
+            Bytes::from(text)
         } else if let Ok(chunks) = body_item.extract::<Vec<String>>() {
-            chunks.join("").into_bytes()
+            Bytes::from(chunks.join(""))
+        } else if let Ok(view) = PyMemoryView::from(&body_item) {
+            buffer_body(&view)?
         } else if let Ok(raw) = body_item.extract::<Vec<u8>>() {
-            raw
+            Bytes::from(raw)
         } else {
             return Err(pyo3::exceptions::PyTypeError::new_err(
-                "response body must be str, bytes, or list[str]",
+                "response body must be str, list[str], or support the buffer protocol",
             ));
         };
-        let headers = tuple.get_item(2)?.extract()?;
+        let headers = header_values(&tuple.get_item(2)?)?;
         Ok((status, body_bytes, headers))
     })
 }
 
+/// Response headers, whose values may be `str` or any type [`RichValue`]
+/// converts; datetimes become HTTP dates.
+fn header_values(headers: &Bound<'_, PyAny>) -> PyResult<HashMap<String, String>> {
+    let headers: HashMap<String, Bound<'_, PyAny>> = headers.extract()?;
+    headers
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value.extract::<String>() {
+                Ok(text) => text,
+                Err(_) => value.extract::<RichValue>()?.to_header(),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// A response body borrowed from a Python buffer, which stays exported,
+/// so its exporter cannot resize it, until the body has been written.
+///
+/// Releasing the buffer takes the GIL, so it is handed to a blocking thread
+/// rather than done on the connection task that drops the body.
+struct BorrowedBody(Option<PyBuffer<u8>>);
+
+impl AsRef<[u8]> for BorrowedBody {
+    fn as_ref(&self) -> &[u8] {
+        let Some(buffer) = &self.0 else {
+            return &[];
+        };
+        let len = buffer.len_bytes();
+        if len == 0 {
+            return &[];
+        }
+        // SAFETY: `buffer_body` only borrows C-contiguous byte buffers, which
+        // stay valid while exported.
+        unsafe { std::slice::from_raw_parts(buffer.buf_ptr() as *const u8, len) }
+    }
+}
+
+impl Drop for BorrowedBody {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.0.take() {
+            runtime_manager::spawn_blocking(move || drop(buffer));
+        }
+    }
+}
+
+/// Bytes of the buffer behind `view`, borrowed if it is C-contiguous and
+/// copied otherwise.
+fn buffer_body(view: &Bound<'_, PyMemoryView>) -> PyResult<Bytes> {
+    match view.call_method1("cast", ("B",)) {
+        Ok(bytes) => {
+            let buffer = PyBuffer::get(&bytes)?;
+            Ok(Bytes::from_owner(BorrowedBody(Some(buffer))))
+        }
+        Err(_) => Ok(Bytes::from(view.call_method0("tobytes")?.extract::<Vec<u8>>()?)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -654,6 +2135,164 @@ mod tests {
         }
     }
 
+    #[test]
+    fn match_route_rich_params_and_trailing_path() {
+        let pattern = parse_pattern("/orders/{id:uuid}/files/{file:path}").unwrap();
+        let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        match match_route(&pattern, &["orders", id, "files", "2024", "q1.csv"]) {
+            Match::Ok(params) => assert_eq!(params, [id, "2024/q1.csv"]),
+            other => panic!("expected Match::Ok, got {:?}", other),
+        }
+        assert!(matches!(
+            match_route(&pattern, &["orders", id, "files"]),
+            Match::Miss
+        ));
+        match match_route(&pattern, &["orders", "42", "files", "a"]) {
+            Match::ValidationError(errors) => assert_eq!(errors[0].typ, "type_error.uuid"),
+            other => panic!("expected Match::ValidationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn handler_pool_follows_configured_size() {
+        let size = || {
+            ThreadPoolManager::global()
+                .pool_sizes()
+                .into_iter()
+                .find(|(name, _)| name == HANDLER_POOL)
+                .map(|(_, threads)| threads)
+        };
+        ensure_handler_pool(3).unwrap();
+        assert_eq!(size(), Some(3));
+        ensure_handler_pool(2).unwrap();
+        assert_eq!(size(), Some(2));
+    }
+
+    #[test]
+    fn coroutine_handlers_reply_from_the_event_loop() {
+        let handler = Python::attach(|py| {
+            let code = c"async def handler(body, params, query, headers):\n    return 201, body + context.get().request_id.encode(), {}\n";
+            let module = PyModule::from_code(py, code, c"routes.py", c"routes").unwrap();
+            module
+                .setattr("context", request_context::context_var(py).unwrap())
+                .unwrap();
+            module.getattr("handler").unwrap().unbind()
+        });
+        let args = ArgLayout::new(&parse_pattern("/items").unwrap());
+        let event_loop = EventLoop::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let request = HandlerRequest::new(
+            Vec::new(),
+            Bytes::from_static(b"hello"),
+            None,
+            RequestBudget::new(DEFAULT_REQUEST_MEMORY_BUDGET),
+            "",
+            &HeaderMap::new(),
+            Arc::new(Context::new(Some("req-1"), None)),
+        );
+        run_handler(
+            &handler,
+            &args,
+            Arc::new(Mutex::new(Vec::new())),
+            &event_loop,
+            request,
+            move |response, _timings| tx.send(response).unwrap(),
+        );
+        let response = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(response.status(), 201);
+        let body = crate::runtime_manager::handle()
+            .block_on(response.into_body().collect())
+            .unwrap();
+        // the request's context is current in the coroutine's task
+        assert_eq!(body.to_bytes(), "helloreq-1");
+    }
+
+    #[test]
+    fn handlers_are_called_with_the_route_argument_layout() {
+        let handler = Python::attach(|py| {
+            let code = c"class Routes:\n    def item(self, body, params, query, headers):\n        return 200, repr((body, params, query, headers['x-a'])).encode(), {}\n";
+            let module = PyModule::from_code(py, code, c"routes.py", c"routes").unwrap();
+            let routes = module.getattr("Routes").unwrap().call0().unwrap();
+            // a bound method, which takes `self` from the vectorcall offset
+            routes.getattr("item").unwrap().unbind()
+        });
+        let args = ArgLayout::new(&parse_pattern("/items/{id:int}/{name}").unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-a", HeaderValue::from_static("1"));
+        let request = HandlerRequest::new(
+            vec!["7".into(), "box".into()],
+            Bytes::from_static(b"hi"),
+            None,
+            RequestBudget::new(DEFAULT_REQUEST_MEMORY_BUDGET),
+            "q=1",
+            &headers,
+            Arc::new(Context::new(None, None)),
+        );
+        let (tx, rx) = std::sync::mpsc::channel();
+        run_handler(
+            &handler,
+            &args,
+            Arc::new(Mutex::new(Vec::new())),
+            &EventLoop::default(),
+            request,
+            move |response, _timings| tx.send(response).unwrap(),
+        );
+        let response = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(response.status(), 200);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let body = runtime.block_on(response.into_body().collect()).unwrap();
+        assert_eq!(body.to_bytes(), "(b'hi', (7, 'box'), b'q=1', '1')");
+    }
+
+    #[test]
+    fn buffer_bodies_are_borrowed_when_contiguous() {
+        let body = |expr: &str| {
+            Python::attach(|py| {
+                let obj = py.eval(&std::ffi::CString::new(expr).unwrap(), None, None).unwrap();
+                extract_response(obj.unbind()).map(|(_, body, _)| body)
+            })
+        };
+        assert_eq!(body("(200, bytearray(b'abc'), {})").unwrap(), "abc");
+        assert_eq!(body("(200, memoryview(b'abcdef')[1:4], {})").unwrap(), "bcd");
+        // strided, so copied
+        assert_eq!(body("(200, memoryview(b'abcdef')[::2], {})").unwrap(), "ace");
+        let wide = body("(200, memoryview(bytes(range(8))).cast('I'), {})").unwrap();
+        assert_eq!(wide.as_ref(), (0..8).collect::<Vec<u8>>());
+        assert!(body("(200, 1.5, {})").is_err());
+    }
+
+    #[test]
+    fn borrowed_bodies_are_released_without_waiting_for_the_gil() {
+        let body = Python::attach(|py| {
+            let obj = py.eval(c"(200, bytearray(b\"abc\"), {})", None, None).unwrap();
+            extract_response(obj.unbind()).unwrap().1
+        });
+        let (held_tx, held_rx) = std::sync::mpsc::channel();
+        let holder = std::thread::spawn(move || {
+            Python::attach(|_py| {
+                held_tx.send(()).unwrap();
+                std::thread::sleep(Duration::from_millis(500));
+            })
+        });
+        held_rx.recv().unwrap();
+        // a connection task dropping the body while a slow handler holds
+        // the GIL, next to a task of another connection
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let started = Instant::now();
+        runtime.block_on(async move {
+            let other = tokio::spawn(tokio::time::sleep(Duration::from_millis(10)));
+            drop(body);
+            other.await.unwrap();
+        });
+        assert!(started.elapsed() < Duration::from_millis(250));
+        holder.join().unwrap();
+    }
+
     #[test]
     fn match_route_validation_error_on_type_mismatch() {
         let pattern = parse_pattern("/users/{id:int}").unwrap();
@@ -669,4 +2308,41 @@ mod tests {
             other => panic!("expected Match::ValidationError, got {:?}", other),
         }
     }
+
+    #[test]
+    fn proxy_routes_match_after_handler_routes() {
+        let proxy = |path: &str, methods: Option<Vec<Method>>| ProxyRoute {
+            path: path.into(),
+            pattern: parse_pattern(path).unwrap(),
+            methods,
+            upstream: Arc::new(
+                Upstream::new("http://old", HashMap::new(), HashMap::new(), false).unwrap(),
+            ),
+        };
+        let proxies = Mutex::new(vec![
+            proxy("/legacy/{rest:path}", None),
+            proxy("/static", Some(vec![Method::GET])),
+        ]);
+        let routes = Python::attach(|py| {
+            let pattern = parse_pattern("/legacy/new").unwrap();
+            let route = Route {
+                path: "/legacy/new".into(),
+                args: Arc::new(ArgLayout::new(&pattern)),
+                pattern,
+                handler: py.None(),
+            };
+            Mutex::new(HashMap::from([(Method::GET, vec![route])]))
+        });
+
+        let (route, _, tail) =
+            find_proxy(&proxies, &routes, &Method::POST, "/legacy/a%20b//c/").unwrap();
+        assert_eq!(&*route, "/legacy/{rest:path}");
+        assert_eq!(tail.as_deref(), Some("a%20b//c/"));
+        assert!(find_proxy(&proxies, &routes, &Method::GET, "/legacy/new").is_none());
+        assert!(find_proxy(&proxies, &routes, &Method::POST, "/legacy/new").is_some());
+        let (_, _, tail) = find_proxy(&proxies, &routes, &Method::GET, "/static").unwrap();
+        assert_eq!(tail, None);
+        assert!(find_proxy(&proxies, &routes, &Method::POST, "/static").is_none());
+        assert!(find_proxy(&proxies, &routes, &Method::GET, "/health").is_none());
+    }
 }
\ No newline at end of file
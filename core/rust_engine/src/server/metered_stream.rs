@@ -0,0 +1,185 @@
+//! Byte and timing accounting of server connections
+//!
+//! [`MeteredStream`] wraps an accepted socket and adds what it reads and
+//! writes to the server's [`ConnectionStats`]. For each request it also
+//! records how long the request took to arrive, from its first byte to the
+//! last one read before the response started, and the time from that last
+//! byte to the first byte of the response. A long receive time points at a
+//! slow client, a long time to first byte at a slow handler.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::server::stats::ConnectionStats;
+
+/// A connection whose traffic is counted in `stats`.
+pub struct MeteredStream<S> {
+    inner: S,
+    stats: Arc<ConnectionStats>,
+    /// When the first and latest bytes of the request being received were
+    /// read; taken once its response starts.
+    receiving: Option<(Instant, Instant)>,
+}
+
+impl<S> MeteredStream<S> {
+    pub fn new(inner: S, stats: Arc<ConnectionStats>) -> Self {
+        Self {
+            inner,
+            stats,
+            receiving: None,
+        }
+    }
+
+    fn read(&mut self, bytes: usize) {
+        self.stats
+            .bytes_read
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        let now = Instant::now();
+        let first = self.receiving.map_or(now, |(first, _)| first);
+        self.receiving = Some((first, now));
+    }
+
+    fn written(&mut self, bytes: usize) {
+        self.stats
+            .bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        if let Some((first, last)) = self.receiving.take() {
+            self.stats.receive.record(last - first);
+            self.stats.time_to_first_byte.record(last.elapsed());
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for MeteredStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = buf.filled().len() - before;
+        if read > 0 {
+            self.read(read);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for MeteredStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll
+            && written > 0
+        {
+            self.written(written);
+        }
+        poll
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+        if let Poll::Ready(Ok(written)) = poll
+            && written > 0
+        {
+            self.written(written);
+        }
+        poll
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Waker;
+    use std::time::Duration;
+
+    /// Yields `incoming` to reads and accepts every write.
+    struct Scripted {
+        incoming: Vec<u8>,
+    }
+
+    impl AsyncRead for Scripted {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let n = buf.remaining().min(self.incoming.len());
+            buf.put_slice(&self.incoming[..n]);
+            self.incoming.drain(..n);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for Scripted {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn traffic_and_request_timings_are_recorded() {
+        let stats = Arc::new(ConnectionStats::default());
+        let inner = Scripted {
+            incoming: b"GET / HTTP/1.1\r\n\r\n".to_vec(),
+        };
+        let mut stream = MeteredStream::new(inner, stats.clone());
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut storage = [0u8; 8];
+
+        for _ in 0..3 {
+            let mut buf = ReadBuf::new(&mut storage);
+            let _ = Pin::new(&mut stream).poll_read(&mut cx, &mut buf);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        let _ = Pin::new(&mut stream).poll_write(&mut cx, b"HTTP/1.1 200 OK\r\n");
+        let _ = Pin::new(&mut stream).poll_write(&mut cx, b"\r\n");
+
+        assert_eq!(stats.bytes_read.load(Ordering::Relaxed), 18);
+        assert_eq!(stats.bytes_written.load(Ordering::Relaxed), 19);
+        let receive = stats.receive.snapshot();
+        assert_eq!(receive.count, 1);
+        assert!(receive.max >= Duration::from_millis(4));
+        let first_byte = stats.time_to_first_byte.snapshot();
+        assert_eq!(first_byte.count, 1);
+        assert!(first_byte.max >= Duration::from_millis(2));
+    }
+}
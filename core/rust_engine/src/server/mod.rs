@@ -1 +1,12 @@
+pub mod access_log;
+pub mod asgi;
+pub mod event_loop;
+pub mod event_stream;
 pub mod http_engine;
+pub mod metered_stream;
+pub mod proxy;
+pub mod request_context;
+pub mod response_cache;
+pub mod route_metrics;
+pub mod slow_requests;
+pub mod stats;
@@ -0,0 +1,346 @@
+//! Reverse proxying to upstream HTTP servers
+//!
+//! Requests matching a proxy route are forwarded to its [`Upstream`] from
+//! the server's runtime, without calling Python. Request and response
+//! bodies are streamed through as they arrive, so large uploads and
+//! downloads are never buffered. Hop-by-hop headers are dropped in both
+//! directions, `X-Forwarded-For`, `X-Forwarded-Proto` and
+//! `X-Forwarded-Host` tell the upstream about the client, and each route
+//! can set or remove further headers of the request and the response.
+
+use http_body_util::{Either, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{CONNECTION, HOST, HeaderName, HeaderValue};
+use hyper::{HeaderMap, Request, Response, Uri};
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::client::describe;
+use crate::error::ForziumError;
+use crate::server::asgi::{AsgiBody, percent_decode};
+use crate::server::event_stream::EventBody;
+
+/// Body of the server's responses: buffered for handlers, streamed from
+/// the upstream for proxy routes, from the broadcast hub for event-stream
+/// routes or from a mounted ASGI app.
+pub type ServerBody = Either<Full<Bytes>, Either<Incoming, Either<EventBody, AsgiBody>>>;
+
+/// Headers that only concern one connection, never forwarded.
+const HOP_BY_HOP: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Connection pool shared by all proxy routes.
+static CLIENT: Lazy<Client<HttpConnector, Incoming>> = Lazy::new(|| {
+    let mut connector = HttpConnector::new();
+    connector.set_nodelay(true);
+    Client::builder(TokioExecutor::new())
+        .pool_timer(TokioTimer::new())
+        .timer(TokioTimer::new())
+        .build(connector)
+});
+
+/// Why a request could not be forwarded.
+#[derive(Debug)]
+pub enum ProxyError {
+    /// The upstream did not answer within the request timeout.
+    Timeout,
+    /// The upstream could not be reached or failed to answer.
+    Upstream(String),
+    /// The request could not be turned into one for the upstream.
+    BadRequest(String),
+}
+
+/// Header to set to a value, or to remove if `None`.
+type HeaderRule = (HeaderName, Option<HeaderValue>);
+
+/// Where a proxy route forwards requests and how it rewrites them.
+#[derive(Debug)]
+pub struct Upstream {
+    /// Scheme, authority and path prefix of the forwarded requests.
+    base: Uri,
+    request_headers: Vec<HeaderRule>,
+    response_headers: Vec<HeaderRule>,
+    preserve_host: bool,
+}
+
+fn header_rules(headers: HashMap<String, Option<String>>) -> Result<Vec<HeaderRule>, ForziumError> {
+    headers
+        .into_iter()
+        .map(|(name, value)| {
+            let header = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| ForziumError::Validation(format!("invalid header name '{name}'")))?;
+            let value = value
+                .map(|value| HeaderValue::from_str(&value))
+                .transpose()
+                .map_err(|_| {
+                    ForziumError::Validation(format!("invalid value for header '{name}'"))
+                })?;
+            Ok((header, value))
+        })
+        .collect()
+}
+
+impl Upstream {
+    /// Forward to `url`, an `http://` URL whose path prefixes the forwarded
+    /// paths, applying the header rules of `request_headers` and
+    /// `response_headers`. The upstream's host is sent as `Host` unless
+    /// `preserve_host` keeps the client's.
+    pub fn new(
+        url: &str,
+        request_headers: HashMap<String, Option<String>>,
+        response_headers: HashMap<String, Option<String>>,
+        preserve_host: bool,
+    ) -> Result<Self, ForziumError> {
+        let base: Uri = url.parse().map_err(|err| {
+            ForziumError::Validation(format!("invalid upstream URL '{url}': {err}"))
+        })?;
+        if base.scheme_str() != Some("http") || base.authority().is_none() {
+            return Err(ForziumError::Validation(format!(
+                "upstream must be an http:// URL, got '{url}'"
+            )));
+        }
+        if base.query().is_some() {
+            return Err(ForziumError::Validation(format!(
+                "upstream URL '{url}' cannot have a query string"
+            )));
+        }
+        Ok(Self {
+            base,
+            request_headers: header_rules(request_headers)?,
+            response_headers: header_rules(response_headers)?,
+            preserve_host,
+        })
+    }
+
+    /// URI of the upstream request: the base path followed by `tail`, if
+    /// given, and the client's query string. Tails with `.` or `..`
+    /// segments or encoded separators are refused, as they could reach
+    /// upstream paths outside the base path.
+    fn target(&self, tail: Option<&str>, query: Option<&str>) -> Result<Uri, ProxyError> {
+        if tail.is_some_and(|tail| tail.split('/').any(is_unsafe_segment)) {
+            return Err(ProxyError::BadRequest(
+                "path cannot contain '.' or '..' segments or encoded separators".into(),
+            ));
+        }
+        let prefix = self.base.path();
+        let mut path = match tail {
+            Some(tail) => format!("{}/{tail}", prefix.trim_end_matches('/')),
+            None => prefix.to_string(),
+        };
+        if let Some(query) = query {
+            path.push('?');
+            path.push_str(query);
+        }
+        let mut parts = self.base.clone().into_parts();
+        parts.path_and_query = Some(
+            path.parse()
+                .map_err(|err| ProxyError::BadRequest(format!("invalid upstream path: {err}")))?,
+        );
+        Uri::from_parts(parts).map_err(|err| ProxyError::BadRequest(err.to_string()))
+    }
+}
+
+/// Whether `segment`, percent-decoded as upstreams may do before resolving
+/// the path, is `.` or `..` or holds a `/` or `\` that would split it.
+fn is_unsafe_segment(segment: &str) -> bool {
+    let decoded = percent_decode(segment);
+    decoded == "." || decoded == ".." || decoded.contains(['/', '\\'])
+}
+
+/// Remove hop-by-hop headers, including those named by `Connection`.
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let named: Vec<HeaderName> = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    for name in named {
+        headers.remove(name);
+    }
+    for name in HOP_BY_HOP {
+        headers.remove(name);
+    }
+}
+
+fn apply_rules(headers: &mut HeaderMap, rules: &[HeaderRule]) {
+    for (name, value) in rules {
+        match value {
+            Some(value) => {
+                headers.insert(name.clone(), value.clone());
+            }
+            None => {
+                headers.remove(name);
+            }
+        }
+    }
+}
+
+/// Tell the upstream about `client`: its address is appended to
+/// `X-Forwarded-For`, while `X-Forwarded-Host` and `X-Forwarded-Proto` are
+/// set from this request, replacing any the client sent.
+fn set_forwarded(headers: &mut HeaderMap, client: SocketAddr) {
+    let forwarded_for = match headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+        Some(earlier) => format!("{earlier}, {}", client.ip()),
+        None => client.ip().to_string(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&forwarded_for) {
+        headers.insert("x-forwarded-for", value);
+    }
+    match headers.get(HOST).cloned() {
+        Some(host) => {
+            headers.insert("x-forwarded-host", host);
+        }
+        None => {
+            headers.remove("x-forwarded-host");
+        }
+    }
+    headers.insert("x-forwarded-proto", HeaderValue::from_static("http"));
+}
+
+/// Forward `req` from `client` to `upstream`, appending `tail` to its base
+/// path, and stream back the response once its head arrives, which must
+/// be within `timeout`.
+pub async fn forward(
+    upstream: &Upstream,
+    req: Request<Incoming>,
+    tail: Option<&str>,
+    client: SocketAddr,
+    timeout: Duration,
+) -> Result<Response<Incoming>, ProxyError> {
+    let (mut parts, body) = req.into_parts();
+    parts.uri = upstream.target(tail, parts.uri.query())?;
+    parts.version = hyper::Version::HTTP_11;
+
+    let headers = &mut parts.headers;
+    strip_hop_by_hop(headers);
+    set_forwarded(headers, client);
+    if !upstream.preserve_host || !headers.contains_key(HOST) {
+        let authority = upstream
+            .base
+            .authority()
+            .map(|a| a.as_str())
+            .unwrap_or_default();
+        if let Ok(value) = HeaderValue::from_str(authority) {
+            headers.insert(HOST, value);
+        }
+    }
+    apply_rules(headers, &upstream.request_headers);
+
+    let response =
+        match tokio::time::timeout(timeout, CLIENT.request(Request::from_parts(parts, body))).await
+        {
+            Ok(Ok(response)) => response,
+            Ok(Err(err)) => return Err(ProxyError::Upstream(describe(&err))),
+            Err(_) => return Err(ProxyError::Timeout),
+        };
+    let (mut parts, body) = response.into_parts();
+    strip_hop_by_hop(&mut parts.headers);
+    apply_rules(&mut parts.headers, &upstream.response_headers);
+    Ok(Response::from_parts(parts, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_join_the_base_path_and_query() {
+        let upstream =
+            Upstream::new("http://old:8080/v1/", HashMap::new(), HashMap::new(), false).unwrap();
+        assert_eq!(
+            upstream.target(Some("users/7/"), Some("page=2")).unwrap(),
+            "http://old:8080/v1/users/7/?page=2"
+        );
+        let upstream = Upstream::new("http://old", HashMap::new(), HashMap::new(), false).unwrap();
+        assert_eq!(upstream.target(None, None).unwrap(), "http://old/");
+        assert!(Upstream::new("https://old", HashMap::new(), HashMap::new(), false).is_err());
+        assert!(Upstream::new("http://old/?a=1", HashMap::new(), HashMap::new(), false).is_err());
+    }
+
+    #[test]
+    fn dot_segments_cannot_leave_the_base_path() {
+        let upstream =
+            Upstream::new("http://old/v1", HashMap::new(), HashMap::new(), false).unwrap();
+        for tail in [
+            "../admin",
+            "users/../../admin",
+            "./users",
+            "users/..",
+            "%2e%2E/admin",
+            "users%2f..%2f..%2fadmin",
+            "users%2F%2E%2E",
+            "..%5c..%5cadmin",
+            "users\\..",
+        ] {
+            assert!(
+                matches!(
+                    upstream.target(Some(tail), None),
+                    Err(ProxyError::BadRequest(_))
+                ),
+                "{tail}"
+            );
+        }
+        assert_eq!(
+            upstream
+                .target(Some("users/..7/.well-known"), None)
+                .unwrap(),
+            "http://old/v1/users/..7/.well-known"
+        );
+    }
+
+    #[test]
+    fn forwarded_host_and_proto_replace_client_values() {
+        let client: SocketAddr = "10.0.0.9:4000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("api.example"));
+        headers.insert("x-forwarded-for", HeaderValue::from_static("1.2.3.4"));
+        headers.insert("x-forwarded-host", HeaderValue::from_static("evil.example"));
+        headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+        set_forwarded(&mut headers, client);
+        assert_eq!(headers["x-forwarded-for"], "1.2.3.4, 10.0.0.9");
+        assert_eq!(headers["x-forwarded-host"], "api.example");
+        assert_eq!(headers["x-forwarded-proto"], "http");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-host", HeaderValue::from_static("evil.example"));
+        set_forwarded(&mut headers, client);
+        assert!(!headers.contains_key("x-forwarded-host"));
+        assert_eq!(headers["x-forwarded-for"], "10.0.0.9");
+    }
+
+    #[test]
+    fn hop_by_hop_headers_are_dropped() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONNECTION, HeaderValue::from_static("close, x-private"));
+        headers.insert("x-private", HeaderValue::from_static("1"));
+        headers.insert("keep-alive", HeaderValue::from_static("timeout=5"));
+        headers.insert("x-kept", HeaderValue::from_static("1"));
+        strip_hop_by_hop(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains_key("x-kept"));
+
+        let rules = header_rules(HashMap::from([
+            ("x-kept".to_string(), None),
+            ("x-added".to_string(), Some("yes".to_string())),
+        ]))
+        .unwrap();
+        apply_rules(&mut headers, &rules);
+        assert_eq!(headers.get("x-added").unwrap(), "yes");
+        assert!(!headers.contains_key("x-kept"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Context of the request being served
+//!
+//! The server creates one [`Context`] per request, before routing it: its
+//! id, taken from the `X-Request-ID` header or generated, and its deadline,
+//! when the request times out. Middleware adds the authenticated claims and
+//! any keys of its own, which handlers read back. The server logs the id,
+//! subject and keys with each request it answers.
+//!
+//! Python code sees the context of the request it serves as a
+//! `RequestContext` in the `forzium_engine.request_context` context
+//! variable, set while its handler runs. Coroutines the handler starts copy
+//! the variable like any other.
+
+use hyper::HeaderMap;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyList};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::client::seconds;
+use crate::error::ForziumError;
+use crate::interpreter::PerInterpreter;
+use crate::trace_context::random_trace_id;
+
+/// Header carrying the request id, sent back with every response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request id accepted from a client.
+const MAX_ID_LEN: usize = 128;
+
+/// Id, deadline, claims and custom keys of a request.
+pub struct Context {
+    id: Box<str>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    deadline: Option<Instant>,
+    claims: Option<Value>,
+    values: Map<String, Value>,
+}
+
+impl Context {
+    /// Context of a request with the id `id`, or a generated one if it is
+    /// missing or not 1 to 128 visible ASCII characters, due within
+    /// `timeout`.
+    pub fn new(id: Option<&str>, timeout: Option<Duration>) -> Self {
+        let id = match id {
+            Some(id) if valid_id(id) => id.into(),
+            _ => format!("{:032x}", u128::from_be_bytes(random_trace_id())).into(),
+        };
+        Self {
+            id,
+            state: Mutex::new(State {
+                deadline: timeout.map(|timeout| Instant::now() + timeout),
+                ..State::default()
+            }),
+        }
+    }
+
+    /// Context of a request with `headers`, due within `timeout`.
+    pub fn from_headers(headers: &HeaderMap, timeout: Option<Duration>) -> Self {
+        let id = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok());
+        Self::new(id, timeout)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.state.lock().deadline
+    }
+
+    /// Time left until the deadline, zero once it passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Move the deadline to within `timeout`, unless it is already sooner.
+    pub fn tighten_deadline(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock();
+        state.deadline = Some(state.deadline.map_or(deadline, |d| d.min(deadline)));
+    }
+
+    /// Claims of the authenticated caller, if middleware set them.
+    pub fn claims(&self) -> Option<Value> {
+        self.state.lock().claims.clone()
+    }
+
+    pub fn set_claims(&self, claims: Option<Value>) {
+        self.state.lock().claims = claims;
+    }
+
+    /// Who made the request: the `sub` claim, or the `user` claim of
+    /// tokens issued by forzium's own middleware.
+    pub fn subject(&self) -> Option<String> {
+        let state = self.state.lock();
+        let claims = state.claims.as_ref()?;
+        ["sub", "user"]
+            .iter()
+            .find_map(|name| match claims.get(name)? {
+                Value::String(subject) => Some(subject.clone()),
+                Value::Number(subject) => Some(subject.to_string()),
+                _ => None,
+            })
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.state.lock().values.get(key).cloned()
+    }
+
+    pub fn insert(&self, key: &str, value: Value) {
+        self.state.lock().values.insert(key.to_string(), value);
+    }
+
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        self.state.lock().values.remove(key)
+    }
+
+    /// The custom keys and their values.
+    pub fn values(&self) -> Map<String, Value> {
+        self.state.lock().values.clone()
+    }
+}
+
+fn valid_id(id: &str) -> bool {
+    (1..=MAX_ID_LEN).contains(&id.len()) && id.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// The `forzium_engine.request_context` context variable of each
+/// interpreter.
+static CONTEXT_VAR: Lazy<PerInterpreter<Py<PyAny>>> = Lazy::new(PerInterpreter::new);
+
+/// The `forzium_engine.request_context` context variable.
+pub fn context_var(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    CONTEXT_VAR
+        .get_or_try_init(py, || {
+            py.import("contextvars")?
+                .getattr("ContextVar")?
+                .call(
+                    ("forzium_request_context",),
+                    Some(&[("default", py.None())].into_py_dict(py)?),
+                )
+                .map(Bound::unbind)
+        })
+        .map(|var| var.bind(py))
+}
+
+/// Call `f` with `context` as the current request's context.
+pub fn with_current<R>(
+    py: Python<'_>,
+    context: &Arc<Context>,
+    f: impl FnOnce() -> PyResult<R>,
+) -> PyResult<R> {
+    let var = context_var(py)?;
+    let current = RequestContext {
+        context: Arc::clone(context),
+    };
+    let token = var.call_method1("set", (current,))?;
+    let result = f();
+    var.call_method1("reset", (token,))?;
+    result
+}
+
+fn to_json(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let text: String = value
+        .py()
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&text).map_err(|e| ForziumError::Validation(e.to_string()).into())
+}
+
+fn from_json<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (value.to_string(),))
+}
+
+/// Context of a request: its id, deadline, authenticated claims and keys
+/// set by middleware
+///
+/// Keys map to JSON values, copied in and out. Handlers served by the
+/// engine find their request's context in `forzium_engine.request_context`;
+/// one created here, due within `timeout` seconds, is for code run outside
+/// the server.
+#[pyclass(module = "forzium_engine", frozen)]
+pub struct RequestContext {
+    context: Arc<Context>,
+}
+
+#[pymethods]
+impl RequestContext {
+    #[new]
+    #[pyo3(signature = (request_id=None, *, timeout=None))]
+    fn new(request_id: Option<&str>, timeout: Option<f64>) -> PyResult<Self> {
+        let timeout = timeout.map(|value| seconds("timeout", value)).transpose()?;
+        Ok(Self {
+            context: Arc::new(Context::new(request_id, timeout)),
+        })
+    }
+
+    /// Id of the request, sent back in the `X-Request-ID` header
+    #[getter]
+    fn request_id(&self) -> &str {
+        self.context.id()
+    }
+
+    /// Seconds left until the request times out, or None without deadline
+    #[getter]
+    fn remaining(&self) -> Option<f64> {
+        self.context.remaining().map(|left| left.as_secs_f64())
+    }
+
+    /// Whether the deadline passed
+    #[getter]
+    fn expired(&self) -> bool {
+        self.context.remaining() == Some(Duration::ZERO)
+    }
+
+    /// Move the deadline to within `timeout` seconds; a later deadline than
+    /// the current one is ignored
+    fn set_deadline(&self, timeout: f64) -> PyResult<()> {
+        self.context.tighten_deadline(seconds("timeout", timeout)?);
+        Ok(())
+    }
+
+    /// Claims of the authenticated caller, such as a decoded JWT payload
+    #[getter]
+    fn claims<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.context
+            .claims()
+            .map(|claims| from_json(py, &claims))
+            .transpose()
+    }
+
+    #[setter]
+    fn set_claims(&self, claims: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+        let claims = claims.map(to_json).transpose()?;
+        self.context
+            .set_claims(claims.filter(|claims| !claims.is_null()));
+        Ok(())
+    }
+
+    /// The `sub` or `user` claim, as logged with the request
+    #[getter]
+    fn subject(&self) -> Option<String> {
+        self.context.subject()
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        match self.context.get(key) {
+            Some(value) => from_json(py, &value),
+            None => Err(PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    fn __setitem__(&self, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.context.insert(key, to_json(value)?);
+        Ok(())
+    }
+
+    fn __delitem__(&self, key: &str) -> PyResult<()> {
+        match self.context.remove(key) {
+            Some(_) => Ok(()),
+            None => Err(PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.context.get(key).is_some()
+    }
+
+    /// Value of `key`, or `default` if it is not set
+    #[pyo3(signature = (key, default=None))]
+    fn get<'py>(
+        &self,
+        py: Python<'py>,
+        key: &str,
+        default: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match self.context.get(key) {
+            Some(value) => from_json(py, &value),
+            None => Ok(default.unwrap_or_else(|| py.None().into_bound(py))),
+        }
+    }
+
+    /// Keys set on the context
+    fn keys<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        PyList::new(py, self.context.values().keys())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<RequestContext {}>", self.context.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn ids_are_taken_from_the_header_or_generated() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("req-42"));
+        assert_eq!(Context::from_headers(&headers, None).id(), "req-42");
+
+        let generated = Context::from_headers(&HeaderMap::new(), None);
+        assert_eq!(generated.id().len(), 32);
+        assert_ne!(generated.id(), Context::new(None, None).id());
+        for invalid in ["", "has space", &"x".repeat(MAX_ID_LEN + 1)] {
+            assert_ne!(Context::new(Some(invalid), None).id(), invalid);
+        }
+    }
+
+    #[test]
+    fn deadlines_only_tighten() {
+        let context = Context::new(None, None);
+        assert_eq!(context.remaining(), None);
+        context.tighten_deadline(Duration::from_secs(10));
+        let deadline = context.deadline().unwrap();
+        context.tighten_deadline(Duration::from_secs(60));
+        assert_eq!(context.deadline(), Some(deadline));
+        context.tighten_deadline(Duration::ZERO);
+        assert_eq!(context.remaining(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn claims_name_the_subject() {
+        let context = Context::new(None, None);
+        assert_eq!(context.subject(), None);
+        context.set_claims(Some(json!({"user": "ada", "scopes": ["read"]})));
+        assert_eq!(context.subject().as_deref(), Some("ada"));
+        context.set_claims(Some(json!({"sub": 7, "user": "ada"})));
+        assert_eq!(context.subject().as_deref(), Some("7"));
+        context.insert("tenant", json!("acme"));
+        assert_eq!(
+            context.values(),
+            *json!({"tenant": "acme"}).as_object().unwrap()
+        );
+    }
+
+    #[test]
+    fn the_current_context_is_seen_by_python() {
+        let context = Arc::new(Context::new(Some("req-1"), None));
+        Python::attach(|py| {
+            let var = context_var(py).unwrap();
+            let seen = with_current(py, &context, || {
+                let current = var.call_method0("get")?;
+                current.set_item("tenant", "acme")?;
+                current.getattr("request_id")?.extract::<String>()
+            })
+            .unwrap();
+            assert_eq!(seen, "req-1");
+            assert!(var.call_method0("get").unwrap().is_none());
+        });
+        assert_eq!(context.get("tenant"), Some(json!("acme")));
+    }
+}
@@ -0,0 +1,169 @@
+//! Caching of handler responses to GET requests
+//!
+//! With a response cache set, a `200` response to a `GET` request is kept
+//! in a [`Cache`] under the request's method, path and query string, e.g.
+//! `GET /users?page=2`, and repeated requests are answered from it without
+//! calling the handler. Cached responses are served before the middleware
+//! chain, so middleware does not run for them either. Requests carrying
+//! credentials are never cached, nor are responses that set cookies, ask not
+//! to be stored, or carry `Vary`: the key holds no request headers, so a
+//! response that depends on them could be served to the wrong client.
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{AUTHORIZATION, CACHE_CONTROL, COOKIE, SET_COOKIE, VARY};
+use hyper::{HeaderMap, Method, Request, Response, StatusCode};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::{Cache, CacheValue};
+
+/// Response kept by the response cache.
+#[derive(Clone)]
+pub struct CachedResponse {
+    /// Path of the route that answered, to label the route's metrics.
+    pub route: Arc<str>,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// Responses of the HTTP server's handlers kept in a [`Cache`].
+pub struct ResponseCache {
+    cache: Arc<Cache<CacheValue>>,
+    ttl: Option<Duration>,
+}
+
+impl ResponseCache {
+    /// Keep responses in `cache` for `ttl`, or the cache's default TTL.
+    pub fn new(cache: Arc<Cache<CacheValue>>, ttl: Option<Duration>) -> Self {
+        Self { cache, ttl }
+    }
+
+    /// Key of the cached response to `req`, or None if it may not be
+    /// answered from the cache.
+    pub fn key(req: &Request<Incoming>) -> Option<String> {
+        let headers = req.headers();
+        if req.method() != Method::GET
+            || headers.contains_key(AUTHORIZATION)
+            || headers.contains_key(COOKIE)
+        {
+            return None;
+        }
+        let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+        Some(format!("GET {path}"))
+    }
+
+    /// Route and a copy of the response cached under `key`.
+    pub fn get(&self, key: &str) -> Option<(Arc<str>, Response<Full<Bytes>>)> {
+        let cached = self.cache.get(key, |value| match value {
+            CacheValue::Response(response) => Some(response.clone()),
+            CacheValue::Object(_) => None,
+        })??;
+        let mut response = Response::new(Full::new(cached.body));
+        *response.status_mut() = cached.status;
+        *response.headers_mut() = cached.headers;
+        Some((cached.route, response))
+    }
+
+    /// Cache `response`, answered by `route`, under `key` if it may be
+    /// reused, and hand it back.
+    pub async fn store(
+        &self,
+        key: String,
+        route: &Arc<str>,
+        response: Response<Full<Bytes>>,
+    ) -> Response<Full<Bytes>> {
+        if !cacheable(&response) {
+            return response;
+        }
+        let (parts, body) = response.into_parts();
+        let Ok(body) = body.collect().await.map(|collected| collected.to_bytes());
+        let size = body.len()
+            + parts
+                .headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum::<usize>();
+        let cached = CachedResponse {
+            route: route.clone(),
+            status: parts.status,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+        };
+        self.cache
+            .insert(key, CacheValue::Response(cached), size, self.ttl);
+        Response::from_parts(parts, Full::new(body))
+    }
+}
+
+/// Whether `response` may be reused for other clients.
+fn cacheable(response: &Response<Full<Bytes>>) -> bool {
+    let headers = response.headers();
+    let forbidden = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| {
+            let directive = directive.trim();
+            ["no-store", "no-cache", "private"]
+                .iter()
+                .any(|forbidden| directive.eq_ignore_ascii_case(forbidden))
+        });
+    response.status() == StatusCode::OK
+        && !forbidden
+        && !headers.contains_key(SET_COOKIE)
+        && !headers.contains_key(VARY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn only_reusable_responses_are_cached() {
+        let response = |status: u16, header: Option<(&'static str, &'static str)>| {
+            let mut response = Response::new(Full::new(Bytes::from_static(b"{}")));
+            *response.status_mut() = StatusCode::from_u16(status).unwrap();
+            if let Some((name, value)) = header {
+                response
+                    .headers_mut()
+                    .insert(name, HeaderValue::from_static(value));
+            }
+            response
+        };
+        assert!(cacheable(&response(200, None)));
+        assert!(cacheable(&response(
+            200,
+            Some(("cache-control", "max-age=60"))
+        )));
+        assert!(!cacheable(&response(201, None)));
+        assert!(!cacheable(&response(
+            200,
+            Some(("cache-control", "max-age=60, Private"))
+        )));
+        assert!(!cacheable(&response(
+            200,
+            Some(("set-cookie", "session=1"))
+        )));
+        assert!(!cacheable(&response(
+            200,
+            Some(("vary", "Accept-Encoding"))
+        )));
+
+        let cache = ResponseCache::new(Arc::new(Cache::new(4096, 1, None)), None);
+        let route: Arc<str> = "/users".into();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let stored =
+            runtime.block_on(cache.store("GET /users".into(), &route, response(200, None)));
+        assert_eq!(stored.status(), StatusCode::OK);
+        let (cached_route, cached) = cache.get("GET /users").unwrap();
+        assert_eq!(&*cached_route, "/users");
+        assert_eq!(cached.status(), StatusCode::OK);
+        assert!(cache.get("GET /users?page=2").is_none());
+    }
+}
@@ -0,0 +1,231 @@
+//! Per-route request latency histograms
+//!
+//! Latencies are kept in log-linear buckets in the style of HdrHistogram:
+//! values below `2 * SUB_BUCKETS` microseconds get a bucket each, and every
+//! power of two above is split into `SUB_BUCKETS` equal buckets. A recorded
+//! latency is therefore off by at most 1/32 of itself whatever its size, and
+//! each histogram has a fixed footprint.
+
+use hyper::Method;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const SUB_BUCKET_BITS: u32 = 5;
+
+/// Buckets per power of two.
+pub const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+/// Longest latency told apart, in microseconds (about 19 hours). Longer
+/// requests are recorded as this.
+pub const MAX_LATENCY_US: u64 = (1 << 36) - 1;
+
+const BUCKETS: usize = bucket_index(MAX_LATENCY_US) + 1;
+
+const fn bucket_index(micros: u64) -> usize {
+    if micros < 2 * SUB_BUCKETS {
+        return micros as usize;
+    }
+    let shift = 63 - micros.leading_zeros() - SUB_BUCKET_BITS;
+    let sub = (micros >> shift) - SUB_BUCKETS;
+    (SUB_BUCKETS * (shift as u64 + 1) + sub) as usize
+}
+
+/// Largest latency in microseconds that lands in bucket `index`.
+const fn bucket_upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < 2 * SUB_BUCKETS {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let sub = index % SUB_BUCKETS;
+    ((SUB_BUCKETS + sub + 1) << shift) - 1
+}
+
+/// Latency histogram of one (method, route, status) combination.
+struct LatencyHistogram {
+    buckets: Box<[AtomicU64; BUCKETS]>,
+    count: AtomicU64,
+    sum_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: Box::new([const { AtomicU64::new(0) }; BUCKETS]),
+            count: AtomicU64::new(0),
+            sum_us: AtomicU64::new(0),
+            max_us: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros())
+            .unwrap_or(u64::MAX)
+            .min(MAX_LATENCY_US);
+        self.buckets[bucket_index(micros)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(micros, Ordering::Relaxed);
+        self.max_us.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Estimate a quantile as the upper bound of the bucket holding its rank,
+    /// capped at the slowest request seen.
+    fn quantile_us(&self, quantile: f64, count: u64, max_us: u64) -> u64 {
+        if count == 0 {
+            return 0;
+        }
+        let rank = ((quantile * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= rank {
+                return bucket_upper_bound(index).min(max_us);
+            }
+        }
+        max_us
+    }
+}
+
+/// Latency summary of the requests to one route that got one status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteLatency {
+    /// HTTP method, e.g. `GET`.
+    pub method: String,
+    /// Route pattern as registered, e.g. `/items/{id:int}`.
+    pub route: String,
+    /// Response status.
+    pub status: u16,
+    /// Requests recorded.
+    pub count: u64,
+    /// Total latency in milliseconds.
+    pub sum_ms: f64,
+    /// Median latency in milliseconds.
+    pub p50_ms: f64,
+    /// 95th percentile latency in milliseconds.
+    pub p95_ms: f64,
+    /// 99th percentile latency in milliseconds.
+    pub p99_ms: f64,
+    /// Slowest request in milliseconds.
+    pub max_ms: f64,
+}
+
+type RouteKey = (Method, String, u16);
+
+/// Histograms of every route served in the process, shared by all servers.
+static ROUTE_LATENCIES: Lazy<RwLock<HashMap<RouteKey, LatencyHistogram>>> =
+    Lazy::new(Default::default);
+
+/// Record the latency of a request matched to `route` that got `status`.
+pub fn record(method: &Method, route: &str, status: u16, latency: Duration) {
+    let key = (method.clone(), route.to_string(), status);
+    if let Some(histogram) = ROUTE_LATENCIES.read().get(&key) {
+        histogram.record(latency);
+        return;
+    }
+    ROUTE_LATENCIES
+        .write()
+        .entry(key)
+        .or_default()
+        .record(latency);
+}
+
+/// Latency summaries ordered by route, method and status.
+pub fn snapshot() -> Vec<RouteLatency> {
+    let ms = |micros: u64| micros as f64 / 1000.0;
+    let mut routes: Vec<RouteLatency> = ROUTE_LATENCIES
+        .read()
+        .iter()
+        .map(|((method, route, status), histogram)| {
+            let count = histogram.count.load(Ordering::Relaxed);
+            let max_us = histogram.max_us.load(Ordering::Relaxed);
+            let quantile = |q| ms(histogram.quantile_us(q, count, max_us));
+            RouteLatency {
+                method: method.to_string(),
+                route: route.clone(),
+                status: *status,
+                count,
+                sum_ms: ms(histogram.sum_us.load(Ordering::Relaxed)),
+                p50_ms: quantile(0.5),
+                p95_ms: quantile(0.95),
+                p99_ms: quantile(0.99),
+                max_ms: ms(max_us),
+            }
+        })
+        .collect();
+    routes.sort_by(|a, b| (&a.route, &a.method, a.status).cmp(&(&b.route, &b.method, b.status)));
+    routes
+}
+
+/// Return per-route latency summaries as a list of dicts with method, route,
+/// status, count, sum_ms, p50_ms, p95_ms, p99_ms and max_ms.
+#[pyfunction]
+pub fn route_stats(py: Python<'_>) -> PyResult<Py<PyList>> {
+    let list = PyList::empty(py);
+    for route in snapshot() {
+        let dict = PyDict::new(py);
+        dict.set_item("method", route.method)?;
+        dict.set_item("route", route.route)?;
+        dict.set_item("status", route.status)?;
+        dict.set_item("count", route.count)?;
+        dict.set_item("sum_ms", route.sum_ms)?;
+        dict.set_item("p50_ms", route.p50_ms)?;
+        dict.set_item("p95_ms", route.p95_ms)?;
+        dict.set_item("p99_ms", route.p99_ms)?;
+        dict.set_item("max_ms", route.max_ms)?;
+        list.append(dict)?;
+    }
+    Ok(list.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_bound_relative_error() {
+        for micros in [0, 1, 63, 64, 65, 127, 128, 1_000, 123_456, MAX_LATENCY_US] {
+            let index = bucket_index(micros);
+            assert!(index < BUCKETS);
+            let upper = bucket_upper_bound(index);
+            assert!(upper >= micros);
+            assert!((upper - micros) * SUB_BUCKETS <= micros.max(1));
+            if index > 0 {
+                assert!(bucket_upper_bound(index - 1) < micros);
+            }
+        }
+    }
+
+    #[test]
+    fn routes_report_percentiles_per_status() {
+        let route = "/route-metrics-test/{id:int}";
+        for millis in 1..=100 {
+            record(&Method::GET, route, 200, Duration::from_millis(millis));
+        }
+        record(&Method::GET, route, 500, Duration::from_millis(7));
+
+        let stats: Vec<_> = snapshot()
+            .into_iter()
+            .filter(|r| r.route == route)
+            .collect();
+        assert_eq!(stats.len(), 2);
+        let ok = &stats[0];
+        assert_eq!((ok.method.as_str(), ok.status, ok.count), ("GET", 200, 100));
+        assert!((ok.p50_ms - 50.0).abs() <= 50.0 / SUB_BUCKETS as f64);
+        assert!((ok.p95_ms - 95.0).abs() <= 95.0 / SUB_BUCKETS as f64);
+        assert!((ok.p99_ms - 99.0).abs() <= 99.0 / SUB_BUCKETS as f64);
+        assert_eq!(ok.max_ms, 100.0);
+        assert_eq!(ok.sum_ms, 5050.0);
+        assert_eq!(
+            (stats[1].status, stats[1].count, stats[1].p99_ms),
+            (500, 1, 7.0)
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Requests slower than a server's configured threshold
+//!
+//! Each one is logged as a `warn` event on this module's target with its
+//! route, a hash of its parameters and where the time went: waiting for a
+//! handler thread, running the handler and turning its result into a
+//! response. Slow requests are also counted per route, so tail latency
+//! offenders stand out in the metrics without tracing every request.
+
+use hyper::Method;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+/// Where a handled request's time went.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HandlerTimings {
+    /// Waiting for a handler thread.
+    pub queue: Duration,
+    /// Calling the Python handler.
+    pub handler: Duration,
+    /// Turning the handler's result into a response.
+    pub serialization: Duration,
+}
+
+/// Hash identifying a request's path parameters and query string without
+/// logging their values. Stable for the life of the process.
+pub fn params_hash(params: &[String], query: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    params.hash(&mut hasher);
+    query.hash(&mut hasher);
+    hasher.finish()
+}
+
+type SlowKey = (Method, String);
+
+/// Slow requests per method and route, shared by all servers.
+static SLOW_REQUESTS: Lazy<RwLock<HashMap<SlowKey, AtomicU64>>> = Lazy::new(Default::default);
+
+/// Log and count a request that took `total`. `route` is the matched route
+/// pattern, if any; unmatched requests are logged with their `path`.
+pub fn record(
+    method: &Method,
+    path: &str,
+    route: Option<&str>,
+    status: u16,
+    total: Duration,
+    params_hash: Option<u64>,
+    timings: Option<&HandlerTimings>,
+) {
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let timings = timings.copied().unwrap_or_default();
+    warn!(
+        %method,
+        route = route.unwrap_or(path),
+        status,
+        total_ms = ms(total),
+        queue_ms = ms(timings.queue),
+        handler_ms = ms(timings.handler),
+        serialization_ms = ms(timings.serialization),
+        params_hash = %params_hash.map(|hash| format!("{hash:016x}")).unwrap_or_default(),
+        "slow request"
+    );
+
+    let key = (method.clone(), route.unwrap_or("").to_string());
+    if let Some(count) = SLOW_REQUESTS.read().get(&key) {
+        count.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+    SLOW_REQUESTS
+        .write()
+        .entry(key)
+        .or_default()
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+/// Slow request counts as (method, route, count), ordered by route and
+/// method. Requests no route matched are counted under an empty route.
+pub fn snapshot() -> Vec<(String, String, u64)> {
+    let mut counts: Vec<_> = SLOW_REQUESTS
+        .read()
+        .iter()
+        .map(|((method, route), count)| {
+            (
+                method.to_string(),
+                route.clone(),
+                count.load(Ordering::Relaxed),
+            )
+        })
+        .collect();
+    counts.sort_by(|a, b| (&a.1, &a.0).cmp(&(&b.1, &b.0)));
+    counts
+}
+
+/// Return how many requests exceeded their server's slow request threshold,
+/// as a list of dicts with method, route and count.
+#[pyfunction]
+pub fn slow_request_stats(py: Python<'_>) -> PyResult<Py<PyList>> {
+    let list = PyList::empty(py);
+    for (method, route, count) in snapshot() {
+        let dict = PyDict::new(py);
+        dict.set_item("method", method)?;
+        dict.set_item("route", route)?;
+        dict.set_item("count", count)?;
+        list.append(dict)?;
+    }
+    Ok(list.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_requests_are_counted_per_route() {
+        let route = "/slow_requests_test/{id:int}";
+        let count = || {
+            snapshot()
+                .into_iter()
+                .find(|(method, r, _)| method == "GET" && r == route)
+                .map_or(0, |(_, _, count)| count)
+        };
+        let timings = HandlerTimings {
+            handler: Duration::from_millis(40),
+            ..HandlerTimings::default()
+        };
+        let total = Duration::from_millis(50);
+        record(
+            &Method::GET,
+            "/slow_requests_test/1",
+            Some(route),
+            200,
+            total,
+            None,
+            Some(&timings),
+        );
+        record(
+            &Method::GET,
+            "/slow_requests_test/2",
+            Some(route),
+            200,
+            total,
+            None,
+            None,
+        );
+        assert_eq!(count(), 2);
+    }
+
+    #[test]
+    fn params_hash_tells_inputs_apart() {
+        let params = ["1".to_string()];
+        assert_eq!(params_hash(&params, "q=a"), params_hash(&params, "q=a"));
+        assert_ne!(params_hash(&params, "q=a"), params_hash(&params, "q=b"));
+        assert_ne!(
+            params_hash(&params, ""),
+            params_hash(&["2".to_string()], "")
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Live snapshot of engine internals served at [`STATS_PATH`]
+//!
+//! Meant for debugging a running server: connection counts and traffic,
+//! Rayon pool activity, memory pools, the route table and the depths of the queues work
+//! waits in. The endpoint is off until a token is configured, and requests
+//! must present it as `Authorization: Bearer <token>`.
+
+use hyper::HeaderMap;
+use hyper::header::AUTHORIZATION;
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::async_compute::Priority;
+use crate::compute::rayon_metrics;
+use crate::compute::resource_limits::OP_QUEUE;
+use crate::compute::thread_pool::ThreadPoolManager;
+use crate::memory::pool_allocator;
+use crate::runtime_manager;
+
+/// Path of the stats endpoint.
+pub const STATS_PATH: &str = "/__forzium/stats";
+
+/// Connection counters of one server.
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    /// Connections currently open.
+    pub active: AtomicUsize,
+    /// Connections accepted since the server was created.
+    pub accepted: AtomicU64,
+    /// Connections closed straight away because the limit was reached.
+    pub rejected: AtomicU64,
+    /// Bytes received on all connections.
+    pub bytes_read: AtomicU64,
+    /// Bytes sent on all connections.
+    pub bytes_written: AtomicU64,
+    /// Time from a request's first byte arriving to the last one read before
+    /// its response started; long when clients send slowly.
+    pub receive: TimingStats,
+    /// Time from a request's last byte arriving to the first byte of its
+    /// response; long when handlers are slow.
+    pub time_to_first_byte: TimingStats,
+}
+
+/// Count, total and maximum of a duration measured repeatedly.
+#[derive(Debug, Default)]
+pub struct TimingStats {
+    count: AtomicU64,
+    total_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+/// Values of a [`TimingStats`] at one point.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimingSnapshot {
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl TimingStats {
+    pub fn record(&self, duration: Duration) {
+        let nanos = duration.as_nanos() as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_ns.fetch_add(nanos, Ordering::Relaxed);
+        self.max_ns.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> TimingSnapshot {
+        TimingSnapshot {
+            count: self.count.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.total_ns.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max_ns.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Count, mean and maximum as a JSON object, in milliseconds.
+    fn to_json(&self) -> Value {
+        let snapshot = self.snapshot();
+        let mean = match snapshot.count {
+            0 => Duration::ZERO,
+            count => snapshot.total.div_f64(count as f64),
+        };
+        json!({
+            "count": snapshot.count,
+            "mean_ms": mean.as_secs_f64() * 1000.0,
+            "max_ms": snapshot.max.as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+/// The endpoint as configured on a server.
+pub struct StatsEndpoint {
+    pub token: String,
+    pub connections: Arc<ConnectionStats>,
+    pub connection_limit: usize,
+}
+
+impl StatsEndpoint {
+    /// The snapshot if `headers` carry the token, `None` otherwise.
+    pub fn respond(&self, headers: &HeaderMap, routes: &[(String, usize)]) -> Option<Value> {
+        authorized(headers, &self.token)
+            .then(|| snapshot(&self.connections, self.connection_limit, routes))
+    }
+}
+
+/// Whether `headers` carry `token` as a bearer token. Compares in constant
+/// time so the token cannot be guessed byte by byte.
+pub fn authorized(headers: &HeaderMap, token: &str) -> bool {
+    let Some(presented) = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    let (presented, token) = (presented.trim().as_bytes(), token.as_bytes());
+    presented.len() == token.len()
+        && presented
+            .iter()
+            .zip(token)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// The stats snapshot: `connections` of the serving server, `routes` counted
+/// per method, then process-wide `rayon` pools, `memory_pools` and `queues`.
+pub fn snapshot(connections: &ConnectionStats, limit: usize, routes: &[(String, usize)]) -> Value {
+    let rayon: Map<String, Value> = rayon_metrics::pool_snapshots()
+        .into_iter()
+        .map(|(pool, snapshot)| {
+            let entry = json!({
+                "observed_threads": snapshot.observed_threads,
+                "max_active_threads": snapshot.max_active_threads,
+                "utilization_percent": snapshot.utilization_percent,
+                "tasks_started": snapshot.total_tasks_started,
+                "tasks_completed": snapshot.total_tasks_completed,
+                "p50_task_duration_us": snapshot.p50_task_duration_us,
+                "p99_task_duration_us": snapshot.p99_task_duration_us,
+            });
+            (pool, entry)
+        })
+        .collect();
+
+    let memory_pools: Vec<Value> = pool_allocator::live_pool_stats()
+        .into_iter()
+        .map(|stats| {
+            json!({
+                "capacity": stats.capacity,
+                "used": stats.used,
+                "peak_usage": stats.peak_usage,
+                "alloc_count": stats.alloc_count,
+                "dealloc_count": stats.dealloc_count,
+                "contention_count": stats.contention_count,
+            })
+        })
+        .collect();
+
+    let mut thread_pools = Map::new();
+    if let Some(manager) = ThreadPoolManager::try_global() {
+        for (pool, threads) in manager.pool_sizes() {
+            let queued: Map<String, Value> = Priority::ALL
+                .iter()
+                .zip(manager.queued_by_priority(&pool))
+                .map(|(priority, queued)| (priority.name().to_string(), queued.into()))
+                .collect();
+            thread_pools.insert(pool, json!({ "threads": threads, "queued": queued }));
+        }
+    }
+    let op_queue = OP_QUEUE.stats();
+    let runtime = runtime_manager::snapshot();
+
+    json!({
+        "connections": {
+            "active": connections.active.load(Ordering::Relaxed),
+            "limit": limit,
+            "accepted": connections.accepted.load(Ordering::Relaxed),
+            "rejected": connections.rejected.load(Ordering::Relaxed),
+            "bytes_read": connections.bytes_read.load(Ordering::Relaxed),
+            "bytes_written": connections.bytes_written.load(Ordering::Relaxed),
+            "receive": connections.receive.to_json(),
+            "time_to_first_byte": connections.time_to_first_byte.to_json(),
+        },
+        "routes": {
+            "total": routes.iter().map(|(_, count)| count).sum::<usize>(),
+            "by_method": routes
+                .iter()
+                .map(|(method, count)| (method.clone(), Value::from(*count)))
+                .collect::<Map<String, Value>>(),
+        },
+        "rayon": rayon,
+        "memory_pools": memory_pools,
+        "queues": {
+            "operations": {
+                "depth": op_queue.depth,
+                "peak_depth": op_queue.peak_depth,
+                "rejected": op_queue.rejected,
+                "timeouts": op_queue.timeouts,
+            },
+            "thread_pools": thread_pools,
+            "runtime": {
+                "alive_tasks": runtime.alive_tasks,
+                "global_queue_depth": runtime.global_queue_depth,
+                "blocking_jobs_active": runtime.blocking_jobs_active,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn only_the_bearer_token_is_authorized() {
+        let mut headers = HeaderMap::new();
+        assert!(!authorized(&headers, "s3cret"));
+        for (value, expected) in [
+            ("Bearer s3cret", true),
+            ("Bearer s3cre", false),
+            ("Bearer s3cretx", false),
+            ("Basic s3cret", false),
+        ] {
+            headers.insert(AUTHORIZATION, HeaderValue::from_static(value));
+            assert_eq!(authorized(&headers, "s3cret"), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn snapshot_reports_connections_and_routes() {
+        let connections = ConnectionStats::default();
+        connections.active.store(2, Ordering::Relaxed);
+        connections.rejected.store(1, Ordering::Relaxed);
+        connections.bytes_read.store(512, Ordering::Relaxed);
+        for ms in [4, 2] {
+            connections
+                .time_to_first_byte
+                .record(Duration::from_millis(ms));
+        }
+        let routes = [("GET".to_string(), 3), ("POST".to_string(), 1)];
+        let snapshot = snapshot(&connections, 100, &routes);
+        assert_eq!(snapshot["connections"]["active"], 2);
+        assert_eq!(snapshot["connections"]["rejected"], 1);
+        assert_eq!(snapshot["connections"]["limit"], 100);
+        assert_eq!(snapshot["connections"]["bytes_read"], 512);
+        let first_byte = &snapshot["connections"]["time_to_first_byte"];
+        assert_eq!(first_byte["count"], 2);
+        assert_eq!(first_byte["mean_ms"], 3.0);
+        assert_eq!(first_byte["max_ms"], 4.0);
+        assert_eq!(snapshot["connections"]["receive"]["count"], 0);
+        assert_eq!(snapshot["routes"]["total"], 4);
+        assert_eq!(snapshot["routes"]["by_method"]["GET"], 3);
+        assert!(snapshot["queues"]["operations"]["depth"].is_u64());
+        assert!(snapshot["memory_pools"].is_array());
+    }
+}
@@ -0,0 +1,111 @@
+//! W3C trace context (`traceparent`) parsing and generation
+//!
+//! Shared by the OTLP exporter, which continues the trace of incoming
+//! requests, and the HTTP client, which propagates it to outgoing ones.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Trace and parent span ids of a W3C `traceparent` header value.
+pub fn parse_traceparent(value: &str) -> Option<([u8; 16], [u8; 8])> {
+    let mut fields = value.trim().split('-');
+    let version = fields.next()?;
+    let trace_id = decode_hex::<16>(fields.next()?)?;
+    let span_id = decode_hex::<8>(fields.next()?)?;
+    decode_hex::<1>(fields.next()?)?;
+    if decode_hex::<1>(version)? == [0xff] || trace_id == [0; 16] || span_id == [0; 8] {
+        return None;
+    }
+    Some((trace_id, span_id))
+}
+
+/// `traceparent` of a new span that is a child of `parent`, a `traceparent`
+/// header value, or the root of a new sampled trace if `parent` is missing
+/// or invalid.
+pub fn child_traceparent(parent: Option<&str>) -> String {
+    let span_id = random_u64().to_be_bytes();
+    match parent.and_then(|value| Some((parse_traceparent(value)?, value))) {
+        Some(((trace_id, _), value)) => {
+            let flags = value.trim().rsplit('-').next().unwrap_or("01");
+            format!(
+                "00-{}-{}-{flags}",
+                encode_hex(&trace_id),
+                encode_hex(&span_id)
+            )
+        }
+        None => format!(
+            "00-{}-{}-01",
+            encode_hex(&random_trace_id()),
+            encode_hex(&span_id)
+        ),
+    }
+}
+
+/// A new random trace id.
+pub fn random_trace_id() -> [u8; 16] {
+    let mut trace_id = [0; 16];
+    trace_id[..8].copy_from_slice(&random_u64().to_be_bytes());
+    trace_id[8..].copy_from_slice(&random_u64().to_be_bytes());
+    trace_id
+}
+
+/// Random id; `RandomState` is seeded per thread and advanced on each call.
+pub fn random_u64() -> u64 {
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(CALLS.fetch_add(1, Ordering::Relaxed));
+    hasher.finish().max(1)
+}
+
+fn decode_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != 2 * N || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traceparent_headers_continue_traces() {
+        let (trace_id, span_id) =
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(trace_id[..2], [0x4b, 0xf9]);
+        assert_eq!(span_id[7], 0xb7);
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473g-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(parse_traceparent(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn child_spans_keep_the_trace_and_flags() {
+        let parent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00";
+        let child = child_traceparent(Some(parent));
+        assert!(child.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(child.ends_with("-00"));
+        let (_, span_id) = parse_traceparent(&child).unwrap();
+        assert_ne!(span_id, parse_traceparent(parent).unwrap().1);
+
+        let root = child_traceparent(Some("garbage"));
+        let (trace_id, _) = parse_traceparent(&root).unwrap();
+        assert_ne!(trace_id, parse_traceparent(parent).unwrap().0);
+        assert!(root.ends_with("-01"));
+    }
+}
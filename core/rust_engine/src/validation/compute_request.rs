@@ -3,8 +3,37 @@ use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use std::collections::HashMap;
 
+use crate::bindings::models::{Fields, PyModel, extract_model, model_into_py};
+
+/// Fields of a compute request.
+struct ComputeRequest {
+    data: Vec<Vec<f64>>,
+    operation: String,
+    parameters: HashMap<String, Py<PyAny>>,
+}
+
+impl PyModel for ComputeRequest {
+    const FIELDS: &'static [&'static str] = &["data", "operation", "parameters"];
+
+    fn from_fields(fields: &Fields<'_>) -> PyResult<Self> {
+        Ok(ComputeRequest {
+            data: fields.required(0)?,
+            operation: fields.required(1)?,
+            parameters: fields.optional(2)?.unwrap_or_default(),
+        })
+    }
+
+    fn into_fields<'py>(self, py: Python<'py>) -> PyResult<Vec<Option<Bound<'py, PyAny>>>> {
+        Ok(vec![
+            Some(self.data.into_pyobject(py)?.into_any()),
+            Some(self.operation.into_pyobject(py)?.into_any()),
+            Some(self.parameters.into_pyobject(py)?.into_any()),
+        ])
+    }
+}
+
 /// Schema validator for ComputeRequest.
-#[pyclass]
+#[pyclass(frozen)]
 pub struct ComputeRequestSchema;
 
 #[pymethods]
@@ -14,40 +43,22 @@ impl ComputeRequestSchema {
         Self
     }
 
-    /// Validate input data and return a dict on success.
+    /// Validate a dict or dataclass instance and return a dict on success.
     fn validate<'py>(
         &self,
         py: Python<'py>,
         input: &Bound<'py, PyAny>,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let mapping: HashMap<String, Py<PyAny>> = input.extract()?;
-
-        let data_obj = mapping
-            .get("data")
-            .ok_or_else(|| PyValueError::new_err("data field required"))?;
-        let data: Vec<Vec<f64>> = data_obj.extract(py)?;
+        let request: ComputeRequest = extract_model(input)?;
+        let data = &request.data;
         if data.is_empty() || data.iter().any(|r| r.len() != data[0].len()) {
             return Err(PyValueError::new_err(
                 "Data must be a non-empty rectangular matrix",
             ));
         }
 
-        let operation_obj = mapping
-            .get("operation")
-            .ok_or_else(|| PyValueError::new_err("operation field required"))?;
-        let operation: String = operation_obj.extract(py)?;
-
-        // Parameters optional dict
-        let params = match mapping.get("parameters") {
-            Some(obj) => obj.extract::<HashMap<String, Py<PyAny>>>(py)?,
-            None => HashMap::new(),
-        };
-
-        let out = PyDict::new(py);
-        out.set_item("data", data)?;
-        out.set_item("operation", operation)?;
-        out.set_item("parameters", params)?;
-        Ok(out)
+        let out = model_into_py(request, &py.get_type::<PyDict>())?;
+        Ok(out.cast_into::<PyDict>()?)
     }
 }
 
@@ -104,4 +115,20 @@ mod tests {
             assert!(schema.validate(py, &data).is_err());
         });
     }
+
+    #[test]
+    fn dataclass_request() {
+        Python::attach(|py| {
+            let schema = ComputeRequestSchema::new();
+            let code = c"import dataclasses\n@dataclasses.dataclass\nclass Request:\n    data: list\n    operation: str\n";
+            let module = PyModule::from_code(py, code, c"request.py", c"request").unwrap();
+            let request = module
+                .getattr("Request")
+                .unwrap()
+                .call1((vec![vec![1.0, 2.0]], "add"))
+                .unwrap();
+            let out = schema.validate(py, &request).unwrap();
+            assert!(out.get_item("parameters").unwrap().is_some());
+        });
+    }
 }
@@ -17,6 +17,7 @@ from .middleware import (
     HTTPSRedirectMiddleware,
     JWTMiddleware,
     RateLimitMiddleware,
+    RedisSessionMiddleware,
     RequestLoggerMiddleware,
     SessionMiddleware,
     SecurityHeadersMiddleware,
@@ -28,6 +29,7 @@ from .task_queue import CeleryTaskQueue, RedisTaskQueue
 from .testclient import Response as TestResponse
 from .testclient import TestClient
 from .websockets import WebSocket, WebSocketRoute
+from .workers import aggregate_metrics, run
 
 __all__ = [
     "__version__",
@@ -42,6 +44,7 @@ __all__ = [
     "TrustedHostMiddleware",
     "SessionMiddleware",
     "FileSessionMiddleware",
+    "RedisSessionMiddleware",
     "JWTMiddleware",
     "RateLimitMiddleware",
     "RequestLoggerMiddleware",
@@ -59,4 +62,6 @@ __all__ = [
     "TestClient",
     "TestResponse",
     "push",
+    "run",
+    "aggregate_metrics",
 ]
\ No newline at end of file
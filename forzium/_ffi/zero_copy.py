@@ -24,14 +24,14 @@ except ImportError:
     # Fallback implementations when Rust engine is unavailable
     def zero_copy_multiply(array: NDArrayFloat, factor: float) -> NDArrayFloat:
         """
-        Multiply a NumPy array by a factor in-place.
+        Multiply a NumPy array by a factor.
         
         Args:
             array: Input NumPy array
             factor: Multiplication factor
             
         Returns:
-            Modified array (same object as input)
+            New array with the same shape
         """
         # Note: This is not truly zero-copy in the fallback implementation
         return array * factor
@@ -114,24 +114,24 @@ except ImportError:
 
 def matrix_multiply_inplace(matrix: NDArrayFloat, factor: float) -> NDArrayFloat:
     """
-    Multiply a matrix by a factor in-place using zero-copy operations.
+    Multiply a matrix by a factor using zero-copy operations.
     
-    This function modifies the input array directly without creating a copy,
-    resulting in significant performance improvements for large arrays.
+    The Rust backend reads the input array through its buffer without copying
+    it to Rust and allocates only the result; the input is left unchanged.
     
     Args:
         matrix: 2D NumPy array of float64 values
         factor: Multiplication factor
         
     Returns:
-        Modified matrix (same object as input)
+        New matrix with the same shape
         
     Examples:
         >>> import numpy as np
         >>> from forzium._ffi.zero_copy import matrix_multiply_inplace
         >>> matrix = np.array([[1.0, 2.0], [3.0, 4.0]])
         >>> result = matrix_multiply_inplace(matrix, 2.0)
-        >>> print(result)  # matrix is also modified
+        >>> print(result)
         [[2. 4.]
          [6. 8.]]
     """
@@ -29,6 +29,7 @@ from urllib.parse import parse_qs
 
 T = TypeVar("T")
 
+import forzium_engine
 from infrastructure.monitoring import (
     current_trace_span,
     get_metric,
@@ -579,6 +580,214 @@ class ForziumApp:
         )
         self._asgi_middleware.append(middleware)
 
+    def apply_config(
+        self, updates: Any, *, source: str = "apply_config"
+    ) -> list[dict[str, Any]]:
+        """
+        Change settings of the running application without a restart.
+
+        The Rust server's request timeout, rate limits, resource limits and
+        log levels may change; other settings are only read at startup and
+        are refused. Nothing is applied if any setting is invalid. Each change is
+        logged and kept in ``forzium_engine.config_audit_log()``.
+
+        Args:
+            updates: A ``forzium_engine.ForziumConfig`` or a dict of sections,
+                e.g. ``{"rate_limit": {"limit": 200}}``
+            source: What made the change, recorded with it
+
+        Returns:
+            The changed settings, as dicts of ``key``, ``old`` and ``new``
+        """
+
+        server = self.server
+        if not isinstance(server, forzium_engine.ForziumHttpServer):
+            server = None
+        changes = forzium_engine.apply_config(updates, server, source=source)
+        rate_limit = {
+            change["key"].removeprefix("rate_limit."): change
+            for change in changes
+            if change["key"].startswith("rate_limit.")
+        }
+        limiter = next(
+            (
+                middleware
+                for middleware in self._asgi_middleware
+                if isinstance(middleware, RateLimitMiddleware)
+            ),
+            None,
+        )
+        if limiter is not None:
+            if "limit" in rate_limit:
+                rate_limit["limit"]["old"] = limiter.limit
+                limiter.limit = rate_limit["limit"]["new"]
+            if "window_secs" in rate_limit:
+                rate_limit["window_secs"]["old"] = limiter.window
+                limiter.window = rate_limit["window_secs"]["new"]
+        elif "limit" in rate_limit:
+            window = rate_limit.get("window_secs", {}).get("new", 1.0)
+            self._asgi_middleware.append(
+                RateLimitMiddleware(limit=rate_limit["limit"]["new"], window=window)
+            )
+        for change in changes:
+            payload = {"event": "config.changed", **change}
+            _LOGGER.info(json.dumps(payload, separators=(",", ":"), default=str))
+        return changes
+
+    def reload_config(self, path: str | os.PathLike[str]) -> list[dict[str, Any]]:
+        """
+        Reload a TOML or YAML configuration file and apply what changed.
+
+        ``FORZIUM_<SECTION>__<KEY>`` environment variables still take
+        precedence. Settings that need a restart must keep their values.
+
+        Args:
+            path: The configuration file
+
+        Returns:
+            The changed settings, as returned by ``apply_config``
+        """
+
+        config = forzium_engine.ForziumConfig.load(os.fspath(path))
+        return self.apply_config(config, source=os.fspath(path))
+
+    def reload_config_on_sighup(self, path: str | os.PathLike[str]) -> None:
+        """
+        Reload the configuration file ``path`` whenever the process receives SIGHUP.
+
+        Must be called from the main thread. A reload that fails is logged and
+        leaves the current settings in place.
+
+        Args:
+            path: The configuration file
+        """
+
+        import signal
+
+        sighup = getattr(signal, "SIGHUP", None)
+        if sighup is None:
+            raise RuntimeError("SIGHUP is not available on this platform")
+
+        def _reload(signum: int, frame: Any) -> None:
+            try:
+                self.reload_config(path)
+            except Exception:
+                _LOGGER.exception("configuration reload from %s failed", path)
+
+        signal.signal(sighup, _reload)
+
+    def add_proxy_route(
+        self,
+        path: str,
+        upstream: str,
+        *,
+        methods: list[str] | None = None,
+        request_headers: dict[str, str | None] | None = None,
+        response_headers: dict[str, str | None] | None = None,
+        preserve_host: bool = False,
+    ) -> None:
+        """
+        Forward requests matching ``path`` to another HTTP server.
+
+        Requests are forwarded by the Rust server without calling Python, so
+        middleware and dependencies do not run for them. A trailing
+        ``{name:path}`` parameter is appended to the upstream URL's path.
+
+        Args:
+            path: Route pattern, e.g. ``"/legacy/{rest:path}"``
+            upstream: ``http://`` URL of the upstream server
+            methods: Methods to forward, all of them if omitted
+            request_headers: Headers to set on forwarded requests, or to
+                remove if mapped to None
+            response_headers: Likewise for the upstream's responses
+            preserve_host: Send the client's ``Host`` instead of the upstream's
+        """
+
+        if not isinstance(self.server, forzium_engine.ForziumHttpServer):
+            raise RuntimeError("proxy routes need a ForziumHttpServer")
+        self.server.add_proxy_route(
+            path,
+            upstream,
+            methods=methods,
+            request_headers=request_headers,
+            response_headers=response_headers,
+            preserve_host=preserve_host,
+        )
+
+    def add_event_stream_route(
+        self,
+        path: str,
+        topic: str,
+        *,
+        capacity: int = 64,
+        policy: str = "drop_oldest",
+        heartbeat: float | None = 15.0,
+    ) -> None:
+        """
+        Stream messages published to ``topic`` to clients as Server-Sent Events.
+
+        Handlers publish with ``forzium_engine.publish(topic, data)``. Streams
+        are served by the Rust server without calling Python, so middleware
+        and dependencies do not run for them.
+
+        Args:
+            path: Route pattern, e.g. ``"/rooms/{room}/events"``
+            topic: Topic to stream; ``{name}`` is replaced with the path
+                parameter ``name``
+            capacity: Messages queued for a client that falls behind
+            policy: ``"drop_oldest"``, ``"drop_newest"`` or ``"disconnect"``,
+                applied when a client's queue is full
+            heartbeat: Seconds after which an idle stream gets a comment,
+                never if None
+        """
+
+        if not isinstance(self.server, forzium_engine.ForziumHttpServer):
+            raise RuntimeError("event-stream routes need a ForziumHttpServer")
+        self.server.add_event_stream_route(
+            path, topic, capacity=capacity, policy=policy, heartbeat=heartbeat
+        )
+
+    def mount_asgi(self, prefix: str, app: Any, *, lifespan: bool = True) -> None:
+        """
+        Serve requests under ``prefix`` with an ASGI application.
+
+        An existing Starlette or FastAPI app can be mounted at ``"/"`` and
+        its routes moved to this app one at a time: routes registered here
+        take precedence over the mounted app. The app sees ``prefix`` as its
+        ``root_path``. Mount apps before the server starts serving.
+
+        Args:
+            prefix: Path the app is mounted at, e.g. ``"/legacy"``
+            app: ASGI application callable
+            lifespan: Run the app's lifespan startup and shutdown with the
+                server
+        """
+
+        if not isinstance(self.server, forzium_engine.ForziumHttpServer):
+            raise RuntimeError("ASGI apps need a ForziumHttpServer")
+        self.server.mount_asgi(prefix, app, lifespan=lifespan)
+
+    def set_response_cache(
+        self, cache: "forzium_engine.ForziumCache | None", *, ttl: float | None = None
+    ) -> None:
+        """
+        Answer repeated GET requests from ``cache`` without calling handlers.
+
+        Cached responses are served by the Rust server, so middleware does not
+        run for them. Only 200 responses to requests without credentials are
+        kept, and never responses carrying ``Vary``, since the cache key holds
+        no request headers; see ``ForziumHttpServer.set_response_cache``.
+        Takes effect when the server starts.
+
+        Args:
+            cache: A ``forzium_engine.ForziumCache``, or None to stop caching
+            ttl: Seconds to keep each response, the cache's default if omitted
+        """
+
+        if not isinstance(self.server, forzium_engine.ForziumHttpServer):
+            raise RuntimeError("response caching needs a ForziumHttpServer")
+        self.server.set_response_cache(cache, ttl=ttl)
+
     def add_security_scheme(self, name: str, scheme: dict[str, Any]) -> None:
         """
         Register security scheme under the specified name.
@@ -1492,6 +1701,67 @@ SwaggerUIBundle({url: \"/openapi.json\", dom_id: \"#swagger-ui\"});
 
         return decorator
 
+    def add_critical_dependency(
+        self, dependency: Callable[..., Any], name: str | None = None
+    ) -> None:
+        """Hold the server's ``/ready`` endpoint at 503 until *dependency*
+        resolves.
+
+        The dependency is resolved like a ``Depends`` parameter, honouring
+        ``dependency_overrides``, and torn down straight away. The server
+        probes it on each readiness request, listing *name* (the callable's
+        name by default) among the failing components, until it succeeds once.
+        """
+        component = name or getattr(dependency, "__name__", repr(dependency))
+
+        async def resolve() -> None:
+            _, cleanup = await solve_dependencies(
+                [(component, dependency)], [self.dependency_overrides]
+            )
+            for close, is_async in reversed(cleanup):
+                if is_async:
+                    await cast(Awaitable[Any], close())
+                else:
+                    close()
+
+        def probe() -> None:
+            asyncio.run(resolve())
+
+        forzium_engine.register_dependency(component, probe)
+
+    def scheduled(
+        self,
+        *,
+        every: float | None = None,
+        cron: str | None = None,
+        name: str | None = None,
+        jitter: float = 0.0,
+        allow_overlap: bool = False,
+    ) -> Callable[[Callable[..., Any]], Callable[..., Any]]:
+        """Run the decorated function every ``every`` seconds, or at the
+        times of the ``cron`` expression in UTC.
+
+        The job is scheduled straight away on the engine's scheduler under
+        *name* (the function's qualified name by default) and stopped on
+        shutdown; see ``forzium_engine.schedule_job``. Coroutine functions
+        are run to completion on each run.
+        """
+
+        def decorator(func: Callable[..., Any]) -> Callable[..., Any]:
+            job = name or func.__qualname__
+            forzium_engine.schedule_job(
+                job,
+                func,
+                every=every,
+                cron=cron,
+                jitter=jitter,
+                allow_overlap=allow_overlap,
+            )
+            self._shutdown_hooks.append(lambda: forzium_engine.unschedule_job(job))
+            return func
+
+        return decorator
+
     async def startup(self) -> None:
         for hook in self._startup_hooks:
             result = hook()
@@ -1703,9 +1973,7 @@ SwaggerUIBundle({url: \"/openapi.json\", dom_id: \"#swagger-ui\"});
                     allowed_mime_types=self.allowed_mime_types,
                 )
                 req_obj.state.route = path
-                request_id = headers.get("x-request-id") if headers else None
-                if request_id:
-                    req_obj.state.request_id = request_id
+                req_obj.state.request_id = req_obj.context.request_id
                 if span_obj is not None:
                     span_obj.set_attribute("http.target", url)
                 if expects_request:
@@ -2080,7 +2348,44 @@ SwaggerUIBundle({url: \"/openapi.json\", dom_id: \"#swagger-ui\"});
         handler = self._apply_asgi_middleware(
             handler, param_names, method, path
         )  # type: ignore[assignment]
-        return handler
+        return self._bind_request_context(handler)
+
+    @staticmethod
+    def _bind_request_context(
+        handler: Callable[..., Any],
+    ) -> Callable[..., Any]:
+        """Run *handler* in a request context of its own when called outside
+        the engine's server.
+
+        The server sets ``forzium_engine.request_context`` itself; this gives
+        direct calls, such as the test client's, a context too.
+        """
+
+        def bound(
+            body: bytes,
+            params: tuple,
+            query: bytes,
+            headers: dict[str, str] | None = None,
+        ) -> Any:
+            if forzium_engine.request_context.get() is not None:
+                return handler(body, params, query, headers)
+            request_id = next(
+                (
+                    value
+                    for name, value in (headers or {}).items()
+                    if name.lower() == "x-request-id"
+                ),
+                None,
+            )
+            token = forzium_engine.request_context.set(
+                forzium_engine.RequestContext(request_id)
+            )
+            try:
+                return handler(body, params, query, headers)
+            finally:
+                forzium_engine.request_context.reset(token)
+
+        return bound
 
     def _apply_asgi_middleware(
         self,
@@ -2116,9 +2421,7 @@ SwaggerUIBundle({url: \"/openapi.json\", dom_id: \"#swagger-ui\"});
                     allowed_mime_types=self.allowed_mime_types,
                 )
                 req.state.route = path
-                request_id = req.headers.get("x-request-id")
-                if request_id:
-                    req.state.request_id = request_id
+                req.state.request_id = req.context.request_id
 
                 def call_next_sync(r: Request) -> HTTPResponse:
                     q = r.url.split("?", 1)[1] if "?" in r.url else ""
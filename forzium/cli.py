@@ -296,6 +296,21 @@ def _resolve_host_port(args: argparse.Namespace) -> tuple[str, int]:
 def _cmd_run(args: argparse.Namespace) -> None:
     host, port = _resolve_host_port(args)
     loaded = _load_app(getattr(args, "app_path", None))
+    workers = getattr(args, "workers", None) or 1
+    handover = getattr(args, "handover", None)
+    if workers > 1 or handover:
+        from .workers import run
+
+        run(
+            loaded.app,
+            host,
+            port,
+            workers=workers,
+            server=loaded.server,
+            reuse_port=getattr(args, "reuse_port", False),
+            handover=handover,
+        )
+        return
     _start_server(loaded, host, port, block=not getattr(args, "no_block", False))
 
 
@@ -334,6 +349,25 @@ def main(argv: list[str] | None = None) -> None:
         dest="app_path",
         help="Python path to the Forzium app, e.g. 'main:app'",
     )
+    run_parser.add_argument(
+        "--workers",
+        type=int,
+        default=int(os.getenv("FORZIUM_WORKERS", "1")),
+        help="Number of worker processes (default: FORZIUM_WORKERS or 1)",
+    )
+    run_parser.add_argument(
+        "--reuse-port",
+        action="store_true",
+        help="Bind a socket in every worker with SO_REUSEPORT instead of sharing one",
+    )
+    run_parser.add_argument(
+        "--handover",
+        default=os.getenv("FORZIUM_HANDOVER"),
+        help=(
+            "Unix socket path for zero-downtime restarts: a new process started "
+            "with the same path takes over the port (default: FORZIUM_HANDOVER)"
+        ),
+    )
     run_parser.add_argument(
         "--no-block",
         action="store_true",
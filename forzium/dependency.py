@@ -70,6 +70,17 @@ class Request:
         self.allowed_mime_types = allowed_mime_types
         self.state: SimpleNamespace = SimpleNamespace()
 
+    @property
+    def context(self) -> Any:
+        """Return the engine's ``RequestContext`` of the request being served.
+
+        It carries the request ID, deadline and authenticated claims, and
+        keys middleware set for handlers. None outside request handling.
+        """
+        import forzium_engine
+
+        return forzium_engine.request_context.get()
+
     async def body(self) -> bytes:
         """Return the request body."""
         return self._body
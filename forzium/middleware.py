@@ -15,6 +15,7 @@ import threading
 import time
 import uuid
 from collections import defaultdict, deque
+from contextvars import ContextVar
 from pathlib import Path
 from typing import TYPE_CHECKING, Any, Awaitable, Callable, Iterable
 from urllib.parse import parse_qs
@@ -233,6 +234,70 @@ class FileSessionMiddleware(BaseHTTPMiddleware):
         return status, body, headers
 
 
+class RedisSessionMiddleware(BaseHTTPMiddleware):
+    """Persist session data in Redis so every instance of the app shares it.
+
+    Sessions are stored as JSON under ``prefix`` followed by the session id,
+    using a ``forzium_engine.ForziumRedis`` client, and expire ``ttl``
+    seconds after the last response that saved them. The session of the
+    request being handled is kept in a context variable, as handlers of
+    concurrent requests share the middleware.
+    """
+
+    def __init__(
+        self,
+        redis: Any,
+        cookie_name: str = "session_id",
+        ttl: float = 86400.0,
+        prefix: str = "forzium:session:",
+    ) -> None:
+        self.redis = redis
+        self.cookie_name = cookie_name
+        self.ttl = ttl
+        self.prefix = prefix
+        self._session: ContextVar[tuple[str, dict[str, Any]] | None] = ContextVar(
+            f"redis_session_{id(self)}", default=None
+        )
+
+    def before_request(
+        self, body: bytes, params: tuple, query: bytes
+    ) -> tuple[bytes, tuple, bytes, tuple[int, str, dict[str, str]] | None]:
+        params_dict = parse_qs(query.decode()) if query else {}
+        sid = params_dict.pop(self.cookie_name, [""])[0] or uuid.uuid4().hex
+        stored = self.redis.get(self.prefix + sid).wait()
+        data = json.loads(stored) if stored else {}
+        self._session.set((sid, data))
+        params = (*params, data)
+        query = (
+            "&".join(f"{k}={v[0]}" for k, v in params_dict.items()).encode()
+            if params_dict
+            else b""
+        )
+        return body, params, query, None
+
+    def after_response(
+        self, status: int, body: str, headers: dict[str, str]
+    ) -> tuple[int, str, dict[str, str]]:
+        session = self._session.get()
+        if session is None:
+            return status, body, headers
+        self._session.set(None)
+        sid, data = session
+        self.redis.set(self.prefix + sid, json.dumps(data), ex=self.ttl).wait()
+        headers["set-cookie"] = f"{self.cookie_name}={sid}"
+        return status, body, headers
+
+
+def _record_claims(payload: dict[str, Any]) -> None:
+    """Set *payload* as the claims of the current request's context."""
+
+    import forzium_engine
+
+    context = forzium_engine.request_context.get()
+    if context is not None:
+        context.claims = payload
+
+
 class JWTMiddleware(BaseHTTPMiddleware):
     """Decode a JWT from query parameters and inject the payload."""
 
@@ -248,6 +313,8 @@ class JWTMiddleware(BaseHTTPMiddleware):
         payload = decode_jwt(token, self.secret) if token else None
         if self.require and not isinstance(payload, dict):
             return body, params, query, (401, "unauthorized", {})
+        if isinstance(payload, dict):
+            _record_claims(payload)
         user = payload.get("user") if isinstance(payload, dict) else None
         params = (*params, user)
         query = (
@@ -293,6 +360,7 @@ class JWTAuthMiddleware(JWTMiddleware):
             log_event(user or "", "forbidden")
             return body, params, query, (403, "forbidden", {})
         log_event(user or "", "authorized")
+        _record_claims(payload)
         params = (user, *params)
         query = (
             "&".join(f"{k}={v[0]}" for k, v in params_dict.items()).encode()
@@ -416,7 +484,14 @@ class RequestLoggerMiddleware:
 
 
 class RateLimitMiddleware:
-    """Enforce configurable request-per-window limits."""
+    """Enforce configurable request-per-window limits.
+
+    Limits are tracked in process unless ``redis``, a
+    ``forzium_engine.ForziumRedis`` client, is given: requests are then
+    counted in fixed windows of wall-clock time shared by every instance
+    using that server. If Redis cannot be reached the in-process limits
+    apply until it can.
+    """
 
     _RATE_LIMITED_METRIC = "requests_rate_limited_total"
 
@@ -430,6 +505,8 @@ class RateLimitMiddleware:
         identifier: Callable[[Request], str] | None = None,
         timer: Callable[[], float] | None = None,
         logger: logging.Logger | None = None,
+        redis: Any | None = None,
+        prefix: str = "forzium:ratelimit:",
     ) -> None:
         if limit <= 0:
             raise ValueError("limit must be positive")
@@ -444,6 +521,8 @@ class RateLimitMiddleware:
         self._history: defaultdict[str, deque[float]] = defaultdict(deque)
         self._lock = threading.Lock()
         self.logger = logger or logging.getLogger("forzium")
+        self.redis = redis
+        self.prefix = prefix
 
     def _client_identifier(self, request: Request) -> str:
         if self._identifier is not None:
@@ -540,6 +619,25 @@ class RateLimitMiddleware:
             self._log_rejection(request, retry_after, remaining, reset)
         return result
 
+    async def _acquire_shared(
+        self, request: Request
+    ) -> tuple[bool, float, int, float]:
+        now = time.time()
+        window = int(now // self.window)
+        reset = (window + 1) * self.window - now
+        key = f"{self.prefix}{self._key_for(request)}:{window}"
+        try:
+            count = await self.redis.incr(key)
+            if count == 1:
+                await self.redis.expire(key, self.window)
+        except Exception as exc:
+            self.logger.warning("rate limit counted locally, Redis failed: %s", exc)
+            return self._acquire(request)
+        if count > self.limit:
+            self._log_rejection(request, reset, 0, reset)
+            return False, reset, 0, reset
+        return True, 0.0, self.limit - count, reset
+
     def _attach_headers(self, response: HTTPResponse, remaining: int, reset: float) -> None:
         headers = getattr(response, "headers", None)
         if headers is None:
@@ -553,7 +651,11 @@ class RateLimitMiddleware:
         request: Request,
         call_next: Callable[[Request], HTTPResponse | Awaitable[HTTPResponse]],
     ) -> HTTPResponse:
-        allowed, retry_after, remaining, reset = self._acquire(request)
+        if self.redis is not None:
+            acquired = await self._acquire_shared(request)
+        else:
+            acquired = self._acquire(request)
+        allowed, retry_after, remaining, reset = acquired
         if not allowed:
             retry_after_seconds = max(0.0, retry_after)
             retry_after_header = str(max(0, math.ceil(retry_after_seconds)))
@@ -616,6 +718,7 @@ __all__ = [
     "TrustedHostMiddleware",
     "SessionMiddleware",
     "FileSessionMiddleware",
+    "RedisSessionMiddleware",
     "JWTMiddleware",
     "JWTAuthMiddleware",
     "LoggingMiddleware",
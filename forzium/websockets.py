@@ -109,3 +109,32 @@ class WebSocketServer(BroadcastChannel):
 
 
 __all__.append("WebSocketServer")
+
+
+async def relay(
+    ws: WebSocket,
+    *topics: str,
+    capacity: int = 64,
+    policy: str = "drop_oldest",
+) -> None:
+    """Send messages published to *topics* on the engine's broadcast hub to *ws*.
+
+    Returns once *ws* is closed or the subscription ends, e.g. because the
+    client fell behind under the ``"disconnect"`` policy. Messages are sent
+    as text, decoded as UTF-8.
+    """
+
+    import forzium_engine
+
+    subscription = forzium_engine.subscribe(*topics, capacity=capacity, policy=policy)
+    ws.add_close_callback(lambda _ws: subscription.close())
+    try:
+        async for _topic, data in subscription:
+            if ws.closed:
+                break
+            await ws.send_text(data.decode("utf-8", errors="replace"))
+    finally:
+        subscription.close()
+
+
+__all__.append("relay")
@@ -0,0 +1,530 @@
+"""Prefork worker processes serving one application.
+
+:func:`run` forks ``workers`` processes that serve the application on the
+same address: through one listening socket bound by the supervising parent
+and inherited by every worker, or through sockets each worker binds with
+``SO_REUSEPORT``. The parent restarts workers that exit unexpectedly and
+stops them all on SIGINT or SIGTERM.
+
+With a ``handover`` path, :func:`run` also supports zero-downtime restarts:
+a new process started with the same path takes over the listening socket of
+the running one, which then stops accepting, drains its connections and
+exits. No connection is refused in between, since the socket stays open.
+
+Every worker writes its engine metrics to a shared directory every few
+seconds; :func:`aggregate_metrics` combines them, so a metrics endpoint in
+any worker reports the whole service.
+"""
+
+from __future__ import annotations
+
+import asyncio
+import logging
+import os
+import shutil
+import signal
+import socket
+import tempfile
+import threading
+import time
+from contextlib import suppress
+from pathlib import Path
+from typing import Any, Callable
+
+import forzium_engine
+
+LOGGER = logging.getLogger("forzium.workers")
+
+#: Directory the workers of :func:`run` write their metrics to.
+METRICS_DIR_ENV = "FORZIUM_METRICS_DIR"
+#: Number of the worker a process is, from 0.
+WORKER_ID_ENV = "FORZIUM_WORKER_ID"
+
+_BACKLOG = 1024
+_POLL_INTERVAL = 0.1
+_HANDOVER_TIMEOUT = 30.0
+
+
+def run(
+    app: Any,
+    host: str = "127.0.0.1",
+    port: int = 8000,
+    *,
+    workers: int = 1,
+    server: Any | None = None,
+    reuse_port: bool = False,
+    metrics_interval: float = 5.0,
+    restart_delay: float = 1.0,
+    shutdown_timeout: float = 30.0,
+    handover: str | os.PathLike[str] | None = None,
+) -> None:
+    """
+    Serve ``app`` on ``host:port`` until SIGINT or SIGTERM.
+
+    With more than one worker the application is served by that many forked
+    processes (Unix only), supervised by this one; see :class:`WorkerManager`.
+    The application's startup and shutdown hooks run in every worker. Engine
+    threads do not survive a fork, so with several workers the parent must
+    not make compute, server or thread pool calls before this one.
+
+    With ``handover``, a process already serving with the same path hands its
+    listening socket over instead of ``host:port`` being bound, and is told
+    to stop once this one serves; see :class:`Handover`.
+
+    Args:
+        app: A ``ForziumApp``, or a ``ForziumHttpServer``
+        host: Interface to bind
+        port: Port to bind
+        workers: Number of processes serving requests
+        server: The server to serve ``app`` with, ``app.server`` by default
+        reuse_port: Let each worker bind its own socket with ``SO_REUSEPORT``
+            instead of sharing one bound by the parent
+        metrics_interval: Seconds between the metrics snapshots of workers
+        restart_delay: Seconds to wait before restarting a worker that exited
+        shutdown_timeout: Seconds workers get to drain before they are killed
+        handover: Path of the Unix socket used to hand the listening socket
+            to a replacement process
+    """
+
+    if workers < 1:
+        raise ValueError("workers must be at least 1")
+    if handover is not None and reuse_port:
+        raise ValueError("handover needs one shared socket, not reuse_port")
+    if workers == 1:
+        _run_single(app, _server_of(app, server), host, port, handover)
+        return
+    WorkerManager(
+        app,
+        host,
+        port,
+        workers=workers,
+        server=server,
+        reuse_port=reuse_port,
+        metrics_interval=metrics_interval,
+        restart_delay=restart_delay,
+        shutdown_timeout=shutdown_timeout,
+        handover=handover,
+    ).run()
+
+
+def aggregate_metrics(format: str = "prometheus") -> str:
+    """
+    Engine metrics of all workers of :func:`run`, combined.
+
+    Counters, gauges and summary sums and counts are added up; latency
+    quantiles are reported per worker with a ``worker`` label. Outside a
+    multi-worker :func:`run`, the metrics of this process.
+
+    Args:
+        format: "prometheus", "statsd" or "json"
+    """
+
+    directory = os.environ.get(METRICS_DIR_ENV)
+    if not directory:
+        return forzium_engine.export_metrics(format)
+    exports: dict[str, str] = {}
+    for path in Path(directory).glob("worker-*.json"):
+        with suppress(OSError):
+            exports[path.stem.removeprefix("worker-")] = path.read_text()
+    worker = os.environ.get(WORKER_ID_ENV)
+    if worker is not None:
+        # this worker's current metrics rather than its last snapshot
+        exports[worker] = forzium_engine.export_metrics("json")
+    return forzium_engine.merge_metrics(exports, format)
+
+
+class WorkerManager:
+    """Fork, supervise and stop the worker processes of :func:`run`."""
+
+    def __init__(
+        self,
+        app: Any,
+        host: str,
+        port: int,
+        *,
+        workers: int,
+        server: Any | None = None,
+        reuse_port: bool = False,
+        metrics_interval: float = 5.0,
+        restart_delay: float = 1.0,
+        shutdown_timeout: float = 30.0,
+        handover: str | os.PathLike[str] | None = None,
+    ) -> None:
+        if not hasattr(os, "fork"):
+            raise RuntimeError("multiple workers are only supported on Unix")
+        self.app = app
+        self.server = _server_of(app, server)
+        self.host = host
+        self.port = port
+        self.worker_count = workers
+        self.reuse_port = reuse_port
+        self.metrics_interval = metrics_interval
+        self.restart_delay = restart_delay
+        self.shutdown_timeout = shutdown_timeout
+        #: Worker numbers by process ID.
+        self.workers: dict[int, int] = {}
+        #: Workers restarted after exiting unexpectedly.
+        self.restarts = 0
+        self.handover = Handover(handover) if handover is not None else None
+        self._socket: socket.socket | None = None
+        self._stopping = False
+
+    def run(self) -> None:
+        """Start the workers and supervise them until SIGINT or SIGTERM."""
+
+        previous = {
+            sig: signal.signal(sig, self._request_stop)
+            for sig in (signal.SIGINT, signal.SIGTERM)
+        }
+        metrics_dir = os.environ.get(METRICS_DIR_ENV)
+        owns_metrics_dir = metrics_dir is None
+        if metrics_dir is None:
+            metrics_dir = tempfile.mkdtemp(prefix="forzium-metrics-")
+            os.environ[METRICS_DIR_ENV] = metrics_dir
+        try:
+            if self.handover is not None:
+                self._socket = self.handover.take()
+            if self._socket is None and not self.reuse_port:
+                self._socket = _bind(self.host, self.port)
+            LOGGER.info(
+                "Starting %d workers on http://%s:%d",
+                self.worker_count,
+                self.host,
+                self.port,
+            )
+            for worker in range(self.worker_count):
+                self._spawn(worker)
+            if self.handover is not None:
+                self.handover.ready()
+            self._supervise()
+        finally:
+            self._stop_workers()
+            if self._socket is not None:
+                self._socket.close()
+                self._socket = None
+            if self.handover is not None:
+                self.handover.close()
+            for sig, handler in previous.items():
+                signal.signal(sig, handler)
+            if owns_metrics_dir:
+                os.environ.pop(METRICS_DIR_ENV, None)
+                shutil.rmtree(metrics_dir, ignore_errors=True)
+
+    def _request_stop(self, signum: int, frame: Any) -> None:
+        self._stopping = True
+
+    def _spawn(self, worker: int) -> None:
+        # a forked child has no threads but the forking one, so engine pools
+        # already started here would hang every worker that used them
+        if forzium_engine.native_threads_started():
+            raise RuntimeError(
+                "the engine started its threads before workers were forked; "
+                "run no compute, server or thread pool calls in the parent"
+            )
+        pid = os.fork()
+        if pid == 0:
+            code = 1
+            try:
+                self._serve_worker(worker)
+                code = 0
+            except BaseException:
+                LOGGER.exception("worker %d failed", worker)
+            finally:
+                logging.shutdown()
+                os._exit(code)
+        self.workers[pid] = worker
+        LOGGER.info("Worker %d started (pid %d)", worker, pid)
+
+    def _serve_worker(self, worker: int) -> None:
+        os.environ[WORKER_ID_ENV] = str(worker)
+        stop = threading.Event()
+        # the parent stops workers with SIGTERM, also on Ctrl-C
+        signal.signal(signal.SIGINT, signal.SIG_IGN)
+        signal.signal(signal.SIGTERM, lambda *_: stop.set())
+        metrics = Path(os.environ[METRICS_DIR_ENV]) / f"worker-{worker}.json"
+        threading.Thread(
+            target=_write_metrics,
+            args=(metrics, stop, self.metrics_interval),
+            name="forzium-metrics",
+            daemon=True,
+        ).start()
+        if self._socket is not None:
+            _serve(self.app, self.server, stop, fd=self._socket.fileno())
+        else:
+            _serve(
+                self.app,
+                self.server,
+                stop,
+                addr=f"{self.host}:{self.port}",
+                reuse_port=True,
+            )
+
+    def _supervise(self) -> None:
+        while not self._stopping:
+            if self.handover is not None and self._socket is not None:
+                if self.handover.poll(self._socket):
+                    LOGGER.info("Listening socket handed over; draining workers")
+                    return
+            try:
+                pid, status = os.waitpid(-1, os.WNOHANG)
+            except ChildProcessError:
+                pid, status = 0, 0
+            if pid == 0:
+                time.sleep(_POLL_INTERVAL)
+                continue
+            worker = self.workers.pop(pid, None)
+            if worker is None:
+                continue
+            LOGGER.warning(
+                "Worker %d (pid %d) exited %s; restarting",
+                worker,
+                pid,
+                _describe_exit(status),
+            )
+            self.restarts += 1
+            time.sleep(self.restart_delay)
+            if not self._stopping:
+                self._spawn(worker)
+
+    def _stop_workers(self) -> None:
+        for pid in self.workers:
+            with suppress(ProcessLookupError):
+                os.kill(pid, signal.SIGTERM)
+        deadline = time.monotonic() + self.shutdown_timeout
+        while self.workers and time.monotonic() < deadline:
+            try:
+                pid, _ = os.waitpid(-1, os.WNOHANG)
+            except ChildProcessError:
+                break
+            if pid == 0:
+                time.sleep(_POLL_INTERVAL)
+            else:
+                self.workers.pop(pid, None)
+        for pid, worker in self.workers.items():
+            LOGGER.warning("Worker %d (pid %d) did not stop; killing it", worker, pid)
+            with suppress(ProcessLookupError):
+                os.kill(pid, signal.SIGKILL)
+            with suppress(ChildProcessError):
+                os.waitpid(pid, 0)
+        self.workers.clear()
+
+
+class Handover:
+    """
+    Hand a listening socket from a running process to its replacement.
+
+    The running process listens on a Unix socket at ``path``. A replacement
+    connects to it in :meth:`take` and receives the listening socket; once it
+    serves, its :meth:`ready` tells the running process to stop and takes the
+    path over for the next replacement. If the replacement exits before it is
+    ready, the running process keeps serving.
+    """
+
+    def __init__(self, path: str | os.PathLike[str]) -> None:
+        if not hasattr(socket, "send_fds"):
+            raise RuntimeError("socket handover is only supported on Unix")
+        self.path = Path(path)
+        #: Whether the listening socket was given to a replacement.
+        self.handed_over = False
+        self._previous: socket.socket | None = None
+        self._server: socket.socket | None = None
+        self._pending: socket.socket | None = None
+
+    def take(self) -> socket.socket | None:
+        """The listening socket of the process serving at ``path``, if any."""
+
+        conn = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        conn.settimeout(_HANDOVER_TIMEOUT)
+        try:
+            conn.connect(str(self.path))
+        except (FileNotFoundError, ConnectionRefusedError):
+            conn.close()
+            return None
+        try:
+            _, fds, _, _ = socket.recv_fds(conn, 16, 1)
+        except BaseException:
+            conn.close()
+            raise
+        if not fds:
+            conn.close()
+            raise RuntimeError(f"no listening socket received from {self.path}")
+        self._previous = conn
+        LOGGER.info("Took over the listening socket from %s", self.path)
+        return socket.socket(fileno=fds[0])
+
+    def ready(self) -> None:
+        """Stop the process the socket was taken from; accept replacements."""
+
+        if self._previous is not None:
+            # the previous process may have stopped on its own meanwhile
+            with self._previous, suppress(OSError):
+                self._previous.sendall(b"stop")
+            self._previous = None
+        with suppress(FileNotFoundError):
+            self.path.unlink()
+        self._server = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        self._server.bind(str(self.path))
+        self._server.listen(1)
+        self._server.setblocking(False)
+
+    def poll(self, listener: socket.socket) -> bool:
+        """
+        Serve a replacement that connected, without blocking.
+
+        Returns whether a replacement took ``listener`` over and this
+        process should stop accepting and drain.
+        """
+
+        if self._server is None:
+            return False
+        if self._pending is None:
+            try:
+                conn, _ = self._server.accept()
+            except BlockingIOError:
+                return False
+            conn.settimeout(_HANDOVER_TIMEOUT)
+            try:
+                socket.send_fds(conn, [b"listener"], [listener.fileno()])
+            except OSError:
+                conn.close()
+                return False
+            conn.setblocking(False)
+            self._pending = conn
+            LOGGER.info("Listening socket sent to a replacement process")
+            return False
+        try:
+            reply = self._pending.recv(16)
+        except BlockingIOError:
+            return False
+        except OSError:
+            reply = b""
+        self._pending.close()
+        self._pending = None
+        if reply != b"stop":
+            LOGGER.warning("Replacement process exited before serving; still serving")
+            return False
+        self.handed_over = True
+        self._server.close()
+        self._server = None
+        return True
+
+    def close(self) -> None:
+        """Stop accepting replacements."""
+
+        for conn in (self._previous, self._pending):
+            if conn is not None:
+                conn.close()
+        self._previous = self._pending = None
+        if self._server is not None:
+            self._server.close()
+            self._server = None
+            # after a handover the path belongs to the replacement
+            with suppress(FileNotFoundError):
+                self.path.unlink()
+
+
+def _run_single(
+    app: Any,
+    server: Any,
+    host: str,
+    port: int,
+    handover: str | os.PathLike[str] | None,
+) -> None:
+    stop = threading.Event()
+    previous = {
+        sig: signal.signal(sig, lambda *_: stop.set())
+        for sig in (signal.SIGINT, signal.SIGTERM)
+    }
+    handoff = Handover(handover) if handover is not None else None
+    listener: socket.socket | None = None
+    try:
+        if handoff is None:
+            _serve(app, server, stop, addr=f"{host}:{port}")
+            return
+        listener = handoff.take() or _bind(host, port)
+        sock = listener
+        _serve(
+            app,
+            server,
+            stop,
+            started=handoff.ready,
+            poll=lambda: handoff.poll(sock),
+            fd=listener.fileno(),
+        )
+    finally:
+        if listener is not None:
+            listener.close()
+        if handoff is not None:
+            handoff.close()
+        for sig, handler in previous.items():
+            signal.signal(sig, handler)
+
+
+def _bind(host: str, port: int) -> socket.socket:
+    family = socket.AF_INET6 if ":" in host else socket.AF_INET
+    return socket.create_server((host, port), family=family, backlog=_BACKLOG)
+
+
+def _server_of(app: Any, server: Any | None) -> Any:
+    if server is not None:
+        return server
+    server = getattr(app, "server", None)
+    if server is not None:
+        return server
+    if hasattr(app, "serve"):
+        return app
+    raise RuntimeError(
+        "Application is not bound to a Forzium server; "
+        "pass server= or set app.server."
+    )
+
+
+def _run_hook(app: Any, server: Any, name: str) -> None:
+    if app is server:
+        return
+    hook = getattr(app, name, None)
+    if hook is None:
+        return
+    result = hook()
+    if asyncio.iscoroutine(result):
+        asyncio.run(result)
+
+
+def _serve(
+    app: Any,
+    server: Any,
+    stop: threading.Event,
+    *,
+    started: Callable[[], None] | None = None,
+    poll: Callable[[], bool] | None = None,
+    **listen: Any,
+) -> None:
+    _run_hook(app, server, "startup")
+    server.serve(**listen)
+    try:
+        if started is not None:
+            started()
+        while not stop.wait(_POLL_INTERVAL if poll else 0.5):
+            if poll is not None and poll():
+                LOGGER.info("Listening socket handed over; draining")
+                break
+    finally:
+        server.shutdown()
+        _run_hook(app, server, "shutdown")
+
+
+def _write_metrics(path: Path, stop: threading.Event, interval: float) -> None:
+    partial = path.with_suffix(".tmp")
+    while True:
+        with suppress(OSError):
+            partial.write_text(forzium_engine.export_metrics("json"))
+            os.replace(partial, path)
+        if stop.wait(interval):
+            return
+
+
+def _describe_exit(status: int) -> str:
+    code = os.waitstatus_to_exitcode(status)
+    if code < 0:
+        return f"on signal {signal.Signals(-code).name}"
+    return f"with status {code}"
@@ -3,7 +3,7 @@
 from __future__ import annotations
 
 import sys
-from typing import Any, Dict, List, Mapping, Optional
+from typing import Any, Callable, Dict, List, Mapping, Optional
 
 # Try to import the real Rust extension
 try:
@@ -53,6 +53,7 @@ class ComputeEngine:
 
     def __init__(self) -> None:
         self._rust_engine = None
+        self._custom_ops: Dict[str, Callable[..., List[List[float]]]] = {}
         if _RUST_AVAILABLE:
             try:
                 self._rust_engine = _rust_engine.ComputeEngine()
@@ -63,7 +64,25 @@ class ComputeEngine:
         """Check if the engine supports the given operation."""
         if self._rust_engine:
             return self._rust_engine.supports(operation)
-        return operation in {"multiply", "add", "matmul"}
+        builtin = operation in {"multiply", "add", "matmul"}
+        return builtin or operation in self._custom_ops
+
+    def register_op(self, name: str, func: Callable[..., List[List[float]]]) -> None:
+        """Register a callable ``func(data, parameters)`` as operation ``name``."""
+        if self._rust_engine:
+            self._rust_engine.register_op(name, func)
+            return
+        if name in {"multiply", "add", "matmul"}:
+            raise ValueError(f"cannot override built-in operation '{name}'")
+        if not callable(func):
+            raise ValueError("operation must be callable")
+        self._custom_ops[name] = func
+
+    def unregister_op(self, name: str) -> bool:
+        """Remove a custom operation, returning whether it was registered."""
+        if self._rust_engine:
+            return self._rust_engine.unregister_op(name)
+        return self._custom_ops.pop(name, None) is not None
 
     def compute(
         self,
@@ -91,6 +110,8 @@ class ComputeEngine:
             if not isinstance(other, list):
                 raise ValueError("matrix_b parameter required")
             return self._matmul_python(data, other)
+        elif operation in self._custom_ops:
+            return self._custom_ops[operation](data, parameters)
         else:
             raise ValueError(f"Unsupported operation: {operation}")
 
@@ -473,10 +473,108 @@ def get_latency_histogram(endpoint: str) -> Iterable[float]:
     return _latency_histograms.get(endpoint, [])
 
 
+_async_compute_sources: Dict[str, Any] = {}
+_async_compute_lock = threading.Lock()
+
+
+def register_async_compute(name: str, engine: Any) -> None:
+    """Export ``engine.stats()`` via :func:`prometheus_metrics` as *name*."""
+
+    with _async_compute_lock:
+        _async_compute_sources[name] = engine
+
+
+def unregister_async_compute(name: str) -> None:
+    """Stop exporting the AsyncCompute instance registered as *name*."""
+
+    with _async_compute_lock:
+        _async_compute_sources.pop(name, None)
+
+
+def _async_compute_lines() -> list[str]:
+    with _async_compute_lock:
+        sources = list(_async_compute_sources.items())
+    return [
+        f'forzium_async_compute_{key}{{instance="{name}"}} {value}'
+        for name, engine in sources
+        for key, value in engine.stats().items()
+    ]
+
+
+_pool_sources: Dict[str, Any] = {}
+_pool_lock = threading.Lock()
+
+# Upper retry counts of the PoolStats contention histogram buckets
+_POOL_CONTENTION_BOUNDS = ("0", "1", "3", "7", "15", "+Inf")
+
+
+def register_pool(name: str, pool: Any) -> None:
+    """Export ``pool.get_stats()`` via :func:`prometheus_metrics` as *name*."""
+
+    with _pool_lock:
+        _pool_sources[name] = pool
+
+
+def unregister_pool(name: str) -> None:
+    """Stop exporting the PoolAllocator registered as *name*."""
+
+    with _pool_lock:
+        _pool_sources.pop(name, None)
+
+
+def _pool_lines() -> list[str]:
+    with _pool_lock:
+        sources = list(_pool_sources.items())
+    lines: list[str] = []
+    for name, pool in sources:
+        stats = pool.get_stats()
+        label = f'instance="{name}"'
+        lines.extend(
+            [
+                f"forzium_pool_capacity_bytes{{{label}}} {stats['capacity']}",
+                f"forzium_pool_used_bytes{{{label}}} {stats['used']}",
+                f"forzium_pool_peak_bytes{{{label}}} {stats['peak_usage']}",
+                f"forzium_pool_utilization_ratio{{{label}}} "
+                f"{stats['utilization_pct'] / 100.0}",
+                f"forzium_pool_allocations_total{{{label}}} {stats['alloc_count']}",
+                f"forzium_pool_deallocations_total{{{label}}} {stats['dealloc_count']}",
+            ]
+        )
+        histogram = stats.get("contention_histogram")
+        if histogram is None:
+            continue
+        cumulative = 0
+        for bound, count in zip(_POOL_CONTENTION_BOUNDS, histogram):
+            cumulative += count
+            lines.append(
+                f'forzium_pool_contention_retries_bucket{{{label},le="{bound}"}} '
+                f"{cumulative}"
+            )
+        lines.append(
+            f"forzium_pool_contention_retries_sum{{{label}}} {stats['contention_count']}"
+        )
+        lines.append(f"forzium_pool_contention_retries_count{{{label}}} {cumulative}")
+    return lines
+
+
+def _engine_lines() -> list[str]:
+    """Metrics every engine subsystem publishes through ``export_metrics``."""
+
+    try:
+        from forzium_engine import export_metrics  # type: ignore
+    except ImportError:
+        return []
+    return export_metrics("prometheus").splitlines()
+
+
 def prometheus_metrics() -> str:
     """Render recorded metrics in Prometheus text format."""
 
-    return "\n".join(f"{k} {v}" for k, v in _metrics.items())
+    lines = [f"{k} {v}" for k, v in _metrics.items()]
+    lines.extend(_async_compute_lines())
+    lines.extend(_pool_lines())
+    lines.extend(_engine_lines())
+    return "\n".join(lines)
 
 
 def get_exporter_choice() -> str:
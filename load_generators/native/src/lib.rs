@@ -0,0 +1,173 @@
+//! Native ForziumAPI load generator.
+//!
+//! Sends the requests of a plan in the JSON format consumed by the WASM
+//! module's `PlanCursor` to a running ForziumAPI instance and reports the
+//! results in the format of `scripts.load_suite`, without Python or k6 in
+//! the measurement path. Plans are paced and latencies aggregated with the
+//! primitives of the WASM crate, so both generators measure the same way.
+
+pub mod report;
+pub mod runner;
+
+use forzium_load_template::payload::{PayloadGenerator, PayloadSpec};
+use forzium_load_template::scenario::{self, ConfigError, RequestTemplate, Scenario, Thresholds};
+use forzium_load_template::ScheduleEntry;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{Method, Uri};
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors preventing a run.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("failed to parse plan {path}: {source}")]
+    Plan {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("invalid payload {path}: {reason}")]
+    Payload { path: PathBuf, reason: String },
+    #[error("invalid thresholds {path}: {source}")]
+    Thresholds {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("invalid scenario document {path}: {source}")]
+    Scenario { path: PathBuf, source: ConfigError },
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("invalid target URL {url:?}: {reason}")]
+    Target { url: String, reason: String },
+    #[error("invalid header {0:?}, expected NAME:VALUE")]
+    Header(String),
+    #[error("failed to write report: {0}")]
+    Report(#[from] io::Error),
+}
+
+/// Read a plan: a JSON array of [`ScheduleEntry`], as produced by
+/// `scripts.load_suite` or the WASM module's `PlanBuilder`.
+pub fn load_plan(path: &Path) -> Result<Vec<ScheduleEntry>, Error> {
+    let text = std::fs::read_to_string(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut entries: Vec<ScheduleEntry> =
+        serde_json::from_str(&text).map_err(|source| Error::Plan {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    entries.sort_by(|a, b| a.offset_s.total_cmp(&b.offset_s));
+    Ok(entries)
+}
+
+/// Read the scenario whose id or name is `identifier` from a load-suite
+/// scenario document.
+pub fn load_scenario(path: &Path, identifier: &str) -> Result<Scenario, Error> {
+    let text = std::fs::read_to_string(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    scenario::find_scenario(&text, identifier).map_err(|source| Error::Scenario {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Read a [`PayloadSpec`] and seed a generator of request bodies with it.
+pub fn load_payloads(path: &Path, seed: u64) -> Result<PayloadGenerator, Error> {
+    let text = std::fs::read_to_string(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let invalid = |reason: String| Error::Payload {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let spec: PayloadSpec = serde_json::from_str(&text).map_err(|err| invalid(err.to_string()))?;
+    PayloadGenerator::from_spec(spec, seed).map_err(|err| invalid(err.to_string()))
+}
+
+/// Read [`Thresholds`] as written in a scenario.
+pub fn load_thresholds(path: &Path) -> Result<Thresholds, Error> {
+    let text = std::fs::read_to_string(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_str(&text).map_err(|source| Error::Thresholds {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The request sent for every plan entry.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    pub body: String,
+    /// Generator of a JSON body per request, replacing `body`.
+    pub payloads: Option<PayloadGenerator>,
+}
+
+impl Target {
+    /// Target `path` on the server at `base_url`, e.g. `http://127.0.0.1:8000`.
+    /// Only plain HTTP is supported.
+    pub fn new(method: Method, base_url: &str, path: &str) -> Result<Target, Error> {
+        let url = format!("{}{}", base_url.trim_end_matches('/'), path);
+        let invalid = |reason: String| Error::Target {
+            url: url.clone(),
+            reason,
+        };
+        let uri: Uri = url.parse().map_err(|err| invalid(format!("{err}")))?;
+        if uri.scheme_str() != Some("http") {
+            return Err(invalid("only http:// targets are supported".to_string()));
+        }
+        Ok(Target {
+            method,
+            uri,
+            headers: Vec::new(),
+            body: String::new(),
+            payloads: None,
+        })
+    }
+
+    /// Target the request of a scenario on the server at `base_url`. Paths
+    /// with parameters are not supported yet.
+    pub fn from_template(template: &RequestTemplate, base_url: &str) -> Result<Target, Error> {
+        if template.path.contains('{') {
+            return Err(Error::Unsupported(format!(
+                "path parameters are not supported yet, got {:?}",
+                template.path
+            )));
+        }
+        let method = Method::from_bytes(template.method.to_ascii_uppercase().as_bytes())
+            .map_err(|_| Error::Unsupported(format!("invalid method {:?}", template.method)))?;
+        let mut target = Target::new(method, base_url, &template.path)?;
+        for (name, value) in &template.headers {
+            target.header(&format!("{name}:{value}"))?;
+        }
+        Ok(target)
+    }
+
+    /// The body of the request of plan entry `sequence`.
+    pub fn body(&self, sequence: u32) -> String {
+        match &self.payloads {
+            Some(payloads) => payloads.body(sequence),
+            None => self.body.clone(),
+        }
+    }
+
+    /// Add a header given as `NAME:VALUE`.
+    pub fn header(&mut self, header: &str) -> Result<(), Error> {
+        let invalid = || Error::Header(header.to_string());
+        let (name, value) = header.split_once(':').ok_or_else(invalid)?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| invalid())?;
+        let value = HeaderValue::from_str(value.trim()).map_err(|_| invalid())?;
+        self.headers.push((name, value));
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! `forzium-load`: send a plan's requests to a ForziumAPI instance and print
+//! a `scripts.load_suite` report, or the run report as JSON or CSV.
+
+use clap::{Parser, ValueEnum};
+use forzium_load_native::report::{run_report, ScenarioReport, SuiteReport};
+use forzium_load_native::runner::{self, Mode};
+use forzium_load_native::{
+    load_payloads, load_plan, load_scenario, load_thresholds, Error, Target,
+};
+use forzium_load_template::payload::PayloadGenerator;
+use forzium_load_template::scenario::{Pattern, Seeds, Thresholds};
+use forzium_load_template::MetricsWindows;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::Method;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum LoopKind {
+    /// Send requests when they fall due, measuring latency from then.
+    Open,
+    /// Send from `--concurrency` workers, one request at a time each.
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// The `scripts.load_suite` report.
+    Suite,
+    /// The run report as JSON.
+    Json,
+    /// The overall and stage statistics of the run report as CSV.
+    Csv,
+}
+
+/// Send the requests of a plan to a ForziumAPI instance.
+#[derive(Debug, Parser)]
+#[command(name = "forzium-load", version)]
+struct Args {
+    /// Plan to run: a JSON array of schedule entries.
+    #[arg(
+        long,
+        required_unless_present = "scenario",
+        conflicts_with = "scenario"
+    )]
+    plan: Option<PathBuf>,
+    /// Load-suite scenario document to take the plan, request, target and
+    /// concurrency from; options given explicitly take precedence.
+    #[arg(long, requires = "scenario_id")]
+    scenario: Option<PathBuf>,
+    /// Id or name of the scenario to run.
+    #[arg(long)]
+    scenario_id: Option<String>,
+    /// Base URL of the server [default: http://127.0.0.1:8000].
+    #[arg(long, env = "FORZIUM_BASE_URL")]
+    base_url: Option<String>,
+    /// [default: GET]
+    #[arg(long)]
+    method: Option<Method>,
+    /// [default: /]
+    #[arg(long)]
+    path: Option<String>,
+    /// Request header as NAME:VALUE; may be repeated.
+    #[arg(long = "header", value_name = "NAME:VALUE")]
+    headers: Vec<String>,
+    /// Request body, the same for every request.
+    #[arg(long, conflicts_with = "payload")]
+    body: Option<String>,
+    /// JSON payload spec (a blob, a matrix or a document matching a JSON
+    /// Schema) generating a body per request; replaces the scenario's.
+    #[arg(long)]
+    payload: Option<PathBuf>,
+    /// Seed of the generated bodies; defaults to the scenario's payload seed.
+    #[arg(long)]
+    payload_seed: Option<u64>,
+    #[arg(long, value_enum, default_value_t = LoopKind::Open)]
+    mode: LoopKind,
+    /// Workers of a closed-loop run [default: 1].
+    #[arg(long)]
+    concurrency: Option<usize>,
+    /// Skip requests an open-loop run falls behind on instead of sending
+    /// them all at once.
+    #[arg(long)]
+    skip_missed: bool,
+    /// Leave the requests of the first this many seconds of the plan out of
+    /// metrics; overrides the scenario's `windows.warmup_s`.
+    #[arg(long)]
+    warmup_window_s: Option<f64>,
+    /// Leave the requests of the last this many seconds of the plan out of
+    /// metrics; overrides the scenario's `windows.cooldown_s`.
+    #[arg(long)]
+    cooldown_window_s: Option<f64>,
+    /// Seconds to wait for a response [default: 30].
+    #[arg(long)]
+    timeout_s: Option<f64>,
+    /// Scenario id in the report; defaults to the scenario's or the plan's
+    /// file name.
+    #[arg(long)]
+    id: Option<String>,
+    /// Pattern in the report; defaults to the scenario's or `plan`.
+    #[arg(long)]
+    pattern: Option<String>,
+    /// JSON thresholds to check the run against, as in a scenario's
+    /// `thresholds`; replaces the scenario's.
+    #[arg(long)]
+    thresholds: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = Format::Suite)]
+    format: Format,
+    /// Write the report here instead of to stdout.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+impl Args {
+    /// `windows` with the windows given on the command line replacing them.
+    fn windows(&self, windows: MetricsWindows) -> MetricsWindows {
+        MetricsWindows {
+            warmup_s: self.warmup_window_s.unwrap_or(windows.warmup_s),
+            cooldown_s: self.cooldown_window_s.unwrap_or(windows.cooldown_s),
+        }
+    }
+}
+
+const DEFAULT_BASE_URL: &str = "http://127.0.0.1:8000";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(2),
+        Err(err) => {
+            eprintln!("forzium-load: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run and write the report; whether the run passed its thresholds.
+async fn run(args: Args) -> Result<bool, Error> {
+    let (plan, closed, plan_duration_s, mut target, mut defaults) =
+        match (&args.plan, &args.scenario) {
+            (_, Some(path)) => {
+                let scenario =
+                    load_scenario(path, args.scenario_id.as_deref().unwrap_or_default())?;
+                let mut builder = scenario.plan();
+                builder.set_windows(args.windows(builder.windows()));
+                let base_url = args
+                    .base_url
+                    .as_deref()
+                    .or(scenario
+                        .target
+                        .as_ref()
+                        .map(|target| target.base_url.as_str()))
+                    .unwrap_or(DEFAULT_BASE_URL);
+                let mut target = Target::from_template(&scenario.request, base_url)?;
+                target.payloads = scenario.payloads();
+                let defaults = Defaults {
+                    id: scenario.identifier().unwrap_or_default().to_string(),
+                    pattern: pattern_name(&scenario.pattern).to_string(),
+                    concurrency: scenario.concurrency as usize,
+                    timeout_s: scenario.target.as_ref().and_then(|target| target.timeout_s),
+                    payload_seed: scenario.seed.payload(),
+                    thresholds: scenario.thresholds.clone(),
+                };
+                let (entries, closed) = (builder.entries(), builder.closed_stages());
+                (
+                    entries,
+                    closed,
+                    builder.total_duration_s(),
+                    target,
+                    defaults,
+                )
+            }
+            (Some(path), None) => {
+                let mut plan = load_plan(path)?;
+                let plan_duration_s = plan.last().map_or(0.0, |entry| entry.offset_s);
+                args.windows(MetricsWindows::default())
+                    .apply(&mut plan, plan_duration_s);
+                let base_url = args.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+                let target = Target::new(Method::GET, base_url, "/")?;
+                let defaults = Defaults {
+                    id: path
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    pattern: "plan".to_string(),
+                    concurrency: 1,
+                    timeout_s: None,
+                    payload_seed: Seeds::default().payload(),
+                    thresholds: None,
+                };
+                (plan, Vec::new(), plan_duration_s, target, defaults)
+            }
+            (None, None) => unreachable!("clap requires --plan or --scenario"),
+        };
+    if let Some(method) = args.method {
+        target.method = method;
+    }
+    if let Some(path) = &args.path {
+        target.uri = Target::new(target.method.clone(), &base_url(&target), path)?.uri;
+    }
+    for header in &args.headers {
+        target.header(header)?;
+    }
+    let payload_seed = args.payload_seed.unwrap_or(defaults.payload_seed);
+    if let Some(body) = args.body {
+        target.body = body;
+        target.payloads = None;
+    } else if let Some(path) = &args.payload {
+        target.payloads = Some(load_payloads(path, payload_seed)?);
+    } else if let Some(payloads) = &target.payloads {
+        target.payloads = PayloadGenerator::from_spec(payloads.spec().clone(), payload_seed).ok();
+    }
+    let has_content_type = target.headers.iter().any(|(name, _)| name == CONTENT_TYPE);
+    if target.payloads.is_some() && !has_content_type {
+        target
+            .headers
+            .push((CONTENT_TYPE, HeaderValue::from_static("application/json")));
+    }
+    if let Some(id) = args.id {
+        defaults.id = id;
+    }
+    if let Some(pattern) = args.pattern {
+        defaults.pattern = pattern;
+    }
+    let mode = match args.mode {
+        LoopKind::Open => Mode::Open {
+            backfill: !args.skip_missed,
+        },
+        LoopKind::Closed => Mode::Closed {
+            concurrency: args.concurrency.unwrap_or(defaults.concurrency),
+        },
+    };
+
+    let timeout_s = args.timeout_s.or(defaults.timeout_s).unwrap_or(30.0);
+    let timeout = Duration::from_secs_f64(timeout_s.max(0.0));
+    let result = runner::run(plan, closed, target, mode, timeout).await;
+
+    let mut report = run_report(&result);
+    let thresholds = match &args.thresholds {
+        Some(path) => Some(load_thresholds(path)?),
+        None => defaults.thresholds,
+    };
+    if let Some(thresholds) = &thresholds {
+        report.evaluate(thresholds);
+    }
+    let text = match args.format {
+        Format::Suite => {
+            let suite = SuiteReport {
+                scenarios: vec![ScenarioReport::new(
+                    &defaults.id,
+                    &defaults.pattern,
+                    plan_duration_s,
+                    &result,
+                    &report,
+                )],
+            };
+            serde_json::to_string_pretty(&suite).expect("reports serialise") + "\n"
+        }
+        Format::Json => report.to_json() + "\n",
+        Format::Csv => report.to_csv(),
+    };
+    match args.output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{text}"),
+    }
+    for failure in report.failures() {
+        eprintln!(
+            "forzium-load: threshold {} failed: {} against a limit of {}",
+            failure.metric, failure.actual, failure.limit
+        );
+    }
+    Ok(report.passed)
+}
+
+/// Settings taken from the plan or scenario unless given on the command line.
+struct Defaults {
+    id: String,
+    pattern: String,
+    concurrency: usize,
+    timeout_s: Option<f64>,
+    payload_seed: u64,
+    thresholds: Option<Thresholds>,
+}
+
+/// `scheme://authority` of the target.
+fn base_url(target: &Target) -> String {
+    let uri = &target.uri;
+    format!(
+        "{}://{}",
+        uri.scheme_str().unwrap_or("http"),
+        uri.authority().map_or("", |authority| authority.as_str())
+    )
+}
+
+fn pattern_name(pattern: &Pattern) -> &'static str {
+    match pattern {
+        Pattern::Steady { .. } => "steady",
+        Pattern::Poisson { .. } => "poisson",
+        Pattern::Burst { .. } => "burst",
+        Pattern::Ramp { .. } => "ramp",
+        Pattern::Spike { .. } => "spike",
+        Pattern::Stages { .. } => "stages",
+    }
+}
@@ -0,0 +1,215 @@
+//! Run results in the format of `scripts.load_suite`
+//!
+//! A report holds one scenario with the same fields as the synthetic runner's
+//! results, so tooling reading those reads these too. Latencies hold the
+//! full [`LatencySummary`] rather than only `mean` and `p95`. As in the
+//! synthetic runner, overall latencies cover the requests included in
+//! metrics and stage latencies every request of the stage. A request fails
+//! if it gets no response in time or one with a status of 400 or above.
+//! `saturation_points` is always empty: it is a model estimate of the
+//! synthetic runner, whereas a real run shows saturation in its latencies.
+//! The statistics are those of the run's [`RunReport`], whose threshold
+//! results the report carries along.
+
+use crate::runner::RunResult;
+use forzium_load_template::report::{RunRecorder, RunReport, ThresholdResult};
+use forzium_load_template::LatencySummary;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Results of a run of one or more scenarios.
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteReport {
+    pub scenarios: Vec<ScenarioReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioReport {
+    pub id: String,
+    pub pattern: String,
+    pub total_requests: usize,
+    pub included_requests: usize,
+    pub plan_duration_s: f64,
+    pub metrics: Metrics,
+    pub stage_metrics: BTreeMap<String, StageMetrics>,
+    pub failure_modes: Vec<FailureMode>,
+    pub saturation_points: Vec<serde_json::Value>,
+    /// `open` or `closed`.
+    pub mode: &'static str,
+    pub elapsed_s: f64,
+    pub failed_requests: usize,
+    /// Requests of the plan that were not sent.
+    pub skipped_requests: u32,
+    pub max_lag_s: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub thresholds: Vec<ThresholdResult>,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Metrics {
+    pub latency_ms: LatencySummary,
+    pub throughput_rps: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageMetrics {
+    pub requests: usize,
+    pub included_requests: usize,
+    pub latency_ms: LatencySummary,
+    pub throughput_rps: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureMode {
+    pub stage: String,
+    pub rate: f64,
+}
+
+/// Aggregate the outcomes of `result`; no thresholds are checked.
+pub fn run_report(result: &RunResult) -> RunReport {
+    let mut recorder = RunRecorder::new();
+    for outcome in &result.outcomes {
+        let entry = &outcome.entry;
+        recorder.record(
+            &entry.stage,
+            entry.include_in_metrics,
+            entry.offset_s,
+            outcome.latency_ms,
+            outcome.ok,
+        );
+    }
+    recorder.report(result.elapsed.as_secs_f64())
+}
+
+impl ScenarioReport {
+    /// Summarise `result`, a run of a plan lasting `plan_duration_s`, whose
+    /// statistics `report` holds.
+    pub fn new(
+        id: &str,
+        pattern: &str,
+        plan_duration_s: f64,
+        result: &RunResult,
+        report: &RunReport,
+    ) -> Self {
+        let mut failure_modes = Vec::new();
+        let stage_metrics = report
+            .stages
+            .iter()
+            .map(|(name, stage)| {
+                let failure_rate = (stage.failed_requests > 0).then_some(stage.error_rate);
+                if let Some(rate) = failure_rate {
+                    failure_modes.push(FailureMode {
+                        stage: name.clone(),
+                        rate,
+                    });
+                }
+                let metrics = StageMetrics {
+                    requests: stage.requests as usize,
+                    included_requests: stage.included_requests as usize,
+                    latency_ms: stage.latency_ms.clone(),
+                    throughput_rps: stage.throughput_rps,
+                    failure_rate,
+                };
+                (name.clone(), metrics)
+            })
+            .collect();
+
+        ScenarioReport {
+            id: id.to_string(),
+            pattern: pattern.to_string(),
+            total_requests: result.outcomes.len(),
+            included_requests: report.overall.requests as usize,
+            plan_duration_s,
+            metrics: Metrics {
+                latency_ms: report.overall.latency_ms.clone(),
+                throughput_rps: report.overall.throughput_rps,
+            },
+            stage_metrics,
+            failure_modes,
+            saturation_points: Vec::new(),
+            mode: result.mode.name(),
+            elapsed_s: result.elapsed.as_secs_f64(),
+            failed_requests: result.outcomes.iter().filter(|outcome| !outcome.ok).count(),
+            skipped_requests: result.skipped,
+            max_lag_s: result.max_lag_s,
+            thresholds: report.thresholds.clone(),
+            passed: report.passed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{Mode, Outcome};
+    use forzium_load_template::ScheduleEntry;
+    use std::time::Duration;
+
+    fn outcome(stage: &str, include_in_metrics: bool, latency_ms: f64, ok: bool) -> Outcome {
+        Outcome {
+            entry: ScheduleEntry {
+                sequence: 0,
+                offset_s: 0.0,
+                stage: stage.to_string(),
+                include_in_metrics,
+            },
+            latency_ms,
+            ok,
+        }
+    }
+
+    /// Whether `value` is `expected` to within the recorder's precision.
+    fn close(value: &serde_json::Value, expected: f64) -> bool {
+        (value.as_f64().unwrap() - expected).abs() <= expected * 0.001
+    }
+
+    #[test]
+    fn results_are_grouped_like_the_synthetic_runner() {
+        let result = RunResult {
+            mode: Mode::Open { backfill: true },
+            outcomes: vec![
+                outcome("warmup", false, 100.0, true),
+                outcome("steady", true, 10.0, true),
+                outcome("steady", true, 20.0, false),
+                outcome("steady", true, 30.0, true),
+                outcome("steady", true, 40.0, true),
+            ],
+            skipped: 0,
+            max_lag_s: 0.0,
+            elapsed: Duration::from_secs(2),
+        };
+        let mut run = run_report(&result);
+        let thresholds = serde_json::from_str(r#"{"latency_ms": {"p95": 30}}"#).unwrap();
+        run.evaluate(&thresholds);
+        let report = ScenarioReport::new("baseline", "steady", 2.0, &result, &run);
+        assert_eq!(report.total_requests, 5);
+        assert_eq!(report.included_requests, 4);
+        assert_eq!(report.failed_requests, 1);
+
+        let warmup = &report.stage_metrics["warmup"];
+        assert_eq!((warmup.requests, warmup.included_requests), (1, 0));
+        assert_eq!(warmup.latency_ms.count, 1);
+        assert_eq!(warmup.failure_rate, None);
+        assert_eq!(report.stage_metrics["steady"].failure_rate, Some(0.25));
+        assert_eq!(report.failure_modes.len(), 1);
+
+        let json = serde_json::to_value(SuiteReport {
+            scenarios: vec![report],
+        })
+        .unwrap();
+        let scenario = &json["scenarios"][0];
+        assert!(close(&scenario["metrics"]["latency_ms"]["p95"], 40.0));
+        assert!(close(&scenario["metrics"]["latency_ms"]["mean"], 25.0));
+        assert!(close(
+            &scenario["stage_metrics"]["warmup"]["latency_ms"]["max"],
+            100.0
+        ));
+        assert_eq!(scenario["failure_modes"][0]["stage"], "steady");
+        assert_eq!(scenario["saturation_points"], serde_json::json!([]));
+        assert_eq!(scenario["thresholds"][0]["metric"], "latency_ms.p95");
+        assert_eq!(scenario["passed"], false);
+    }
+}
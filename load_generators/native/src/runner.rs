@@ -0,0 +1,407 @@
+//! Sending a plan's requests
+//!
+//! In open-loop mode requests are sent when they fall due, however many are
+//! still waiting for a response, and their latency is measured from when
+//! they were due: a server that stalls is charged for the requests that
+//! queued behind the stall. In closed-loop mode a fixed number of workers
+//! each send one request at a time, waiting for its entry's offset first,
+//! like the k6 and Locust harnesses; latency is measured from the actual
+//! send.
+//!
+//! The closed-loop stages of a scenario run alongside the plan whatever the
+//! mode: each of their virtual users sends a request, waits for the
+//! response and thinks before sending the next, with latency measured from
+//! the actual send.
+
+use crate::Target;
+use forzium_load_template::users::{ClosedStage, VirtualUsers};
+use forzium_load_template::{Pacer, ScheduleEntry};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::Request;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// How requests are issued.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Send each request when it falls due. Without `backfill`, requests
+    /// missed while the generator itself fell behind are skipped.
+    Open { backfill: bool },
+    /// `concurrency` workers sending one request at a time.
+    Closed { concurrency: usize },
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Open { .. } => "open",
+            Mode::Closed { .. } => "closed",
+        }
+    }
+}
+
+/// A sent request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub entry: ScheduleEntry,
+    pub latency_ms: f64,
+    /// Whether a response with a status below 400 arrived in time.
+    pub ok: bool,
+}
+
+/// Everything a run sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResult {
+    pub mode: Mode,
+    /// Sent requests in plan order, those of closed-loop stages last.
+    pub outcomes: Vec<Outcome>,
+    /// Requests of the plan that were not sent.
+    pub skipped: u32,
+    /// How far the generator fell behind the plan, in seconds.
+    pub max_lag_s: f64,
+    pub elapsed: Duration,
+}
+
+type HttpClient = Client<HttpConnector, Full<Bytes>>;
+
+/// Send the requests of `plan` and of the virtual users of the `closed`
+/// stages to `target`, giving up on responses after `timeout`.
+pub async fn run(
+    plan: Vec<ScheduleEntry>,
+    closed: Vec<ClosedStage>,
+    target: Target,
+    mode: Mode,
+    timeout: Duration,
+) -> RunResult {
+    let client: HttpClient = Client::builder(TokioExecutor::new()).build_http();
+    let sender = Arc::new(Sender {
+        client,
+        target,
+        timeout,
+    });
+    let start = Instant::now();
+    let mut stages = JoinSet::new();
+    for stage in closed {
+        stages.spawn(virtual_users(stage, sender.clone(), start));
+    }
+    let (mut outcomes, skipped, mut max_lag_s) = match mode {
+        Mode::Open { backfill } => open_loop(plan, sender, backfill, start).await,
+        Mode::Closed { concurrency } => closed_loop(plan, sender, concurrency, start).await,
+    };
+    while let Some(stage) = stages.join_next().await {
+        if let Ok((sent, lag_s)) = stage {
+            outcomes.extend(sent);
+            max_lag_s = max_lag_s.max(lag_s);
+        }
+    }
+    outcomes.sort_by_key(|outcome| outcome.entry.sequence);
+    RunResult {
+        mode,
+        outcomes,
+        skipped,
+        max_lag_s,
+        elapsed: start.elapsed(),
+    }
+}
+
+async fn open_loop(
+    plan: Vec<ScheduleEntry>,
+    sender: Arc<Sender>,
+    backfill: bool,
+    start: Instant,
+) -> (Vec<Outcome>, u32, f64) {
+    let mut pacer = Pacer::from_entries(plan, backfill);
+    let mut requests = JoinSet::new();
+    while let Some(offset_s) = pacer.next_offset_s() {
+        tokio::time::sleep_until(start + Duration::from_secs_f64(offset_s.max(0.0))).await;
+        while let Some(send) = pacer.poll_send(start.elapsed().as_secs_f64()) {
+            let sender = sender.clone();
+            requests.spawn(async move {
+                let ok = sender.send(send.entry.sequence).await;
+                let completed_s = start.elapsed().as_secs_f64();
+                Outcome {
+                    latency_ms: (completed_s - send.entry.offset_s) * 1000.0,
+                    entry: send.entry,
+                    ok,
+                }
+            });
+        }
+    }
+    let mut outcomes = Vec::new();
+    while let Some(outcome) = requests.join_next().await {
+        outcomes.extend(outcome.ok());
+    }
+    (outcomes, pacer.skipped(), pacer.max_lag_s())
+}
+
+async fn closed_loop(
+    plan: Vec<ScheduleEntry>,
+    sender: Arc<Sender>,
+    concurrency: usize,
+    start: Instant,
+) -> (Vec<Outcome>, u32, f64) {
+    let plan = Arc::new(plan);
+    let next = Arc::new(AtomicUsize::new(0));
+    let mut workers = JoinSet::new();
+    for _ in 0..concurrency.max(1) {
+        let (plan, next, sender) = (plan.clone(), next.clone(), sender.clone());
+        workers.spawn(async move {
+            let mut outcomes = Vec::new();
+            let mut max_lag_s = 0.0_f64;
+            while let Some(entry) = plan.get(next.fetch_add(1, Ordering::Relaxed)) {
+                let due = start + Duration::from_secs_f64(entry.offset_s.max(0.0));
+                tokio::time::sleep_until(due).await;
+                let sent = Instant::now();
+                max_lag_s = max_lag_s.max((sent - due).as_secs_f64());
+                let ok = sender.send(entry.sequence).await;
+                outcomes.push(Outcome {
+                    entry: entry.clone(),
+                    latency_ms: sent.elapsed().as_secs_f64() * 1000.0,
+                    ok,
+                });
+            }
+            (outcomes, max_lag_s)
+        });
+    }
+    let mut outcomes = Vec::new();
+    let mut max_lag_s = 0.0_f64;
+    while let Some(worker) = workers.join_next().await {
+        if let Ok((sent, lag_s)) = worker {
+            outcomes.extend(sent);
+            max_lag_s = max_lag_s.max(lag_s);
+        }
+    }
+    (outcomes, 0, max_lag_s)
+}
+
+/// Run the virtual users of a closed-loop stage until it ends.
+async fn virtual_users(
+    stage: ClosedStage,
+    sender: Arc<Sender>,
+    start: Instant,
+) -> (Vec<Outcome>, f64) {
+    let users = Arc::new(Mutex::new(VirtualUsers::from_stage(stage)));
+    let count = users.lock().expect("users lock").users();
+    let mut tasks = JoinSet::new();
+    for user in 0..count {
+        let (users, sender) = (users.clone(), sender.clone());
+        tasks.spawn(async move {
+            let mut outcomes = Vec::new();
+            let mut max_lag_s = 0.0_f64;
+            let mut next_s = Some(users.lock().expect("users lock").start_s(user));
+            while let Some(offset_s) = next_s {
+                let due = start + Duration::from_secs_f64(offset_s.max(0.0));
+                tokio::time::sleep_until(due).await;
+                let sent = Instant::now();
+                max_lag_s = max_lag_s.max((sent - due).as_secs_f64());
+                let now_s = (sent - start).as_secs_f64();
+                let Some(entry) = users.lock().expect("users lock").send_entry(user, now_s) else {
+                    break;
+                };
+                let ok = sender.send(entry.sequence).await;
+                let completed_s = start.elapsed().as_secs_f64();
+                outcomes.push(Outcome {
+                    entry,
+                    latency_ms: (completed_s - now_s) * 1000.0,
+                    ok,
+                });
+                next_s = users.lock().expect("users lock").think(user, completed_s);
+            }
+            (outcomes, max_lag_s)
+        });
+    }
+    let mut outcomes = Vec::new();
+    let mut max_lag_s = 0.0_f64;
+    while let Some(task) = tasks.join_next().await {
+        if let Ok((sent, lag_s)) = task {
+            outcomes.extend(sent);
+            max_lag_s = max_lag_s.max(lag_s);
+        }
+    }
+    (outcomes, max_lag_s)
+}
+
+struct Sender {
+    client: HttpClient,
+    target: Target,
+    timeout: Duration,
+}
+
+impl Sender {
+    /// Send the target request of plan entry `sequence` and read its
+    /// response; whether it succeeded.
+    async fn send(&self, sequence: u32) -> bool {
+        let mut request = Request::builder()
+            .method(self.target.method.clone())
+            .uri(self.target.uri.clone());
+        for (name, value) in &self.target.headers {
+            request = request.header(name, value);
+        }
+        let Ok(request) = request.body(Full::new(Bytes::from(self.target.body(sequence)))) else {
+            return false;
+        };
+        let exchange = async {
+            let response = self.client.request(request).await.ok()?;
+            let status = response.status();
+            response.into_body().collect().await.ok()?;
+            Some(status.as_u16() < 400)
+        };
+        matches!(
+            tokio::time::timeout(self.timeout, exchange).await,
+            Ok(Some(true))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forzium_load_template::users::ThinkTime;
+    use hyper::Method;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve `HTTP 200` on keep-alive connections, failing every request
+    /// whose sequence of arrival is a multiple of `fail_every`.
+    async fn serve(fail_every: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let served = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let served = served.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    while let Ok(read) = socket.read(&mut buf).await {
+                        if read == 0 {
+                            break;
+                        }
+                        let count = served.fetch_add(1, Ordering::Relaxed) + 1;
+                        let status = if count.is_multiple_of(fail_every) {
+                            500
+                        } else {
+                            200
+                        };
+                        let response =
+                            format!("HTTP/1.1 {status} X\r\ncontent-length: 2\r\n\r\nok");
+                        if socket.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        format!("http://{address}")
+    }
+
+    fn plan(requests: u32, interval_s: f64) -> Vec<ScheduleEntry> {
+        (0..requests)
+            .map(|sequence| ScheduleEntry {
+                sequence,
+                offset_s: f64::from(sequence) * interval_s,
+                stage: "steady".to_string(),
+                include_in_metrics: true,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn both_modes_send_the_whole_plan() {
+        let base_url = serve(5).await;
+        for mode in [
+            Mode::Open { backfill: true },
+            Mode::Closed { concurrency: 2 },
+        ] {
+            let target = Target::new(Method::GET, &base_url, "/items").unwrap();
+            let result = run(
+                plan(10, 0.01),
+                Vec::new(),
+                target,
+                mode,
+                Duration::from_secs(5),
+            )
+            .await;
+            assert_eq!(result.outcomes.len(), 10, "{mode:?}");
+            assert_eq!(result.skipped, 0);
+            assert_eq!(
+                result.outcomes.iter().filter(|outcome| !outcome.ok).count(),
+                2,
+                "{mode:?}"
+            );
+            assert!(result.elapsed >= Duration::from_millis(90));
+            assert!(result
+                .outcomes
+                .iter()
+                .all(|outcome| outcome.latency_ms >= 0.0));
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_targets_fail_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        let target = Target::new(Method::GET, &format!("http://{address}"), "/").unwrap();
+        let mode = Mode::Open { backfill: false };
+        let result = run(
+            plan(3, 0.0),
+            Vec::new(),
+            target,
+            mode,
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(result.outcomes.len() as u32 + result.skipped, 3);
+        assert!(result.outcomes.iter().all(|outcome| !outcome.ok));
+    }
+    #[tokio::test]
+    async fn virtual_users_run_alongside_the_plan() {
+        let base_url = serve(usize::MAX).await;
+        let target = Target::new(Method::GET, &base_url, "/items").unwrap();
+        let stage = ClosedStage {
+            stage: "users".to_string(),
+            include_in_metrics: true,
+            start_s: 0.0,
+            duration_s: 0.2,
+            users: 3,
+            ramp_up_s: 0.03,
+            think_time: ThinkTime::Constant { seconds: 0.05 },
+            seed: 1,
+            first_sequence: 10,
+        };
+        let mode = Mode::Open { backfill: true };
+        let result = run(
+            plan(5, 0.01),
+            vec![stage],
+            target,
+            mode,
+            Duration::from_secs(5),
+        )
+        .await;
+        let (planned, users): (Vec<_>, Vec<_>) = result
+            .outcomes
+            .iter()
+            .partition(|outcome| outcome.entry.sequence < 10);
+        assert_eq!(planned.len(), 5);
+        // each user sends every 50 ms or so for 200 ms
+        assert!((6..=12).contains(&users.len()), "{}", users.len());
+        assert!(users.iter().all(|outcome| outcome.ok));
+        assert!(users
+            .iter()
+            .all(|outcome| outcome.entry.stage == "users" && outcome.entry.offset_s < 0.2));
+        for user in 0..3 {
+            assert!(users
+                .iter()
+                .any(|outcome| outcome.entry.sequence == 10 + user));
+        }
+        assert!(result.elapsed >= Duration::from_millis(150));
+    }
+}
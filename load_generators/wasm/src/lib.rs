@@ -3,13 +3,26 @@
 //! This template mirrors the behaviour of the Python harness exposed in
 //! `load_generators/common.py`.  It allows hosts such as k6 or bespoke Rust
 //! runners to iterate through an execution plan generated offline (e.g. via
-//! `scripts.load_suite`) while staying fully deterministic.
+//! `scripts.load_suite`) while staying fully deterministic.  Open-loop plans
+//! can also be generated in the module itself with [`PlanBuilder`], and the
+//! latencies hosts observe summarised with [`LatencyRecorder`]. Scenario
+//! documents of the load suite are parsed and validated by [`scenario`],
+//! seeded request bodies generated by [`payload`] and run results exported
+//! and checked against thresholds by [`report`]. Plans may mix offset-based
+//! stages with closed-loop stages of virtual users, scheduled by [`users`].
 
+pub mod payload;
+pub mod report;
+pub mod scenario;
+pub mod users;
+
+use hdrhistogram::Histogram;
 use serde::{Deserialize, Serialize};
+use users::{ClosedStage, ThinkTime, CLOSED_STAGE_SEQUENCES};
 use wasm_bindgen::prelude::*;
 
 /// Single scheduled request entry.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScheduleEntry {
     /// Monotonic sequence identifier.
     pub sequence: u32,
@@ -53,6 +66,570 @@ impl DeterministicRng {
         let u = self.next_f64().max(f64::EPSILON);
         -u.ln() / lambda
     }
+
+    /// Sample a normal variate with mean `mu` and standard deviation
+    /// `sigma` (Box-Muller).
+    pub fn gauss(&mut self, mu: f64, sigma: f64) -> f64 {
+        let u = self.next_f64().max(f64::EPSILON);
+        let v = self.next_f64();
+        mu + sigma * (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+    }
+
+    /// Sample a gamma variate with the given `shape` and `scale`
+    /// (Marsaglia-Tsang).
+    pub fn gammavariate(&mut self, shape: f64, scale: f64) -> f64 {
+        if shape <= 0.0 || scale <= 0.0 {
+            return 0.0;
+        }
+        if shape < 1.0 {
+            let boost = self.next_f64().max(f64::EPSILON).powf(1.0 / shape);
+            return self.gammavariate(shape + 1.0, scale) * boost;
+        }
+        let d = shape - 1.0 / 3.0;
+        let c = 1.0 / (9.0 * d).sqrt();
+        loop {
+            let x = self.gauss(0.0, 1.0);
+            let v = (1.0 + c * x).powi(3);
+            if v <= 0.0 {
+                continue;
+            }
+            let u = self.next_f64().max(f64::EPSILON);
+            if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+                return d * v * scale;
+            }
+        }
+    }
+}
+
+impl DeterministicRng {
+    /// A uniform index below `len`, which must not be zero.
+    pub(crate) fn index(&mut self, len: usize) -> usize {
+        ((self.next_f64() * len as f64) as usize).min(len - 1)
+    }
+}
+
+/// Segments per second used to approximate a ramp, the harness's default.
+const DEFAULT_RAMP_RESOLUTION: f64 = 8.0;
+
+fn default_ramp_resolution() -> f64 {
+    DEFAULT_RAMP_RESOLUTION
+}
+
+/// One stage of a generated plan. In JSON the `type` field selects the
+/// stage; the other fields are named as in `scripts.load_suite` scenarios.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Stage {
+    /// One request, then `duration_s` seconds without load.
+    Warmup {
+        duration_s: f64,
+        #[serde(default = "default_discard_metrics")]
+        discard_metrics: bool,
+    },
+    /// Requests evenly spaced at `target_rps`.
+    Steady { duration_s: f64, target_rps: f64 },
+    /// A rate changing linearly from `start_rps` to `end_rps`, approximated
+    /// by `resolution` constant-rate segments per second.
+    Ramp {
+        duration_s: f64,
+        start_rps: f64,
+        end_rps: f64,
+        #[serde(default = "default_ramp_resolution")]
+        resolution: f64,
+    },
+    /// `steps` constant-rate segments of equal length whose rates climb
+    /// evenly from `start_rps` to `end_rps`.
+    Step {
+        duration_s: f64,
+        start_rps: f64,
+        end_rps: f64,
+        steps: u32,
+    },
+    /// `base_rps` with `peak_rps` for `peak_duration_s` in the middle.
+    Spike {
+        duration_s: f64,
+        base_rps: f64,
+        peak_rps: f64,
+        peak_duration_s: f64,
+    },
+    /// Open-loop Poisson arrivals averaging `lambda_rps`.
+    Poisson { duration_s: f64, lambda_rps: f64 },
+    /// No requests.
+    Pause { duration_s: f64 },
+    /// Closed loop: `users` virtual users, started evenly over `ramp_up_s`,
+    /// each sending a request, waiting for its response and thinking for
+    /// `think_time` before the next; see [`users`].
+    Users {
+        duration_s: f64,
+        users: u32,
+        #[serde(default)]
+        ramp_up_s: f64,
+        #[serde(default)]
+        think_time: ThinkTime,
+    },
+}
+
+fn default_discard_metrics() -> bool {
+    true
+}
+
+impl Stage {
+    /// Label of the stage's requests unless overridden.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Warmup { .. } => "warmup",
+            Stage::Steady { .. } => "steady",
+            Stage::Ramp { .. } => "ramp",
+            Stage::Step { .. } => "step",
+            Stage::Spike { .. } => "spike",
+            Stage::Poisson { .. } => "poisson",
+            Stage::Pause { .. } => "pause",
+            Stage::Users { .. } => "users",
+        }
+    }
+
+    /// Whether the stage's requests count towards metrics unless overridden:
+    /// all but a warmup that discards its metrics.
+    pub fn include_in_metrics(&self) -> bool {
+        !matches!(
+            self,
+            Stage::Warmup {
+                discard_metrics: true,
+                ..
+            }
+        )
+    }
+}
+
+/// A [`Stage`] as written in a stage list, optionally relabelled or with its
+/// requests explicitly included in or excluded from metrics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageSpec {
+    #[serde(flatten)]
+    pub stage: Stage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_in_metrics: Option<bool>,
+}
+
+impl From<Stage> for StageSpec {
+    fn from(stage: Stage) -> Self {
+        StageSpec {
+            stage,
+            label: None,
+            include_in_metrics: None,
+        }
+    }
+}
+
+/// Windows at the start and end of a plan whose requests are left out of
+/// metrics whatever their stage, e.g. while caches warm up and while
+/// in-flight requests drain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsWindows {
+    /// Seconds from the start of the plan.
+    #[serde(default)]
+    pub warmup_s: f64,
+    /// Seconds before the end of the plan.
+    #[serde(default)]
+    pub cooldown_s: f64,
+}
+
+impl MetricsWindows {
+    /// Whether a request at `offset_s` in a plan lasting `duration_s` falls
+    /// outside both windows.
+    pub fn includes(&self, offset_s: f64, duration_s: f64) -> bool {
+        let cooldown_start = duration_s - self.cooldown_s.max(0.0);
+        offset_s >= self.warmup_s && (self.cooldown_s <= 0.0 || offset_s < cooldown_start)
+    }
+
+    /// Leave the requests inside the windows out of metrics.
+    pub fn apply(&self, entries: &mut [ScheduleEntry], duration_s: f64) {
+        for entry in entries {
+            entry.include_in_metrics &= self.includes(entry.offset_s, duration_s);
+        }
+    }
+}
+
+/// Generates a plan from a seed instead of loading one produced by the
+/// Python harness.  Stages are appended back to back, each starting where the
+/// previous one ended. Closed-loop stages are kept apart from the scheduled
+/// requests of the other stages, which is what cursors, pacers and the JSON
+/// form of the plan cover.
+#[wasm_bindgen]
+pub struct PlanBuilder {
+    rng: DeterministicRng,
+    entries: Vec<ScheduleEntry>,
+    closed: Vec<ClosedStage>,
+    offset_s: f64,
+    windows: MetricsWindows,
+}
+
+#[wasm_bindgen]
+impl PlanBuilder {
+    /// Create an empty plan whose random arrivals are drawn from `seed`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64) -> PlanBuilder {
+        PlanBuilder {
+            rng: DeterministicRng::new(seed),
+            entries: Vec::new(),
+            closed: Vec::new(),
+            offset_s: 0.0,
+            windows: MetricsWindows::default(),
+        }
+    }
+
+    /// The plan of the scenario whose id or name is `identifier` in a
+    /// load-suite scenario document; see [`scenario::Scenario::plan`].
+    pub fn from_scenario(document_json: &str, identifier: &str) -> Result<PlanBuilder, JsValue> {
+        scenario::find_scenario(document_json, identifier)
+            .map(|scenario| scenario.plan())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Append a warmup request, like the harness's `warmup` block: a single
+    /// request labelled `warmup` followed by `duration_s` seconds without
+    /// load. It is left out of metrics when `discard_metrics` is set.
+    pub fn warmup(&mut self, duration_s: f64, discard_metrics: bool) {
+        self.add(
+            &Stage::Warmup {
+                duration_s,
+                discard_metrics,
+            }
+            .into(),
+        );
+    }
+
+    /// Append requests evenly spaced at `target_rps` for `duration_s`
+    /// seconds, like the harness's `steady` pattern.
+    pub fn steady(&mut self, target_rps: f64, duration_s: f64) {
+        self.add(
+            &Stage::Steady {
+                duration_s,
+                target_rps,
+            }
+            .into(),
+        );
+    }
+
+    /// Append a rate changing linearly from `start_rps` to `end_rps` over
+    /// `duration_s` seconds, like a phase of the harness's `ramp` pattern.
+    pub fn ramp(&mut self, start_rps: f64, end_rps: f64, duration_s: f64) {
+        self.add(
+            &Stage::Ramp {
+                duration_s,
+                start_rps,
+                end_rps,
+                resolution: DEFAULT_RAMP_RESOLUTION,
+            }
+            .into(),
+        );
+    }
+
+    /// Append `steps` constant-rate segments sharing `duration_s`, their
+    /// rates climbing evenly from `start_rps` to `end_rps`.
+    pub fn step(&mut self, start_rps: f64, end_rps: f64, steps: u32, duration_s: f64) {
+        self.add(
+            &Stage::Step {
+                duration_s,
+                start_rps,
+                end_rps,
+                steps,
+            }
+            .into(),
+        );
+    }
+
+    /// Append `duration_s` seconds at `base_rps` with a burst at `peak_rps`
+    /// lasting `peak_duration_s` in the middle.
+    pub fn spike(&mut self, base_rps: f64, peak_rps: f64, peak_duration_s: f64, duration_s: f64) {
+        self.add(
+            &Stage::Spike {
+                duration_s,
+                base_rps,
+                peak_rps,
+                peak_duration_s,
+            }
+            .into(),
+        );
+    }
+
+    /// Append open-loop Poisson arrivals averaging `rate_rps` requests per
+    /// second for `duration_s` seconds, like the harness's `poisson` pattern.
+    pub fn poisson(&mut self, rate_rps: f64, duration_s: f64) {
+        self.add(
+            &Stage::Poisson {
+                duration_s,
+                lambda_rps: rate_rps,
+            }
+            .into(),
+        );
+    }
+
+    /// Append `duration_s` seconds without requests.
+    pub fn pause(&mut self, duration_s: f64) {
+        self.add(&Stage::Pause { duration_s }.into());
+    }
+
+    /// Append `duration_s` seconds of `users` virtual users sending in a
+    /// closed loop, each thinking for `think_time_s` between a response and
+    /// its next request. Other think time distributions are available
+    /// through [`PlanBuilder::stages`].
+    pub fn users(&mut self, users: u32, think_time_s: f64, duration_s: f64) {
+        self.add(
+            &Stage::Users {
+                duration_s,
+                users,
+                ramp_up_s: 0.0,
+                think_time: ThinkTime::Constant {
+                    seconds: think_time_s,
+                },
+            }
+            .into(),
+        );
+    }
+
+    /// Append the stages of a JSON array of [`StageSpec`], e.g.
+    /// `[{"type": "warmup", "duration_s": 5}, {"type": "ramp", "duration_s":
+    /// 30, "start_rps": 10, "end_rps": 200}, {"type": "pause", "duration_s":
+    /// 2}]`. Nothing is appended if the array does not parse.
+    pub fn stages(&mut self, stages_json: &str) -> Result<(), JsValue> {
+        self.extend_from_json(stages_json)
+            .map_err(|err| JsValue::from_str(&format!("failed to parse stages: {err}")))
+    }
+
+    /// Leave the requests of the first `duration_s` seconds of the plan out
+    /// of metrics, whichever stages they belong to.
+    pub fn warmup_window(&mut self, duration_s: f64) {
+        self.windows.warmup_s = duration_s.max(0.0);
+    }
+
+    /// Leave the requests of the last `duration_s` seconds of the plan, as
+    /// finally built, out of metrics.
+    pub fn cooldown_window(&mut self, duration_s: f64) {
+        self.windows.cooldown_s = duration_s.max(0.0);
+    }
+
+    /// Number of scheduled requests.
+    pub fn len(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    /// Whether no requests are scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Offset at which the last stage ends, in seconds.
+    pub fn total_duration_s(&self) -> f64 {
+        self.offset_s
+    }
+
+    /// The closed-loop stages as a JSON array of [`ClosedStage`], to be run
+    /// with [`users::VirtualUsers`].
+    pub fn closed_stages_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.closed_stages())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// The plan as a JSON array of [`ScheduleEntry`].
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.entries()).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// A cursor over the plan.
+    pub fn cursor(&self) -> PlanCursor {
+        PlanCursor {
+            entries: self.entries(),
+            index: 0,
+        }
+    }
+
+    /// A [`Pacer`] over the plan.
+    pub fn pacer(&self, backfill: bool) -> Pacer {
+        Pacer::from_entries(self.entries(), backfill)
+    }
+}
+
+impl PlanBuilder {
+    /// The scheduled requests in order, those inside the metrics windows
+    /// left out of metrics.
+    pub fn entries(&self) -> Vec<ScheduleEntry> {
+        let mut entries = self.entries.clone();
+        self.windows.apply(&mut entries, self.offset_s);
+        entries
+    }
+
+    /// The closed-loop stages in order, their requests numbered after the
+    /// scheduled ones.
+    pub fn closed_stages(&self) -> Vec<ClosedStage> {
+        let first_sequence = self.entries.len() as u32;
+        self.closed
+            .iter()
+            .zip(0u32..)
+            .map(|(stage, index)| ClosedStage {
+                first_sequence: first_sequence
+                    .wrapping_add(index.wrapping_mul(CLOSED_STAGE_SEQUENCES)),
+                ..stage.clone()
+            })
+            .collect()
+    }
+
+    /// The declared metrics windows.
+    pub fn windows(&self) -> MetricsWindows {
+        self.windows
+    }
+
+    /// Declare both metrics windows at once.
+    pub fn set_windows(&mut self, windows: MetricsWindows) {
+        self.warmup_window(windows.warmup_s);
+        self.cooldown_window(windows.cooldown_s);
+    }
+
+    /// Append the stages of a JSON array of [`StageSpec`].
+    pub fn extend_from_json(&mut self, stages_json: &str) -> Result<(), serde_json::Error> {
+        let specs: Vec<StageSpec> = serde_json::from_str(stages_json)?;
+        for spec in &specs {
+            self.add(spec);
+        }
+        Ok(())
+    }
+
+    /// Append a stage. The next stage starts after its full duration.
+    pub fn add(&mut self, spec: &StageSpec) {
+        let label = spec.label.as_deref().unwrap_or(spec.stage.name());
+        let include = spec
+            .include_in_metrics
+            .unwrap_or_else(|| spec.stage.include_in_metrics());
+        let start = self.offset_s;
+        match spec.stage {
+            Stage::Warmup { duration_s, .. } => {
+                if duration_s > 0.0 {
+                    self.push(start, label, include);
+                }
+                self.offset_s = start + duration_s.max(0.0);
+            }
+            Stage::Steady {
+                duration_s,
+                target_rps,
+            } => self.constant(duration_s, target_rps, label, include),
+            Stage::Ramp {
+                duration_s,
+                start_rps,
+                end_rps,
+                resolution,
+            } => {
+                if duration_s <= 0.0 {
+                    return;
+                }
+                let segments = ((duration_s * resolution.max(1.0)).round() as u32).max(1);
+                let segment_duration = duration_s / f64::from(segments);
+                for index in 0..segments {
+                    let fraction = (f64::from(index) + 0.5) / f64::from(segments);
+                    let rps = start_rps + (end_rps - start_rps) * fraction;
+                    self.constant(segment_duration, rps, label, include);
+                }
+            }
+            Stage::Step {
+                duration_s,
+                start_rps,
+                end_rps,
+                steps,
+            } => {
+                let steps = steps.max(1);
+                let segment_duration = duration_s / f64::from(steps);
+                for index in 0..steps {
+                    let fraction = if steps == 1 {
+                        0.0
+                    } else {
+                        f64::from(index) / f64::from(steps - 1)
+                    };
+                    let rps = start_rps + (end_rps - start_rps) * fraction;
+                    self.constant(segment_duration, rps, label, include);
+                }
+            }
+            Stage::Spike {
+                duration_s,
+                base_rps,
+                peak_rps,
+                peak_duration_s,
+            } => {
+                let peak_duration_s = peak_duration_s.clamp(0.0, duration_s.max(0.0));
+                let base_duration_s = (duration_s - peak_duration_s) / 2.0;
+                self.constant(base_duration_s, base_rps, label, include);
+                self.constant(peak_duration_s, peak_rps, label, include);
+                self.constant(base_duration_s, base_rps, label, include);
+            }
+            Stage::Poisson {
+                duration_s,
+                lambda_rps,
+            } => {
+                self.offset_s = start + duration_s.max(0.0);
+                if duration_s <= 0.0 || lambda_rps <= 0.0 {
+                    return;
+                }
+                let mut elapsed = 0.0;
+                loop {
+                    elapsed += self.rng.expovariate(lambda_rps);
+                    if elapsed >= duration_s {
+                        break;
+                    }
+                    self.push(start + elapsed, label, include);
+                }
+            }
+            Stage::Pause { duration_s } => self.offset_s = start + duration_s.max(0.0),
+            Stage::Users {
+                duration_s,
+                users,
+                ramp_up_s,
+                ref think_time,
+            } => {
+                self.offset_s = start + duration_s.max(0.0);
+                if duration_s <= 0.0 || users == 0 {
+                    return;
+                }
+                let seed = (self.rng.next_f64() * (1u64 << 53) as f64) as u64;
+                self.closed.push(ClosedStage {
+                    stage: label.to_string(),
+                    include_in_metrics: include,
+                    start_s: start,
+                    duration_s,
+                    users,
+                    ramp_up_s,
+                    think_time: think_time.clone(),
+                    seed,
+                    first_sequence: 0,
+                });
+            }
+        }
+    }
+
+    /// Requests evenly spaced at `target_rps` from the current offset, as in
+    /// the harness's `_constant_stage`.
+    fn constant(&mut self, duration_s: f64, target_rps: f64, label: &str, include: bool) {
+        let start = self.offset_s;
+        self.offset_s = start + duration_s.max(0.0);
+        if duration_s <= 0.0 || target_rps <= 0.0 {
+            return;
+        }
+        let interval = 1.0 / target_rps;
+        let mut elapsed = 0.0;
+        while elapsed < duration_s {
+            self.push(start + elapsed, label, include);
+            elapsed += interval;
+        }
+    }
+
+    fn push(&mut self, offset_s: f64, stage: &str, include_in_metrics: bool) {
+        self.entries.push(ScheduleEntry {
+            sequence: self.entries.len() as u32,
+            offset_s,
+            stage: stage.to_string(),
+            include_in_metrics,
+        });
+    }
 }
 
 /// Iterator over a pre-computed plan.
@@ -86,18 +663,557 @@ impl PlanCursor {
     pub fn next(&mut self) -> JsValue {
         if let Some(entry) = self.entries.get(self.index) {
             self.index += 1;
-            JsValue::from_serde(entry).unwrap_or(JsValue::NULL)
+            to_js(entry)
         } else {
             JsValue::NULL
         }
     }
 }
 
+/// A request due to be sent, and how late it is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PacedSend {
+    #[serde(flatten)]
+    pub entry: ScheduleEntry,
+    /// Seconds between the entry's offset and when it was handed out.
+    pub lag_s: f64,
+}
+
+/// Hands out a plan's requests as they fall due on the host's clock.
+///
+/// A host that cannot keep up with the plan, e.g. because all its
+/// connections are waiting on a stalled server, would otherwise send fewer
+/// requests than planned and time each from when it was actually sent,
+/// hiding the stall from its latencies (coordinated omission). Each send
+/// therefore carries its intended offset, from which the latency should be
+/// measured with [`LatencyRecorder::record_intended`]. Sends missed while the
+/// host was behind are either all handed out at once to catch up
+/// (`backfill`) or skipped, keeping only the latest, and counted.
+#[wasm_bindgen]
+pub struct Pacer {
+    entries: Vec<ScheduleEntry>,
+    index: usize,
+    backfill: bool,
+    skipped: u32,
+    max_lag_s: f64,
+}
+
+#[wasm_bindgen]
+impl Pacer {
+    /// Construct a pacer from a JSON array of [`ScheduleEntry`].
+    #[wasm_bindgen(constructor)]
+    pub fn new(plan_json: &str, backfill: bool) -> Result<Pacer, JsValue> {
+        let entries: Vec<ScheduleEntry> = serde_json::from_str(plan_json)
+            .map_err(|err| JsValue::from_str(&format!("failed to parse plan: {err}")))?;
+        Ok(Pacer::from_entries(entries, backfill))
+    }
+
+    /// Offset of the next request to send, in seconds from the start of the
+    /// run, or `undefined` when the plan is exhausted. Hosts sleep until then
+    /// and call [`Pacer::poll`].
+    pub fn next_offset_s(&self) -> Option<f64> {
+        self.entries.get(self.index).map(|entry| entry.offset_s)
+    }
+
+    /// The request due `elapsed_s` seconds into the run as a JSON
+    /// [`PacedSend`], or `null` if none is due yet or the plan is exhausted.
+    pub fn poll(&mut self, elapsed_s: f64) -> JsValue {
+        match self.poll_send(elapsed_s) {
+            Some(send) => to_js(&send),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Requests not yet handed out or skipped.
+    pub fn remaining(&self) -> u32 {
+        self.entries.len().saturating_sub(self.index) as u32
+    }
+
+    /// Requests skipped because the host fell behind without `backfill`.
+    pub fn skipped(&self) -> u32 {
+        self.skipped
+    }
+
+    /// Largest lag of a handed out request, in seconds.
+    pub fn max_lag_s(&self) -> f64 {
+        self.max_lag_s
+    }
+}
+
+impl Pacer {
+    pub fn from_entries(entries: Vec<ScheduleEntry>, backfill: bool) -> Pacer {
+        Pacer {
+            entries,
+            index: 0,
+            backfill,
+            skipped: 0,
+            max_lag_s: 0.0,
+        }
+    }
+
+    /// The request due `elapsed_s` seconds into the run, if any.
+    pub fn poll_send(&mut self, elapsed_s: f64) -> Option<PacedSend> {
+        let due = self.entries[self.index..]
+            .iter()
+            .take_while(|entry| entry.offset_s <= elapsed_s)
+            .count();
+        if due == 0 {
+            return None;
+        }
+        if !self.backfill {
+            self.skipped += due as u32 - 1;
+            self.index += due - 1;
+        }
+        let entry = self.entries[self.index].clone();
+        self.index += 1;
+        let lag_s = elapsed_s - entry.offset_s;
+        self.max_lag_s = self.max_lag_s.max(lag_s);
+        Some(PacedSend { entry, lag_s })
+    }
+}
+
+/// Significant decimal digits kept by [`LatencyRecorder`]: recorded values
+/// are exact to within 0.1%.
+const LATENCY_SIGNIFICANT_FIGURES: u8 = 3;
+
+/// Percentiles and moments of recorded latencies, in milliseconds. `mean`
+/// and `p95` match the `latency_ms` metrics of `scripts.load_suite`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub min: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub p99_9: f64,
+    pub max: f64,
+}
+
+impl LatencySummary {
+    /// The statistic named like the field holding it, except `count`.
+    pub fn statistic(&self, name: &str) -> Option<f64> {
+        Some(match name {
+            "min" => self.min,
+            "mean" => self.mean,
+            "stddev" => self.stddev,
+            "p50" => self.p50,
+            "p90" => self.p90,
+            "p95" => self.p95,
+            "p99" => self.p99,
+            "p99_9" => self.p99_9,
+            "max" => self.max,
+            _ => return None,
+        })
+    }
+}
+
+/// Aggregates per-request latencies in an HDR histogram of microseconds, so
+/// every host derives the same percentiles from the same samples without
+/// keeping them. Memory use depends on the range of latencies, not on how
+/// many are recorded.
+#[wasm_bindgen]
+pub struct LatencyRecorder {
+    histogram: Histogram<u64>,
+}
+
+#[wasm_bindgen]
+impl LatencyRecorder {
+    /// Create an empty recorder.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> LatencyRecorder {
+        LatencyRecorder {
+            histogram: Histogram::new(LATENCY_SIGNIFICANT_FIGURES)
+                .expect("valid significant figures"),
+        }
+    }
+
+    /// Record a request that took `latency_ms` milliseconds. Negative
+    /// latencies count as zero; NaN and infinite ones are ignored.
+    pub fn record(&mut self, latency_ms: f64) {
+        if latency_ms.is_finite() {
+            // cannot fail: the histogram grows to fit
+            let _ = self.histogram.record(to_micros(latency_ms));
+        }
+    }
+
+    /// Record a request planned to start `intended_s` seconds into the run
+    /// and completed at `completed_s`. Measuring from the intended rather
+    /// than the actual start counts the time it waited on a host that fell
+    /// behind; see [`Pacer`].
+    pub fn record_intended(&mut self, intended_s: f64, completed_s: f64) {
+        self.record((completed_s - intended_s) * 1000.0);
+    }
+
+    /// Record a request of a closed-loop host that sends one request every
+    /// `expected_interval_ms` while responses keep up. A slower response
+    /// also records the requests that would have been sent meanwhile, each
+    /// with `expected_interval_ms` less latency than the one before.
+    pub fn record_corrected(&mut self, latency_ms: f64, expected_interval_ms: f64) {
+        if latency_ms.is_finite() && expected_interval_ms.is_finite() {
+            let interval = to_micros(expected_interval_ms);
+            // cannot fail: the histogram grows to fit
+            let _ = self
+                .histogram
+                .record_correct(to_micros(latency_ms), interval);
+        }
+    }
+
+    /// Number of recorded latencies.
+    pub fn count(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    /// Latency below which `percentile` percent of the recorded ones fall, in
+    /// milliseconds; zero if none were recorded.
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        if self.histogram.is_empty() {
+            return 0.0;
+        }
+        let quantile = (percentile / 100.0).clamp(0.0, 1.0);
+        to_millis(self.histogram.value_at_quantile(quantile))
+    }
+
+    /// Largest recorded latency in milliseconds.
+    pub fn max(&self) -> f64 {
+        to_millis(self.histogram.max())
+    }
+
+    /// Mean recorded latency in milliseconds.
+    pub fn mean(&self) -> f64 {
+        self.histogram.mean() / 1000.0
+    }
+
+    /// Standard deviation of the recorded latencies in milliseconds.
+    pub fn stddev(&self) -> f64 {
+        self.histogram.stdev() / 1000.0
+    }
+
+    /// Add the latencies recorded by `other`, e.g. by another virtual user.
+    pub fn merge(&mut self, other: &LatencyRecorder) {
+        // cannot fail either: both histograms have the same precision
+        let _ = self.histogram.add(&other.histogram);
+    }
+
+    /// Forget all recorded latencies.
+    pub fn reset(&mut self) {
+        self.histogram.reset();
+    }
+
+    /// The [`LatencySummary`] as JSON.
+    pub fn summary_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.summary()).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+impl Default for LatencyRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyRecorder {
+    /// Percentiles and moments of the recorded latencies; all zero if none
+    /// were recorded.
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count(),
+            min: to_millis(self.histogram.min()),
+            mean: self.mean(),
+            stddev: self.stddev(),
+            p50: self.percentile(50.0),
+            p90: self.percentile(90.0),
+            p95: self.percentile(95.0),
+            p99: self.percentile(99.0),
+            p99_9: self.percentile(99.9),
+            max: self.max(),
+        }
+    }
+}
+
+fn to_micros(latency_ms: f64) -> u64 {
+    // saturates, so negative latencies become zero
+    (latency_ms * 1000.0).round() as u64
+}
+
+fn to_millis(micros: u64) -> f64 {
+    micros as f64 / 1000.0
+}
+
+/// Validate a load-suite scenario document, returning how many scenarios it
+/// holds or an error naming the offending field.
+#[wasm_bindgen]
+pub fn validate_scenarios(document_json: &str) -> Result<u32, JsValue> {
+    scenario::parse_scenarios(document_json)
+        .map(|scenarios| scenarios.len() as u32)
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
 /// Helper that converts a `ScheduleEntry` slice into JSON for transport.
 #[wasm_bindgen]
 pub fn serialise_plan(entries: JsValue) -> Result<String, JsValue> {
-    let schedule: Vec<ScheduleEntry> = entries
-        .into_serde()
+    let schedule: Vec<ScheduleEntry> = serde_wasm_bindgen::from_value(entries)
         .map_err(|err| JsValue::from_str(&format!("invalid plan entries: {err}")))?;
     serde_json::to_string(&schedule).map_err(|err| JsValue::from_str(&err.to_string()))
 }
+
+/// Convert `value` to the JS value its JSON parses to, or `null` if it
+/// cannot be converted.
+pub(crate) fn to_js<T: Serialize + ?Sized>(value: &T) -> JsValue {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap_or(JsValue::NULL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poisson_arrivals_are_seeded_and_bounded() {
+        let mut builder = PlanBuilder::new(42);
+        builder.poisson(200.0, 5.0);
+        let entries = builder.entries();
+        // 1000 arrivals expected; allow for sampling noise
+        assert!((850..1150).contains(&entries.len()), "{}", entries.len());
+        assert!(entries
+            .windows(2)
+            .all(|pair| pair[0].offset_s <= pair[1].offset_s));
+        assert!(entries
+            .iter()
+            .all(|entry| entry.offset_s < 5.0 && entry.stage == "poisson"));
+        assert!(entries
+            .iter()
+            .enumerate()
+            .all(|(i, entry)| entry.sequence == i as u32));
+
+        let mut again = PlanBuilder::new(42);
+        again.poisson(200.0, 5.0);
+        assert_eq!(again.to_json().unwrap(), builder.to_json().unwrap());
+    }
+
+    #[test]
+    fn stages_follow_each_other() {
+        let mut builder = PlanBuilder::new(7);
+        builder.poisson(0.0, 2.0);
+        assert!(builder.is_empty());
+        builder.poisson(50.0, 1.0);
+        assert_eq!(builder.total_duration_s(), 3.0);
+        assert!(builder
+            .entries()
+            .iter()
+            .all(|entry| (2.0..3.0).contains(&entry.offset_s)));
+        assert_eq!(builder.cursor().remaining(), builder.len());
+    }
+
+    #[test]
+    fn metrics_windows_exclude_requests_whatever_their_stage() {
+        let mut builder = PlanBuilder::new(3);
+        builder.steady(4.0, 2.0);
+        builder.cooldown_window(0.5);
+        builder.warmup_window(1.0);
+        builder.steady(4.0, 1.0);
+        // The cooldown window follows the end of the plan as it grows
+        assert_eq!(builder.total_duration_s(), 3.0);
+        let included: Vec<f64> = builder
+            .entries()
+            .iter()
+            .filter(|entry| entry.include_in_metrics)
+            .map(|entry| entry.offset_s)
+            .collect();
+        assert_eq!(included, [1.0, 1.25, 1.5, 1.75, 2.0, 2.25]);
+        assert!(builder
+            .entries()
+            .iter()
+            .all(|entry| entry.stage == "steady"));
+        let plan: Vec<ScheduleEntry> = serde_json::from_str(&builder.to_json().unwrap()).unwrap();
+        assert_eq!(plan, builder.entries());
+
+        // Requests left out by their stage stay left out
+        let mut builder = PlanBuilder::new(3);
+        builder.warmup(1.0, true);
+        builder.steady(4.0, 1.0);
+        builder.cooldown_window(0.5);
+        let included = builder
+            .entries()
+            .iter()
+            .filter(|e| e.include_in_metrics)
+            .count();
+        assert_eq!(included, 2);
+    }
+
+    #[test]
+    fn closed_loop_stages_are_kept_apart() {
+        let mut builder = PlanBuilder::new(9);
+        builder
+            .extend_from_json(
+                r#"[
+                    {"type": "steady", "duration_s": 1, "target_rps": 4},
+                    {"type": "users", "duration_s": 2, "users": 3,
+                     "think_time": {"distribution": "exponential", "mean_s": 0.2}},
+                    {"type": "steady", "duration_s": 1, "target_rps": 4},
+                    {"type": "users", "duration_s": 1, "users": 2, "label": "soak"}
+                ]"#,
+            )
+            .unwrap();
+        assert_eq!(builder.total_duration_s(), 5.0);
+        assert_eq!(builder.len(), 8);
+        assert!(builder
+            .entries()
+            .iter()
+            .all(|entry| !(1.0..3.0).contains(&entry.offset_s)));
+
+        let closed = builder.closed_stages();
+        assert_eq!(closed.len(), 2);
+        assert_eq!((closed[0].start_s, closed[0].users), (1.0, 3));
+        assert_eq!(
+            closed[0].think_time,
+            users::ThinkTime::Exponential { mean_s: 0.2 }
+        );
+        assert_eq!((closed[1].stage.as_str(), closed[1].start_s), ("soak", 4.0));
+        assert_eq!(closed[0].first_sequence, 8);
+        assert_eq!(closed[1].first_sequence, 8 + CLOSED_STAGE_SEQUENCES);
+        assert_ne!(closed[0].seed, closed[1].seed);
+    }
+
+    #[test]
+    fn stage_dsl_compiles_like_the_harness() {
+        let mut builder = PlanBuilder::new(1);
+        builder
+            .extend_from_json(
+                r#"[
+                    {"type": "warmup", "duration_s": 2},
+                    {"type": "steady", "duration_s": 1, "target_rps": 8},
+                    {"type": "pause", "duration_s": 1},
+                    {"type": "ramp", "duration_s": 2, "start_rps": 0, "end_rps": 16},
+                    {"type": "step", "duration_s": 3, "start_rps": 2, "end_rps": 8, "steps": 3},
+                    {"type": "spike", "duration_s": 3, "base_rps": 2, "peak_rps": 20,
+                     "peak_duration_s": 1, "label": "flash", "include_in_metrics": false}
+                ]"#,
+            )
+            .unwrap();
+        assert_eq!(builder.total_duration_s(), 12.0);
+
+        let count = |stage: &str| {
+            let entries = builder.entries();
+            entries.iter().filter(|entry| entry.stage == stage).count()
+        };
+        assert_eq!(count("warmup"), 1);
+        assert_eq!(count("steady"), 8);
+        // 16 segments of 1/8 s at 0.5, 1.5, ..., 15.5 rps, each starting
+        // with a request
+        assert_eq!(count("ramp"), 24);
+        assert_eq!(count("step"), 2 + 5 + 8);
+        assert_eq!(count("flash"), 2 + 20 + 2);
+
+        let entries = builder.entries();
+        assert!(!entries[0].include_in_metrics);
+        assert!(entries[1].offset_s == 2.0 && entries[1].include_in_metrics);
+        assert!(entries
+            .iter()
+            .all(|entry| !(3.0..4.0).contains(&entry.offset_s)));
+        assert!(entries
+            .iter()
+            .filter(|entry| entry.stage == "flash")
+            .all(|entry| !entry.include_in_metrics && entry.offset_s >= 9.0));
+        assert!(entries
+            .iter()
+            .enumerate()
+            .all(|(i, entry)| entry.sequence == i as u32));
+    }
+
+    #[test]
+    fn invalid_stages_append_nothing() {
+        let mut builder = PlanBuilder::new(1);
+        builder.steady(5.0, 1.0);
+        let err = builder.extend_from_json(r#"[{"type": "steady", "duration_s": 1}]"#);
+        assert!(err.is_err());
+        assert_eq!(builder.len(), 5);
+        assert_eq!(builder.total_duration_s(), 1.0);
+    }
+
+    #[test]
+    fn latency_percentiles_are_within_precision() {
+        let mut recorder = LatencyRecorder::new();
+        assert_eq!(recorder.summary().p99, 0.0);
+        for latency in 1..=10_000 {
+            recorder.record(f64::from(latency) / 10.0);
+        }
+        recorder.record(f64::NAN);
+        recorder.record(-1.0);
+
+        let summary = recorder.summary();
+        assert_eq!(summary.count, 10_001);
+        assert_eq!(summary.min, 0.0);
+        for (value, expected) in [
+            (summary.p50, 500.0),
+            (summary.p90, 900.0),
+            (summary.p99, 990.0),
+            (summary.p99_9, 999.0),
+            (summary.max, 1000.0),
+            (summary.mean, 500.0),
+            (summary.stddev, 288.7),
+        ] {
+            assert!(
+                (value - expected).abs() <= expected * 0.001,
+                "{value} vs {expected}"
+            );
+        }
+
+        let mut other = LatencyRecorder::new();
+        other.record(5000.0);
+        recorder.merge(&other);
+        assert_eq!(recorder.count(), 10_002);
+        assert!((recorder.max() - 5000.0).abs() <= 5.0);
+        recorder.reset();
+        assert_eq!(recorder.count(), 0);
+    }
+
+    #[test]
+    fn late_sends_are_backfilled_or_skipped() {
+        let mut builder = PlanBuilder::new(1);
+        builder.steady(4.0, 2.0);
+        for backfill in [true, false] {
+            let mut pacer = builder.pacer(backfill);
+            assert!(pacer.poll_send(-0.1).is_none());
+            let first = pacer.poll_send(0.0).unwrap();
+            assert_eq!((first.entry.sequence, first.lag_s), (0, 0.0));
+            assert!(pacer.poll_send(0.1).is_none());
+            assert_eq!(pacer.next_offset_s(), Some(0.25));
+
+            // stalled until 1.1 s: sends at 0.25 to 1.0 are due
+            let mut sent = Vec::new();
+            while let Some(send) = pacer.poll_send(1.1) {
+                sent.push((send.entry.sequence, send.lag_s));
+            }
+            if backfill {
+                let sequences: Vec<_> = sent.iter().map(|(sequence, _)| *sequence).collect();
+                assert_eq!(sequences, [1, 2, 3, 4]);
+                assert_eq!(pacer.skipped(), 0);
+                assert!((pacer.max_lag_s() - 0.85).abs() < 1e-9);
+            } else {
+                assert_eq!(sent.len(), 1);
+                assert_eq!(sent[0].0, 4);
+                assert_eq!(pacer.skipped(), 3);
+                assert!((pacer.max_lag_s() - 0.1).abs() < 1e-9);
+            }
+            assert_eq!(pacer.remaining(), 3);
+        }
+    }
+
+    #[test]
+    fn latencies_count_from_intended_start() {
+        let mut recorder = LatencyRecorder::new();
+        recorder.record_intended(0.25, 1.3);
+        assert!((recorder.max() - 1050.0).abs() <= 1.0);
+
+        let mut corrected = LatencyRecorder::new();
+        corrected.record_corrected(100.0, 10.0);
+        corrected.record_corrected(5.0, 10.0);
+        // 100 ms, plus 90, 80, ..., 10 ms for the requests it held up
+        assert_eq!(corrected.count(), 11);
+        assert!((corrected.percentile(50.0) - 50.0).abs() <= 0.1);
+    }
+}
@@ -0,0 +1,546 @@
+//! Seeded request bodies
+//!
+//! A [`PayloadGenerator`] produces the body of each request of a plan from a
+//! [`PayloadSpec`] and a seed. As in the k6 runner, the body of a request is
+//! drawn from a generator seeded with the request's sequence number, so it
+//! does not depend on the order in which requests are sent: the WASM and
+//! native generators send the same bodies for the same plan and seed, in
+//! open and closed loop alike.
+
+use crate::scenario::{
+    finite, non_negative, validate_payload_parameters, ComponentDistribution, ConfigError,
+    PayloadDistribution, PayloadParameters,
+};
+use crate::DeterministicRng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use wasm_bindgen::prelude::*;
+
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Largest number of matrix elements per body, keeping bodies in memory.
+const MAX_MATRIX_ELEMENTS: usize = 1 << 22;
+
+/// Longest string or array a schema may ask for when it gives no maximum,
+/// beyond its minimum.
+const DEFAULT_SPREAD: u64 = 8;
+
+/// What the body of each request looks like. In JSON the `type` field
+/// selects the kind of body.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum PayloadSpec {
+    /// `{"sequence": n, "blob": "..."}` with a random alphanumeric blob of
+    /// about `size_bytes` characters, its length drawn from `distribution`
+    /// as in the Python harness and the k6 runner.
+    Blob {
+        size_bytes: u64,
+        #[serde(default)]
+        distribution: PayloadDistribution,
+        #[serde(default)]
+        parameters: PayloadParameters,
+    },
+    /// An object holding a matrix of the given `shape` under `field`, e.g.
+    /// the `data` of a compute request, alongside the constant `fields`.
+    /// Elements are drawn uniformly from `[min, max)`, rounded down to
+    /// integers if `integers` is set.
+    Matrix {
+        shape: Vec<usize>,
+        #[serde(default)]
+        min: f64,
+        #[serde(default = "default_matrix_max")]
+        max: f64,
+        #[serde(default)]
+        integers: bool,
+        #[serde(default = "default_matrix_field")]
+        field: String,
+        #[serde(default)]
+        fields: Map<String, Value>,
+    },
+    /// A document matching a JSON Schema. The keywords `type`, `const`,
+    /// `enum`, `oneOf`, `anyOf`, `properties`, `required`, `items`,
+    /// `minItems`, `maxItems`, `minLength`, `maxLength`, `minimum` and
+    /// `maximum` are honoured; optional properties are present about half
+    /// the time.
+    Json { schema: Value },
+}
+
+fn default_matrix_max() -> f64 {
+    1.0
+}
+
+fn default_matrix_field() -> String {
+    "data".to_string()
+}
+
+impl PayloadSpec {
+    /// Check that the spec can produce bodies; `path` locates it in errors.
+    pub fn validate(&self, path: &str) -> Result<(), ConfigError> {
+        match self {
+            PayloadSpec::Blob {
+                distribution,
+                parameters,
+                ..
+            } => validate_payload_parameters(
+                &format!("{path}.parameters"),
+                *distribution,
+                parameters,
+            ),
+            PayloadSpec::Matrix {
+                shape, min, max, ..
+            } => {
+                let elements = shape
+                    .iter()
+                    .try_fold(1_usize, |total, &dimension| total.checked_mul(dimension));
+                if shape.is_empty() || shape.contains(&0) {
+                    return Err(ConfigError::new(
+                        format!("{path}.shape"),
+                        "must list at least one dimension, all positive",
+                    ));
+                }
+                if elements.is_none_or(|elements| elements > MAX_MATRIX_ELEMENTS) {
+                    return Err(ConfigError::new(
+                        format!("{path}.shape"),
+                        format!("must have at most {MAX_MATRIX_ELEMENTS} elements"),
+                    ));
+                }
+                if !(min.is_finite() && max.is_finite() && min < max) {
+                    return Err(ConfigError::new(
+                        format!("{path}.max"),
+                        format!("must be finite and above min ({min}), got {max}"),
+                    ));
+                }
+                Ok(())
+            }
+            PayloadSpec::Json { schema } => validate_schema(&format!("{path}.schema"), schema),
+        }
+    }
+}
+
+/// Produces the body of each request of a plan.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PayloadGenerator {
+    spec: PayloadSpec,
+    seed: u64,
+}
+
+#[wasm_bindgen]
+impl PayloadGenerator {
+    /// Construct a generator from a JSON [`PayloadSpec`], e.g. `{"type":
+    /// "matrix", "shape": [16, 16], "fields": {"operation": "multiply"}}`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(spec_json: &str, seed: u64) -> Result<PayloadGenerator, JsValue> {
+        let spec: PayloadSpec = serde_json::from_str(spec_json)
+            .map_err(|err| JsValue::from_str(&format!("failed to parse payload: {err}")))?;
+        PayloadGenerator::from_spec(spec, seed).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// The body of request `sequence` as JSON text.
+    pub fn body(&self, sequence: u32) -> String {
+        self.generate(sequence).to_string()
+    }
+}
+
+impl PayloadGenerator {
+    pub fn from_spec(spec: PayloadSpec, seed: u64) -> Result<PayloadGenerator, ConfigError> {
+        spec.validate("")?;
+        Ok(PayloadGenerator { spec, seed })
+    }
+
+    pub fn spec(&self) -> &PayloadSpec {
+        &self.spec
+    }
+
+    /// The body of request `sequence`.
+    pub fn generate(&self, sequence: u32) -> Value {
+        let seed = self.seed.wrapping_add(u64::from(sequence).wrapping_mul(29));
+        let mut rng = DeterministicRng::new(mix(seed));
+        match &self.spec {
+            PayloadSpec::Blob {
+                size_bytes,
+                distribution,
+                parameters,
+            } => {
+                let size = blob_size(&mut rng, *size_bytes, *distribution, parameters);
+                let blob = (0..size)
+                    .map(|_| ALPHABET[rng.index(ALPHABET.len())] as char)
+                    .collect::<String>();
+                serde_json::json!({ "sequence": sequence, "blob": blob })
+            }
+            PayloadSpec::Matrix {
+                shape,
+                min,
+                max,
+                integers,
+                field,
+                fields,
+            } => {
+                let mut element = || {
+                    let value = min + (max - min) * rng.next_f64();
+                    if *integers {
+                        Value::from(value.floor() as i64)
+                    } else {
+                        number(value)
+                    }
+                };
+                let mut body = fields.clone();
+                body.insert(field.clone(), matrix(shape, &mut element));
+                Value::Object(body)
+            }
+            PayloadSpec::Json { schema } => document(schema, &mut rng),
+        }
+    }
+}
+
+/// The SplitMix64 finaliser: neighbouring seeds give unrelated streams,
+/// where the generator's first outputs for them would be close.
+fn mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Length of a blob around `size`, sampled like the k6 runner when
+/// `parameters` are given and like the Python harness otherwise. Lengths are
+/// kept within `1..=4 * size`.
+fn blob_size(
+    rng: &mut DeterministicRng,
+    size: u64,
+    distribution: PayloadDistribution,
+    parameters: &PayloadParameters,
+) -> u64 {
+    if size == 0 {
+        return 0;
+    }
+    let base = size as f64;
+    let sampled = match distribution {
+        PayloadDistribution::Fixed => base,
+        PayloadDistribution::Lognormal => {
+            let mean = parameters.mean.unwrap_or(base.ln());
+            rng.gauss(mean, parameters.sigma.unwrap_or(0.25)).exp()
+        }
+        PayloadDistribution::Gamma => {
+            let shape = parameters.shape.unwrap_or((base / 8.0).max(1.0));
+            let scale = parameters.scale.unwrap_or((base / shape).max(1.0));
+            rng.gammavariate(shape, scale)
+        }
+        PayloadDistribution::Mixture if parameters.components.is_empty() => {
+            if rng.next_f64() < 0.5 {
+                base
+            } else {
+                rng.expovariate(1.0 / base)
+            }
+        }
+        PayloadDistribution::Mixture => {
+            let components = &parameters.components;
+            let total: f64 = components.iter().map(|component| component.weight).sum();
+            let mut probe = rng.next_f64() * total;
+            let component = components
+                .iter()
+                .find(|component| {
+                    probe -= component.weight;
+                    probe < 0.0
+                })
+                .unwrap_or(&components[components.len() - 1]);
+            let parameters = &component.parameters;
+            match component.distribution {
+                ComponentDistribution::Normal => {
+                    let mean = parameters.mean.unwrap_or(base);
+                    rng.gauss(mean, parameters.stddev.unwrap_or(mean * 0.1))
+                        .abs()
+                }
+                ComponentDistribution::Exponential => {
+                    rng.expovariate(parameters.rate.unwrap_or(1.0 / base))
+                }
+            }
+        }
+    };
+    (sampled as u64).clamp(1, size.saturating_mul(4))
+}
+
+fn matrix(shape: &[usize], element: &mut impl FnMut() -> Value) -> Value {
+    match shape.split_first() {
+        None => element(),
+        Some((&length, rest)) => Value::Array((0..length).map(|_| matrix(rest, element)).collect()),
+    }
+}
+
+fn number(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+const TYPES: [&str; 7] = [
+    "object", "array", "string", "integer", "number", "boolean", "null",
+];
+
+fn validate_schema(path: &str, schema: &Value) -> Result<(), ConfigError> {
+    let Value::Object(schema) = schema else {
+        return Err(ConfigError::new(path, "must be an object"));
+    };
+    match schema.get("type") {
+        None => {}
+        Some(Value::String(kind)) if TYPES.contains(&kind.as_str()) => {}
+        Some(Value::Array(kinds))
+            if !kinds.is_empty()
+                && kinds
+                    .iter()
+                    .all(|kind| kind.as_str().is_some_and(|kind| TYPES.contains(&kind))) => {}
+        Some(kind) => {
+            return Err(ConfigError::new(
+                format!("{path}.type"),
+                format!("unsupported type {kind}, expected one of {TYPES:?}"),
+            ))
+        }
+    }
+    for keyword in ["enum", "oneOf", "anyOf"] {
+        match schema.get(keyword) {
+            None => {}
+            Some(Value::Array(values)) if !values.is_empty() => {
+                if keyword != "enum" {
+                    for (index, branch) in values.iter().enumerate() {
+                        validate_schema(&format!("{path}.{keyword}[{index}]"), branch)?;
+                    }
+                }
+            }
+            Some(_) => {
+                return Err(ConfigError::new(
+                    format!("{path}.{keyword}"),
+                    "must be a non-empty array",
+                ))
+            }
+        }
+    }
+    match schema.get("properties") {
+        None => {}
+        Some(Value::Object(properties)) => {
+            for (name, property) in properties {
+                validate_schema(&format!("{path}.properties.{name}"), property)?;
+            }
+        }
+        Some(_) => {
+            return Err(ConfigError::new(
+                format!("{path}.properties"),
+                "must be an object",
+            ))
+        }
+    }
+    if let Some(items) = schema.get("items") {
+        validate_schema(&format!("{path}.items"), items)?;
+    }
+    for (low, high) in [
+        ("minItems", "maxItems"),
+        ("minLength", "maxLength"),
+        ("minimum", "maximum"),
+    ] {
+        let bound = |keyword: &str| -> Result<Option<f64>, ConfigError> {
+            let Some(value) = schema.get(keyword) else {
+                return Ok(None);
+            };
+            let path = format!("{path}.{keyword}");
+            let value = value
+                .as_f64()
+                .ok_or_else(|| ConfigError::new(&path, "must be a number"))?;
+            if low == "minimum" {
+                finite(&path, value)?;
+            } else {
+                non_negative(&path, value)?;
+            }
+            Ok(Some(value))
+        };
+        if let (Some(min), Some(max)) = (bound(low)?, bound(high)?) {
+            if min > max {
+                return Err(ConfigError::new(
+                    format!("{path}.{high}"),
+                    format!("must not be below {low} ({min}), got {max}"),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A document matching `schema`, which [`validate_schema`] accepted.
+fn document(schema: &Value, rng: &mut DeterministicRng) -> Value {
+    if let Some(value) = schema.get("const") {
+        return value.clone();
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        return values[rng.index(values.len())].clone();
+    }
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(Value::Array(branches)) = schema.get(keyword) {
+            return document(&branches[rng.index(branches.len())], rng);
+        }
+    }
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        Some(Value::Array(kinds)) => kinds[rng.index(kinds.len())].as_str().unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "null",
+    };
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    let length = |rng: &mut DeterministicRng, low: &str, high: &str, default_min: u64| {
+        let min = bound(low).map_or(default_min, |min| min as u64);
+        let max = bound(high).map_or(min + DEFAULT_SPREAD, |max| max as u64);
+        let min = min.min(max);
+        min + rng.index((max - min + 1) as usize) as u64
+    };
+    match kind {
+        "object" => {
+            let required: Vec<&str> = match schema.get("required") {
+                Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            let mut object = Map::new();
+            if let Some(Value::Object(properties)) = schema.get("properties") {
+                for (name, property) in properties {
+                    let present = rng.next_f64() < 0.5;
+                    if present || required.contains(&name.as_str()) {
+                        object.insert(name.clone(), document(property, rng));
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        "array" => {
+            let items = schema.get("items").unwrap_or(&Value::Null);
+            let length = length(rng, "minItems", "maxItems", 0);
+            Value::Array((0..length).map(|_| document(items, rng)).collect())
+        }
+        "string" => {
+            let length = length(rng, "minLength", "maxLength", 1);
+            let string = (0..length)
+                .map(|_| ALPHABET[rng.index(ALPHABET.len())] as char)
+                .collect::<String>();
+            Value::String(string)
+        }
+        "integer" => {
+            let min = bound("minimum").map_or_else(
+                || bound("maximum").map_or(0, |max| (max.floor() as i64 - 1000).min(0)),
+                |min| min.ceil() as i64,
+            );
+            let max = bound("maximum").map_or(min.saturating_add(1000), |max| max.floor() as i64);
+            let span = max.saturating_sub(min).max(0) as f64 + 1.0;
+            Value::from(
+                min.saturating_add((rng.next_f64() * span) as i64)
+                    .min(max.max(min)),
+            )
+        }
+        "number" => {
+            let min = bound("minimum")
+                .unwrap_or_else(|| bound("maximum").map_or(0.0, |max| (max - 1.0).min(0.0)));
+            let max = bound("maximum").unwrap_or(min + 1.0);
+            number(min + (max - min) * rng.next_f64())
+        }
+        "boolean" => Value::Bool(rng.next_f64() < 0.5),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator(spec: &str) -> PayloadGenerator {
+        PayloadGenerator::from_spec(serde_json::from_str(spec).unwrap(), 99).unwrap()
+    }
+
+    #[test]
+    fn bodies_depend_only_on_seed_and_sequence() {
+        let blobs = generator(r#"{"type": "blob", "size_bytes": 64, "distribution": "gamma"}"#);
+        let first: Vec<Value> = (0..50).map(|sequence| blobs.generate(sequence)).collect();
+        let reversed: Vec<Value> = (0..50)
+            .rev()
+            .map(|sequence| blobs.generate(sequence))
+            .collect();
+        assert!(first.iter().eq(reversed.iter().rev()));
+        assert_ne!(first[0], first[1]);
+        assert_eq!(first[7]["sequence"], 7);
+        assert!(first.iter().all(|body| {
+            let length = body["blob"].as_str().unwrap().len();
+            (1..=256).contains(&length)
+        }));
+
+        let fixed = generator(r#"{"type": "blob", "size_bytes": 32}"#);
+        assert_eq!(fixed.generate(3)["blob"].as_str().unwrap().len(), 32);
+        let reseeded = PayloadGenerator::from_spec(fixed.spec().clone(), 100).unwrap();
+        assert_ne!(reseeded.generate(3), fixed.generate(3));
+    }
+
+    #[test]
+    fn matrices_have_the_requested_shape() {
+        let matrices = generator(
+            r#"{"type": "matrix", "shape": [3, 4], "min": -5, "max": 5, "integers": true,
+                "fields": {"operation": "multiply", "parameters": {"factor": 2}}}"#,
+        );
+        let body = matrices.generate(0);
+        assert_eq!(body["operation"], "multiply");
+        let rows = body["data"].as_array().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| {
+            let row = row.as_array().unwrap();
+            row.len() == 4
+                && row
+                    .iter()
+                    .all(|value| (-5..5).contains(&value.as_i64().unwrap()))
+        }));
+        assert_eq!(body, matrices.generate(0));
+    }
+
+    #[test]
+    fn documents_match_their_schema() {
+        let documents = generator(
+            r#"{"type": "json", "schema": {
+                "type": "object",
+                "required": ["name", "tags", "price"],
+                "properties": {
+                    "name": {"type": "string", "minLength": 3, "maxLength": 5},
+                    "tags": {"type": "array", "items": {"enum": ["a", "b"]}, "maxItems": 2},
+                    "price": {"type": "number", "minimum": 1, "maximum": 2},
+                    "count": {"type": "integer", "minimum": 10, "maximum": 12},
+                    "kind": {"const": "widget"}
+                }
+            }}"#,
+        );
+        let mut optional = 0;
+        for sequence in 0..40 {
+            let body = documents.generate(sequence);
+            let name = body["name"].as_str().unwrap();
+            assert!((3..=5).contains(&name.len()));
+            let tags = body["tags"].as_array().unwrap();
+            assert!(tags.len() <= 2 && tags.iter().all(|tag| tag == "a" || tag == "b"));
+            assert!((1.0..=2.0).contains(&body["price"].as_f64().unwrap()));
+            if let Some(count) = body.get("count") {
+                assert!((10..=12).contains(&count.as_i64().unwrap()));
+                optional += 1;
+            }
+            assert!(body.get("kind").is_none_or(|kind| kind == "widget"));
+        }
+        assert!((1..40).contains(&optional), "{optional}");
+    }
+
+    #[test]
+    fn invalid_specs_are_located() {
+        let error = |spec: &str| {
+            let spec: PayloadSpec = serde_json::from_str(spec).unwrap();
+            spec.validate("request.payload").unwrap_err().to_string()
+        };
+        assert_eq!(
+            error(r#"{"type": "matrix", "shape": [2, 0]}"#),
+            "request.payload.shape: must list at least one dimension, all positive"
+        );
+        assert_eq!(
+            error(r#"{"type": "json", "schema": {"properties": {"a": {"type": "date"}}}}"#),
+            "request.payload.schema.properties.a.type: unsupported type \"date\", expected one \
+             of [\"object\", \"array\", \"string\", \"integer\", \"number\", \"boolean\", \
+             \"null\"]"
+        );
+        assert_eq!(
+            error(r#"{"type": "json", "schema": {"type": "array", "minItems": 3, "maxItems": 1}}"#),
+            "request.payload.schema.maxItems: must not be below minItems (3), got 1"
+        );
+    }
+}
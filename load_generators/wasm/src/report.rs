@@ -0,0 +1,372 @@
+//! Run results and threshold evaluation
+//!
+//! A [`RunRecorder`] collects the outcome of each request a host sends and
+//! produces a [`RunReport`]: request counts, error rates, throughput and
+//! latency percentiles per stage and overall, and whether the run stayed
+//! within a scenario's [`Thresholds`]. Reports serialise to JSON and CSV, so
+//! the WASM and native generators export the same results. As in
+//! `scripts.load_suite`, the overall statistics cover the requests included
+//! in metrics and the stage statistics every request of the stage.
+
+use crate::scenario::Thresholds;
+use crate::{LatencyRecorder, LatencySummary};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Statistics of the requests of a stage, or of all included requests.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageReport {
+    pub requests: u64,
+    pub included_requests: u64,
+    /// Requests without a successful response.
+    pub failed_requests: u64,
+    /// `failed_requests / requests`, or 0 without requests.
+    pub error_rate: f64,
+    /// Seconds from the intended start of the first request to the
+    /// completion of the last.
+    pub duration_s: f64,
+    /// Successful requests per second of `duration_s`.
+    pub throughput_rps: f64,
+    pub latency_ms: LatencySummary,
+}
+
+/// Outcome of checking one threshold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdResult {
+    /// `latency_ms.<statistic>`, `failure_rate` or `throughput_rps`.
+    pub metric: String,
+    pub limit: f64,
+    pub actual: f64,
+    pub passed: bool,
+}
+
+/// Results of a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Wall-clock duration of the run as measured by the host.
+    pub elapsed_s: f64,
+    /// The requests included in metrics.
+    pub overall: StageReport,
+    pub stages: BTreeMap<String, StageReport>,
+    /// Checked thresholds; empty until [`RunReport::evaluate`] is called.
+    pub thresholds: Vec<ThresholdResult>,
+    /// Whether every checked threshold passed.
+    pub passed: bool,
+}
+
+const CSV_HEADER: &str = "scope,stage,requests,included_requests,failed_requests,error_rate,\
+duration_s,throughput_rps,latency_count,latency_min_ms,latency_mean_ms,latency_stddev_ms,\
+latency_p50_ms,latency_p90_ms,latency_p95_ms,latency_p99_ms,latency_p99_9_ms,latency_max_ms";
+
+impl RunReport {
+    /// Check the overall statistics against `thresholds`, replacing the
+    /// results of earlier checks.
+    pub fn evaluate(&mut self, thresholds: &Thresholds) {
+        let overall = &self.overall;
+        let mut results: Vec<ThresholdResult> = thresholds
+            .latency_ms
+            .limits()
+            .into_iter()
+            .map(|(statistic, limit)| {
+                let actual = overall.latency_ms.statistic(statistic).unwrap_or(f64::NAN);
+                ThresholdResult {
+                    metric: format!("latency_ms.{statistic}"),
+                    limit,
+                    actual,
+                    passed: actual <= limit,
+                }
+            })
+            .collect();
+        if let Some(limit) = thresholds.max_failure_rate {
+            results.push(ThresholdResult {
+                metric: "failure_rate".to_string(),
+                limit,
+                actual: overall.error_rate,
+                passed: overall.error_rate <= limit,
+            });
+        }
+        if let Some(limit) = thresholds.min_throughput_rps {
+            results.push(ThresholdResult {
+                metric: "throughput_rps".to_string(),
+                limit,
+                actual: overall.throughput_rps,
+                passed: overall.throughput_rps >= limit,
+            });
+        }
+        self.passed = results.iter().all(|result| result.passed);
+        self.thresholds = results;
+    }
+
+    /// The thresholds that did not pass.
+    pub fn failures(&self) -> impl Iterator<Item = &ThresholdResult> {
+        self.thresholds.iter().filter(|result| !result.passed)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("reports serialise")
+    }
+
+    /// The overall and stage statistics, one row each, with a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{CSV_HEADER}\n");
+        let rows = std::iter::once(("overall", "", &self.overall)).chain(
+            self.stages
+                .iter()
+                .map(|(name, stage)| ("stage", name.as_str(), stage)),
+        );
+        for (scope, name, stage) in rows {
+            let latency = &stage.latency_ms;
+            let _ = writeln!(
+                csv,
+                "{scope},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                csv_field(name),
+                stage.requests,
+                stage.included_requests,
+                stage.failed_requests,
+                stage.error_rate,
+                stage.duration_s,
+                stage.throughput_rps,
+                latency.count,
+                latency.min,
+                latency.mean,
+                latency.stddev,
+                latency.p50,
+                latency.p90,
+                latency.p95,
+                latency.p99,
+                latency.p99_9,
+                latency.max,
+            );
+        }
+        csv
+    }
+
+    /// The threshold results, one row each, with a header.
+    pub fn thresholds_csv(&self) -> String {
+        let mut csv = "metric,limit,actual,passed\n".to_string();
+        for result in &self.thresholds {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{}",
+                result.metric, result.limit, result.actual, result.passed
+            );
+        }
+        csv
+    }
+}
+
+/// `value`, quoted if it contains a comma, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Default)]
+struct Aggregate {
+    requests: u64,
+    included: u64,
+    failed: u64,
+    latencies: LatencyRecorder,
+    first_s: Option<f64>,
+    last_s: Option<f64>,
+}
+
+impl Aggregate {
+    fn record(&mut self, intended_s: f64, latency_ms: f64, ok: bool, included: bool) {
+        self.requests += 1;
+        self.included += u64::from(included);
+        self.failed += u64::from(!ok);
+        self.latencies.record(latency_ms);
+        let completed_s = intended_s + latency_ms.max(0.0) / 1000.0;
+        self.first_s = Some(
+            self.first_s
+                .map_or(intended_s, |first| first.min(intended_s)),
+        );
+        self.last_s = Some(
+            self.last_s
+                .map_or(completed_s, |last| last.max(completed_s)),
+        );
+    }
+
+    fn report(&self) -> StageReport {
+        let duration_s = match (self.first_s, self.last_s) {
+            (Some(first), Some(last)) => (last - first).max(0.0),
+            _ => 0.0,
+        };
+        let succeeded = (self.requests - self.failed) as f64;
+        StageReport {
+            requests: self.requests,
+            included_requests: self.included,
+            failed_requests: self.failed,
+            error_rate: if self.requests == 0 {
+                0.0
+            } else {
+                self.failed as f64 / self.requests as f64
+            },
+            duration_s,
+            throughput_rps: if duration_s > 0.0 {
+                succeeded / duration_s
+            } else {
+                0.0
+            },
+            latency_ms: self.latencies.summary(),
+        }
+    }
+}
+
+/// Collects the outcomes of a run's requests into a [`RunReport`].
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct RunRecorder {
+    overall: Aggregate,
+    stages: BTreeMap<String, Aggregate>,
+}
+
+#[wasm_bindgen]
+impl RunRecorder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RunRecorder {
+        RunRecorder::default()
+    }
+
+    /// Record a request of `stage` due `intended_s` seconds into the run
+    /// that took `latency_ms` from then, and whether it succeeded.
+    pub fn record(
+        &mut self,
+        stage: &str,
+        include_in_metrics: bool,
+        intended_s: f64,
+        latency_ms: f64,
+        ok: bool,
+    ) {
+        if !self.stages.contains_key(stage) {
+            self.stages.insert(stage.to_string(), Aggregate::default());
+        }
+        if let Some(aggregate) = self.stages.get_mut(stage) {
+            aggregate.record(intended_s, latency_ms, ok, include_in_metrics);
+        }
+        if include_in_metrics {
+            self.overall.record(intended_s, latency_ms, ok, true);
+        }
+    }
+
+    /// Number of recorded requests.
+    pub fn count(&self) -> u64 {
+        self.stages.values().map(|stage| stage.requests).sum()
+    }
+
+    /// The report of a run lasting `elapsed_s` as JSON, with the
+    /// [`Thresholds`] given as JSON evaluated.
+    pub fn report_json(
+        &self,
+        elapsed_s: f64,
+        thresholds_json: Option<String>,
+    ) -> Result<String, JsValue> {
+        let mut report = self.report(elapsed_s);
+        if let Some(json) = thresholds_json {
+            let thresholds: Thresholds = serde_json::from_str(&json)
+                .map_err(|err| JsValue::from_str(&format!("failed to parse thresholds: {err}")))?;
+            report.evaluate(&thresholds);
+        }
+        Ok(report.to_json())
+    }
+
+    /// The statistics of a run lasting `elapsed_s` as CSV.
+    pub fn report_csv(&self, elapsed_s: f64) -> String {
+        self.report(elapsed_s).to_csv()
+    }
+}
+
+impl RunRecorder {
+    /// The report of a run lasting `elapsed_s`, no thresholds checked.
+    pub fn report(&self, elapsed_s: f64) -> RunReport {
+        RunReport {
+            elapsed_s,
+            overall: self.overall.report(),
+            stages: self
+                .stages
+                .iter()
+                .map(|(name, stage)| (name.clone(), stage.report()))
+                .collect(),
+            thresholds: Vec::new(),
+            passed: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded() -> RunRecorder {
+        let mut recorder = RunRecorder::new();
+        recorder.record("warmup", false, 0.0, 500.0, true);
+        for index in 0..10 {
+            let ok = index != 3;
+            recorder.record("steady", true, 1.0 + f64::from(index) * 0.1, 20.0, ok);
+        }
+        recorder.record("burst, 1", true, 2.0, 80.0, true);
+        recorder
+    }
+
+    #[test]
+    fn stages_and_included_requests_are_aggregated() {
+        let report = recorded().report(3.0);
+        let steady = &report.stages["steady"];
+        assert_eq!((steady.requests, steady.failed_requests), (10, 1));
+        assert_eq!(steady.error_rate, 0.1);
+        // 1.0 s to 1.9 s + 20 ms
+        assert!((steady.duration_s - 0.92).abs() < 1e-9);
+        assert!((steady.throughput_rps - 9.0 / 0.92).abs() < 1e-9);
+        assert_eq!(report.stages["warmup"].included_requests, 0);
+        assert_eq!(report.overall.requests, 11);
+        assert!((report.overall.latency_ms.max - 80.0).abs() <= 0.08);
+        assert!(report.passed && report.thresholds.is_empty());
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["stages"]["steady"]["failed_requests"], 1);
+        assert_eq!(json["overall"]["latency_ms"]["count"], 11);
+        assert_eq!(json["passed"], true);
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0].split(',').count(), 18);
+        assert!(lines[1].starts_with("overall,,11,11,1,"));
+        assert!(lines[2].starts_with("stage,\"burst, 1\",1,1,0,"));
+        assert!(lines[3].starts_with("stage,steady,10,10,1,0.1,"));
+        assert!(lines[4].starts_with("stage,warmup,1,0,0,0,"));
+    }
+
+    #[test]
+    fn thresholds_are_checked_against_included_requests() {
+        let mut report = recorded().report(3.0);
+        let thresholds: Thresholds = serde_json::from_str(
+            r#"{"latency_ms": {"p50": 25, "max": 50}, "max_failure_rate": 0.1,
+                "min_throughput_rps": 5}"#,
+        )
+        .unwrap();
+        report.evaluate(&thresholds);
+        let outcomes: Vec<(&str, bool)> = report
+            .thresholds
+            .iter()
+            .map(|result| (result.metric.as_str(), result.passed))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("latency_ms.p50", true),
+                ("latency_ms.max", false),
+                ("failure_rate", true),
+                ("throughput_rps", true),
+            ]
+        );
+        assert!(!report.passed);
+        assert_eq!(report.failures().count(), 1);
+        assert!(report.thresholds_csv().contains("\nlatency_ms.max,50,80.0"));
+    }
+}
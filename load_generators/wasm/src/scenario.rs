@@ -0,0 +1,1213 @@
+//! Load-suite scenario configuration.
+//!
+//! Typed form of the scenario documents read by `scripts.load_suite` and the
+//! k6 runner, shared by the WASM and native generators. A document is either
+//! `{"scenarios": [...]}` or a bare array of scenarios. Parsing is stricter
+//! than the Python loader: unknown fields, unknown distributions and values
+//! that would silently produce an empty or nonsensical plan are rejected with
+//! the path of the offending field, e.g.
+//! `scenarios[1].pattern.phases[0].duration_s: must be positive, got 0`.
+
+use crate::payload::{PayloadGenerator, PayloadSpec};
+use crate::users::ThinkTime;
+use crate::{MetricsWindows, PlanBuilder, Stage, StageSpec};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// A configuration error and where in the document it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// Path of the offending field, e.g. `scenarios[0].request.path`.
+    pub path: String,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub(crate) fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        ConfigError {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() || self.path == "." {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// One scenario of a load-suite document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_pattern")]
+    pub pattern: Pattern,
+    #[serde(default = "default_concurrency")]
+    pub concurrency: u32,
+    #[serde(default)]
+    pub seed: Seeds,
+    #[serde(default)]
+    pub request: RequestTemplate,
+    #[serde(default)]
+    pub tenants: Option<Tenants>,
+    #[serde(default)]
+    pub warmup: Option<Warmup>,
+    /// Windows at the start and end of the plan left out of metrics.
+    #[serde(default)]
+    pub windows: Option<MetricsWindows>,
+    /// Server to send requests to, unless overridden by the runner.
+    #[serde(default)]
+    pub target: Option<TargetConfig>,
+    /// Limits a run must stay within to pass.
+    #[serde(default)]
+    pub thresholds: Option<Thresholds>,
+}
+
+fn default_concurrency() -> u32 {
+    1
+}
+
+/// How requests are spread over time. The `type` field selects the pattern;
+/// without one a pattern is `steady`, as in `scripts.load_suite`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Pattern {
+    Steady {
+        duration_s: f64,
+        target_rps: f64,
+    },
+    Poisson {
+        duration_s: f64,
+        lambda_rps: f64,
+    },
+    /// Constant-rate stages back to back.
+    Burst {
+        stages: Vec<BurstStage>,
+    },
+    /// Linear ramps back to back.
+    Ramp {
+        phases: Vec<RampPhase>,
+    },
+    /// Pre-warm excluded from metrics, then the spike and a recovery, as in
+    /// the k6 runner.
+    Spike {
+        #[serde(default)]
+        pre_warm_duration_s: f64,
+        #[serde(default)]
+        pre_warm_rps: Option<f64>,
+        spike_duration_s: f64,
+        spike_rps: f64,
+        #[serde(default)]
+        recovery_duration_s: f64,
+        #[serde(default)]
+        recovery_rps: Option<f64>,
+    },
+    /// Stages of the [`PlanBuilder`] stage list.
+    Stages {
+        stages: Vec<StageSpec>,
+    },
+}
+
+impl Default for Pattern {
+    fn default() -> Self {
+        Pattern::Steady {
+            duration_s: 1.0,
+            target_rps: 1.0,
+        }
+    }
+}
+
+/// Deserialize a [`Pattern`], defaulting a missing `type` to `steady`.
+fn deserialize_pattern<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pattern, D::Error> {
+    let mut value = serde_json::Value::deserialize(deserializer)?;
+    if let Some(object) = value.as_object_mut() {
+        object
+            .entry("type")
+            .or_insert_with(|| serde_json::Value::from("steady"));
+    }
+    Pattern::deserialize(value).map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BurstStage {
+    pub duration_s: f64,
+    pub target_rps: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RampPhase {
+    pub duration_s: f64,
+    pub start_rps: f64,
+    /// Defaults to `start_rps`.
+    #[serde(default)]
+    pub end_rps: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Seeds {
+    #[serde(default)]
+    pub traffic: Option<u64>,
+    #[serde(default)]
+    pub payload: Option<u64>,
+}
+
+impl Seeds {
+    /// Seed of arrivals and parameter sampling; 1 by default, as in
+    /// `scripts.load_suite`.
+    pub fn traffic(&self) -> u64 {
+        self.traffic.unwrap_or(1)
+    }
+
+    /// Seed of payload sampling; the traffic seed plus 17 by default.
+    pub fn payload(&self) -> u64 {
+        self.payload.unwrap_or(self.traffic() + 17)
+    }
+}
+
+/// The request sent for every plan entry. Path placeholders such as `{id}`
+/// or `{id:int}` are filled from `path_params`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestTemplate {
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default = "default_path")]
+    pub path: String,
+    #[serde(default)]
+    pub path_params: BTreeMap<String, PathParam>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub payload_size_bytes: u64,
+    #[serde(default)]
+    pub payload_distribution: PayloadDistribution,
+    #[serde(default)]
+    pub payload_parameters: PayloadParameters,
+    /// Body of each request, replacing the blob sized by
+    /// `payload_size_bytes`.
+    #[serde(default)]
+    pub payload: Option<PayloadSpec>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_path() -> String {
+    "/".to_string()
+}
+
+impl Default for RequestTemplate {
+    fn default() -> Self {
+        RequestTemplate {
+            method: default_method(),
+            path: default_path(),
+            path_params: BTreeMap::new(),
+            headers: BTreeMap::new(),
+            payload_size_bytes: 0,
+            payload_distribution: PayloadDistribution::default(),
+            payload_parameters: PayloadParameters::default(),
+            payload: None,
+        }
+    }
+}
+
+impl RequestTemplate {
+    /// Names of the path's placeholders, in order.
+    pub fn placeholders(&self) -> Result<Vec<&str>, String> {
+        let mut names = Vec::new();
+        let mut rest = self.path.as_str();
+        while let Some(open) = rest.find('{') {
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed placeholder in {:?}", self.path))?;
+            let placeholder = &rest[open + 1..open + close];
+            let name = placeholder.split(':').next().unwrap_or_default();
+            if name.is_empty() {
+                return Err(format!("empty placeholder in {:?}", self.path));
+            }
+            names.push(name);
+            rest = &rest[open + close + 1..];
+        }
+        Ok(names)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathParam {
+    #[serde(default)]
+    pub distribution: ParamDistribution,
+    #[serde(default)]
+    pub parameters: ParamParameters,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamDistribution {
+    /// `start`, `start + step`, ...
+    #[default]
+    Sequential,
+    /// Zipf-distributed ranks below `size` with exponent `s`.
+    Zipf,
+    /// Uniform random integers.
+    Random,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamParameters {
+    #[serde(default)]
+    pub start: Option<i64>,
+    #[serde(default)]
+    pub step: Option<i64>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub s: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadDistribution {
+    #[default]
+    Fixed,
+    Lognormal,
+    Gamma,
+    Mixture,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PayloadParameters {
+    #[serde(default)]
+    pub mean: Option<f64>,
+    #[serde(default)]
+    pub sigma: Option<f64>,
+    #[serde(default)]
+    pub shape: Option<f64>,
+    #[serde(default)]
+    pub scale: Option<f64>,
+    #[serde(default)]
+    pub components: Vec<MixtureComponent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MixtureComponent {
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(default)]
+    pub distribution: ComponentDistribution,
+    #[serde(default)]
+    pub parameters: ComponentParameters,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentDistribution {
+    #[default]
+    Normal,
+    Exponential,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComponentParameters {
+    #[serde(default)]
+    pub mean: Option<f64>,
+    #[serde(default)]
+    pub stddev: Option<f64>,
+    #[serde(default)]
+    pub rate: Option<f64>,
+}
+
+/// Tenant header of each request: drawn from weighted tenants or rotated
+/// through a sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tenants {
+    #[serde(default)]
+    pub header: Option<String>,
+    #[serde(default)]
+    pub distribution: Option<Vec<TenantWeight>>,
+    #[serde(default)]
+    pub rotation_order: Option<TenantRotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantWeight {
+    pub tenant: String,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantRotation {
+    pub sequence: Vec<String>,
+    #[serde(default = "default_cycle_seconds")]
+    pub cycle_seconds: f64,
+}
+
+fn default_cycle_seconds() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Warmup {
+    #[serde(default)]
+    pub duration_s: f64,
+    #[serde(default = "default_discard_metrics")]
+    pub discard_metrics: bool,
+}
+
+fn default_discard_metrics() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    /// e.g. `http://127.0.0.1:8000`.
+    pub base_url: String,
+    /// Seconds to wait for a response.
+    #[serde(default)]
+    pub timeout_s: Option<f64>,
+}
+
+/// Pass/fail limits of a run. Latencies are in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Thresholds {
+    #[serde(default)]
+    pub latency_ms: LatencyThresholds,
+    /// Largest acceptable fraction of failed requests.
+    #[serde(default)]
+    pub max_failure_rate: Option<f64>,
+    /// Smallest acceptable rate of completed requests.
+    #[serde(default)]
+    pub min_throughput_rps: Option<f64>,
+}
+
+/// Upper bounds on latency statistics, named as in a
+/// [`LatencySummary`](crate::LatencySummary).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LatencyThresholds {
+    #[serde(default)]
+    pub mean: Option<f64>,
+    #[serde(default)]
+    pub p50: Option<f64>,
+    #[serde(default)]
+    pub p90: Option<f64>,
+    #[serde(default)]
+    pub p95: Option<f64>,
+    #[serde(default)]
+    pub p99: Option<f64>,
+    #[serde(default)]
+    pub p99_9: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl LatencyThresholds {
+    /// The set limits as (statistic, limit).
+    pub fn limits(&self) -> Vec<(&'static str, f64)> {
+        [
+            ("mean", self.mean),
+            ("p50", self.p50),
+            ("p90", self.p90),
+            ("p95", self.p95),
+            ("p99", self.p99),
+            ("p99_9", self.p99_9),
+            ("max", self.max),
+        ]
+        .into_iter()
+        .filter_map(|(name, limit)| limit.map(|limit| (name, limit)))
+        .collect()
+    }
+}
+
+/// Parse and validate a scenario document.
+pub fn parse_scenarios(json: &str) -> Result<Vec<Scenario>, ConfigError> {
+    let document: serde_json::Value = serde_json::from_str(json)
+        .map_err(|err| ConfigError::new("", format!("invalid JSON: {err}")))?;
+    let (prefix, list) = match document {
+        serde_json::Value::Object(mut object) => match object.remove("scenarios") {
+            Some(list) => ("scenarios", list),
+            None => {
+                return Err(ConfigError::new(
+                    "",
+                    "expected a list of scenarios or an object with a \"scenarios\" list",
+                ))
+            }
+        },
+        list => ("", list),
+    };
+
+    let scenarios: Vec<Scenario> = serde_path_to_error::deserialize(list).map_err(|err| {
+        let path = err.path().to_string();
+        ConfigError::new(join(prefix, &path), err.into_inner().to_string())
+    })?;
+
+    let mut identifiers = HashSet::new();
+    for (index, scenario) in scenarios.iter().enumerate() {
+        let path = join(prefix, &format!("[{index}]"));
+        scenario.validate(&path)?;
+        let identifier = scenario.identifier().unwrap_or_default();
+        if !identifiers.insert(identifier) {
+            return Err(ConfigError::new(
+                path,
+                format!("duplicate scenario {identifier:?}"),
+            ));
+        }
+    }
+    Ok(scenarios)
+}
+
+fn join(prefix: &str, path: &str) -> String {
+    match (prefix.is_empty(), path.is_empty() || path == ".") {
+        (true, _) => path.to_string(),
+        (false, true) => prefix.to_string(),
+        (false, false) if path.starts_with('[') => format!("{prefix}{path}"),
+        (false, false) => format!("{prefix}.{path}"),
+    }
+}
+
+/// Parse a scenario document and return the scenario whose id or name is
+/// `identifier`.
+pub fn find_scenario(json: &str, identifier: &str) -> Result<Scenario, ConfigError> {
+    let scenarios = parse_scenarios(json)?;
+    let known: Vec<_> = scenarios
+        .iter()
+        .filter_map(Scenario::identifier)
+        .map(str::to_string)
+        .collect();
+    scenarios
+        .into_iter()
+        .find(|scenario| {
+            scenario.id.as_deref() == Some(identifier)
+                || scenario.name.as_deref() == Some(identifier)
+        })
+        .ok_or_else(|| {
+            ConfigError::new(
+                "",
+                format!("no scenario {identifier:?}; the document has {known:?}"),
+            )
+        })
+}
+
+/// Check the parameters of a payload size distribution.
+pub(crate) fn validate_payload_parameters(
+    path: &str,
+    distribution: PayloadDistribution,
+    parameters: &PayloadParameters,
+) -> Result<(), ConfigError> {
+    optional(&format!("{path}.sigma"), parameters.sigma, positive)?;
+    optional(&format!("{path}.shape"), parameters.shape, positive)?;
+    optional(&format!("{path}.scale"), parameters.scale, positive)?;
+    if distribution == PayloadDistribution::Mixture {
+        for (index, component) in parameters.components.iter().enumerate() {
+            let component_path = format!("{path}.components[{index}]");
+            positive(&format!("{component_path}.weight"), component.weight)?;
+            let parameters = &component.parameters;
+            let parameters_path = format!("{component_path}.parameters");
+            optional(
+                &format!("{parameters_path}.mean"),
+                parameters.mean,
+                non_negative,
+            )?;
+            optional(
+                &format!("{parameters_path}.stddev"),
+                parameters.stddev,
+                non_negative,
+            )?;
+            optional(
+                &format!("{parameters_path}.rate"),
+                parameters.rate,
+                positive,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn finite(path: &str, value: f64) -> Result<(), ConfigError> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::new(
+            path,
+            format!("must be a finite number, got {value}"),
+        ))
+    }
+}
+
+pub(crate) fn positive(path: &str, value: f64) -> Result<(), ConfigError> {
+    finite(path, value)?;
+    if value > 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::new(
+            path,
+            format!("must be positive, got {value}"),
+        ))
+    }
+}
+
+pub(crate) fn non_negative(path: &str, value: f64) -> Result<(), ConfigError> {
+    finite(path, value)?;
+    if value >= 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::new(
+            path,
+            format!("must not be negative, got {value}"),
+        ))
+    }
+}
+
+fn optional(
+    path: &str,
+    value: Option<f64>,
+    check: fn(&str, f64) -> Result<(), ConfigError>,
+) -> Result<(), ConfigError> {
+    value.map_or(Ok(()), |value| check(path, value))
+}
+
+impl Scenario {
+    /// The scenario's id, or else its name.
+    pub fn identifier(&self) -> Option<&str> {
+        self.id.as_deref().or(self.name.as_deref())
+    }
+
+    /// Check the scenario, reporting errors under `path`.
+    pub fn validate(&self, path: &str) -> Result<(), ConfigError> {
+        if self.identifier().is_none_or(str::is_empty) {
+            return Err(ConfigError::new(path, "missing \"id\" or \"name\""));
+        }
+        if self.concurrency == 0 {
+            return Err(ConfigError::new(
+                format!("{path}.concurrency"),
+                "must be at least 1",
+            ));
+        }
+        self.validate_pattern(&format!("{path}.pattern"))?;
+        self.validate_request(&format!("{path}.request"))?;
+        if let Some(tenants) = &self.tenants {
+            validate_tenants(&format!("{path}.tenants"), tenants)?;
+        }
+        if let Some(warmup) = &self.warmup {
+            non_negative(&format!("{path}.warmup.duration_s"), warmup.duration_s)?;
+        }
+        if let Some(windows) = &self.windows {
+            non_negative(&format!("{path}.windows.warmup_s"), windows.warmup_s)?;
+            non_negative(&format!("{path}.windows.cooldown_s"), windows.cooldown_s)?;
+        }
+        if let Some(target) = &self.target {
+            let path = format!("{path}.target");
+            let url = &target.base_url;
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(ConfigError::new(
+                    format!("{path}.base_url"),
+                    format!("must be an http:// or https:// URL, got {url:?}"),
+                ));
+            }
+            optional(&format!("{path}.timeout_s"), target.timeout_s, positive)?;
+        }
+        if let Some(thresholds) = &self.thresholds {
+            let path = format!("{path}.thresholds");
+            for (name, limit) in thresholds.latency_ms.limits() {
+                positive(&format!("{path}.latency_ms.{name}"), limit)?;
+            }
+            if let Some(rate) = thresholds.max_failure_rate {
+                if !(0.0..=1.0).contains(&rate) {
+                    return Err(ConfigError::new(
+                        format!("{path}.max_failure_rate"),
+                        format!("must be a fraction between 0 and 1, got {rate}"),
+                    ));
+                }
+            }
+            optional(
+                &format!("{path}.min_throughput_rps"),
+                thresholds.min_throughput_rps,
+                non_negative,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn validate_pattern(&self, path: &str) -> Result<(), ConfigError> {
+        let field = |name: &str| format!("{path}.{name}");
+        match &self.pattern {
+            Pattern::Steady {
+                duration_s,
+                target_rps,
+            } => {
+                positive(&field("duration_s"), *duration_s)?;
+                positive(&field("target_rps"), *target_rps)
+            }
+            Pattern::Poisson {
+                duration_s,
+                lambda_rps,
+            } => {
+                positive(&field("duration_s"), *duration_s)?;
+                positive(&field("lambda_rps"), *lambda_rps)
+            }
+            Pattern::Burst { stages } => {
+                if stages.is_empty() {
+                    return Err(ConfigError::new(field("stages"), "must not be empty"));
+                }
+                for (index, stage) in stages.iter().enumerate() {
+                    let stage_path = format!("{path}.stages[{index}]");
+                    positive(&format!("{stage_path}.duration_s"), stage.duration_s)?;
+                    non_negative(&format!("{stage_path}.target_rps"), stage.target_rps)?;
+                }
+                Ok(())
+            }
+            Pattern::Ramp { phases } => {
+                if phases.is_empty() {
+                    return Err(ConfigError::new(field("phases"), "must not be empty"));
+                }
+                for (index, phase) in phases.iter().enumerate() {
+                    let phase_path = format!("{path}.phases[{index}]");
+                    positive(&format!("{phase_path}.duration_s"), phase.duration_s)?;
+                    non_negative(&format!("{phase_path}.start_rps"), phase.start_rps)?;
+                    optional(
+                        &format!("{phase_path}.end_rps"),
+                        phase.end_rps,
+                        non_negative,
+                    )?;
+                }
+                Ok(())
+            }
+            Pattern::Spike {
+                pre_warm_duration_s,
+                pre_warm_rps,
+                spike_duration_s,
+                spike_rps,
+                recovery_duration_s,
+                recovery_rps,
+            } => {
+                non_negative(&field("pre_warm_duration_s"), *pre_warm_duration_s)?;
+                optional(&field("pre_warm_rps"), *pre_warm_rps, non_negative)?;
+                positive(&field("spike_duration_s"), *spike_duration_s)?;
+                positive(&field("spike_rps"), *spike_rps)?;
+                non_negative(&field("recovery_duration_s"), *recovery_duration_s)?;
+                optional(&field("recovery_rps"), *recovery_rps, non_negative)
+            }
+            Pattern::Stages { stages } => {
+                if stages.is_empty() {
+                    return Err(ConfigError::new(field("stages"), "must not be empty"));
+                }
+                for (index, spec) in stages.iter().enumerate() {
+                    validate_stage(&format!("{path}.stages[{index}]"), &spec.stage)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn validate_request(&self, path: &str) -> Result<(), ConfigError> {
+        let request = &self.request;
+        let method = &request.method;
+        if method.is_empty() || !method.bytes().all(|byte| byte.is_ascii_alphabetic()) {
+            return Err(ConfigError::new(
+                format!("{path}.method"),
+                format!("must be an HTTP method such as GET or POST, got {method:?}"),
+            ));
+        }
+        if !request.path.starts_with('/') {
+            return Err(ConfigError::new(
+                format!("{path}.path"),
+                format!("must start with '/', got {:?}", request.path),
+            ));
+        }
+        let placeholders = request
+            .placeholders()
+            .map_err(|message| ConfigError::new(format!("{path}.path"), message))?;
+        for name in &placeholders {
+            if !request.path_params.contains_key(*name) {
+                return Err(ConfigError::new(
+                    format!("{path}.path_params"),
+                    format!("missing parameter {name:?} used in the path"),
+                ));
+            }
+        }
+        for (name, param) in &request.path_params {
+            let param_path = format!("{path}.path_params.{name}");
+            if !placeholders.contains(&name.as_str()) {
+                return Err(ConfigError::new(
+                    param_path,
+                    format!("not used in the path {:?}", request.path),
+                ));
+            }
+            if param.distribution == ParamDistribution::Zipf {
+                if param.parameters.size.unwrap_or(1) == 0 {
+                    return Err(ConfigError::new(
+                        format!("{param_path}.parameters.size"),
+                        "must be at least 1",
+                    ));
+                }
+                optional(
+                    &format!("{param_path}.parameters.s"),
+                    param.parameters.s,
+                    positive,
+                )?;
+            }
+        }
+        for name in request.headers.keys() {
+            let valid = !name.is_empty()
+                && name
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphanumeric() || b"-_".contains(&byte));
+            if !valid {
+                return Err(ConfigError::new(
+                    format!("{path}.headers"),
+                    format!("invalid header name {name:?}"),
+                ));
+            }
+        }
+
+        validate_payload_parameters(
+            &format!("{path}.payload_parameters"),
+            request.payload_distribution,
+            &request.payload_parameters,
+        )?;
+        if let Some(payload) = &request.payload {
+            payload.validate(&format!("{path}.payload"))?;
+        }
+        Ok(())
+    }
+
+    /// Generator of the scenario's request bodies, seeded from the payload
+    /// seed: the request's `payload`, or else a blob of about
+    /// `payload_size_bytes` as sent by the Python harness. Like the k6
+    /// runner, `GET` and `DELETE` requests have no body unless a `payload`
+    /// is given.
+    pub fn payloads(&self) -> Option<PayloadGenerator> {
+        let request = &self.request;
+        let spec = match &request.payload {
+            Some(spec) => spec.clone(),
+            None if request.payload_size_bytes == 0
+                || ["GET", "DELETE"].contains(&request.method.to_ascii_uppercase().as_str()) =>
+            {
+                return None
+            }
+            None => PayloadSpec::Blob {
+                size_bytes: request.payload_size_bytes,
+                distribution: request.payload_distribution,
+                parameters: request.payload_parameters.clone(),
+            },
+        };
+        PayloadGenerator::from_spec(spec, self.seed.payload()).ok()
+    }
+
+    /// The scenario's plan: the warmup request, if any, then the pattern,
+    /// labelled as in `scripts.load_suite` and the k6 runner. Poisson
+    /// arrivals are drawn from the traffic seed. Requests inside the
+    /// scenario's `windows` are left out of metrics.
+    pub fn plan(&self) -> PlanBuilder {
+        let mut builder = PlanBuilder::new(self.seed.traffic());
+        if let Some(windows) = self.windows {
+            builder.set_windows(windows);
+        }
+        if let Some(warmup) = &self.warmup {
+            builder.warmup(warmup.duration_s, warmup.discard_metrics);
+        }
+        let labelled = |stage: Stage, label: &str, include_in_metrics: bool| StageSpec {
+            stage,
+            label: Some(label.to_string()),
+            include_in_metrics: Some(include_in_metrics),
+        };
+        match &self.pattern {
+            Pattern::Steady {
+                duration_s,
+                target_rps,
+            } => builder.steady(*target_rps, *duration_s),
+            Pattern::Poisson {
+                duration_s,
+                lambda_rps,
+            } => builder.poisson(*lambda_rps, *duration_s),
+            Pattern::Burst { stages } => {
+                for stage in stages {
+                    let steady = Stage::Steady {
+                        duration_s: stage.duration_s,
+                        target_rps: stage.target_rps,
+                    };
+                    builder.add(&labelled(steady, "burst", true));
+                }
+            }
+            Pattern::Ramp { phases } => {
+                for phase in phases {
+                    builder.ramp(
+                        phase.start_rps,
+                        phase.end_rps.unwrap_or(phase.start_rps),
+                        phase.duration_s,
+                    );
+                }
+            }
+            Pattern::Spike {
+                pre_warm_duration_s,
+                pre_warm_rps,
+                spike_duration_s,
+                spike_rps,
+                recovery_duration_s,
+                recovery_rps,
+            } => {
+                let recovery_rps = recovery_rps.unwrap_or((spike_rps * 0.3).max(1.0));
+                let steady = |duration_s: f64, target_rps: f64| Stage::Steady {
+                    duration_s,
+                    target_rps,
+                };
+                builder.add(&labelled(
+                    steady(*pre_warm_duration_s, pre_warm_rps.unwrap_or(recovery_rps)),
+                    "spike-prewarm",
+                    false,
+                ));
+                builder.add(&labelled(
+                    steady(*spike_duration_s, *spike_rps),
+                    "spike",
+                    true,
+                ));
+                builder.add(&labelled(
+                    steady(*recovery_duration_s, recovery_rps),
+                    "spike-recovery",
+                    true,
+                ));
+            }
+            Pattern::Stages { stages } => {
+                for spec in stages {
+                    builder.add(spec);
+                }
+            }
+        }
+        builder
+    }
+}
+
+fn validate_stage(path: &str, stage: &Stage) -> Result<(), ConfigError> {
+    let field = |name: &str| format!("{path}.{name}");
+    match *stage {
+        Stage::Warmup { duration_s, .. } | Stage::Pause { duration_s } => {
+            non_negative(&field("duration_s"), duration_s)
+        }
+        Stage::Steady {
+            duration_s,
+            target_rps,
+        } => {
+            positive(&field("duration_s"), duration_s)?;
+            non_negative(&field("target_rps"), target_rps)
+        }
+        Stage::Ramp {
+            duration_s,
+            start_rps,
+            end_rps,
+            resolution,
+        } => {
+            positive(&field("duration_s"), duration_s)?;
+            non_negative(&field("start_rps"), start_rps)?;
+            non_negative(&field("end_rps"), end_rps)?;
+            positive(&field("resolution"), resolution)
+        }
+        Stage::Step {
+            duration_s,
+            start_rps,
+            end_rps,
+            steps,
+        } => {
+            positive(&field("duration_s"), duration_s)?;
+            non_negative(&field("start_rps"), start_rps)?;
+            non_negative(&field("end_rps"), end_rps)?;
+            if steps == 0 {
+                return Err(ConfigError::new(field("steps"), "must be at least 1"));
+            }
+            Ok(())
+        }
+        Stage::Spike {
+            duration_s,
+            base_rps,
+            peak_rps,
+            peak_duration_s,
+        } => {
+            positive(&field("duration_s"), duration_s)?;
+            non_negative(&field("base_rps"), base_rps)?;
+            positive(&field("peak_rps"), peak_rps)?;
+            positive(&field("peak_duration_s"), peak_duration_s)?;
+            if peak_duration_s > duration_s {
+                return Err(ConfigError::new(
+                    field("peak_duration_s"),
+                    format!("must not exceed duration_s ({duration_s}), got {peak_duration_s}"),
+                ));
+            }
+            Ok(())
+        }
+        Stage::Poisson {
+            duration_s,
+            lambda_rps,
+        } => {
+            positive(&field("duration_s"), duration_s)?;
+            positive(&field("lambda_rps"), lambda_rps)
+        }
+        Stage::Users {
+            duration_s,
+            users,
+            ramp_up_s,
+            ref think_time,
+        } => {
+            positive(&field("duration_s"), duration_s)?;
+            if users == 0 {
+                return Err(ConfigError::new(field("users"), "must be at least 1"));
+            }
+            non_negative(&field("ramp_up_s"), ramp_up_s)?;
+            let think_path = field("think_time");
+            match *think_time {
+                ThinkTime::Constant { seconds } => {
+                    non_negative(&format!("{think_path}.seconds"), seconds)
+                }
+                ThinkTime::Uniform { min_s, max_s } => {
+                    non_negative(&format!("{think_path}.min_s"), min_s)?;
+                    non_negative(&format!("{think_path}.max_s"), max_s)?;
+                    if max_s < min_s {
+                        return Err(ConfigError::new(
+                            format!("{think_path}.max_s"),
+                            format!("must not be below min_s ({min_s}), got {max_s}"),
+                        ));
+                    }
+                    Ok(())
+                }
+                ThinkTime::Exponential { mean_s } => {
+                    non_negative(&format!("{think_path}.mean_s"), mean_s)
+                }
+                ThinkTime::Lognormal { median_s, sigma } => {
+                    positive(&format!("{think_path}.median_s"), median_s)?;
+                    non_negative(&format!("{think_path}.sigma"), sigma)
+                }
+            }
+        }
+    }
+}
+
+fn validate_tenants(path: &str, tenants: &Tenants) -> Result<(), ConfigError> {
+    if tenants.distribution.is_some() && tenants.rotation_order.is_some() {
+        return Err(ConfigError::new(
+            path,
+            "set either \"distribution\" or \"rotation_order\", not both",
+        ));
+    }
+    if let Some(distribution) = &tenants.distribution {
+        if distribution.is_empty() {
+            return Err(ConfigError::new(
+                format!("{path}.distribution"),
+                "must not be empty",
+            ));
+        }
+        for (index, tenant) in distribution.iter().enumerate() {
+            positive(
+                &format!("{path}.distribution[{index}].weight"),
+                tenant.weight,
+            )?;
+        }
+    }
+    if let Some(rotation) = &tenants.rotation_order {
+        if rotation.sequence.is_empty() {
+            return Err(ConfigError::new(
+                format!("{path}.rotation_order.sequence"),
+                "must not be empty",
+            ));
+        }
+        non_negative(
+            &format!("{path}.rotation_order.cycle_seconds"),
+            rotation.cycle_seconds,
+        )?;
+    }
+    if tenants.header.is_none()
+        && (tenants.distribution.is_some() || tenants.rotation_order.is_some())
+    {
+        return Err(ConfigError::new(
+            format!("{path}.header"),
+            "required to send the chosen tenant",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(json: &str) -> String {
+        parse_scenarios(json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn documents_parse_and_compile_to_plans() {
+        let json = r#"{
+            "version": "v0.1.4",
+            "scenarios": [
+                {"name": "smoke", "description": "Baseline release verification"},
+                {
+                    "id": "items-ramp",
+                    "concurrency": 8,
+                    "seed": {"traffic": 7},
+                    "warmup": {"duration_s": 2},
+                    "pattern": {"type": "ramp", "phases": [
+                        {"duration_s": 2, "start_rps": 0, "end_rps": 16}
+                    ]},
+                    "request": {
+                        "method": "POST",
+                        "path": "/items/{item_id:int}",
+                        "path_params": {"item_id": {"distribution": "zipf",
+                                                    "parameters": {"size": 100, "s": 1.1}}},
+                        "payload_size_bytes": 256,
+                        "payload_distribution": "lognormal"
+                    },
+                    "tenants": {"header": "X-Tenant-ID", "rotation_order": {"sequence": ["a", "b"]}},
+                    "target": {"base_url": "http://127.0.0.1:8000"},
+                    "thresholds": {"latency_ms": {"p99": 250}, "max_failure_rate": 0.01}
+                }
+            ]
+        }"#;
+        let scenarios = parse_scenarios(json).unwrap();
+        assert_eq!(scenarios.len(), 2);
+        assert_eq!(scenarios[0].identifier(), Some("smoke"));
+        assert_eq!(scenarios[0].pattern, Pattern::default());
+
+        let ramp = find_scenario(json, "items-ramp").unwrap();
+        assert_eq!(ramp.seed.payload(), 24);
+        assert_eq!(ramp.request.placeholders().unwrap(), ["item_id"]);
+        assert_eq!(
+            ramp.thresholds.as_ref().unwrap().latency_ms.limits(),
+            [("p99", 250.0)]
+        );
+        let payloads = ramp.payloads().unwrap();
+        assert!(matches!(
+            payloads.spec(),
+            PayloadSpec::Blob {
+                size_bytes: 256,
+                ..
+            }
+        ));
+        assert_eq!(payloads.generate(5), ramp.payloads().unwrap().generate(5));
+        assert!(scenarios[0].payloads().is_none());
+        let plan = ramp.plan();
+        assert_eq!(plan.total_duration_s(), 4.0);
+        let entries = plan.entries();
+        assert_eq!(
+            (entries[0].stage.as_str(), entries[0].include_in_metrics),
+            ("warmup", false)
+        );
+        assert!(entries[1..]
+            .iter()
+            .all(|entry| entry.stage == "ramp" && entry.offset_s >= 2.0));
+
+        let bare = r#"[{"id": "spiky", "pattern": {"type": "spike", "pre_warm_duration_s": 1,
+                        "spike_duration_s": 1, "spike_rps": 8}}]"#;
+        let spike = parse_scenarios(bare).unwrap().remove(0).plan();
+        let entries = spike.entries();
+        let stages: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.stage.as_str(), entry.include_in_metrics))
+            .collect();
+        assert_eq!(stages[0], ("spike-prewarm", false));
+        assert_eq!(stages.iter().filter(|stage| stage.0 == "spike").count(), 8);
+        assert!(find_scenario(bare, "missing")
+            .unwrap_err()
+            .message
+            .contains("spiky"));
+    }
+
+    #[test]
+    fn pattern_type_defaults_to_steady() {
+        let scenarios =
+            parse_scenarios(r#"[{"id": "a", "pattern": {"duration_s": 2, "target_rps": 4}}]"#)
+                .unwrap();
+        assert_eq!(
+            scenarios[0].pattern,
+            Pattern::Steady {
+                duration_s: 2.0,
+                target_rps: 4.0
+            }
+        );
+    }
+
+    #[test]
+    fn bad_configs_are_located() {
+        assert_eq!(
+            error(r#"{"scenarios": [{"id": "a"}, {"id": "b", "concurency": 2}]}"#),
+            "scenarios[1].concurency: unknown field `concurency`, expected one of `id`, `name`, \
+             `description`, `pattern`, `concurrency`, `seed`, `request`, `tenants`, `warmup`, \
+             `windows`, `target`, `thresholds`"
+        );
+        assert_eq!(
+            error(
+                r#"[{"id": "a", "pattern": {"type": "ramp", "phases": [{"duration_s": 0, "start_rps": 1}]}}]"#
+            ),
+            "[0].pattern.phases[0].duration_s: must be positive, got 0"
+        );
+        assert!(error(r#"[{"id": "a", "pattern": {"type": "zigzag"}}]"#)
+            .contains("unknown variant `zigzag`"));
+        assert_eq!(
+            error(r#"[{"id": "a", "request": {"path": "/items/{item_id}"}}]"#),
+            "[0].request.path_params: missing parameter \"item_id\" used in the path"
+        );
+        assert_eq!(
+            error(
+                r#"[{"id": "a", "request": {"path_params": {"x": {"distribution": "uniform"}}}}]"#
+            ),
+            "[0].request.path_params.x.distribution: unknown variant `uniform`, expected one of \
+             `sequential`, `zipf`, `random`"
+        );
+        assert_eq!(
+            error(r#"[{"id": "a", "thresholds": {"max_failure_rate": 5}}]"#),
+            "[0].thresholds.max_failure_rate: must be a fraction between 0 and 1, got 5"
+        );
+        assert_eq!(
+            error(r#"[{"id": "a", "target": {"base_url": "127.0.0.1:8000"}}]"#),
+            "[0].target.base_url: must be an http:// or https:// URL, got \"127.0.0.1:8000\""
+        );
+        assert_eq!(
+            error(r#"[{"id": "a", "request": {"payload": {"type": "matrix", "shape": []}}}]"#),
+            "[0].request.payload.shape: must list at least one dimension, all positive"
+        );
+        assert_eq!(
+            error(r#"[{"id": "a", "windows": {"cooldown_s": -1}}]"#),
+            "[0].windows.cooldown_s: must not be negative, got -1"
+        );
+        assert_eq!(
+            error(r#"[{"id": "a"}, {"name": "a"}]"#),
+            "[1]: duplicate scenario \"a\""
+        );
+        assert_eq!(
+            error(r#"[{"description": "x"}]"#),
+            "[0]: missing \"id\" or \"name\""
+        );
+        assert!(error(r#"{"version": 1}"#).contains("\"scenarios\" list"));
+    }
+}
@@ -0,0 +1,246 @@
+//! Closed-loop stages
+//!
+//! A `users` stage has no precomputed offsets: a fixed number of virtual
+//! users each send a request, wait for its response, think for a while and
+//! send the next until the stage ends, like the k6 and Locust harnesses. The
+//! load such a stage offers therefore follows the server's latency, whereas
+//! the offset-based stages of a plan keep their rate whatever the latency.
+//! [`VirtualUsers`] schedules the requests of one such stage; the think
+//! times of each user are drawn from their own seeded generator, so they do
+//! not depend on how the users' requests interleave.
+
+use crate::{to_js, DeterministicRng, ScheduleEntry};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Sequence numbers reserved for the requests of each closed-loop stage.
+/// Those of the `k`-th closed-loop stage of a plan start at the plan's
+/// number of entries plus `k` times this.
+pub const CLOSED_STAGE_SEQUENCES: u32 = 1 << 24;
+
+/// How long a virtual user waits after a response before sending again. In
+/// JSON the `distribution` field selects the distribution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "distribution", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ThinkTime {
+    /// Always `seconds`.
+    Constant { seconds: f64 },
+    /// Uniformly between `min_s` and `max_s`.
+    Uniform { min_s: f64, max_s: f64 },
+    /// Exponentially distributed with mean `mean_s`, as between the
+    /// arrivals of a Poisson process.
+    Exponential { mean_s: f64 },
+    /// Log-normally distributed around `median_s`, with `sigma` the
+    /// standard deviation of its logarithm.
+    Lognormal { median_s: f64, sigma: f64 },
+}
+
+impl Default for ThinkTime {
+    fn default() -> Self {
+        ThinkTime::Constant { seconds: 0.0 }
+    }
+}
+
+impl ThinkTime {
+    /// Draw a think time in seconds; never negative.
+    pub fn sample(&self, rng: &mut DeterministicRng) -> f64 {
+        let seconds = match *self {
+            ThinkTime::Constant { seconds } => seconds,
+            ThinkTime::Uniform { min_s, max_s } => min_s + (max_s - min_s) * rng.next_f64(),
+            ThinkTime::Exponential { mean_s } if mean_s > 0.0 => rng.expovariate(1.0 / mean_s),
+            ThinkTime::Exponential { .. } => 0.0,
+            ThinkTime::Lognormal { median_s, sigma } if median_s > 0.0 => {
+                rng.gauss(median_s.ln(), sigma).exp()
+            }
+            ThinkTime::Lognormal { .. } => 0.0,
+        };
+        seconds.max(0.0)
+    }
+}
+
+/// A closed-loop stage of a plan, placed in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClosedStage {
+    /// Label of the stage's requests.
+    pub stage: String,
+    pub include_in_metrics: bool,
+    /// Offset of the start of the stage in seconds.
+    pub start_s: f64,
+    pub duration_s: f64,
+    pub users: u32,
+    /// Seconds over which the users start, evenly spread.
+    pub ramp_up_s: f64,
+    pub think_time: ThinkTime,
+    /// Seed of the users' think times.
+    pub seed: u64,
+    /// Sequence number of the first user's first request; see
+    /// [`CLOSED_STAGE_SEQUENCES`].
+    pub first_sequence: u32,
+}
+
+impl ClosedStage {
+    /// Offset at which the stage ends, in seconds.
+    pub fn end_s(&self) -> f64 {
+        self.start_s + self.duration_s
+    }
+}
+
+/// Schedules the requests of the virtual users of a [`ClosedStage`]. Users
+/// are numbered from 0; each calls [`VirtualUsers::start_s`] once, then
+/// alternates between sending the request of [`VirtualUsers::send`] and
+/// waiting until [`VirtualUsers::think`] says to send the next.
+#[wasm_bindgen]
+pub struct VirtualUsers {
+    stage: ClosedStage,
+    rngs: Vec<DeterministicRng>,
+    iterations: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl VirtualUsers {
+    /// Construct the users of a JSON [`ClosedStage`], as listed by
+    /// `PlanBuilder::closed_stages_json`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(stage_json: &str) -> Result<VirtualUsers, JsValue> {
+        let stage: ClosedStage = serde_json::from_str(stage_json)
+            .map_err(|err| JsValue::from_str(&format!("failed to parse stage: {err}")))?;
+        Ok(VirtualUsers::from_stage(stage))
+    }
+
+    /// Number of users.
+    pub fn users(&self) -> u32 {
+        self.stage.users
+    }
+
+    /// Offset at which `user` sends its first request, in seconds.
+    pub fn start_s(&self, user: u32) -> f64 {
+        let users = f64::from(self.stage.users.max(1));
+        self.stage.start_s + self.stage.ramp_up_s.max(0.0) * f64::from(user) / users
+    }
+
+    /// The request `user` sends `now_s` seconds into the run as a JSON
+    /// [`ScheduleEntry`], or `null` once the stage is over.
+    pub fn send(&mut self, user: u32, now_s: f64) -> JsValue {
+        match self.send_entry(user, now_s) {
+            Some(entry) => to_js(&entry),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// When `user`, whose response arrived `completed_s` seconds into the
+    /// run, sends its next request, or `undefined` if that would be after
+    /// the stage.
+    pub fn think(&mut self, user: u32, completed_s: f64) -> Option<f64> {
+        let rng = self.rngs.get_mut(user as usize)?;
+        let next_s = completed_s + self.stage.think_time.sample(rng);
+        (next_s < self.stage.end_s()).then_some(next_s)
+    }
+}
+
+impl VirtualUsers {
+    pub fn from_stage(stage: ClosedStage) -> VirtualUsers {
+        let mut seeds = DeterministicRng::new(stage.seed);
+        let rngs = (0..stage.users)
+            .map(|_| DeterministicRng::new((seeds.next_f64() * (1u64 << 53) as f64) as u64))
+            .collect();
+        VirtualUsers {
+            iterations: vec![0; stage.users as usize],
+            rngs,
+            stage,
+        }
+    }
+
+    pub fn stage(&self) -> &ClosedStage {
+        &self.stage
+    }
+
+    /// The entry of the request `user` sends at `now_s`, its offset being
+    /// `now_s`, or `None` once the stage is over. The `i`-th request of
+    /// user `u` of `n` is numbered `first_sequence + i * n + u`.
+    pub fn send_entry(&mut self, user: u32, now_s: f64) -> Option<ScheduleEntry> {
+        if now_s >= self.stage.end_s() {
+            return None;
+        }
+        let iteration = self.iterations.get_mut(user as usize)?;
+        let sequence = self
+            .stage
+            .first_sequence
+            .wrapping_add(iteration.wrapping_mul(self.stage.users))
+            .wrapping_add(user);
+        *iteration += 1;
+        Some(ScheduleEntry {
+            sequence,
+            offset_s: now_s,
+            stage: self.stage.stage.clone(),
+            include_in_metrics: self.stage.include_in_metrics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(think_time: ThinkTime) -> ClosedStage {
+        ClosedStage {
+            stage: "users".to_string(),
+            include_in_metrics: true,
+            start_s: 2.0,
+            duration_s: 1.0,
+            users: 4,
+            ramp_up_s: 0.4,
+            think_time,
+            seed: 5,
+            first_sequence: 100,
+        }
+    }
+
+    #[test]
+    fn users_alternate_sending_and_thinking_until_the_stage_ends() {
+        let mut users = VirtualUsers::from_stage(stage(ThinkTime::Constant { seconds: 0.3 }));
+        assert_eq!(users.start_s(0), 2.0);
+        assert!((users.start_s(3) - 2.3).abs() < 1e-12);
+
+        // user 1 whose requests take 100 ms
+        let mut now_s = users.start_s(1);
+        let mut sequences = Vec::new();
+        while let Some(entry) = users.send_entry(1, now_s) {
+            assert_eq!(entry.offset_s, now_s);
+            sequences.push(entry.sequence);
+            match users.think(1, now_s + 0.1) {
+                Some(next_s) => now_s = next_s,
+                None => break,
+            }
+        }
+        // sends at 2.1, 2.5 and 2.9; the next would be at 3.3
+        assert_eq!(sequences, [101, 105, 109]);
+        assert_eq!(users.send_entry(0, 3.0), None);
+    }
+
+    #[test]
+    fn think_times_follow_their_distribution_per_user() {
+        let think_time = ThinkTime::Exponential { mean_s: 0.5 };
+        let mut users = VirtualUsers::from_stage(stage(think_time.clone()));
+        let samples: Vec<f64> = (0..2000)
+            .filter_map(|_| users.think(2, 2.0).map(|next_s| next_s - 2.0))
+            .collect();
+        // think times of 1 s or more end the stage
+        assert!(samples.iter().all(|sample| (0.0..1.0).contains(sample)));
+        let share = samples.len() as f64 / 2000.0;
+        assert!((share - (1.0 - (-2.0_f64).exp())).abs() < 0.05, "{share}");
+
+        // a user's think times do not depend on the other users
+        let mut again = VirtualUsers::from_stage(stage(think_time));
+        again.think(0, 2.0);
+        again.think(3, 2.0);
+        let mut first = VirtualUsers::from_stage(stage(ThinkTime::Exponential { mean_s: 0.5 }));
+        assert_eq!(again.think(2, 2.0), first.think(2, 2.0));
+
+        let uniform = ThinkTime::Uniform {
+            min_s: 0.1,
+            max_s: 0.2,
+        };
+        let mut rng = DeterministicRng::new(1);
+        assert!((0..100).all(|_| (0.1..0.2).contains(&uniform.sample(&mut rng))));
+    }
+}
@@ -279,6 +279,53 @@ class TestAPIBindingErrors:
         with pytest.raises(TypeError):
             forzium_engine.echo_u64("42")
 
+    def test_errors_carry_machine_readable_code(self):
+        """Test that errors raised from Rust expose their code."""
+        with pytest.raises(ValueError) as exc_info:
+            forzium_engine.set_memory_limit(0)
+        assert exc_info.value.code == "RUST_CORE_VALIDATION"
+
+    def test_errors_are_forzium_errors(self):
+        """Test that Rust errors raise the category's ForziumError subclass."""
+        with pytest.raises(forzium_engine.ForziumError) as exc_info:
+            forzium_engine.set_memory_limit(0)
+        assert isinstance(exc_info.value, forzium_engine.ValidationError)
+        assert isinstance(exc_info.value, ValueError)
+        assert exc_info.value.category == "validation"
+        assert "positive" in str(exc_info.value)
+        assert exc_info.value.is_retryable is False
+
+    def test_resource_limit_errors_are_retryable(self):
+        """Test that resource limit errors are flagged as retryable."""
+        forzium_engine.set_memory_limit(1)
+        try:
+            with pytest.raises(forzium_engine.ResourceLimitError) as exc_info:
+                forzium_engine.matmul([[1.0] * 50] * 50, [[1.0] * 50] * 50)
+        finally:
+            forzium_engine.set_memory_limit()
+        assert exc_info.value.is_retryable is True
+
+    def test_stack_traces_captured_when_enabled(self):
+        """Test that enabling capture adds Rust and Python stacks to details."""
+        previous = forzium_engine.set_capture_stack_traces(True)
+        try:
+            with pytest.raises(forzium_engine.ForziumError) as exc_info:
+                forzium_engine.set_memory_limit(0)
+        finally:
+            forzium_engine.set_capture_stack_traces(previous)
+        assert "Rust backtrace" in exc_info.value.details
+        assert "Python stack" in exc_info.value.details
+        assert "Rust backtrace" in forzium_engine.get_last_error()
+
+    def test_add_exception_handler_rejects_non_exceptions(self):
+        """Test that only exception classes and callables are accepted."""
+        server = forzium_engine.ForziumHttpServer()
+        server.add_exception_handler(LookupError, lambda exc: (404, "", {}))
+        with pytest.raises(TypeError):
+            server.add_exception_handler(int, lambda exc: (404, "", {}))
+        with pytest.raises(TypeError):
+            server.add_exception_handler(LookupError, None)
+
 
 @pytest.mark.edge_case
 class TestBoundaryValues:
@@ -92,6 +92,99 @@ class TestFFIMemoryManagement(unittest.TestCase):
         stats = pool.get_stats()
         self.assertEqual(stats["alloc_count"], 1)
         self.assertEqual(stats["dealloc_count"], 1)
+        self.assertEqual(sum(stats["contention_histogram"]), 1)
+    
+    def test_pool_block_context_manager(self):
+        """Test that pool blocks return themselves on exit"""
+        pool = fe.PoolAllocator(4096)
+        
+        with pool.allocate_block(256) as block:
+            self.assertEqual(len(block), 256)
+            self.assertEqual(pool.available(), 4096 - 256)
+            block.write(b"abc", offset=1)
+            self.assertEqual(block.read()[:4], b"\x00abc")
+            with self.assertRaises(ValueError):
+                block.write(b"x" * 300)
+        
+        self.assertTrue(block.released)
+        self.assertEqual(pool.available(), 4096)
+        with self.assertRaises(ValueError):
+            block.read()
+        
+        # Dropping an unreleased block also returns it
+        block = pool.allocate_block(128)
+        del block
+        gc.collect()
+        self.assertEqual(pool.available(), 4096)
+    
+    def test_pool_block_buffer_is_zero_copy(self):
+        """Test that memoryview writes land in the pooled block"""
+        pool = fe.PoolAllocator(4096)
+        block = pool.allocate(16)
+        
+        view = memoryview(block)
+        view[:3] = b"xyz"
+        self.assertEqual(block.read()[:3], b"xyz")
+        
+        # The block cannot go back to the pool while a view is alive
+        with self.assertRaises(BufferError):
+            pool.deallocate(block)
+        
+        view.release()
+        pool.deallocate(block)
+        self.assertEqual(pool.available(), 4096)
+    
+    def test_pool_pressure_callback_and_trim(self):
+        """Test watermark notifications and releasing cached blocks"""
+        pool = fe.PoolAllocator(1000)
+        events = []
+        pool.on_pressure(events.append, watermark=0.5)
+        
+        block = pool.allocate(600)
+        self.assertEqual(len(events), 1)
+        self.assertEqual(events[0]["used"], 600)
+        
+        # Cached blocks are kept while usage is above the threshold
+        pool.deallocate(pool.allocate(100))
+        self.assertEqual(pool.trim(0.5), 0)
+        self.assertGreater(pool.cached_bytes(), 0)
+        
+        pool.deallocate(block)
+        self.assertGreater(pool.trim(0.5), 0)
+        self.assertEqual(pool.cached_bytes(), 0)
+        
+        with self.assertRaises(ValueError):
+            pool.on_pressure(events.append, watermark=1.5)
+        pool.on_pressure(None)
+        pool.deallocate(pool.allocate(900))
+        self.assertEqual(len(events), 1)
+    
+    def test_memory_accounting_tracks_pool_blocks(self):
+        """Test that pool blocks register with the process-wide accountant"""
+        pool = fe.PoolAllocator(1 << 20)
+        before = fe.memory_accounting()["by_category"]["pool"]
+        
+        block = pool.allocate(4096)
+        self.assertEqual(fe.memory_accounting()["by_category"]["pool"], before + 4096)
+        block.release()
+        self.assertEqual(fe.memory_accounting()["by_category"]["pool"], before)
+        
+        fe.set_memory_limit(fe.memory_accounting()["total_bytes"] + 1024)
+        try:
+            with self.assertRaises(ResourceWarning):
+                pool.allocate(4096)
+        finally:
+            fe.set_memory_limit(None)
+    
+    def test_allocator_memory_stats(self):
+        """Test allocator statistics for capacity monitoring"""
+        stats = fe.memory_stats()
+        self.assertIn(stats["backend"], {"system", "mimalloc", "jemalloc"})
+        for key in ("allocated_bytes", "active_bytes", "resident_bytes", "fragmentation"):
+            self.assertIn(key, stats)
+        if stats["fragmentation"] is not None:
+            self.assertGreaterEqual(stats["fragmentation"], 0.0)
+            self.assertLessEqual(stats["fragmentation"], 1.0)
     
     def test_gc_interaction(self):
         """Test interaction with Python's garbage collector"""
@@ -5,8 +5,11 @@ Tests all public Rust functions exposed through PyO3 bindings,
 including tensor operations, data transforms, and utility functions.
 """
 
+import asyncio
 import math
+import os
 import sys
+import threading
 from typing import List
 
 import pytest
@@ -265,6 +268,23 @@ class TestConvolutionOperations:
         assert len(result) == 3
         assert len(result[0]) == 3
 
+    def test_conv2d_transpose_stride(self):
+        """Test transposed convolution upsamples with stride and padding."""
+        input_matrix = [[1.0, 2.0], [3.0, 4.0]]
+        kernel = [[1.0, 1.0], [1.0, 1.0]]
+        result = forzium_engine.conv2d_transpose(input_matrix, kernel, stride=2)
+        expected = [
+            [1.0, 1.0, 2.0, 2.0],
+            [1.0, 1.0, 2.0, 2.0],
+            [3.0, 3.0, 4.0, 4.0],
+            [3.0, 3.0, 4.0, 4.0],
+        ]
+        pytest.assert_matrices_equal(result, expected)
+        cropped = forzium_engine.conv2d_transpose(
+            input_matrix, kernel, stride=2, padding=1
+        )
+        pytest.assert_matrices_equal(cropped, input_matrix)
+
     def test_max_pool2d_basic(self):
         """Test basic 2D max pooling."""
         input_matrix = [
@@ -390,6 +410,984 @@ class TestUtilityFunctions:
         # Should complete without error
         assert result is None or isinstance(result, int)
 
+    def test_gc_stats_count_force_gc(self):
+        """Test that force_gc invocations and durations are recorded."""
+        before = forzium_engine.gc_stats()
+        forzium_engine.force_gc()
+        after = forzium_engine.gc_stats()
+        assert after["invocations"] == before["invocations"] + 1
+        assert after["total_seconds"] >= before["total_seconds"]
+        assert after["max_seconds"] >= after["last_seconds"]
+
+    def test_error_stats_count_by_category(self):
+        """Test that errors raised from Rust are counted by category."""
+        before = forzium_engine.error_stats()
+        with pytest.raises(ValueError):
+            forzium_engine.set_memory_limit(0)
+        after = forzium_engine.error_stats()
+        assert (
+            after["by_category"]["validation"]
+            == before["by_category"]["validation"] + 1
+        )
+        assert set(after["by_status_class"]) == {"4xx", "5xx"}
+
+    def test_route_stats_structure(self):
+        """Test that route_stats returns latency summaries per route."""
+        server = forzium_engine.ForziumHttpServer()
+        stats = server.route_stats()
+        assert stats == forzium_engine.route_stats()
+        for entry in stats:
+            assert {"method", "route", "status", "count"} <= set(entry)
+            assert entry["p50_ms"] <= entry["p95_ms"] <= entry["p99_ms"]
+            assert entry["p99_ms"] <= entry["max_ms"]
+
+    def test_export_metrics_formats(self):
+        """Test that engine metrics render in every exporter format."""
+        import json
+
+        forzium_engine.force_gc()
+        text = forzium_engine.export_metrics()
+        assert "# TYPE forzium_gc_invocations_total counter" in text
+        statsd = forzium_engine.export_metrics("statsd")
+        assert "forzium.gc_invocations_total:" in statsd
+        samples = json.loads(forzium_engine.export_metrics("json"))
+        assert any(s["name"] == "forzium_errors_total" for s in samples)
+        assert "memory" in forzium_engine.metrics_sources()
+        with pytest.raises(ValueError):
+            forzium_engine.export_metrics("xml")
+
+    def test_merge_metrics_across_processes(self):
+        """Test that the metrics of several processes combine into one set."""
+        export = forzium_engine.export_metrics("json")
+        merged = forzium_engine.merge_metrics({"0": export, "1": export})
+        single = forzium_engine.export_metrics()
+        assert merged.count("# TYPE") == single.count("# TYPE")
+        with pytest.raises(ValueError):
+            forzium_engine.merge_metrics({"0": "not json"})
+
+    def test_serve_on_inherited_socket(self):
+        """Test that a server accepts connections on a socket bound elsewhere."""
+        import socket
+        import urllib.error
+        import urllib.request
+
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        server = forzium_engine.ForziumHttpServer()
+        server.serve(fd=listener.fileno())
+        listener.close()
+        try:
+            with pytest.raises(urllib.error.HTTPError) as info:
+                urllib.request.urlopen(f"http://127.0.0.1:{port}/missing", timeout=5)
+            assert info.value.code == 404
+        finally:
+            server.shutdown()
+        with pytest.raises(TypeError):
+            forzium_engine.ForziumHttpServer().serve("127.0.0.1:0", fd=listener.fileno())
+
+    def test_http_client_sends_requests(self):
+        """Test that the HTTP client retries, propagates traces and can be awaited."""
+        import json
+        from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+        received = []
+
+        class Handler(BaseHTTPRequestHandler):
+            def log_message(self, *args):
+                pass
+
+            def do_GET(self):
+                received.append(self.headers)
+                status = 503 if len(received) == 1 else 200
+                body = json.dumps({"path": self.path}).encode()
+                self.send_response(status)
+                self.send_header("Content-Length", str(len(body)))
+                self.end_headers()
+                self.wfile.write(body)
+
+        upstream = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+        threading.Thread(target=upstream.serve_forever, daemon=True).start()
+        client = forzium_engine.ForziumHttpClient(
+            f"http://127.0.0.1:{upstream.server_port}/api", backoff=0.01
+        )
+        parent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        try:
+            response = client.get("/items", headers={"traceparent": parent}).wait()
+            assert response.status == 200
+            assert response.attempts == 2
+            assert response.json() == {"path": "/api/items"}
+            traceparent = received[-1]["traceparent"]
+            assert traceparent.startswith("00-4bf92f3577b34da6a3ce929d0e0e4736-")
+            assert traceparent != parent
+
+            async def fetch_all():
+                return await asyncio.gather(*(client.get(f"/{i}") for i in range(3)))
+
+            responses = asyncio.run(fetch_all())
+            assert [r.json()["path"] for r in responses] == ["/api/0", "/api/1", "/api/2"]
+        finally:
+            upstream.shutdown()
+        with pytest.raises(ValueError):
+            client.get("https://example.com/")
+        with pytest.raises(ConnectionError):
+            forzium_engine.ForziumHttpClient(retries=0).get("http://127.0.0.1:1/").wait()
+        assert forzium_engine.http_client_stats()["retries"] >= 1
+
+    def test_proxy_routes_forward_to_upstream(self):
+        """Test that proxy routes forward requests and rewrite headers."""
+        import json
+        import socket
+        import urllib.error
+        import urllib.request
+        from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+        class Handler(BaseHTTPRequestHandler):
+            def log_message(self, *args):
+                pass
+
+            def do_POST(self):
+                body = self.rfile.read(int(self.headers["Content-Length"]))
+                reply = json.dumps(
+                    {
+                        "path": self.path,
+                        "body": body.decode(),
+                        "forwarded_for": self.headers["X-Forwarded-For"],
+                        "service": self.headers["X-Service"],
+                        "cookie": self.headers["Cookie"],
+                    }
+                ).encode()
+                self.send_response(201)
+                self.send_header("Content-Length", str(len(reply)))
+                self.send_header("Server-Timing", "db;dur=3")
+                self.end_headers()
+                self.wfile.write(reply)
+
+        upstream = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+        threading.Thread(target=upstream.serve_forever, daemon=True).start()
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        server = forzium_engine.ForziumHttpServer()
+        server.add_proxy_route(
+            "/legacy/{rest:path}",
+            f"http://127.0.0.1:{upstream.server_port}/v1",
+            methods=["POST"],
+            request_headers={"X-Service": "forzium", "Cookie": None},
+            response_headers={"Server-Timing": None},
+        )
+        server.serve(fd=listener.fileno())
+        listener.close()
+        try:
+            request = urllib.request.Request(
+                f"http://127.0.0.1:{port}/legacy/users/7?page=2",
+                data=b"hello",
+                headers={"Cookie": "session=1"},
+            )
+            with urllib.request.urlopen(request, timeout=5) as response:
+                assert response.status == 201
+                assert response.headers["Server-Timing"] is None
+                assert json.loads(response.read()) == {
+                    "path": "/v1/users/7?page=2",
+                    "body": "hello",
+                    "forwarded_for": "127.0.0.1",
+                    "service": "forzium",
+                    "cookie": None,
+                }
+            with pytest.raises(urllib.error.HTTPError) as info:
+                urllib.request.urlopen(f"http://127.0.0.1:{port}/legacy/users", timeout=5)
+            assert info.value.code == 404
+        finally:
+            server.shutdown()
+            upstream.shutdown()
+            upstream.server_close()
+        with pytest.raises(ValueError):
+            server.add_proxy_route("/old", "https://example.com")
+
+    def test_cache_evicts_and_serves_responses(self):
+        """Test the LRU cache and the server's response cache built on it."""
+        import json
+        import socket
+        import urllib.request
+
+        cache = forzium_engine.ForziumCache(300, shards=1, name="test")
+        assert cache.set("a", b"x" * 100)
+        assert cache.set("b", "y" * 100)
+        assert cache.get("a") == b"x" * 100
+        assert cache.set("c", {"n": 1}, size=99)
+        assert "b" not in cache and cache.get("c") == {"n": 1}
+        assert not cache.set("big", b"z", size=1000)
+        assert cache.get("missing", 0) == 0
+        stats = cache.stats()
+        assert (stats["hits"], stats["misses"], stats["evictions"]) == (2, 1, 1)
+        assert len(cache) == 2 and cache.delete("a") and not cache.delete("a")
+        assert "forzium_cache_requests_total" in forzium_engine.export_metrics()
+        with pytest.raises(ValueError):
+            forzium_engine.ForziumCache(ttl=0)
+
+        calls = []
+
+        def handler(body, params, query, headers):
+            calls.append(query)
+            return 200, json.dumps({"calls": len(calls)}), {"content-type": "application/json"}
+
+        responses = forzium_engine.ForziumCache()
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        server = forzium_engine.ForziumHttpServer()
+        server.add_route("GET", "/count", handler)
+        server.set_response_cache(responses, ttl=60)
+        server.serve(fd=listener.fileno())
+        listener.close()
+
+        def get(path, **headers):
+            request = urllib.request.Request(f"http://127.0.0.1:{port}{path}", headers=headers)
+            with urllib.request.urlopen(request, timeout=5) as response:
+                return json.loads(response.read())["calls"]
+
+        try:
+            assert get("/count") == 1
+            assert get("/count") == 1
+            assert get("/count?page=2") == 2
+            assert get("/count", Authorization="Bearer t") == 3
+            status, body, _ = responses.get("GET /count")
+            assert status == 200 and json.loads(body) == {"calls": 1}
+            responses.delete("GET /count")
+            assert get("/count") == 4
+        finally:
+            server.shutdown()
+
+    def test_redis_client_commands_and_pubsub(self):
+        """Test the RESP3 client against a fake server, and middleware using it."""
+        import json
+        import socketserver
+        from types import SimpleNamespace
+
+        from forzium.middleware import RateLimitMiddleware, RedisSessionMiddleware
+
+        store, expiry, subscribers = {}, {}, []
+
+        def bulk(data):
+            return b"$%d\r\n%s\r\n" % (len(data), data)
+
+        def push(*items):
+            return b">%d\r\n" % len(items) + b"".join(bulk(item) for item in items)
+
+        class Handler(socketserver.StreamRequestHandler):
+            def read_command(self):
+                header = self.rfile.readline()
+                if not header:
+                    return None
+                args = []
+                for _ in range(int(header[1:])):
+                    size = int(self.rfile.readline()[1:])
+                    args.append(self.rfile.read(size + 2)[:-2])
+                return args
+
+            def reply(self, name, args):
+                if name == b"HELLO":
+                    if args[1:] != [b"AUTH", b"default", b"pw"]:
+                        return b"-WRONGPASS invalid password\r\n"
+                    return b"%1\r\n+proto\r\n:3\r\n"
+                if name in (b"SELECT", b"PING"):
+                    return b"+OK\r\n" if name == b"SELECT" else b"+PONG\r\n"
+                if name == b"GET":
+                    return bulk(store[args[0]]) if args[0] in store else b"_\r\n"
+                if name == b"SET":
+                    if b"NX" in args and args[0] in store:
+                        return b"_\r\n"
+                    store[args[0]] = args[1]
+                    if b"PX" in args:
+                        expiry[args[0]] = int(args[args.index(b"PX") + 1])
+                    return b"+OK\r\n"
+                if name == b"PTTL":
+                    return b":%d\r\n" % expiry.get(args[0], -2 if args[0] not in store else -1)
+                if name == b"INCRBY":
+                    store[args[0]] = b"%d" % (int(store.get(args[0], b"0")) + int(args[1]))
+                    return b":%s\r\n" % store[args[0]]
+                if name == b"PEXPIRE":
+                    expiry[args[0]] = int(args[1])
+                    return b":%d\r\n" % (args[0] in store)
+                if name == b"DEL":
+                    return b":%d\r\n" % sum(store.pop(key, None) is not None for key in args)
+                if name == b"PUBLISH":
+                    receivers = [w for channel, w in subscribers if channel == args[0]]
+                    for wfile in receivers:
+                        wfile.write(push(b"message", args[0], args[1]))
+                    return b":%d\r\n" % len(receivers)
+                if name == b"SUBSCRIBE":
+                    subscribers.extend((channel, self.wfile) for channel in args)
+                    return b"".join(
+                        b">3\r\n" + bulk(b"subscribe") + bulk(channel) + b":1\r\n"
+                        for channel in args
+                    )
+                if name == b"UNSUBSCRIBE":
+                    subscribers[:] = [s for s in subscribers if s[1] is not self.wfile]
+                    return b""
+                return b"-ERR unknown command '%s'\r\n" % name
+
+            def handle(self):
+                while (args := self.read_command()) is not None:
+                    self.wfile.write(self.reply(args[0].upper(), args[1:]))
+
+        class Server(socketserver.ThreadingTCPServer):
+            daemon_threads = True
+            allow_reuse_address = True
+
+        server = Server(("127.0.0.1", 0), Handler)
+        port = server.server_address[1]
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+        try:
+            client = forzium_engine.ForziumRedis(f"redis://:pw@127.0.0.1:{port}/1", timeout=2)
+            assert client.ping().wait() is True
+            assert client.set("k", "v", ex=10).wait() is True
+            assert client.set("k", "w", nx=True).wait() is False
+            assert client.get("k").wait() == b"v"
+            assert client.ttl("k").wait() == 10.0
+            assert client.ttl("missing").wait() is None
+            assert client.incr("n").wait() == 1
+            assert client.incr("n", 5).wait() == 6
+            assert client.expire("n", 1.5).wait() is True
+            assert client.delete("k", "n", "missing").wait() == 2
+            with pytest.raises(forzium_engine.RedisReplyError, match="unknown command"):
+                client.execute("BOGUS", 1, 2.5).wait()
+
+            async def pipelined():
+                replies = [client.incr("counter") for _ in range(20)]
+                return sorted([await reply for reply in replies])
+
+            assert asyncio.run(pipelined()) == list(range(1, 21))
+
+            subscription = client.subscribe("news").wait()
+            assert subscription.channels == ["news"]
+            assert subscription.get_message(timeout=0.05) is None
+            assert client.publish("news", b"hello").wait() == 1
+            assert subscription.get_message(timeout=2) == ("news", b"hello")
+
+            async def receive():
+                await client.publish("news", "again")
+                async for message in subscription:
+                    return message
+
+            assert asyncio.run(receive()) == ("news", b"again")
+            subscription.close()
+            with pytest.raises(ConnectionError):
+                subscription.get_message(timeout=2)
+
+            limiters = [RateLimitMiddleware(2, 60, redis=client) for _ in range(2)]
+            request = SimpleNamespace(headers={"x-real-ip": "10.0.0.1"}, url="/")
+
+            async def call(limiter):
+                response = await limiter(request, lambda _: SimpleNamespace(headers={}))
+                return getattr(response, "status_code", 200)
+
+            statuses = [asyncio.run(call(limiters[i % 2])) for i in range(3)]
+            assert statuses == [200, 200, 429]
+
+            first = RedisSessionMiddleware(client, ttl=30)
+            _, params, _, _ = first.before_request(b"", (), b"")
+            params[-1]["user"] = "ada"
+            _, _, headers = first.after_response(200, "", {})
+            sid = headers["set-cookie"].split("=", 1)[1]
+            second = RedisSessionMiddleware(client)
+            _, params, _, _ = second.before_request(b"", (), f"session_id={sid}".encode())
+            assert params[-1] == {"user": "ada"}
+            assert client.ttl(f"forzium:session:{sid}").wait() == 30.0
+
+            both_started = threading.Barrier(2)
+            cookies = {}
+
+            def handle(user):
+                _, params, _, _ = second.before_request(b"", (), f"session_id={user}".encode())
+                params[-1]["user"] = user
+                both_started.wait(timeout=5)
+                cookies[user] = second.after_response(200, "", {})[2]["set-cookie"]
+
+            threads = [threading.Thread(target=handle, args=(user,)) for user in ("ada", "bob")]
+            for thread in threads:
+                thread.start()
+            for thread in threads:
+                thread.join()
+            assert cookies == {"ada": "session_id=ada", "bob": "session_id=bob"}
+            for user in ("ada", "bob"):
+                assert json.loads(client.get(f"forzium:session:{user}").wait()) == {"user": user}
+        finally:
+            server.shutdown()
+            server.server_close()
+
+        unreachable = forzium_engine.ForziumRedis("redis://127.0.0.1:1", connect_timeout=1)
+        with pytest.raises(ConnectionError):
+            unreachable.ping().wait()
+        limiter = RateLimitMiddleware(1, 60, redis=unreachable)
+        assert asyncio.run(call(limiter)) == 200
+        stats = forzium_engine.redis_stats()
+        assert stats["replies"] >= 30 and stats["errors"] >= 1 and stats["failures"] >= 1
+        assert "forzium_redis_commands_total" in forzium_engine.export_metrics()
+        with pytest.raises(ValueError):
+            forzium_engine.ForziumRedis("http://127.0.0.1:6379")
+
+    def test_scheduled_jobs_run_and_report_history(self):
+        """Test interval and cron jobs, overlap protection and the app decorator."""
+        import time
+
+        from forzium import ForziumApp
+
+        runs = []
+
+        def tick():
+            runs.append(time.monotonic())
+            if len(runs) == 1:
+                raise RuntimeError("first run fails")
+
+        async def slow():
+            await asyncio.sleep(0.1)
+
+        forzium_engine.schedule_job("tick", tick, every=0.02)
+        forzium_engine.schedule_job("slow", slow, every=0.02)
+        time.sleep(0.3)
+        jobs = forzium_engine.scheduled_jobs()
+        assert "forzium_scheduler_runs_total" in forzium_engine.export_metrics()
+        assert forzium_engine.unschedule_job("tick") and forzium_engine.unschedule_job("slow")
+        assert not forzium_engine.unschedule_job("tick")
+        assert jobs["tick"]["schedule"] == "every 0.02s"
+        assert len(runs) >= 3 and jobs["tick"]["failures"] == 1
+        assert jobs["tick"]["successes"] >= 2
+        assert "first run fails" in jobs["tick"]["history"][0]["error"]
+        assert jobs["slow"]["skipped"] > 0 and jobs["slow"]["running"] <= 1
+
+        forzium_engine.schedule_job("nightly-gc", "gc", cron="@daily", jitter=60)
+        time.sleep(0.05)
+        job = forzium_engine.scheduled_jobs()["nightly-gc"]
+        assert job["schedule"] == "cron @daily"
+        assert 0 < job["next_run"] - time.time() <= 86400
+        forzium_engine.unschedule_job("nightly-gc")
+        with pytest.raises(ValueError):
+            forzium_engine.schedule_job("bad", tick, every=1, cron="* * * * *")
+        with pytest.raises(ValueError):
+            forzium_engine.schedule_job("bad", tick, cron="61 * * * *")
+        with pytest.raises(ValueError):
+            forzium_engine.schedule_job("bad", "reboot", every=1)
+
+        app = ForziumApp()
+
+        @app.scheduled(every=60, name="refresh")
+        def refresh():
+            pass
+
+        assert "refresh" in forzium_engine.scheduled_jobs()
+        asyncio.run(app.shutdown())
+        assert "refresh" not in forzium_engine.scheduled_jobs()
+
+    def test_broadcast_hub_fans_out_and_streams_events(self):
+        """Test broadcast subscriptions, their drop policies and SSE routes."""
+        import socket
+        import time
+
+        from forzium.websockets import WebSocket, relay
+
+        first = forzium_engine.subscribe("test.news", capacity=1, policy="drop_newest")
+        second = forzium_engine.subscribe("test.news", "test.other")
+        assert forzium_engine.publish("test.news", b"a") == 2
+        assert forzium_engine.publish("test.news", "b") == 1
+        assert forzium_engine.publish("test.nobody", b"c") == 0
+        assert first.get_message(timeout=1) == ("test.news", b"a")
+        assert first.get_message(timeout=0) is None
+        assert first.dropped == 1
+        assert second.get_message(timeout=1) == ("test.news", b"a")
+        assert second.get_message(timeout=1) == ("test.news", b"b")
+        first.close()
+        with pytest.raises(ConnectionError):
+            first.get_message(timeout=1)
+        with pytest.raises(ValueError):
+            forzium_engine.subscribe("test.news", policy="drop_all")
+
+        async def read_two():
+            messages = []
+            async for message in second:
+                messages.append(message)
+                if len(messages) == 2:
+                    return messages
+
+        async def publish_later():
+            task = asyncio.ensure_future(read_two())
+            await asyncio.sleep(0.05)
+            forzium_engine.publish("test.other", b"x")
+            forzium_engine.publish("test.news", b"y")
+            return await asyncio.wait_for(task, 5)
+
+        assert asyncio.run(publish_later()) == [("test.other", b"x"), ("test.news", b"y")]
+
+        ws = WebSocket()
+
+        async def relay_one():
+            task = asyncio.ensure_future(relay(ws, "test.ws"))
+            while forzium_engine.publish("test.ws", b"hi") == 0:
+                await asyncio.sleep(0.01)
+            while not ws.sent:
+                await asyncio.sleep(0.01)
+            await ws.close()
+            await asyncio.wait_for(task, 5)
+
+        asyncio.run(relay_one())
+        assert ws.sent[0] == "hi"
+
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        server = forzium_engine.ForziumHttpServer()
+        server.add_event_stream_route("/rooms/{room}/events", "test.room.{room}", heartbeat=None)
+        server.serve(fd=listener.fileno())
+        listener.close()
+        try:
+            client = socket.create_connection(("127.0.0.1", port), timeout=5)
+            client.sendall(b"GET /rooms/7/events HTTP/1.1\r\nHost: test\r\n\r\n")
+            deadline = time.monotonic() + 5
+            while forzium_engine.publish("test.room.7", b"line 1\nline 2") == 0:
+                assert time.monotonic() < deadline
+                time.sleep(0.01)
+            received = b""
+            while b"data: line 2\n\n" not in received:
+                received += client.recv(4096)
+            assert received.startswith(b"HTTP/1.1 200")
+            assert b"content-type: text/event-stream" in received.lower()
+            assert b"data: line 1\ndata: line 2\n\n" in received
+        finally:
+            server.shutdown()
+        # The stream ends with the server
+        while client.recv(4096):
+            pass
+        client.close()
+        assert forzium_engine.broadcast_stats()["dropped"] >= 1
+
+    def test_request_context_reaches_handlers_and_responses(self):
+        """Test request contexts, from the server to handlers and middleware."""
+        import json
+        import socket
+        import urllib.request
+
+        from forzium import ForziumApp
+        from forzium.dependency import Request
+        from forzium.testclient import TestClient
+
+        context = forzium_engine.RequestContext("req-1", timeout=5)
+        assert context.request_id == "req-1"
+        assert 0 < context.remaining <= 5 and not context.expired
+        context.set_deadline(60)
+        assert context.remaining <= 5
+        context["tenant"] = {"id": 7}
+        assert context["tenant"] == {"id": 7} and "tenant" in context
+        assert context.get("missing", 0) == 0 and context.keys() == ["tenant"]
+        del context["tenant"]
+        with pytest.raises(KeyError):
+            context["tenant"]
+        context.claims = {"sub": "ada", "scopes": ["read"]}
+        assert context.subject == "ada" and context.claims["scopes"] == ["read"]
+        assert len(forzium_engine.RequestContext("has space").request_id) == 32
+        assert forzium_engine.request_context.get() is None
+
+        def handler(body, params, query, headers):
+            current = forzium_engine.request_context.get()
+            current["seen"] = True
+            reply = {"id": current.request_id, "header": headers["x-request-id"]}
+            return 200, json.dumps(reply), {"content-type": "application/json"}
+
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        server = forzium_engine.ForziumHttpServer()
+        server.add_route("GET", "/whoami", handler)
+        server.serve(fd=listener.fileno())
+        listener.close()
+
+        def get(**headers):
+            request = urllib.request.Request(f"http://127.0.0.1:{port}/whoami", headers=headers)
+            with urllib.request.urlopen(request, timeout=5) as response:
+                return json.loads(response.read()), response.headers["X-Request-ID"]
+
+        try:
+            assert get(**{"X-Request-ID": "abc-123"}) == ({"id": "abc-123", "header": "abc-123"}, "abc-123")
+            reply, returned = get()
+            assert len(returned) == 32 and reply == {"id": returned, "header": returned}
+        finally:
+            server.shutdown()
+
+        app = ForziumApp()
+
+        @app.middleware("http")
+        def tenant(request, call_next):
+            request.context["tenant"] = request.headers.get("x-tenant")
+            return call_next(request)
+
+        @app.get("/tenant")
+        async def read_tenant(request: Request):
+            return {
+                "tenant": request.context["tenant"],
+                "request_id": request.state.request_id,
+            }
+
+        response = TestClient(app).get("/tenant", headers={"X-Tenant": "acme", "X-Request-ID": "r-9"})
+        assert response.json() == {"tenant": "acme", "request_id": "r-9"}
+        assert forzium_engine.request_context.get() is None
+
+    def test_mounted_asgi_apps(self):
+        """Test ASGI apps mounted on the server, with their lifespan."""
+        import json
+        import socket
+        import urllib.error
+        import urllib.request
+
+        events = []
+
+        async def legacy(scope, receive, send):
+            if scope["type"] == "lifespan":
+                while True:
+                    message = await receive()
+                    events.append(message["type"])
+                    if message["type"] == "lifespan.startup":
+                        scope["state"]["db"] = "connected"
+                        await send({"type": "lifespan.startup.complete"})
+                    else:
+                        await send({"type": "lifespan.shutdown.complete"})
+                        return
+            if scope["path"] == "/fail":
+                raise ValueError("broken")
+            body = b""
+            while True:
+                message = await receive()
+                body += message["body"]
+                if not message["more_body"]:
+                    break
+            reply = {
+                "path": scope["path"],
+                "root_path": scope["root_path"],
+                "query": scope["query_string"].decode(),
+                "db": scope["state"].get("db"),
+                "body": body.decode(),
+                "request_id": forzium_engine.request_context.get().request_id,
+            }
+            await send({
+                "type": "http.response.start",
+                "status": 201,
+                "headers": [(b"content-type", b"application/json")],
+            })
+            await send({"type": "http.response.body", "body": json.dumps(reply)[:5].encode(), "more_body": True})
+            await send({"type": "http.response.body", "body": json.dumps(reply)[5:].encode()})
+
+        async def admin(scope, receive, send):
+            await send({"type": "http.response.start", "status": 200, "headers": []})
+            await send({"type": "http.response.body", "body": scope["root_path"].encode()})
+
+        def native(body, params, query, headers):
+            return 200, "native", {}
+
+        with pytest.raises(ValueError):
+            forzium_engine.ForziumHttpServer().mount_asgi("/items/{id}", legacy)
+
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        server = forzium_engine.ForziumHttpServer()
+        server.add_route("GET", "/native", native)
+        server.mount_asgi("/", legacy)
+        server.mount_asgi("/admin/", admin, lifespan=False)
+        server.serve(fd=listener.fileno())
+        listener.close()
+        assert events == ["lifespan.startup"]
+
+        def request(path, data=None):
+            url = f"http://127.0.0.1:{port}{path}"
+            req = urllib.request.Request(url, data=data, headers={"X-Request-ID": "asgi-1"})
+            try:
+                with urllib.request.urlopen(req, timeout=5) as response:
+                    return response.status, response.read().decode()
+            except urllib.error.HTTPError as e:
+                return e.code, e.read().decode()
+
+        try:
+            status, body = request("/orders/a%20b?page=2", data=b"payload")
+            assert status == 201
+            assert json.loads(body) == {
+                "path": "/orders/a b",
+                "root_path": "",
+                "query": "page=2",
+                "db": "connected",
+                "body": "payload",
+                "request_id": "asgi-1",
+            }
+            assert request("/native") == (200, "native")
+            assert request("/admin/users") == (200, "/admin")
+            assert request("/administrators")[0] == 201
+            assert request("/fail")[0] == 500
+        finally:
+            server.shutdown()
+        assert events == ["lifespan.startup", "lifespan.shutdown"]
+
+    def test_listening_socket_handover(self, tmp_path):
+        """Test that a replacement process takes over the listening socket."""
+        import socket
+        import urllib.error
+        import urllib.request
+
+        from forzium.workers import Handover
+
+        path = tmp_path / "handover.sock"
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        old = Handover(path)
+        assert old.take() is None
+        old.ready()
+
+        new = Handover(path)
+        taken = {}
+        thread = threading.Thread(target=lambda: taken.update(sock=new.take()))
+        thread.start()
+        while thread.is_alive():
+            assert not old.poll(listener)
+            thread.join(0.01)
+        listener.close()
+        server = forzium_engine.ForziumHttpServer()
+        server.serve(fd=taken["sock"].fileno())
+        try:
+            new.ready()
+            while not old.poll(taken["sock"]):
+                pass
+            assert old.handed_over
+            old.close()
+            assert path.exists()
+            with pytest.raises(urllib.error.HTTPError) as info:
+                urllib.request.urlopen(f"http://127.0.0.1:{port}/missing", timeout=5)
+            assert info.value.code == 404
+        finally:
+            server.shutdown()
+            taken["sock"].close()
+            new.close()
+        assert not path.exists()
+
+    def test_workers_are_not_forked_after_compute_calls(self, tmp_path):
+        """Test that workers are not forked once the engine started threads."""
+        import subprocess
+        import textwrap
+
+        # a fresh interpreter, whose engine has not started any threads yet
+        code = textwrap.dedent(
+            """
+            import forzium_engine
+            from forzium.workers import WorkerManager
+
+            manager = WorkerManager(object(), "127.0.0.1", 0, workers=2, server=object())
+            assert not forzium_engine.native_threads_started()
+            forzium_engine.matmul([[1.0]], [[2.0]])
+            assert forzium_engine.native_threads_started()
+            try:
+                manager._spawn(0)
+            except RuntimeError as e:
+                print(e)
+            else:
+                raise AssertionError("worker forked")
+            assert not manager.workers
+            """
+        )
+        result = subprocess.run(
+            [sys.executable, "-c", code],
+            cwd=tmp_path,
+            capture_output=True,
+            text=True,
+            timeout=60,
+        )
+        assert result.returncode == 0, result.stderr
+        assert "before workers were forked" in result.stdout
+
+    def test_log_levels_per_module(self):
+        """Test that log levels can be set per module and reset."""
+        forzium_engine.set_log_level("debug", "server::http_engine")
+        try:
+            levels = forzium_engine.get_log_levels()
+            assert levels["forzium_engine::server::http_engine"] == "debug"
+            with pytest.raises(ValueError):
+                forzium_engine.set_log_level("loud")
+        finally:
+            forzium_engine.set_log_level(None, "server::http_engine")
+        levels = forzium_engine.get_log_levels()
+        assert levels["default"] == "warn"
+        assert "forzium_engine::server::http_engine" not in levels
+
+    def test_config_loads_file_and_environment(self, tmp_path, monkeypatch):
+        """Test that configuration files are validated and applied in one call."""
+        path = tmp_path / "forzium.toml"
+        path.write_text(
+            "[server]\nconnection_limit = 7\nhandler_threads = 3\n"
+            '[logging]\nmodules = { "server::http_engine" = "debug" }\n'
+        )
+        monkeypatch.setenv("FORZIUM_SERVER__CONNECTION_LIMIT", "9")
+        config = forzium_engine.ForziumConfig.load(str(path))
+        assert config.to_dict()["server"] == {"connection_limit": 9, "handler_threads": 3}
+        server = forzium_engine.ForziumHttpServer()
+        try:
+            config.apply(server)
+            assert server.get_connection_limit() == 9
+            assert server.get_handler_threads() == 3
+            levels = forzium_engine.get_log_levels()
+            assert levels["forzium_engine::server::http_engine"] == "debug"
+        finally:
+            forzium_engine.set_log_level(None, "server::http_engine")
+
+        with pytest.raises(ValueError):
+            forzium_engine.ForziumConfig({"server": {"conection_limit": 1}})
+        with pytest.raises(ValueError):
+            forzium_engine.ForziumConfig({"server": {"handler_threads": 0}})
+        with pytest.raises(FileNotFoundError):
+            forzium_engine.ForziumConfig.load(str(tmp_path / "missing.toml"))
+
+    def test_apply_config_changes_running_settings(self):
+        """Test that reloadable settings change at runtime and are audited."""
+        server = forzium_engine.ForziumHttpServer()
+        changes = forzium_engine.apply_config(
+            {"server": {"request_timeout_secs": 3}}, server, source="test"
+        )
+        assert [(c["key"], c["old"], c["new"]) for c in changes] == [
+            ("server.request_timeout_secs", 30, 3)
+        ]
+        assert forzium_engine.config_audit_log()[-1]["source"] == "test"
+        assert forzium_engine.apply_config({"server": {"request_timeout_secs": 3}}, server) == []
+
+        with pytest.raises(ValueError, match="restart"):
+            forzium_engine.apply_config({"server": {"handler_threads": 2}}, server)
+        with pytest.raises(ValueError):
+            forzium_engine.apply_config({"server": {"request_timeout_secs": 5}})
+
+    def test_stats_endpoint_requires_token(self):
+        """Test that the stats endpoint can only be enabled with a token."""
+        server = forzium_engine.ForziumHttpServer()
+        with pytest.raises(ValueError):
+            server.enable_stats_endpoint("  ")
+        server.enable_stats_endpoint("s3cret")
+        server.disable_stats_endpoint()
+
+    def test_slow_request_threshold(self):
+        """Test that the slow request threshold can be set and cleared."""
+        server = forzium_engine.ForziumHttpServer()
+        assert server.get_slow_request_threshold() is None
+        server.set_slow_request_threshold(250)
+        assert server.get_slow_request_threshold() == 250
+        server.set_slow_request_threshold(None)
+        assert server.get_slow_request_threshold() is None
+        assert isinstance(forzium_engine.slow_request_stats(), list)
+
+    def test_gil_stats_count_compute_calls(self):
+        """Test that compute calls are counted in the GIL hold-time stats."""
+        before = forzium_engine.gil_stats()["compute"]
+        forzium_engine.matmul([[1.0, 2.0]], [[3.0], [4.0]])
+        forzium_engine.simd_matmul([[1.0, 2.0]], [[3.0], [4.0]])
+        after = forzium_engine.gil_stats()["compute"]
+        assert after["calls"] == before["calls"] + 2
+        assert after["held_ms"] >= before["held_ms"]
+        assert 0.0 <= after["held_ratio"] <= 1.0
+        assert set(forzium_engine.gil_stats()) == {"request", "compute"}
+        assert "gil" in forzium_engine.metrics_sources()
+
+    def test_compute_calls_release_the_gil(self):
+        """Test that time spent computing is not counted as holding the GIL."""
+        matrix = [[float(i + j) for j in range(120)] for i in range(120)]
+        calls = [
+            lambda: forzium_engine.multiply(matrix, 2.0),
+            lambda: forzium_engine.add(matrix, 1.0),
+            lambda: forzium_engine.matmul(matrix, matrix),
+            lambda: forzium_engine.transpose(matrix),
+            lambda: forzium_engine.optimal_matmul(matrix, matrix),
+            lambda: forzium_engine.optimal_add(matrix, matrix),
+        ]
+        before = forzium_engine.gil_stats()["compute"]
+        for call in calls:
+            call()
+        after = forzium_engine.gil_stats()["compute"]
+        elapsed = after["elapsed_ms"] - before["elapsed_ms"]
+        held = after["held_ms"] - before["held_ms"]
+        assert held < elapsed / 2
+
+    def test_ffi_stats_count_binding_calls(self):
+        """Test that binding calls are counted and timed by ffi_stats."""
+        forzium_engine.ffi_stats(reset=True)
+        for _ in range(3):
+            forzium_engine.noop()
+        with pytest.raises(RuntimeError):
+            forzium_engine.trigger_panic()
+        stats = forzium_engine.ffi_stats()
+        assert stats["noop"]["calls"] == 3
+        assert stats["trigger_panic"]["calls"] == 1
+        assert stats["noop"]["total_ms"] >= 0
+        assert stats["noop"]["mean_us"] >= 0
+        forzium_engine.ffi_stats(reset=True)
+        assert forzium_engine.ffi_stats()["noop"]["calls"] == 0
+
+    def test_health_checks_aggregate(self):
+        """Test that registered health checks are run and reported."""
+
+        def failing():
+            raise RuntimeError("db down")
+
+        forzium_engine.register_health_check("test_ok", lambda: None)
+        forzium_engine.register_health_check("test_db", failing)
+        pool = forzium_engine.PoolAllocator(100)
+        pool.register_health_check("test_pool", max_utilization=0.5)
+        try:
+            report = forzium_engine.run_health_checks()
+            assert report["status"] == "fail"
+            assert report["checks"]["memory"]["status"] == "ok"
+            assert report["checks"]["test_ok"]["status"] == "ok"
+            assert report["checks"]["test_pool"]["status"] == "ok"
+            assert "db down" in report["checks"]["test_db"]["error"]
+            assert report["checks"]["test_db"]["latency_ms"] >= 0
+        finally:
+            for name in ("test_ok", "test_db", "test_pool"):
+                assert forzium_engine.unregister_health_check(name)
+        assert forzium_engine.run_health_checks()["status"] == "ok"
+        with pytest.raises(ValueError):
+            forzium_engine.register_health_check("test_bad", "not callable")
+
+    def test_dependency_probed_until_ready(self):
+        """Test that a critical dependency fails readiness until it succeeds."""
+        state = {"up": False, "calls": 0}
+
+        def probe():
+            state["calls"] += 1
+            return state["up"]
+
+        forzium_engine.register_dependency("test_dependency", probe)
+        try:
+            report = forzium_engine.run_health_checks()
+            assert report["failing"] == ["test_dependency"]
+            state["up"] = True
+            assert forzium_engine.run_health_checks()["failing"] == []
+            state["up"] = False
+            assert forzium_engine.run_health_checks()["status"] == "ok"
+            assert state["calls"] == 2
+        finally:
+            forzium_engine.unregister_health_check("test_dependency")
+
+    def test_access_log_settings_validated(self):
+        """Test that access log sampling rates and header names are checked."""
+        server = forzium_engine.ForziumHttpServer()
+        with pytest.raises(ValueError):
+            server.enable_access_log(sample_rate=1.5)
+        with pytest.raises(ValueError):
+            server.enable_access_log(redact_headers=["bad header"])
+        server.enable_access_log(
+            sample_rate=0.1, redact_headers=["X-Token"], max_body_bytes=256
+        )
+        server.disable_access_log()
+        levels = forzium_engine.get_log_levels()
+        assert levels["forzium_engine::server::access_log"] == "info"
+
+    def test_configure_otlp_validates_and_stops(self):
+        """Test that the OTLP exporter checks its settings and shuts down."""
+        if not hasattr(forzium_engine, "configure_otlp"):
+            pytest.skip("engine built without the otlp feature")
+        with pytest.raises(ValueError):
+            forzium_engine.configure_otlp(endpoint="https://collector:4317")
+        with pytest.raises(ValueError):
+            forzium_engine.configure_otlp(traces=False, metrics=False)
+        forzium_engine.configure_otlp(endpoint="http://127.0.0.1:9")
+        try:
+            assert forzium_engine.otlp_stats()["running"] is True
+        finally:
+            assert forzium_engine.shutdown_otlp() is True
+        assert forzium_engine.otlp_stats()["running"] is False
+
 
 @pytest.mark.unit
 @pytest.mark.rust_ffi
@@ -411,8 +1409,13 @@ class TestRayonMetrics:
             "mean_task_duration_us",
             "max_task_duration_us",
             "min_task_duration_us",
+            "p50_task_duration_us",
+            "p99_task_duration_us",
+            "task_duration_histogram",
             "busy_time_seconds",
             "observation_seconds",
+            "pools",
+            "operations",
         }
         
         assert set(metrics.keys()) == expected_keys
@@ -453,6 +1456,106 @@ class TestRayonMetrics:
         assert metrics["total_tasks_completed"] > 0
         assert metrics["busy_time_seconds"] > 0
 
+    def test_rayon_pool_metrics_per_pool(self, medium_matrix):
+        """Test tasks are broken down by the pool that ran them."""
+        forzium_engine.matmul(medium_matrix, medium_matrix)
+
+        metrics = forzium_engine.rayon_pool_metrics()
+        pools = metrics["pools"]
+        assert pools
+        assert sum(p["total_tasks_completed"] for p in pools.values()) <= (
+            metrics["total_tasks_completed"]
+        )
+        for pool in pools.values():
+            assert "utilization_percent" in pool
+
+    def test_rayon_pool_metrics_histograms(self, medium_matrix):
+        """Test task durations are bucketed overall and per operation."""
+        forzium_engine.matmul(medium_matrix, medium_matrix)
+
+        metrics = forzium_engine.rayon_pool_metrics()
+        histogram = metrics["task_duration_histogram"]
+        assert histogram[-1][0] is None
+        assert sum(count for _, count in histogram) <= metrics["total_tasks_completed"]
+        assert metrics["p50_task_duration_us"] <= metrics["p99_task_duration_us"]
+        assert "matmul" in metrics["operations"]
+
+
+@pytest.mark.unit
+@pytest.mark.rust_ffi
+class TestThreadPools:
+    """Test managing named thread pools at runtime."""
+
+    def test_recreate_and_shutdown_pool(self):
+        """Test pools can be rebuilt with new settings and removed."""
+        forzium_engine.recreate_pool("test_batch", thread_count=2, stack_size_mb=4)
+        assert forzium_engine.list_pools()["test_batch"] == 2
+        forzium_engine.recreate_pool("test_batch", thread_count=3)
+        assert forzium_engine.list_pools()["test_batch"] == 3
+        assert forzium_engine.shutdown_pool("test_batch")
+        assert not forzium_engine.shutdown_pool("test_batch")
+        assert "test_batch" not in forzium_engine.list_pools()
+
+    def test_recreate_rejects_empty_pool(self):
+        """Test a pool cannot be recreated without threads."""
+        with pytest.raises(RuntimeError):
+            forzium_engine.recreate_pool("test_empty", thread_count=0)
+
+    @pytest.mark.skipif(
+        not hasattr(os, "sched_getaffinity"), reason="needs os.sched_getaffinity"
+    )
+    def test_set_cpu_affinity(self):
+        """Test pools can be pinned to available cores only."""
+        core = sorted(os.sched_getaffinity(0))[0]
+        forzium_engine.recreate_pool("test_pinned", thread_count=1)
+        forzium_engine.set_cpu_affinity("test_pinned", [core])
+        with pytest.raises(ValueError):
+            forzium_engine.set_cpu_affinity("test_pinned", [])
+        with pytest.raises(ValueError):
+            forzium_engine.set_cpu_affinity("test_missing", [core])
+        assert forzium_engine.shutdown_pool("test_pinned")
+
+    def test_run_in_pool_with_priority(self):
+        """Test prioritized calls run on a named pool and return results."""
+        assert forzium_engine.run_in_pool("default", lambda: 42, priority="high") == 42
+        assert forzium_engine.pool_queue_depth("default") == {
+            "high": 0,
+            "normal": 0,
+            "low": 0,
+        }
+        with pytest.raises(ValueError):
+            forzium_engine.run_in_pool("default", lambda: 1, priority="urgent")
+        with pytest.raises(ValueError):
+            forzium_engine.run_in_pool("test_missing", lambda: 1)
+
+    def test_run_in_compute_threadpool_with_priority(self):
+        """Test compute pool calls are queued by priority."""
+        assert forzium_engine.run_in_compute_threadpool(lambda: 7) == 7
+        assert forzium_engine.run_in_compute_threadpool(lambda: 8, priority="high") == 8
+        assert sum(forzium_engine.pool_queue_depth("compute").values()) == 0
+        with pytest.raises(ValueError):
+            forzium_engine.run_in_compute_threadpool(lambda: 1, priority="urgent")
+
+    def test_submit_batch(self, small_matrix):
+        """Test a batch of operations runs in one call with ordered results."""
+        results = forzium_engine.submit_batch(
+            [
+                ("multiply", small_matrix, {"factor": 2.0}),
+                ("add", small_matrix, None),
+                ("matmul", small_matrix, {"matrix_b": [[1.0], [1.0]]}),
+            ]
+        )
+        assert results == [[[2.0, 4.0], [6.0, 8.0]], small_matrix, [[3.0], [7.0]]]
+
+        engine = forzium_engine.ComputeEngine()
+        engine.register_op("negate", lambda data, params: [[-x for x in row] for row in data])
+        assert forzium_engine.submit_batch([("negate", [[1.0]], None)], engine=engine) == [
+            [[-1.0]]
+        ]
+        assert forzium_engine.submit_batch([]) == []
+        with pytest.raises(forzium_engine.ComputeError):
+            forzium_engine.submit_batch([("add", small_matrix, None), ("nope", [[1.0]], None)])
+
 
 @pytest.mark.unit
 @pytest.mark.rust_ffi
@@ -495,3 +1598,199 @@ class TestAPIBindings:
         input_list = [2**32, 2**40, 2**50]
         result = forzium_engine.echo_list(input_list)
         assert result == input_list
+
+
+@pytest.mark.unit
+@pytest.mark.rust_ffi
+class TestAsyncCompute:
+    """Test awaiting AsyncCompute handles from asyncio."""
+
+    def test_await_matmul(self):
+        """Test awaiting a handle resolves to the computed matrix."""
+
+        async def run():
+            engine = forzium_engine.AsyncCompute()
+            return await engine.matmul([[1.0, 2.0]], [[3.0], [4.0]])
+
+        assert asyncio.run(run()) == [[11.0]]
+
+    def test_await_propagates_errors(self):
+        """Test awaiting a failed computation raises the mapped exception."""
+
+        async def run():
+            engine = forzium_engine.AsyncCompute()
+            await engine.matmul([[1.0, 2.0]], [[3.0, 4.0]])
+
+        with pytest.raises(ValueError):
+            asyncio.run(run())
+
+    def test_wait_times_out_without_consuming(self):
+        """Test wait returns None on timeout and the result afterwards."""
+        engine = forzium_engine.AsyncCompute()
+        matrix = [[1.0] * 300 for _ in range(300)]
+        handle = engine.matmul(matrix, matrix)
+        assert handle.wait(0) is None or handle.is_ready()
+        result = handle.wait(60_000)
+        assert result[0][0] == 300.0
+        with pytest.raises(RuntimeError):
+            handle.get_result()
+
+    def test_is_ready_polls_without_consuming(self):
+        """Test is_ready caches the result for a later get_result."""
+        engine = forzium_engine.AsyncCompute()
+        handle = engine.matmul([[2.0]], [[3.0]])
+        while not handle.is_ready():
+            pass
+        assert handle.is_ready()
+        assert handle.get_result() == [[6.0]]
+
+    def test_cancel_resolves_with_error(self):
+        """Test cancelling a running computation fails its result."""
+        engine = forzium_engine.AsyncCompute()
+        matrix = [[1.0] * 400 for _ in range(400)]
+        handle = engine.matmul(matrix, matrix)
+        assert handle.cancel()
+        assert handle.is_cancelled()
+        with pytest.raises(RuntimeError, match="cancelled"):
+            handle.get_result()
+
+    def test_cancel_after_completion_is_noop(self):
+        """Test cancel returns False once the result has arrived."""
+        engine = forzium_engine.AsyncCompute()
+        handle = engine.matmul([[2.0]], [[3.0]])
+        assert handle.wait(60_000) == [[6.0]]
+        assert not handle.cancel()
+
+    def test_priority_queue_limits_pending_work(self):
+        """Test full priority lanes reject submissions without blocking others."""
+        engine = forzium_engine.AsyncCompute(max_in_flight=1, max_queued=1)
+        matrix = [[1.0] * 300 for _ in range(300)]
+        handles = [engine.matmul(matrix, matrix, priority="low") for _ in range(2)]
+        with pytest.raises(ResourceWarning):
+            engine.matmul(matrix, matrix, priority="low")
+        assert engine.queue_stats()["queued"]["low"] == 1
+        assert engine.matmul([[2.0]], [[3.0]], priority="high").wait(60_000) == [[6.0]]
+        for handle in handles:
+            assert handle.wait(60_000)[0][0] == 300.0
+
+    def test_unknown_priority_rejected(self):
+        """Test submitting with an unknown priority raises ValueError."""
+        engine = forzium_engine.AsyncCompute()
+        with pytest.raises(ValueError):
+            engine.matmul([[1.0]], [[1.0]], priority="urgent")
+
+    def test_submit_routes_through_engine(self):
+        """Test submit runs built-in and registered engine operations."""
+        engine = forzium_engine.AsyncCompute()
+        handle = engine.submit("multiply", [[1.0, 2.0]], {"factor": 3.0})
+        assert handle.get_result() == [[3.0, 6.0]]
+        engine.engine.register_op(
+            "shift", lambda data, params: [[v + params["by"] for v in row] for row in data]
+        )
+        handle = engine.submit("shift", [[1.0]], {"by": 0.5}, priority="high")
+        assert handle.get_result() == [[1.5]]
+
+    def test_submit_runs_every_tensor_op(self):
+        """Test every built-in tensor operation can be submitted by name."""
+        engine = forzium_engine.AsyncCompute()
+        a = [[1.0, 2.0], [3.0, 4.0]]
+        b = [[5.0, 6.0], [7.0, 8.0]]
+        product = [[19.0, 22.0], [43.0, 50.0]]
+        cases = {
+            "multiply": ({"factor": 2.0}, [[2.0, 4.0], [6.0, 8.0]]),
+            "add": ({"addend": 1.0}, [[2.0, 3.0], [4.0, 5.0]]),
+            "matmul": ({"matrix_b": b}, product),
+            "simd_matmul": ({"matrix_b": b}, product),
+            "optimal_matmul": ({"matrix_b": b}, product),
+            "transpose": ({}, [[1.0, 3.0], [2.0, 4.0]]),
+            "elementwise_add": ({"matrix_b": b}, [[6.0, 8.0], [10.0, 12.0]]),
+            "simd_elementwise_add": ({"matrix_b": b}, [[6.0, 8.0], [10.0, 12.0]]),
+            "optimal_add": ({"matrix_b": b}, [[6.0, 8.0], [10.0, 12.0]]),
+            "hadamard": ({"matrix_b": b}, [[5.0, 12.0], [21.0, 32.0]]),
+            "optimal_mul": ({"matrix_b": b}, [[5.0, 12.0], [21.0, 32.0]]),
+            "optimal_multiply": ({"factor": 3.0}, [[3.0, 6.0], [9.0, 12.0]]),
+            "conv2d": ({"kernel": [[1.0]]}, a),
+            "conv2d_transpose": (
+                {"kernel": [[1.0]], "stride": 2},
+                [[1.0, 0.0, 2.0], [0.0, 0.0, 0.0], [3.0, 0.0, 4.0]],
+            ),
+            "max_pool2d": ({"size": 2}, [[4.0]]),
+        }
+        handles = {name: engine.submit(name, a, params) for name, (params, _) in cases.items()}
+        for name, (_, expected) in cases.items():
+            assert engine.engine.supports(name)
+            assert handles[name].wait(60_000) == expected, name
+        with pytest.raises(ValueError):
+            engine.submit("max_pool2d", a, {})
+
+    def test_submit_validates_before_queueing(self):
+        """Test unknown operations and bad parameters raise on submit."""
+        engine = forzium_engine.AsyncCompute()
+        with pytest.raises(RuntimeError):
+            engine.submit("nope", [[1.0]])
+        with pytest.raises(ValueError):
+            engine.submit("matmul", [[1.0]], {})
+
+    def test_done_callback_receives_result(self):
+        """Test completion callbacks get the result or the raised error."""
+        engine = forzium_engine.AsyncCompute()
+        outcomes = []
+        finished = threading.Event()
+
+        def on_done(result, error):
+            outcomes.append((result, error))
+            if len(outcomes) == 2:
+                finished.set()
+
+        handle = engine.matmul([[2.0]], [[3.0]])
+        handle.add_done_callback(on_done)
+        engine.matmul([[1.0, 2.0]], [[3.0, 4.0]]).add_done_callback(on_done)
+        assert finished.wait(60)
+        assert ([[6.0]], None) in outcomes
+        assert any(isinstance(error, ValueError) for _, error in outcomes)
+        with pytest.raises(RuntimeError):
+            handle.get_result()
+
+    def test_done_callback_rejects_non_callable(self):
+        """Test attaching a non-callable completion callback fails."""
+        engine = forzium_engine.AsyncCompute()
+        with pytest.raises(ValueError):
+            engine.matmul([[1.0]], [[1.0]]).add_done_callback(42)
+
+    def test_instances_share_one_runtime(self):
+        """Test AsyncCompute instances run on the shared runtime."""
+        first = forzium_engine.AsyncCompute()
+        second = forzium_engine.AsyncCompute()
+        before = forzium_engine.shared_runtime_metrics()["blocking_jobs_spawned"]
+        assert first.matmul([[2.0]], [[3.0]]).get_result() == [[6.0]]
+        assert second.matmul([[1.0]], [[4.0]]).get_result() == [[4.0]]
+        metrics = forzium_engine.shared_runtime_metrics()
+        assert metrics["started"]
+        assert metrics["blocking_jobs_spawned"] == before + 2
+        with pytest.raises(ValueError):
+            forzium_engine.configure_shared_runtime(metrics["worker_threads"] + 1)
+
+    def test_iterate_result_in_chunks(self):
+        """Test results can be consumed as row chunks."""
+        engine = forzium_engine.AsyncCompute()
+        matrix = [[1.0] * 4 for _ in range(600)]
+        chunks = engine.matmul(matrix, [[1.0]] * 4).iter_chunks(250)
+        assert [len(chunk) for chunk in chunks] == [250, 250, 100]
+        assert chunks.remaining_rows == 0
+        rows = [row for chunk in engine.matmul(matrix, [[2.0]] * 4) for row in chunk]
+        assert rows == [[8.0]] * 600
+        with pytest.raises(ValueError):
+            engine.matmul([[1.0]], [[1.0]]).iter_chunks(0)
+
+    def test_stats_count_outcomes(self):
+        """Test stats reports task counters and latency percentiles."""
+        engine = forzium_engine.AsyncCompute()
+        assert engine.matmul([[2.0]], [[3.0]]).get_result() == [[6.0]]
+        with pytest.raises(ValueError):
+            engine.matmul([[1.0, 2.0]], [[3.0, 4.0]]).get_result()
+        stats = engine.stats()
+        assert stats["submitted"] == 2
+        assert stats["completed"] == 1
+        assert stats["failed"] == 1
+        assert stats["queue_depth"] == 0
+        assert 0.0 < stats["latency_p50_ms"] <= stats["latency_p99_ms"]